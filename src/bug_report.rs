@@ -0,0 +1,205 @@
+//!What a navipod bug report needs besides the steps: the version, the os,
+//!the last lines traced, how much the cache holds and the shape of
+//!`config.yaml`.  Config values are redacted and cache entries are only
+//!counted, the traced lines are kept as they are and are worth a look
+//!before the file is attached anywhere.
+//!
+use crate::config::config_dir;
+use crate::k8s::cache::cache_manager::get_cache;
+use crate::k8s::cache::memory::{self, human};
+use serde_yaml::Value;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// traced lines kept for a report
+pub const RECENT_LINES: usize = 200;
+
+const REDACTED: &str = "<redacted>";
+
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Traces to stderr like the default subscriber while keeping the last
+/// `RECENT_LINES` for a report.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Recorder;
+
+impl<'a> MakeWriter<'a> for Recorder {
+    type Writer = Self;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        *self
+    }
+}
+
+impl Write for Recorder {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        record(&String::from_utf8_lossy(buf));
+        io::stderr().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+/// the color codes of the terminal output removed
+fn strip_ansi(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // `ESC [ params letter`
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+fn record(text: &str) {
+    // a panic while tracing must not leave the lock stuck for the report
+    let Ok(mut recent) = RECENT.try_lock() else {
+        return;
+    };
+    for line in strip_ansi(text).lines().filter(|line| !line.is_empty()) {
+        if recent.len() == RECENT_LINES {
+            recent.pop_front();
+        }
+        recent.push_back(line.to_string());
+    }
+}
+
+/// every string in the yaml replaced, keys and numbers kept
+fn redact(value: Value) -> Value {
+    match value {
+        Value::String(_) => Value::String(REDACTED.to_string()),
+        Value::Sequence(items) => Value::Sequence(items.into_iter().map(redact).collect()),
+        Value::Mapping(mapping) => Value::Mapping(
+            mapping
+                .into_iter()
+                .map(|(key, value)| (key, redact(value)))
+                .collect(),
+        ),
+        Value::Tagged(mut tagged) => {
+            tagged.value = redact(tagged.value);
+            Value::Tagged(tagged)
+        }
+        other => other,
+    }
+}
+
+fn redacted_config() -> String {
+    let Some(path) = config_dir().map(|dir| dir.join("config.yaml")) else {
+        return "no config dir".to_string();
+    };
+    let Ok(yaml) = std::fs::read_to_string(&path) else {
+        return "no config.yaml".to_string();
+    };
+    match serde_yaml::from_str::<Value>(&yaml) {
+        Ok(value) => serde_yaml::to_string(&redact(value)).unwrap_or_default(),
+        Err(e) => format!("config.yaml does not parse: {e}"),
+    }
+}
+
+fn cache_stats() -> String {
+    let usage = memory::report();
+    let (entries, cached) = usage
+        .iter()
+        .filter(|line| line.area == "cache")
+        .fold((0, 0), |(count, bytes), line| {
+            (count + 1, bytes + line.bytes)
+        });
+    let total: usize = usage.iter().map(|line| line.bytes).sum();
+    format!(
+        "cache: {entries} entries, {} of {}\nestimated total: {}",
+        human(cached),
+        human(get_cache().max_bytes()),
+        human(total)
+    )
+}
+
+/// The whole report as text.
+#[must_use]
+pub fn bundle() -> String {
+    let mut text = String::new();
+    _ = writeln!(text, "navipod {}", env!("CARGO_PKG_VERSION"));
+    _ = writeln!(
+        text,
+        "os: {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    _ = writeln!(
+        text,
+        "term: {}",
+        std::env::var("TERM").unwrap_or_else(|_| "unset".to_string())
+    );
+    _ = writeln!(text, "\n{}", cache_stats());
+    _ = writeln!(text, "\n# config.yaml\n{}", redacted_config().trim_end());
+    _ = writeln!(text, "\n# last {RECENT_LINES} traced lines");
+    if let Ok(recent) = RECENT.try_lock() {
+        for line in recent.iter() {
+            _ = writeln!(text, "{line}");
+        }
+    }
+    text
+}
+
+/// `bundle`, or `None` when a lock held by whatever went wrong keeps it
+/// from being collected in time
+#[must_use]
+pub fn bundle_within(timeout: Duration) -> Option<String> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        _ = tx.send(bundle());
+    });
+    rx.recv_timeout(timeout).ok()
+}
+
+/// Write `text` to a new `navipod-<kind>-<seconds>.txt` in the temp dir.
+///
+/// # Errors
+///
+/// Will return `Err` if the file can not be written
+pub fn write(kind: &str, text: &str) -> io::Result<PathBuf> {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let path = std::env::temp_dir().join(format!("navipod-{kind}-{seconds}.txt"));
+    std::fs::write(&path, text)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_and_strip() {
+        let yaml: Value = serde_yaml::from_str(
+            "dashboards:\n  - name: Grafana\n    url: https://grafana.acme.io\nhooks: []\npanes:\n  open: tmux\n",
+        )
+        .unwrap();
+        let redacted = serde_yaml::to_string(&redact(yaml)).unwrap();
+        assert!(redacted.contains("name: <redacted>"));
+        assert!(redacted.contains("open: <redacted>"));
+        assert!(redacted.contains("hooks: []"));
+        assert!(!redacted.contains("acme"));
+
+        assert_eq!(
+            strip_ansi("\x1b[2m2026\x1b[0m \x1b[33m WARN\x1b[0m slow"),
+            "2026  WARN slow"
+        );
+    }
+}
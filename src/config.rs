@@ -0,0 +1,244 @@
+//!Locations of files navipod keeps between sessions.
+//!
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use tracing::debug;
+
+const APP_DIR: &str = "navipod";
+
+/// `$XDG_CONFIG_HOME/navipod`, falling back to `~/.config/navipod`
+#[must_use]
+pub fn config_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join(APP_DIR))
+}
+
+/// `$XDG_DATA_HOME/navipod`, falling back to `~/.local/share/navipod`
+#[must_use]
+pub fn data_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share"))
+        })
+        .map(|dir| dir.join(APP_DIR))
+}
+
+/// path of a file in the config dir, the dir is created if missing
+#[must_use]
+pub fn config_file(name: &str) -> Option<PathBuf> {
+    let dir = config_dir()?;
+    std::fs::create_dir_all(&dir).ok()?;
+    Some(dir.join(name))
+}
+
+const SETTINGS_FILE: &str = "config.yaml";
+
+/// what `config.yaml` in the config dir can set, ie:
+///
+/// ```yaml
+/// group_labels: [version, team]
+/// dashboards:
+///   - name: Grafana
+///     url: https://grafana.example.com/d/pods?var-namespace={namespace}&var-pod={pod}
+/// columns:
+///   rs:
+///     - header: TEAM
+///       label: team
+///   pod:
+///     - header: VERSION
+///       annotation: app.kubernetes.io/version
+/// hooks:
+///   - name: restart storm
+///     when: restarts > 5
+///     then: annotate("navipod/restart-storm", "true")
+/// panes:
+///   open: tmux new-window {command}
+/// sidecars: [log-forwarder]
+/// symbols: ascii
+/// storage: /srv/navipod/scans.db
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct Settings {
+    pub columns: ViewColumns,
+    /// labels the pod view can group by besides node, replicaset and image
+    pub group_labels: Vec<String>,
+    /// links offered by the "open in" menu
+    pub dashboards: Vec<Dashboard>,
+    /// scripts run on pod watch events
+    pub hooks: Vec<Hook>,
+    /// exec shells and log tails in tmux or wezterm panes
+    pub panes: Panes,
+    /// container names to hide as sidecars besides the well known ones
+    pub sidecars: Vec<String>,
+    /// the symbols views draw with, detected from the terminal when unset
+    pub symbols: Option<SymbolSet>,
+    /// where scans are written instead of the context's db, a sqlite path
+    /// or, built with the postgres feature, a postgres url
+    pub storage: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolSet {
+    Unicode,
+    /// for fonts and terminals that draw block, arrow and braille
+    /// characters badly
+    Ascii,
+}
+
+/// extra columns per view
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct ViewColumns {
+    pub rs: Vec<CustomColumn>,
+    pub pod: Vec<CustomColumn>,
+}
+
+/// a column showing a label, or an annotation when there is no such label
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct CustomColumn {
+    pub header: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub annotation: Option<String>,
+}
+
+impl CustomColumn {
+    #[must_use]
+    pub fn value(&self, meta: &ObjectMeta) -> String {
+        let label = self
+            .label
+            .as_ref()
+            .and_then(|key| meta.labels.as_ref()?.get(key));
+        let annotation = || {
+            self.annotation
+                .as_ref()
+                .and_then(|key| meta.annotations.as_ref()?.get(key))
+        };
+        label.or_else(annotation).cloned().unwrap_or_default()
+    }
+}
+
+/// a url template, `{namespace}`, `{replicaset}`, `{pod}`, `{container}`
+/// and `{context}` are replaced with those of the selected resource
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Dashboard {
+    pub name: String,
+    pub url: String,
+}
+
+impl Dashboard {
+    /// `None` when the template needs a variable the view does not have
+    #[must_use]
+    pub fn url(&self, vars: &BTreeMap<&str, String>) -> Option<String> {
+        fill(&self.url, vars)
+    }
+}
+
+/// replace every `{name}` in `template`, `None` if one is not in `vars`
+#[must_use]
+pub fn fill(template: &str, vars: &BTreeMap<&str, String>) -> Option<String> {
+    let mut filled = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = start + rest[start..].find('}')?;
+        filled.push_str(&rest[..start]);
+        filled.push_str(vars.get(&rest[start + 1..end])?);
+        rest = &rest[end + 1..];
+    }
+    filled.push_str(rest);
+    Some(filled)
+}
+
+/// Command templates for opening exec shells and log tails next to
+/// navipod.  `exec` and `logs` are shell commands with the variables of
+/// the dashboards, `open` is the program opening a pane with `{command}`
+/// as one argument.  tmux and wezterm are found from the environment when
+/// `open` is not set.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct Panes {
+    pub open: Option<String>,
+    pub exec: Option<String>,
+    pub logs: Option<String>,
+}
+
+/// rhai: `when` is an expression on the pod, `then` a script run when it
+/// turns true, at most once per `cooldown_secs` for the same pod
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Hook {
+    pub name: String,
+    pub when: String,
+    #[serde(default)]
+    pub then: String,
+    #[serde(default)]
+    pub cooldown_secs: Option<u64>,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// read once, a missing or broken file gives the defaults
+pub fn settings() -> &'static Settings {
+    SETTINGS.get_or_init(|| {
+        let Some(path) = config_dir().map(|dir| dir.join(SETTINGS_FILE)) else {
+            return Settings::default();
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(yaml) => serde_yaml::from_str(&yaml).unwrap_or_else(|e| {
+                debug!("ignoring {}: {e}", path.display());
+                Settings::default()
+            }),
+            Err(_) => Settings::default(),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_columns() {
+        let settings: Settings = serde_yaml::from_str(
+            "columns:\n  pod:\n    - header: VERSION\n      label: version\n      annotation: app.kubernetes.io/version\n",
+        )
+        .unwrap();
+        assert!(settings.columns.rs.is_empty());
+        let column = &settings.columns.pod[0];
+        let mut meta = ObjectMeta {
+            annotations: Some(BTreeMap::from([(
+                "app.kubernetes.io/version".to_string(),
+                "1.2".to_string(),
+            )])),
+            ..ObjectMeta::default()
+        };
+        assert_eq!(column.value(&meta), "1.2");
+        meta.labels = Some(BTreeMap::from([("version".to_string(), "1.3".to_string())]));
+        assert_eq!(column.value(&meta), "1.3");
+    }
+
+    #[test]
+    fn test_dashboard_url() {
+        let dashboard = Dashboard {
+            name: "Kibana".to_string(),
+            url: "https://kibana/app?q=kubernetes.pod.name:{pod}&ns={namespace}".to_string(),
+        };
+        let mut vars = BTreeMap::from([("namespace", "default".to_string())]);
+        assert_eq!(dashboard.url(&vars), None);
+        vars.insert("pod", "web-1".to_string());
+        assert_eq!(
+            dashboard.url(&vars).unwrap(),
+            "https://kibana/app?q=kubernetes.pod.name:web-1&ns=default"
+        );
+    }
+}
@@ -1,31 +1,203 @@
 use crate::k8s::client::UserAgentError;
 use derive_more::From;
 use k8s_openapi::serde_json;
+use std::sync::Arc;
+use std::time::Duration;
 
 pub type Result<T> = core::result::Result<T, Error>;
 
+const UNAUTHORIZED: u16 = 401;
+const FORBIDDEN: u16 = 403;
+const NOT_FOUND: u16 = 404;
+const CONFLICT: u16 = 409;
+
+/// Errors are classified when they are created so every view can tell the
+/// user what went wrong and what to do about it without digging through
+/// `kube::Error` itself.
 #[derive(Debug, From)]
 pub enum Error {
     #[from]
     UserAgentError(UserAgentError),
 
     #[from]
-    Json(serde_json::Error),
+    Infer(kube::config::InferConfigError),
 
     #[from]
-    Kube(kube::Error),
+    HttpHeader(hyper::http::Error),
 
     #[from]
-    Infer(kube::config::InferConfigError),
+    Io(std::io::Error),
 
-    #[from]
-    HttpHeader(hyper::http::Error),
+    /// api errors that do not fall into one of the categories below
+    Kube(kube::Error),
+
+    /// credentials missing, expired or rejected (401)
+    Auth(String),
+
+    /// authenticated but not allowed by RBAC (403)
+    Forbidden(String),
+
+    NotFound(String),
+
+    /// the object changed since it was read (409)
+    Conflict(String),
+
+    /// the api server or remote host could not be reached
+    Network(String),
+
+    /// a response that could not be decoded
+    Parse(String),
+
+    Timeout {
+        operation: String,
+        after: Duration,
+    },
+
+    /// a plugin failed or gave an answer that could not be used
+    Plugin(String),
+
+    /// a tmux or wezterm pane could not be opened
+    Pane(String),
+
+    /// one failed fetch handed to each caller waiting on it, for errors that
+    /// can not be copied; it reads and classifies like the error itself
+    Shared(Arc<Error>),
+}
+
+impl From<kube::Error> for Error {
+    fn from(e: kube::Error) -> Self {
+        match e {
+            kube::Error::Api(response) => match response.code {
+                UNAUTHORIZED => Self::Auth(response.message),
+                FORBIDDEN => Self::Forbidden(response.message),
+                NOT_FOUND => Self::NotFound(response.message),
+                CONFLICT => Self::Conflict(response.message),
+                _ => Self::Kube(kube::Error::Api(response)),
+            },
+            kube::Error::Auth(e) => Self::Auth(e.to_string()),
+            kube::Error::HyperError(e) => Self::Network(e.to_string()),
+            kube::Error::Service(e) => Self::Network(e.to_string()),
+            kube::Error::ReadEvents(e) => Self::Network(e.to_string()),
+            kube::Error::SerdeError(e) => Self::Parse(e.to_string()),
+            e => Self::Kube(e),
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Parse(e.to_string())
+    }
+}
+
+impl Error {
+    /// short stable code shown on error cards and in logs
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UserAgentError(_) | Self::Infer(_) | Self::HttpHeader(_) => "NP-CONFIG",
+            Self::Io(_) => "NP-IO",
+            Self::Kube(_) => "NP-API",
+            Self::Auth(_) => "NP-AUTH",
+            Self::Forbidden(_) => "NP-FORBIDDEN",
+            Self::NotFound(_) => "NP-NOT-FOUND",
+            Self::Conflict(_) => "NP-CONFLICT",
+            Self::Network(_) => "NP-NETWORK",
+            Self::Parse(_) => "NP-PARSE",
+            Self::Timeout { .. } => "NP-TIMEOUT",
+            Self::Plugin(_) => "NP-PLUGIN",
+            Self::Pane(_) => "NP-PANE",
+            Self::Shared(e) => e.code(),
+        }
+    }
+
+    /// what the user can do about it
+    #[must_use]
+    pub fn remediation(&self) -> &'static str {
+        match self {
+            Self::UserAgentError(_) | Self::Infer(_) | Self::HttpHeader(_) => {
+                "No usable kubeconfig was found. Set KUBECONFIG or select a context with kubectl config use-context."
+            }
+            Self::Io(_) => "A local read or write failed. Check disk space and permissions.",
+            Self::Kube(_) => "The api server rejected the request. See the navipod log for details.",
+            Self::Auth(_) => {
+                "Your credentials were rejected. Log in again (ie: refresh your cloud CLI session) or check the user of the current context."
+            }
+            Self::Forbidden(_) => {
+                "RBAC does not allow this. Check kubectl auth can-i for the resource and namespace, or switch to a context with more access."
+            }
+            Self::NotFound(_) => {
+                "The resource no longer exists, it may have been deleted or replaced. Go back and reload the list."
+            }
+            Self::Conflict(_) => "The resource changed since it was read. Reload and try again.",
+            Self::Network(_) => {
+                "The server could not be reached. Check your VPN or network and the server address in the kubeconfig."
+            }
+            Self::Parse(_) => {
+                "The response could not be read. The cluster may serve an api version navipod does not understand."
+            }
+            Self::Timeout { .. } => {
+                "The api server is slow to respond. Try again or raise --request-timeout-secs."
+            }
+            Self::Plugin(_) => {
+                "A plugin failed. Check its manifest in the plugins dir and run its command by hand to see why."
+            }
+            Self::Pane(_) => {
+                "Run navipod inside tmux or wezterm, or set panes.open in config.yaml to the command that opens a pane."
+            }
+            Self::Shared(e) => e.remediation(),
+        }
+    }
 }
 
 impl core::fmt::Display for Error {
     fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
-        write!(fmt, "{self:?}")
+        match self {
+            Self::UserAgentError(e) => write!(fmt, "{e}"),
+            Self::Infer(e) => write!(fmt, "kubeconfig: {e}"),
+            Self::HttpHeader(e) => write!(fmt, "invalid request: {e}"),
+            Self::Io(e) => write!(fmt, "io: {e}"),
+            Self::Kube(e) => write!(fmt, "api error: {e}"),
+            Self::Auth(message) => write!(fmt, "unauthorized: {message}"),
+            Self::Forbidden(message) => write!(fmt, "forbidden: {message}"),
+            Self::NotFound(message) => write!(fmt, "not found: {message}"),
+            Self::Conflict(message) => write!(fmt, "conflict: {message}"),
+            Self::Network(message) => write!(fmt, "network: {message}"),
+            Self::Parse(message) => write!(fmt, "unreadable response: {message}"),
+            Self::Timeout { operation, after } => {
+                write!(fmt, "timed out after {}s: {operation}", after.as_secs())
+            }
+            Self::Plugin(message) => write!(fmt, "plugin {message}"),
+            Self::Pane(message) => write!(fmt, "pane: {message}"),
+            Self::Shared(e) => write!(fmt, "{e}"),
+        }
     }
 }
 
 impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kube::core::ErrorResponse;
+
+    fn api_error(code: u16) -> Error {
+        Error::from(kube::Error::Api(ErrorResponse {
+            status: "Failure".to_string(),
+            message: "pods is forbidden".to_string(),
+            reason: String::new(),
+            code,
+        }))
+    }
+
+    #[test]
+    fn test_api_errors_are_classified() {
+        assert!(matches!(api_error(401), Error::Auth(_)));
+        assert!(matches!(api_error(403), Error::Forbidden(ref m) if m == "pods is forbidden"));
+        assert!(matches!(api_error(404), Error::NotFound(_)));
+        assert!(matches!(api_error(409), Error::Conflict(_)));
+        assert!(matches!(api_error(503), Error::Kube(_)));
+        assert_eq!(api_error(403).code(), "NP-FORBIDDEN");
+        assert!(api_error(403).remediation().contains("RBAC"));
+    }
+}
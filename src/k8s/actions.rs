@@ -0,0 +1,338 @@
+//!Changes navipod makes to the cluster.  Every action remembers what it
+//!replaced so the last one can be undone.
+//!
+use crate::error::{Error, Result};
+use crate::k8s::client_manager::get_client;
+use crate::k8s::timeout::with_timeout;
+use k8s_openapi::api::apps::v1::{Deployment, ReplicaSet};
+use k8s_openapi::api::core::v1::{Namespace, Pod};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use kube::api::{Patch, PatchParams};
+use kube::{Api, Resource};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::fmt;
+use std::sync::Mutex;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// in the current namespace when `namespace` is `None`
+    Pod {
+        name: String,
+        namespace: Option<String>,
+    },
+    ReplicaSet(String),
+    Deployment(String),
+    Namespace(String),
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pod {
+                name,
+                namespace: None,
+            } => write!(f, "pod {name}"),
+            Self::Pod {
+                name,
+                namespace: Some(namespace),
+            } => write!(f, "pod {namespace}/{name}"),
+            Self::ReplicaSet(name) => write!(f, "replicaset {name}"),
+            Self::Deployment(name) => write!(f, "deployment {name}"),
+            Self::Namespace(name) => write!(f, "namespace {name}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MetadataKind {
+    Label,
+    Annotation,
+}
+
+impl MetadataKind {
+    const fn field(self) -> &'static str {
+        match self {
+            Self::Label => "labels",
+            Self::Annotation => "annotations",
+        }
+    }
+}
+
+impl fmt::Display for MetadataKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Label => write!(f, "label"),
+            Self::Annotation => write!(f, "annotation"),
+        }
+    }
+}
+
+/// Set or, with no value, remove one label or annotation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MetadataEdit {
+    pub target: Target,
+    pub kind: MetadataKind,
+    pub key: String,
+    pub value: Option<String>,
+}
+
+impl fmt::Display for MetadataEdit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => write!(
+                f,
+                "set {} {}={value} on {}",
+                self.kind, self.key, self.target
+            ),
+            None => write!(f, "remove {} {} from {}", self.kind, self.key, self.target),
+        }
+    }
+}
+
+impl MetadataEdit {
+    /// Parse kubectl style input: `key=value` sets, `key-` removes.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` with a message for the user if the key or, for
+    /// labels, the value is not valid
+    pub fn parse(
+        target: Target,
+        kind: MetadataKind,
+        input: &str,
+    ) -> core::result::Result<Self, String> {
+        let input = input.trim();
+        let (key, value) = match input.split_once('=') {
+            Some((key, value)) => (key, Some(value.to_string())),
+            None => match input.strip_suffix('-') {
+                Some(key) => (key, None),
+                None => return Err("use key=value to set or key- to remove".to_string()),
+            },
+        };
+        validate_key(key)?;
+        if let (MetadataKind::Label, Some(value)) = (kind, &value) {
+            if !value.is_empty() {
+                validate_name(value, "label value")?;
+            }
+        }
+        Ok(Self {
+            target,
+            kind,
+            key: key.to_string(),
+            value,
+        })
+    }
+
+    /// `previous` is the value before the edit, a missing map is created
+    #[must_use]
+    pub fn json_patch(&self, map_exists: bool, previous: Option<&str>) -> Value {
+        let field = self.kind.field();
+        let path = format!(
+            "/metadata/{field}/{}",
+            self.key.replace('~', "~0").replace('/', "~1")
+        );
+        let mut ops = Vec::new();
+        if let Some(previous) = previous {
+            // fail instead of clobbering a value someone changed meanwhile
+            ops.push(json!({"op": "test", "path": path, "value": previous}));
+        }
+        match (&self.value, map_exists) {
+            (Some(value), true) => ops.push(json!({"op": "add", "path": path, "value": value})),
+            (Some(value), false) => ops.push(json!({
+                "op": "add",
+                "path": format!("/metadata/{field}"),
+                "value": {self.key.clone(): value},
+            })),
+            (None, _) if previous.is_some() => ops.push(json!({"op": "remove", "path": path})),
+            (None, _) => {}
+        }
+        Value::Array(ops)
+    }
+}
+
+pub(crate) fn validate_name(name: &str, what: &str) -> core::result::Result<(), String> {
+    let valid_chars = name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    let valid_ends = name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name.ends_with(|c: char| c.is_ascii_alphanumeric());
+    if name.len() > 63 || !valid_chars || !valid_ends {
+        return Err(format!(
+            "{what} {name:?} must be at most 63 alphanumerics, '-', '_' or '.', starting and ending alphanumeric"
+        ));
+    }
+    Ok(())
+}
+
+/// `[prefix/]name` where prefix is a DNS subdomain, as for labels and
+/// annotations alike
+fn validate_key(key: &str) -> core::result::Result<(), String> {
+    let name = match key.split_once('/') {
+        Some((prefix, name)) => {
+            let valid_prefix = prefix.len() <= 253
+                && prefix.split('.').all(|part| {
+                    !part.is_empty()
+                        && part
+                            .chars()
+                            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+                        && !part.starts_with('-')
+                        && !part.ends_with('-')
+                });
+            if !valid_prefix {
+                return Err(format!("key prefix {prefix:?} must be a DNS subdomain"));
+            }
+            name
+        }
+        None => key,
+    };
+    validate_name(name, "key")
+}
+
+/// what `undo_last` restores
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Undo {
+    pub edit: MetadataEdit,
+    pub previous: Option<String>,
+}
+
+static LAST: Mutex<Option<Undo>> = Mutex::new(None);
+
+/// the edit the next `undo_last` reverts
+#[must_use]
+pub fn last_edit() -> Option<MetadataEdit> {
+    LAST.lock().ok()?.as_ref().map(|undo| undo.edit.clone())
+}
+
+async fn patch_metadata<K>(api: Api<K>, name: &str, edit: &MetadataEdit) -> Result<Option<String>>
+where
+    K: Resource<DynamicType = ()> + Clone + DeserializeOwned + fmt::Debug,
+{
+    let current = with_timeout("get metadata", api.get_metadata(name)).await?;
+    let map = metadata_map(current.meta(), edit.kind);
+    let previous = map.and_then(|map| map.get(&edit.key).cloned());
+    let patch: json_patch::Patch =
+        serde_json::from_value(edit.json_patch(map.is_some(), previous.as_deref()))?;
+    with_timeout(
+        "patch metadata",
+        api.patch(name, &PatchParams::default(), &Patch::Json::<()>(patch)),
+    )
+    .await?;
+    Ok(previous)
+}
+
+fn metadata_map(
+    meta: &ObjectMeta,
+    kind: MetadataKind,
+) -> Option<&std::collections::BTreeMap<String, String>> {
+    match kind {
+        MetadataKind::Label => meta.labels.as_ref(),
+        MetadataKind::Annotation => meta.annotations.as_ref(),
+    }
+}
+
+/// Apply an edit without making it the one `undo_last` restores, for
+/// changes the user did not make themselves.
+pub(crate) async fn apply_edit(edit: &MetadataEdit) -> Result<Option<String>> {
+    let client = get_client().await?;
+    match &edit.target {
+        Target::Pod { name, namespace } => {
+            let api = match namespace {
+                Some(namespace) => Api::<Pod>::namespaced(client, namespace),
+                None => Api::<Pod>::default_namespaced(client),
+            };
+            patch_metadata(api, name, edit).await
+        }
+        Target::ReplicaSet(name) => {
+            patch_metadata(Api::<ReplicaSet>::default_namespaced(client), name, edit).await
+        }
+        Target::Deployment(name) => {
+            patch_metadata(Api::<Deployment>::default_namespaced(client), name, edit).await
+        }
+        Target::Namespace(name) => patch_metadata(Api::<Namespace>::all(client), name, edit).await,
+    }
+}
+
+/// Apply an edit and remember it for `undo_last`.
+///
+/// # Errors
+///
+/// Will return `Err` if the object can not be read or patched
+pub async fn apply(edit: MetadataEdit) -> Result<()> {
+    let previous = apply_edit(&edit).await?;
+    if let Ok(mut last) = LAST.lock() {
+        *last = Some(Undo { edit, previous });
+    }
+    Ok(())
+}
+
+/// Put back the value the last edit replaced.
+///
+/// # Errors
+///
+/// Will return `Err` if there is nothing to undo or the patch fails
+pub async fn undo_last() -> Result<MetadataEdit> {
+    let undo = LAST
+        .lock()
+        .ok()
+        .and_then(|mut last| last.take())
+        .ok_or_else(|| Error::NotFound("no edit to undo".to_string()))?;
+    let restore = MetadataEdit {
+        value: undo.previous,
+        ..undo.edit
+    };
+    apply_edit(&restore).await?;
+    Ok(restore)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_patch() {
+        let pod = || Target::Pod {
+            name: "web-1".to_string(),
+            namespace: None,
+        };
+        let edit = MetadataEdit::parse(pod(), MetadataKind::Label, "team=payments").unwrap();
+        assert_eq!(edit.value.as_deref(), Some("payments"));
+        assert!(MetadataEdit::parse(pod(), MetadataKind::Label, "team=not ok").is_err());
+        assert!(MetadataEdit::parse(pod(), MetadataKind::Label, "-team=x").is_err());
+        assert!(MetadataEdit::parse(pod(), MetadataKind::Label, "team").is_err());
+        let annotation = MetadataEdit::parse(
+            pod(),
+            MetadataKind::Annotation,
+            "example.com/note=any text at all",
+        )
+        .unwrap();
+        assert_eq!(
+            annotation.json_patch(true, Some("old")),
+            json!([
+                {"op": "test", "path": "/metadata/annotations/example.com~1note", "value": "old"},
+                {"op": "add", "path": "/metadata/annotations/example.com~1note", "value": "any text at all"},
+            ])
+        );
+        assert_eq!(
+            edit.json_patch(false, None),
+            json!([{"op": "add", "path": "/metadata/labels", "value": {"team": "payments"}}])
+        );
+
+        let elsewhere = Target::Pod {
+            name: "web-1".to_string(),
+            namespace: Some("shop".to_string()),
+        };
+        assert_eq!(elsewhere.to_string(), "pod shop/web-1");
+        assert_eq!(pod().to_string(), "pod web-1");
+
+        let remove = MetadataEdit::parse(pod(), MetadataKind::Label, "team-").unwrap();
+        assert_eq!(remove.value, None);
+        assert_eq!(
+            remove.json_patch(true, Some("payments")),
+            json!([
+                {"op": "test", "path": "/metadata/labels/team", "value": "payments"},
+                {"op": "remove", "path": "/metadata/labels/team"},
+            ])
+        );
+    }
+}
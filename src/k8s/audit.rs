@@ -0,0 +1,267 @@
+//!Who changed what: mutating calls from a Kubernetes audit log, ie: the
+//!file of the log backend or one a webhook receiver writes, tailed while
+//!navipod runs.  Calls on workloads are matched with the pod changes the
+//!pod watch saw right after them.
+//!
+use crate::k8s::watch::WatchUpdate;
+use crate::shutdown;
+use chrono::{DateTime, TimeDelta, Utc};
+use k8s_openapi::api::core::v1::Pod;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::io::SeekFrom;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::fs::File;
+use tokio::io::{AsyncBufReadExt, AsyncSeekExt, BufReader};
+use tracing::debug;
+
+const MAX_ENTRIES: usize = 500;
+const MAX_POD_CHANGES: usize = 2000;
+/// how much of an existing log is read at start
+const BACKLOG_BYTES: u64 = 1024 * 1024;
+const POLL: Duration = Duration::from_secs(1);
+/// pod changes this soon after a call are attributed to it
+const CORRELATION_WINDOW_SECS: i64 = 120;
+
+const MUTATING_VERBS: [&str; 5] = ["create", "update", "patch", "delete", "deletecollection"];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditEntry {
+    pub time: DateTime<Utc>,
+    pub user: String,
+    pub verb: String,
+    pub resource: String,
+    pub namespace: Option<String>,
+    pub name: Option<String>,
+    pub code: Option<i64>,
+}
+
+impl AuditEntry {
+    fn from_event(event: &Value) -> Option<Self> {
+        if event["stage"].as_str() != Some("ResponseComplete") {
+            return None;
+        }
+        let verb = event["verb"].as_str()?;
+        if !MUTATING_VERBS.contains(&verb) {
+            return None;
+        }
+        let object = &event["objectRef"];
+        let resource = match object["subresource"].as_str() {
+            Some(sub) => format!("{}/{sub}", object["resource"].as_str()?),
+            None => object["resource"].as_str()?.to_string(),
+        };
+        Some(Self {
+            time: event["stageTimestamp"]
+                .as_str()
+                .and_then(|t| DateTime::parse_from_rfc3339(t).ok())?
+                .with_timezone(&Utc),
+            user: event["user"]["username"]
+                .as_str()
+                .unwrap_or("unknown")
+                .to_string(),
+            verb: verb.to_string(),
+            resource,
+            namespace: object["namespace"].as_str().map(String::from),
+            name: object["name"].as_str().map(String::from),
+            code: event["responseStatus"]["code"].as_i64(),
+        })
+    }
+}
+
+/// the mutating calls in one line of the log, a single audit Event or an
+/// EventList as webhooks deliver them
+#[must_use]
+pub fn parse_line(line: &str) -> Vec<AuditEntry> {
+    let Ok(value) = serde_json::from_str::<Value>(line) else {
+        return vec![];
+    };
+    match value["kind"].as_str() {
+        Some("EventList") => value["items"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(AuditEntry::from_event)
+            .collect(),
+        _ => AuditEntry::from_event(&value).into_iter().collect(),
+    }
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    source: Option<String>,
+    /// only calls in this namespace are kept
+    namespace: Option<String>,
+    entries: VecDeque<AuditEntry>,
+    /// (replicaset of the pod, when the watch saw it change)
+    pod_changes: VecDeque<(String, DateTime<Utc>)>,
+}
+
+impl Registry {
+    fn push(&mut self, entry: AuditEntry) {
+        if self.namespace.is_some() && entry.namespace != self.namespace {
+            return;
+        }
+        if self.entries.len() == MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    fn record_pod_change(&mut self, replicaset: String, at: DateTime<Utc>) {
+        if self.pod_changes.len() == MAX_POD_CHANGES {
+            self.pod_changes.pop_front();
+        }
+        self.pod_changes.push_back((replicaset, at));
+    }
+
+    /// pod changes in the window after a call on a deployment, replicaset or
+    /// pod, matched by name prefix
+    fn pod_changes_after(&self, entry: &AuditEntry) -> usize {
+        let Some(name) = &entry.name else {
+            return 0;
+        };
+        let workload = matches!(
+            entry.resource.as_str(),
+            "deployments" | "deployments/scale" | "replicasets" | "replicasets/scale" | "pods"
+        );
+        if !workload {
+            return 0;
+        }
+        let until = entry.time + TimeDelta::seconds(CORRELATION_WINDOW_SECS);
+        self.pod_changes
+            .iter()
+            .filter(|(replicaset, at)| {
+                *at >= entry.time
+                    && *at <= until
+                    && (replicaset == name || replicaset.starts_with(&format!("{name}-")))
+            })
+            .count()
+    }
+}
+
+static REGISTRY: Mutex<Option<Registry>> = Mutex::new(None);
+
+fn with_registry<T>(f: impl FnOnce(&mut Registry) -> T) -> Option<T> {
+    let mut registry = REGISTRY.lock().ok()?;
+    Some(f(registry.get_or_insert_with(Registry::default)))
+}
+
+/// the audit log being tailed, `None` without `--audit-log`
+#[must_use]
+pub fn source() -> Option<String> {
+    with_registry(|registry| registry.source.clone()).flatten()
+}
+
+/// Feed pod watch updates in so calls can be matched with what followed.
+pub fn record_pod_change(update: &WatchUpdate<Pod>) {
+    let (WatchUpdate::Applied(pod) | WatchUpdate::Deleted(pod)) = update else {
+        return;
+    };
+    let owner = pod
+        .metadata
+        .owner_references
+        .iter()
+        .flatten()
+        .find(|owner| owner.kind == "ReplicaSet");
+    if let Some(owner) = owner {
+        let name = owner.name.clone();
+        with_registry(|registry| registry.record_pod_change(name, Utc::now()));
+    }
+}
+
+/// newest first, each with the pod changes seen right after it
+#[must_use]
+pub fn recent() -> Vec<(AuditEntry, usize)> {
+    with_registry(|registry| {
+        registry
+            .entries
+            .iter()
+            .rev()
+            .map(|entry| (entry.clone(), registry.pod_changes_after(entry)))
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Tail `path` for as long as navipod runs, starting with the end of what
+/// is already there, keeping the calls made in `namespace`.
+pub fn spawn_tail(path: String, namespace: String) {
+    with_registry(|registry| {
+        registry.source = Some(path.clone());
+        registry.namespace = Some(namespace);
+    });
+    tokio::spawn(async move {
+        let result = tokio::select! {
+            result = tail(&path) => result,
+            () = shutdown::token().cancelled() => Ok(()),
+        };
+        if let Err(e) = result {
+            debug!("stopped reading audit log {path}: {e}");
+        }
+    });
+}
+
+async fn tail(path: &str) -> std::io::Result<()> {
+    let mut file = File::open(path).await?;
+    let len = file.metadata().await?.len();
+    let start = len.saturating_sub(BACKLOG_BYTES);
+    file.seek(SeekFrom::Start(start)).await?;
+    let mut reader = BufReader::new(file);
+    let mut line = String::new();
+    if start > 0 {
+        // most likely the middle of an entry
+        reader.read_line(&mut line).await?;
+    }
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            tokio::time::sleep(POLL).await;
+            continue;
+        }
+        let entries = parse_line(&line);
+        with_registry(|registry| entries.into_iter().for_each(|e| registry.push(e)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_correlate() {
+        let event = |stage: &str, verb: &str, resource: &str| {
+            format!(
+                r#"{{"kind":"Event","stage":"{stage}","verb":"{verb}","stageTimestamp":"2024-03-01T10:00:00.000000Z","user":{{"username":"alice"}},"objectRef":{{"resource":"{resource}","namespace":"default","name":"web"}},"responseStatus":{{"code":200}}}}"#
+            )
+        };
+        assert!(parse_line(&event("ResponseStarted", "patch", "deployments")).is_empty());
+        assert!(parse_line(&event("ResponseComplete", "get", "deployments")).is_empty());
+        let entries = parse_line(&event("ResponseComplete", "patch", "deployments"));
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].user, "alice");
+        assert_eq!(entries[0].code, Some(200));
+
+        let list = format!(
+            r#"{{"kind":"EventList","items":[{},{}]}}"#,
+            event("ResponseComplete", "delete", "pods"),
+            event("ResponseComplete", "create", "configmaps")
+        );
+        assert_eq!(parse_line(&list).len(), 2);
+
+        let mut registry = Registry {
+            namespace: Some("default".to_string()),
+            ..Registry::default()
+        };
+        registry.push(entries[0].clone());
+        let mut elsewhere = entries[0].clone();
+        elsewhere.namespace = Some("kube-system".to_string());
+        registry.push(elsewhere);
+        assert_eq!(registry.entries.len(), 1);
+        let at = entries[0].time;
+        registry.record_pod_change("web-5d8f7".to_string(), at + TimeDelta::seconds(5));
+        registry.record_pod_change("web-5d8f7".to_string(), at + TimeDelta::seconds(500));
+        registry.record_pod_change("webhook-1".to_string(), at + TimeDelta::seconds(5));
+        assert_eq!(registry.pod_changes_after(&entries[0]), 1);
+    }
+}
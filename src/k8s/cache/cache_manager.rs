@@ -0,0 +1,435 @@
+//!Process wide cache and the startup warmup that fills it.
+//!
+use crate::error::Result;
+use crate::k8s::cache::fetcher::fetch;
+use crate::k8s::cache::history::{NavigationHistory, ScopeHistory, EVENTS_KEY};
+use crate::k8s::cache::throttle;
+use crate::k8s::cache::{
+    BackgroundFetcher, CachedData, DataCache, DataRequest, FetchPriority, FetchStatus,
+};
+use crate::k8s::client_manager::get_client;
+use crate::k8s::watch_manager;
+use crate::tui::data::{DeploymentRow, PluginRow, ResourceEvent, Rs, RsPod, StatefulSetRow};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tracing::debug;
+
+pub const DEFAULT_MAX_PREFETCH_REPLICASETS: usize = 5;
+/// how long a view waits on a promoted prefetch before fetching itself
+const PROMOTED_WAIT: Duration = Duration::from_secs(2);
+/// navigation is written out at most this often while browsing, and on exit
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+static CACHE: OnceLock<Arc<DataCache>> = OnceLock::new();
+static FETCHER: OnceLock<BackgroundFetcher> = OnceLock::new();
+static HISTORY: OnceLock<Mutex<NavigationHistory>> = OnceLock::new();
+static SCOPE: OnceLock<String> = OnceLock::new();
+/// when the navigation history was last written out
+static SAVED: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// fetches filling the cache in the background
+static BACKGROUND: AtomicUsize = AtomicUsize::new(0);
+/// fetches a view is waiting on because the cache could not answer
+static BLOCKING: AtomicUsize = AtomicUsize::new(0);
+
+/// counts one fetch for as long as it is alive
+pub(crate) struct InFlight(&'static AtomicUsize);
+
+impl InFlight {
+    pub(crate) fn background() -> Self {
+        BACKGROUND.fetch_add(1, Ordering::Relaxed);
+        Self(&BACKGROUND)
+    }
+
+    pub(crate) fn blocking() -> Self {
+        BLOCKING.fetch_add(1, Ordering::Relaxed);
+        Self(&BLOCKING)
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[must_use]
+pub fn has_network_activity() -> bool {
+    BACKGROUND.load(Ordering::Relaxed) > 0
+}
+
+#[must_use]
+pub fn has_blocking_activity() -> bool {
+    BLOCKING.load(Ordering::Relaxed) > 0
+}
+
+pub fn get_cache() -> Arc<DataCache> {
+    CACHE.get_or_init(|| Arc::new(DataCache::default())).clone()
+}
+
+fn history() -> &'static Mutex<NavigationHistory> {
+    HISTORY.get_or_init(|| Mutex::new(NavigationHistory::load()))
+}
+
+/// learned navigation is kept per cluster and namespace
+async fn current_scope() -> String {
+    kube::Config::infer().await.map_or_else(
+        |_| "default".to_string(),
+        |config| format!("{}/{}", config.cluster_url, config.default_namespace),
+    )
+}
+
+fn scope() -> &'static str {
+    SCOPE.get().map_or("default", String::as_str)
+}
+
+/// desired replicas from the "ready/desired" column
+fn desired_replicas(rs: &Rs) -> u32 {
+    rs.pods
+        .split('/')
+        .nth(1)
+        .and_then(|desired| desired.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Decide what to prefetch once the replicasets are known: replicasets the
+/// user keeps opening in this namespace come first, then the largest ones,
+/// and the namespace events.  How many replicasets get prefetched grows
+/// with the number the user regularly works with.
+#[must_use]
+pub fn warmup_plan(
+    replicasets: &[Rs],
+    history: &ScopeHistory,
+    default_max_replicasets: usize,
+) -> Vec<(FetchPriority, DataRequest)> {
+    let mut candidates: Vec<&Rs> = replicasets
+        .iter()
+        .filter(|rs| rs.selectors.is_some())
+        .collect();
+    candidates.sort_by(|a, b| {
+        history
+            .weight(&b.name)
+            .total_cmp(&history.weight(&a.name))
+            .then_with(|| desired_replicas(b).cmp(&desired_replicas(a)))
+    });
+
+    let mut plan: Vec<(FetchPriority, DataRequest)> = candidates
+        .into_iter()
+        .take(history.prefetch_limit(default_max_replicasets))
+        .filter_map(|rs| {
+            let priority = if history.weight(&rs.name) > 0.0 {
+                FetchPriority::Medium
+            } else {
+                FetchPriority::Low
+            };
+            rs.selectors
+                .clone()
+                .map(|selector| (priority, DataRequest::Pods { selector }))
+        })
+        .collect();
+
+    // a user with history who never opens events does not need them early
+    let events_priority = if history.weights.is_empty() || history.weight(EVENTS_KEY) > 0.0 {
+        FetchPriority::Medium
+    } else {
+        FetchPriority::Low
+    };
+    plan.push((events_priority, DataRequest::Events));
+    plan
+}
+
+/// Start the background fetcher and the watches of the client's namespace,
+/// and warm the cache.  Replicasets are fetched first since the warmup plan
+/// depends on them.
+pub async fn initialize_cache() {
+    let cache = get_cache();
+    let _ = SCOPE.set(current_scope().await);
+    let fetcher = FETCHER.get_or_init(|| BackgroundFetcher::spawn(cache.clone()));
+    if let Ok(client) = get_client().await {
+        watch_manager::manager().add(client.default_namespace());
+    }
+
+    let replicasets = match fetch(&DataRequest::ReplicaSets).await {
+        Ok(CachedData::ReplicaSets(replicasets)) => replicasets,
+        Ok(_) => vec![],
+        Err(e) => {
+            debug!("cache warmup failed: {e}");
+            return;
+        }
+    };
+    cache.put(
+        DataRequest::ReplicaSets,
+        CachedData::ReplicaSets(replicasets.clone()),
+    );
+
+    let plan = history().lock().map_or_else(
+        |_| vec![],
+        |history| {
+            warmup_plan(
+                &replicasets,
+                &history.scope(scope()),
+                throttle::batch(DEFAULT_MAX_PREFETCH_REPLICASETS)
+                    .unwrap_or(DEFAULT_MAX_PREFETCH_REPLICASETS),
+            )
+        },
+    );
+    for (priority, request) in plan {
+        fetcher.request(priority, request);
+    }
+}
+
+/// Fetch `request` in the background ahead of the user, `false` when it
+/// is cached already or there is no fetcher yet.  It waits at medium
+/// priority so the throttle holds and sheds it like any other prefetch.
+pub fn prefetch(request: DataRequest) -> bool {
+    if get_cache().status(&request) != FetchStatus::Missing {
+        return false;
+    }
+    FETCHER.get().is_some_and(|fetcher| {
+        fetcher.request(FetchPriority::Medium, request);
+        true
+    })
+}
+
+/// Save the navigation history with this session's hit rate, fetches
+/// under way are dropped with the cache.
+pub fn shutdown_cache() {
+    if let Some(Ok(history)) = HISTORY.get().map(Mutex::lock) {
+        history.save();
+    }
+}
+
+/// Remember what the user opened from a view so the next session warms it.
+/// `resource` is a replicaset name or `EVENTS_KEY`.
+pub fn record_navigation(resource: &str) {
+    let Ok(mut history) = history().lock() else {
+        return;
+    };
+    history.scope_mut(scope()).record(resource);
+    if save_due(Instant::now()) {
+        // off the event loop, a crash loses at most the last interval
+        let history = history.clone();
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn_blocking(move || history.save());
+        }
+    }
+}
+
+/// whether `SAVE_INTERVAL` has passed since the history was last saved
+fn save_due(now: Instant) -> bool {
+    let Ok(mut saved) = SAVED.lock() else {
+        return false;
+    };
+    let due = saved.is_none_or(|at| now.duration_since(at) >= SAVE_INTERVAL);
+    if due {
+        *saved = Some(now);
+    }
+    due
+}
+
+fn record_lookup(hit: bool) {
+    if let Ok(mut history) = history().lock() {
+        history.scope_mut(scope()).record_lookup(hit);
+    }
+}
+
+/// cache hit rate of drill-downs in the current namespace
+#[must_use]
+pub fn hit_rate() -> Option<f64> {
+    history().lock().ok()?.scope(scope()).hit_rate()
+}
+
+/// `request` as the background fetcher puts it, `None` if it does not
+/// within `PROMOTED_WAIT`
+async fn landed(
+    cache: &DataCache,
+    mut puts: broadcast::Receiver<DataRequest>,
+    request: &DataRequest,
+) -> Option<CachedData> {
+    let wait = async {
+        loop {
+            match puts.recv().await {
+                Ok(put) if put == *request => return cache.get(request),
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    };
+    tokio::time::timeout(PROMOTED_WAIT, wait)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Fresh cached data, or fetch it now and cache it for the next caller.
+/// A request queued as a prefetch is promoted and waited for rather than
+/// fetched twice.
+///
+/// # Errors
+///
+/// Will return `Err` if the data is not fresh and can not be fetched
+pub async fn get_or_fetch(request: DataRequest) -> Result<CachedData> {
+    let cache = get_cache();
+    if cache.status(&request) == FetchStatus::Fresh {
+        if let Some(data) = cache.get(&request) {
+            return Ok(data);
+        }
+    }
+    let puts = cache.subscribe();
+    if FETCHER
+        .get()
+        .is_some_and(|fetcher| fetcher.promote(&request))
+    {
+        if let Some(data) = landed(&cache, puts, &request).await {
+            return Ok(data);
+        }
+        debug!("promoted fetch of {request} did not land, fetching it now");
+    }
+    let fetched = {
+        let _blocking = InFlight::blocking();
+        fetch(&request).await
+    };
+    let data = fetched.inspect_err(|e| cache.mark_failed(&request, e.to_string()))?;
+    cache.put(request, data.clone());
+    Ok(data)
+}
+
+/// Fetch `request` now whatever the cache holds, for when the cache is
+/// suspected to be wrong.  The entry is dropped first and the fetch runs at
+/// once, ahead of anything queued for the background fetcher.
+///
+/// # Errors
+///
+/// Will return `Err` if the data can not be fetched
+pub async fn force_refresh(request: DataRequest) -> Result<(CachedData, Duration)> {
+    let cache = get_cache();
+    cache.invalidate(&request);
+    let started = Instant::now();
+    let data = {
+        let _blocking = InFlight::blocking();
+        fetch(&request).await?
+    };
+    let took = started.elapsed();
+    cache.put(request, data.clone());
+    Ok((data, took))
+}
+
+#[must_use]
+pub fn cached_replicasets() -> Option<Vec<Rs>> {
+    match get_cache().get(&DataRequest::ReplicaSets)? {
+        CachedData::ReplicaSets(replicasets) => Some(replicasets),
+        _ => None,
+    }
+}
+
+#[must_use]
+pub fn cached_pods(selector: &BTreeMap<String, String>) -> Option<Vec<RsPod>> {
+    let request = DataRequest::Pods {
+        selector: selector.clone(),
+    };
+    let pods = match get_cache().get(&request) {
+        Some(CachedData::Pods(pods)) => Some(pods),
+        _ => None,
+    };
+    record_lookup(pods.is_some());
+    pods
+}
+
+#[must_use]
+pub fn cached_events() -> Option<Vec<ResourceEvent>> {
+    let events = match get_cache().get(&DataRequest::Events) {
+        Some(CachedData::Events(events)) => Some(events),
+        _ => None,
+    };
+    record_lookup(events.is_some());
+    events
+}
+
+#[must_use]
+pub fn cached_deployments() -> Option<Vec<DeploymentRow>> {
+    let deployments = match get_cache().get(&DataRequest::Deployments) {
+        Some(CachedData::Deployments(deployments)) => Some(deployments),
+        _ => None,
+    };
+    record_lookup(deployments.is_some());
+    deployments
+}
+
+#[must_use]
+pub fn cached_statefulsets() -> Option<Vec<StatefulSetRow>> {
+    let statefulsets = match get_cache().get(&DataRequest::StatefulSets) {
+        Some(CachedData::StatefulSets(statefulsets)) => Some(statefulsets),
+        _ => None,
+    };
+    record_lookup(statefulsets.is_some());
+    statefulsets
+}
+
+#[must_use]
+pub fn cached_plugin_rows(plugin: &str) -> Option<Vec<PluginRow>> {
+    let request = DataRequest::Custom {
+        plugin: plugin.to_string(),
+    };
+    match get_cache().get(&request)? {
+        CachedData::Custom(rows) => Some(rows),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rs(name: &str, pods: &str) -> Rs {
+        Rs {
+            name: name.to_string(),
+            owner: String::new(),
+            description: String::new(),
+            age: String::new(),
+            pods: pods.to_string(),
+            deprecations: String::new(),
+            selectors: Some(BTreeMap::from([("app".to_string(), name.to_string())])),
+            events: vec![],
+            extra: vec![],
+        }
+    }
+
+    fn selector_app(request: &DataRequest) -> Option<&str> {
+        match request {
+            DataRequest::Pods { selector } => selector.get("app").map(String::as_str),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_warmup_plan_prefers_history_then_size() {
+        let replicasets = vec![rs("small", "1/1"), rs("big", "9/9"), rs("visited", "2/2")];
+        let mut history = ScopeHistory::default();
+        history.record("visited");
+        history.record(EVENTS_KEY);
+
+        let plan = warmup_plan(&replicasets, &history, 2);
+        assert_eq!(plan.len(), 3);
+        assert_eq!(plan[0].0, FetchPriority::Medium);
+        assert_eq!(selector_app(&plan[0].1), Some("visited"));
+        assert_eq!(plan[1].0, FetchPriority::Low);
+        assert_eq!(selector_app(&plan[1].1), Some("big"));
+        assert_eq!(plan[2], (FetchPriority::Medium, DataRequest::Events));
+    }
+
+    #[test]
+    fn test_warmup_plan_demotes_unused_events() {
+        let replicasets = vec![rs("a", "1/1")];
+        let mut history = ScopeHistory::default();
+        history.record("a");
+
+        let plan = warmup_plan(&replicasets, &history, 2);
+        assert_eq!(
+            plan.last(),
+            Some(&(FetchPriority::Low, DataRequest::Events))
+        );
+    }
+}
@@ -0,0 +1,346 @@
+use crate::k8s::cache::memory::{estimate, DEFAULT_CACHE_SIZE_MB};
+use crate::k8s::list_filter::ListFilter;
+use crate::k8s::scheduling::NodeInfo;
+use crate::tui::data::{
+    Container, DeploymentRow, Ingress, PluginRow, ResourceEvent, Rs, RsPod, StatefulSetRow,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+/// entries older than this are still served but get refetched
+const DEFAULT_TTL: Duration = Duration::from_secs(30);
+
+/// Order matters: the fetcher works on the highest priority first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FetchPriority {
+    Low,
+    Medium,
+    /// the user is waiting on it
+    High,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DataRequest {
+    ReplicaSets,
+    /// the replicasets the api server selected for the filter bar
+    ReplicaSetsMatching(ListFilter),
+    Pods {
+        selector: BTreeMap<String, String>,
+    },
+    Events,
+    Nodes,
+    Containers {
+        selector: BTreeMap<String, String>,
+        pod: String,
+    },
+    /// the table view of a plugin
+    Custom {
+        plugin: String,
+    },
+    /// the ingress rows of the replicaset `selector` picks
+    Ingresses {
+        selector: BTreeMap<String, String>,
+    },
+    Deployments,
+    StatefulSets,
+}
+
+impl fmt::Display for DataRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let selector = |selector: &BTreeMap<String, String>| {
+            selector
+                .iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        match self {
+            Self::ReplicaSets => write!(f, "replicasets"),
+            Self::ReplicaSetsMatching(filter) => write!(f, "replicasets {filter}"),
+            Self::Pods { selector: s } => write!(f, "pods {}", selector(s)),
+            Self::Events => write!(f, "events"),
+            Self::Nodes => write!(f, "nodes"),
+            Self::Containers { pod, .. } => write!(f, "containers {pod}"),
+            Self::Custom { plugin } => write!(f, "plugin {plugin}"),
+            Self::Ingresses { selector: s } => write!(f, "ingresses {}", selector(s)),
+            Self::Deployments => write!(f, "deployments"),
+            Self::StatefulSets => write!(f, "statefulsets"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum CachedData {
+    ReplicaSets(Vec<Rs>),
+    Pods(Vec<RsPod>),
+    Events(Vec<ResourceEvent>),
+    Nodes(Vec<NodeInfo>),
+    Containers(Vec<Container>),
+    Custom(Vec<PluginRow>),
+    Ingresses(Vec<Ingress>),
+    Deployments(Vec<DeploymentRow>),
+    StatefulSets(Vec<StatefulSetRow>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FetchStatus {
+    Missing,
+    Stale,
+    Fresh,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    data: CachedData,
+    fetched_at: Instant,
+    /// estimated, see `memory::estimate`
+    bytes: usize,
+    /// the last refetch failed, `data` is what an earlier one got
+    error: Option<String>,
+}
+
+/// How much to trust a cached entry, for the badge in the view headers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Freshness {
+    pub age: Duration,
+    /// older than the ttl
+    pub stale: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct DataCache {
+    entries: RwLock<HashMap<DataRequest, Entry>>,
+    ttl: Duration,
+    /// the oldest entries are evicted beyond this
+    max_bytes: AtomicUsize,
+    /// every put, for views waiting on a prefetch
+    landed: broadcast::Sender<DataRequest>,
+}
+
+impl Default for DataCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_TTL)
+    }
+}
+
+impl DataCache {
+    #[must_use]
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: RwLock::new(HashMap::new()),
+            ttl,
+            max_bytes: AtomicUsize::new(DEFAULT_CACHE_SIZE_MB * 1024 * 1024),
+            landed: broadcast::channel(64).0,
+        }
+    }
+
+    pub fn set_max_bytes(&self, max_bytes: usize) {
+        self.max_bytes.store(max_bytes, Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Store `data`, evicting the least recently fetched other entries
+    /// while the cache is over its size.
+    pub fn put(&self, request: DataRequest, data: CachedData) {
+        if let Ok(mut entries) = self.entries.write() {
+            let bytes = estimate(&data);
+            entries.insert(
+                request.clone(),
+                Entry {
+                    data,
+                    fetched_at: Instant::now(),
+                    bytes,
+                    error: None,
+                },
+            );
+            let max_bytes = self.max_bytes();
+            let mut total: usize = entries.values().map(|entry| entry.bytes).sum();
+            while total > max_bytes {
+                let Some(oldest) = entries
+                    .iter()
+                    .filter(|(key, _)| **key != request)
+                    .min_by_key(|(_, entry)| entry.fetched_at)
+                    .map(|(key, _)| key.clone())
+                else {
+                    break;
+                };
+                if let Some(evicted) = entries.remove(&oldest) {
+                    total -= evicted.bytes;
+                }
+            }
+        }
+        // nobody listening is fine
+        let _ = self.landed.send(request);
+    }
+
+    /// the requests put from now on
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<DataRequest> {
+        self.landed.subscribe()
+    }
+
+    #[must_use]
+    pub fn get(&self, request: &DataRequest) -> Option<CachedData> {
+        self.entries
+            .read()
+            .ok()?
+            .get(request)
+            .map(|entry| entry.data.clone())
+    }
+
+    #[must_use]
+    pub fn status(&self, request: &DataRequest) -> FetchStatus {
+        let Ok(entries) = self.entries.read() else {
+            return FetchStatus::Missing;
+        };
+        match entries.get(request) {
+            None => FetchStatus::Missing,
+            Some(entry) if entry.fetched_at.elapsed() > self.ttl => FetchStatus::Stale,
+            Some(_) => FetchStatus::Fresh,
+        }
+    }
+
+    /// how long ago `request` was fetched, `None` if it never was
+    #[must_use]
+    pub fn age(&self, request: &DataRequest) -> Option<Duration> {
+        self.entries
+            .read()
+            .ok()?
+            .get(request)
+            .map(|entry| entry.fetched_at.elapsed())
+    }
+
+    /// Drop `request` so nobody is served it until it is fetched again.
+    pub fn invalidate(&self, request: &DataRequest) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.remove(request);
+        }
+    }
+
+    /// Drop every entry `stale` picks, the requests dropped are returned so
+    /// they can be fetched again.
+    pub fn invalidate_where(&self, stale: impl Fn(&DataRequest) -> bool) -> Vec<DataRequest> {
+        let Ok(mut entries) = self.entries.write() else {
+            return vec![];
+        };
+        let dropped: Vec<DataRequest> = entries.keys().filter(|key| stale(key)).cloned().collect();
+        for request in &dropped {
+            entries.remove(request);
+        }
+        dropped
+    }
+
+    /// Remember that refetching `request` failed, the data it has is kept.
+    pub fn mark_failed(&self, request: &DataRequest, error: String) {
+        if let Ok(mut entries) = self.entries.write() {
+            if let Some(entry) = entries.get_mut(request) {
+                entry.error = Some(error);
+            }
+        }
+    }
+
+    /// age and health of `request`, `None` if it was never fetched
+    #[must_use]
+    pub fn freshness(&self, request: &DataRequest) -> Option<Freshness> {
+        self.entries.read().ok()?.get(request).map(|entry| {
+            let age = entry.fetched_at.elapsed();
+            Freshness {
+                age,
+                stale: age > self.ttl,
+                error: entry.error.clone(),
+            }
+        })
+    }
+
+    #[must_use]
+    pub const fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// every entry with how long ago it was fetched
+    #[must_use]
+    pub fn snapshot(&self) -> Vec<(DataRequest, CachedData, Duration)> {
+        self.entries.read().map_or_else(
+            |_| vec![],
+            |entries| {
+                entries
+                    .iter()
+                    .map(|(request, entry)| {
+                        (
+                            request.clone(),
+                            entry.data.clone(),
+                            entry.fetched_at.elapsed(),
+                        )
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    /// estimated bytes of every entry
+    #[must_use]
+    pub fn usage(&self) -> Vec<(DataRequest, usize)> {
+        self.entries.read().map_or_else(
+            |_| vec![],
+            |entries| {
+                entries
+                    .iter()
+                    .map(|(request, entry)| (request.clone(), entry.bytes))
+                    .collect()
+            },
+        )
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.read().map_or(0, |entries| entries.len())
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscribers_see_puts() {
+        let cache = DataCache::default();
+        let mut landed = cache.subscribe();
+        assert_eq!(cache.status(&DataRequest::Events), FetchStatus::Missing);
+        cache.put(DataRequest::Events, CachedData::Events(vec![]));
+        assert_eq!(landed.try_recv().unwrap(), DataRequest::Events);
+        assert_eq!(cache.status(&DataRequest::Events), FetchStatus::Fresh);
+        assert!(cache.age(&DataRequest::Events).is_some());
+
+        cache.mark_failed(&DataRequest::Events, "timed out".to_string());
+        let freshness = cache.freshness(&DataRequest::Events).unwrap();
+        assert!(!freshness.stale);
+        assert_eq!(freshness.error.as_deref(), Some("timed out"));
+        cache.put(DataRequest::Events, CachedData::Events(vec![]));
+        assert_eq!(cache.freshness(&DataRequest::Events).unwrap().error, None);
+        assert_eq!(cache.freshness(&DataRequest::Nodes), None);
+        cache.invalidate(&DataRequest::Events);
+        assert_eq!(cache.status(&DataRequest::Events), FetchStatus::Missing);
+
+        cache.put(DataRequest::Events, CachedData::Events(vec![]));
+        cache.put(DataRequest::Nodes, CachedData::Nodes(vec![]));
+        assert_eq!(
+            cache.invalidate_where(|request| *request == DataRequest::Nodes),
+            [DataRequest::Nodes]
+        );
+        assert_eq!(cache.len(), 1);
+    }
+}
@@ -0,0 +1,451 @@
+use crate::error::{Error, Result};
+use crate::k8s::cache::cache_manager::InFlight;
+use crate::k8s::cache::throttle;
+use crate::k8s::cache::{CachedData, DataCache, DataRequest, FetchPriority, FetchStatus};
+use crate::k8s::containers::list as list_containers;
+use crate::k8s::events::list_all;
+use crate::k8s::pods::list_rspods;
+use crate::k8s::rs::{list_replicas, list_replicas_paged};
+use crate::k8s::rs_ingress::list_for_selector;
+use crate::k8s::scheduling::list_nodes;
+use crate::k8s::workloads::{list_deployments, list_statefulsets};
+use crate::plugins::view_rows;
+use crate::tui::data::PluginRow;
+use futures::future::{BoxFuture, Shared};
+use futures::FutureExt;
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::debug;
+
+/// prefetches kept queued while throttled, before halving
+const QUEUE_BATCH: usize = 16;
+/// how often a paused prefetch checks whether it may run
+const PAUSE_POLL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, PartialEq, Eq)]
+struct Queued {
+    priority: FetchPriority,
+    /// lower sequence numbers were queued first
+    seq: u64,
+    request: DataRequest,
+}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+type Flight<T> = Shared<BoxFuture<'static, std::result::Result<T, Arc<Error>>>>;
+
+/// Fetches under way keyed by request, so callers asking for the same data
+/// at once share one fetch, ie: the events of the events view and of the
+/// background fetcher.
+struct Flights<T> {
+    flights: OnceLock<Mutex<HashMap<DataRequest, Flight<T>>>>,
+}
+
+impl<T: Clone + Send + Sync + 'static> Flights<T> {
+    const fn new() -> Self {
+        Self {
+            flights: OnceLock::new(),
+        }
+    }
+
+    fn flights(&self) -> &Mutex<HashMap<DataRequest, Flight<T>>> {
+        self.flights.get_or_init(Mutex::default)
+    }
+
+    /// Join the fetch of `request` under way, or start one with `start`.
+    /// The fetch is forgotten once done, so the next caller fetches anew.
+    async fn join(
+        &'static self,
+        request: &DataRequest,
+        start: impl FnOnce() -> BoxFuture<'static, Result<T>>,
+    ) -> Result<T> {
+        let flight = {
+            let mut flights = self
+                .flights()
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            flights
+                .entry(request.clone())
+                .or_insert_with(|| {
+                    let fetching = start();
+                    let request = request.clone();
+                    async move {
+                        let fetched = fetching.await.map_err(Arc::new);
+                        if let Ok(mut flights) = self.flights().lock() {
+                            flights.remove(&request);
+                        }
+                        fetched
+                    }
+                    .boxed()
+                    .shared()
+                })
+                .clone()
+        };
+        // the last one out gets the error itself, the others a copy
+        flight
+            .await
+            .map_err(|e| Arc::try_unwrap(e).unwrap_or_else(copy_error))
+    }
+}
+
+/// `e` for a caller that shared the failed fetch, classified the same
+fn copy_error(e: Arc<Error>) -> Error {
+    match e.as_ref() {
+        Error::Kube(kube::Error::Api(response)) => Error::Kube(kube::Error::Api(response.clone())),
+        Error::Auth(message) => Error::Auth(message.clone()),
+        Error::Forbidden(message) => Error::Forbidden(message.clone()),
+        Error::NotFound(message) => Error::NotFound(message.clone()),
+        Error::Conflict(message) => Error::Conflict(message.clone()),
+        Error::Parse(message) => Error::Parse(message.clone()),
+        Error::Plugin(message) => Error::Plugin(message.clone()),
+        Error::Pane(message) => Error::Pane(message.clone()),
+        Error::Timeout { operation, after } => Error::Timeout {
+            operation: operation.clone(),
+            after: *after,
+        },
+        Error::Network(message) => Error::Network(message.clone()),
+        Error::Io(io) => Error::Io(std::io::Error::new(io.kind(), io.to_string())),
+        Error::Shared(e) => Error::Shared(e.clone()),
+        Error::Kube(_) | Error::Infer(_) | Error::UserAgentError(_) | Error::HttpHeader(_) => {
+            Error::Shared(e)
+        }
+    }
+}
+
+static FLIGHTS: Flights<CachedData> = Flights::new();
+
+/// Fetch `request`, sharing the fetch of it already under way if there is
+/// one.  Boxed since fetching some data fetches other data through here.
+///
+/// # Errors
+///
+/// Will return `Err` if data can not be retrieved from k8s cluster api
+pub fn fetch(request: &DataRequest) -> BoxFuture<'static, Result<CachedData>> {
+    let request = request.clone();
+    async move {
+        let owned = request.clone();
+        FLIGHTS
+            .join(&request, move || fetch_now(owned).boxed())
+            .await
+    }
+    .boxed()
+}
+
+async fn fetch_now(request: DataRequest) -> Result<CachedData> {
+    let request = &request;
+    Ok(match request {
+        DataRequest::ReplicaSets => CachedData::ReplicaSets(list_replicas().await?),
+        DataRequest::ReplicaSetsMatching(filter) => {
+            CachedData::ReplicaSets(list_replicas_paged(filter, &|_| {}).await?)
+        }
+        DataRequest::Pods { selector } => CachedData::Pods(list_rspods(selector.clone()).await?),
+        DataRequest::Events => CachedData::Events(list_all().await?),
+        DataRequest::Nodes => CachedData::Nodes(list_nodes().await?),
+        DataRequest::Containers { selector, pod } => {
+            CachedData::Containers(list_containers(selector.clone(), pod.clone()).await?)
+        }
+        DataRequest::Custom { plugin } => CachedData::Custom(
+            view_rows(plugin)
+                .await?
+                .into_iter()
+                .map(PluginRow::new)
+                .collect(),
+        ),
+        DataRequest::Ingresses { selector } => {
+            CachedData::Ingresses(list_for_selector(selector).await?)
+        }
+        DataRequest::Deployments => CachedData::Deployments(list_deployments().await?),
+        DataRequest::StatefulSets => CachedData::StatefulSets(list_statefulsets().await?),
+    })
+}
+
+/// The requests waiting for the fetcher, indexed by request so asking for
+/// one already queued only ever raises its priority.  A raised request
+/// leaves its old entry in the heap, which is skipped when popped.
+#[derive(Debug, Default)]
+struct Queue {
+    heap: BinaryHeap<Queued>,
+    /// the priority each queued request is waiting at
+    index: HashMap<DataRequest, FetchPriority>,
+    seq: u64,
+}
+
+/// what the fetcher should do next
+#[derive(Debug, PartialEq, Eq)]
+enum Next {
+    Fetch(DataRequest),
+    /// only prefetches are queued and the throttle holds them
+    Paused,
+    Empty,
+}
+
+impl Queue {
+    /// Queue `request` at `priority`, `false` when it already waits at
+    /// that priority or a higher one.
+    fn push(&mut self, priority: FetchPriority, request: DataRequest) -> bool {
+        if self
+            .index
+            .get(&request)
+            .is_some_and(|queued| *queued >= priority)
+        {
+            return false;
+        }
+        self.index.insert(request.clone(), priority);
+        self.heap.push(Queued {
+            priority,
+            seq: self.seq,
+            request,
+        });
+        self.seq += 1;
+        true
+    }
+
+    fn is_live(&self, queued: &Queued) -> bool {
+        self.index.get(&queued.request) == Some(&queued.priority)
+    }
+
+    /// Drop the least urgent prefetches past `limit`, requests the user
+    /// waits on are kept.  The number dropped is returned.
+    fn shed(&mut self, limit: usize) -> usize {
+        let mut kept = std::mem::take(&mut self.heap).into_sorted_vec();
+        kept.retain(|queued| self.is_live(queued));
+        let prefetches = kept
+            .iter()
+            .filter(|queued| queued.priority != FetchPriority::High)
+            .count();
+        let dropped = prefetches.saturating_sub(limit);
+        // least urgent first, so only prefetches are drained
+        for queued in kept.drain(..dropped) {
+            self.index.remove(&queued.request);
+        }
+        self.heap = kept.into();
+        dropped
+    }
+
+    fn next(&mut self, admits: impl Fn(FetchPriority) -> bool) -> Next {
+        while let Some(queued) = self.heap.pop() {
+            if !self.is_live(&queued) {
+                continue;
+            }
+            if !admits(queued.priority) {
+                self.heap.push(queued);
+                return Next::Paused;
+            }
+            self.index.remove(&queued.request);
+            return Next::Fetch(queued.request);
+        }
+        Next::Empty
+    }
+}
+
+/// Single worker that fills the cache, always taking the most urgent queued
+/// request next.  Prefetches wait while the throttle is tripped.
+#[derive(Clone, Debug)]
+pub struct BackgroundFetcher {
+    queue: Arc<Mutex<Queue>>,
+    wake: Arc<Notify>,
+}
+
+impl BackgroundFetcher {
+    #[must_use]
+    pub fn spawn(cache: Arc<DataCache>) -> Self {
+        let fetcher = Self {
+            queue: Arc::default(),
+            wake: Arc::default(),
+        };
+        tokio::spawn(run(cache, fetcher.clone()));
+        fetcher
+    }
+
+    /// Queue `request`, or raise it to `priority` if it waits at a lower
+    /// one.
+    pub fn request(&self, priority: FetchPriority, request: DataRequest) {
+        if let Ok(mut queue) = self.queue.lock() {
+            if queue.push(priority, request) {
+                self.wake.notify_one();
+            }
+        }
+    }
+
+    /// The user now waits on `request`: if it is queued as a prefetch it
+    /// is fetched next.  `false` when it is not queued.
+    pub fn promote(&self, request: &DataRequest) -> bool {
+        let Ok(mut queue) = self.queue.lock() else {
+            return false;
+        };
+        if !queue.index.contains_key(request) {
+            return false;
+        }
+        if queue.push(FetchPriority::High, request.clone()) {
+            self.wake.notify_one();
+        }
+        true
+    }
+}
+
+async fn run(cache: Arc<DataCache>, fetcher: BackgroundFetcher) {
+    loop {
+        let next = {
+            let Ok(mut queue) = fetcher.queue.lock() else {
+                return;
+            };
+            if let Some(limit) = throttle::batch(QUEUE_BATCH) {
+                let dropped = queue.shed(limit);
+                if dropped > 0 {
+                    debug!("throttled, dropped {dropped} queued prefetches");
+                }
+            }
+            queue.next(throttle::admits)
+        };
+        let request = match next {
+            Next::Fetch(request) => request,
+            // wait out the pause, waking early for anything more urgent
+            Next::Paused => {
+                let _ = tokio::time::timeout(PAUSE_POLL, fetcher.wake.notified()).await;
+                continue;
+            }
+            Next::Empty => {
+                fetcher.wake.notified().await;
+                continue;
+            }
+        };
+        if cache.status(&request) == FetchStatus::Fresh {
+            continue;
+        }
+        let background = InFlight::background();
+        let fetched = fetch(&request).await;
+        drop(background);
+        match fetched {
+            Ok(data) => cache.put(request, data),
+            Err(e) => {
+                debug!("background fetch of {request:?} failed: {e}");
+                cache.mark_failed(&request, e.to_string());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_orders_by_priority_then_age() {
+        let mut queue = BinaryHeap::new();
+        queue.push(Queued {
+            priority: FetchPriority::Medium,
+            seq: 0,
+            request: DataRequest::Events,
+        });
+        queue.push(Queued {
+            priority: FetchPriority::High,
+            seq: 1,
+            request: DataRequest::ReplicaSets,
+        });
+        queue.push(Queued {
+            priority: FetchPriority::Medium,
+            seq: 2,
+            request: DataRequest::Pods {
+                selector: std::collections::BTreeMap::new(),
+            },
+        });
+        assert_eq!(queue.pop().unwrap().request, DataRequest::ReplicaSets);
+        assert_eq!(queue.pop().unwrap().request, DataRequest::Events);
+        assert_eq!(queue.pop().unwrap().seq, 2);
+    }
+
+    #[tokio::test]
+    async fn test_fetches_at_once_are_shared() {
+        static SHARED: Flights<usize> = Flights::new();
+        static STARTED: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let fetch = || {
+            SHARED.join(&DataRequest::Events, || {
+                async {
+                    let started = STARTED.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    Ok(started)
+                }
+                .boxed()
+            })
+        };
+        let (first, second) = tokio::join!(fetch(), fetch());
+        assert_eq!((first.unwrap(), second.unwrap()), (0, 0));
+        assert_eq!(fetch().await.unwrap(), 1);
+
+        let failing = || {
+            SHARED.join(&DataRequest::Nodes, || {
+                async { Err(Error::Forbidden("nodes".to_string())) }.boxed()
+            })
+        };
+        let (first, second) = tokio::join!(failing(), failing());
+        assert!(matches!(first, Err(Error::Forbidden(_))));
+        assert!(matches!(second, Err(Error::Forbidden(_))));
+    }
+
+    #[test]
+    fn test_copied_errors_keep_their_class() {
+        use crate::k8s::retry::{classify, Retryable};
+        let tls = Arc::new(Error::Kube(kube::Error::TlsRequired));
+        let copy = copy_error(tls.clone());
+        assert_eq!(copy.code(), "NP-API");
+        assert_eq!(copy.to_string(), tls.to_string());
+        assert_eq!(classify(&copy), Retryable::Fatal);
+
+        let network = copy_error(Arc::new(Error::Network("refused".to_string())));
+        assert!(matches!(network, Error::Network(ref m) if m == "refused"));
+        let auth = copy_error(Arc::new(Error::Auth("expired".to_string())));
+        assert_eq!(classify(&auth), Retryable::Auth);
+    }
+
+    fn pods(app: &str) -> DataRequest {
+        DataRequest::Pods {
+            selector: std::collections::BTreeMap::from([("app".to_string(), app.to_string())]),
+        }
+    }
+
+    #[test]
+    fn test_shed_keeps_what_the_user_waits_on() {
+        let mut queue = Queue::default();
+        queue.push(FetchPriority::Low, DataRequest::Events);
+        queue.push(FetchPriority::High, DataRequest::ReplicaSets);
+        queue.push(FetchPriority::Medium, DataRequest::Nodes);
+        queue.push(FetchPriority::Low, pods("web"));
+        assert_eq!(queue.shed(1), 2);
+        let admit_all = |_| true;
+        assert_eq!(queue.next(admit_all), Next::Fetch(DataRequest::ReplicaSets));
+        assert_eq!(queue.next(admit_all), Next::Fetch(DataRequest::Nodes));
+        assert_eq!(queue.next(admit_all), Next::Empty);
+        assert_eq!(queue.shed(1), 0);
+    }
+
+    #[test]
+    fn test_requests_queued_again_are_promoted_not_duplicated() {
+        let mut queue = Queue::default();
+        assert!(queue.push(FetchPriority::Low, pods("web")));
+        assert!(queue.push(FetchPriority::Medium, DataRequest::Events));
+        assert!(!queue.push(FetchPriority::Low, pods("web")));
+        assert!(queue.push(FetchPriority::High, pods("web")));
+        assert!(!queue.push(FetchPriority::Medium, pods("web")));
+
+        let prefetches_paused = |priority| priority == FetchPriority::High;
+        assert_eq!(queue.next(prefetches_paused), Next::Fetch(pods("web")));
+        assert_eq!(queue.next(prefetches_paused), Next::Paused);
+        let admit_all = |_| true;
+        assert_eq!(queue.next(admit_all), Next::Fetch(DataRequest::Events));
+        assert_eq!(queue.next(admit_all), Next::Empty);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::config::config_file;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::debug;
+
+const HISTORY_FILE: &str = "navigation.json";
+
+/// every new visit fades older ones so the model follows changing workflows
+const DECAY: f64 = 0.9;
+/// weight a resource needs to count as part of the usual workflow
+const ACTIVE_WEIGHT: f64 = 0.5;
+/// never grow the prefetch set past this multiple of the default
+const MAX_PREFETCH_FACTOR: usize = 4;
+
+pub const EVENTS_KEY: &str = "events";
+
+/// Learned navigation for one cluster/namespace.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScopeHistory {
+    /// decayed open counts per resource, replicaset names plus `EVENTS_KEY`
+    pub weights: BTreeMap<String, f64>,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl ScopeHistory {
+    pub fn record(&mut self, resource: &str) {
+        for weight in self.weights.values_mut() {
+            *weight *= DECAY;
+        }
+        self.weights.retain(|_, weight| *weight > 0.01);
+        *self.weights.entry(resource.to_string()).or_insert(0.0) += 1.0;
+    }
+
+    pub fn record_lookup(&mut self, hit: bool) {
+        if hit {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+    }
+
+    #[must_use]
+    pub fn weight(&self, resource: &str) -> f64 {
+        self.weights.get(resource).copied().unwrap_or(0.0)
+    }
+
+    /// Prefetch enough replicasets to cover the ones the user keeps coming
+    /// back to, but never fewer than the default.
+    #[must_use]
+    pub fn prefetch_limit(&self, default: usize) -> usize {
+        let active = self
+            .weights
+            .iter()
+            .filter(|(name, weight)| name.as_str() != EVENTS_KEY && **weight >= ACTIVE_WEIGHT)
+            .count();
+        active.clamp(default, default * MAX_PREFETCH_FACTOR)
+    }
+
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn hit_rate(&self) -> Option<f64> {
+        let total = self.hits + self.misses;
+        (total > 0).then(|| self.hits as f64 / total as f64)
+    }
+}
+
+/// What the user opened after landing on a view, kept per cluster/namespace
+/// between sessions so the cache can be warmed along the same path next time.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NavigationHistory {
+    pub scopes: BTreeMap<String, ScopeHistory>,
+}
+
+impl NavigationHistory {
+    #[must_use]
+    pub fn load() -> Self {
+        config_file(HISTORY_FILE)
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        let Some(path) = config_file(HISTORY_FILE) else {
+            return;
+        };
+        match serde_json::to_string_pretty(self) {
+            Ok(json) => {
+                if let Err(e) = std::fs::write(path, json) {
+                    debug!("can not save navigation history: {e}");
+                }
+            }
+            Err(e) => debug!("can not serialize navigation history: {e}"),
+        }
+    }
+
+    pub fn scope_mut(&mut self, scope: &str) -> &mut ScopeHistory {
+        self.scopes.entry(scope.to_string()).or_default()
+    }
+
+    #[must_use]
+    pub fn scope(&self, scope: &str) -> ScopeHistory {
+        self.scopes.get(scope).cloned().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_visits_outweigh_old_ones() {
+        let mut history = ScopeHistory::default();
+        for _ in 0..3 {
+            history.record("old");
+        }
+        for _ in 0..10 {
+            history.record("new");
+        }
+        assert!(history.weight("new") > history.weight("old"));
+        assert!(history.weight("old") < 1.0);
+    }
+
+    #[test]
+    fn test_prefetch_limit_grows_with_active_resources() {
+        let mut history = ScopeHistory::default();
+        assert_eq!(history.prefetch_limit(2), 2);
+        for name in ["a", "b", "c", "d"] {
+            history.record(name);
+        }
+        history.record(EVENTS_KEY);
+        assert_eq!(history.prefetch_limit(2), 4);
+        assert_eq!(history.prefetch_limit(1), 4);
+    }
+}
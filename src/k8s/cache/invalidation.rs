@@ -0,0 +1,76 @@
+//!What a watched change makes stale in the cache.  A change to an Ingress,
+//!Service or EndpointSlice drops the cached ingress rows, which are then
+//!fetched again in the background.  Changes arriving together are handled
+//!once, a second after the first.
+//!
+use crate::k8s::cache::cache_manager::{get_cache, prefetch};
+use crate::k8s::cache::DataRequest;
+use std::collections::BTreeSet;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tracing::debug;
+
+/// changes within this of the first are handled with it
+const SETTLE: Duration = Duration::from_secs(1);
+
+/// whether a change to an object of `kind` makes `request` stale
+#[must_use]
+pub fn invalidates(kind: &str, request: &DataRequest) -> bool {
+    match request {
+        DataRequest::Ingresses { .. } => {
+            matches!(kind, "Ingress" | "Service" | "EndpointSlice")
+        }
+        _ => false,
+    }
+}
+
+/// Drop what changes to `kinds` made stale and fetch it again, the
+/// requests dropped are returned.
+pub fn apply(kinds: &BTreeSet<&str>) -> Vec<DataRequest> {
+    let dropped =
+        get_cache().invalidate_where(|request| kinds.iter().any(|kind| invalidates(kind, request)));
+    for request in &dropped {
+        prefetch(request.clone());
+    }
+    dropped
+}
+
+/// An object of `kind` was added, changed or deleted.
+pub fn changed(kind: &'static str) {
+    static CHANGES: OnceLock<UnboundedSender<&'static str>> = OnceLock::new();
+    let changes = CHANGES.get_or_init(|| {
+        let (tx, mut rx) = unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(kind) = rx.recv().await {
+                tokio::time::sleep(SETTLE).await;
+                let mut kinds = BTreeSet::from([kind]);
+                while let Ok(kind) = rx.try_recv() {
+                    kinds.insert(kind);
+                }
+                for request in apply(&kinds) {
+                    debug!("{kinds:?} changed, refetching {request}");
+                }
+            }
+        });
+        tx
+    });
+    let _ = changes.send(kind);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_invalidates() {
+        let ingresses = DataRequest::Ingresses {
+            selector: BTreeMap::from([("app".to_string(), "web".to_string())]),
+        };
+        assert!(invalidates("Ingress", &ingresses));
+        assert!(invalidates("EndpointSlice", &ingresses));
+        assert!(!invalidates("Pod", &ingresses));
+        assert!(!invalidates("Service", &DataRequest::Events));
+    }
+}
@@ -0,0 +1,158 @@
+//!Rough accounting of what navipod keeps in memory: every cache entry,
+//!the log view's buffer and the histories kept for sparklines and
+//!percentiles.  Sizes are estimates from the data's debug rendering, close
+//!enough to tell which entries matter and to pick a `--cache-size-mb`.
+//!
+use crate::k8s::cache::cache_manager::get_cache;
+use crate::k8s::{metrics_history, restarts};
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Write};
+use std::sync::Mutex;
+
+/// the cache evicts the oldest entries beyond this
+pub const DEFAULT_CACHE_SIZE_MB: usize = 64;
+
+/// buffers outside the cache, by holder
+static TRACKED: Mutex<BTreeMap<&'static str, usize>> = Mutex::new(BTreeMap::new());
+
+/// Estimated bytes behind `value`, its text plus its own size.
+#[must_use]
+pub fn estimate<T: Debug + ?Sized>(value: &T) -> usize {
+    struct Counter(usize);
+    impl Write for Counter {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.0 += s.len();
+            Ok(())
+        }
+    }
+    let mut counter = Counter(std::mem::size_of_val(value));
+    _ = write!(counter, "{value:?}");
+    counter.0
+}
+
+/// Record the size of a buffer kept outside the cache, replacing what
+/// `holder` recorded before.
+pub fn track(holder: &'static str, bytes: usize) {
+    if let Ok(mut tracked) = TRACKED.lock() {
+        tracked.insert(holder, bytes);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Usage {
+    pub area: &'static str,
+    pub name: String,
+    pub bytes: usize,
+}
+
+/// 1536 as 1.5 KB
+#[must_use]
+pub fn human(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    #[allow(clippy::cast_precision_loss)]
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+/// Everything accounted for, largest first.
+#[must_use]
+pub fn report() -> Vec<Usage> {
+    let mut usage: Vec<Usage> = get_cache()
+        .usage()
+        .into_iter()
+        .map(|(request, bytes)| Usage {
+            area: "cache",
+            name: request.to_string(),
+            bytes,
+        })
+        .collect();
+    if let Ok(tracked) = TRACKED.lock() {
+        usage.extend(tracked.iter().map(|(holder, bytes)| Usage {
+            area: "buffer",
+            name: (*holder).to_string(),
+            bytes: *bytes,
+        }));
+    }
+    usage.push(Usage {
+        area: "history",
+        name: "metrics history".to_string(),
+        bytes: metrics_history::with_loaded(estimate).unwrap_or(0),
+    });
+    usage.push(Usage {
+        area: "history",
+        name: "restart history".to_string(),
+        bytes: restarts::estimated_bytes(),
+    });
+    usage.sort_by_key(|line| std::cmp::Reverse(line.bytes));
+    usage
+}
+
+/// `report` as aligned text with the total and the cache limit
+#[must_use]
+pub fn render(usage: &[Usage], limit_bytes: usize) -> String {
+    let mut out = String::new();
+    for line in usage {
+        _ = writeln!(
+            out,
+            "{:8} {:>10}  {}",
+            line.area,
+            human(line.bytes),
+            line.name
+        );
+    }
+    let cached: usize = usage
+        .iter()
+        .filter(|line| line.area == "cache")
+        .map(|line| line.bytes)
+        .sum();
+    let total: usize = usage.iter().map(|line| line.bytes).sum();
+    _ = writeln!(out, "total    {:>10}", human(total));
+    _ = write!(
+        out,
+        "cache    {:>10}  of {} (--cache-size-mb)",
+        human(cached),
+        human(limit_bytes)
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_and_render() {
+        let small = estimate(&vec!["a".to_string()]);
+        let large = estimate(&vec!["a".repeat(1000)]);
+        assert!(large >= small + 999);
+        assert_eq!(human(12), "12 B");
+        assert_eq!(human(1536), "1.5 KB");
+        assert_eq!(human(3 * 1024 * 1024), "3.0 MB");
+
+        let usage = vec![
+            Usage {
+                area: "cache",
+                name: "replicasets".to_string(),
+                bytes: 2048,
+            },
+            Usage {
+                area: "buffer",
+                name: "log view".to_string(),
+                bytes: 1024,
+            },
+        ];
+        let text = render(&usage, DEFAULT_CACHE_SIZE_MB * 1024 * 1024);
+        assert!(text.contains("2.0 KB  replicasets"));
+        assert!(text.contains("total        3.0 KB"));
+        assert!(text.ends_with("2.0 KB  of 64.0 MB (--cache-size-mb)"));
+    }
+}
@@ -0,0 +1,13 @@
+//!In-memory cache of the data behind the TUI views, filled in the
+//!background so drilling into a view does not wait on the api server.
+//!
+pub mod cache_manager;
+pub mod data;
+pub mod fetcher;
+pub mod history;
+pub mod invalidation;
+pub mod memory;
+pub mod throttle;
+
+pub use data::{CachedData, DataCache, DataRequest, FetchPriority, FetchStatus, Freshness};
+pub use fetcher::BackgroundFetcher;
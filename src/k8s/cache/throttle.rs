@@ -0,0 +1,198 @@
+//!Circuit breaker of the background fetcher.  Throttling by the api server
+//!or watches that keep failing to reconnect trip it: prefetches wait and
+//!fewer of them are kept queued, while fetches the user waits on go ahead.
+//!Prefetches resume after `COOLDOWN` without trouble, and the queue grows
+//!back one step per quiet `COOLDOWN` after that.
+//!
+use crate::k8s::cache::FetchPriority;
+use crate::k8s::watch_manager;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+const COOLDOWN: Duration = Duration::from_secs(30);
+/// every trip halves the prefetches kept, down to 1/8th
+const MAX_SHIFT: u32 = 3;
+/// a watch waiting this long to reconnect has failed a few times in a row
+const DEGRADED_BACKOFF: Duration = Duration::from_secs(8);
+
+static THROTTLE: Mutex<Throttle> = Mutex::new(Throttle {
+    tripped: None,
+    shift: 0,
+    trips: 0,
+});
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThrottleState {
+    Normal,
+    /// prefetches wait for this much longer
+    Paused(Duration),
+    /// prefetches run again, fewer of them than usual
+    Recovering,
+}
+
+impl fmt::Display for ThrottleState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Normal => write!(f, "normal"),
+            Self::Paused(left) => write!(f, "prefetch paused for {}s", left.as_secs()),
+            Self::Recovering => write!(f, "recovering"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Throttle {
+    tripped: Option<Instant>,
+    /// the prefetches kept are halved this many times at the trip
+    shift: u32,
+    trips: u64,
+}
+
+impl Throttle {
+    /// Trouble at `now`.  A trip while paused only extends the pause.
+    pub fn trip(&mut self, now: Instant) {
+        if !matches!(self.state(now), ThrottleState::Paused(_)) {
+            self.shift = (self.shift(now) + 1).min(MAX_SHIFT);
+            self.trips += 1;
+        }
+        self.tripped = Some(now);
+    }
+
+    /// halvings in effect at `now`, one is regained per quiet `COOLDOWN`
+    /// after the pause
+    fn shift(&self, now: Instant) -> u32 {
+        self.tripped.map_or(0, |tripped| {
+            let quiet = now.saturating_duration_since(tripped).as_secs() / COOLDOWN.as_secs();
+            let regained = u32::try_from(quiet.saturating_sub(1)).unwrap_or(u32::MAX);
+            self.shift.saturating_sub(regained)
+        })
+    }
+
+    #[must_use]
+    pub fn state(&self, now: Instant) -> ThrottleState {
+        match self.tripped {
+            Some(tripped) if now.saturating_duration_since(tripped) < COOLDOWN => {
+                ThrottleState::Paused(COOLDOWN - now.saturating_duration_since(tripped))
+            }
+            _ if self.shift(now) > 0 => ThrottleState::Recovering,
+            _ => ThrottleState::Normal,
+        }
+    }
+
+    /// whether a fetch of `priority` may run at `now`
+    #[must_use]
+    pub fn admits(&self, priority: FetchPriority, now: Instant) -> bool {
+        priority == FetchPriority::High || !matches!(self.state(now), ThrottleState::Paused(_))
+    }
+
+    /// `full` cut down to what may be prefetched at `now`, at least 1
+    #[must_use]
+    pub fn batch(&self, full: usize, now: Instant) -> usize {
+        (full >> self.shift(now)).max(1)
+    }
+}
+
+/// the throttle for the diagnostics view
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ThrottleStats {
+    pub state: ThrottleState,
+    /// 1 of this many of the usual prefetches are kept
+    pub divisor: usize,
+    pub trips: u64,
+}
+
+fn with_throttle<T>(f: impl FnOnce(&mut Throttle) -> T) -> T {
+    let mut throttle = THROTTLE
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    f(&mut throttle)
+}
+
+/// The api server or the watches are struggling, `reason` is logged.
+pub fn pressure(reason: &str) {
+    debug!("throttling prefetches: {reason}");
+    with_throttle(|throttle| throttle.trip(Instant::now()));
+}
+
+/// trip while any namespace's watches wait on a long reconnect
+fn check_watches() {
+    let degraded = watch_manager::manager()
+        .stats()
+        .into_iter()
+        .find(|(_, stats)| {
+            stats.health.streaming < stats.health.running && stats.backoff >= DEGRADED_BACKOFF
+        });
+    if let Some((namespace, stats)) = degraded {
+        pressure(&format!(
+            "watches of {namespace} reconnecting after {}s",
+            stats.backoff.as_secs()
+        ));
+    }
+}
+
+/// whether the background fetcher may run a fetch of `priority` now
+#[must_use]
+pub fn admits(priority: FetchPriority) -> bool {
+    if priority != FetchPriority::High {
+        check_watches();
+    }
+    with_throttle(|throttle| throttle.admits(priority, Instant::now()))
+}
+
+/// how many of `full` prefetches to keep now, `None` unless throttled
+#[must_use]
+pub fn batch(full: usize) -> Option<usize> {
+    let now = Instant::now();
+    with_throttle(|throttle| {
+        (throttle.state(now) != ThrottleState::Normal).then(|| throttle.batch(full, now))
+    })
+}
+
+#[must_use]
+pub fn stats() -> ThrottleStats {
+    check_watches();
+    let now = Instant::now();
+    with_throttle(|throttle| ThrottleStats {
+        state: throttle.state(now),
+        divisor: 1 << throttle.shift(now),
+        trips: throttle.trips,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trips_pause_then_recover() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut throttle = Throttle::default();
+        assert_eq!(throttle.state(start), ThrottleState::Normal);
+        assert_eq!(throttle.batch(8, start), 8);
+
+        throttle.trip(at(0));
+        throttle.trip(at(10));
+        assert_eq!(throttle.trips, 1);
+        assert_eq!(
+            throttle.state(at(20)),
+            ThrottleState::Paused(Duration::from_secs(20))
+        );
+        assert!(!throttle.admits(FetchPriority::Low, at(20)));
+        assert!(throttle.admits(FetchPriority::High, at(20)));
+        assert_eq!(throttle.batch(8, at(20)), 4);
+
+        throttle.trip(at(45));
+        assert_eq!(throttle.trips, 2);
+        assert_eq!(throttle.batch(8, at(45)), 2);
+
+        assert_eq!(throttle.state(at(80)), ThrottleState::Recovering);
+        assert!(throttle.admits(FetchPriority::Low, at(80)));
+        assert_eq!(throttle.batch(8, at(80)), 2);
+        assert_eq!(throttle.batch(8, at(105)), 4);
+        assert_eq!(throttle.state(at(135)), ThrottleState::Normal);
+        assert_eq!(throttle.batch(8, at(135)), 8);
+    }
+}
@@ -0,0 +1,156 @@
+//!cert-manager Certificates, to answer why a host's certificate is not
+//!renewing without leaving navipod: whether the Certificate behind a TLS
+//!secret is Ready, when it renews and why the last issuance failed.
+//!
+use crate::error::Result;
+use crate::k8s::dynamic::{field, Condition, Crd};
+use chrono::{DateTime, Utc};
+use kube::api::DynamicObject;
+use kube::Client;
+use serde::Deserialize;
+
+const CERTIFICATE: Crd = Crd {
+    group: "cert-manager.io",
+    version: "v1",
+    kind: "Certificate",
+};
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct IssuerRef {
+    pub name: String,
+    pub kind: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CertificateSpec {
+    pub secret_name: String,
+    pub dns_names: Vec<String>,
+    pub issuer_ref: IssuerRef,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CertificateStatus {
+    pub conditions: Vec<Condition>,
+    pub not_after: Option<DateTime<Utc>>,
+    pub renewal_time: Option<DateTime<Utc>>,
+    pub failed_issuance_attempts: Option<u32>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Certificate {
+    pub name: String,
+    pub spec: CertificateSpec,
+    pub status: CertificateStatus,
+}
+
+fn to_certificate(object: &DynamicObject) -> Certificate {
+    Certificate {
+        name: object.metadata.name.clone().unwrap_or_default(),
+        spec: field(object, "spec"),
+        status: field(object, "status"),
+    }
+}
+
+/// # Errors
+///
+/// Will return `Err` if data can not be retrieved from k8s cluster api
+pub async fn list_certificates(client: &Client, namespace: &str) -> Result<Vec<Certificate>> {
+    Ok(CERTIFICATE
+        .list(client, Some(namespace))
+        .await?
+        .iter()
+        .map(to_certificate)
+        .collect())
+}
+
+/// the Certificate writing `secret`
+#[must_use]
+pub fn for_secret<'a>(certificates: &'a [Certificate], secret: &str) -> Option<&'a Certificate> {
+    certificates
+        .iter()
+        .find(|certificate| certificate.spec.secret_name == secret)
+}
+
+fn days(from: DateTime<Utc>, to: DateTime<Utc>) -> i64 {
+    to.signed_duration_since(from).num_days()
+}
+
+impl Certificate {
+    fn condition(&self, type_: &str) -> Option<&Condition> {
+        self.status.conditions.iter().find(|c| c.type_ == type_)
+    }
+
+    /// `ready, renews in 20d, expires in 50d`, or why it is not
+    #[must_use]
+    pub fn summary(&self, now: DateTime<Utc>) -> String {
+        let mut parts = vec![];
+        match self.condition("Ready") {
+            Some(ready) if ready.status == "True" => parts.push("ready".to_string()),
+            Some(ready) => parts.push(format!("not ready, {}: {}", ready.reason, ready.message)),
+            None => parts.push("no status yet".to_string()),
+        }
+        if self
+            .condition("Issuing")
+            .is_some_and(|issuing| issuing.status == "True")
+        {
+            parts.push("issuing".to_string());
+        }
+        if let Some(attempts) = self.status.failed_issuance_attempts.filter(|n| *n > 0) {
+            parts.push(format!("{attempts} failed attempts"));
+        }
+        match self.status.renewal_time {
+            Some(at) if at < now => parts.push(format!("renewal {}d overdue", days(at, now))),
+            Some(at) => parts.push(format!("renews in {}d", days(now, at))),
+            None => {}
+        }
+        match self.status.not_after {
+            Some(at) if at < now => parts.push("expired".to_string()),
+            Some(at) => parts.push(format!("expires in {}d", days(now, at))),
+            None => {}
+        }
+        parts.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::serde_json::{from_value, json};
+
+    #[test]
+    fn test_certificate_summary() {
+        let object: DynamicObject = from_value(json!({
+            "apiVersion": "cert-manager.io/v1",
+            "kind": "Certificate",
+            "metadata": {"name": "shop", "namespace": "web"},
+            "spec": {
+                "secretName": "shop-tls",
+                "dnsNames": ["shop.acme.io"],
+                "issuerRef": {"name": "letsencrypt", "kind": "ClusterIssuer"}
+            },
+            "status": {
+                "conditions": [
+                    {"type": "Ready", "status": "False", "reason": "Failed", "message": "acme challenge failed"},
+                    {"type": "Issuing", "status": "True", "reason": "Renewing"}
+                ],
+                "failedIssuanceAttempts": 3,
+                "notAfter": "2024-03-20T00:00:00Z",
+                "renewalTime": "2024-02-19T00:00:00Z"
+            }
+        }))
+        .unwrap();
+        let certificates = [to_certificate(&object)];
+        let certificate = for_secret(&certificates, "shop-tls").unwrap();
+        assert_eq!(certificate.spec.issuer_ref.name, "letsencrypt");
+        assert!(for_secret(&certificates, "other").is_none());
+
+        let now = "2024-03-01T00:00:00Z".parse().unwrap();
+        assert_eq!(
+            certificate.summary(now),
+            "not ready, Failed: acme challenge failed, issuing, 3 failed attempts, renewal 11d overdue, expires in 19d"
+        );
+    }
+}
@@ -0,0 +1,444 @@
+//!Shared, pre-built k8s clients.  Building a client means reading the
+//!kubeconfig, running any exec credential plugin and a TLS handshake, so a
+//!small pool per context is kept warm and handed out round-robin.  Clones
+//!of a client share its connection pool, so repeated calls reuse the same
+//!HTTP/2 connection.
+//!
+//!Short lived credentials (exec plugins, OIDC id-tokens) are refreshed ahead
+//!of expiry by a background task that rebuilds the pool, and calls that
+//!still hit a 401 are retried once on a fresh client by `with_auth_retry`,
+//!which `retry::with_retry` runs each of its attempts through.
+//!
+//!Pools are kept by context.  The current context is read from the
+//!kubeconfig once and read again only after its files changed, so a
+//!`kubectl config use-context` elsewhere is picked up without parsing the
+//!kubeconfig on every call.
+//!
+use crate::error::Result;
+use crate::k8s::client::new;
+use crate::k8s::retry::{classify, Retryable};
+use crate::k8s::timeout::with_timeout;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use kube::config::{AuthInfo, Kubeconfig};
+use kube::Client;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::debug;
+
+/// two clients let a hedged request go out on a second connection
+pub const POOL_SIZE: usize = 2;
+
+/// exec plugins do not tell us when their token expires, EKS tokens last 15m
+const EXEC_REFRESH_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// refresh this long before a known expiry
+const REFRESH_MARGIN_SECS: i64 = 120;
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+/// how long the current context is trusted before the kubeconfig files are
+/// looked at for changes
+const CONTEXT_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Default)]
+struct Pool {
+    clients: Vec<Client>,
+    next: usize,
+}
+
+static POOLS: Mutex<Option<HashMap<String, Pool>>> = Mutex::new(None);
+
+/// the current context as last read, with what its files looked like then
+#[derive(Debug)]
+struct CachedContext {
+    context: String,
+    /// modification time of each kubeconfig file, `None` when missing
+    stamp: Vec<Option<SystemTime>>,
+    checked: Instant,
+}
+
+impl CachedContext {
+    /// The context, read again with `read` only when `stamp` says the files
+    /// changed since they were last looked at.
+    fn current(
+        cached: &mut Option<Self>,
+        now: Instant,
+        stamp: impl FnOnce() -> Vec<Option<SystemTime>>,
+        read: impl FnOnce() -> String,
+    ) -> String {
+        if let Some(cached) = cached.as_ref() {
+            if now.duration_since(cached.checked) < CONTEXT_CHECK_INTERVAL {
+                return cached.context.clone();
+            }
+        }
+        let stamp = stamp();
+        match cached {
+            Some(cached) if cached.stamp == stamp => {
+                cached.checked = now;
+                cached.context.clone()
+            }
+            _ => {
+                let context = read();
+                *cached = Some(Self {
+                    context: context.clone(),
+                    stamp,
+                    checked: now,
+                });
+                context
+            }
+        }
+    }
+}
+
+static CONTEXT: Mutex<Option<CachedContext>> = Mutex::new(None);
+
+static BUILT: AtomicU64 = AtomicU64::new(0);
+static REBUILDS: AtomicU64 = AtomicU64::new(0);
+static WARMUPS: AtomicU64 = AtomicU64::new(0);
+static REUSED: AtomicU64 = AtomicU64::new(0);
+static TOKEN_REFRESHES: AtomicU64 = AtomicU64::new(0);
+static AUTH_RETRIES: AtomicU64 = AtomicU64::new(0);
+static REFRESH_STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ClientStats {
+    /// clients constructed since startup
+    pub built: u64,
+    /// pools thrown away, ie: after an auth refresh
+    pub rebuilds: u64,
+    pub warmups: u64,
+    /// requests served by an already built client
+    pub reused: u64,
+    /// scheduled rebuilds ahead of credential expiry
+    pub token_refreshes: u64,
+    /// calls retried after a 401
+    pub auth_retries: u64,
+}
+
+#[must_use]
+pub fn stats() -> ClientStats {
+    ClientStats {
+        built: BUILT.load(Ordering::Relaxed),
+        rebuilds: REBUILDS.load(Ordering::Relaxed),
+        warmups: WARMUPS.load(Ordering::Relaxed),
+        reused: REUSED.load(Ordering::Relaxed),
+        token_refreshes: TOKEN_REFRESHES.load(Ordering::Relaxed),
+        auth_retries: AUTH_RETRIES.load(Ordering::Relaxed),
+    }
+}
+
+/// the files `Kubeconfig::read` merges
+fn kubeconfig_paths() -> Vec<PathBuf> {
+    match std::env::var_os("KUBECONFIG").filter(|paths| !paths.is_empty()) {
+        Some(paths) => std::env::split_paths(&paths).collect(),
+        None => std::env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join(".kube").join("config"))
+            .into_iter()
+            .collect(),
+    }
+}
+
+fn kubeconfig_stamp() -> Vec<Option<SystemTime>> {
+    kubeconfig_paths()
+        .iter()
+        .map(|path| {
+            std::fs::metadata(path)
+                .and_then(|meta| meta.modified())
+                .ok()
+        })
+        .collect()
+}
+
+fn read_context() -> String {
+    Kubeconfig::read()
+        .ok()
+        .and_then(|config| config.current_context)
+        .unwrap_or_else(|| "in-cluster".to_string())
+}
+
+/// the kubeconfig context clients are built for
+#[must_use]
+pub fn current_context() -> String {
+    match CONTEXT.lock() {
+        Ok(mut cached) => {
+            CachedContext::current(&mut cached, Instant::now(), kubeconfig_stamp, read_context)
+        }
+        Err(_) => read_context(),
+    }
+}
+
+/// every context of the kubeconfig, in the order it lists them
+#[must_use]
+pub fn contexts() -> Vec<String> {
+    Kubeconfig::read()
+        .map(|config| {
+            config
+                .contexts
+                .into_iter()
+                .map(|named| named.name)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn pooled(context: &str) -> Option<Client> {
+    let mut pools = POOLS.lock().ok()?;
+    let pool = pools.as_mut()?.get_mut(context)?;
+    if pool.clients.is_empty() {
+        return None;
+    }
+    let client = pool.clients[pool.next % pool.clients.len()].clone();
+    pool.next = pool.next.wrapping_add(1);
+    drop(pools);
+    REUSED.fetch_add(1, Ordering::Relaxed);
+    Some(client)
+}
+
+fn add_to_pool(context: &str, client: Client) {
+    if let Ok(mut pools) = POOLS.lock() {
+        let pool = pools
+            .get_or_insert_with(HashMap::new)
+            .entry(context.to_string())
+            .or_default();
+        if pool.clients.len() < POOL_SIZE {
+            pool.clients.push(client);
+        }
+    }
+}
+
+async fn build() -> Result<Client> {
+    let client = new(None).await?;
+    BUILT.fetch_add(1, Ordering::Relaxed);
+    Ok(client)
+}
+
+/// A client for the current context, built on first use.
+///
+/// # Errors
+///
+/// Will return `Err` if a client can not be built from the kubeconfig
+pub async fn get_client() -> Result<Client> {
+    let context = current_context();
+    if let Some(client) = pooled(&context) {
+        return Ok(client);
+    }
+    let client = build().await?;
+    add_to_pool(&context, client.clone());
+    Ok(client)
+}
+
+/// Fill the pool for the current context and push a cheap request through
+/// each client so TLS and the HTTP/2 connection are set up before the UI
+/// needs them.
+pub async fn warm_up() {
+    let context = current_context();
+    WARMUPS.fetch_add(1, Ordering::Relaxed);
+    for _ in 0..POOL_SIZE {
+        let client = match build().await {
+            Ok(client) => client,
+            Err(e) => {
+                debug!("client warm up failed: {e}");
+                return;
+            }
+        };
+        if let Err(e) = with_timeout("warm up client", client.apiserver_version()).await {
+            debug!("client warm up request failed: {e}");
+        }
+        add_to_pool(&context, client);
+    }
+}
+
+fn drop_pool() {
+    let context = current_context();
+    if let Ok(mut pools) = POOLS.lock() {
+        if let Some(pools) = pools.as_mut() {
+            pools.remove(&context);
+        }
+    }
+    REBUILDS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Drop the clients of the current context, ie: after credentials changed,
+/// and rebuild them in the background so the next request finds them warm.
+pub fn invalidate() {
+    drop_pool();
+    tokio::spawn(warm_up());
+}
+
+/// Run a fetch and, if the api server rejects the credentials, retry it once
+/// on a freshly built client, which runs the credential plugin again.
+///
+/// # Errors
+///
+/// Will return `Err` if the fetch fails for any other reason or fails again
+pub async fn with_auth_retry<T, F, Fut>(make: F) -> Result<T>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    match make().await {
+        Err(e) if classify(&e) == Retryable::Auth => {
+            debug!("credentials rejected, retrying on a new client: {e}");
+            AUTH_RETRIES.fetch_add(1, Ordering::Relaxed);
+            drop_pool();
+            make().await
+        }
+        result => result,
+    }
+}
+
+/// `exp` claim of a JWT, without verifying it
+fn jwt_expiry(token: &str) -> Option<i64> {
+    let claims = token.split('.').nth(1)?;
+    let json = URL_SAFE_NO_PAD.decode(claims.trim_end_matches('=')).ok()?;
+    let claims: serde_json::Value = serde_json::from_slice(&json).ok()?;
+    claims.get("exp")?.as_i64()
+}
+
+/// How long until the credentials of this user should be refreshed, `None`
+/// for credentials that do not expire on their own.
+fn refresh_in(auth: &AuthInfo, now: i64) -> Option<Duration> {
+    if auth.exec.is_some() {
+        return Some(EXEC_REFRESH_INTERVAL);
+    }
+    let id_token = auth.auth_provider.as_ref()?.config.get("id-token")?;
+    let refresh_at = jwt_expiry(id_token)? - REFRESH_MARGIN_SECS;
+    let secs = u64::try_from(refresh_at - now).unwrap_or(0);
+    Some(Duration::from_secs(secs).max(MIN_REFRESH_INTERVAL))
+}
+
+fn current_auth_info() -> Option<AuthInfo> {
+    let config = Kubeconfig::read().ok()?;
+    let context_name = config.current_context.clone()?;
+    let user = config
+        .contexts
+        .iter()
+        .find(|context| context.name == context_name)?
+        .context
+        .as_ref()?
+        .user
+        .clone();
+    config
+        .auth_infos
+        .into_iter()
+        .find(|auth| auth.name == user)?
+        .auth_info
+}
+
+/// Start the background task that rebuilds clients ahead of credential
+/// expiry.  Only the first call starts a task.
+pub fn start_token_refresh() {
+    if REFRESH_STARTED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    tokio::spawn(async {
+        loop {
+            let Some(wait) =
+                current_auth_info().and_then(|auth| refresh_in(&auth, Utc::now().timestamp()))
+            else {
+                debug!("credentials do not expire, token refresh not scheduled");
+                return;
+            };
+            tokio::time::sleep(wait).await;
+            debug!("refreshing clients ahead of credential expiry");
+            TOKEN_REFRESHES.fetch_add(1, Ordering::Relaxed);
+            drop_pool();
+            warm_up().await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kube::config::{AuthProviderConfig, ExecConfig};
+
+    fn jwt(exp: i64) -> String {
+        let claims = URL_SAFE_NO_PAD.encode(format!(r#"{{"sub":"me","exp":{exp}}}"#));
+        format!("e30.{claims}.sig")
+    }
+
+    #[test]
+    fn test_context_is_read_again_only_after_a_change() {
+        let start = Instant::now();
+        let later = start + CONTEXT_CHECK_INTERVAL;
+        let edited = SystemTime::UNIX_EPOCH + Duration::from_secs(1);
+        let mut cached = None;
+        let current = |cached: &mut Option<CachedContext>, now, stamp, context: &str| {
+            CachedContext::current(cached, now, || vec![stamp], || context.to_string())
+        };
+        assert_eq!(current(&mut cached, start, None, "prod"), "prod");
+        // within the interval the files are not even looked at
+        assert_eq!(current(&mut cached, start, Some(edited), "staging"), "prod");
+        // unchanged files keep the context without reading the kubeconfig
+        assert_eq!(current(&mut cached, later, None, "staging"), "prod");
+        // use-context elsewhere touched the file
+        let after = later + CONTEXT_CHECK_INTERVAL;
+        assert_eq!(
+            current(&mut cached, after, Some(edited), "staging"),
+            "staging"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pools_are_kept_by_context() {
+        use hyper::http::{Request, Response};
+        use kube::client::Body;
+        let client = || {
+            let (service, _) = tower_test::mock::pair::<Request<Body>, Response<Body>>();
+            Client::new(service, "default")
+        };
+        for _ in 0..=POOL_SIZE {
+            add_to_pool("pool-test-a", client());
+        }
+        assert!(pooled("pool-test-a").is_some());
+        assert!(pooled("pool-test-b").is_none());
+        let sizes = POOLS.lock().unwrap().as_ref().map(|pools| {
+            pools
+                .get("pool-test-a")
+                .map(|pool| (pool.clients.len(), pool.next))
+        });
+        assert_eq!(sizes, Some(Some((POOL_SIZE, 1))));
+        add_to_pool("pool-test-b", client());
+        assert!(pooled("pool-test-b").is_some());
+    }
+
+    #[test]
+    fn test_jwt_expiry() {
+        assert_eq!(jwt_expiry(&jwt(1_700_000_000)), Some(1_700_000_000));
+        assert_eq!(jwt_expiry("not-a-jwt"), None);
+    }
+
+    #[test]
+    fn test_refresh_in() {
+        let now = 1_700_000_000;
+        let oidc = AuthInfo {
+            auth_provider: Some(AuthProviderConfig {
+                name: "oidc".to_string(),
+                config: HashMap::from([("id-token".to_string(), jwt(now + 600))]),
+            }),
+            ..AuthInfo::default()
+        };
+        assert_eq!(refresh_in(&oidc, now), Some(Duration::from_secs(480)));
+        assert_eq!(refresh_in(&oidc, now + 590), Some(MIN_REFRESH_INTERVAL));
+
+        let exec = AuthInfo {
+            exec: Some(ExecConfig {
+                api_version: None,
+                command: Some("aws".to_string()),
+                args: None,
+                env: None,
+                drop_env: None,
+                interactive_mode: None,
+                cluster: None,
+                provide_cluster_info: false,
+            }),
+            ..AuthInfo::default()
+        };
+        assert_eq!(refresh_in(&exec, now), Some(EXEC_REFRESH_INTERVAL));
+        assert_eq!(refresh_in(&AuthInfo::default(), now), None);
+    }
+}
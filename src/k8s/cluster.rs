@@ -0,0 +1,437 @@
+//!Cluster scoped resources: namespaces, persistent volumes, storage classes
+//!and CRDs, the objects everything else is namespaced below.  A kind is only
+//!listed when a SelfSubjectAccessReview allows it, one the user may not list
+//!is reported as such instead of failing the view.
+//!
+use crate::error::{Error, Result};
+use crate::k8s::client_manager::get_client;
+use crate::k8s::events::format_duration;
+use crate::k8s::paging;
+use crate::k8s::timeout::with_timeout;
+use crate::tui::data::ClusterRow;
+use chrono::{DateTime, Utc};
+use k8s_openapi::api::authorization::v1::{
+    ResourceAttributes, SelfSubjectAccessReview, SelfSubjectAccessReviewSpec,
+};
+use k8s_openapi::api::core::v1::{Namespace, PersistentVolume};
+use k8s_openapi::api::storage::v1::StorageClass;
+use k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use k8s_openapi::ClusterResourceScope;
+use kube::api::{ListParams, PostParams};
+use kube::{Api, Client, Resource};
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use tracing::debug;
+
+const DEFAULT_CLASS: &str = "storageclass.kubernetes.io/is-default-class";
+
+/// how many objects of a kind there are, `None` when the user may not list it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KindCount {
+    pub kind: &'static str,
+    pub count: Option<usize>,
+    /// `Terminating`, `Released`: statuses that need a look, with counts
+    pub attention: BTreeMap<String, usize>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClusterListing {
+    pub rows: Vec<ClusterRow>,
+    pub counts: Vec<KindCount>,
+}
+
+impl ClusterListing {
+    fn add(&mut self, kind: &'static str, rows: Option<Vec<(ClusterRow, bool)>>) {
+        let Some(mut rows) = rows else {
+            self.counts.push(KindCount {
+                kind,
+                count: None,
+                attention: BTreeMap::new(),
+            });
+            return;
+        };
+        let mut attention = BTreeMap::new();
+        for (row, healthy) in &rows {
+            if !healthy {
+                *attention.entry(row.status.clone()).or_insert(0) += 1;
+            }
+        }
+        // the rows needing a look first
+        rows.sort_by(|(a, a_healthy), (b, b_healthy)| {
+            a_healthy.cmp(b_healthy).then_with(|| a.name.cmp(&b.name))
+        });
+        self.counts.push(KindCount {
+            kind,
+            count: Some(rows.len()),
+            attention,
+        });
+        self.rows.extend(rows.into_iter().map(|(row, _)| row));
+    }
+
+    /// `12 Namespace (1 Terminating), 3 StorageClass, PersistentVolume not allowed`
+    #[must_use]
+    pub fn summary(&self) -> String {
+        self.counts
+            .iter()
+            .map(|count| match count.count {
+                None => format!("{} not allowed", count.kind),
+                Some(n) if count.attention.is_empty() => format!("{n} {}", count.kind),
+                Some(n) => {
+                    let attention: Vec<String> = count
+                        .attention
+                        .iter()
+                        .map(|(status, n)| format!("{n} {status}"))
+                        .collect();
+                    format!("{n} {} ({})", count.kind, attention.join(", "))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// whether the user may `list` `resource` of `group`, "" being the core group
+async fn can_list(client: &Client, group: &str, resource: &str) -> bool {
+    let review = SelfSubjectAccessReview {
+        spec: SelfSubjectAccessReviewSpec {
+            resource_attributes: Some(ResourceAttributes {
+                group: Some(group.to_string()),
+                resource: Some(resource.to_string()),
+                verb: Some("list".to_string()),
+                ..ResourceAttributes::default()
+            }),
+            ..SelfSubjectAccessReviewSpec::default()
+        },
+        ..SelfSubjectAccessReview::default()
+    };
+    let api: Api<SelfSubjectAccessReview> = Api::all(client.clone());
+    match with_timeout("review access", api.create(&PostParams::default(), &review)).await {
+        Ok(review) => review.status.is_some_and(|status| status.allowed),
+        Err(e) => {
+            // no answer is no verdict, the list itself tells
+            debug!("can not review access to {resource}: {e}");
+            true
+        }
+    }
+}
+
+fn age(metadata: &ObjectMeta, now: DateTime<Utc>) -> String {
+    metadata
+        .creation_timestamp
+        .as_ref()
+        .map(|created| format_duration(now.signed_duration_since(created.0)))
+        .unwrap_or_default()
+}
+
+fn row(
+    kind: &str,
+    metadata: &ObjectMeta,
+    status: String,
+    detail: String,
+    now: DateTime<Utc>,
+) -> ClusterRow {
+    ClusterRow {
+        kind: kind.to_string(),
+        name: metadata.name.clone().unwrap_or_default(),
+        status,
+        detail,
+        age: age(metadata, now),
+    }
+}
+
+fn namespace_row(namespace: &Namespace, now: DateTime<Utc>) -> (ClusterRow, bool) {
+    let phase = namespace
+        .status
+        .as_ref()
+        .and_then(|status| status.phase.clone())
+        .unwrap_or_else(|| "Unknown".to_string());
+    let healthy = phase == "Active";
+    let detail = namespace
+        .metadata
+        .labels
+        .iter()
+        .flatten()
+        .filter(|(key, _)| key.as_str() != "kubernetes.io/metadata.name")
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    (
+        row("Namespace", &namespace.metadata, phase, detail, now),
+        healthy,
+    )
+}
+
+fn volume_row(volume: &PersistentVolume, now: DateTime<Utc>) -> (ClusterRow, bool) {
+    let phase = volume
+        .status
+        .as_ref()
+        .and_then(|status| status.phase.clone())
+        .unwrap_or_else(|| "Unknown".to_string());
+    let healthy = phase == "Bound" || phase == "Available";
+    let mut detail = vec![];
+    if let Some(spec) = &volume.spec {
+        if let Some(claim) = &spec.claim_ref {
+            detail.push(format!(
+                "claim {}/{}",
+                claim.namespace.as_deref().unwrap_or_default(),
+                claim.name.as_deref().unwrap_or_default()
+            ));
+        }
+        if let Some(storage) = spec.capacity.as_ref().and_then(|c| c.get("storage")) {
+            detail.push(storage.0.clone());
+        }
+        if let Some(class) = &spec.storage_class_name {
+            detail.push(format!("class {class}"));
+        }
+        if let Some(policy) = &spec.persistent_volume_reclaim_policy {
+            detail.push(policy.clone());
+        }
+    }
+    (
+        row(
+            "PersistentVolume",
+            &volume.metadata,
+            phase,
+            detail.join(", "),
+            now,
+        ),
+        healthy,
+    )
+}
+
+fn storage_class_row(class: &StorageClass, now: DateTime<Utc>) -> (ClusterRow, bool) {
+    let default = class
+        .metadata
+        .annotations
+        .as_ref()
+        .and_then(|annotations| annotations.get(DEFAULT_CLASS))
+        .is_some_and(|value| value == "true");
+    let mut detail = vec![class.provisioner.clone()];
+    detail.extend(class.reclaim_policy.clone());
+    detail.extend(class.volume_binding_mode.clone());
+    if class.allow_volume_expansion == Some(true) {
+        detail.push("expandable".to_string());
+    }
+    let status = if default { "default" } else { "" };
+    (
+        row(
+            "StorageClass",
+            &class.metadata,
+            status.to_string(),
+            detail.join(", "),
+            now,
+        ),
+        true,
+    )
+}
+
+fn crd_row(crd: &CustomResourceDefinition, now: DateTime<Utc>) -> (ClusterRow, bool) {
+    let established = crd
+        .status
+        .as_ref()
+        .and_then(|status| status.conditions.as_ref())
+        .and_then(|conditions| conditions.iter().find(|c| c.type_ == "Established"));
+    let (status, healthy) = match established {
+        Some(condition) if condition.status == "True" => ("Established".to_string(), true),
+        Some(condition) => (
+            format!(
+                "not established: {}",
+                condition.reason.as_deref().unwrap_or_default()
+            ),
+            false,
+        ),
+        None => ("not established".to_string(), false),
+    };
+    let versions: Vec<&str> = crd
+        .spec
+        .versions
+        .iter()
+        .filter(|version| version.served)
+        .map(|version| version.name.as_str())
+        .collect();
+    let detail = format!(
+        "{}, {}, {}",
+        crd.spec.group,
+        crd.spec.scope,
+        versions.join(" ")
+    );
+    (
+        row(
+            "CustomResourceDefinition",
+            &crd.metadata,
+            status,
+            detail,
+            now,
+        ),
+        healthy,
+    )
+}
+
+/// The objects of one kind as rows, `None` when the user may not list them.
+async fn list<K>(
+    client: &Client,
+    group: &str,
+    resource: &str,
+    operation: &'static str,
+    to_row: fn(&K, DateTime<Utc>) -> (ClusterRow, bool),
+) -> Result<Option<Vec<(ClusterRow, bool)>>>
+where
+    K: Resource<Scope = ClusterResourceScope> + Clone + DeserializeOwned + Debug,
+    <K as Resource>::DynamicType: Default,
+{
+    if !can_list(client, group, resource).await {
+        return Ok(None);
+    }
+    let api: Api<K> = Api::all(client.clone());
+    let now = Utc::now();
+    match paging::list(&api, ListParams::default(), operation).await {
+        Ok(objects) => Ok(Some(objects.iter().map(|o| to_row(o, now)).collect())),
+        Err(Error::Forbidden(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Namespaces, persistent volumes, storage classes and CRDs, each kind's
+/// rows with the ones needing a look first.
+///
+/// # Errors
+///
+/// Will return `Err` if data can not be retrieved from k8s cluster api
+pub async fn list_cluster_resources() -> Result<ClusterListing> {
+    let client = get_client().await?;
+    let mut listing = ClusterListing::default();
+    listing.add(
+        "Namespace",
+        list(&client, "", "namespaces", "list namespaces", namespace_row).await?,
+    );
+    listing.add(
+        "PersistentVolume",
+        list(
+            &client,
+            "",
+            "persistentvolumes",
+            "list persistent volumes",
+            volume_row,
+        )
+        .await?,
+    );
+    listing.add(
+        "StorageClass",
+        list(
+            &client,
+            "storage.k8s.io",
+            "storageclasses",
+            "list storage classes",
+            storage_class_row,
+        )
+        .await?,
+    );
+    listing.add(
+        "CustomResourceDefinition",
+        list(
+            &client,
+            "apiextensions.k8s.io",
+            "customresourcedefinitions",
+            "list crds",
+            crd_row,
+        )
+        .await?,
+    );
+    Ok(listing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::serde_json::{from_value, json};
+
+    #[test]
+    fn test_cluster_listing() {
+        let now: DateTime<Utc> = "2024-03-01T00:00:00Z".parse().unwrap();
+        let namespaces: Vec<Namespace> = from_value(json!([
+            {"metadata": {"name": "web", "creationTimestamp": "2024-02-01T00:00:00Z",
+                          "labels": {"kubernetes.io/metadata.name": "web", "team": "shop"}},
+             "status": {"phase": "Active"}},
+            {"metadata": {"name": "old"}, "status": {"phase": "Terminating"}}
+        ]))
+        .unwrap();
+        let volume: PersistentVolume = from_value(json!({
+            "metadata": {"name": "pv-1"},
+            "spec": {
+                "capacity": {"storage": "10Gi"},
+                "claimRef": {"namespace": "web", "name": "data"},
+                "storageClassName": "standard",
+                "persistentVolumeReclaimPolicy": "Retain"
+            },
+            "status": {"phase": "Released"}
+        }))
+        .unwrap();
+        let class: StorageClass = from_value(json!({
+            "metadata": {"name": "standard",
+                         "annotations": {"storageclass.kubernetes.io/is-default-class": "true"}},
+            "provisioner": "rancher.io/local-path",
+            "reclaimPolicy": "Delete",
+            "volumeBindingMode": "WaitForFirstConsumer"
+        }))
+        .unwrap();
+        let crd: CustomResourceDefinition = from_value(json!({
+            "metadata": {"name": "gateways.gateway.networking.k8s.io"},
+            "spec": {
+                "group": "gateway.networking.k8s.io",
+                "names": {"kind": "Gateway", "plural": "gateways"},
+                "scope": "Namespaced",
+                "versions": [
+                    {"name": "v1", "served": true, "storage": true},
+                    {"name": "v1beta1", "served": true, "storage": false}
+                ]
+            },
+            "status": {"conditions": [{"type": "Established", "status": "True"}]}
+        }))
+        .unwrap();
+
+        let mut listing = ClusterListing::default();
+        listing.add(
+            "Namespace",
+            Some(namespaces.iter().map(|n| namespace_row(n, now)).collect()),
+        );
+        listing.add("PersistentVolume", Some(vec![volume_row(&volume, now)]));
+        listing.add("StorageClass", Some(vec![storage_class_row(&class, now)]));
+        listing.add("CustomResourceDefinition", Some(vec![crd_row(&crd, now)]));
+        listing.add("Node", None);
+
+        let rows: Vec<(&str, &str, &str)> = listing
+            .rows
+            .iter()
+            .map(|r| (r.name.as_str(), r.status.as_str(), r.detail.as_str()))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("old", "Terminating", ""),
+                ("web", "Active", "team=shop"),
+                (
+                    "pv-1",
+                    "Released",
+                    "claim web/data, 10Gi, class standard, Retain"
+                ),
+                (
+                    "standard",
+                    "default",
+                    "rancher.io/local-path, Delete, WaitForFirstConsumer"
+                ),
+                (
+                    "gateways.gateway.networking.k8s.io",
+                    "Established",
+                    "gateway.networking.k8s.io, Namespaced, v1 v1beta1"
+                ),
+            ]
+        );
+        assert_eq!(listing.rows[1].age, "29d");
+        assert_eq!(
+            listing.summary(),
+            "2 Namespace (1 Terminating), 1 PersistentVolume (1 Released), 1 StorageClass, 1 CustomResourceDefinition, Node not allowed"
+        );
+    }
+}
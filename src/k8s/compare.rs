@@ -0,0 +1,219 @@
+//!Side by side view of what configures two pods: images, env vars,
+//!resources, labels and mounts.  Replicas of one workload should only
+//!differ in a few generated labels, anything else is worth a look when one
+//!of them misbehaves.
+//!
+use crate::error::Result;
+use crate::k8s::client_manager::get_client;
+use crate::k8s::timeout::with_timeout;
+use k8s_openapi::api::core::v1::{Container, EnvVar, Pod};
+use kube::Api;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompareRow {
+    pub section: &'static str,
+    pub key: String,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+impl CompareRow {
+    #[must_use]
+    pub fn differs(&self) -> bool {
+        self.left != self.right
+    }
+}
+
+type Facts = BTreeMap<(&'static str, String), String>;
+
+fn env_value(env: &EnvVar) -> String {
+    if let Some(value) = &env.value {
+        return value.clone();
+    }
+    let Some(from) = &env.value_from else {
+        return String::new();
+    };
+    if let Some(secret) = &from.secret_key_ref {
+        format!("secret {}/{}", secret.name, secret.key)
+    } else if let Some(config_map) = &from.config_map_key_ref {
+        format!("configmap {}/{}", config_map.name, config_map.key)
+    } else if let Some(field) = &from.field_ref {
+        format!("field {}", field.field_path)
+    } else if let Some(resource) = &from.resource_field_ref {
+        format!("resource {}", resource.resource)
+    } else {
+        String::new()
+    }
+}
+
+fn container_facts(facts: &mut Facts, container: &Container) {
+    let name = &container.name;
+    facts.insert(
+        ("image", name.clone()),
+        container.image.clone().unwrap_or_default(),
+    );
+    for env in container.env.iter().flatten() {
+        facts.insert(("env", format!("{name}: {}", env.name)), env_value(env));
+    }
+    if let Some(resources) = &container.resources {
+        for (kind, quantities) in [
+            ("requests", &resources.requests),
+            ("limits", &resources.limits),
+        ] {
+            for (resource, quantity) in quantities.iter().flatten() {
+                facts.insert(
+                    ("resources", format!("{name}: {kind}.{resource}")),
+                    quantity.0.clone(),
+                );
+            }
+        }
+    }
+    for mount in container.volume_mounts.iter().flatten() {
+        let mut value = mount.name.clone();
+        if let Some(sub_path) = &mount.sub_path {
+            let _ = write!(value, " ({sub_path})");
+        }
+        if mount.read_only == Some(true) {
+            value.push_str(" ro");
+        }
+        facts.insert(("mounts", format!("{name}: {}", mount.mount_path)), value);
+    }
+}
+
+fn pod_facts(pod: &Pod) -> Facts {
+    let mut facts = Facts::new();
+    if let Some(spec) = &pod.spec {
+        for container in spec
+            .init_containers
+            .iter()
+            .flatten()
+            .chain(&spec.containers)
+        {
+            container_facts(&mut facts, container);
+        }
+    }
+    for (key, value) in pod.metadata.labels.iter().flatten() {
+        facts.insert(("labels", key.clone()), value.clone());
+    }
+    facts
+}
+
+/// Every fact of either pod, grouped by section.  Containers are matched by
+/// name.
+#[must_use]
+pub fn compare_pods(left: &Pod, right: &Pod) -> Vec<CompareRow> {
+    let mut left = pod_facts(left);
+    let right = pod_facts(right);
+    let mut rows: Vec<CompareRow> = right
+        .into_iter()
+        .map(|((section, key), value)| CompareRow {
+            left: left.remove(&(section, key.clone())),
+            section,
+            key,
+            right: Some(value),
+        })
+        .collect();
+    rows.extend(left.into_iter().map(|((section, key), value)| CompareRow {
+        section,
+        key,
+        left: Some(value),
+        right: None,
+    }));
+    let order = ["image", "env", "resources", "mounts", "labels"];
+    rows.sort_by_key(|row| {
+        (
+            order.iter().position(|s| *s == row.section),
+            row.key.clone(),
+        )
+    });
+    rows
+}
+
+/// `namespace/name` or a plain name in the client's namespace
+///
+/// # Errors
+///
+/// Will return `Err` if the pod can not be read
+pub async fn get_pod(pod_ref: &str) -> Result<Pod> {
+    let client = get_client().await?;
+    let pods: Api<Pod> = match pod_ref.split_once('/') {
+        Some((namespace, _)) => Api::namespaced(client, namespace),
+        None => Api::default_namespaced(client),
+    };
+    let name = pod_ref.rsplit('/').next().unwrap_or(pod_ref);
+    with_timeout("get pod", pods.get(name)).await
+}
+
+/// Print the rows that differ as two columns.
+///
+/// # Errors
+///
+/// Will return `Err` if either pod can not be read
+pub async fn explain_diff(left: &str, right: &str) -> Result<()> {
+    let rows = compare_pods(&get_pod(left).await?, &get_pod(right).await?);
+    let differing: Vec<&CompareRow> = rows.iter().filter(|row| row.differs()).collect();
+    if differing.is_empty() {
+        println!("{left} and {right} are configured the same");
+        return Ok(());
+    }
+    let key_width = differing.iter().map(|row| row.key.len()).max().unwrap_or(0);
+    let left_width = differing
+        .iter()
+        .map(|row| row.left.as_deref().map_or(1, str::len))
+        .max()
+        .unwrap_or(0)
+        .max(left.len());
+    println!("{:10} {:key_width$} {left:left_width$} {right}", "", "");
+    for row in differing {
+        println!(
+            "{:10} {:key_width$} {:left_width$} {}",
+            row.section,
+            row.key,
+            row.left.as_deref().unwrap_or("-"),
+            row.right.as_deref().unwrap_or("-"),
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::serde_json::{from_value, json};
+
+    fn pod(image: &str, level: &str, hash: &str) -> Pod {
+        from_value(json!({
+            "metadata": {"name": "web", "labels": {"app": "web", "pod-template-hash": hash}},
+            "spec": {"containers": [{
+                "name": "web",
+                "image": image,
+                "env": [{"name": "LOG_LEVEL", "value": level}],
+                "resources": {"limits": {"memory": "256Mi"}},
+                "volumeMounts": [{"name": "config", "mountPath": "/etc/web", "readOnly": true}]
+            }]}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_compare_pods() {
+        let rows = compare_pods(
+            &pod("web:1.2", "info", "abc"),
+            &pod("web:1.3", "info", "def"),
+        );
+        let differing: Vec<(&str, &str)> = rows
+            .iter()
+            .filter(|row| row.differs())
+            .map(|row| (row.section, row.key.as_str()))
+            .collect();
+        assert_eq!(
+            differing,
+            vec![("image", "web"), ("labels", "pod-template-hash")]
+        );
+        assert_eq!(rows.len(), 6);
+        assert_eq!(rows[2].key, "web: limits.memory");
+        assert_eq!(rows[3].left.as_deref(), Some("config ro"));
+    }
+}
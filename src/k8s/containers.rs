@@ -1,14 +1,25 @@
 use crate::error::Result;
-use crate::k8s::client::new;
+use crate::k8s::client_manager::get_client;
+use crate::k8s::paging;
+use crate::k8s::restarts::{record_pod, trend};
+use crate::k8s::retry::{with_retry, RetryPolicy};
+use crate::k8s::sidecars::is_sidecar;
+use crate::k8s::timeout::with_timeout;
 use crate::k8s::utils::format_label_selector;
 use crate::tui::data::{Container, ContainerEnvVar, ContainerMount, LogRec};
+use crate::tui::utils::ansi::sanitize;
+use crate::tui::utils::time::{TimeZoneSetting, LOG_TIME_FORMAT};
+use chrono::{DateTime, Utc};
+use futures::{stream, StreamExt};
 use k8s_openapi::api::core::v1::ContainerPort;
 use k8s_openapi::api::core::v1::Pod;
 use kube::{
     api::{Api, ListParams, LogParams, ObjectList},
-    Client, ResourceExt,
+    ResourceExt,
 };
+use regex::Regex;
 use std::collections::BTreeMap;
+use tracing::debug;
 
 fn format_ports(ports: Option<Vec<ContainerPort>>) -> String {
     ports.map_or_else(
@@ -32,18 +43,37 @@ fn format_ports(ports: Option<Vec<ContainerPort>>) -> String {
 #[allow(clippy::significant_drop_tightening)]
 #[allow(clippy::too_many_lines)]
 pub async fn list(selector: BTreeMap<String, String>, pod_name: String) -> Result<Vec<Container>> {
-    let client = Client::try_default().await?;
+    with_retry(&RetryPolicy::default(), || {
+        fetch_containers(&selector, Some(&pod_name))
+    })
+    .await
+}
+
+/// the containers of `pod_name`, of every pod when `None`, without retrying,
+/// for callers that retry it themselves
+///
+/// # Errors
+///
+/// Will return `Err` if data can not be retrieved from k8s cluster api
+pub async fn fetch_containers(
+    selector: &BTreeMap<String, String>,
+    pod_name: Option<&str>,
+) -> Result<Vec<Container>> {
+    let client = get_client().await?;
 
-    let label_selector = format_label_selector(&selector);
+    let label_selector = format_label_selector(selector);
 
     let lp = ListParams::default().labels(&label_selector);
 
-    // Assuming there should be a single pod matching the selector and name
-    let pod_list: ObjectList<Pod> = Api::default_namespaced(client).list(&lp).await?;
+    let pods: Api<Pod> = Api::default_namespaced(client);
+    let pod_list = paging::list(&pods, lp, "list pods").await?;
 
     let mut container_vec = Vec::new();
 
-    for pod in pod_list.items {
+    let now = Utc::now().timestamp();
+    for pod in pod_list {
+        record_pod(&pod, now);
+        let namespace = pod.metadata.namespace.clone().unwrap_or_default();
         let container_statuses = pod
             .status
             .as_ref()
@@ -52,7 +82,7 @@ pub async fn list(selector: BTreeMap<String, String>, pod_name: String) -> Resul
 
         if let Some(name) = pod.metadata.name {
             let container_selectors = pod.metadata.labels;
-            if name == pod_name.clone() {
+            if pod_name.is_none_or(|pod_name| pod_name == name) {
                 if let Some(spec) = pod.spec {
                     for container in spec.containers {
                         let image = container.image.unwrap_or_else(|| "unknown".to_string());
@@ -62,6 +92,7 @@ pub async fn list(selector: BTreeMap<String, String>, pod_name: String) -> Resul
                             .find(|cs| cs.name == container.name)
                             .map_or(0, |cs| cs.restart_count)
                             .to_string();
+                        let restart_trend = trend(&namespace, &name, &container.name, now);
 
                         let volume_mounts = container.volume_mounts;
                         let mounts: Vec<ContainerMount> = volume_mounts
@@ -86,13 +117,14 @@ pub async fn list(selector: BTreeMap<String, String>, pod_name: String) -> Resul
                         let c = Container {
                             name: container.name,
                             description: "a pod container".to_string(),
+                            restart_trend,
                             restarts,
                             image,
                             ports,
                             mounts,
                             envvars,
                             selectors: container_selectors.clone(),
-                            pod_name: pod_name.clone(),
+                            pod_name: name.clone(),
                         };
                         container_vec.push(c);
                     }
@@ -105,6 +137,7 @@ pub async fn list(selector: BTreeMap<String, String>, pod_name: String) -> Resul
                                 .find(|cs| cs.name == container.name)
                                 .map_or(0, |cs| cs.restart_count)
                                 .to_string();
+                            let restart_trend = trend(&namespace, &name, &container.name, now);
 
                             let volume_mounts = container.volume_mounts;
                             let mounts: Vec<ContainerMount> = volume_mounts
@@ -129,13 +162,14 @@ pub async fn list(selector: BTreeMap<String, String>, pod_name: String) -> Resul
                             let c = Container {
                                 name: container.name,
                                 description: "an init container".to_string(), // Distinguish init containers
+                                restart_trend,
                                 restarts,
                                 image,
                                 ports: String::new(),
                                 mounts,
                                 envvars,
                                 selectors: container_selectors.clone(),
-                                pod_name: pod_name.clone(),
+                                pod_name: name.clone(),
                             };
                             container_vec.push(c);
                         }
@@ -157,14 +191,25 @@ pub async fn logs(
     pod_name: String,
     container_name: String,
 ) -> Result<Vec<LogRec>> {
-    let client = new(None).await?;
+    with_retry(&RetryPolicy::default(), || {
+        fetch_logs(&selector, &pod_name, &container_name)
+    })
+    .await
+}
+
+async fn fetch_logs(
+    selector: &BTreeMap<String, String>,
+    pod_name: &str,
+    container_name: &str,
+) -> Result<Vec<LogRec>> {
+    let client = get_client().await?;
     let pods: Api<Pod> = Api::default_namespaced(client);
 
-    let label_selector = format_label_selector(&selector);
+    let label_selector = format_label_selector(selector);
 
     let lp = ListParams::default().labels(&label_selector);
 
-    let pod_list: ObjectList<Pod> = pods.list(&lp).await?;
+    let pod_list: ObjectList<Pod> = with_timeout("list pods", pods.list(&lp)).await?;
 
     let mut log_vec = Vec::new();
 
@@ -175,24 +220,326 @@ pub async fn logs(
         .filter(|pod| pod.name_any() == pod_name)
     {
         let log_params = LogParams {
-            container: Some(container_name.clone()),
+            container: Some(container_name.to_string()),
+            timestamps: true,
             tail_lines: Some(100), // Adjust based on how many lines you want
             ..Default::default()
         };
 
         // Fetch logs for the specified container
-        let logs = pods.logs(&pod.name_any(), &log_params).await?;
-
-        // Parse and map logs to Vec<Log>
-        logs.lines().for_each(|line| {
-            log_vec.push(LogRec {
-                datetime: String::new(), //need a smart parser that can figure out the format
-                level: String::new(),
-                message: line.to_string(),
-            });
-        });
+        let logs = with_timeout("fetch logs", pods.logs(&pod.name_any(), &log_params)).await?;
+
+        log_vec.extend(to_log_recs(&logs));
     }
     log_vec.reverse(); // Reverse the order of logs to show the latest logs first
 
     Ok(log_vec)
 }
+
+/// Logs of the instance of a container that ran before its last restart.
+/// `namespace` defaults to the client's namespace.
+///
+/// # Errors
+///
+/// Will return `Err` if data can not be retrieved from k8s cluster api
+pub async fn previous_logs(
+    namespace: Option<&str>,
+    pod_name: &str,
+    container_name: &str,
+    tail_lines: i64,
+) -> Result<Vec<LogRec>> {
+    let client = get_client().await?;
+    let pods: Api<Pod> = match namespace {
+        Some(ns) => Api::namespaced(client, ns),
+        None => Api::default_namespaced(client),
+    };
+    let log_params = LogParams {
+        container: Some(container_name.to_string()),
+        timestamps: true,
+        previous: true,
+        tail_lines: Some(tail_lines),
+        ..Default::default()
+    };
+    let logs = with_timeout("fetch previous logs", pods.logs(pod_name, &log_params)).await?;
+    let mut log_vec = to_log_recs(&logs);
+    log_vec.reverse();
+    Ok(log_vec)
+}
+
+pub const RESTART_DIVIDER: &str = "─── container restarted ───";
+
+/// the lines of earlier instances of a followed container, newest first,
+/// each instance below a divider
+#[derive(Clone, Debug, Default)]
+pub struct EarlierInstances {
+    restarts: Option<i32>,
+    lines: Vec<LogRec>,
+}
+
+impl EarlierInstances {
+    /// earlier lines kept across restarts
+    const MAX_LINES: usize = 1000;
+
+    /// `true` when `restarts` went up since the last call, the first count
+    /// seen is the baseline
+    pub fn restarted(&mut self, restarts: Option<i32>) -> bool {
+        let restarted =
+            matches!((self.restarts, restarts), (Some(before), Some(after)) if after > before);
+        if restarts.is_some() {
+            self.restarts = restarts;
+        }
+        restarted
+    }
+
+    /// keep the lines of the instance that just ended
+    pub fn push(&mut self, previous: Vec<LogRec>) {
+        let mut lines = vec![LogRec {
+            message: RESTART_DIVIDER.to_string(),
+            ..LogRec::default()
+        }];
+        lines.extend(previous);
+        lines.append(&mut self.lines);
+        lines.truncate(Self::MAX_LINES);
+        self.lines = lines;
+    }
+
+    /// the current instance's lines followed by the earlier ones
+    #[must_use]
+    pub fn below(&self, mut current: Vec<LogRec>) -> Vec<LogRec> {
+        current.extend(self.lines.iter().cloned());
+        current
+    }
+}
+
+/// Fetch logs for a pod outside the current namespace, ie: an ingress controller.
+///
+/// # Errors
+///
+/// Will return `Err` if data can not be retrieved from k8s cluster api
+pub async fn namespaced_logs(
+    namespace: &str,
+    pod_name: &str,
+    container_name: &str,
+    tail_lines: i64,
+) -> Result<Vec<LogRec>> {
+    with_retry(&RetryPolicy::default(), || {
+        fetch_namespaced_logs(namespace, pod_name, container_name, tail_lines)
+    })
+    .await
+}
+
+async fn fetch_namespaced_logs(
+    namespace: &str,
+    pod_name: &str,
+    container_name: &str,
+    tail_lines: i64,
+) -> Result<Vec<LogRec>> {
+    let client = get_client().await?;
+    let pods: Api<Pod> = Api::namespaced(client, namespace);
+
+    let log_params = LogParams {
+        container: Some(container_name.to_string()),
+        timestamps: true,
+        tail_lines: Some(tail_lines),
+        ..Default::default()
+    };
+
+    let logs = with_timeout("fetch logs", pods.logs(pod_name, &log_params)).await?;
+
+    let mut log_vec = to_log_recs(&logs);
+    log_vec.reverse(); // Reverse the order of logs to show the latest logs first
+
+    Ok(log_vec)
+}
+
+/// Recent logs of one container of a replica and the lines a search matched.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplicaLogs {
+    pub pod: String,
+    pub container: String,
+    /// newest first, as the log view shows them
+    pub logs: Vec<LogRec>,
+    /// indexes into `logs`
+    pub matches: Vec<usize>,
+}
+
+impl ReplicaLogs {
+    fn search(pod: String, container: String, logs: Vec<LogRec>, pattern: &Regex) -> Self {
+        let matches = logs
+            .iter()
+            .enumerate()
+            .filter(|(_, rec)| pattern.is_match(&rec.message))
+            .map(|(i, _)| i)
+            .collect();
+        Self {
+            pod,
+            container,
+            logs,
+            matches,
+        }
+    }
+}
+
+/// logs read at once by a replica log search, a large replicaset is searched
+/// a few containers at a time instead of all of them together
+const LOG_SEARCH_CONCURRENCY: usize = 8;
+
+/// the searches with matches, by pod then container
+fn with_matches(searches: Vec<Option<ReplicaLogs>>) -> Vec<ReplicaLogs> {
+    let mut results: Vec<ReplicaLogs> = searches
+        .into_iter()
+        .flatten()
+        .filter(|result| !result.matches.is_empty())
+        .collect();
+    results.sort_by(|a, b| (&a.pod, &a.container).cmp(&(&b.pod, &b.container)));
+    results
+}
+
+/// Search the recent logs of every container of every pod matching the
+/// selector, `LOG_SEARCH_CONCURRENCY` at a time, sidecars only when `sidecars` is set.  Containers
+/// without matches are left out.
+///
+/// # Errors
+///
+/// Will return `Err` if the pods can not be listed, a container whose logs
+/// can not be read is skipped
+pub async fn search_replica_logs(
+    selector: &BTreeMap<String, String>,
+    pattern: &Regex,
+    tail_lines: i64,
+    sidecars: bool,
+) -> Result<Vec<ReplicaLogs>> {
+    let client = get_client().await?;
+    let pods: Api<Pod> = Api::default_namespaced(client);
+    let lp = ListParams::default().labels(&format_label_selector(selector));
+    let pod_list = with_timeout("list pods", pods.list(&lp)).await?;
+
+    let targets: Vec<(String, String)> = pod_list
+        .items
+        .iter()
+        .flat_map(|pod| {
+            pod.spec
+                .iter()
+                .flat_map(|spec| &spec.containers)
+                .filter(|c| sidecars || !is_sidecar(&c.name, c.image.as_deref().unwrap_or("")))
+                .map(|c| (pod.name_any(), c.name.clone()))
+        })
+        .collect();
+    let searches = stream::iter(targets).map(|(pod, container)| {
+        let pods = pods.clone();
+        async move {
+            let log_params = LogParams {
+                container: Some(container.clone()),
+                timestamps: true,
+                tail_lines: Some(tail_lines),
+                ..Default::default()
+            };
+            match with_timeout("fetch logs", pods.logs(&pod, &log_params)).await {
+                Ok(logs) => {
+                    let mut recs = to_log_recs(&logs);
+                    recs.reverse();
+                    Some(ReplicaLogs::search(pod, container, recs, pattern))
+                }
+                Err(e) => {
+                    debug!("can not search logs of {pod}/{container}: {e}");
+                    None
+                }
+            }
+        }
+    });
+    let searched = searches
+        .buffer_unordered(LOG_SEARCH_CONCURRENCY)
+        .collect()
+        .await;
+    Ok(with_matches(searched))
+}
+
+/// one raw log line as written by the container, escapes and all
+#[must_use]
+pub fn parse_log_line(line: &str) -> LogRec {
+    // logs are requested with timestamps, the kubelet prefixes an RFC 3339 time
+    let (timestamp, line) = match line.split_once(' ') {
+        Some((ts, rest)) => DateTime::parse_from_rfc3339(ts)
+            .map_or((None, line), |ts| (Some(ts.with_timezone(&Utc)), rest)),
+        None => (None, line),
+    };
+    let (message, colors) = sanitize(line);
+    LogRec {
+        datetime: timestamp.map_or_else(String::new, |ts| {
+            TimeZoneSetting::Utc.format(&ts, LOG_TIME_FORMAT)
+        }),
+        timestamp,
+        level: String::new(),
+        message,
+        colors,
+        event_type: None,
+    }
+}
+
+fn to_log_recs(logs: &str) -> Vec<LogRec> {
+    logs.lines().map(parse_log_line).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(message: &str) -> LogRec {
+        LogRec {
+            message: message.to_string(),
+            ..LogRec::default()
+        }
+    }
+
+    #[test]
+    fn test_earlier_instances() {
+        let mut earlier = EarlierInstances::default();
+        assert!(!earlier.restarted(None));
+        assert!(!earlier.restarted(Some(2)));
+        assert!(!earlier.restarted(None));
+        assert!(earlier.restarted(Some(3)));
+        earlier.push(vec![line("crashing")]);
+        let messages: Vec<String> = earlier
+            .below(vec![line("starting")])
+            .into_iter()
+            .map(|rec| rec.message)
+            .collect();
+        assert_eq!(messages, vec!["starting", RESTART_DIVIDER, "crashing"]);
+    }
+
+    #[test]
+    fn test_replica_log_matches() {
+        let pattern = Regex::new("(?i)timeout").unwrap();
+        let search = |pod: &str, container: &str, messages: &[&str]| {
+            Some(ReplicaLogs::search(
+                pod.to_string(),
+                container.to_string(),
+                messages.iter().map(|m| line(m)).collect(),
+                &pattern,
+            ))
+        };
+        let results = with_matches(vec![
+            search("web-2", "app", &["ok", "Timeout calling db"]),
+            None,
+            search(
+                "web-1",
+                "proxy",
+                &["upstream timeout", "ok", "timeout again"],
+            ),
+            search("web-1", "app", &["all good"]),
+            search("web-1", "agent", &["TIMEOUT"]),
+        ]);
+        let found: Vec<(&str, &str, &[usize])> = results
+            .iter()
+            .map(|r| (r.pod.as_str(), r.container.as_str(), r.matches.as_slice()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("web-1", "agent", &[0][..]),
+                ("web-1", "proxy", &[0, 2][..]),
+                ("web-2", "app", &[1][..]),
+            ]
+        );
+    }
+}
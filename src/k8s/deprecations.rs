@@ -0,0 +1,306 @@
+//!Compare the API versions workloads were written with against the cluster
+//!version and flag the ones that are deprecated or already removed.
+//!
+use crate::error::Result;
+use crate::k8s::paging;
+use crate::k8s::timeout::with_timeout;
+use k8s_openapi::api::apps::v1::{Deployment, ReplicaSet};
+use k8s_openapi::api::autoscaling::v2::HorizontalPodAutoscaler;
+use k8s_openapi::api::batch::v1::CronJob;
+use k8s_openapi::api::networking::v1::Ingress;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use k8s_openapi::serde::de::DeserializeOwned;
+use k8s_openapi::NamespaceResourceScope;
+use kube::api::ListParams;
+use kube::{Api, Client, Resource, ResourceExt};
+use std::collections::HashMap;
+use std::fmt::{Debug, Write};
+use std::sync::{Mutex, OnceLock};
+
+use super::client::new;
+use super::client_manager::current_context;
+
+/// minor versions of the clusters already asked, by context
+static CLUSTER_MINORS: OnceLock<Mutex<HashMap<String, Option<u32>>>> = OnceLock::new();
+
+/// (apiVersion, kind, deprecated in minor, removed in minor, replacement)
+const DEPRECATED_APIS: [(&str, &str, u32, u32, &str); 16] = [
+    ("extensions/v1beta1", "Deployment", 9, 16, "apps/v1"),
+    ("apps/v1beta1", "Deployment", 9, 16, "apps/v1"),
+    ("apps/v1beta2", "Deployment", 9, 16, "apps/v1"),
+    ("extensions/v1beta1", "ReplicaSet", 9, 16, "apps/v1"),
+    ("apps/v1beta1", "ReplicaSet", 9, 16, "apps/v1"),
+    ("apps/v1beta2", "ReplicaSet", 9, 16, "apps/v1"),
+    (
+        "extensions/v1beta1",
+        "Ingress",
+        14,
+        22,
+        "networking.k8s.io/v1",
+    ),
+    (
+        "networking.k8s.io/v1beta1",
+        "Ingress",
+        19,
+        22,
+        "networking.k8s.io/v1",
+    ),
+    (
+        "extensions/v1beta1",
+        "PodSecurityPolicy",
+        10,
+        16,
+        "Pod Security Admission",
+    ),
+    (
+        "policy/v1beta1",
+        "PodSecurityPolicy",
+        21,
+        25,
+        "Pod Security Admission",
+    ),
+    ("policy/v1beta1", "PodDisruptionBudget", 21, 25, "policy/v1"),
+    ("batch/v1beta1", "CronJob", 21, 25, "batch/v1"),
+    (
+        "autoscaling/v2beta1",
+        "HorizontalPodAutoscaler",
+        22,
+        25,
+        "autoscaling/v2",
+    ),
+    (
+        "autoscaling/v2beta2",
+        "HorizontalPodAutoscaler",
+        23,
+        26,
+        "autoscaling/v2",
+    ),
+    (
+        "flowcontrol.apiserver.k8s.io/v1beta2",
+        "FlowSchema",
+        26,
+        29,
+        "flowcontrol.apiserver.k8s.io/v1",
+    ),
+    (
+        "flowcontrol.apiserver.k8s.io/v1beta3",
+        "FlowSchema",
+        29,
+        32,
+        "flowcontrol.apiserver.k8s.io/v1",
+    ),
+];
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Deprecation {
+    pub kind: String,
+    pub api_version: String,
+    pub deprecated_in: u32,
+    pub removed_in: u32,
+    pub replacement: String,
+    pub removed: bool,
+}
+
+impl Deprecation {
+    /// short marker for table columns
+    #[must_use]
+    pub fn badge(&self) -> String {
+        if self.removed {
+            format!("[removed {}]", self.api_version)
+        } else {
+            format!("[deprecated {}]", self.api_version)
+        }
+    }
+}
+
+impl std::fmt::Display for Deprecation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = if self.removed {
+            "removed"
+        } else {
+            "deprecated"
+        };
+        write!(
+            f,
+            "{} {} is {state} (deprecated 1.{}, removed 1.{}), use {}",
+            self.api_version, self.kind, self.deprecated_in, self.removed_in, self.replacement
+        )
+    }
+}
+
+/// api versions recorded by the field managers that wrote the object
+#[must_use]
+pub fn api_versions(meta: &ObjectMeta) -> Vec<String> {
+    let mut versions: Vec<String> = meta
+        .managed_fields
+        .iter()
+        .flatten()
+        .filter_map(|entry| entry.api_version.clone())
+        .collect();
+    versions.sort();
+    versions.dedup();
+    versions
+}
+
+/// Check one apiVersion/kind pair.  Without a cluster version everything
+/// deprecated is reported but nothing is marked as removed.
+#[must_use]
+pub fn check(api_version: &str, kind: &str, cluster_minor: Option<u32>) -> Option<Deprecation> {
+    DEPRECATED_APIS
+        .iter()
+        .find(|(version, k, ..)| *version == api_version && *k == kind)
+        .map(
+            |(version, k, deprecated_in, removed_in, replacement)| Deprecation {
+                kind: (*k).to_string(),
+                api_version: (*version).to_string(),
+                deprecated_in: *deprecated_in,
+                removed_in: *removed_in,
+                replacement: (*replacement).to_string(),
+                removed: cluster_minor.is_some_and(|minor| minor >= *removed_in),
+            },
+        )
+}
+
+/// Deprecations for every api version a workload has been written with.
+#[must_use]
+pub fn deprecations(meta: &ObjectMeta, kind: &str, cluster_minor: Option<u32>) -> Vec<Deprecation> {
+    api_versions(meta)
+        .iter()
+        .filter_map(|version| check(version, kind, cluster_minor))
+        .collect()
+}
+
+/// "29+" style minor versions are reported by some managed clusters
+fn parse_minor(minor: &str) -> Option<u32> {
+    minor
+        .trim_end_matches(|c: char| !c.is_ascii_digit())
+        .parse()
+        .ok()
+}
+
+/// # Errors
+///
+/// Will return `Err` if data can not be retrieved from k8s cluster api
+pub async fn cluster_minor_version(client: &Client) -> Result<Option<u32>> {
+    let info = with_timeout("get server version", client.apiserver_version()).await?;
+    Ok(parse_minor(&info.minor))
+}
+
+/// `cluster_minor_version` of the current context, asking the apiserver only
+/// the first time; a failed ask is tried again on the next call.
+pub async fn cached_cluster_minor_version(client: &Client) -> Option<u32> {
+    let context = current_context();
+    let minors = CLUSTER_MINORS.get_or_init(Mutex::default);
+    if let Some(minor) = minors
+        .lock()
+        .ok()
+        .and_then(|minors| minors.get(&context).copied())
+    {
+        return minor;
+    }
+    let minor = cluster_minor_version(client).await.ok()?;
+    if let Ok(mut minors) = minors.lock() {
+        minors.insert(context, minor);
+    }
+    minor
+}
+
+async fn scan<K>(
+    client: &Client,
+    namespace: &str,
+    cluster_minor: Option<u32>,
+) -> Result<Vec<(String, Deprecation)>>
+where
+    K: Resource<Scope = NamespaceResourceScope> + Clone + DeserializeOwned + Debug,
+    K::DynamicType: Default,
+{
+    let kind = K::kind(&K::DynamicType::default()).to_string();
+    let api: Api<K> = Api::namespaced(client.clone(), namespace);
+    let list = paging::list(&api, ListParams::default(), "list workloads").await?;
+    Ok(list
+        .iter()
+        .flat_map(|obj| {
+            let name = format!("{kind}/{}", obj.name_any());
+            deprecations(obj.meta(), &kind, cluster_minor)
+                .into_iter()
+                .map(move |d| (name.clone(), d))
+        })
+        .collect())
+}
+
+/// # Errors
+///
+/// Will return `Err` if data can not be retrieved from k8s cluster api
+pub async fn report(namespace: &str) -> Result<String> {
+    let client = new(None).await?;
+    // a cluster that hides its version is still scanned
+    let cluster_minor = cached_cluster_minor_version(&client).await;
+
+    // one kind that can not be listed does not hide the others
+    let scans = [
+        (
+            "Deployment",
+            scan::<Deployment>(&client, namespace, cluster_minor).await,
+        ),
+        (
+            "ReplicaSet",
+            scan::<ReplicaSet>(&client, namespace, cluster_minor).await,
+        ),
+        (
+            "Ingress",
+            scan::<Ingress>(&client, namespace, cluster_minor).await,
+        ),
+        (
+            "CronJob",
+            scan::<CronJob>(&client, namespace, cluster_minor).await,
+        ),
+        (
+            "HorizontalPodAutoscaler",
+            scan::<HorizontalPodAutoscaler>(&client, namespace, cluster_minor).await,
+        ),
+    ];
+    let mut found = Vec::new();
+    let mut failed = Vec::new();
+    for (kind, scanned) in scans {
+        match scanned {
+            Ok(deprecations) => found.extend(deprecations),
+            Err(e) => failed.push((kind, e)),
+        }
+    }
+
+    let version = cluster_minor.map_or_else(|| "unknown".to_string(), |minor| format!("1.{minor}"));
+    let mut out = format!(
+        "Cluster version: {version}\nDeprecated APIs: {}",
+        found.len()
+    );
+    for (name, deprecation) in found {
+        let _ = write!(out, "\n  {name}: {deprecation}");
+    }
+    for (kind, e) in failed {
+        let _ = write!(out, "\n  {kind}: not scanned: {e}");
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_marks_removed_by_cluster_version() {
+        let d = check("networking.k8s.io/v1beta1", "Ingress", Some(21)).unwrap();
+        assert!(!d.removed);
+        assert_eq!(d.badge(), "[deprecated networking.k8s.io/v1beta1]");
+        let d = check("networking.k8s.io/v1beta1", "Ingress", Some(29)).unwrap();
+        assert!(d.removed);
+        assert!(check("networking.k8s.io/v1", "Ingress", Some(29)).is_none());
+        assert!(check("apps/v1beta2", "Ingress", Some(29)).is_none());
+    }
+
+    #[test]
+    fn test_parse_minor() {
+        assert_eq!(parse_minor("29"), Some(29));
+        assert_eq!(parse_minor("29+"), Some(29));
+        assert_eq!(parse_minor(""), None);
+    }
+}
@@ -0,0 +1,79 @@
+//!Custom resources k8s-openapi has no types for, ie: Gateway API objects,
+//!cert-manager Certificates and external-dns DNSEndpoints.  They are read
+//!as dynamic objects and the fields navipod shows are decoded into small
+//!serde structs, a cluster without the CRD simply has none of them.
+//!
+use crate::error::{Error, Result};
+use crate::k8s::timeout::with_timeout;
+use k8s_openapi::serde_json::{self, Value};
+use kube::api::{ApiResource, DynamicObject, GroupVersionKind, ListParams};
+use kube::{Api, Client};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+/// the status condition every operator's CRDs share
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Condition {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub status: String,
+    pub reason: String,
+    pub message: String,
+}
+
+/// `group/version` `kind`, ie: `gateway.networking.k8s.io/v1` `Gateway`
+#[derive(Clone, Copy, Debug)]
+pub struct Crd {
+    pub group: &'static str,
+    pub version: &'static str,
+    pub kind: &'static str,
+}
+
+impl Crd {
+    fn resource(self) -> ApiResource {
+        ApiResource::from_gvk(&GroupVersionKind::gvk(self.group, self.version, self.kind))
+    }
+
+    /// the objects in `namespace`, the client's when `None`
+    #[must_use]
+    pub fn api(self, client: &Client, namespace: Option<&str>) -> Api<DynamicObject> {
+        let resource = self.resource();
+        match namespace {
+            Some(namespace) => Api::namespaced_with(client.clone(), namespace, &resource),
+            None => Api::default_namespaced_with(client.clone(), &resource),
+        }
+    }
+
+    /// The objects in `namespace`, none when the cluster does not have the
+    /// CRD.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if data can not be retrieved from k8s cluster api
+    pub async fn list(
+        self,
+        client: &Client,
+        namespace: Option<&str>,
+    ) -> Result<Vec<DynamicObject>> {
+        let api = self.api(client, namespace);
+        let operation = format!("list {}", self.kind);
+        match with_timeout(&operation, api.list(&ListParams::default())).await {
+            Ok(list) => Ok(list.items),
+            Err(Error::NotFound(_)) => Ok(vec![]),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// the top level field `name`, ie: `spec`, its default when missing or of
+/// another shape
+#[must_use]
+pub fn field<T: DeserializeOwned + Default>(object: &DynamicObject, name: &str) -> T {
+    object
+        .data
+        .get(name)
+        .cloned()
+        .and_then(|value: Value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
@@ -1,5 +1,10 @@
 use crate::error::Result as NvResult;
-use crate::tui::data::ResourceEvent;
+use crate::k8s::client_manager::get_client;
+use crate::k8s::paging;
+use crate::k8s::retry::{with_retry, RetryPolicy};
+use crate::k8s::timeout::with_timeout;
+use crate::tui::data::{LogRec, ResourceEvent};
+use crate::tui::utils::time::{timezone, TimeZoneSetting, EVENT_TIME_FORMAT, LOG_TIME_FORMAT};
 use k8s_openapi::api::core::v1::Event;
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
 use kube::api::ListParams;
@@ -31,6 +36,12 @@ fn convert_event_to_resource_event(event: &Event, rs_name: &str) -> ResourceEven
     let object = event.involved_object.name.clone().unwrap_or_default();
     let type_ = event.type_.clone().unwrap_or_default();
     let age = calculate_event_age(event.last_timestamp.as_ref());
+    let time = event
+        .last_timestamp
+        .as_ref()
+        .map_or_else(String::new, |time| {
+            timezone().format(&time.0, EVENT_TIME_FORMAT)
+        });
 
     ResourceEvent {
         resource_name: rs_name.to_string(),
@@ -39,16 +50,18 @@ fn convert_event_to_resource_event(event: &Event, rs_name: &str) -> ResourceEven
         reason,
         type_,
         age,
+        time,
     }
 }
 
 /// # Errors
 ///
 /// Will return `Err` if events cannot be retrieved from k8s cluster api
-pub async fn list_k8sevents(client: Client) -> Result<Vec<Event>, kube::Error> {
+pub async fn list_k8sevents(client: Client) -> NvResult<Vec<Event>> {
     let lp = ListParams::default();
 
-    let mut unfiltered_events: Vec<Event> = Api::default_namespaced(client).list(&lp).await?.items;
+    let events: Api<Event> = Api::default_namespaced(client);
+    let mut unfiltered_events: Vec<Event> = paging::list(&events, lp, "list events").await?;
 
     unfiltered_events.sort_by(|a, b| {
         b.last_timestamp
@@ -68,13 +81,14 @@ pub async fn list_k8sevents(client: Client) -> Result<Vec<Event>, kube::Error> {
 ///
 /// Will return `Err` if events cannot be retrieved from k8s cluster api
 pub async fn list_all() -> NvResult<Vec<ResourceEvent>> {
+    with_retry(&RetryPolicy::default(), fetch_all).await
+}
+
+async fn fetch_all() -> NvResult<Vec<ResourceEvent>> {
     let lp = ListParams::default();
 
-    let mut unfiltered_events: Vec<Event> =
-        Api::default_namespaced(super::client::new(None).await?)
-            .list(&lp)
-            .await?
-            .items;
+    let events: Api<Event> = Api::default_namespaced(get_client().await?);
+    let mut unfiltered_events: Vec<Event> = paging::list(&events, lp, "list events").await?;
 
     unfiltered_events.sort_by(|a, b| {
         b.last_timestamp
@@ -140,6 +154,73 @@ pub async fn list_events_for_resource(
     Ok(resource_events)
 }
 
+/// Events about one pod as log records, so they can be read in line with
+/// its logs.  `namespace` defaults to the client's namespace.
+///
+/// # Errors
+///
+/// Will return `Err` if events cannot be retrieved from k8s cluster api
+pub async fn pod_event_log_recs(namespace: Option<&str>, pod_name: &str) -> NvResult<Vec<LogRec>> {
+    let events = pod_events(namespace, pod_name).await?;
+    Ok(events.iter().filter_map(event_to_log_rec).collect())
+}
+
+/// # Errors
+///
+/// Will return `Err` if events cannot be retrieved from k8s cluster api
+pub async fn pod_events(namespace: Option<&str>, pod_name: &str) -> NvResult<Vec<Event>> {
+    let client = get_client().await?;
+    let events: Api<Event> = match namespace {
+        Some(ns) => Api::namespaced(client, ns),
+        None => Api::default_namespaced(client),
+    };
+    let lp = ListParams::default().fields(&format!("involvedObject.name={pod_name}"));
+    Ok(with_timeout("list pod events", events.list(&lp))
+        .await?
+        .items)
+}
+
+fn event_to_log_rec(event: &Event) -> Option<LogRec> {
+    let timestamp = event
+        .last_timestamp
+        .as_ref()
+        .map(|t| t.0)
+        .or_else(|| event.event_time.as_ref().map(|t| t.0))
+        .or_else(|| event.first_timestamp.as_ref().map(|t| t.0))?;
+    let count = event.count.filter(|count| *count > 1);
+    Some(LogRec {
+        datetime: TimeZoneSetting::Utc.format(&timestamp, LOG_TIME_FORMAT),
+        timestamp: Some(timestamp),
+        level: event.reason.clone().unwrap_or_default(),
+        message: format!(
+            "{}{}",
+            event.message.as_deref().unwrap_or_default().trim(),
+            count.map_or_else(String::new, |count| format!(" (x{count})"))
+        ),
+        colors: vec![],
+        event_type: Some(event.type_.clone().unwrap_or_else(|| "Normal".to_string())),
+    })
+}
+
+/// Merge events into logs that are newest first, an event goes above the
+/// log lines written before it.  Logs without timestamps keep their place.
+#[must_use]
+pub fn interleave_events(logs: Vec<LogRec>, mut events: Vec<LogRec>) -> Vec<LogRec> {
+    events.sort_by_key(|e| std::cmp::Reverse(e.timestamp));
+    let mut events = events.into_iter().peekable();
+    let mut merged = Vec::with_capacity(logs.len() + events.len());
+    for log in logs {
+        if let Some(at) = log.timestamp {
+            while let Some(event) = events.next_if(|e| e.timestamp >= Some(at)) {
+                merged.push(event);
+            }
+        }
+        merged.push(log);
+    }
+    merged.extend(events);
+    merged
+}
+
 #[must_use]
 pub fn format_duration(duration: Duration) -> String {
     if duration.num_days() > 0 {
@@ -152,3 +233,47 @@ pub fn format_duration(duration: Duration) -> String {
         format!("{}s", duration.num_seconds())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::k8s::containers::parse_log_line;
+    use k8s_openapi::serde_json::{from_value, json};
+
+    #[test]
+    fn test_interleave_events() {
+        let logs: Vec<LogRec> = [
+            "2024-03-01T10:00:03Z started",
+            "2024-03-01T10:00:01Z loading",
+            "no timestamp",
+        ]
+        .into_iter()
+        .map(parse_log_line)
+        .collect();
+        let event: Event = from_value(json!({
+            "metadata": {"name": "web.1"},
+            "involvedObject": {"name": "web"},
+            "reason": "Pulled",
+            "message": "Successfully pulled image",
+            "type": "Normal",
+            "count": 2,
+            "lastTimestamp": "2024-03-01T10:00:02Z"
+        }))
+        .unwrap();
+        let events = vec![event_to_log_rec(&event).unwrap()];
+
+        let merged = interleave_events(logs, events);
+        let messages: Vec<&str> = merged.iter().map(|r| r.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "started",
+                "Successfully pulled image (x2)",
+                "loading",
+                "no timestamp"
+            ]
+        );
+        assert_eq!(merged[1].level, "Pulled");
+        assert_eq!(merged[1].event_type.as_deref(), Some("Normal"));
+    }
+}
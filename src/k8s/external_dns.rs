@@ -0,0 +1,151 @@
+//!external-dns ownership of ingress hosts: the records DNSEndpoint objects
+//!ask for, with the object owning each, and the hostname and target
+//!annotations external-dns reads from an ingress.
+//!
+use crate::error::Result;
+use crate::k8s::dynamic::{field, Crd};
+use k8s_openapi::api::networking::v1::Ingress;
+use kube::api::DynamicObject;
+use kube::Client;
+use serde::Deserialize;
+
+const DNS_ENDPOINT: Crd = Crd {
+    group: "externaldns.k8s.io",
+    version: "v1alpha1",
+    kind: "DNSEndpoint",
+};
+
+const HOSTNAME: &str = "external-dns.alpha.kubernetes.io/hostname";
+const TARGET: &str = "external-dns.alpha.kubernetes.io/target";
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+struct Endpoint {
+    dns_name: String,
+    record_type: String,
+    targets: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+struct DnsEndpointSpec {
+    endpoints: Vec<Endpoint>,
+}
+
+/// one record a DNSEndpoint asks external-dns for
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DnsRecord {
+    pub dns_name: String,
+    pub record_type: String,
+    pub targets: Vec<String>,
+    /// `DNSEndpoint shop`, with the object that created it if any
+    pub owner: String,
+}
+
+fn to_records(object: &DynamicObject) -> Vec<DnsRecord> {
+    let name = object.metadata.name.clone().unwrap_or_default();
+    let owner = object
+        .metadata
+        .owner_references
+        .iter()
+        .flatten()
+        .next()
+        .map_or_else(
+            || format!("DNSEndpoint {name}"),
+            |owner| format!("DNSEndpoint {name} of {} {}", owner.kind, owner.name),
+        );
+    let spec: DnsEndpointSpec = field(object, "spec");
+    spec.endpoints
+        .into_iter()
+        .map(|endpoint| DnsRecord {
+            dns_name: endpoint.dns_name.trim_end_matches('.').to_string(),
+            record_type: endpoint.record_type,
+            targets: endpoint.targets,
+            owner: owner.clone(),
+        })
+        .collect()
+}
+
+/// # Errors
+///
+/// Will return `Err` if data can not be retrieved from k8s cluster api
+pub async fn list_records(client: &Client, namespace: &str) -> Result<Vec<DnsRecord>> {
+    Ok(DNS_ENDPOINT
+        .list(client, Some(namespace))
+        .await?
+        .iter()
+        .flat_map(to_records)
+        .collect())
+}
+
+/// Who publishes `host`: a DNSEndpoint record for it, else the ingress's
+/// hostname annotation, `None` when neither mentions it.
+#[must_use]
+pub fn ownership(records: &[DnsRecord], ingress: &Ingress, host: &str) -> Option<String> {
+    if let Some(record) = records
+        .iter()
+        .find(|record| record.dns_name.eq_ignore_ascii_case(host))
+    {
+        return Some(format!(
+            "{} {}, {}",
+            record.record_type,
+            record.targets.join(" "),
+            record.owner
+        ));
+    }
+    let annotations = ingress.metadata.annotations.as_ref()?;
+    let named = annotations.get(HOSTNAME).is_some_and(|hostnames| {
+        hostnames
+            .split(',')
+            .any(|hostname| hostname.trim().trim_end_matches('.') == host)
+    });
+    named.then(|| {
+        let target = annotations
+            .get(TARGET)
+            .map_or_else(String::new, |target| format!(" to {target}"));
+        format!(
+            "published{target} by the hostname annotation of ingress {}",
+            ingress.metadata.name.as_deref().unwrap_or_default()
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::serde_json::{from_value, json};
+
+    #[test]
+    fn test_ownership() {
+        let object: DynamicObject = from_value(json!({
+            "apiVersion": "externaldns.k8s.io/v1alpha1",
+            "kind": "DNSEndpoint",
+            "metadata": {
+                "name": "shop",
+                "ownerReferences": [{"apiVersion": "v1", "kind": "Service", "name": "shop-lb", "uid": "1"}]
+            },
+            "spec": {"endpoints": [{"dnsName": "shop.acme.io.", "recordType": "A", "targets": ["10.0.0.7"]}]}
+        }))
+        .unwrap();
+        let records = to_records(&object);
+        let ingress: Ingress = from_value(json!({
+            "metadata": {
+                "name": "web",
+                "annotations": {
+                    "external-dns.alpha.kubernetes.io/hostname": "api.acme.io, www.acme.io",
+                    "external-dns.alpha.kubernetes.io/target": "lb.acme.io"
+                }
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            ownership(&records, &ingress, "shop.acme.io").as_deref(),
+            Some("A 10.0.0.7, DNSEndpoint shop of Service shop-lb")
+        );
+        assert_eq!(
+            ownership(&records, &ingress, "www.acme.io").as_deref(),
+            Some("published to lb.acme.io by the hostname annotation of ingress web")
+        );
+        assert_eq!(ownership(&records, &ingress, "other.acme.io"), None);
+    }
+}
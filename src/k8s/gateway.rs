@@ -0,0 +1,481 @@
+//!Gateway API, the successor of Ingress: Gateways with their listeners and
+//!the HTTPRoutes attached to them.  k8s-openapi has no types for the CRDs,
+//!objects are read as dynamic objects and only the fields navipod shows are
+//!decoded.  A cluster without the CRDs has no gateways rather than an error.
+//!
+use crate::error::Result;
+use crate::k8s::dynamic::{field, Condition, Crd};
+use crate::k8s::ingress_details::covers;
+use crate::k8s::pod_ingress::Route;
+use crate::k8s::timeout::with_timeout;
+use crate::tui::data::GatewayRow;
+use kube::api::DynamicObject;
+use kube::Client;
+use serde::Deserialize;
+
+pub const GROUP: &str = "gateway.networking.k8s.io";
+
+const GATEWAY: Crd = Crd {
+    group: GROUP,
+    version: "v1",
+    kind: "Gateway",
+};
+const HTTP_ROUTE: Crd = Crd {
+    group: GROUP,
+    version: "v1",
+    kind: "HTTPRoute",
+};
+
+/// conditions that are bad when not `True`
+const POSITIVE: [&str; 4] = ["Accepted", "Programmed", "ResolvedRefs", "Ready"];
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ParentRef {
+    pub name: String,
+    pub namespace: Option<String>,
+    /// the listener attached to, all of them when unset
+    pub section_name: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct BackendRef {
+    pub name: String,
+    /// `Service` when unset
+    pub kind: Option<String>,
+    pub port: Option<i32>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PathMatch {
+    pub value: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RouteMatch {
+    pub path: Option<PathMatch>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RouteRule {
+    pub matches: Vec<RouteMatch>,
+    pub backend_refs: Vec<BackendRef>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HttpRouteSpec {
+    pub parent_refs: Vec<ParentRef>,
+    pub hostnames: Vec<String>,
+    pub rules: Vec<RouteRule>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RouteParentStatus {
+    pub parent_ref: ParentRef,
+    pub conditions: Vec<Condition>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HttpRouteStatus {
+    pub parents: Vec<RouteParentStatus>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HttpRoute {
+    pub name: String,
+    pub namespace: String,
+    pub spec: HttpRouteSpec,
+    pub status: HttpRouteStatus,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Listener {
+    pub name: String,
+    pub hostname: Option<String>,
+    pub port: i32,
+    pub protocol: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ListenerStatus {
+    pub name: String,
+    pub attached_routes: i32,
+    pub conditions: Vec<Condition>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GatewaySpec {
+    pub gateway_class_name: String,
+    pub listeners: Vec<Listener>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GatewayAddress {
+    pub value: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GatewayStatus {
+    pub addresses: Vec<GatewayAddress>,
+    pub listeners: Vec<ListenerStatus>,
+    pub conditions: Vec<Condition>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Gateway {
+    pub name: String,
+    pub namespace: String,
+    pub spec: GatewaySpec,
+    pub status: GatewayStatus,
+}
+
+impl Gateway {
+    /// the listeners `parent` attaches to
+    fn listeners_for<'a>(&'a self, parent: &'a ParentRef) -> impl Iterator<Item = &'a Listener> {
+        self.spec.listeners.iter().filter(move |listener| {
+            parent
+                .section_name
+                .as_ref()
+                .is_none_or(|section| *section == listener.name)
+        })
+    }
+}
+
+fn to_route(object: &DynamicObject) -> HttpRoute {
+    HttpRoute {
+        name: object.metadata.name.clone().unwrap_or_default(),
+        namespace: object.metadata.namespace.clone().unwrap_or_default(),
+        spec: field(object, "spec"),
+        status: field(object, "status"),
+    }
+}
+
+fn to_gateway(object: &DynamicObject) -> Gateway {
+    Gateway {
+        name: object.metadata.name.clone().unwrap_or_default(),
+        namespace: object.metadata.namespace.clone().unwrap_or_default(),
+        spec: field(object, "spec"),
+        status: field(object, "status"),
+    }
+}
+
+/// The namespace's HTTPRoutes and Gateways, with the Gateways in other
+/// namespaces its routes attach to.  `namespace` defaults to the client's.
+///
+/// # Errors
+///
+/// Will return `Err` if data can not be retrieved from k8s cluster api
+pub async fn fetch(
+    client: &Client,
+    namespace: Option<&str>,
+) -> Result<(Vec<Gateway>, Vec<HttpRoute>)> {
+    let routes: Vec<HttpRoute> = HTTP_ROUTE
+        .list(client, namespace)
+        .await?
+        .iter()
+        .map(to_route)
+        .collect();
+    let mut gateways: Vec<Gateway> = GATEWAY
+        .list(client, namespace)
+        .await?
+        .iter()
+        .map(to_gateway)
+        .collect();
+    for route in &routes {
+        for parent in &route.spec.parent_refs {
+            let parent_namespace = parent.namespace.as_deref().unwrap_or(&route.namespace);
+            if gateways
+                .iter()
+                .any(|g| g.name == parent.name && g.namespace == parent_namespace)
+            {
+                continue;
+            }
+            let api = GATEWAY.api(client, Some(parent_namespace));
+            // a parent in a namespace we can not read is left out
+            if let Ok(Some(gateway)) = with_timeout("get gateway", api.get_opt(&parent.name)).await
+            {
+                gateways.push(to_gateway(&gateway));
+            }
+        }
+    }
+    Ok((gateways, routes))
+}
+
+/// `ok`, or each condition that is not as it should be
+#[must_use]
+pub fn condition_summary(conditions: &[Condition]) -> String {
+    if conditions.is_empty() {
+        return "no status".to_string();
+    }
+    let problems: Vec<String> = conditions
+        .iter()
+        .filter(|c| {
+            (POSITIVE.contains(&c.type_.as_str()) && c.status != "True")
+                || (c.type_ == "Conflicted" && c.status == "True")
+        })
+        .map(|c| format!("{} {}: {}", c.type_, c.status.to_lowercase(), c.reason))
+        .collect();
+    if problems.is_empty() {
+        "ok".to_string()
+    } else {
+        problems.join(", ")
+    }
+}
+
+fn parent_name(parent: &ParentRef, route_namespace: &str) -> String {
+    let namespace = parent
+        .namespace
+        .as_deref()
+        .filter(|namespace| *namespace != route_namespace)
+        .map_or_else(String::new, |namespace| format!("{namespace}/"));
+    let section = parent
+        .section_name
+        .as_deref()
+        .map_or_else(String::new, |section| format!("/{section}"));
+    format!("{namespace}{}{section}", parent.name)
+}
+
+/// the view's rows: each gateway, its listeners, then the routes
+#[must_use]
+pub fn rows(gateways: &[Gateway], routes: &[HttpRoute]) -> Vec<GatewayRow> {
+    let mut rows = vec![];
+    for gateway in gateways {
+        rows.push(GatewayRow {
+            kind: "Gateway".to_string(),
+            name: gateway.name.clone(),
+            hosts: gateway
+                .status
+                .addresses
+                .iter()
+                .map(|address| address.value.clone())
+                .collect::<Vec<_>>()
+                .join(", "),
+            attached: format!("class {}", gateway.spec.gateway_class_name),
+            backends: String::new(),
+            status: condition_summary(&gateway.status.conditions),
+        });
+        for listener in &gateway.spec.listeners {
+            let status = gateway
+                .status
+                .listeners
+                .iter()
+                .find(|status| status.name == listener.name);
+            rows.push(GatewayRow {
+                kind: "Listener".to_string(),
+                name: format!("{}/{}", gateway.name, listener.name),
+                hosts: listener.hostname.clone().unwrap_or_else(|| "*".to_string()),
+                attached: format!(
+                    "{} {}, {} routes",
+                    listener.protocol,
+                    listener.port,
+                    status.map_or(0, |status| status.attached_routes)
+                ),
+                backends: String::new(),
+                status: status.map_or_else(
+                    || "no status".to_string(),
+                    |status| condition_summary(&status.conditions),
+                ),
+            });
+        }
+    }
+    for route in routes {
+        let statuses: Vec<String> = route
+            .status
+            .parents
+            .iter()
+            .map(|parent| {
+                format!(
+                    "{} {}",
+                    parent_name(&parent.parent_ref, &route.namespace),
+                    condition_summary(&parent.conditions)
+                )
+            })
+            .collect();
+        rows.push(GatewayRow {
+            kind: "HTTPRoute".to_string(),
+            name: route.name.clone(),
+            hosts: if route.spec.hostnames.is_empty() {
+                "*".to_string()
+            } else {
+                route.spec.hostnames.join(", ")
+            },
+            attached: route
+                .spec
+                .parent_refs
+                .iter()
+                .map(|parent| parent_name(parent, &route.namespace))
+                .collect::<Vec<_>>()
+                .join(", "),
+            backends: route
+                .spec
+                .rules
+                .iter()
+                .flat_map(|rule| &rule.backend_refs)
+                .map(|backend| match backend.port {
+                    Some(port) => format!("{}:{port}", backend.name),
+                    None => backend.name.clone(),
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+            status: if statuses.is_empty() {
+                "not attached".to_string()
+            } else {
+                statuses.join(", ")
+            },
+        });
+    }
+    rows
+}
+
+/// whether a parent listener of `route` terminates tls for `host`
+fn terminates_tls(route: &HttpRoute, gateways: &[Gateway], host: Option<&str>) -> bool {
+    route.spec.parent_refs.iter().any(|parent| {
+        let parent_namespace = parent.namespace.as_deref().unwrap_or(&route.namespace);
+        gateways
+            .iter()
+            .filter(|g| g.name == parent.name && g.namespace == parent_namespace)
+            .flat_map(|g| g.listeners_for(parent))
+            .any(|listener| {
+                matches!(listener.protocol.as_str(), "HTTPS" | "TLS")
+                    && match (&listener.hostname, host) {
+                        (None, _) => true,
+                        (Some(hostname), Some(host)) => {
+                            covers(std::slice::from_ref(hostname), host)
+                        }
+                        (Some(_), None) => false,
+                    }
+            })
+    })
+}
+
+/// The paths of `routes` ending at one of `services`, for explain-pod.
+#[must_use]
+pub fn routes_to(routes: &[HttpRoute], gateways: &[Gateway], services: &[String]) -> Vec<Route> {
+    let mut found = vec![];
+    for route in routes {
+        let hosts: Vec<Option<&str>> = if route.spec.hostnames.is_empty() {
+            vec![None]
+        } else {
+            route
+                .spec
+                .hostnames
+                .iter()
+                .map(|h| Some(h.as_str()))
+                .collect()
+        };
+        for rule in &route.spec.rules {
+            let paths: Vec<String> = rule
+                .matches
+                .iter()
+                .filter_map(|m| m.path.as_ref().and_then(|path| path.value.clone()))
+                .collect();
+            let paths = if paths.is_empty() {
+                vec!["/".to_string()]
+            } else {
+                paths
+            };
+            for backend in &rule.backend_refs {
+                let is_service = backend.kind.as_deref().is_none_or(|kind| kind == "Service");
+                if !is_service || !services.contains(&backend.name) {
+                    continue;
+                }
+                for host in &hosts {
+                    for path in &paths {
+                        found.push(Route {
+                            kind: "HTTPRoute".to_string(),
+                            ingress: route.name.clone(),
+                            host: host.map(ToString::to_string),
+                            path: path.clone(),
+                            service: backend.name.clone(),
+                            port: backend.port,
+                            tls: terminates_tls(route, gateways, *host),
+                            cert: None,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::serde_json::{from_value, json, Value};
+
+    fn dynamic(value: Value) -> DynamicObject {
+        from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_gateway_rows_and_routes() {
+        let gateway = to_gateway(&dynamic(json!({
+            "apiVersion": "gateway.networking.k8s.io/v1",
+            "kind": "Gateway",
+            "metadata": {"name": "edge", "namespace": "infra"},
+            "spec": {
+                "gatewayClassName": "istio",
+                "listeners": [
+                    {"name": "https", "hostname": "*.acme.io", "port": 443, "protocol": "HTTPS"},
+                    {"name": "http", "port": 80, "protocol": "HTTP"}
+                ]
+            },
+            "status": {
+                "addresses": [{"value": "10.0.0.7"}],
+                "conditions": [{"type": "Programmed", "status": "True", "reason": "Programmed"}],
+                "listeners": [{"name": "https", "attachedRoutes": 1, "conditions": [
+                    {"type": "ResolvedRefs", "status": "False", "reason": "InvalidCertificateRef"}
+                ]}]
+            }
+        })));
+        let route = to_route(&dynamic(json!({
+            "apiVersion": "gateway.networking.k8s.io/v1",
+            "kind": "HTTPRoute",
+            "metadata": {"name": "shop", "namespace": "web"},
+            "spec": {
+                "parentRefs": [{"name": "edge", "namespace": "infra", "sectionName": "https"}],
+                "hostnames": ["shop.acme.io"],
+                "rules": [{
+                    "matches": [{"path": {"type": "PathPrefix", "value": "/cart"}}],
+                    "backendRefs": [{"name": "cart", "port": 8080}, {"name": "other", "port": 80}]
+                }]
+            },
+            "status": {"parents": [{
+                "parentRef": {"name": "edge", "namespace": "infra", "sectionName": "https"},
+                "conditions": [{"type": "Accepted", "status": "True", "reason": "Accepted"}]
+            }]}
+        })));
+
+        let (gateways, routes) = (vec![gateway], vec![route]);
+        let rows = rows(&gateways, &routes);
+        let kinds: Vec<&str> = rows.iter().map(|row| row.kind.as_str()).collect();
+        assert_eq!(kinds, ["Gateway", "Listener", "Listener", "HTTPRoute"]);
+        assert_eq!(rows[0].status, "ok");
+        assert_eq!(rows[1].attached, "HTTPS 443, 1 routes");
+        assert_eq!(rows[1].status, "ResolvedRefs false: InvalidCertificateRef");
+        assert_eq!(rows[2].status, "no status");
+        assert_eq!(rows[3].attached, "infra/edge/https");
+        assert_eq!(rows[3].backends, "cart:8080, other:80");
+        assert_eq!(rows[3].status, "infra/edge/https ok");
+
+        let found = routes_to(&routes, &gateways, &["cart".to_string()]);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, "HTTPRoute");
+        assert_eq!(found[0].host.as_deref(), Some("shop.acme.io"));
+        assert_eq!(found[0].path, "/cart");
+        assert!(found[0].tls);
+    }
+}
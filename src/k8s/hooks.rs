@@ -0,0 +1,487 @@
+//!Automation hooks: rhai scripts from `config.yaml` run against the pod
+//!watch, ie:
+//!
+//!```yaml
+//!hooks:
+//!  - name: restart storm
+//!    when: restarts > 5 && !ready
+//!    then: |
+//!      annotate("navipod/restart-storm", "true");
+//!      webhook("https://hooks.example.com/T0", pod + " restarted " + restarts + " times");
+//!    cooldown_secs: 600
+//!```
+//!
+//!`when` sees `namespace`, `pod`, `replicaset`, `node`, `phase`, `ready`,
+//!`restarts`, `reason` (why a container waits, or "") and `labels`.  `then`
+//!sees the same and can call `annotate(key, value)` on the pod,
+//!`webhook(url, text)`, `run(script)` for an executable in the `hooks` dir
+//!of the config dir, and `log(text)`.  Those calls only queue actions that
+//!are carried out once the script is done, at most `MAX_ACTIONS_PER_MINUTE`
+//!across all hooks.  Scripts are capped at `MAX_OPERATIONS` and can not
+//!reach files, the network or the cluster any other way.
+//!
+use crate::config::{config_dir, settings, Hook};
+use crate::k8s::actions::{apply_edit, MetadataEdit, MetadataKind, Target};
+use crate::k8s::watch::WatchUpdate;
+use crate::shutdown::Pending;
+use k8s_openapi::api::core::v1::Pod;
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use serde_json::json;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::process::Stdio;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tracing::debug;
+
+const DEFAULT_COOLDOWN_SECS: u64 = 300;
+const MAX_ACTIONS_PER_MINUTE: usize = 30;
+const MAX_OPERATIONS: u64 = 10_000;
+const MAX_STRING_SIZE: usize = 10_000;
+const HOOKS_DIR: &str = "hooks";
+const ACTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// what a `then` script asked for
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    Annotate { key: String, value: String },
+    Webhook { url: String, text: String },
+    Run { script: String },
+    Log(String),
+}
+
+thread_local! {
+    /// actions of the script running on this thread
+    static QUEUED: RefCell<Vec<Action>> = const { RefCell::new(Vec::new()) };
+}
+
+fn queue(action: Action) {
+    QUEUED.with(|queued| queued.borrow_mut().push(action));
+}
+
+fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let mut engine = Engine::new();
+        engine
+            .set_max_operations(MAX_OPERATIONS)
+            .set_max_string_size(MAX_STRING_SIZE)
+            .set_max_array_size(1000)
+            .set_max_map_size(1000)
+            .set_max_expr_depths(32, 32)
+            .disable_symbol("eval");
+        engine.on_print(|text| debug!("hook: {text}"));
+        engine.register_fn("annotate", |key: &str, value: &str| {
+            queue(Action::Annotate {
+                key: key.to_string(),
+                value: value.to_string(),
+            });
+        });
+        engine.register_fn("webhook", |url: &str, text: &str| {
+            queue(Action::Webhook {
+                url: url.to_string(),
+                text: text.to_string(),
+            });
+        });
+        engine.register_fn("run", |script: &str| {
+            queue(Action::Run {
+                script: script.to_string(),
+            });
+        });
+        engine.register_fn("log", |text: &str| queue(Action::Log(text.to_string())));
+        engine
+    })
+}
+
+/// what `when` and `then` know about a pod
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PodFacts {
+    pub namespace: String,
+    pub pod: String,
+    pub replicaset: String,
+    pub node: String,
+    pub phase: String,
+    pub ready: bool,
+    pub restarts: i64,
+    pub reason: String,
+    pub labels: Vec<(String, String)>,
+}
+
+impl PodFacts {
+    #[must_use]
+    pub fn from_pod(pod: &Pod) -> Self {
+        let statuses = pod
+            .status
+            .as_ref()
+            .and_then(|status| status.container_statuses.clone())
+            .unwrap_or_default();
+        Self {
+            namespace: pod.metadata.namespace.clone().unwrap_or_default(),
+            pod: pod.metadata.name.clone().unwrap_or_default(),
+            replicaset: pod
+                .metadata
+                .owner_references
+                .iter()
+                .flatten()
+                .find(|owner| owner.kind == "ReplicaSet")
+                .map(|owner| owner.name.clone())
+                .unwrap_or_default(),
+            node: pod
+                .spec
+                .as_ref()
+                .and_then(|spec| spec.node_name.clone())
+                .unwrap_or_default(),
+            phase: pod
+                .status
+                .as_ref()
+                .and_then(|status| status.phase.clone())
+                .unwrap_or_default(),
+            ready: !statuses.is_empty() && statuses.iter().all(|status| status.ready),
+            restarts: statuses
+                .iter()
+                .map(|status| i64::from(status.restart_count))
+                .sum(),
+            reason: statuses
+                .iter()
+                .find_map(|status| status.state.as_ref()?.waiting.as_ref()?.reason.clone())
+                .unwrap_or_default(),
+            labels: pod
+                .metadata
+                .labels
+                .iter()
+                .flatten()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        }
+    }
+
+    fn scope(&self) -> Scope<'static> {
+        let mut scope = Scope::new();
+        scope.push_constant("namespace", self.namespace.clone());
+        scope.push_constant("pod", self.pod.clone());
+        scope.push_constant("replicaset", self.replicaset.clone());
+        scope.push_constant("node", self.node.clone());
+        scope.push_constant("phase", self.phase.clone());
+        scope.push_constant("ready", self.ready);
+        scope.push_constant("restarts", self.restarts);
+        scope.push_constant("reason", self.reason.clone());
+        let labels: Map = self
+            .labels
+            .iter()
+            .map(|(key, value)| (key.into(), Dynamic::from(value.clone())))
+            .collect();
+        scope.push_constant("labels", labels);
+        scope
+    }
+}
+
+#[derive(Debug)]
+struct Compiled {
+    name: String,
+    when: AST,
+    then: AST,
+    cooldown: Duration,
+}
+
+fn compile(hook: &Hook) -> Result<Compiled, String> {
+    let engine = engine();
+    Ok(Compiled {
+        name: hook.name.clone(),
+        when: engine
+            .compile_expression(&hook.when)
+            .map_err(|e| e.to_string())?,
+        then: engine.compile(&hook.then).map_err(|e| e.to_string())?,
+        cooldown: Duration::from_secs(hook.cooldown_secs.unwrap_or(DEFAULT_COOLDOWN_SECS)),
+    })
+}
+
+fn hooks() -> &'static [Compiled] {
+    static HOOKS: OnceLock<Vec<Compiled>> = OnceLock::new();
+    HOOKS.get_or_init(|| {
+        settings()
+            .hooks
+            .iter()
+            .filter_map(|hook| {
+                compile(hook)
+                    .map_err(|e| debug!("ignoring hook {}: {e}", hook.name))
+                    .ok()
+            })
+            .collect()
+    })
+}
+
+impl Compiled {
+    /// the actions of `then` when `when` holds, a failing script is logged
+    /// and does nothing
+    fn evaluate(&self, facts: &PodFacts) -> Option<Vec<Action>> {
+        let engine = engine();
+        let mut scope = facts.scope();
+        match engine.eval_ast_with_scope::<bool>(&mut scope, &self.when) {
+            Ok(true) => {}
+            Ok(false) => return None,
+            Err(e) => {
+                debug!("hook {} condition failed: {e}", self.name);
+                return None;
+            }
+        }
+        QUEUED.with(|queued| queued.borrow_mut().clear());
+        let result = engine.run_ast_with_scope(&mut scope, &self.then);
+        let actions = QUEUED.with(|queued| queued.take());
+        match result {
+            Ok(()) => Some(actions),
+            Err(e) => {
+                debug!("hook {} script failed: {e}", self.name);
+                None
+            }
+        }
+    }
+}
+
+/// cooldowns per hook and pod, and the global action budget
+#[derive(Debug, Default)]
+struct Limiter {
+    /// when a hook last fired for a pod and how long it then waits
+    fired: HashMap<(String, String), (Instant, Duration)>,
+    actions: VecDeque<Instant>,
+}
+
+impl Limiter {
+    /// `pod` is namespace/name
+    fn may_fire(&mut self, hook: &str, pod: &str, cooldown: Duration, now: Instant) -> bool {
+        // a cooldown that ran out is the same as never having fired
+        self.fired
+            .retain(|_, (at, cooldown)| now.duration_since(*at) < *cooldown);
+        let key = (hook.to_string(), pod.to_string());
+        if self.fired.contains_key(&key) {
+            return false;
+        }
+        self.fired.insert(key, (now, cooldown));
+        true
+    }
+
+    /// the actions that fit in the budget of the last minute
+    fn take(&mut self, actions: Vec<Action>, now: Instant) -> Vec<Action> {
+        while self
+            .actions
+            .front()
+            .is_some_and(|at| now.duration_since(*at) >= Duration::from_secs(60))
+        {
+            self.actions.pop_front();
+        }
+        let room = MAX_ACTIONS_PER_MINUTE.saturating_sub(self.actions.len());
+        let allowed: Vec<Action> = actions.into_iter().take(room).collect();
+        self.actions.extend(allowed.iter().map(|_| now));
+        allowed
+    }
+}
+
+static LIMITER: Mutex<Option<Limiter>> = Mutex::new(None);
+
+fn with_limiter<T>(f: impl FnOnce(&mut Limiter) -> T) -> Option<T> {
+    let mut limiter = LIMITER.lock().ok()?;
+    Some(f(limiter.get_or_insert_with(Limiter::default)))
+}
+
+/// Run the hooks against a pod the watch saw change.
+pub fn on_pod_update(update: &WatchUpdate<Pod>) {
+    let WatchUpdate::Applied(pod) = update else {
+        return;
+    };
+    if hooks().is_empty() {
+        return;
+    }
+    let facts = PodFacts::from_pod(pod);
+    for hook in hooks() {
+        let Some(actions) = hook.evaluate(&facts) else {
+            continue;
+        };
+        let now = Instant::now();
+        let actions = with_limiter(|limiter| {
+            let pod = format!("{}/{}", facts.namespace, facts.pod);
+            if limiter.may_fire(&hook.name, &pod, hook.cooldown, now) {
+                limiter.take(actions, now)
+            } else {
+                vec![]
+            }
+        })
+        .unwrap_or_default();
+        if actions.is_empty() {
+            continue;
+        }
+        let name = hook.name.clone();
+        let facts = facts.clone();
+        tokio::spawn(async move {
+            let _pending = Pending::start(format!("hook {name} on {}", facts.pod));
+            for action in actions {
+                if let Err(e) = execute(&name, &facts, &action).await {
+                    debug!("hook {name} could not {action:?}: {e}");
+                }
+            }
+        });
+    }
+}
+
+async fn execute(hook: &str, facts: &PodFacts, action: &Action) -> Result<(), String> {
+    match action {
+        Action::Log(text) => {
+            debug!("hook {hook} on {}: {text}", facts.pod);
+            Ok(())
+        }
+        Action::Annotate { key, value } => {
+            let edit = MetadataEdit::parse(
+                Target::Pod {
+                    name: facts.pod.clone(),
+                    namespace: Some(facts.namespace.clone()).filter(|ns| !ns.is_empty()),
+                },
+                MetadataKind::Annotation,
+                &format!("{key}={value}"),
+            )?;
+            apply_edit(&edit)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        }
+        Action::Webhook { url, text } => {
+            let body = json!({
+                "text": text,
+                "hook": hook,
+                "namespace": facts.namespace,
+                "pod": facts.pod,
+            });
+            reqwest::Client::new()
+                .post(url)
+                .timeout(ACTION_TIMEOUT)
+                .json(&body)
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status)
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        }
+        Action::Run { script } => run_script(script, facts).await,
+    }
+}
+
+/// an executable directly in the hooks dir, with the pod in its environment
+async fn run_script(script: &str, facts: &PodFacts) -> Result<(), String> {
+    if script.is_empty() || script.contains(['/', '\\']) || script.starts_with('.') {
+        return Err(format!("{script} is not a script name in the hooks dir"));
+    }
+    let dir = config_dir()
+        .map(|dir| dir.join(HOOKS_DIR))
+        .ok_or("no config dir")?;
+    let mut child = Command::new(dir.join(script))
+        .env_clear()
+        .envs(
+            ["PATH", "HOME"]
+                .iter()
+                .filter_map(|key| Some((key, std::env::var(key).ok()?))),
+        )
+        .env("NAVIPOD_NAMESPACE", &facts.namespace)
+        .env("NAVIPOD_POD", &facts.pod)
+        .env("NAVIPOD_REPLICASET", &facts.replicaset)
+        .current_dir(&dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    let status = tokio::time::timeout(ACTION_TIMEOUT, child.wait())
+        .await
+        .map_err(|_| format!("{script} still running after {ACTION_TIMEOUT:?}"))?
+        .map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{script} {status}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::serde_json::{from_value, json};
+
+    #[test]
+    fn test_hook_fires_with_cooldown_and_budget() {
+        let pod: Pod = from_value(json!({
+            "metadata": {"name": "web-1", "namespace": "default", "labels": {"team": "a"}},
+            "status": {
+                "phase": "Running",
+                "containerStatuses": [{
+                    "name": "web", "image": "web:1", "imageID": "", "ready": false,
+                    "restartCount": 7,
+                    "state": {"waiting": {"reason": "CrashLoopBackOff"}}
+                }]
+            }
+        }))
+        .unwrap();
+        let facts = PodFacts::from_pod(&pod);
+        assert_eq!(facts.reason, "CrashLoopBackOff");
+
+        let hook = compile(&Hook {
+            name: "storm".to_string(),
+            when: r#"restarts > 5 && !ready && labels.team == "a""#.to_string(),
+            then: r#"annotate("navipod/storm", "true"); log(pod + " " + restarts);"#.to_string(),
+            cooldown_secs: Some(60),
+        })
+        .unwrap();
+        let actions = hook.evaluate(&facts).unwrap();
+        assert_eq!(
+            actions,
+            vec![
+                Action::Annotate {
+                    key: "navipod/storm".to_string(),
+                    value: "true".to_string()
+                },
+                Action::Log("web-1 7".to_string()),
+            ]
+        );
+        let calm = PodFacts {
+            restarts: 1,
+            ..facts.clone()
+        };
+        assert!(hook.evaluate(&calm).is_none());
+
+        let endless = compile(&Hook {
+            name: "endless".to_string(),
+            when: "true".to_string(),
+            then: "loop {}".to_string(),
+            cooldown_secs: None,
+        })
+        .unwrap();
+        assert!(endless.evaluate(&facts).is_none());
+
+        let mut limiter = Limiter::default();
+        let now = Instant::now();
+        assert!(limiter.may_fire("storm", "web-1", hook.cooldown, now));
+        assert!(!limiter.may_fire(
+            "storm",
+            "web-1",
+            hook.cooldown,
+            now + Duration::from_secs(30)
+        ));
+        assert!(limiter.may_fire("storm", "web-2", hook.cooldown, now));
+        assert!(limiter.may_fire(
+            "storm",
+            "web-1",
+            hook.cooldown,
+            now + Duration::from_secs(61)
+        ));
+        // only web-1's fresh cooldown is left, web-2's ran out
+        assert_eq!(limiter.fired.len(), 1);
+
+        let many = vec![Action::Log(String::new()); MAX_ACTIONS_PER_MINUTE + 5];
+        assert_eq!(
+            limiter.take(many.clone(), now).len(),
+            MAX_ACTIONS_PER_MINUTE
+        );
+        assert!(limiter.take(many.clone(), now).is_empty());
+        assert_eq!(
+            limiter.take(many, now + Duration::from_secs(60)).len(),
+            MAX_ACTIONS_PER_MINUTE
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//!Describe the identity a pod runs with: its ServiceAccount, how the API
+//!token gets into the pod, and which other secrets are mounted alongside it.
+//!
+use crate::k8s::events::format_duration;
+use crate::tui::data::IdentityDetail;
+use crate::tui::theme::symbols;
+use chrono::Duration;
+use k8s_openapi::api::core::v1::{Pod, PodSpec};
+
+/// kubelet default when a projected token does not ask for an expiry
+const DEFAULT_TOKEN_EXPIRATION_SECONDS: i64 = 3600;
+
+fn detail(name: &str, value: String) -> IdentityDetail {
+    IdentityDetail {
+        name: name.to_string(),
+        value,
+    }
+}
+
+/// secrets named `<serviceaccount>-token-<suffix>` are the pre-1.24 static,
+/// never expiring tokens
+fn is_legacy_token_secret(secret_name: &str, service_account: &str) -> bool {
+    secret_name.starts_with(&format!("{service_account}-token-"))
+}
+
+fn automount(spec: &PodSpec) -> String {
+    match spec.automount_service_account_token {
+        Some(true) => "true".to_string(),
+        Some(false) => "false".to_string(),
+        None => "unset (ServiceAccount decides)".to_string(),
+    }
+}
+
+fn projected_details(spec: &PodSpec) -> Vec<IdentityDetail> {
+    let mut details = Vec::new();
+    for volume in spec.volumes.iter().flatten() {
+        let Some(projected) = &volume.projected else {
+            continue;
+        };
+        for source in projected.sources.iter().flatten() {
+            if let Some(token) = &source.service_account_token {
+                let expiry = token
+                    .expiration_seconds
+                    .unwrap_or(DEFAULT_TOKEN_EXPIRATION_SECONDS);
+                let audience = token
+                    .audience
+                    .clone()
+                    .unwrap_or_else(|| "api server".to_string());
+                details.push(detail(
+                    &format!("token {}", volume.name),
+                    format!(
+                        "expires {} audience={audience} path={}",
+                        format_duration(Duration::seconds(expiry)),
+                        token.path
+                    ),
+                ));
+            }
+            if let Some(secret) = &source.secret {
+                details.push(detail(
+                    &format!("projected {}", volume.name),
+                    format!("secret {}", secret.name),
+                ));
+            }
+        }
+    }
+    details
+}
+
+fn secret_details(spec: &PodSpec, service_account: &str) -> Vec<IdentityDetail> {
+    let mut details = Vec::new();
+    for volume in spec.volumes.iter().flatten() {
+        let Some(secret_name) = volume.secret.as_ref().and_then(|s| s.secret_name.clone()) else {
+            continue;
+        };
+        if is_legacy_token_secret(&secret_name, service_account) {
+            details.push(detail(
+                &format!("{} {}", symbols().warning, volume.name),
+                format!("{secret_name} is a long-lived static token"),
+            ));
+        } else {
+            details.push(detail(&format!("secret {}", volume.name), secret_name));
+        }
+    }
+    for pull_secret in spec.image_pull_secrets.iter().flatten() {
+        details.push(detail("imagePullSecret", pull_secret.name.clone()));
+    }
+    details
+}
+
+/// Summarize the ServiceAccount identity of a pod as name/value rows for the
+/// details panel.
+#[must_use]
+pub fn identity_details(pod: &Pod) -> Vec<IdentityDetail> {
+    let Some(spec) = &pod.spec else {
+        return vec![];
+    };
+    let service_account = spec
+        .service_account_name
+        .clone()
+        .unwrap_or_else(|| "default".to_string());
+
+    let mut details = vec![
+        detail("ServiceAccount", service_account.clone()),
+        detail("automount", automount(spec)),
+    ];
+    let projected = projected_details(spec);
+    if projected.is_empty() && spec.automount_service_account_token != Some(false) {
+        details.push(detail(
+            "token",
+            "no projected token volume found".to_string(),
+        ));
+    }
+    details.extend(projected);
+    details.extend(secret_details(spec, &service_account));
+    details
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::serde_json::{from_value, json};
+
+    #[test]
+    fn test_identity_details() {
+        let pod: Pod = from_value(json!({
+            "metadata": {"name": "p"},
+            "spec": {
+                "serviceAccountName": "builder",
+                "containers": [{"name": "app"}],
+                "volumes": [
+                    {"name": "kube-api-access-x", "projected": {"sources": [
+                        {"serviceAccountToken": {"expirationSeconds": 3607, "path": "token"}}
+                    ]}},
+                    {"name": "old", "secret": {"secretName": "builder-token-abcde"}},
+                    {"name": "tls", "secret": {"secretName": "my-tls"}}
+                ]
+            }
+        }))
+        .unwrap();
+
+        let details = identity_details(&pod);
+        assert_eq!(details[0].value, "builder");
+        assert_eq!(details[1].value, "unset (ServiceAccount decides)");
+        assert_eq!(details[2].name, "token kube-api-access-x");
+        assert!(details[2].value.starts_with("expires 1h"));
+        assert_eq!(details[3].name, "⚠ old");
+        assert_eq!(details[4].value, "my-tls");
+    }
+}
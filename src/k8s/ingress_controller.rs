@@ -0,0 +1,175 @@
+//!Locate the ingress controller pods serving the cluster so their access
+//!logs can be read alongside the ingress rules that route to a workload.
+//!
+use crate::error::{Error, Result};
+use crate::k8s::timeout::with_timeout;
+use futures::future::join_all;
+use k8s_openapi::api::core::v1::Pod;
+use kube::api::{Api, ListParams};
+use kube::Client;
+use std::collections::BTreeMap;
+
+use super::client_manager::get_client;
+
+/// label selectors used by the common controller helm charts and manifests
+const CONTROLLER_SELECTORS: [(&str, &str); 7] = [
+    ("nginx", "app.kubernetes.io/name=ingress-nginx"),
+    ("nginx", "app.kubernetes.io/name=nginx-ingress"),
+    ("nginx", "app=nginx-ingress"),
+    ("traefik", "app.kubernetes.io/name=traefik"),
+    ("haproxy", "app.kubernetes.io/name=haproxy-ingress"),
+    ("haproxy", "app.kubernetes.io/name=kubernetes-ingress"),
+    ("haproxy", "run=haproxy-ingress"),
+];
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ControllerPod {
+    pub flavor: String,
+    pub namespace: String,
+    pub pod_name: String,
+    pub container_name: String,
+    pub selectors: Option<BTreeMap<String, String>>,
+}
+
+fn is_running(pod: &Pod) -> bool {
+    pod.status
+        .as_ref()
+        .and_then(|status| status.phase.as_deref())
+        .is_some_and(|phase| phase == "Running")
+}
+
+/// the controller container is usually named after the flavor ("controller"
+/// for ingress-nginx), otherwise fall back to the first container.
+fn controller_container(pod: &Pod, flavor: &str) -> Option<String> {
+    let containers = &pod.spec.as_ref()?.containers;
+    containers
+        .iter()
+        .find(|c| c.name == "controller" || c.name.contains(flavor))
+        .or_else(|| containers.first())
+        .map(|c| c.name.clone())
+}
+
+/// # Errors
+///
+/// Will return `Err` if data can not be retrieved from k8s cluster api
+pub async fn list_controller_pods() -> Result<Vec<ControllerPod>> {
+    controller_pods(get_client().await?).await
+}
+
+/// All selectors are listed at once; a selector the user may not list pods
+/// with is skipped, the controller may well be found by another.
+async fn controller_pods(client: Client) -> Result<Vec<ControllerPod>> {
+    let pods: Api<Pod> = Api::all(client);
+    let lists = join_all(CONTROLLER_SELECTORS.iter().map(|(_, selector)| {
+        let lp = ListParams::default().labels(selector);
+        let pods = pods.clone();
+        async move { with_timeout("list ingress controllers", pods.list(&lp)).await }
+    }))
+    .await;
+
+    let mut controllers: Vec<ControllerPod> = Vec::new();
+
+    for ((flavor, _), listed) in CONTROLLER_SELECTORS.iter().zip(lists) {
+        let mut pod_list = match listed {
+            Ok(list) => list.items,
+            Err(Error::Forbidden(_)) => continue,
+            Err(e) => return Err(e),
+        };
+        // running pods first so the caller can just take the head of the list
+        pod_list.sort_by_key(|pod| !is_running(pod));
+
+        for pod in pod_list {
+            let Some(container_name) = controller_container(&pod, flavor) else {
+                continue;
+            };
+            let controller = ControllerPod {
+                flavor: (*flavor).to_string(),
+                namespace: pod.metadata.namespace.clone().unwrap_or_default(),
+                pod_name: pod.metadata.name.clone().unwrap_or_default(),
+                container_name,
+                selectors: pod.metadata.labels.clone(),
+            };
+            if !controllers.contains(&controller) {
+                controllers.push(controller);
+            }
+        }
+    }
+
+    Ok(controllers)
+}
+
+/// Build a log filter for an ingress rule.  Access log lines carry the request
+/// path but rarely the host, so the path is preferred unless it is the root.
+#[must_use]
+pub fn log_filter(host: &str, path: &str) -> String {
+    if path.is_empty() || path == "/" {
+        regex::escape(host)
+    } else {
+        regex::escape(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::http::{Request, Response};
+    use k8s_openapi::serde_json::{json, to_vec};
+    use kube::client::Body;
+    use tower_test::mock;
+
+    #[tokio::test]
+    async fn test_forbidden_selectors_are_skipped() {
+        let (service, mut handle) = mock::pair::<Request<Body>, Response<Body>>();
+        let server = tokio::spawn(async move {
+            for _ in CONTROLLER_SELECTORS {
+                let (request, send) = handle.next_request().await.unwrap();
+                let query = request.uri().query().unwrap_or_default().to_string();
+                let (status, body) = if query.contains("traefik") {
+                    let pod = json!({
+                        "metadata": {"name": "traefik-7c9", "namespace": "kube-system"},
+                        "spec": {"containers": [{"name": "traefik"}]},
+                        "status": {"phase": "Running"}
+                    });
+                    (
+                        200,
+                        json!({"kind": "PodList", "apiVersion": "v1", "metadata": {}, "items": [pod]}),
+                    )
+                } else if query.contains("nginx") {
+                    (
+                        403,
+                        json!({
+                            "kind": "Status", "apiVersion": "v1", "metadata": {}, "status": "Failure",
+                            "message": "pods is forbidden", "reason": "Forbidden", "code": 403
+                        }),
+                    )
+                } else {
+                    (
+                        200,
+                        json!({"kind": "PodList", "apiVersion": "v1", "metadata": {}, "items": []}),
+                    )
+                };
+                let response = Response::builder()
+                    .status(status)
+                    .body(Body::from(to_vec(&body).unwrap()))
+                    .unwrap();
+                send.send_response(response);
+            }
+        });
+        let controllers = controller_pods(Client::new(service, "default"))
+            .await
+            .unwrap();
+        server.await.unwrap();
+        assert_eq!(controllers.len(), 1);
+        assert_eq!(controllers[0].flavor, "traefik");
+        assert_eq!(controllers[0].namespace, "kube-system");
+        assert_eq!(controllers[0].container_name, "traefik");
+    }
+
+    #[test]
+    fn test_log_filter_prefers_path() {
+        assert_eq!(log_filter("example.com", "/api/v1"), "/api/v1");
+        assert_eq!(log_filter("example.com", "/"), r"example\.com");
+        assert_eq!(log_filter("example.com", ""), r"example\.com");
+        assert_eq!(log_filter("example.com", "/a.b(x)"), r"/a\.b\(x\)");
+    }
+}
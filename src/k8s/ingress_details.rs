@@ -0,0 +1,479 @@
+//!What an ingress row does not show: the class that picks the controller,
+//!the controller annotations that change routing, ie: rewrite targets, auth
+//!and rate limits, whether the TLS secret a host is served with exists and
+//!holds a certificate for that host, whether the live endpoint serves that
+//!certificate, the cert-manager Certificate renewing it and the external-dns
+//!record publishing the host.  The live endpoints are dialed after the
+//!listing, all at once, and what they serve is kept a while so a refresh
+//!does not dial them again.
+//!
+use crate::k8s::cert_manager::{self, Certificate};
+use crate::k8s::external_dns::{self, DnsRecord};
+use crate::k8s::timeout::with_timeout;
+use crate::net::{analyze_tls_certificate, CertificateInfo};
+use crate::tui::data::IngressDetail;
+use crate::tui::utils::time::asn1time_to_future_days_string;
+use chrono::Utc;
+use futures::future::join_all;
+use k8s_openapi::api::core::v1::Secret;
+use k8s_openapi::api::networking::v1::Ingress;
+use kube::{Api, Client};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::time::timeout;
+use x509_parser::extensions::GeneralName;
+use x509_parser::pem::parse_x509_pem;
+
+/// annotations read by a controller rather than by kubernetes
+const CONTROLLER_PREFIXES: [&str; 7] = [
+    "nginx.ingress.kubernetes.io/",
+    "traefik.ingress.kubernetes.io/",
+    "haproxy.org/",
+    "haproxy.router.openshift.io/",
+    "alb.ingress.kubernetes.io/",
+    "konghq.com/",
+    "cert-manager.io/",
+];
+
+/// how long the live endpoint gets to answer the handshake
+const LIVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// how long what a host served is shown before it is dialed again
+const LIVE_TTL: Duration = Duration::from_secs(300);
+
+/// what each host served, `None` while it is being dialed
+static LIVE: OnceLock<Mutex<HashMap<String, Option<Served>>>> = OnceLock::new();
+
+/// what a live endpoint answered the handshake with, and when
+#[derive(Clone)]
+struct Served {
+    at: Instant,
+    certificate: Result<CertificateInfo, String>,
+}
+
+/// the class the pre 1.18 annotation names when `ingressClassName` is unset
+const LEGACY_CLASS: &str = "kubernetes.io/ingress.class";
+
+/// `ingressClassName`, the legacy annotation, or the cluster's default class
+#[must_use]
+pub fn class(ingress: &Ingress) -> String {
+    ingress
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.ingress_class_name.clone())
+        .or_else(|| {
+            ingress
+                .metadata
+                .annotations
+                .as_ref()
+                .and_then(|annotations| annotations.get(LEGACY_CLASS).cloned())
+        })
+        .unwrap_or_else(|| "default".to_string())
+}
+
+fn detail(name: impl Into<String>, value: impl Into<String>) -> IngressDetail {
+    IngressDetail {
+        name: name.into(),
+        value: value.into(),
+    }
+}
+
+/// the controller annotations, in key order
+#[must_use]
+pub fn annotation_details(ingress: &Ingress) -> Vec<IngressDetail> {
+    ingress
+        .metadata
+        .annotations
+        .iter()
+        .flatten()
+        .filter(|(key, _)| {
+            CONTROLLER_PREFIXES
+                .iter()
+                .any(|prefix| key.starts_with(prefix))
+        })
+        .map(|(key, value)| detail(key.as_str(), value.trim()))
+        .collect()
+}
+
+/// the secret of the tls entry listing `host`, `None` when the host is
+/// served without tls
+#[must_use]
+pub fn tls_secret<'a>(ingress: &'a Ingress, host: &str) -> Option<&'a str> {
+    ingress
+        .spec
+        .as_ref()?
+        .tls
+        .iter()
+        .flatten()
+        .find(|tls| {
+            tls.hosts
+                .as_ref()
+                .is_none_or(|hosts| hosts.is_empty() || hosts.iter().any(|h| h == host))
+        })
+        .and_then(|tls| tls.secret_name.as_deref())
+}
+
+/// whether a certificate for `names` is good for `host`, a wildcard covers
+/// one label
+#[must_use]
+pub fn covers(names: &[String], host: &str) -> bool {
+    names.iter().any(|name| {
+        name.eq_ignore_ascii_case(host)
+            || name.strip_prefix("*.").is_some_and(|domain| {
+                host.split_once('.')
+                    .is_some_and(|(_, rest)| rest.eq_ignore_ascii_case(domain))
+            })
+    })
+}
+
+/// the leaf certificate of a tls secret
+struct SecretCert {
+    /// the dns names, the common name when it has no subject alternative
+    /// names
+    names: Vec<String>,
+    serial: String,
+}
+
+/// the first certificate in `pem`
+fn parse_cert(pem: &[u8]) -> Option<SecretCert> {
+    let (_, pem) = parse_x509_pem(pem).ok()?;
+    let cert = pem.parse_x509().ok()?;
+    let mut names: Vec<String> = cert
+        .subject_alternative_name()
+        .ok()
+        .flatten()
+        .map(|san| {
+            san.value
+                .general_names
+                .iter()
+                .filter_map(|name| match name {
+                    GeneralName::DNSName(dns) => Some((*dns).to_string()),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if names.is_empty() {
+        names.extend(
+            cert.subject()
+                .iter_common_name()
+                .filter_map(|cn| cn.as_str().ok().map(ToString::to_string)),
+        );
+    }
+    Some(SecretCert {
+        names,
+        serial: cert.tbs_certificate.raw_serial_as_string(),
+    })
+}
+
+fn tls_crt(secret: &Secret) -> Option<&[u8]> {
+    secret
+        .data
+        .as_ref()
+        .and_then(|data| data.get("tls.crt"))
+        .map(|crt| crt.0.as_slice())
+}
+
+/// what the secret `name` says about `host`
+fn check_secret(name: &str, secret: Option<&Secret>, host: &str) -> IngressDetail {
+    let Some(secret) = secret else {
+        return detail(
+            format!("tls {name}"),
+            "missing, the controller serves its default certificate",
+        );
+    };
+    let Some(crt) = tls_crt(secret) else {
+        return detail(format!("tls {name}"), "has no tls.crt");
+    };
+    match parse_cert(crt) {
+        None => detail(format!("tls {name}"), "tls.crt does not parse"),
+        Some(cert) if host.is_empty() || covers(&cert.names, host) => {
+            detail(format!("tls {name}"), format!("ok, covers {host}"))
+        }
+        Some(cert) => detail(
+            format!("tls {name}"),
+            format!("does not cover {host}, only {}", cert.names.join(", ")),
+        ),
+    }
+}
+
+/// whether the endpoint serves the secret's certificate, another one means
+/// a load balancer holding a stale copy or an ingress read from the wrong
+/// secret
+fn compare_live(serial: &str, live: &Result<CertificateInfo, String>) -> IngressDetail {
+    let value = match live {
+        Ok(info) if info.serial == serial => "serves the secret's certificate".to_string(),
+        Ok(info) => format!(
+            "serves another certificate, serial {} from {} expiring {}, stale load balancer or wrong secret?",
+            info.serial,
+            info.issued_by,
+            asn1time_to_future_days_string(&info.expires)
+        ),
+        Err(e) => format!("can not check: {e}"),
+    };
+    detail("live", value)
+}
+
+fn live_hosts() -> &'static Mutex<HashMap<String, Option<Served>>> {
+    LIVE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// what `host` served when last dialed, `None` before the first answer
+fn served(host: &str) -> Option<Served> {
+    live_hosts().lock().ok()?.get(host).cloned().flatten()
+}
+
+/// whether `host` is dialed again: never dialed, or last answered more
+/// than `LIVE_TTL` ago and not being dialed now
+fn due(entry: Option<&Option<Served>>, now: Instant) -> bool {
+    match entry {
+        None => true,
+        Some(None) => false,
+        Some(Some(served)) => now.duration_since(served.at) > LIVE_TTL,
+    }
+}
+
+async fn dial(host: String) -> (String, Served) {
+    let certificate = match timeout(LIVE_TIMEOUT, analyze_tls_certificate(&host)).await {
+        Ok(Ok(info)) => Ok(info),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("no answer in {}s", LIVE_TIMEOUT.as_secs())),
+    };
+    let served = Served {
+        at: Instant::now(),
+        certificate,
+    };
+    (host, served)
+}
+
+/// Dial the `hosts` that are due, all at once and off the caller, for the
+/// next listing to compare.
+fn check_live(hosts: BTreeSet<String>) {
+    let Ok(mut live) = live_hosts().lock() else {
+        return;
+    };
+    let now = Instant::now();
+    let hosts: Vec<String> = hosts
+        .into_iter()
+        .filter(|host| due(live.get(host), now))
+        .collect();
+    if hosts.is_empty() {
+        return;
+    }
+    for host in &hosts {
+        // a host dialed again shows its last answer meanwhile, restamped
+        // so it is not dialed twice, a new one shows as checking
+        live.entry(host.clone())
+            .and_modify(|entry| {
+                if let Some(served) = entry {
+                    served.at = now;
+                }
+            })
+            .or_insert(None);
+    }
+    drop(live);
+    tokio::spawn(async move {
+        let answers = join_all(hosts.into_iter().map(dial)).await;
+        if let Ok(mut live) = live_hosts().lock() {
+            for (host, served) in answers {
+                live.insert(host, Some(served));
+            }
+        }
+    });
+}
+
+/// What the rows of one listing share, read once: secrets by
+/// `namespace/name`, or why they could not be, and the Certificates and
+/// DNSEndpoint records of each namespace, none where the CRDs are missing
+/// or can not be read.
+#[derive(Default)]
+pub struct Lookups {
+    secrets: HashMap<String, Result<Option<Secret>, String>>,
+    certificates: HashMap<String, Vec<Certificate>>,
+    records: HashMap<String, Vec<DnsRecord>>,
+    /// the hosts whose live certificate the rows compare
+    hosts: BTreeSet<String>,
+}
+
+impl Lookups {
+    async fn secret(
+        &mut self,
+        client: &Client,
+        namespace: &str,
+        name: &str,
+    ) -> &Result<Option<Secret>, String> {
+        let key = format!("{namespace}/{name}");
+        if !self.secrets.contains_key(&key) {
+            let api: Api<Secret> = Api::namespaced(client.clone(), namespace);
+            let fetched = with_timeout("get tls secret", api.get_opt(name))
+                .await
+                .map_err(|e| e.to_string());
+            self.secrets.insert(key.clone(), fetched);
+        }
+        &self.secrets[&key]
+    }
+
+    async fn certificates(&mut self, client: &Client, namespace: &str) -> &[Certificate] {
+        if !self.certificates.contains_key(namespace) {
+            let found = cert_manager::list_certificates(client, namespace)
+                .await
+                .unwrap_or_default();
+            self.certificates.insert(namespace.to_string(), found);
+        }
+        &self.certificates[namespace]
+    }
+
+    async fn records(&mut self, client: &Client, namespace: &str) -> &[DnsRecord] {
+        if !self.records.contains_key(namespace) {
+            let found = external_dns::list_records(client, namespace)
+                .await
+                .unwrap_or_default();
+            self.records.insert(namespace.to_string(), found);
+        }
+        &self.records[namespace]
+    }
+
+    /// Dial the hosts the rows compare, in the background, the listing
+    /// shows what they answer once it is refreshed.
+    pub fn check_live(self) {
+        check_live(self.hosts);
+    }
+}
+
+/// Class, the tls check, the Certificate and dns record behind `host` and
+/// the controller annotations.
+pub async fn details(
+    client: &Client,
+    ingress: &Ingress,
+    host: &str,
+    lookups: &mut Lookups,
+) -> Vec<IngressDetail> {
+    let namespace = ingress.metadata.namespace.as_deref().unwrap_or_default();
+    let mut details = vec![detail("class", class(ingress))];
+    match tls_secret(ingress, host) {
+        None => details.push(detail("tls", "none, plain http")),
+        Some(name) => {
+            let serial = match lookups.secret(client, namespace, name).await {
+                Ok(secret) => {
+                    details.push(check_secret(name, secret.as_ref(), host));
+                    secret
+                        .as_ref()
+                        .and_then(tls_crt)
+                        .and_then(parse_cert)
+                        .map(|cert| cert.serial)
+                }
+                Err(e) => {
+                    details.push(detail(format!("tls {name}"), format!("can not read: {e}")));
+                    None
+                }
+            };
+            if let Some(serial) = serial.filter(|_| !host.is_empty()) {
+                lookups.hosts.insert(host.to_string());
+                details.push(served(host).map_or_else(
+                    || detail("live", "checking, shown at the next refresh"),
+                    |served| compare_live(&serial, &served.certificate),
+                ));
+            }
+            let certificates = lookups.certificates(client, namespace).await;
+            if let Some(certificate) = cert_manager::for_secret(certificates, name) {
+                details.push(detail(
+                    format!("certificate {}", certificate.name),
+                    certificate.summary(Utc::now()),
+                ));
+            }
+        }
+    }
+    let records = lookups.records(client, namespace).await;
+    if let Some(owner) = external_dns::ownership(records, ingress, host) {
+        details.push(detail("dns", owner));
+    }
+    details.extend(annotation_details(ingress));
+    details
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::serde_json::{from_value, json};
+
+    #[test]
+    fn test_class_annotations_and_tls() {
+        let ingress: Ingress = from_value(json!({
+            "metadata": {
+                "name": "web",
+                "annotations": {
+                    "kubernetes.io/ingress.class": "nginx",
+                    "nginx.ingress.kubernetes.io/rewrite-target": "/$2",
+                    "nginx.ingress.kubernetes.io/limit-rps": "10",
+                    "meta.helm.sh/release-name": "web"
+                }
+            },
+            "spec": {
+                "tls": [{"hosts": ["shop.acme.io"], "secretName": "shop-tls"}],
+                "rules": [{"host": "shop.acme.io"}, {"host": "api.acme.io"}]
+            }
+        }))
+        .unwrap();
+        assert_eq!(class(&ingress), "nginx");
+        let names: Vec<String> = annotation_details(&ingress)
+            .into_iter()
+            .map(|d| d.name)
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "nginx.ingress.kubernetes.io/limit-rps",
+                "nginx.ingress.kubernetes.io/rewrite-target"
+            ]
+        );
+        assert_eq!(tls_secret(&ingress, "shop.acme.io"), Some("shop-tls"));
+        assert_eq!(tls_secret(&ingress, "api.acme.io"), None);
+        assert_eq!(
+            check_secret("shop-tls", None, "shop.acme.io").value,
+            "missing, the controller serves its default certificate"
+        );
+
+        let names = ["*.acme.io".to_string(), "acme.io".to_string()];
+        assert!(covers(&names, "shop.acme.io"));
+        assert!(covers(&names, "ACME.io"));
+        assert!(!covers(&names, "a.shop.acme.io"));
+        assert!(!covers(&names, "acme.com"));
+
+        let live = |serial: &str| {
+            Ok(CertificateInfo {
+                host: "shop.acme.io".to_string(),
+                is_valid: true,
+                expires: x509_parser::time::ASN1Time::from_timestamp(0).unwrap(),
+                issued_by: "CN: old-ca".to_string(),
+                serial: serial.to_string(),
+            })
+        };
+        assert_eq!(
+            compare_live("0a:1b", &live("0a:1b")).value,
+            "serves the secret's certificate"
+        );
+        assert!(compare_live("0a:1b", &live("ff:01"))
+            .value
+            .starts_with("serves another certificate, serial ff:01 from CN: old-ca"));
+        assert_eq!(
+            compare_live("0a:1b", &Err("refused".to_string())).value,
+            "can not check: refused"
+        );
+    }
+
+    #[test]
+    fn test_live_is_dialed_again_once_stale() {
+        let now = Instant::now();
+        let served = |ago: Duration| {
+            Some(Served {
+                at: now.checked_sub(ago).unwrap(),
+                certificate: Err("refused".to_string()),
+            })
+        };
+        assert!(due(None, now));
+        assert!(!due(Some(&None), now));
+        assert!(!due(Some(&served(Duration::from_secs(60))), now));
+        assert!(due(Some(&served(LIVE_TTL * 2)), now));
+    }
+}
@@ -0,0 +1,402 @@
+//!A journal of what changed: every add, modify and delete the pod and
+//!replicaset watches see while the TUI runs.  The changes view reads the
+//!recent ones from memory, the db keeps them so `navipod changes` answers
+//!"what changed in this namespace in the last hour" after the TUI is gone.
+//!
+use crate::k8s::events::format_duration;
+use crate::k8s::scan::storage::{Storage, StorageBackend};
+use crate::k8s::watch::WatchUpdate;
+use crate::tui::utils::time::{timezone, EVENT_TIME_FORMAT};
+use chrono::{DateTime, Duration, Utc};
+use kube::Resource;
+use sqlx::{Pool, Sqlite};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{self, Write};
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tracing::debug;
+
+/// changes kept in memory, the oldest go first
+const KEEP: usize = 2000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeType {
+    Added,
+    Modified,
+    Deleted,
+}
+
+impl fmt::Display for ChangeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Added => write!(f, "added"),
+            Self::Modified => write!(f, "modified"),
+            Self::Deleted => write!(f, "deleted"),
+        }
+    }
+}
+
+impl FromStr for ChangeType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "added" => Ok(Self::Added),
+            "modified" => Ok(Self::Modified),
+            "deleted" => Ok(Self::Deleted),
+            other => Err(format!("{other:?} is not a change type")),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    pub at: DateTime<Utc>,
+    pub namespace: String,
+    pub kind: String,
+    pub name: String,
+    pub change: ChangeType,
+    pub resource_version: String,
+}
+
+impl Change {
+    /// `12m ago`, from `now`
+    #[must_use]
+    pub fn ago(&self, now: DateTime<Utc>) -> String {
+        format!(
+            "{} ago",
+            format_duration(now.signed_duration_since(self.at))
+        )
+    }
+}
+
+/// What the watches have shown, and the objects known to exist so an
+/// applied object can be told apart as added or modified.
+#[derive(Debug, Default)]
+struct Journal {
+    changes: VecDeque<Change>,
+    known: HashMap<&'static str, HashSet<String>>,
+}
+
+impl Journal {
+    fn observe<K: Resource>(
+        &mut self,
+        kind: &'static str,
+        update: &WatchUpdate<K>,
+        at: DateTime<Utc>,
+    ) -> Option<Change> {
+        let key = |object: &K| {
+            format!(
+                "{}/{}",
+                object.meta().namespace.as_deref().unwrap_or_default(),
+                object.meta().name.as_deref().unwrap_or_default()
+            )
+        };
+        let known = self.known.entry(kind).or_default();
+        let (object, change) = match update {
+            WatchUpdate::Resynced(objects) => {
+                *known = objects.iter().map(key).collect();
+                return None;
+            }
+            WatchUpdate::Applied(object) if known.insert(key(object)) => {
+                (object, ChangeType::Added)
+            }
+            WatchUpdate::Applied(object) => (object, ChangeType::Modified),
+            WatchUpdate::Deleted(object) => {
+                known.remove(&key(object));
+                (object, ChangeType::Deleted)
+            }
+        };
+        let meta = object.meta();
+        let change = Change {
+            at,
+            namespace: meta.namespace.clone().unwrap_or_default(),
+            kind: kind.to_string(),
+            name: meta.name.clone().unwrap_or_default(),
+            change,
+            resource_version: meta.resource_version.clone().unwrap_or_default(),
+        };
+        self.push(change.clone());
+        Some(change)
+    }
+
+    fn push(&mut self, change: Change) {
+        if self.changes.len() == KEEP {
+            self.changes.pop_front();
+        }
+        self.changes.push_back(change);
+    }
+}
+
+fn journal() -> &'static Mutex<Journal> {
+    static JOURNAL: OnceLock<Mutex<Journal>> = OnceLock::new();
+    JOURNAL.get_or_init(|| Mutex::new(Journal::default()))
+}
+
+static WRITER: OnceLock<UnboundedSender<Change>> = OnceLock::new();
+
+/// Feed a watch update in, `kind` as the journal names the object.
+pub fn record<K: Resource>(kind: &'static str, update: &WatchUpdate<K>) {
+    let change = journal()
+        .lock()
+        .ok()
+        .and_then(|mut journal| journal.observe(kind, update, Utc::now()));
+    if let (Some(change), Some(writer)) = (change, WRITER.get()) {
+        let _ = writer.send(change);
+    }
+}
+
+/// the changes seen after `since`, newest first
+#[must_use]
+pub fn recent(since: DateTime<Utc>) -> Vec<Change> {
+    journal()
+        .lock()
+        .map(|journal| {
+            journal
+                .changes
+                .iter()
+                .rev()
+                .take_while(|change| change.at > since)
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// # Errors
+///
+/// Will return `Err` if the table can not be created
+pub async fn create_table(pool: &Pool<Sqlite>) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r"
+        CREATE TABLE IF NOT EXISTS journal (
+            id INTEGER PRIMARY KEY,
+            at TEXT NOT NULL,
+            namespace TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            name TEXT NOT NULL,
+            change TEXT NOT NULL,
+            resource_version TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_journal_at ON journal (namespace, at);
+        ",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub(crate) const INSERT_SQL: &str = r"
+    INSERT INTO journal (at, namespace, kind, name, change, resource_version)
+    VALUES ($1, $2, $3, $4, $5, $6)
+    ";
+
+pub(crate) const SINCE_SQL: &str = r"
+    SELECT at, namespace, kind, name, change, resource_version FROM journal
+    WHERE namespace = $1 AND at > $2 ORDER BY at DESC, id DESC
+    ";
+
+/// a row of `SINCE_SQL`
+pub(crate) type Row = (String, String, String, String, String, String);
+
+/// the change of a row of `SINCE_SQL`, `None` if it does not parse
+pub(crate) fn from_row(
+    (at, namespace, kind, name, change, resource_version): Row,
+) -> Option<Change> {
+    Some(Change {
+        at: at.parse().ok()?,
+        namespace,
+        kind,
+        name,
+        change: change.parse().ok()?,
+        resource_version,
+    })
+}
+
+/// # Errors
+///
+/// Will return `Err` if the db can not be written
+pub async fn insert(pool: &Pool<Sqlite>, change: &Change) -> Result<(), sqlx::Error> {
+    sqlx::query(INSERT_SQL)
+        .bind(change.at.to_rfc3339())
+        .bind(&change.namespace)
+        .bind(&change.kind)
+        .bind(&change.name)
+        .bind(change.change.to_string())
+        .bind(&change.resource_version)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// The changes to `namespace` after `since`, newest first.
+///
+/// # Errors
+///
+/// Will return `Err` if the db can not be read
+pub async fn since(
+    pool: &Pool<Sqlite>,
+    namespace: &str,
+    since: DateTime<Utc>,
+) -> Result<Vec<Change>, sqlx::Error> {
+    let exists: Option<(String,)> =
+        sqlx::query_as("SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'journal'")
+            .fetch_optional(pool)
+            .await?;
+    if exists.is_none() {
+        return Ok(vec![]);
+    }
+    let rows: Vec<Row> = sqlx::query_as(SINCE_SQL)
+        .bind(namespace)
+        .bind(since.to_rfc3339())
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().filter_map(from_row).collect())
+}
+
+/// Journal entries before `cutoff` (rfc3339) deleted, for `db prune`.
+///
+/// # Errors
+///
+/// Will return `Err` if the db can not be written
+pub async fn prune(pool: &Pool<Sqlite>, cutoff: &str) -> Result<u64, sqlx::Error> {
+    create_table(pool).await?;
+    let deleted = sqlx::query("DELETE FROM journal WHERE at < ?")
+        .bind(cutoff)
+        .execute(pool)
+        .await?;
+    Ok(deleted.rows_affected())
+}
+
+/// Start the writer that keeps every change in the db at `location`, a
+/// sqlite path or postgres url, with the last hour of `namespace` loaded
+/// from it first.  The pod and replicaset watches feed `record` already.
+pub fn spawn(location: String, namespace: String) {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    if WRITER.set(tx).is_err() {
+        return;
+    }
+    tokio::spawn(async move {
+        let storage = match Storage::open_journal(&location).await {
+            Ok(storage) => storage,
+            Err(e) => {
+                debug!("journal not kept in {location}: {e}");
+                return;
+            }
+        };
+        let earlier = storage
+            .changes(&namespace, Utc::now() - Duration::hours(1))
+            .await;
+        if let Ok(earlier) = earlier {
+            if let Ok(mut journal) = journal().lock() {
+                let seen = std::mem::take(&mut journal.changes);
+                for change in earlier.into_iter().rev().chain(seen) {
+                    journal.push(change);
+                }
+            }
+        }
+        while let Some(change) = rx.recv().await {
+            if let Err(e) = storage.record_change(&change).await {
+                debug!("journal write failed: {e}");
+            }
+        }
+    });
+}
+
+/// The changes to the client's namespace over the last `window`, from the
+/// db at `location` the TUI journaled into.
+///
+/// # Errors
+///
+/// Will return `Err` if the db can not be read
+pub async fn list(
+    location: &str,
+    namespace: &str,
+    window: Duration,
+) -> Result<Vec<Change>, Box<dyn std::error::Error>> {
+    let storage = Storage::open_read_only(location).await?;
+    Ok(storage.changes(namespace, Utc::now() - window).await?)
+}
+
+/// `changes` as text, with counts by change type
+#[must_use]
+pub fn render(changes: &[Change], namespace: &str, window: Duration) -> String {
+    let count = |change: ChangeType| changes.iter().filter(|c| c.change == change).count();
+    let mut out = format!(
+        "Changes in {namespace} in the last {}: {} ({} added, {} modified, {} deleted)",
+        format_duration(window),
+        changes.len(),
+        count(ChangeType::Added),
+        count(ChangeType::Modified),
+        count(ChangeType::Deleted)
+    );
+    let tz = timezone();
+    for change in changes {
+        let _ = write!(
+            out,
+            "\n  {}  {:<8}  {}/{}  rv {}",
+            tz.format(&change.at, EVENT_TIME_FORMAT),
+            change.change.to_string(),
+            change.kind,
+            change.name,
+            change.resource_version
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::Pod;
+    use k8s_openapi::serde_json::{from_value, json};
+
+    fn pod(name: &str, rv: &str) -> Pod {
+        from_value(json!({"metadata": {"name": name, "namespace": "web", "resourceVersion": rv}}))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_journal() {
+        let at: DateTime<Utc> = "2024-03-01T10:00:00Z".parse().unwrap();
+        let mut journal = Journal::default();
+        assert_eq!(
+            journal.observe("Pod", &WatchUpdate::Resynced(vec![pod("a", "1")]), at),
+            None
+        );
+        let changes: Vec<ChangeType> = [
+            WatchUpdate::Applied(pod("a", "2")),
+            WatchUpdate::Applied(pod("b", "3")),
+            WatchUpdate::Applied(pod("b", "4")),
+            WatchUpdate::Deleted(pod("a", "5")),
+            WatchUpdate::Applied(pod("a", "6")),
+        ]
+        .iter()
+        .filter_map(|update| journal.observe("Pod", update, at))
+        .map(|change| change.change)
+        .collect();
+        assert_eq!(
+            changes,
+            [
+                ChangeType::Modified,
+                ChangeType::Added,
+                ChangeType::Modified,
+                ChangeType::Deleted,
+                ChangeType::Added
+            ]
+        );
+        let last = journal.changes.back().unwrap();
+        assert_eq!(
+            (last.name.as_str(), last.resource_version.as_str()),
+            ("a", "6")
+        );
+
+        let changes: Vec<Change> = journal.changes.iter().rev().take(2).cloned().collect();
+        let text = render(&changes, "web", Duration::hours(1));
+        assert!(text.starts_with(
+            "Changes in web in the last 1h: 2 (1 added, 0 modified, 1 deleted)\n  03-01 10:00:00  added     Pod/a  rv 6"
+        ));
+    }
+}
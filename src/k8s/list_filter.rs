@@ -0,0 +1,163 @@
+//!Filter bar entries the api server can answer itself.  An exact name,
+//!`^web-5d8f7$`, becomes a field selector and label expressions like
+//!`app=web,tier!=cache` or `env in (prod,stage)` become a label selector,
+//!so a namespace of thousands of objects is not listed and cached only to
+//!be filtered down to a handful.  Anything else stays a regex over the rows.
+//!
+use kube::api::ListParams;
+use regex::Regex;
+use std::fmt;
+use std::sync::OnceLock;
+
+const NAME: &str = r"^\^([a-z0-9]([-a-z0-9.]*[a-z0-9])?)\$$";
+const KEY: &str = r"([A-Za-z0-9.-]+/)?[A-Za-z0-9]([-A-Za-z0-9_.]*[A-Za-z0-9])?";
+const VALUE: &str = r"([A-Za-z0-9]([-A-Za-z0-9_.]*[A-Za-z0-9])?)?";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ListFilter {
+    /// `metadata.name` to match exactly
+    pub name: Option<String>,
+    /// a label selector
+    pub labels: Option<String>,
+}
+
+fn name_pattern() -> Option<&'static Regex> {
+    static PATTERN: OnceLock<Option<Regex>> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(NAME).ok()).as_ref()
+}
+
+fn term_pattern() -> Option<&'static Regex> {
+    static PATTERN: OnceLock<Option<Regex>> = OnceLock::new();
+    PATTERN
+        .get_or_init(|| {
+            let values = format!(r"{VALUE}(\s*,\s*{VALUE})*");
+            Regex::new(&format!(
+                r"^(!{KEY}|{KEY}\s*(=|==|!=)\s*{VALUE}|{KEY}\s+(in|notin)\s+\(\s*{values}\s*\))$"
+            ))
+            .ok()
+        })
+        .as_ref()
+}
+
+/// split on the commas between terms, not those inside `in (a,b)`
+fn terms(expression: &str) -> Vec<&str> {
+    let mut terms = vec![];
+    let mut depth = 0_usize;
+    let mut start = 0;
+    for (i, c) in expression.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                terms.push(expression[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    terms.push(expression[start..].trim());
+    terms
+}
+
+impl ListFilter {
+    /// The server side form of `filter`, `None` when only a regex over the
+    /// rows can answer it.
+    #[must_use]
+    pub fn parse(filter: &str) -> Option<Self> {
+        let filter = filter.trim();
+        if let Some(name) = name_pattern()?.captures(filter) {
+            return Some(Self {
+                name: Some(name[1].to_string()),
+                labels: None,
+            });
+        }
+        // a bare word is a name search, not a label that exists
+        let selects = |term: &str| term.contains(['=', '!', '(']);
+        let pattern = term_pattern()?;
+        let terms = terms(filter);
+        terms
+            .iter()
+            .all(|term| selects(term) && pattern.is_match(term))
+            .then(|| Self {
+                name: None,
+                labels: Some(terms.join(",")),
+            })
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.name.is_none() && self.labels.is_none()
+    }
+
+    /// `params` narrowed to what the filter selects, on top of any
+    /// selector they already have
+    #[must_use]
+    pub fn apply(&self, mut params: ListParams) -> ListParams {
+        let join = |current: Option<String>, more: String| match current {
+            Some(current) if !current.is_empty() => format!("{current},{more}"),
+            _ => more,
+        };
+        if let Some(name) = &self.name {
+            params.field_selector = Some(join(
+                params.field_selector.take(),
+                format!("metadata.name={name}"),
+            ));
+        }
+        if let Some(labels) = &self.labels {
+            params.label_selector = Some(join(params.label_selector.take(), labels.clone()));
+        }
+        params
+    }
+}
+
+/// the selectors sent, ie: `metadata.name=web-5d8f7` or `app=web`
+impl fmt::Display for ListFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let selectors: Vec<String> = self
+            .name
+            .iter()
+            .map(|name| format!("metadata.name={name}"))
+            .chain(self.labels.clone())
+            .collect();
+        write!(f, "{}", selectors.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_filters_the_server_can_answer() {
+        assert_eq!(
+            ListFilter::parse("^web-5d8f7$").unwrap().name.as_deref(),
+            Some("web-5d8f7")
+        );
+        let labels =
+            |filter: &str| ListFilter::parse(filter).and_then(|filter| filter.labels.clone());
+        assert_eq!(
+            labels("app=web, tier!=cache").as_deref(),
+            Some("app=web,tier!=cache")
+        );
+        assert_eq!(
+            labels("app.kubernetes.io/name in (web, api),!canary").as_deref(),
+            Some("app.kubernetes.io/name in (web, api),!canary")
+        );
+        assert_eq!(ListFilter::parse("web"), None);
+        assert_eq!(ListFilter::parse("^web"), None);
+        assert_eq!(ListFilter::parse("web-.*"), None);
+        assert_eq!(ListFilter::parse("app=web,db"), None);
+        assert_eq!(ListFilter::parse(""), None);
+
+        let params = ListFilter::parse("tier=db")
+            .unwrap()
+            .apply(ListParams::default().labels("app=web"));
+        assert_eq!(params.label_selector.as_deref(), Some("app=web,tier=db"));
+        let params = ListFilter::parse("^db-0$")
+            .unwrap()
+            .apply(ListParams::default());
+        assert_eq!(params.field_selector.as_deref(), Some("metadata.name=db-0"));
+        assert!(ListFilter::default().is_empty());
+        assert_eq!(ListFilter::parse("app=web").unwrap().to_string(), "app=web");
+    }
+}
@@ -0,0 +1,232 @@
+//!Time series of small measurements navipod takes itself, ie: probe
+//!response times, kept between sessions so percentiles survive a restart.
+//!
+use crate::config::config_file;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+const HISTORY_FILE: &str = "metrics_history.json";
+/// samples kept per series, the oldest are dropped first
+const MAX_SAMPLES: usize = 200;
+/// series without a sample for this long belong to pods that are gone
+const MAX_AGE_SECS: i64 = 7 * 24 * 60 * 60;
+/// how often the store is written out while samples keep coming
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Sample {
+    /// unix seconds
+    pub at: i64,
+    pub value: f64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MetricsHistoryStore {
+    pub series: BTreeMap<String, VecDeque<Sample>>,
+}
+
+impl MetricsHistoryStore {
+    #[must_use]
+    pub fn load() -> Self {
+        config_file(HISTORY_FILE)
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        match serde_json::to_string(self) {
+            Ok(json) => write(&json),
+            Err(e) => debug!("can not serialize metrics history: {e}"),
+        }
+    }
+
+    pub fn record(&mut self, key: &str, sample: Sample) {
+        let series = self.series.entry(key.to_string()).or_default();
+        series.push_back(sample);
+        while series.len() > MAX_SAMPLES {
+            series.pop_front();
+        }
+    }
+
+    /// drop the series whose newest sample is older than `before`
+    pub fn prune(&mut self, before: i64) {
+        self.series
+            .retain(|_, series| series.back().is_some_and(|sample| sample.at >= before));
+    }
+
+    /// drop the series whose key starts with `prefix`
+    pub fn forget(&mut self, prefix: &str) {
+        self.series.retain(|key, _| !key.starts_with(prefix));
+    }
+
+    #[must_use]
+    pub fn last(&self, key: &str) -> Option<Sample> {
+        self.series.get(key)?.back().copied()
+    }
+
+    #[must_use]
+    pub fn len(&self, key: &str) -> usize {
+        self.series.get(key).map_or(0, VecDeque::len)
+    }
+
+    /// nearest-rank percentile, `p` in 0..=100
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    pub fn percentile(&self, key: &str, p: f64) -> Option<f64> {
+        let mut values: Vec<f64> = self.series.get(key)?.iter().map(|s| s.value).collect();
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(f64::total_cmp);
+        let rank = ((p / 100.0) * values.len() as f64).ceil() as usize;
+        Some(values[rank.clamp(1, values.len()) - 1])
+    }
+}
+
+fn write(json: &str) {
+    let Some(path) = config_file(HISTORY_FILE) else {
+        return;
+    };
+    if let Err(e) = std::fs::write(path, json) {
+        debug!("can not save metrics history: {e}");
+    }
+}
+
+static STORE: Mutex<Option<MetricsHistoryStore>> = Mutex::new(None);
+/// when the store was last written out
+static SAVED: Mutex<Option<Instant>> = Mutex::new(None);
+/// samples were recorded since the store was last written out
+static UNSAVED: AtomicBool = AtomicBool::new(false);
+
+/// Run `f` on the shared store, loading it from disk on first use.
+pub fn with_store<T>(f: impl FnOnce(&mut MetricsHistoryStore) -> T) -> Option<T> {
+    let mut store = STORE.lock().ok()?;
+    Some(f(store.get_or_insert_with(MetricsHistoryStore::load)))
+}
+
+/// Run `f` on the shared store if it is loaded already, for callers like
+/// the render that must not wait on the disk.
+pub fn with_loaded<T>(f: impl FnOnce(&MetricsHistoryStore) -> T) -> Option<T> {
+    STORE.lock().ok()?.as_ref().map(f)
+}
+
+/// Read the store from disk ahead of its first use, the lock is not held
+/// while reading.
+pub fn preload() {
+    if with_loaded(|_| ()).is_some() {
+        return;
+    }
+    let loaded = MetricsHistoryStore::load();
+    if let Ok(mut store) = STORE.lock() {
+        store.get_or_insert(loaded);
+    }
+}
+
+/// whether `SAVE_INTERVAL` has passed since the store was last written out
+fn save_due(now: Instant) -> bool {
+    let Ok(mut saved) = SAVED.lock() else {
+        return false;
+    };
+    let due = saved.is_none_or(|at| now.duration_since(at) >= SAVE_INTERVAL);
+    if due {
+        *saved = Some(now);
+    }
+    due
+}
+
+/// Add samples to the shared store, dropping the series that went stale,
+/// and write it out at most every `SAVE_INTERVAL`.  This touches the disk,
+/// call it off the event loop.
+pub fn record_samples(samples: &[(String, Sample)]) {
+    let Some(newest) = samples.iter().map(|(_, sample)| sample.at).max() else {
+        return;
+    };
+    let json = with_store(|store| {
+        for (key, sample) in samples {
+            store.record(key, *sample);
+        }
+        store.prune(newest - MAX_AGE_SECS);
+        // serialized under the lock, written without it
+        save_due(Instant::now()).then(|| serde_json::to_string(store))
+    })
+    .flatten();
+    match json {
+        Some(Ok(json)) => {
+            UNSAVED.store(false, Ordering::Relaxed);
+            write(&json);
+        }
+        Some(Err(e)) => debug!("can not serialize metrics history: {e}"),
+        None => UNSAVED.store(true, Ordering::Relaxed),
+    }
+}
+
+/// Drop the series whose key starts with `prefix`, ie: of a pod that is gone.
+pub fn forget(prefix: &str) {
+    if let Ok(mut store) = STORE.lock() {
+        if let Some(store) = store.as_mut() {
+            store.forget(prefix);
+        }
+    }
+}
+
+/// Write out the samples recorded since the last save, on the way out.
+pub fn flush() {
+    if UNSAVED.swap(false, Ordering::Relaxed) {
+        with_loaded(MetricsHistoryStore::save);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile_and_cap() {
+        let mut store = MetricsHistoryStore::default();
+        for (i, value) in (1..=20).enumerate() {
+            store.record(
+                "probe",
+                Sample {
+                    at: i64::try_from(i).unwrap(),
+                    value: f64::from(value),
+                },
+            );
+        }
+        assert_eq!(store.percentile("probe", 95.0), Some(19.0));
+        assert_eq!(store.percentile("probe", 50.0), Some(10.0));
+        assert_eq!(store.percentile("missing", 95.0), None);
+
+        for at in 0..300 {
+            store.record("probe", Sample { at, value: 1.0 });
+        }
+        assert_eq!(store.len("probe"), MAX_SAMPLES);
+        assert_eq!(store.last("probe").map(|s| s.at), Some(299));
+    }
+
+    #[test]
+    fn test_stale_and_forgotten_series_are_dropped() {
+        let mut store = MetricsHistoryStore::default();
+        store.record("probe/ns/web-1/app/liveness", Sample { at: 10, value: 1.0 });
+        store.record("probe/ns/web-2/app/liveness", Sample { at: 50, value: 1.0 });
+        store.record("probe/ns/web-3/app/liveness", Sample { at: 90, value: 1.0 });
+
+        store.prune(40);
+        assert_eq!(store.len("probe/ns/web-1/app/liveness"), 0);
+        assert_eq!(store.len("probe/ns/web-2/app/liveness"), 1);
+
+        store.forget("probe/ns/web-2/");
+        assert_eq!(
+            store.series.keys().collect::<Vec<_>>(),
+            ["probe/ns/web-3/app/liveness"]
+        );
+    }
+}
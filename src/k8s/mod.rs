@@ -1,9 +1,42 @@
+pub mod actions;
+pub mod audit;
+pub mod cache;
+pub mod cert_manager;
 pub mod client;
+pub mod client_manager;
+pub mod cluster;
+pub mod compare;
 pub mod containers;
+pub mod deprecations;
+pub mod dynamic;
 pub mod events;
+pub mod external_dns;
+pub mod gateway;
+pub mod hooks;
+pub mod identity;
+pub mod ingress_controller;
+pub mod ingress_details;
+pub mod journal;
+pub mod list_filter;
+pub mod metrics_history;
+pub mod namespaces;
+pub mod noisy;
+pub mod paging;
 pub mod pod_ingress;
 pub mod pods;
+pub mod probes;
+pub mod problems;
+pub mod restarts;
+pub mod retry;
 pub mod rs;
 pub mod rs_ingress;
 pub mod scan;
+pub mod scheduling;
+pub mod security;
+pub mod sidecars;
+pub mod timeline;
+pub mod timeout;
 pub mod utils;
+pub mod watch;
+pub mod watch_manager;
+pub mod workloads;
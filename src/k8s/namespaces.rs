@@ -0,0 +1,267 @@
+//!Creating and deleting namespaces.  A namespace is created with the labels
+//!of a preset, ie: pod security admission or sidecar injection, and only an
+//!empty one is deleted: what it still holds is listed first, the api server
+//!dry-runs the delete and the user types the name to confirm it.
+//!
+use crate::error::{Error, Result};
+use crate::k8s::actions::validate_name;
+use crate::k8s::client_manager::get_client;
+use crate::k8s::dynamic::Crd;
+use crate::k8s::timeout::with_timeout;
+use k8s_openapi::api::core::v1::Namespace;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use kube::api::{DeleteParams, PostParams};
+use kube::{Api, Client};
+use std::collections::BTreeMap;
+
+/// labels a new namespace can start with
+pub const PRESETS: &[(&str, &[(&str, &str)])] = &[
+    ("no labels", &[]),
+    (
+        "restricted pods",
+        &[("pod-security.kubernetes.io/enforce", "restricted")],
+    ),
+    (
+        "baseline pods",
+        &[("pod-security.kubernetes.io/enforce", "baseline")],
+    ),
+    ("istio sidecars", &[("istio-injection", "enabled")]),
+    ("linkerd sidecars", &[("linkerd.io/inject", "enabled")]),
+];
+
+/// the kinds a namespace is checked for before it is deleted
+const CONTENTS: &[Crd] = &[
+    Crd {
+        group: "",
+        version: "v1",
+        kind: "Pod",
+    },
+    Crd {
+        group: "apps",
+        version: "v1",
+        kind: "Deployment",
+    },
+    Crd {
+        group: "apps",
+        version: "v1",
+        kind: "StatefulSet",
+    },
+    Crd {
+        group: "apps",
+        version: "v1",
+        kind: "DaemonSet",
+    },
+    Crd {
+        group: "apps",
+        version: "v1",
+        kind: "ReplicaSet",
+    },
+    Crd {
+        group: "batch",
+        version: "v1",
+        kind: "Job",
+    },
+    Crd {
+        group: "batch",
+        version: "v1",
+        kind: "CronJob",
+    },
+    Crd {
+        group: "",
+        version: "v1",
+        kind: "Service",
+    },
+    Crd {
+        group: "networking.k8s.io",
+        version: "v1",
+        kind: "Ingress",
+    },
+    Crd {
+        group: "",
+        version: "v1",
+        kind: "PersistentVolumeClaim",
+    },
+    Crd {
+        group: "",
+        version: "v1",
+        kind: "ConfigMap",
+    },
+    Crd {
+        group: "",
+        version: "v1",
+        kind: "Secret",
+    },
+    Crd {
+        group: "",
+        version: "v1",
+        kind: "ServiceAccount",
+    },
+];
+
+/// what the cluster puts in every namespace, not counted against deleting it
+fn created_by_cluster(kind: &str, name: &str) -> bool {
+    matches!(
+        (kind, name),
+        ("ConfigMap", "kube-root-ca.crt" | "openshift-service-ca.crt")
+            | ("ServiceAccount", "default")
+    ) || (kind == "Secret" && name.starts_with("default-token-"))
+}
+
+/// A namespace to create, `name` and the labels of `PRESETS[preset]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NewNamespace {
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+}
+
+impl NewNamespace {
+    /// # Errors
+    ///
+    /// Will return `Err` with a message for the user if `name` is not a valid
+    /// namespace name
+    pub fn parse(name: &str, preset: usize) -> core::result::Result<Self, String> {
+        let name = name.trim();
+        let dns_label = name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+        validate_name(name, "namespace")?;
+        if !dns_label {
+            return Err(format!(
+                "namespace {name:?} must be lowercase alphanumerics or '-'"
+            ));
+        }
+        let labels = PRESETS[preset % PRESETS.len()]
+            .1
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect();
+        Ok(Self {
+            name: name.to_string(),
+            labels,
+        })
+    }
+}
+
+impl std::fmt::Display for NewNamespace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "create namespace {}", self.name)?;
+        if !self.labels.is_empty() {
+            let labels: Vec<String> = self
+                .labels
+                .iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect();
+            write!(f, " with {}", labels.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// # Errors
+///
+/// Will return `Err` if the namespace exists or can not be created
+pub async fn create(namespace: &NewNamespace) -> Result<()> {
+    let client = get_client().await?;
+    let api: Api<Namespace> = Api::all(client);
+    let object = Namespace {
+        metadata: ObjectMeta {
+            name: Some(namespace.name.clone()),
+            labels: (!namespace.labels.is_empty()).then(|| namespace.labels.clone()),
+            ..ObjectMeta::default()
+        },
+        ..Namespace::default()
+    };
+    with_timeout(
+        "create namespace",
+        api.create(&PostParams::default(), &object),
+    )
+    .await?;
+    Ok(())
+}
+
+async fn list_contents(client: &Client, namespace: &str) -> Result<Vec<String>> {
+    let mut contents = vec![];
+    for kind in CONTENTS {
+        for object in kind.list(client, Some(namespace)).await? {
+            let name = object.metadata.name.unwrap_or_default();
+            if !created_by_cluster(kind.kind, &name) {
+                contents.push(format!("{} {name}", kind.kind));
+            }
+        }
+    }
+    Ok(contents)
+}
+
+/// What deleting `namespace` would delete with it, the cluster's own
+/// defaults left out.  The delete is dry-run by the api server first, so
+/// missing permission shows here and not after the user confirmed.
+///
+/// # Errors
+///
+/// Will return `Err` if the contents can not be listed or the dry-run
+/// delete is refused
+pub async fn delete_dry_run(namespace: &str) -> Result<Vec<String>> {
+    let client = get_client().await?;
+    let contents = list_contents(&client, namespace).await?;
+    let api: Api<Namespace> = Api::all(client);
+    with_timeout(
+        "dry-run delete namespace",
+        api.delete(namespace, &DeleteParams::default().dry_run()),
+    )
+    .await?;
+    Ok(contents)
+}
+
+/// Delete `namespace` when `typed` is its name and it is still empty.
+///
+/// # Errors
+///
+/// Will return `Err` if the name does not match, the namespace is not empty
+/// or can not be deleted
+pub async fn delete_empty(namespace: &str, typed: &str) -> Result<()> {
+    if typed.trim() != namespace {
+        return Err(Error::Conflict(format!(
+            "type {namespace} to delete the namespace"
+        )));
+    }
+    let client = get_client().await?;
+    let contents = list_contents(&client, namespace).await?;
+    if let Some(first) = contents.first() {
+        return Err(Error::Conflict(format!(
+            "namespace {namespace} still holds {} objects, ie: {first}",
+            contents.len()
+        )));
+    }
+    let api: Api<Namespace> = Api::all(client);
+    with_timeout(
+        "delete namespace",
+        api.delete(namespace, &DeleteParams::default()),
+    )
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_namespace() {
+        let namespace = NewNamespace::parse(" shop-dev ", 1).unwrap();
+        assert_eq!(
+            namespace.to_string(),
+            "create namespace shop-dev with pod-security.kubernetes.io/enforce=restricted"
+        );
+        assert_eq!(
+            NewNamespace::parse("shop", 0).unwrap().to_string(),
+            "create namespace shop"
+        );
+        assert!(NewNamespace::parse("Shop", 0).is_err());
+        assert!(NewNamespace::parse("shop_dev", 0).is_err());
+        assert!(NewNamespace::parse("-shop", 0).is_err());
+
+        assert!(created_by_cluster("ConfigMap", "kube-root-ca.crt"));
+        assert!(created_by_cluster("Secret", "default-token-x7k2p"));
+        assert!(!created_by_cluster("ServiceAccount", "deployer"));
+    }
+}
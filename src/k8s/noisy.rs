@@ -0,0 +1,238 @@
+//!Which objects fill the event stream, ie: a pod whose failing readiness
+//!probe posts Unhealthy every ten seconds.  Events are counted by involved
+//!object and reason over a window, the noisiest first, so the one workload
+//!drowning out everything else in the events view has a name.
+//!
+use crate::error::Result as NvResult;
+use crate::k8s::client_manager::get_client;
+use crate::k8s::events::format_duration;
+use crate::k8s::paging;
+use chrono::{DateTime, Duration, Utc};
+use k8s_openapi::api::core::v1::Event;
+use kube::api::ListParams;
+use kube::Api;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// the window the report counts over, by default
+pub const DEFAULT_WINDOW_MINUTES: i64 = 60;
+/// rows the report shows, by default
+pub const DEFAULT_TOP: usize = 20;
+
+/// Every event of one reason about one object in the window.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NoisyObject {
+    /// `Pod/web-5d8f7-x2x9k`
+    pub object: String,
+    pub reason: String,
+    pub type_: String,
+    /// occurrences, the repeats the api server folded into one event count
+    pub count: i64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    /// the latest message
+    pub message: String,
+}
+
+impl NoisyObject {
+    /// `12m ago`, from `now`
+    #[must_use]
+    pub fn ago(at: DateTime<Utc>, now: DateTime<Utc>) -> String {
+        format!("{} ago", format_duration(now.signed_duration_since(at)))
+    }
+}
+
+/// when an event first and last happened, newer events only carry
+/// `eventTime` and older ones only the timestamps
+fn seen(event: &Event) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let last = event
+        .last_timestamp
+        .as_ref()
+        .map(|t| t.0)
+        .or_else(|| event.event_time.as_ref().map(|t| t.0))
+        .or_else(|| event.metadata.creation_timestamp.as_ref().map(|t| t.0))?;
+    let first = event
+        .first_timestamp
+        .as_ref()
+        .map(|t| t.0)
+        .or_else(|| event.event_time.as_ref().map(|t| t.0))
+        .unwrap_or(last);
+    Some((first.min(last), last))
+}
+
+fn occurrences(event: &Event) -> i64 {
+    event
+        .series
+        .as_ref()
+        .and_then(|series| series.count)
+        .or(event.count)
+        .map_or(1, |count| i64::from(count.max(1)))
+}
+
+/// `events` last seen after `since`, summed by object and reason, the
+/// noisiest first
+#[must_use]
+pub fn aggregate(events: &[Event], since: DateTime<Utc>) -> Vec<NoisyObject> {
+    let mut by_object: HashMap<(String, String), NoisyObject> = HashMap::new();
+    for event in events {
+        let Some((first, last)) = seen(event) else {
+            continue;
+        };
+        if last < since {
+            continue;
+        }
+        let involved = &event.involved_object;
+        let object = format!(
+            "{}/{}",
+            involved.kind.as_deref().unwrap_or("Object"),
+            involved.name.as_deref().unwrap_or_default()
+        );
+        let reason = event.reason.clone().unwrap_or_default();
+        let noisy = by_object
+            .entry((object.clone(), reason.clone()))
+            .or_insert_with(|| NoisyObject {
+                object,
+                reason,
+                type_: String::new(),
+                count: 0,
+                first_seen: first,
+                last_seen: last,
+                message: String::new(),
+            });
+        noisy.count += occurrences(event);
+        noisy.first_seen = noisy.first_seen.min(first);
+        if last >= noisy.last_seen || noisy.message.is_empty() {
+            noisy.last_seen = last;
+            noisy.type_ = event.type_.clone().unwrap_or_default();
+            noisy.message = event
+                .message
+                .as_deref()
+                .unwrap_or_default()
+                .trim()
+                .to_string();
+        }
+    }
+    let mut noisy: Vec<NoisyObject> = by_object.into_values().collect();
+    noisy.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then(b.last_seen.cmp(&a.last_seen))
+            .then(a.object.cmp(&b.object))
+    });
+    noisy
+}
+
+/// The namespace's events over the last `window`, by object and reason.
+///
+/// # Errors
+///
+/// Will return `Err` if events cannot be retrieved from k8s cluster api
+pub async fn list(window: Duration) -> NvResult<Vec<NoisyObject>> {
+    let events: Api<Event> = Api::default_namespaced(get_client().await?);
+    let events = paging::list(&events, ListParams::default(), "list events").await?;
+    Ok(aggregate(&events, Utc::now() - window))
+}
+
+/// the `top` noisiest as text
+#[must_use]
+pub fn render(noisy: &[NoisyObject], window: Duration, top: usize, now: DateTime<Utc>) -> String {
+    let total: i64 = noisy.iter().map(|n| n.count).sum();
+    let mut out = format!(
+        "Events in the last {}: {total} from {} objects and reasons",
+        format_duration(window),
+        noisy.len()
+    );
+    if noisy.is_empty() {
+        return out;
+    }
+    let shown = &noisy[..top.min(noisy.len())];
+    let object_width = shown.iter().map(|n| n.object.len()).max().unwrap_or(0);
+    let reason_width = shown.iter().map(|n| n.reason.len()).max().unwrap_or(0);
+    let _ = write!(
+        out,
+        "\n  {:>6}  {:<object_width$}  {:<reason_width$}  {:>10}  {:>10}  MESSAGE",
+        "COUNT", "OBJECT", "REASON", "FIRST SEEN", "LAST SEEN"
+    );
+    for n in shown {
+        let _ = write!(
+            out,
+            "\n  {:>6}  {:<object_width$}  {:<reason_width$}  {:>10}  {:>10}  {}",
+            n.count,
+            n.object,
+            n.reason,
+            NoisyObject::ago(n.first_seen, now),
+            NoisyObject::ago(n.last_seen, now),
+            n.message
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::serde_json::{from_value, json};
+
+    fn event(name: &str, reason: &str, count: i32, first: &str, last: &str) -> Event {
+        from_value(json!({
+            "metadata": {"name": format!("{name}.{reason}")},
+            "involvedObject": {"kind": "Pod", "name": name},
+            "reason": reason,
+            "message": format!("{reason} {last}"),
+            "type": "Warning",
+            "count": count,
+            "firstTimestamp": first,
+            "lastTimestamp": last
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_aggregate_and_render() {
+        let events = [
+            event(
+                "web-a",
+                "Unhealthy",
+                40,
+                "2024-03-01T09:00:00Z",
+                "2024-03-01T10:00:00Z",
+            ),
+            event(
+                "web-a",
+                "Unhealthy",
+                2,
+                "2024-03-01T09:50:00Z",
+                "2024-03-01T09:55:00Z",
+            ),
+            event(
+                "web-a",
+                "BackOff",
+                5,
+                "2024-03-01T09:30:00Z",
+                "2024-03-01T09:59:00Z",
+            ),
+            event(
+                "db-0",
+                "Unhealthy",
+                9,
+                "2024-03-01T07:00:00Z",
+                "2024-03-01T08:00:00Z",
+            ),
+        ];
+        let since = "2024-03-01T09:00:00Z".parse().unwrap();
+        let noisy = aggregate(&events, since);
+        assert_eq!(noisy.len(), 2);
+        assert_eq!(noisy[0].object, "Pod/web-a");
+        assert_eq!(noisy[0].reason, "Unhealthy");
+        assert_eq!(noisy[0].count, 42);
+        assert_eq!(noisy[0].message, "Unhealthy 2024-03-01T10:00:00Z");
+        assert_eq!(noisy[1].reason, "BackOff");
+
+        let now = "2024-03-01T10:05:00Z".parse().unwrap();
+        let text = render(&noisy, Duration::minutes(60), 1, now);
+        assert!(text.starts_with("Events in the last 1h: 47 from 2 objects and reasons\n"));
+        assert!(text.contains("42  Pod/web-a  Unhealthy"));
+        assert!(text.contains("1h ago"));
+        assert!(!text.contains("BackOff"));
+    }
+}
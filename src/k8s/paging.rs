@@ -0,0 +1,311 @@
+//!Lists fetched a page at a time with limit and continue tokens, so a
+//!namespace with tens of thousands of objects neither stalls the api
+//!server nor the view waiting on it.  Views get the rows of each page as it
+//!lands and the status line shows how far the longest list got.  A
+//!continue token that expires part way, 410 Gone, starts the list over
+//!from the first page instead of failing it.  Each page is hedged when
+//!`--hedge-ms` is set.
+//!
+use crate::error::{Error, Result};
+use crate::k8s::timeout::hedge;
+use kube::api::{ListParams, ObjectList};
+use kube::Api;
+use serde::de::DeserializeOwned;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tracing::debug;
+
+/// objects asked for per request
+pub const PAGE_SIZE: u32 = 500;
+/// the continue token expired
+const GONE: u16 = 410;
+/// times a list starts over before the 410 is returned
+const MAX_RESTARTS: u32 = 3;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// lists with more pages to come, by pager
+static LOADING: Mutex<Vec<(u64, Loading)>> = Mutex::new(Vec::new());
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Loading {
+    pub loaded: u64,
+    /// `None` when the api server does not say how many remain
+    pub total: Option<u64>,
+}
+
+/// The list furthest from done, while any is being paged through.
+#[must_use]
+pub fn loading() -> Option<Loading> {
+    let loading = LOADING.lock().ok()?;
+    loading
+        .iter()
+        .map(|(_, progress)| *progress)
+        .max_by_key(|progress| progress.total.unwrap_or(0).saturating_sub(progress.loaded))
+}
+
+/// Walks a list page by page, counting towards `loading` until dropped.
+pub struct Pager<'a, K> {
+    api: &'a Api<K>,
+    params: ListParams,
+    operation: &'static str,
+    id: u64,
+    token: Option<String>,
+    done: bool,
+    loaded: u64,
+    resource_version: Option<String>,
+    restarts: u32,
+    /// the pages handed out so far were dropped, see `take_restart`
+    restarted: bool,
+}
+
+impl<'a, K> Pager<'a, K>
+where
+    K: Clone + DeserializeOwned + Debug,
+{
+    #[must_use]
+    pub fn new(api: &'a Api<K>, params: ListParams, operation: &'static str) -> Self {
+        Self {
+            api,
+            params: params.limit(PAGE_SIZE),
+            operation,
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            token: None,
+            done: false,
+            loaded: 0,
+            resource_version: None,
+            restarts: 0,
+            restarted: false,
+        }
+    }
+
+    /// The next page, `None` once the list is through.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a page can not be retrieved from k8s cluster api
+    pub async fn next_page(&mut self) -> Result<Option<Vec<K>>> {
+        if self.done {
+            return Ok(None);
+        }
+        let page = match self.list().await {
+            Err(Error::Kube(kube::Error::Api(response)))
+                if response.code == GONE
+                    && self.token.is_some()
+                    && self.restarts < MAX_RESTARTS =>
+            {
+                debug!("{}: continue token expired, listing again", self.operation);
+                self.restarts += 1;
+                self.restarted = true;
+                self.token = None;
+                self.loaded = 0;
+                self.list().await?
+            }
+            page => page?,
+        };
+        self.loaded += page.items.len() as u64;
+        self.resource_version = page.metadata.resource_version.clone();
+        self.token = page.metadata.continue_.filter(|token| !token.is_empty());
+        self.done = self.token.is_none();
+        let remaining = page
+            .metadata
+            .remaining_item_count
+            .and_then(|remaining| u64::try_from(remaining).ok());
+        self.report(remaining);
+        Ok(Some(page.items))
+    }
+
+    async fn list(&self) -> Result<ObjectList<K>> {
+        let params = match &self.token {
+            Some(token) => self.params.clone().continue_token(token),
+            None => self.params.clone(),
+        };
+        hedge(self.operation, || async {
+            Ok(self.api.list(&params).await?)
+        })
+        .await
+    }
+
+    /// every page, for callers with nothing to show until the end
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a page can not be retrieved from k8s cluster api
+    pub async fn collect(mut self) -> Result<Vec<K>> {
+        let mut items = vec![];
+        while let Some(page) = self.next_page().await? {
+            if self.take_restart() {
+                items.clear();
+            }
+            items.extend(page);
+        }
+        Ok(items)
+    }
+
+    /// `true` once after the list started over, the page just returned is
+    /// the first again and the ones before it are to be dropped
+    pub fn take_restart(&mut self) -> bool {
+        std::mem::take(&mut self.restarted)
+    }
+
+    /// `true` once the last page was read
+    #[must_use]
+    pub const fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// the version the list was read at, where a watch picks up
+    #[must_use]
+    pub fn resource_version(&self) -> Option<&str> {
+        self.resource_version.as_deref()
+    }
+
+    fn report(&self, remaining: Option<u64>) {
+        let Ok(mut loading) = LOADING.lock() else {
+            return;
+        };
+        loading.retain(|(id, _)| *id != self.id);
+        if !self.done {
+            loading.push((
+                self.id,
+                Loading {
+                    loaded: self.loaded,
+                    total: remaining.map(|remaining| self.loaded + remaining),
+                },
+            ));
+        }
+    }
+}
+
+impl<K> Drop for Pager<'_, K> {
+    fn drop(&mut self) {
+        if let Ok(mut loading) = LOADING.lock() {
+            loading.retain(|(id, _)| *id != self.id);
+        }
+    }
+}
+
+/// Every object of a list, fetched a page at a time.
+///
+/// # Errors
+///
+/// Will return `Err` if a page can not be retrieved from k8s cluster api
+pub async fn list<K>(api: &Api<K>, params: ListParams, operation: &'static str) -> Result<Vec<K>>
+where
+    K: Clone + DeserializeOwned + Debug,
+{
+    Pager::new(api, params, operation).collect().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::http::{Request, Response};
+    use k8s_openapi::api::core::v1::Pod;
+    use k8s_openapi::serde_json::{json, to_vec, Value};
+    use kube::client::Body;
+    use kube::Client;
+    use tower_test::mock;
+
+    type Handle = mock::Handle<Request<Body>, Response<Body>>;
+
+    fn page(names: &[&str], token: &str) -> (u16, Value) {
+        let items: Vec<Value> = names
+            .iter()
+            .map(|name| json!({"metadata": {"name": name}}))
+            .collect();
+        let metadata = json!({"continue": token, "resourceVersion": "7"});
+        (
+            200,
+            json!({"kind": "PodList", "apiVersion": "v1", "metadata": metadata, "items": items}),
+        )
+    }
+
+    fn gone() -> (u16, Value) {
+        (
+            410,
+            json!({
+                "kind": "Status", "apiVersion": "v1", "metadata": {}, "status": "Failure",
+                "message": "the continue token is too old", "reason": "Expired", "code": 410
+            }),
+        )
+    }
+
+    /// answer each request with the next response, returning the queries asked
+    fn serve(
+        mut handle: Handle,
+        responses: Vec<(u16, Value)>,
+    ) -> tokio::task::JoinHandle<Vec<String>> {
+        tokio::spawn(async move {
+            let mut queries = vec![];
+            for (status, body) in responses {
+                let Some((request, send)) = handle.next_request().await else {
+                    break;
+                };
+                queries.push(request.uri().query().unwrap_or_default().to_string());
+                let response = Response::builder()
+                    .status(status)
+                    .body(Body::from(to_vec(&body).unwrap()))
+                    .unwrap();
+                send.send_response(response);
+            }
+            queries
+        })
+    }
+
+    fn names(pods: &[Pod]) -> Vec<&str> {
+        pods.iter()
+            .filter_map(|pod| pod.metadata.name.as_deref())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_pages_follow_the_continue_token() {
+        let (service, handle) = mock::pair::<Request<Body>, Response<Body>>();
+        let server = serve(handle, vec![page(&["a", "b"], "next"), page(&["c"], "")]);
+        let api: Api<Pod> = Api::default_namespaced(Client::new(service, "default"));
+        let mut pager = Pager::new(&api, ListParams::default(), "list pods");
+        assert_eq!(
+            names(&pager.next_page().await.unwrap().unwrap()),
+            ["a", "b"]
+        );
+        assert!(!pager.is_done());
+        assert_eq!(names(&pager.next_page().await.unwrap().unwrap()), ["c"]);
+        assert!(pager.is_done());
+        assert!(pager.next_page().await.unwrap().is_none());
+        assert_eq!(pager.resource_version(), Some("7"));
+        let queries = server.await.unwrap();
+        assert!(queries[0].contains("limit=500") && !queries[0].contains("continue"));
+        assert!(queries[1].contains("continue=next"));
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_starts_over() {
+        let (service, handle) = mock::pair::<Request<Body>, Response<Body>>();
+        let server = serve(
+            handle,
+            vec![page(&["a"], "old"), gone(), page(&["a", "b"], "")],
+        );
+        let api: Api<Pod> = Api::default_namespaced(Client::new(service, "default"));
+        let pods = Pager::new(&api, ListParams::default(), "list pods")
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(names(&pods), ["a", "b"]);
+        let queries = server.await.unwrap();
+        assert!(queries[1].contains("continue=old"));
+        assert!(!queries[2].contains("continue"));
+    }
+
+    #[tokio::test]
+    async fn test_gone_on_the_first_page_is_an_error() {
+        let (service, handle) = mock::pair::<Request<Body>, Response<Body>>();
+        let _server = serve(handle, vec![gone()]);
+        let api: Api<Pod> = Api::default_namespaced(Client::new(service, "default"));
+        let listed = Pager::new(&api, ListParams::default(), "list pods")
+            .collect()
+            .await;
+        assert!(matches!(listed, Err(Error::Kube(kube::Error::Api(r))) if r.code == GONE));
+    }
+}
@@ -1,26 +1,294 @@
 use crate::error::Result;
+use crate::k8s::gateway;
+use crate::k8s::timeout::with_timeout;
+use crate::k8s::utils::format_label_selector;
+use crate::net::analyze_tls_certificate;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
 use k8s_openapi::api::apps::v1::ReplicaSet;
 use k8s_openapi::api::core::v1::Pod;
 use k8s_openapi::api::core::v1::Service;
 use k8s_openapi::api::networking::v1::Ingress;
 use kube::api::ListParams;
 use kube::{Api, Client};
+use serde::Serialize;
 
 use super::client::new;
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ExplainOutput {
+    #[default]
+    Text,
+    Json,
+    Yaml,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Owner {
+    pub kind: String,
+    pub name: String,
+}
+
+/// the certificate a TLS host serves, or why it could not be read
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CertStatus {
+    pub host: String,
+    pub valid: bool,
+    pub expires: Option<DateTime<Utc>>,
+    pub days_left: Option<i64>,
+    pub issued_by: Option<String>,
+    pub error: Option<String>,
+}
+
+/// one ingress or Gateway API path that ends at the pod
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Route {
+    /// `Ingress` or `HTTPRoute`
+    pub kind: String,
+    /// name of the ingress or route
+    pub ingress: String,
+    pub host: Option<String>,
+    pub path: String,
+    pub service: String,
+    pub port: Option<i32>,
+    /// the ingress, or a gateway listener, terminates TLS for the host
+    pub tls: bool,
+    pub cert: Option<CertStatus>,
+}
+
+/// How traffic reaches a pod: its owners, the services selecting it and the
+/// ingress and HTTPRoute routes, with certificates, in front of those
+/// services.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PodExposure {
+    pub pod: String,
+    pub namespace: String,
+    pub owners: Vec<Owner>,
+    pub services: Vec<String>,
+    pub routes: Vec<Route>,
+}
+
+impl PodExposure {
+    /// the prose explain-pod has always printed, plus certificate lines
+    #[must_use]
+    pub fn text(&self) -> String {
+        let mut lines = Vec::new();
+        for owner in &self.owners {
+            lines.push(format!("Belongs to {} {}", owner.kind, owner.name));
+        }
+        for service in &self.services {
+            lines.push(format!("Implements Service {service}"));
+        }
+        for route in &self.routes {
+            let port = route
+                .port
+                .filter(|port| *port > 0)
+                .map_or_else(String::new, |port| format!(" on port {port}"));
+            lines.push(format!(
+                "{} {} routes {}{} to pod via Service {}{port}",
+                route.kind,
+                route.ingress,
+                route.host.as_deref().unwrap_or(""),
+                route.path,
+                route.service
+            ));
+            match &route.cert {
+                Some(CertStatus {
+                    host,
+                    valid,
+                    days_left: Some(days),
+                    issued_by,
+                    ..
+                }) => lines.push(format!(
+                    "  TLS for {host}: {} cert from {}, expires in {days}d",
+                    if *valid { "valid" } else { "invalid" },
+                    issued_by.as_deref().unwrap_or("unknown issuer"),
+                )),
+                Some(CertStatus {
+                    host,
+                    error: Some(error),
+                    ..
+                }) => lines.push(format!("  TLS for {host}: {error}")),
+                _ if !route.tls => lines.push("  no TLS".to_string()),
+                _ => {}
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if the document cannot be serialized
+    pub fn render(
+        &self,
+        output: ExplainOutput,
+    ) -> std::result::Result<String, Box<dyn std::error::Error>> {
+        Ok(match output {
+            ExplainOutput::Text => self.text(),
+            ExplainOutput::Json => serde_json::to_string_pretty(self)?,
+            ExplainOutput::Yaml => serde_yaml::to_string(self)?,
+        })
+    }
+}
+
+/// # Errors
+///
+/// Will return `Err` if function cannot connect to Kubernetes
+pub async fn explain(namespace: &str, pod_name: &str, output: ExplainOutput) -> Result<()> {
+    let exposure = exposure(namespace, pod_name).await?;
+    match exposure.render(output) {
+        Ok(document) => println!("{document}"),
+        Err(e) => eprintln!("can not render {pod_name}: {e}"),
+    }
+    Ok(())
+}
+
+/// Explain every pod matching a label selector or belonging to a
+/// replicaset, ending text output with a table of all routes.
+///
+/// # Errors
+///
+/// Will return `Err` if function cannot connect to Kubernetes
+pub async fn explain_matching(
+    namespace: &str,
+    selector: Option<&str>,
+    replicaset: Option<&str>,
+    output: ExplainOutput,
+) -> Result<()> {
+    let mut exposures = Vec::new();
+    for pod_name in matching_pods(namespace, selector, replicaset).await? {
+        exposures.push(exposure(namespace, &pod_name).await?);
+    }
+    let rendered: std::result::Result<String, Box<dyn std::error::Error>> = match output {
+        ExplainOutput::Text => Ok(exposures
+            .iter()
+            .map(|exposure| format!("== {} ==\n{}\n", exposure.pod, exposure.text()))
+            .chain([summary_table(&exposures)])
+            .collect::<Vec<_>>()
+            .join("\n")),
+        ExplainOutput::Json => serde_json::to_string_pretty(&exposures).map_err(Into::into),
+        ExplainOutput::Yaml => serde_yaml::to_string(&exposures).map_err(Into::into),
+    };
+    match rendered {
+        Ok(document) => println!("{document}"),
+        Err(e) => eprintln!("can not render pods: {e}"),
+    }
+    Ok(())
+}
+
+/// names of the pods selected by `selector`, or by the selector of
+/// `replicaset` when that is given instead
+async fn matching_pods(
+    namespace: &str,
+    selector: Option<&str>,
+    replicaset: Option<&str>,
+) -> Result<Vec<String>> {
+    let client = new(None).await?;
+    let label_selector = match replicaset {
+        Some(name) => {
+            let replica_sets: Api<ReplicaSet> = Api::namespaced(client.clone(), namespace);
+            let rs = with_timeout("get replicaset", replica_sets.get(name)).await?;
+            rs.spec
+                .and_then(|spec| spec.selector.match_labels)
+                .map(|labels| format_label_selector(&labels))
+                .unwrap_or_default()
+        }
+        None => selector.unwrap_or_default().to_string(),
+    };
+    let pods: Api<Pod> = Api::namespaced(client, namespace);
+    let pod_list = with_timeout(
+        "list pods",
+        pods.list(&ListParams::default().labels(&label_selector)),
+    )
+    .await?;
+    Ok(pod_list
+        .iter()
+        .filter_map(|pod| pod.metadata.name.clone())
+        .collect())
+}
+
+/// one row per route of every pod: host, path and certificate status
+#[must_use]
+pub fn summary_table(exposures: &[PodExposure]) -> String {
+    let mut rows = vec![[
+        "POD".to_string(),
+        "HOST".to_string(),
+        "PATH".to_string(),
+        "CERT".to_string(),
+    ]];
+    for exposure in exposures {
+        if exposure.routes.is_empty() {
+            rows.push([
+                exposure.pod.clone(),
+                "-".to_string(),
+                "-".to_string(),
+                "not exposed".to_string(),
+            ]);
+        }
+        for route in &exposure.routes {
+            let cert = match &route.cert {
+                Some(CertStatus {
+                    valid: true,
+                    days_left: Some(days),
+                    ..
+                }) => format!("valid, {days}d left"),
+                Some(CertStatus { error: Some(_), .. }) => "unreadable".to_string(),
+                Some(_) => "invalid".to_string(),
+                None if route.tls => "unchecked".to_string(),
+                None => "no TLS".to_string(),
+            };
+            rows.push([
+                exposure.pod.clone(),
+                route.host.clone().unwrap_or_else(|| "*".to_string()),
+                route.path.clone(),
+                cert,
+            ]);
+        }
+    }
+    let widths: Vec<usize> = (0..4)
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].len())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|(value, width)| format!("{value:width$}"))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 /// # Errors
 ///
 /// Will return `Err` if function cannot connect to Kubernetes
-pub async fn explain(namespace: &str, pod_name: &str) -> Result<()> {
+pub async fn exposure(namespace: &str, pod_name: &str) -> Result<PodExposure> {
     let client = new(None).await?;
     let pod = get_pod(&client, namespace, pod_name).await?;
 
-    check_replica_set(&client, &pod, namespace).await?;
-    check_services(&client, &pod, namespace).await?;
-    check_ingresses(&client, &pod, namespace).await?;
+    let owners = owners_of(&client, &pod, namespace).await?;
+    let services = services_for_pod(&client, &pod, namespace).await?;
+    let mut routes = routes_to(&client, &services, namespace).await?;
+    let (gateways, http_routes) = gateway::fetch(&client, Some(namespace)).await?;
+    routes.extend(gateway::routes_to(&http_routes, &gateways, &services));
+    add_certs(&mut routes).await;
 
     drop(client);
-    Ok(())
+    Ok(PodExposure {
+        pod: pod_name.to_string(),
+        namespace: namespace.to_string(),
+        owners,
+        services,
+        routes,
+    })
 }
 
 async fn get_pod(
@@ -32,32 +300,35 @@ async fn get_pod(
     pods.get(pod_name).await
 }
 
-async fn check_replica_set(client: &Client, pod: &Pod, namespace: &str) -> Result<()> {
+/// owners of the replicasets whose selector matches the pod
+async fn owners_of(client: &Client, pod: &Pod, namespace: &str) -> Result<Vec<Owner>> {
     let replica_sets: Api<ReplicaSet> = Api::namespaced(client.clone(), namespace);
-    let rs_list = replica_sets.list(&ListParams::default()).await?;
+    let rs_list = with_timeout(
+        "list replicasets",
+        replica_sets.list(&ListParams::default()),
+    )
+    .await?;
     drop(replica_sets);
 
-    for rs in rs_list.iter() {
-        if let Some(rs_ref) = rs.spec.as_ref() {
-            if let Some(selector) = rs_ref.selector.match_labels.clone() {
-                if matches_pod_labels(pod, &selector) {
-                    if let Some(owners) = rs.metadata.owner_references.clone() {
-                        for owner in owners {
-                            let kind = owner.kind;
-                            let name = owner.name;
-                            println!("Belongs to {kind} {name}");
-                        }
-                    }
-                }
-            }
-        }
-    }
-    Ok(())
+    Ok(rs_list
+        .iter()
+        .filter(|rs| {
+            rs.spec
+                .as_ref()
+                .and_then(|spec| spec.selector.match_labels.as_ref())
+                .is_some_and(|selector| matches_pod_labels(pod, selector))
+        })
+        .flat_map(|rs| rs.metadata.owner_references.clone().unwrap_or_default())
+        .map(|owner| Owner {
+            kind: owner.kind,
+            name: owner.name,
+        })
+        .collect())
 }
 
 async fn services_for_pod(client: &Client, pod: &Pod, namespace: &str) -> Result<Vec<String>> {
     let services: Api<Service> = Api::namespaced(client.clone(), namespace);
-    let service_list = services.list(&ListParams::default()).await?;
+    let service_list = with_timeout("list services", services.list(&ListParams::default())).await?;
     drop(services);
 
     Ok(service_list
@@ -75,102 +346,172 @@ async fn services_for_pod(client: &Client, pod: &Pod, namespace: &str) -> Result
         .collect())
 }
 
-async fn check_services(client: &Client, pod: &Pod, namespace: &str) -> Result<()> {
-    let services_names = services_for_pod(client, pod, namespace).await?;
-    for svc_name in &services_names {
-        println!("Implements Service {svc_name}");
-    }
-    Ok(())
-}
-
 fn matches_pod_labels(pod: &Pod, selector: &std::collections::BTreeMap<String, String>) -> bool {
     selector.iter().all(|(key, value)| {
         pod.metadata
             .labels
             .as_ref()
-            .map_or(false, |labels| labels.get(key.as_str()) == Some(value))
+            .is_some_and(|labels| labels.get(key.as_str()) == Some(value))
     })
 }
 
-async fn check_ingresses(client: &Client, pod: &Pod, namespace: &str) -> Result<()> {
+async fn routes_to(client: &Client, services: &[String], namespace: &str) -> Result<Vec<Route>> {
     let ingresses: Api<Ingress> = Api::namespaced(client.clone(), namespace);
-    let services = services_for_pod(client, pod, namespace).await?;
-
-    let ingress_list = ingresses.list(&ListParams::default()).await?;
+    let ingress_list =
+        with_timeout("list ingresses", ingresses.list(&ListParams::default())).await?;
     drop(ingresses);
-    for ingress in ingress_list.iter() {
-        if let Some(rules_ref) = &ingress.spec.as_ref() {
-            handle_ingress_rules(rules_ref.rules.as_ref(), &services, ingress);
-        }
-    }
-
-    Ok(())
+    Ok(ingress_list
+        .iter()
+        .flat_map(|ingress| ingress_routes(ingress, services))
+        .collect())
 }
 
-fn handle_ingress_rules(
-    rules: Option<&Vec<k8s_openapi::api::networking::v1::IngressRule>>,
-    services: &[String],
-    ingress: &Ingress,
-) {
-    if let Some(rules) = rules {
-        for rule in rules {
-            handle_ingress_rule(rule, services, ingress);
+/// the paths of `ingress` backed by one of `services`
+fn ingress_routes(ingress: &Ingress, services: &[String]) -> Vec<Route> {
+    let Some(spec) = ingress.spec.as_ref() else {
+        return vec![];
+    };
+    let ingress_name = ingress.metadata.name.clone().unwrap_or_default();
+    let tls_hosts: Vec<&String> = spec
+        .tls
+        .iter()
+        .flatten()
+        .flat_map(|tls| tls.hosts.iter().flatten())
+        .collect();
+    let mut routes = Vec::new();
+    for rule in spec.rules.iter().flatten() {
+        for path in rule.http.iter().flat_map(|http| &http.paths) {
+            let Some(backend) = &path.backend.service else {
+                continue;
+            };
+            if !services.contains(&backend.name) {
+                continue;
+            }
+            routes.push(Route {
+                kind: "Ingress".to_string(),
+                ingress: ingress_name.clone(),
+                host: rule.host.clone(),
+                path: path.path.clone().unwrap_or_default(),
+                service: backend.name.clone(),
+                port: backend.port.as_ref().and_then(|port| port.number),
+                tls: rule
+                    .host
+                    .as_ref()
+                    .is_some_and(|host| tls_hosts.contains(&host)),
+                cert: None,
+            });
         }
     }
+    routes
 }
 
-fn handle_ingress_rule(
-    rule: &k8s_openapi::api::networking::v1::IngressRule,
-    services: &[String],
-    ingress: &Ingress,
-) {
-    if let Some(http) = &rule.http {
-        for path in &http.paths {
-            handle_http_path(path, services, ingress, rule.host.as_deref());
+/// Read the certificate each TLS host serves, once per host.
+async fn add_certs(routes: &mut [Route]) {
+    let mut checked: Vec<CertStatus> = Vec::new();
+    for route in routes.iter_mut().filter(|route| route.tls) {
+        let Some(host) = route.host.clone() else {
+            continue;
+        };
+        if let Some(status) = checked.iter().find(|status| status.host == host) {
+            route.cert = Some(status.clone());
+            continue;
         }
+        let status = match analyze_tls_certificate(&host).await {
+            Ok(info) => {
+                let expires = DateTime::from_timestamp(info.expires.timestamp(), 0);
+                CertStatus {
+                    valid: info.is_valid,
+                    days_left: expires.map(|expires| (expires - Utc::now()).num_days()),
+                    expires,
+                    issued_by: Some(info.issued_by),
+                    error: None,
+                    host,
+                }
+            }
+            Err(e) => CertStatus {
+                valid: false,
+                expires: None,
+                days_left: None,
+                issued_by: None,
+                error: Some(e.to_string()),
+                host,
+            },
+        };
+        checked.push(status.clone());
+        route.cert = Some(status);
     }
 }
 
-fn handle_http_path(
-    path: &k8s_openapi::api::networking::v1::HTTPIngressPath,
-    services: &[String],
-    ingress: &Ingress,
-    host: Option<&str>,
-) {
-    if let Some(backend_service_name) = &path.backend.service {
-        if services.contains(&backend_service_name.name) {
-            print_ingress_info(ingress, host, path, backend_service_name);
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::serde_json::{from_value, json};
+
+    #[test]
+    fn test_ingress_routes() {
+        let ingress: Ingress = from_value(json!({
+            "metadata": {"name": "web"},
+            "spec": {
+                "tls": [{"hosts": ["web.example.com"], "secretName": "web-tls"}],
+                "rules": [
+                    {"host": "web.example.com", "http": {"paths": [
+                        {"path": "/", "pathType": "Prefix", "backend": {"service": {"name": "web", "port": {"number": 80}}}},
+                        {"path": "/other", "pathType": "Prefix", "backend": {"service": {"name": "other", "port": {"number": 80}}}}
+                    ]}},
+                    {"host": "plain.example.com", "http": {"paths": [
+                        {"path": "/", "pathType": "Prefix", "backend": {"service": {"name": "web", "port": {"number": 80}}}}
+                    ]}}
+                ]
+            }
+        }))
+        .unwrap();
+        let routes = ingress_routes(&ingress, &["web".to_string()]);
+        assert_eq!(routes.len(), 2);
+        assert!(routes[0].tls);
+        assert!(!routes[1].tls);
+
+        let exposure = PodExposure {
+            pod: "web-1".to_string(),
+            namespace: "default".to_string(),
+            owners: vec![],
+            services: vec!["web".to_string()],
+            routes,
+        };
+        assert_eq!(
+            exposure.text(),
+            "Implements Service web\nIngress web routes web.example.com/ to pod via Service web on port 80\nIngress web routes plain.example.com/ to pod via Service web on port 80\n  no TLS"
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&exposure.render(ExplainOutput::Json).unwrap()).unwrap();
+        assert_eq!(json["routes"][1]["tls"], json!(false));
     }
-}
 
-fn print_ingress_info(
-    ingress: &Ingress,
-    host: Option<&str>,
-    path: &k8s_openapi::api::networking::v1::HTTPIngressPath,
-    backend_service_name: &k8s_openapi::api::networking::v1::IngressServiceBackend,
-) {
-    let ingress_name = ingress.metadata.name.as_deref().unwrap_or("");
-    let path_txt = path.path.clone().unwrap_or_default();
-    if let Some(port_info) = backend_service_name.port.clone() {
-        let port_num = port_info.number.unwrap_or(0);
-        if port_num <= 0 {
-            println!(
-                "Ingress {} routes {}{} to pod via Service {}",
-                ingress_name,
-                host.unwrap_or(""),
-                path_txt,
-                backend_service_name.name
-            );
-        } else {
-            println!(
-                "Ingress {} routes {}{} to pod via Service {} on port {}",
-                ingress_name,
-                host.unwrap_or(""),
-                path_txt,
-                backend_service_name.name,
-                port_num
-            );
-        }
+    #[test]
+    fn test_summary_table() {
+        let route = |host: &str, tls: bool| Route {
+            kind: "Ingress".to_string(),
+            ingress: "web".to_string(),
+            host: Some(host.to_string()),
+            path: "/".to_string(),
+            service: "web".to_string(),
+            port: Some(80),
+            tls,
+            cert: None,
+        };
+        let exposure = |pod: &str, routes| PodExposure {
+            pod: pod.to_string(),
+            namespace: "default".to_string(),
+            owners: vec![],
+            services: vec![],
+            routes,
+        };
+        let table = summary_table(&[
+            exposure("web-1", vec![route("plain.example.com", false)]),
+            exposure("worker-1", vec![]),
+        ]);
+        assert_eq!(
+            table,
+            "POD       HOST               PATH  CERT\nweb-1     plain.example.com  /     no TLS\nworker-1  -                  -     not exposed"
+        );
     }
 }
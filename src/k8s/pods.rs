@@ -1,15 +1,24 @@
+use crate::config::settings;
 use crate::error::Result;
 use crate::k8s::events::{format_duration, list_events_for_resource, list_k8sevents};
+use crate::k8s::identity::identity_details;
+use crate::k8s::paging::Pager;
+use crate::k8s::probes::probes_for;
+use crate::k8s::problems::observe_pod;
+use crate::k8s::restarts::record_pod;
+use crate::k8s::retry::{with_retry, RetryPolicy};
+use crate::k8s::scheduling::{cached_nodes, diagnose, is_unscheduled};
+use crate::k8s::security::security_details;
 use crate::k8s::utils::format_label_selector;
 use crate::tui::data::RsPod;
 use chrono::{DateTime, Utc};
 use k8s_openapi::api::core::v1::Pod;
 use kube::api::ListParams;
-use kube::api::ObjectList;
 use kube::Api;
 use std::collections::BTreeMap;
 
-use super::client::new;
+use super::client_manager::get_client;
+use tracing::debug;
 
 fn calculate_pod_age(pod: &Pod) -> String {
     pod.metadata.creation_timestamp.as_ref().map_or_else(
@@ -35,7 +44,7 @@ fn get_pod_state(pod: &Pod) -> String {
             return match phase.as_str() {
                 "Pending" => "Pending".to_string(),
                 "Running" => {
-                    if status.conditions.as_ref().map_or(false, |conds| {
+                    if status.conditions.as_ref().is_some_and(|conds| {
                         conds
                             .iter()
                             .any(|c| c.type_ == "Ready" && c.status == "True")
@@ -60,65 +69,131 @@ fn get_pod_state(pod: &Pod) -> String {
 /// Will return `Err` if data can not be retrieved from k8s cluster api
 #[allow(clippy::significant_drop_tightening)]
 pub async fn list_rspods(selector: BTreeMap<String, String>) -> Result<Vec<RsPod>> {
-    let client = new(None).await?;
+    list_rspods_paged(selector, &|_| {}).await
+}
+
+/// Like `list_rspods`, handing `on_page` the pods so far while more pages
+/// are coming.
+///
+/// # Errors
+///
+/// Will return `Err` if data can not be retrieved from k8s cluster api
+pub async fn list_rspods_paged(
+    selector: BTreeMap<String, String>,
+    on_page: &(dyn Fn(&[RsPod]) + Sync),
+) -> Result<Vec<RsPod>> {
+    with_retry(&RetryPolicy::default(), || fetch_rspods(&selector, on_page)).await
+}
+
+async fn fetch_rspods(
+    selector: &BTreeMap<String, String>,
+    on_page: &(dyn Fn(&[RsPod]) + Sync),
+) -> Result<Vec<RsPod>> {
+    let client = get_client().await?;
 
     // Format the label selector from the BTreeMap
-    let label_selector = format_label_selector(&selector);
+    let label_selector = format_label_selector(selector);
 
     // Apply the label selector in ListParams
     let lp = ListParams::default().labels(&label_selector);
 
-    let pod_list: ObjectList<Pod> = Api::default_namespaced(client.clone()).list(&lp).await?;
+    let pods: Api<Pod> = Api::default_namespaced(client.clone());
+    let mut pager = Pager::new(&pods, lp, "list pods");
 
     let mut pod_vec = Vec::new();
 
     // get all events from the cluster to avoid calls for each pod
     let events = list_k8sevents(client).await?;
 
-    for pod in pod_list.items {
-        if let Some(owners) = &pod.metadata.owner_references {
-            for owner in owners {
-                let instance_name = &pod
-                    .metadata
-                    .name
-                    .clone()
-                    .unwrap_or_else(|| "unknown".to_string()); // Fixed typo in "unknown"
-
-                // Adjusted actual container count to reflect only ready containers
-                let actual_container_count = pod.status.as_ref().map_or(0, |status| {
-                    status
-                        .container_statuses
-                        .as_ref()
-                        .map_or(0, |container_statuses| {
-                            container_statuses.iter().filter(|cs| cs.ready).count()
-                        })
-                });
-
-                // Desired container count remains the same
-                let desired_container_count =
-                    pod.spec.as_ref().map_or(0, |spec| spec.containers.len());
-                let kind = &owner.kind;
-
-                let age = calculate_pod_age(&pod);
-                let status = get_pod_state(&pod);
-                let selectors = pod.metadata.labels.clone();
-
-                let resource_events =
-                    list_events_for_resource(events.clone(), instance_name).await?;
-
-                let data = RsPod {
-                    name: instance_name.to_string(),
-                    status: status.to_string(),
-                    description: kind.to_string(),
-                    age,
-                    containers: format!("{actual_container_count}/{desired_container_count}"),
-                    selectors,
-                    events: resource_events,
-                };
-
-                pod_vec.push(data);
+    let now = Utc::now().timestamp();
+    while let Some(page) = pager.next_page().await? {
+        if pager.take_restart() {
+            pod_vec.clear();
+        }
+        for pod in page {
+            record_pod(&pod, now);
+            observe_pod(&pod);
+            let scheduling = if is_unscheduled(&pod) {
+                match cached_nodes().await {
+                    Ok(nodes) => diagnose(&pod, &nodes),
+                    Err(e) => {
+                        debug!("can not read nodes to diagnose pending pods: {e}");
+                        vec![]
+                    }
+                }
+            } else {
+                vec![]
+            };
+            if let Some(owners) = &pod.metadata.owner_references {
+                for owner in owners {
+                    let instance_name = &pod
+                        .metadata
+                        .name
+                        .clone()
+                        .unwrap_or_else(|| "unknown".to_string()); // Fixed typo in "unknown"
+
+                    // Adjusted actual container count to reflect only ready containers
+                    let actual_container_count = pod.status.as_ref().map_or(0, |status| {
+                        status
+                            .container_statuses
+                            .as_ref()
+                            .map_or(0, |container_statuses| {
+                                container_statuses.iter().filter(|cs| cs.ready).count()
+                            })
+                    });
+
+                    // Desired container count remains the same
+                    let desired_container_count =
+                        pod.spec.as_ref().map_or(0, |spec| spec.containers.len());
+                    let kind = &owner.kind;
+
+                    let age = calculate_pod_age(&pod);
+                    let status = get_pod_state(&pod);
+                    let selectors = pod.metadata.labels.clone();
+
+                    let resource_events =
+                        list_events_for_resource(events.clone(), instance_name).await?;
+
+                    let data = RsPod {
+                        name: instance_name.to_string(),
+                        namespace: pod.metadata.namespace.clone().unwrap_or_default(),
+                        status: status.to_string(),
+                        description: kind.to_string(),
+                        age,
+                        containers: format!("{actual_container_count}/{desired_container_count}"),
+                        selectors,
+                        events: resource_events,
+                        security: security_details(&pod),
+                        identity: identity_details(&pod),
+                        probes: probes_for(&pod),
+                        scheduling: scheduling.clone(),
+                        extra: settings()
+                            .columns
+                            .pod
+                            .iter()
+                            .map(|column| column.value(&pod.metadata))
+                            .collect(),
+                        node: pod
+                            .spec
+                            .as_ref()
+                            .and_then(|spec| spec.node_name.clone())
+                            .unwrap_or_default(),
+                        owner: owner.name.clone(),
+                        images: pod.spec.as_ref().map_or_else(Vec::new, |spec| {
+                            spec.containers
+                                .iter()
+                                .filter_map(|c| c.image.clone())
+                                .collect()
+                        }),
+                    };
+
+                    pod_vec.push(data);
+                }
             }
         }
+        if !pager.is_done() {
+            on_page(&pod_vec);
+        }
     }
 
     Ok(pod_vec)
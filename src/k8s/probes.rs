@@ -0,0 +1,501 @@
+//!Run a pod's liveness, readiness and startup probes from here, through the
+//!api server's pod proxy, and keep their response times so slow probes
+//!show up before the kubelet starts killing containers.
+//!
+use crate::error::Result;
+use crate::k8s::client_manager::get_client;
+use crate::k8s::metrics_history::{self, record_samples, with_loaded, Sample};
+use crate::k8s::timeout::with_timeout;
+use chrono::Utc;
+use k8s_openapi::api::core::v1::{Container, Pod, Probe};
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+use kube::Api;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ProbeKind {
+    Startup,
+    Liveness,
+    Readiness,
+}
+
+impl fmt::Display for ProbeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Startup => write!(f, "startup"),
+            Self::Liveness => write!(f, "liveness"),
+            Self::Readiness => write!(f, "readiness"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProbeHandler {
+    Http {
+        scheme: String,
+        path: String,
+        /// a port number or the name of a container port
+        port: String,
+    },
+    Tcp {
+        port: String,
+    },
+    Exec {
+        command: Vec<String>,
+    },
+    Grpc {
+        port: i32,
+    },
+}
+
+impl fmt::Display for ProbeHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Http { scheme, path, port } => {
+                write!(f, "{} :{port}{path}", scheme.to_lowercase())
+            }
+            Self::Tcp { port } => write!(f, "tcp :{port}"),
+            Self::Exec { command } => write!(f, "exec {}", command.join(" ")),
+            Self::Grpc { port } => write!(f, "grpc :{port}"),
+        }
+    }
+}
+
+/// A probe as declared on a container, with the kubelet defaults filled in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProbeSpec {
+    pub container: String,
+    pub kind: ProbeKind,
+    pub handler: ProbeHandler,
+    pub timeout_secs: i32,
+    pub period_secs: i32,
+    pub initial_delay_secs: i32,
+    pub failure_threshold: i32,
+    pub success_threshold: i32,
+}
+
+/// what the probe simulator lets the user tweak, in display order
+pub const PROBE_FIELDS: [&str; 7] = [
+    "path",
+    "port",
+    "timeoutSeconds",
+    "periodSeconds",
+    "initialDelaySeconds",
+    "failureThreshold",
+    "successThreshold",
+];
+
+impl ProbeSpec {
+    /// series key in the metrics history
+    #[must_use]
+    pub fn key(&self, namespace: &str, pod: &str) -> String {
+        format!("probe/{namespace}/{pod}/{}/{}", self.container, self.kind)
+    }
+
+    /// value of one of `PROBE_FIELDS`, empty if the handler has no such field
+    #[must_use]
+    pub fn field(&self, index: usize) -> String {
+        match (index, &self.handler) {
+            (0, ProbeHandler::Http { path, .. }) => path.clone(),
+            (1, ProbeHandler::Http { port, .. } | ProbeHandler::Tcp { port }) => port.clone(),
+            (1, ProbeHandler::Grpc { port }) => port.to_string(),
+            (2, _) => self.timeout_secs.to_string(),
+            (3, _) => self.period_secs.to_string(),
+            (4, _) => self.initial_delay_secs.to_string(),
+            (5, _) => self.failure_threshold.to_string(),
+            (6, _) => self.success_threshold.to_string(),
+            _ => String::new(),
+        }
+    }
+
+    /// Set one of `PROBE_FIELDS` from user input.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` with a message for the user if the value is invalid
+    pub fn set_field(&mut self, index: usize, value: &str) -> core::result::Result<(), String> {
+        let number = || {
+            value
+                .trim()
+                .parse::<i32>()
+                .ok()
+                .filter(|n| *n >= 0)
+                .ok_or_else(|| format!("{} must be a number, got {value:?}", PROBE_FIELDS[index]))
+        };
+        match (index, &mut self.handler) {
+            (0, ProbeHandler::Http { path, .. }) => {
+                *path = if value.starts_with('/') {
+                    value.to_string()
+                } else {
+                    format!("/{value}")
+                };
+            }
+            (1, ProbeHandler::Http { port, .. } | ProbeHandler::Tcp { port }) => {
+                *port = value.trim().to_string();
+            }
+            (1, ProbeHandler::Grpc { port }) => *port = number()?,
+            (2, _) => self.timeout_secs = number()?.max(1),
+            (3, _) => self.period_secs = number()?.max(1),
+            (4, _) => self.initial_delay_secs = number()?,
+            (5, _) => self.failure_threshold = number()?.max(1),
+            (6, _) => self.success_threshold = number()?.max(1),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn yaml_name(&self) -> &'static str {
+        match self.kind {
+            ProbeKind::Startup => "startupProbe",
+            ProbeKind::Liveness => "livenessProbe",
+            ProbeKind::Readiness => "readinessProbe",
+        }
+    }
+
+    /// A strategic merge patch that applies this probe to `deployment`.
+    #[must_use]
+    pub fn patch_yaml(&self, deployment: &str) -> String {
+        let port = |port: &str| {
+            if port.parse::<u16>().is_ok() {
+                port.to_string()
+            } else {
+                format!("\"{port}\"")
+            }
+        };
+        let handler = match &self.handler {
+            ProbeHandler::Http { scheme, path, port: p } => format!(
+                "          httpGet:\n            scheme: {scheme}\n            path: {path}\n            port: {}\n",
+                port(p)
+            ),
+            ProbeHandler::Tcp { port: p } => {
+                format!("          tcpSocket:\n            port: {}\n", port(p))
+            }
+            ProbeHandler::Exec { command } => format!(
+                "          exec:\n            command: [{}]\n",
+                command
+                    .iter()
+                    .map(|c| format!("{c:?}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            ProbeHandler::Grpc { port } => format!("          grpc:\n            port: {port}\n"),
+        };
+        format!(
+            "# kubectl patch deployment {deployment} --patch-file probe-patch.yaml\n\
+             spec:\n  template:\n    spec:\n      containers:\n      - name: {}\n        {}:\n{handler}\
+             \x20         timeoutSeconds: {}\n          periodSeconds: {}\n          initialDelaySeconds: {}\n\
+             \x20         failureThreshold: {}\n          successThreshold: {}\n",
+            self.container,
+            self.yaml_name(),
+            self.timeout_secs,
+            self.period_secs,
+            self.initial_delay_secs,
+            self.failure_threshold,
+            self.success_threshold,
+        )
+    }
+}
+
+/// The deployment behind a replicaset pod: pod names are
+/// `<deployment>-<pod-template-hash>-<suffix>`.
+#[must_use]
+pub fn deployment_name(pod_name: &str, labels: Option<&BTreeMap<String, String>>) -> String {
+    labels
+        .and_then(|labels| labels.get("pod-template-hash"))
+        .and_then(|hash| pod_name.split_once(&format!("-{hash}-")))
+        .map_or_else(
+            || pod_name.to_string(),
+            |(deployment, _)| deployment.to_string(),
+        )
+}
+
+fn port_string(port: &IntOrString) -> String {
+    match port {
+        IntOrString::Int(port) => port.to_string(),
+        IntOrString::String(name) => name.clone(),
+    }
+}
+
+#[must_use]
+pub fn to_spec(container: &str, kind: ProbeKind, probe: &Probe) -> Option<ProbeSpec> {
+    let handler = if let Some(http) = &probe.http_get {
+        ProbeHandler::Http {
+            scheme: http.scheme.clone().unwrap_or_else(|| "HTTP".to_string()),
+            path: http.path.clone().unwrap_or_else(|| "/".to_string()),
+            port: port_string(&http.port),
+        }
+    } else if let Some(tcp) = &probe.tcp_socket {
+        ProbeHandler::Tcp {
+            port: port_string(&tcp.port),
+        }
+    } else if let Some(exec) = &probe.exec {
+        ProbeHandler::Exec {
+            command: exec.command.clone().unwrap_or_default(),
+        }
+    } else {
+        ProbeHandler::Grpc {
+            port: probe.grpc.as_ref()?.port,
+        }
+    };
+    Some(ProbeSpec {
+        container: container.to_string(),
+        kind,
+        handler,
+        timeout_secs: probe.timeout_seconds.unwrap_or(1),
+        period_secs: probe.period_seconds.unwrap_or(10),
+        initial_delay_secs: probe.initial_delay_seconds.unwrap_or(0),
+        failure_threshold: probe.failure_threshold.unwrap_or(3),
+        success_threshold: probe.success_threshold.unwrap_or(1),
+    })
+}
+
+fn container_probes(container: &Container) -> Vec<ProbeSpec> {
+    [
+        (ProbeKind::Startup, &container.startup_probe),
+        (ProbeKind::Liveness, &container.liveness_probe),
+        (ProbeKind::Readiness, &container.readiness_probe),
+    ]
+    .into_iter()
+    .filter_map(|(kind, probe)| to_spec(&container.name, kind, probe.as_ref()?))
+    .collect()
+}
+
+#[must_use]
+pub fn probes_for(pod: &Pod) -> Vec<ProbeSpec> {
+    pod.spec
+        .as_ref()
+        .map(|spec| spec.containers.iter().flat_map(container_probes).collect())
+        .unwrap_or_default()
+}
+
+/// named ports are looked up on the probed container
+fn resolve_port(pod: &Pod, container: &str, port: &str) -> String {
+    if port.parse::<u16>().is_ok() {
+        return port.to_string();
+    }
+    pod.spec
+        .as_ref()
+        .and_then(|spec| spec.containers.iter().find(|c| c.name == container))
+        .and_then(|c| c.ports.as_ref())
+        .and_then(|ports| ports.iter().find(|p| p.name.as_deref() == Some(port)))
+        .map_or_else(|| port.to_string(), |p| p.container_port.to_string())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProbeResult {
+    pub ok: bool,
+    pub response_time_ms: u64,
+    pub detail: String,
+}
+
+/// Run one http probe through the api server proxy.  Other probe types run
+/// inside the node and can not be reproduced from here.
+///
+/// # Errors
+///
+/// Will return `Err` if the pod proxy can not be reached at all
+pub async fn run_probe(pod: &Pod, spec: &ProbeSpec) -> Result<Option<ProbeResult>> {
+    let ProbeHandler::Http { scheme, path, port } = &spec.handler else {
+        return Ok(None);
+    };
+    let client = get_client().await?;
+    let namespace = pod
+        .metadata
+        .namespace
+        .clone()
+        .unwrap_or_else(|| client.default_namespace().to_string());
+    let name = pod.metadata.name.clone().unwrap_or_default();
+    let port = resolve_port(pod, &spec.container, port);
+    let target = if scheme.eq_ignore_ascii_case("https") {
+        format!("https:{name}:{port}")
+    } else {
+        format!("{name}:{port}")
+    };
+    let uri = format!("/api/v1/namespaces/{namespace}/pods/{target}/proxy{path}");
+    let request = hyper::http::Request::get(uri).body(vec![])?;
+
+    let probe_timeout = Duration::from_secs(u64::try_from(spec.timeout_secs.max(1)).unwrap_or(1));
+    let started = Instant::now();
+    let outcome = tokio::time::timeout(probe_timeout, client.request_text(request)).await;
+    let response_time_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+    let (ok, detail) = match outcome {
+        Ok(Ok(_)) => (true, "ok".to_string()),
+        Ok(Err(kube::Error::Api(response))) => (false, format!("http {}", response.code)),
+        Ok(Err(e)) => (false, e.to_string()),
+        Err(_) => (false, format!("timed out after {}s", spec.timeout_secs)),
+    };
+    Ok(Some(ProbeResult {
+        ok,
+        response_time_ms,
+        detail,
+    }))
+}
+
+async fn get_pod(pod_name: &str) -> Result<(Pod, String)> {
+    let client = get_client().await?;
+    let pods: Api<Pod> = Api::default_namespaced(client.clone());
+    let pod = with_timeout("get pod", pods.get(pod_name)).await?;
+    Ok((pod, client.default_namespace().to_string()))
+}
+
+/// Run a probe with tweaked parameters once, without recording it.
+///
+/// # Errors
+///
+/// Will return `Err` if the pod can not be read or the proxy can not be reached
+pub async fn run_tuned_probe(pod_name: &str, spec: &ProbeSpec) -> Result<Option<ProbeResult>> {
+    let (pod, _) = get_pod(pod_name).await?;
+    run_probe(&pod, spec).await
+}
+
+/// Run every probe of a pod in the default namespace and record the response
+/// times in the metrics history.
+///
+/// # Errors
+///
+/// Will return `Err` if the pod can not be read or the proxy can not be reached
+pub async fn run_pod_probes(pod_name: &str) -> Result<Vec<(ProbeSpec, ProbeResult)>> {
+    let (pod, namespace) = get_pod(pod_name).await?;
+
+    let mut results = Vec::new();
+    for spec in probes_for(&pod) {
+        if let Some(result) = run_probe(&pod, &spec).await? {
+            results.push((spec, result));
+        }
+    }
+    let now = Utc::now().timestamp();
+    let samples: Vec<(String, Sample)> = results
+        .iter()
+        .map(|(spec, result)| {
+            (
+                spec.key(&namespace, pod_name),
+                Sample {
+                    at: now,
+                    #[allow(clippy::cast_precision_loss)]
+                    value: result.response_time_ms as f64,
+                },
+            )
+        })
+        .collect();
+    // the store is written out from here now and then
+    if let Err(e) = tokio::task::spawn_blocking(move || record_samples(&samples)).await {
+        debug!("can not record probe samples: {e}");
+    }
+    Ok(results)
+}
+
+/// Drop the probe history of a pod that is gone.
+pub fn forget_pod(namespace: &str, pod: &str) {
+    metrics_history::forget(&format!("probe/{namespace}/{pod}/"));
+}
+
+/// How a probe's p95 latency compares to its own timeout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Slo {
+    /// under half the timeout
+    Met,
+    /// getting close to the timeout
+    AtRisk,
+    /// at or over the timeout, the kubelet would count these as failures
+    Breached,
+}
+
+#[must_use]
+pub fn slo(p95_ms: f64, timeout_secs: i32) -> Slo {
+    let timeout_ms = f64::from(timeout_secs.max(1)) * 1000.0;
+    if p95_ms >= timeout_ms {
+        Slo::Breached
+    } else if p95_ms >= timeout_ms / 2.0 {
+        Slo::AtRisk
+    } else {
+        Slo::Met
+    }
+}
+
+/// latency summary of one probe from the metrics history
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProbeLatency {
+    pub last_ms: f64,
+    pub p95_ms: f64,
+    pub samples: usize,
+    pub slo: Slo,
+}
+
+#[must_use]
+pub fn latency(spec: &ProbeSpec, namespace: &str, pod: &str) -> Option<ProbeLatency> {
+    let key = spec.key(namespace, pod);
+    // the render asks, a store still loading shows as not run yet
+    with_loaded(|store| {
+        let p95_ms = store.percentile(&key, 95.0)?;
+        Some(ProbeLatency {
+            last_ms: store.last(&key)?.value,
+            p95_ms,
+            samples: store.len(&key),
+            slo: slo(p95_ms, spec.timeout_secs),
+        })
+    })
+    .flatten()
+}
+
+static SCHEDULE: OnceLock<Option<Duration>> = OnceLock::new();
+
+/// Run the probes of listed pods in the background every `interval`, `None`
+/// leaves probing to the user.  Later calls are ignored.
+pub fn configure_schedule(interval: Option<Duration>) {
+    let _ = SCHEDULE.set(interval);
+}
+
+#[must_use]
+pub fn schedule() -> Option<Duration> {
+    SCHEDULE.get().copied().flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::serde_json::{from_value, json};
+
+    #[test]
+    fn test_probes_for_fills_defaults() {
+        let pod: Pod = from_value(json!({
+            "metadata": { "name": "web-1" },
+            "spec": { "containers": [{
+                "name": "web",
+                "ports": [{ "name": "http", "containerPort": 8080 }],
+                "livenessProbe": { "httpGet": { "path": "/healthz", "port": "http" } },
+                "readinessProbe": { "tcpSocket": { "port": 8080 }, "timeoutSeconds": 2 }
+            }]}
+        }))
+        .unwrap();
+        let probes = probes_for(&pod);
+        assert_eq!(probes.len(), 2);
+        assert_eq!(probes[0].kind, ProbeKind::Liveness);
+        assert_eq!(probes[0].handler.to_string(), "http :http/healthz");
+        assert_eq!(probes[0].timeout_secs, 1);
+        assert_eq!(probes[1].timeout_secs, 2);
+        assert_eq!(resolve_port(&pod, "web", "http"), "8080");
+
+        let mut tuned = probes[0].clone();
+        tuned.set_field(0, "ready").unwrap();
+        tuned.set_field(2, "3").unwrap();
+        assert!(tuned.set_field(5, "many").is_err());
+        let patch = tuned.patch_yaml("web");
+        assert!(patch.contains("      - name: web\n        livenessProbe:\n          httpGet:\n"));
+        assert!(patch.contains("            path: /ready\n            port: \"http\"\n"));
+        assert!(patch.contains("          timeoutSeconds: 3\n"));
+
+        let labels = BTreeMap::from([("pod-template-hash".to_string(), "5d8f7".to_string())]);
+        assert_eq!(
+            deployment_name("web-api-5d8f7-x2x9q", Some(&labels)),
+            "web-api"
+        );
+
+        assert_eq!(slo(100.0, 1), Slo::Met);
+        assert_eq!(slo(600.0, 1), Slo::AtRisk);
+        assert_eq!(slo(1000.0, 1), Slo::Breached);
+    }
+}
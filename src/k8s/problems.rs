@@ -0,0 +1,248 @@
+//!Pod problems rolled up to the replicaset that owns the pod, so a crash
+//!looping replica shows on the replicaset list without drilling down.  Fed
+//!from every pod navipod fetches and from pod watch updates.
+//!
+use crate::k8s::audit;
+use crate::k8s::hooks;
+use crate::k8s::journal;
+use crate::k8s::restarts;
+use crate::k8s::timeline;
+use crate::k8s::watch::WatchUpdate;
+use crate::tui::theme::symbols;
+use chrono::{DateTime, Utc};
+use k8s_openapi::api::core::v1::Pod;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// pending for longer than this counts as a problem
+pub const PENDING_GRACE_SECS: i64 = 300;
+
+const IMAGE_PULL_REASONS: [&str; 4] = [
+    "ErrImagePull",
+    "ImagePullBackOff",
+    "InvalidImageName",
+    "ErrImageNeverPull",
+];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct PodState {
+    replicaset: String,
+    crash_looping: bool,
+    image_pull: bool,
+    pending_since: Option<DateTime<Utc>>,
+}
+
+fn pod_state(pod: &Pod) -> Option<PodState> {
+    let replicaset = pod
+        .metadata
+        .owner_references
+        .iter()
+        .flatten()
+        .find(|owner| owner.kind == "ReplicaSet")?
+        .name
+        .clone();
+    let status = pod.status.as_ref();
+    let waiting_reasons: Vec<&str> = status
+        .into_iter()
+        .flat_map(|s| {
+            s.container_statuses
+                .iter()
+                .chain(s.init_container_statuses.iter())
+                .flatten()
+        })
+        .filter_map(|cs| cs.state.as_ref()?.waiting.as_ref()?.reason.as_deref())
+        .collect();
+    let pending = status.and_then(|s| s.phase.as_deref()) == Some("Pending");
+    Some(PodState {
+        replicaset,
+        crash_looping: waiting_reasons.contains(&"CrashLoopBackOff"),
+        image_pull: waiting_reasons
+            .iter()
+            .any(|reason| IMAGE_PULL_REASONS.contains(reason)),
+        pending_since: pending
+            .then(|| pod.metadata.creation_timestamp.as_ref().map(|t| t.0))
+            .flatten(),
+    })
+}
+
+/// counts of problem pods of one replicaset
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProblemCounts {
+    pub crash_looping: usize,
+    pub image_pull: usize,
+    pub pending: usize,
+}
+
+impl ProblemCounts {
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.crash_looping == 0 && self.image_pull == 0 && self.pending == 0
+    }
+
+    /// ie: "⚠ 2 crashloop 1 pull", empty without problems
+    #[must_use]
+    pub fn badges(&self) -> String {
+        let badges: Vec<String> = [
+            (self.crash_looping, "crashloop"),
+            (self.image_pull, "pull"),
+            (self.pending, "pending>5m"),
+        ]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, name)| format!("{count} {name}"))
+        .collect();
+        if badges.is_empty() {
+            String::new()
+        } else {
+            format!("{} {}", symbols().warning, badges.join(" "))
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    /// keyed by namespace/pod
+    pods: HashMap<String, PodState>,
+}
+
+impl Registry {
+    fn observe(&mut self, pod: &Pod) {
+        let key = pod_key(pod);
+        match pod_state(pod) {
+            Some(state) if pod.metadata.deletion_timestamp.is_none() => {
+                self.pods.insert(key, state);
+            }
+            _ => {
+                self.pods.remove(&key);
+            }
+        }
+    }
+
+    fn counts(&self, replicaset: &str, now: DateTime<Utc>) -> ProblemCounts {
+        let mut counts = ProblemCounts::default();
+        for state in self.pods.values().filter(|s| s.replicaset == replicaset) {
+            counts.crash_looping += usize::from(state.crash_looping);
+            counts.image_pull += usize::from(state.image_pull);
+            counts.pending += usize::from(state.pending_since.is_some_and(|since| {
+                now.signed_duration_since(since).num_seconds() > PENDING_GRACE_SECS
+            }));
+        }
+        counts
+    }
+}
+
+fn pod_key(pod: &Pod) -> String {
+    format!(
+        "{}/{}",
+        pod.metadata.namespace.as_deref().unwrap_or_default(),
+        pod.metadata.name.as_deref().unwrap_or_default()
+    )
+}
+
+static REGISTRY: Mutex<Option<Registry>> = Mutex::new(None);
+
+fn with_registry<T>(f: impl FnOnce(&mut Registry) -> T) -> Option<T> {
+    let mut registry = REGISTRY.lock().ok()?;
+    Some(f(registry.get_or_insert_with(Registry::default)))
+}
+
+pub fn observe_pod(pod: &Pod) {
+    with_registry(|registry| registry.observe(pod));
+}
+
+pub fn apply_watch_update(update: &WatchUpdate<Pod>) {
+    with_registry(|registry| match update {
+        WatchUpdate::Applied(pod) => registry.observe(pod),
+        WatchUpdate::Deleted(pod) => {
+            registry.pods.remove(&pod_key(pod));
+        }
+        WatchUpdate::Resynced(pods) => {
+            registry.pods.clear();
+            for pod in pods {
+                registry.observe(pod);
+            }
+        }
+    });
+}
+
+/// Keep the registry and everything else following pods current from an
+/// update of a pod watch.
+pub fn on_pod_update(update: &WatchUpdate<Pod>) {
+    apply_watch_update(update);
+    match update {
+        WatchUpdate::Applied(pod) => restarts::record_pod(pod, Utc::now().timestamp()),
+        WatchUpdate::Deleted(pod) => restarts::forget_pod(pod),
+        WatchUpdate::Resynced(pods) => restarts::retain_pods(pods),
+    }
+    audit::record_pod_change(update);
+    journal::record("Pod", update);
+    timeline::record(update);
+    hooks::on_pod_update(update);
+}
+
+#[must_use]
+pub fn problem_counts(replicaset: &str) -> ProblemCounts {
+    with_registry(|registry| registry.counts(replicaset, Utc::now())).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::serde_json::{from_value, json};
+
+    fn pod(name: &str, phase: &str, waiting: Option<&str>) -> Pod {
+        from_value(json!({
+            "metadata": {
+                "name": name,
+                "namespace": "default",
+                "creationTimestamp": "2024-03-01T10:00:00Z",
+                "ownerReferences": [{"apiVersion": "apps/v1", "kind": "ReplicaSet", "name": "web-5d8f7", "uid": "1"}]
+            },
+            "status": {
+                "phase": phase,
+                "containerStatuses": waiting.map(|reason| vec![json!({
+                    "name": "web", "image": "web", "imageID": "", "ready": false, "restartCount": 4,
+                    "state": {"waiting": {"reason": reason}}
+                })])
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_problem_counts() {
+        let mut registry = Registry::default();
+        registry.observe(&pod("a", "Running", Some("CrashLoopBackOff")));
+        registry.observe(&pod("b", "Pending", Some("ImagePullBackOff")));
+        registry.observe(&pod("c", "Running", None));
+        let at = |time: &str| {
+            DateTime::parse_from_rfc3339(time)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+
+        let early = registry.counts("web-5d8f7", at("2024-03-01T10:04:00Z"));
+        assert_eq!(
+            early,
+            ProblemCounts {
+                crash_looping: 1,
+                image_pull: 1,
+                pending: 0
+            }
+        );
+        let late = registry.counts("web-5d8f7", at("2024-03-01T10:06:00Z"));
+        assert_eq!(late.badges(), "⚠ 1 crashloop 1 pull 1 pending>5m");
+        assert!(registry
+            .counts("other", at("2024-03-01T10:06:00Z"))
+            .is_empty());
+
+        // a recovered pod clears its problems
+        registry.observe(&pod("a", "Running", None));
+        assert_eq!(
+            registry
+                .counts("web-5d8f7", at("2024-03-01T10:04:00Z"))
+                .crash_looping,
+            0
+        );
+    }
+}
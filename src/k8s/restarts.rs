@@ -0,0 +1,240 @@
+//!Restart counts only ever go up, so a container that crash loops a few
+//!times an hour looks no different from one that restarted once last week.
+//!Every time a pod is observed the count deltas are added to a per-minute
+//!histogram per container, which gives a recent trend to show next to the
+//!absolute count.
+//!
+use crate::k8s::cache::memory::estimate;
+use crate::tui::theme::symbols;
+use k8s_openapi::api::core::v1::Pod;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+
+/// window the trend summarizes
+pub const TREND_WINDOW_MINUTES: i64 = 10;
+/// histogram buckets older than this are dropped
+const RETAIN_MINUTES: i64 = 60;
+
+#[derive(Debug, Default)]
+struct History {
+    last_count: i32,
+    /// (minute, restarts in that minute), oldest first
+    buckets: VecDeque<(i64, u32)>,
+}
+
+impl History {
+    fn record(&mut self, count: i32, minute: i64) {
+        // a lower count means the pod was replaced, start over from it
+        let delta = u32::try_from(count - self.last_count).unwrap_or(0);
+        self.last_count = count;
+        if delta > 0 {
+            match self.buckets.back_mut() {
+                Some((m, restarts)) if *m == minute => *restarts += delta,
+                _ => self.buckets.push_back((minute, delta)),
+            }
+        }
+        while self
+            .buckets
+            .front()
+            .is_some_and(|(m, _)| *m <= minute - RETAIN_MINUTES)
+        {
+            self.buckets.pop_front();
+        }
+    }
+
+    fn recent(&self, minute: i64) -> u32 {
+        self.buckets
+            .iter()
+            .filter(|(m, _)| *m > minute - TREND_WINDOW_MINUTES)
+            .map(|(_, restarts)| restarts)
+            .sum()
+    }
+
+    /// one character per minute of the trend window, oldest first
+    fn sparkline(&self, minute: i64) -> String {
+        let per_minute: Vec<u32> = (minute - TREND_WINDOW_MINUTES + 1..=minute)
+            .map(|m| {
+                self.buckets
+                    .iter()
+                    .find(|(bucket, _)| *bucket == m)
+                    .map_or(0, |(_, restarts)| *restarts)
+            })
+            .collect();
+        let max = per_minute.iter().copied().max().unwrap_or(0).max(1);
+        per_minute
+            .into_iter()
+            .map(|restarts| {
+                let sparks = symbols().sparks;
+                let level = (restarts * (sparks.len() as u32 - 1)).div_ceil(max);
+                sparks[level as usize]
+            })
+            .collect()
+    }
+}
+
+static HISTORY: Mutex<Option<HashMap<String, History>>> = Mutex::new(None);
+
+/// estimated bytes of every container's histogram
+#[must_use]
+pub fn estimated_bytes() -> usize {
+    HISTORY
+        .lock()
+        .ok()
+        .and_then(|history| history.as_ref().map(estimate))
+        .unwrap_or(0)
+}
+
+fn key(namespace: &str, pod: &str, container: &str) -> String {
+    format!("{namespace}/{pod}/{container}")
+}
+
+/// Add the restart counts of every container of a pod to the histogram.
+pub fn record_pod(pod: &Pod, now_secs: i64) {
+    let namespace = pod.metadata.namespace.as_deref().unwrap_or_default();
+    let name = pod.metadata.name.as_deref().unwrap_or_default();
+    let Some(status) = &pod.status else {
+        return;
+    };
+    let Ok(mut history) = HISTORY.lock() else {
+        return;
+    };
+    let history = history.get_or_insert_with(HashMap::new);
+    let statuses = status
+        .container_statuses
+        .iter()
+        .chain(status.init_container_statuses.iter())
+        .flatten();
+    for cs in statuses {
+        history
+            .entry(key(namespace, name, &cs.name))
+            .or_insert_with(|| History {
+                // the first sighting is the baseline, not a burst of restarts
+                last_count: cs.restart_count,
+                buckets: VecDeque::new(),
+            })
+            .record(cs.restart_count, now_secs / 60);
+    }
+}
+
+fn pod_key(pod: &Pod) -> String {
+    format!(
+        "{}/{}/",
+        pod.metadata.namespace.as_deref().unwrap_or_default(),
+        pod.metadata.name.as_deref().unwrap_or_default()
+    )
+}
+
+/// Drop the histograms of a deleted pod's containers.
+pub fn forget_pod(pod: &Pod) {
+    let prefix = pod_key(pod);
+    if let Ok(mut history) = HISTORY.lock() {
+        if let Some(history) = history.as_mut() {
+            history.retain(|key, _| !key.starts_with(&prefix));
+        }
+    }
+}
+
+/// Keep only the histograms of `pods`, the complete list of a relist, so a
+/// long session does not hold on to every pod it ever saw.
+pub fn retain_pods(pods: &[Pod]) {
+    let live: HashSet<String> = pods.iter().map(pod_key).collect();
+    if let Ok(mut history) = HISTORY.lock() {
+        if let Some(history) = history.as_mut() {
+            history.retain(|key, _| {
+                // the key is namespace/pod/container, keep namespace/pod/
+                key.rsplit_once('/')
+                    .is_some_and(|(pod, _)| live.contains(&format!("{pod}/")))
+            });
+        }
+    }
+}
+
+/// The restart count last observed for a container, in any namespace when
+/// `namespace` is `None`.
+#[must_use]
+pub fn restart_count(namespace: Option<&str>, pod: &str, container: &str) -> Option<i32> {
+    let history = HISTORY.lock().ok()?;
+    let history = history.as_ref()?;
+    match namespace {
+        Some(namespace) => history.get(&key(namespace, pod, container)),
+        None => {
+            let suffix = format!("/{pod}/{container}");
+            history
+                .iter()
+                .find(|(key, _)| key.ends_with(&suffix))
+                .map(|(_, history)| history)
+        }
+    }
+    .map(|history| history.last_count)
+}
+
+/// ie: "+3 in last 10m ▁▁▃▁▁▁█▁▁▁", empty when nothing restarted recently
+#[must_use]
+pub fn trend(namespace: &str, pod: &str, container: &str, now_secs: i64) -> String {
+    let minute = now_secs / 60;
+    HISTORY
+        .lock()
+        .ok()
+        .and_then(|history| {
+            let history = history.as_ref()?.get(&key(namespace, pod, container))?;
+            let recent = history.recent(minute);
+            (recent > 0).then(|| {
+                format!(
+                    "+{recent} in last {TREND_WINDOW_MINUTES}m {}",
+                    history.sparkline(minute)
+                )
+            })
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_counts_recent_restarts() {
+        let mut history = History {
+            last_count: 2,
+            buckets: VecDeque::new(),
+        };
+        history.record(2, 100);
+        history.record(4, 101);
+        history.record(5, 109);
+        assert_eq!(history.recent(109), 3);
+        // the burst at minute 101 has left the window
+        assert_eq!(history.recent(111), 1);
+        assert_eq!(history.sparkline(110).chars().count(), 10);
+        assert!(history.sparkline(110).starts_with('█'));
+        assert!(history.sparkline(110).ends_with("▅▁"));
+
+        // a replaced pod starts counting again from zero
+        history.record(0, 112);
+        assert_eq!(history.recent(112), 1);
+    }
+
+    #[test]
+    fn test_gone_pods_are_pruned() {
+        use k8s_openapi::serde_json::{from_value, json};
+        let pod = |name: &str| -> Pod {
+            from_value(json!({
+                "metadata": {"name": name, "namespace": "prune-test"},
+                "status": {"containerStatuses": [{
+                    "name": "app", "image": "app", "imageID": "", "ready": true,
+                    "restartCount": 1
+                }]}
+            }))
+            .unwrap()
+        };
+        let (web, web2, db) = (pod("web"), pod("web-2"), pod("db"));
+        for pod in [&web, &web2, &db] {
+            record_pod(pod, 0);
+        }
+        retain_pods(&[web.clone(), web2]);
+        assert_eq!(restart_count(Some("prune-test"), "db", "app"), None);
+        assert_eq!(restart_count(Some("prune-test"), "web", "app"), Some(1));
+        forget_pod(&web);
+        assert_eq!(restart_count(Some("prune-test"), "web", "app"), None);
+        assert_eq!(restart_count(Some("prune-test"), "web-2", "app"), Some(1));
+    }
+}
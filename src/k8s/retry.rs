@@ -0,0 +1,213 @@
+//!Retry k8s api calls that failed for reasons likely to go away on their
+//!own: throttling, server errors, timeouts, dropped connections and expired
+//!credentials.
+//!
+use crate::error::{Error, Result};
+use crate::k8s::cache::throttle;
+use crate::k8s::client_manager;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::debug;
+
+const TOO_MANY_REQUESTS: u16 = 429;
+
+static RETRIES: AtomicU64 = AtomicU64::new(0);
+
+/// number of calls retried since startup
+#[must_use]
+pub fn retries() -> u64 {
+    RETRIES.load(Ordering::Relaxed)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Retryable {
+    /// credentials were rejected, retry on a freshly built client
+    Auth,
+    /// throttling, server errors, timeouts and network errors
+    Transient,
+    Fatal,
+}
+
+#[must_use]
+pub fn classify(e: &Error) -> Retryable {
+    match e {
+        Error::Auth(_) => Retryable::Auth,
+        Error::Kube(kube::Error::Api(response))
+            if matches!(response.code, TOO_MANY_REQUESTS | 500..=599) =>
+        {
+            Retryable::Transient
+        }
+        Error::Network(_) | Error::Timeout { .. } => Retryable::Transient,
+        Error::Shared(e) => classify(e),
+        _ => Retryable::Fatal,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// total attempts including the first one
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// randomize delays so concurrent fetchers do not retry in lockstep
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(2),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// exponential backoff for the given retry, 0 being the first retry
+    #[must_use]
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay)
+    }
+
+    fn delay(&self, retry: u32) -> Duration {
+        let backoff = self.backoff(retry);
+        if !self.jitter {
+            return backoff;
+        }
+        // full jitter, the clock is random enough to spread retries out
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.subsec_nanos());
+        let millis = u64::try_from(backoff.as_millis()).unwrap_or(u64::MAX);
+        Duration::from_millis(u64::from(nanos) % millis.saturating_add(1))
+    }
+}
+
+/// Run `op` until it succeeds, fails with an error that is not worth
+/// retrying, or the policy runs out of attempts.  Each attempt goes through
+/// `client_manager::with_auth_retry`, so `op` has to fetch its client from
+/// the client manager on every call.  This is the one retry layer: `op`
+/// should not retry itself, and should carry its own timeout.
+///
+/// # Errors
+///
+/// Will return `Err` with the last error if no attempt succeeded
+pub async fn with_retry<T, F, Fut>(policy: &RetryPolicy, op: F) -> Result<T>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut retry = 0;
+    loop {
+        let e = match client_manager::with_auth_retry(&op).await {
+            Ok(v) => return Ok(v),
+            Err(e) => e,
+        };
+        if matches!(&e, Error::Kube(kube::Error::Api(response)) if response.code == TOO_MANY_REQUESTS)
+        {
+            throttle::pressure("api server answered 429");
+        }
+        if retry + 1 >= policy.max_attempts {
+            return Err(e);
+        }
+        match classify(&e) {
+            // the fresh client was rejected too, a real denial
+            Retryable::Fatal | Retryable::Auth => return Err(e),
+            Retryable::Transient => {
+                let delay = policy.delay(retry);
+                debug!("retrying in {delay:?} after: {e}");
+                tokio::time::sleep(delay).await;
+            }
+        }
+        RETRIES.fetch_add(1, Ordering::Relaxed);
+        retry += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kube::core::ErrorResponse;
+    use std::sync::atomic::AtomicU32;
+
+    fn api_error(code: u16) -> Error {
+        Error::from(kube::Error::Api(ErrorResponse {
+            status: "Failure".to_string(),
+            message: String::new(),
+            reason: String::new(),
+            code,
+        }))
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify(&api_error(401)), Retryable::Auth);
+        assert_eq!(classify(&api_error(429)), Retryable::Transient);
+        assert_eq!(classify(&api_error(503)), Retryable::Transient);
+        assert_eq!(classify(&api_error(404)), Retryable::Fatal);
+        let timeout = Error::Timeout {
+            operation: "list pods".to_string(),
+            after: Duration::from_secs(1),
+        };
+        assert_eq!(classify(&timeout), Retryable::Transient);
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(0), Duration::from_millis(200));
+        assert_eq!(policy.backoff(2), Duration::from_millis(800));
+        assert_eq!(policy.backoff(10), Duration::from_secs(2));
+        assert!(policy.delay(1) <= policy.backoff(1));
+    }
+
+    #[tokio::test]
+    async fn test_with_retry_stops_on_fatal_and_retries_transient() {
+        let policy = RetryPolicy {
+            base_delay: Duration::from_millis(1),
+            ..RetryPolicy::default()
+        };
+
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = with_retry(&policy, || async {
+            calls.fetch_add(1, Ordering::Relaxed);
+            Err(api_error(404))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+        let calls = AtomicU32::new(0);
+        let result = with_retry(&policy, || async {
+            if calls.fetch_add(1, Ordering::Relaxed) == 0 {
+                Err(api_error(503))
+            } else {
+                Ok(7)
+            }
+        })
+        .await;
+        assert_eq!(result.ok(), Some(7));
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn test_with_retry_tries_a_fresh_client_once() {
+        let policy = RetryPolicy {
+            base_delay: Duration::from_millis(1),
+            ..RetryPolicy::default()
+        };
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = with_retry(&policy, || async {
+            calls.fetch_add(1, Ordering::Relaxed);
+            Err(api_error(401))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+    }
+}
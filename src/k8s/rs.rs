@@ -1,16 +1,22 @@
+use crate::config::settings;
 use crate::error::Result;
+use crate::k8s::deprecations::{cached_cluster_minor_version, deprecations};
 use crate::k8s::events::{format_duration, list_events_for_resource, list_k8sevents};
+use crate::k8s::list_filter::ListFilter;
+use crate::k8s::paging::Pager;
+use crate::k8s::retry::{with_retry, RetryPolicy};
+use crate::k8s::timeout::with_timeout;
 use crate::k8s::utils::format_label_selector;
 use crate::tui::data::Rs;
 use k8s_openapi::api::apps::v1::ReplicaSet;
 use kube::api::ListParams;
 use kube::api::ObjectList;
-use kube::{Api, Client};
+use kube::Api;
 use std::collections::BTreeMap;
 
 use chrono::{DateTime, Utc};
 
-use super::client::new;
+use super::client_manager::get_client;
 
 fn calculate_rs_age(rs: &ReplicaSet) -> String {
     rs.metadata.creation_timestamp.as_ref().map_or_else(
@@ -29,54 +35,95 @@ fn calculate_rs_age(rs: &ReplicaSet) -> String {
 /// Will return `Err` if data can not be retrieved from k8s cluster api
 #[allow(clippy::significant_drop_tightening)]
 pub async fn list_replicas() -> Result<Vec<Rs>> {
-    let client = new(None).await?;
+    list_replicas_paged(&ListFilter::default(), &|_| {}).await
+}
 
-    let rs_list: ObjectList<ReplicaSet> = Api::default_namespaced(client.clone())
-        .list(&ListParams::default())
-        .await?;
+/// Like `list_replicas`, only those `filter` selects, handing `on_page`
+/// the replicasets so far while more pages of a large namespace are coming.
+///
+/// # Errors
+///
+/// Will return `Err` if data can not be retrieved from k8s cluster api
+pub async fn list_replicas_paged(
+    filter: &ListFilter,
+    on_page: &(dyn Fn(&[Rs]) + Sync),
+) -> Result<Vec<Rs>> {
+    with_retry(&RetryPolicy::default(), || fetch_replicas(filter, on_page)).await
+}
+
+async fn fetch_replicas(filter: &ListFilter, on_page: &(dyn Fn(&[Rs]) + Sync)) -> Result<Vec<Rs>> {
+    let client = get_client().await?;
+
+    let replicasets: Api<ReplicaSet> = Api::default_namespaced(client.clone());
+    let lp = filter.apply(ListParams::default());
+    let mut pager = Pager::new(&replicasets, lp, "list replicasets");
 
     let mut rs_vec = Vec::new();
 
+    // a cluster that hides its version still gets deprecation badges
+    let cluster_minor = cached_cluster_minor_version(&client).await;
+
     // get all events from the cluster to avoid calls for each rs
     let events = list_k8sevents(client).await?;
 
-    for rs in rs_list.items {
-        if let Some(owners) = &rs.metadata.owner_references {
-            for owner in owners {
-                let selectors = rs.metadata.labels.clone();
-
-                let age = calculate_rs_age(&rs);
-                let instance_name = &rs.metadata.name.as_deref().unwrap_or("unknown").to_string();
-                let f_instance_name = format!("{instance_name} "); //padding for just high level
-                let desired_replicas = &rs
-                    .spec
-                    .as_ref()
-                    .map_or(0, |spec| spec.replicas.unwrap_or(0));
-                let ready_replicas = &rs
-                    .status
-                    .as_ref()
-                    .map_or(0, |status| status.ready_replicas.unwrap_or(0));
-                let kind = &owner.kind;
-                let owner_name = &owner.name;
-
-                let resource_events =
-                    list_events_for_resource(events.clone(), &f_instance_name).await?;
-                let data = Rs {
-                    name: instance_name.to_string(),
-                    pods: format!("{ready_replicas}/{desired_replicas}"),
-                    age,
-                    description: kind.to_string(),
-                    owner: owner_name.to_owned(),
-                    selectors,
-                    events: resource_events,
-                };
-
-                if desired_replicas <= &0 {
-                    continue;
-                };
-                rs_vec.push(data);
+    while let Some(page) = pager.next_page().await? {
+        if pager.take_restart() {
+            rs_vec.clear();
+        }
+        for rs in page {
+            if let Some(owners) = &rs.metadata.owner_references {
+                for owner in owners {
+                    let selectors = rs.metadata.labels.clone();
+
+                    let age = calculate_rs_age(&rs);
+                    let instance_name =
+                        &rs.metadata.name.as_deref().unwrap_or("unknown").to_string();
+                    let f_instance_name = format!("{instance_name} "); //padding for just high level
+                    let desired_replicas = &rs
+                        .spec
+                        .as_ref()
+                        .map_or(0, |spec| spec.replicas.unwrap_or(0));
+                    let ready_replicas = &rs
+                        .status
+                        .as_ref()
+                        .map_or(0, |status| status.ready_replicas.unwrap_or(0));
+                    let kind = &owner.kind;
+                    let owner_name = &owner.name;
+
+                    let resource_events =
+                        list_events_for_resource(events.clone(), &f_instance_name).await?;
+                    let badges: Vec<String> =
+                        deprecations(&rs.metadata, "ReplicaSet", cluster_minor)
+                            .iter()
+                            .map(super::deprecations::Deprecation::badge)
+                            .collect();
+                    let data = Rs {
+                        name: instance_name.to_string(),
+                        pods: format!("{ready_replicas}/{desired_replicas}"),
+                        age,
+                        description: kind.to_string(),
+                        owner: owner_name.to_owned(),
+                        deprecations: badges.join(" "),
+                        selectors,
+                        events: resource_events,
+                        extra: settings()
+                            .columns
+                            .rs
+                            .iter()
+                            .map(|column| column.value(&rs.metadata))
+                            .collect(),
+                    };
+
+                    if desired_replicas <= &0 {
+                        continue;
+                    };
+                    rs_vec.push(data);
+                }
             }
         }
+        if !pager.is_done() {
+            on_page(&rs_vec);
+        }
     }
 
     Ok(rs_vec)
@@ -86,13 +133,25 @@ pub async fn list_replicas() -> Result<Vec<Rs>> {
 ///
 /// Will return `Err` if data can not be retrieved from k8s cluster api
 pub async fn get_replicaset(selector: BTreeMap<String, String>) -> Result<Option<ReplicaSet>> {
-    let client = Client::try_default().await?;
+    with_retry(&RetryPolicy::default(), || fetch_replicaset(&selector)).await
+}
+
+/// `get_replicaset` without retrying, for callers like `hedged` that retry
+/// around it
+///
+/// # Errors
+///
+/// Will return `Err` if data can not be retrieved from k8s cluster api
+pub async fn fetch_replicaset(selector: &BTreeMap<String, String>) -> Result<Option<ReplicaSet>> {
+    let client = get_client().await?;
 
-    let label_selector = format_label_selector(&selector);
+    let label_selector = format_label_selector(selector);
 
     let lp = ListParams::default().labels(&label_selector);
 
-    let rs_list: ObjectList<ReplicaSet> = Api::default_namespaced(client.clone()).list(&lp).await?;
+    let replicasets: Api<ReplicaSet> = Api::default_namespaced(client.clone());
+    let rs_list: ObjectList<ReplicaSet> =
+        with_timeout("list replicasets", replicasets.list(&lp)).await?;
 
     let rs = rs_list.into_iter().next();
     Ok(rs)
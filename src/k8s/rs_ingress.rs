@@ -1,12 +1,16 @@
 use crate::error::Result;
+use crate::k8s::ingress_details;
+use crate::k8s::rs::fetch_replicaset;
+use crate::k8s::timeout::{hedged, with_timeout};
 use crate::tui::data;
 use k8s_openapi::api::apps::v1::ReplicaSet;
 use k8s_openapi::api::core::v1::Service;
 use k8s_openapi::api::networking::v1::Ingress;
 use kube::api::ListParams;
 use kube::{Api, Client};
+use std::collections::BTreeMap;
 
-use super::client::new;
+use super::client_manager::get_client;
 
 fn matches_rs_labels(
     rs: &ReplicaSet,
@@ -16,13 +20,13 @@ fn matches_rs_labels(
         rs.metadata
             .labels
             .as_ref()
-            .map_or(false, |labels| labels.get(key.as_str()) == Some(value))
+            .is_some_and(|labels| labels.get(key.as_str()) == Some(value))
     })
 }
 
 async fn services_for_rs(client: &Client, rs: &ReplicaSet, namespace: &str) -> Result<Vec<String>> {
     let services: Api<Service> = Api::namespaced(client.clone(), namespace);
-    let service_list = services.list(&ListParams::default()).await?;
+    let service_list = with_timeout("list services", services.list(&ListParams::default())).await?;
     drop(services);
 
     Ok(service_list
@@ -44,27 +48,48 @@ async fn services_for_rs(client: &Client, rs: &ReplicaSet, namespace: &str) -> R
 ///
 /// Will return `Err` if data can not be retrieved from k8s cluster api
 pub async fn list_ingresses(rs: &ReplicaSet, namespace: &str) -> Result<Vec<data::Ingress>> {
-    let client = new(None).await?;
+    let client = get_client().await?;
 
     let ingresses: Api<Ingress> = Api::namespaced(client.clone(), namespace);
     let services = services_for_rs(&client, rs, namespace).await?;
-    drop(client);
 
-    let ingress_list = ingresses.list(&ListParams::default()).await?;
+    let ingress_list =
+        with_timeout("list ingresses", ingresses.list(&ListParams::default())).await?;
     drop(ingresses);
 
     let mut all_ingresses = Vec::new();
 
+    let mut lookups = ingress_details::Lookups::default();
     for ingress in ingress_list {
         if let Some(rules_ref) = ingress.spec.as_ref().map(|spec| &spec.rules) {
-            let ingresses_for_rule = handle_ingress_rules(rules_ref.as_ref(), &services, &ingress);
+            let mut ingresses_for_rule =
+                handle_ingress_rules(rules_ref.as_ref(), &services, &ingress);
+            for row in &mut ingresses_for_rule {
+                row.details =
+                    ingress_details::details(&client, &ingress, &row.host, &mut lookups).await;
+            }
             all_ingresses.extend(ingresses_for_rule);
         }
     }
+    lookups.check_live();
 
     Ok(all_ingresses)
 }
 
+/// The ingress rows of the replicaset `selector` picks, none if there is no
+/// such replicaset.
+///
+/// # Errors
+///
+/// Will return `Err` if data can not be retrieved from k8s cluster api
+pub async fn list_for_selector(selector: &BTreeMap<String, String>) -> Result<Vec<data::Ingress>> {
+    let rso = hedged("get replicaset", || fetch_replicaset(selector)).await?;
+    match rso {
+        Some(rs) => hedged("list ingresses", || list_ingresses(&rs, "")).await,
+        _ => Ok(vec![]),
+    }
+}
+
 fn handle_ingress_rules(
     rules: Option<&Vec<k8s_openapi::api::networking::v1::IngressRule>>,
     services: &[String],
@@ -134,6 +159,8 @@ pub(crate) fn get_rs_ingress_info(
             path: path_txt,
             backend_svc: backend_service_name.name.to_string(),
             port: port_txt,
+            class: ingress_details::class(ingress),
+            details: vec![],
         })
     } else {
         None
@@ -0,0 +1,235 @@
+//!Exports that can leave the org, ie: attached to a navipod bug report.
+//!Names, hosts, images and label values are replaced by a keyed hash, the
+//!same value always becoming the same token so owner links, service
+//!selection and the topology still line up.  Hosts and images keep their
+//!dots, slashes and digests, uids are random already and are kept.
+//!
+//!Short well known names can be guessed from an unkeyed hash, pass a key
+//!nobody outside knows to rule that out.
+//!
+use crate::k8s::scan::db::ClusterIdentity;
+use crate::k8s::scan::live::Triple;
+use sha2::{Digest, Sha256};
+
+/// hex digits of the hash kept per token
+const TOKEN_LEN: usize = 10;
+
+/// predicates whose objects are navipod's own vocabulary or numbers
+const KEPT: [&str; 18] = [
+    "navipod_kind",
+    "navipod_phase",
+    "navipod_replicas",
+    "navipod_ready_replicas",
+    "navipod_restarts",
+    "navipod_created",
+    "navipod_datetime",
+    "navipod_valid",
+    "navipod_expires",
+    "navipod_value",
+    "navipod_type",
+    "navipod_metric_name",
+    "navipod_description",
+    "navipod_k8s_version",
+    "navipod_version",
+    "navipod_started",
+    "navipod_duration_ms",
+    "navipod_pods",
+];
+
+/// predicates whose objects are the subject of another triple
+const LINKS: [&str; 4] = [
+    "navipod_owner",
+    "navipod_pod",
+    "navipod_selects",
+    "navipod_ingress",
+];
+
+#[derive(Clone, Debug, Default)]
+pub struct Anonymizer {
+    key: String,
+}
+
+fn is_uid(value: &str) -> bool {
+    value.len() == 36
+        && value.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+impl Anonymizer {
+    #[must_use]
+    pub fn new(key: impl Into<String>) -> Self {
+        Self { key: key.into() }
+    }
+
+    fn token(&self, value: &str) -> String {
+        if value.is_empty() {
+            return String::new();
+        }
+        let mut hasher = Sha256::new();
+        hasher.update(self.key.as_bytes());
+        hasher.update([0]);
+        hasher.update(value.as_bytes());
+        let hex: String = hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        format!("x{}", &hex[..TOKEN_LEN])
+    }
+
+    /// every dns label hashed on its own, so hosts of one domain still
+    /// share a suffix, and any port kept
+    fn host(&self, host: &str) -> String {
+        let (name, port) = host
+            .split_once(':')
+            .map_or((host, None), |(name, port)| (name, Some(port)));
+        let mut hashed = name
+            .split('.')
+            .map(|label| {
+                if label == "*" {
+                    label.to_string()
+                } else {
+                    self.token(label)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(".");
+        if let Some(port) = port {
+            hashed.push(':');
+            hashed.push_str(port);
+        }
+        hashed
+    }
+
+    /// `registry/path/name:tag@digest` with the digest kept
+    fn image(&self, image: &str) -> String {
+        let (reference, digest) = image
+            .split_once('@')
+            .map_or((image, None), |(reference, digest)| {
+                (reference, Some(digest))
+            });
+        let (path, tag) = match reference.rsplit_once(':') {
+            Some((path, tag)) if !tag.contains('/') => (path, Some(tag)),
+            _ => (reference, None),
+        };
+        let mut hashed = path
+            .split('/')
+            .enumerate()
+            .map(|(i, part)| {
+                let registry = i == 0 && path.contains('/') && part.contains(['.', ':']);
+                if registry {
+                    self.host(part)
+                } else {
+                    self.token(part)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        if let Some(tag) = tag {
+            hashed.push(':');
+            hashed.push_str(&self.token(tag));
+        }
+        if let Some(digest) = digest {
+            hashed.push('@');
+            hashed.push_str(digest);
+        }
+        hashed
+    }
+
+    /// a subject, or an object naming one
+    fn subject(&self, subject: &str) -> String {
+        if is_uid(subject) {
+            return subject.to_string();
+        }
+        // containers are `<pod uid>-<name>`
+        match subject.split_at_checked(36) {
+            Some((uid, name)) if is_uid(uid) && name.starts_with('-') => {
+                format!("{uid}-{}", self.token(&name[1..]))
+            }
+            _ => self.token(subject),
+        }
+    }
+
+    #[must_use]
+    pub fn triple(&self, (subject, predicate, object): Triple) -> Triple {
+        let object = match predicate.as_str() {
+            p if KEPT.contains(&p) => object,
+            p if LINKS.contains(&p) => self.subject(&object),
+            "navipod_host" => self.host(&object),
+            "navipod_image" => self.image(&object),
+            // names and metric label values
+            _ => self.token(&object),
+        };
+        (self.subject(&subject), predicate, object)
+    }
+
+    /// the cluster an export came from, for its manifest
+    #[must_use]
+    pub fn cluster(&self, cluster: &ClusterIdentity) -> ClusterIdentity {
+        ClusterIdentity {
+            context: self.token(&cluster.context),
+            server: self.token(&cluster.server),
+        }
+    }
+
+    #[must_use]
+    pub fn triples(&self, triples: Vec<Triple>) -> Vec<Triple> {
+        triples
+            .into_iter()
+            .map(|triple| self.triple(triple))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn t(subject: &str, predicate: &str, object: &str) -> Triple {
+        (
+            subject.to_string(),
+            predicate.to_string(),
+            object.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_anonymize_keeps_structure() {
+        let anonymizer = Anonymizer::new("k");
+        let pod = "0b7e6c1a-52a4-4a5e-9f0e-2c6a1d9b3e71";
+        let container = format!("{pod}-web");
+
+        let (subject, _, image) = anonymizer.triple(t(
+            &container,
+            "navipod_image",
+            "registry.acme.io:5000/payments/web:1.4@sha256:abc",
+        ));
+        assert!(subject.starts_with(&format!("{pod}-x")));
+        assert!(!subject.contains("web"));
+        let parts: Vec<&str> = image.split(['/', ':', '@']).collect();
+        assert_eq!(parts.len(), 7, "{image}");
+        assert!(image.ends_with("@sha256:abc"));
+        assert!(!image.contains("acme") && !image.contains("payments"));
+
+        let (_, _, host) = anonymizer.triple(t(pod, "navipod_host", "api.acme.io"));
+        let (_, _, other) = anonymizer.triple(t(pod, "navipod_host", "www.acme.io"));
+        assert_eq!(host.split('.').count(), 3);
+        assert_eq!(
+            host.split_once('.').unwrap().1,
+            other.split_once('.').unwrap().1
+        );
+
+        assert_eq!(
+            anonymizer.triple(t(pod, "navipod_owner", pod)).2,
+            pod,
+            "uids stay so links resolve"
+        );
+        assert_eq!(anonymizer.triple(t(pod, "navipod_restarts", "3")).2, "3");
+        let name = anonymizer.triple(t(pod, "navipod_name", "web-5d8f7")).2;
+        assert_eq!(name, anonymizer.triple(t(pod, "method", "web-5d8f7")).2);
+        assert_ne!(name, Anonymizer::new("other").token("web-5d8f7"));
+        assert_eq!(anonymizer.triple(t(pod, "navipod_node", "")).2, "");
+    }
+}
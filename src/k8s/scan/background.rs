@@ -0,0 +1,344 @@
+//!A metrics scan of the namespace started from the TUI, the one `navipod
+//!scan-metrics` runs, into the db the TUI journals changes to.  It runs in
+//!the background with its progress on the status line, and the way out
+//!waits for it like for any other task writing somewhere.
+//!
+//!Exports of the db or of a live snapshot of the namespace run the same
+//!way, as Turtle, N-Triples or JSON.
+//!
+use crate::k8s::client_manager;
+use crate::k8s::scan::db::{self, Age, ClusterIdentity, PruneReport, ScanRun};
+use crate::k8s::scan::live;
+use crate::k8s::scan::pods;
+use crate::k8s::scan::rdf::RdfFormat;
+use crate::k8s::scan::storage::{self, Storage, StorageBackend};
+use crate::k8s::timeout::with_timeout;
+use crate::shutdown::Pending;
+use chrono::{TimeDelta, Utc};
+use kube::config::{Config, KubeConfigOptions};
+use kube::Client;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// how long a finished scan stays on the status line
+const SHOWN_FOR: Duration = Duration::from_secs(10);
+
+/// what a scan started from the TUI writes where
+#[derive(Clone, Debug)]
+struct ScanConfig {
+    db_location: String,
+    namespace: String,
+    retention_days: i64,
+}
+
+static CONFIG: OnceLock<ScanConfig> = OnceLock::new();
+
+static STATUS: Mutex<Option<ScanStatus>> = Mutex::new(None);
+
+static EXPORT: Mutex<Option<ExportStatus>> = Mutex::new(None);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScanStatus {
+    /// `done` of the `total` pods of the namespace looked at
+    Running {
+        done: usize,
+        total: usize,
+    },
+    Done {
+        pods: usize,
+        at: Instant,
+    },
+    Failed {
+        error: String,
+        at: Instant,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExportStatus {
+    Running {
+        written: u64,
+    },
+    Done {
+        written: u64,
+        path: String,
+        at: Instant,
+    },
+    Failed {
+        error: String,
+        at: Instant,
+    },
+}
+
+/// Set where scans started from the TUI go once at startup, later calls
+/// are ignored.
+pub fn configure(db_location: String, namespace: String, retention_days: i64) {
+    let _ = CONFIG.set(ScanConfig {
+        db_location,
+        namespace,
+        retention_days,
+    });
+}
+
+/// the scan running, or the last one for a while after it finished
+#[must_use]
+pub fn status() -> Option<ScanStatus> {
+    let status = STATUS.lock().ok()?.clone()?;
+    match &status {
+        ScanStatus::Done { at, .. } | ScanStatus::Failed { at, .. } if at.elapsed() > SHOWN_FOR => {
+            None
+        }
+        _ => Some(status),
+    }
+}
+
+/// the export running, or the last one for a while after it finished
+#[must_use]
+pub fn export_status() -> Option<ExportStatus> {
+    let status = EXPORT.lock().ok()?.clone()?;
+    match &status {
+        ExportStatus::Done { at, .. } | ExportStatus::Failed { at, .. }
+            if at.elapsed() > SHOWN_FOR =>
+        {
+            None
+        }
+        _ => Some(status),
+    }
+}
+
+fn set_export_status(status: ExportStatus) {
+    if let Ok(mut current) = EXPORT.lock() {
+        *current = Some(status);
+    }
+}
+
+/// The default file of an export of the namespace as `format`.
+#[must_use]
+pub fn export_path(format: RdfFormat) -> String {
+    let extension = format.extension();
+    CONFIG.get().map_or_else(
+        || format!("navipod.{extension}"),
+        |config| format!("navipod-{}.{extension}", config.namespace),
+    )
+}
+
+/// Export the namespace to `path` as `format` in the background, from the
+/// db or, when `live`, from what the api server has now.  `false` when an
+/// export is already running or exports were not configured.
+pub fn export(path: String, format: RdfFormat, live: bool) -> bool {
+    let Some(config) = CONFIG.get().cloned() else {
+        return false;
+    };
+    let Ok(mut status) = EXPORT.lock() else {
+        return false;
+    };
+    if matches!(*status, Some(ExportStatus::Running { .. })) {
+        return false;
+    }
+    *status = Some(ExportStatus::Running { written: 0 });
+    drop(status);
+    tokio::spawn(async move {
+        let _pending = Pending::start(format!("export to {path}"));
+        let progress = |written| set_export_status(ExportStatus::Running { written });
+        let result = if live {
+            export_live(&config.namespace, &path, format, progress).await
+        } else {
+            export_db(&config.db_location, &path, format, progress).await
+        };
+        set_export_status(match result {
+            Ok(written) => ExportStatus::Done {
+                written,
+                path,
+                at: Instant::now(),
+            },
+            Err(error) => ExportStatus::Failed {
+                error,
+                at: Instant::now(),
+            },
+        });
+    });
+    true
+}
+
+fn set_status(status: ScanStatus) {
+    if let Ok(mut current) = STATUS.lock() {
+        *current = Some(status);
+    }
+}
+
+/// Start a scan in the background, `false` when one is already running or
+/// scans were not configured.
+pub fn start() -> bool {
+    let Some(config) = CONFIG.get().cloned() else {
+        return false;
+    };
+    let Ok(mut status) = STATUS.lock() else {
+        return false;
+    };
+    if matches!(*status, Some(ScanStatus::Running { .. })) {
+        return false;
+    }
+    *status = Some(ScanStatus::Running { done: 0, total: 0 });
+    drop(status);
+    tokio::spawn(async move {
+        let _pending = Pending::start(format!("metrics scan of {}", config.namespace));
+        let progress = |done, total| set_status(ScanStatus::Running { done, total });
+        let result = scan(
+            &config.db_location,
+            config.namespace,
+            config.retention_days,
+            progress,
+        )
+        .await;
+        set_status(match result {
+            Ok((pods, _)) => ScanStatus::Done {
+                pods,
+                at: Instant::now(),
+            },
+            Err(error) => ScanStatus::Failed {
+                error,
+                at: Instant::now(),
+            },
+        });
+    });
+    true
+}
+
+async fn export_live(
+    namespace: &str,
+    path: &str,
+    format: RdfFormat,
+    progress: impl FnMut(u64) + Send + 'static,
+) -> Result<u64, String> {
+    let triples = live::snapshot(namespace).await.map_err(|e| e.to_string())?;
+    let cluster = live::cluster().await.map_err(|e| e.to_string())?;
+    db::export_triples(triples, path, format, &[cluster], progress).map_err(|e| e.to_string())
+}
+
+async fn export_db(
+    db_location: &str,
+    path: &str,
+    format: RdfFormat,
+    progress: impl FnMut(u64) + Send + 'static,
+) -> Result<u64, String> {
+    let storage = Storage::open_read_only(&storage::location(db_location))
+        .await
+        .map_err(|e| e.to_string())?;
+    db::export_rdf(&storage, path, format, None, progress)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// One scan into the db, or the one `--db-url` or the config's `storage:`
+/// names, `progress` told how many of the pods were looked at.  A sqlite
+/// db's write lock is held for the scan, a second navipod scanning the same db fails
+/// instead of interleaving.  Gives the pods of the namespace and what
+/// pruning past `retention_days` took out.
+///
+/// # Errors
+///
+/// Will return `Err` if the db is locked or can not be written, or the pods
+/// can not be listed
+pub async fn scan(
+    db_location: &str,
+    namespace: String,
+    retention_days: i64,
+    progress: impl Fn(usize, usize),
+) -> Result<(usize, Option<PruneReport>), String> {
+    let storage = Storage::open(&storage::location(db_location))
+        .await
+        .map_err(|e| e.to_string())?;
+    scan_into(&storage, namespace, retention_days, progress).await
+}
+
+async fn scan_into(
+    storage: &impl StorageBackend,
+    namespace: String,
+    retention_days: i64,
+    progress: impl Fn(usize, usize),
+) -> Result<(usize, Option<PruneReport>), String> {
+    storage.create_schema().await.map_err(|e| e.to_string())?;
+    let config = Config::from_kubeconfig(&KubeConfigOptions::default())
+        .await
+        .map_err(|e| e.to_string())?;
+    let identity = ClusterIdentity {
+        context: client_manager::current_context(),
+        server: config.cluster_url.to_string(),
+    };
+    let client = Client::try_default().await.map_err(|e| e.to_string())?;
+    let total = scan_cluster(storage, &identity, client, namespace, progress).await?;
+    let report = if retention_days > 0 {
+        Some(prune(storage, retention_days).await?)
+    } else {
+        None
+    };
+    Ok((total, report))
+}
+
+/// Scan the pods of `namespace` in the cluster `client` talks to into
+/// `storage`, whose schema is already created, with every observation
+/// tagged as `identity`'s, and note the run with the cluster's version and
+/// how long it took.  Gives how many pods there were.
+///
+/// # Errors
+///
+/// Will return `Err` if the db can not be written or the pods can not be
+/// listed
+pub async fn scan_cluster(
+    storage: &impl StorageBackend,
+    identity: &ClusterIdentity,
+    client: Client,
+    namespace: String,
+    progress: impl Fn(usize, usize),
+) -> Result<usize, String> {
+    let started = Utc::now();
+    let timer = Instant::now();
+    storage
+        .record_cluster(identity)
+        .await
+        .map_err(|e| e.to_string())?;
+    let k8s_version = with_timeout("api server version", client.apiserver_version())
+        .await
+        .map_or_else(|_| "unknown".to_string(), |info| info.git_version);
+    let (pod_list, pods) = pods::fetch(client, namespace.clone())
+        .await
+        .map_err(|e| e.to_string())?;
+    let total = pod_list.items.len();
+    pods::gather_metrics(
+        storage,
+        pod_list,
+        &pods,
+        &identity.context,
+        namespace,
+        progress,
+    )
+    .await;
+    let run = ScanRun {
+        id: Uuid::new_v4().to_string(),
+        context: identity.context.clone(),
+        server_hash: ScanRun::server_hash(&identity.server),
+        k8s_version,
+        navipod_version: env!("CARGO_PKG_VERSION").to_string(),
+        started: started.to_rfc3339(),
+        duration_ms: i64::try_from(timer.elapsed().as_millis()).unwrap_or(i64::MAX),
+        pods: i64::try_from(total).unwrap_or(i64::MAX),
+    };
+    storage.record_run(&run).await.map_err(|e| e.to_string())?;
+    Ok(total)
+}
+
+/// Delete what was scanned more than `retention_days` ago.
+///
+/// # Errors
+///
+/// Will return `Err` if the db can not be written
+pub async fn prune(
+    storage: &impl StorageBackend,
+    retention_days: i64,
+) -> Result<PruneReport, String> {
+    storage
+        .prune(Age(TimeDelta::days(retention_days)))
+        .await
+        .map_err(|e| e.to_string())
+}
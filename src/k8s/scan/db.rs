@@ -4,18 +4,31 @@
 //!subject,predicate,object cols to enable
 //!open-ended scheema-less variable len record types.
 //!
+use crate::config::data_dir;
+use crate::k8s::journal;
+use crate::k8s::scan::anonymize::Anonymizer;
+use crate::k8s::scan::live::Triple;
+use crate::k8s::scan::manifest::{self, Tally};
+use crate::k8s::scan::rdf::{Output, RdfFormat, RdfWriter};
+use crate::k8s::scan::storage::StorageBackend;
+use chrono::{TimeDelta, Utc};
+use futures::TryStreamExt;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode};
 use sqlx::Pool;
-use sqlx::Row;
 use sqlx::Sqlite;
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::Write;
-use std::path::Path;
+use std::collections::BTreeSet;
+use std::fmt::{self, Write as _};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
 use tracing::debug;
 
-const BASE_URI: &str = "http://navipod.navicore.tech";
-const RESOURCE_PREFIX: &str = "res";
-const PROPERTY_PREFIX: &str = "prop";
+/// how long a statement waits on a lock held by another process
+pub(crate) const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// # Errors
 ///
@@ -30,14 +43,121 @@ pub async fn init(db_location: String) -> Result<Pool<Sqlite>, Box<dyn std::erro
         File::create(&db_location)?;
     }
 
-    let pool = Pool::connect(&db_url).await?;
+    // WAL lets reports and exports read while a scan writes
+    let options = SqliteConnectOptions::from_str(&db_url)?
+        .journal_mode(SqliteJournalMode::Wal)
+        .busy_timeout(BUSY_TIMEOUT);
+    let pool = Pool::connect_with(options).await?;
     Ok(pool)
 }
 
+/// Another navipod holds the write lock of the db.
+#[derive(Debug)]
+pub struct DbLocked {
+    pub db_location: String,
+    /// pid written by the holder, when it could be read
+    pub holder: Option<u32>,
+}
+
+impl fmt::Display for DbLocked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is being written by another navipod",
+            self.db_location
+        )?;
+        if let Some(pid) = self.holder {
+            write!(f, " (pid {pid})")?;
+        }
+        write!(
+            f,
+            ", wait for it to finish or give this one its own --db-location"
+        )
+    }
+}
+
+impl std::error::Error for DbLocked {}
+
+/// Held while writing the db so a second scan or prune fails up front
+/// instead of with "database is locked" halfway through.  Released when
+/// dropped or when the process dies.
+#[derive(Debug)]
+pub struct WriteLock {
+    _file: File,
+}
+
+#[must_use]
+pub fn lock_path(db_location: &str) -> PathBuf {
+    PathBuf::from(format!("{db_location}.lock"))
+}
+
+/// # Errors
+///
+/// Will return `Err` if another process holds the lock or the lock file
+/// can not be created
+pub fn lock_for_write(db_location: &str) -> Result<WriteLock, Box<dyn std::error::Error>> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(lock_path(db_location))?;
+    match file.try_lock() {
+        Ok(()) => {
+            file.set_len(0)?;
+            file.rewind()?;
+            write!(file, "{}", std::process::id())?;
+            Ok(WriteLock { _file: file })
+        }
+        Err(TryLockError::WouldBlock) => {
+            let mut pid = String::new();
+            let holder = file
+                .read_to_string(&mut pid)
+                .ok()
+                .and_then(|_| pid.trim().parse().ok());
+            Err(Box::new(DbLocked {
+                db_location: db_location.to_string(),
+                holder,
+            }))
+        }
+        Err(TryLockError::Error(e)) => Err(Box::new(e)),
+    }
+}
+
+/// The db for a command that writes it, with the write lock held.
+///
+/// # Errors
+///
+/// Will return `Err` if another navipod is writing the db or it can not be
+/// created
+pub async fn open_for_write(
+    db_location: &str,
+) -> Result<(Pool<Sqlite>, WriteLock), Box<dyn std::error::Error>> {
+    let lock = lock_for_write(db_location)?;
+    let pool = init(db_location.to_string()).await?;
+    Ok((pool, lock))
+}
+
+/// The db for a command that only reads it, safe to use while a scan
+/// writes.
+///
+/// # Errors
+///
+/// Will return `Err` if there is no db yet or it can not be opened
+pub async fn open_read_only(db_location: &str) -> Result<Pool<Sqlite>, Box<dyn std::error::Error>> {
+    if !Path::new(db_location).exists() {
+        return Err(format!("no db at {db_location}, run navipod scan-metrics first").into());
+    }
+    let options = SqliteConnectOptions::from_str(&format!("sqlite:{db_location}"))?
+        .read_only(true)
+        .busy_timeout(BUSY_TIMEOUT);
+    Ok(Pool::connect_with(options).await?)
+}
+
 /// # Errors
 ///
 /// Will return `Err` if function cannot create db table
-pub async fn create_table(pool: &Pool<Sqlite>) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn create_table(pool: &Pool<Sqlite>) -> Result<(), sqlx::Error> {
     sqlx::query(
         r"
         CREATE TABLE IF NOT EXISTS triples (
@@ -50,132 +170,476 @@ pub async fn create_table(pool: &Pool<Sqlite>) -> Result<(), Box<dyn std::error:
         CREATE INDEX IF NOT EXISTS idx_subject ON triples (subject);
         CREATE INDEX IF NOT EXISTS idx_predicate ON triples (predicate);
         CREATE INDEX IF NOT EXISTS idx_object ON triples (object);
+
+        CREATE TABLE IF NOT EXISTS cluster (
+            context TEXT NOT NULL,
+            server TEXT NOT NULL,
+            first_seen TEXT NOT NULL,
+            last_seen TEXT NOT NULL,
+            PRIMARY KEY (context, server)
+        );
+
+        CREATE TABLE IF NOT EXISTS imports (
+            id INTEGER PRIMARY KEY,
+            source TEXT NOT NULL,
+            context TEXT,
+            server TEXT,
+            imported_at TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS scan_runs (
+            id TEXT PRIMARY KEY,
+            context TEXT NOT NULL,
+            server_hash TEXT NOT NULL,
+            k8s_version TEXT NOT NULL,
+            navipod_version TEXT NOT NULL,
+            started TEXT NOT NULL,
+            duration_ms INTEGER NOT NULL,
+            pods INTEGER NOT NULL
+        );
         ",
     )
     .execute(pool)
     .await?;
 
+    // rows merged from another db point at their import, scanned rows are null
+    let columns: Vec<(String,)> = sqlx::query_as("SELECT name FROM pragma_table_info('triples')")
+        .fetch_all(pool)
+        .await?;
+    if !columns.iter().any(|(name,)| name == "import_id") {
+        sqlx::query("ALTER TABLE triples ADD COLUMN import_id INTEGER REFERENCES imports (id)")
+            .execute(pool)
+            .await?;
+    }
+
     Ok(())
 }
 
+/// the cluster a db was scanned from
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ClusterIdentity {
+    pub context: String,
+    pub server: String,
+}
+
+/// Note that `identity` was scanned into the db now.
+///
 /// # Errors
 ///
-/// Will return `Err` if function cannot read db file
-pub async fn export_to_nt_rdf(
+/// Will return `Err` if function cannot write the db
+pub async fn record_cluster(
     pool: &Pool<Sqlite>,
-    rdffile_name: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let mut file = File::create(rdffile_name)?;
+    identity: &ClusterIdentity,
+) -> Result<(), sqlx::Error> {
+    let now = Utc::now().to_rfc3339();
+    sqlx::query(
+        r"
+        INSERT INTO cluster (context, server, first_seen, last_seen) VALUES (?, ?, ?, ?)
+        ON CONFLICT (context, server) DO UPDATE SET last_seen = excluded.last_seen
+        ",
+    )
+    .bind(&identity.context)
+    .bind(&identity.server)
+    .bind(&now)
+    .bind(&now)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
 
-    let rows = sqlx::query("SELECT subject, predicate, object FROM triples")
-        .fetch_all(pool)
-        .await?;
+/// the clusters scanned into the db, most recent first
+///
+/// # Errors
+///
+/// Will return `Err` if function cannot read the db
+pub async fn clusters(pool: &Pool<Sqlite>) -> Result<Vec<ClusterIdentity>, sqlx::Error> {
+    let rows: Vec<(String, String)> =
+        sqlx::query_as("SELECT context, server FROM cluster ORDER BY last_seen DESC")
+            .fetch_all(pool)
+            .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(context, server)| ClusterIdentity { context, server })
+        .collect())
+}
 
-    for row in rows {
-        let subject: String = row.get("subject");
-        let predicate: String = row.get("predicate");
-        let object: String = row.get("object");
+/// One scan of a cluster into the db, so what it wrote can be told apart
+/// from the data of other clusters and navipods.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, sqlx::FromRow)]
+pub struct ScanRun {
+    pub id: String,
+    pub context: String,
+    /// of the api server url, the same cluster under any context name
+    pub server_hash: String,
+    pub k8s_version: String,
+    pub navipod_version: String,
+    /// rfc3339 in utc
+    pub started: String,
+    pub duration_ms: i64,
+    pub pods: i64,
+}
 
-        let subject_uri = format!("{BASE_URI}/resource/{subject}");
-        let predicate_uri = format!("{BASE_URI}/property/{predicate}");
+/// hex digits of the server url hash kept
+const SERVER_HASH_LEN: usize = 12;
 
-        //let object = object.replace("\"", "\\\"");
-        let object = object.replace('\"', "\\\"");
+impl ScanRun {
+    /// a short SHA-256 of `server`, identifying the cluster without naming it
+    #[must_use]
+    pub fn server_hash(server: &str) -> String {
+        Sha256::digest(server.as_bytes())
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>()[..SERVER_HASH_LEN]
+            .to_string()
+    }
 
-        writeln!(file, "<{subject_uri}> <{predicate_uri}> \"{object}\" .")?;
+    /// the run as triples for an export, under its id
+    #[must_use]
+    pub fn triples(&self) -> Vec<Triple> {
+        [
+            ("navipod_kind", "ScanRun".to_string()),
+            ("navipod_context", self.context.clone()),
+            ("navipod_server_hash", self.server_hash.clone()),
+            ("navipod_k8s_version", self.k8s_version.clone()),
+            ("navipod_version", self.navipod_version.clone()),
+            ("navipod_started", self.started.clone()),
+            ("navipod_duration_ms", self.duration_ms.to_string()),
+            ("navipod_pods", self.pods.to_string()),
+        ]
+        .into_iter()
+        .map(|(predicate, object)| (self.id.clone(), predicate.to_string(), object))
+        .collect()
     }
+}
 
-    Ok(())
+impl fmt::Display for ScanRun {
+    #[allow(clippy::cast_precision_loss)]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (server {}) k8s {}, navipod {}, at {}, {} pods in {:.1}s",
+            self.context,
+            self.server_hash,
+            self.k8s_version,
+            self.navipod_version,
+            self.started,
+            self.pods,
+            self.duration_ms as f64 / 1000.0
+        )
+    }
 }
 
+/// Note `run` in the db.
+///
 /// # Errors
 ///
-/// Will return `Err` if function cannot create db table
-pub async fn report(pool: &sqlx::SqlitePool) -> Result<String, sqlx::Error> {
-    let total_records: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM triples")
-        .fetch_one(pool)
+/// Will return `Err` if function cannot write the db
+pub async fn record_run(pool: &Pool<Sqlite>, run: &ScanRun) -> Result<(), sqlx::Error> {
+    sqlx::query(RECORD_RUN_SQL)
+        .bind(&run.id)
+        .bind(&run.context)
+        .bind(&run.server_hash)
+        .bind(&run.k8s_version)
+        .bind(&run.navipod_version)
+        .bind(&run.started)
+        .bind(run.duration_ms)
+        .bind(run.pods)
+        .execute(pool)
         .await?;
+    Ok(())
+}
 
-    let unique_subjects: (i64,) = sqlx::query_as("SELECT COUNT(DISTINCT subject) FROM triples")
-        .fetch_one(pool)
-        .await?;
+/// the insert of a `ScanRun`, the same sql on every backend
+pub(crate) const RECORD_RUN_SQL: &str = r"
+    INSERT INTO scan_runs
+        (id, context, server_hash, k8s_version, navipod_version, started, duration_ms, pods)
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+    ";
 
-    let unique_predicates: (i64,) = sqlx::query_as("SELECT COUNT(DISTINCT predicate) FROM triples")
-        .fetch_one(pool)
-        .await?;
+/// every `ScanRun`, the most recent first
+pub(crate) const RUNS_SQL: &str = "SELECT * FROM scan_runs ORDER BY started DESC";
 
-    let unique_objects: (i64,) = sqlx::query_as("SELECT COUNT(DISTINCT object) FROM triples")
-        .fetch_one(pool)
-        .await?;
+/// The scan runs of the db, the most recent first.
+///
+/// # Errors
+///
+/// Will return `Err` if function cannot read the db
+pub async fn runs(pool: &Pool<Sqlite>) -> Result<Vec<ScanRun>, sqlx::Error> {
+    sqlx::query_as(RUNS_SQL).fetch_all(pool).await
+}
+
+const DB_FILE: &str = "navipod.db";
+
+/// a context name usable as one path component
+fn path_safe(context: &str) -> String {
+    context
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "._-".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// `navipod/<context>/navipod.db` in the data dir, the dir is created if
+/// missing
+#[must_use]
+pub fn default_location(context: &str) -> Option<PathBuf> {
+    let dir = data_dir()?.join(path_safe(context));
+    std::fs::create_dir_all(&dir).ok()?;
+    Some(dir.join(DB_FILE))
+}
+
+/// one db found by `list`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DbInfo {
+    pub path: PathBuf,
+    pub bytes: u64,
+    /// empty for dbs written before clusters were recorded
+    pub clusters: Vec<ClusterIdentity>,
+}
+
+impl fmt::Display for DbInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cluster = self.clusters.first().map_or_else(
+            || "unknown cluster".to_string(),
+            |cluster| format!("{} ({})", cluster.context, cluster.server),
+        );
+        write!(
+            f,
+            "{cluster}  {} KiB  {}",
+            self.bytes / 1024,
+            self.path.display()
+        )
+    }
+}
+
+/// The per-context dbs under `root`, ie: the data dir.
+pub async fn list(root: &Path) -> Vec<DbInfo> {
+    let Ok(dirs) = std::fs::read_dir(root) else {
+        return vec![];
+    };
+    let mut paths: Vec<PathBuf> = dirs
+        .filter_map(|dir| Some(dir.ok()?.path().join(DB_FILE)))
+        .filter(|path| path.is_file())
+        .collect();
+    paths.sort();
+    let mut dbs = vec![];
+    for path in paths {
+        let location = path.display().to_string();
+        let clusters = match open_read_only(&location).await {
+            Ok(pool) => clusters(&pool).await.unwrap_or_default(),
+            Err(e) => {
+                debug!("can not read {location}: {e}");
+                vec![]
+            }
+        };
+        dbs.push(DbInfo {
+            bytes: std::fs::metadata(&path).map_or(0, |meta| meta.len()),
+            path,
+            clusters,
+        });
+    }
+    dbs
+}
 
-    Ok(format!(
-        "Records: {}\nSubjects: {}\nPredicates: {}\nObjects: {}",
-        total_records.0, unique_subjects.0, unique_predicates.0, unique_objects.0
-    ))
+/// the counts `report` shows, the same sql on every backend
+pub(crate) const STATS_SQL: &str = r"
+    SELECT COUNT(*), COUNT(DISTINCT subject), COUNT(DISTINCT predicate), COUNT(DISTINCT object)
+    FROM triples
+    ";
+
+/// The report of the counts `STATS_SQL` gives, under a header of how many
+/// scan runs there were and the last one of each cluster.
+pub(crate) fn stats(
+    (records, subjects, predicates, objects): (i64, i64, i64, i64),
+    runs: &[ScanRun],
+) -> String {
+    let mut out = format!("Scan runs: {}", runs.len());
+    let mut seen = BTreeSet::new();
+    for run in runs {
+        if seen.insert((&run.context, &run.server_hash)) {
+            let _ = write!(out, "\n  last of {run}");
+        }
+    }
+    let _ = write!(
+        out,
+        "\nRecords: {records}\nSubjects: {subjects}\nPredicates: {predicates}\nObjects: {objects}"
+    );
+    out
 }
 
 /// # Errors
 ///
-/// Will return `Err` if function cannot read db file
-pub async fn export_to_ttl_rdf(
-    pool: &Pool<Sqlite>,
-    ttlfile_name: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let mut file = File::create(ttlfile_name)?;
-
-    // Define the prefixes
-    writeln!(
-        file,
-        "@prefix {RESOURCE_PREFIX}: <{BASE_URI}/resource/> .\n"
-    )?;
-    writeln!(
-        file,
-        "@prefix {PROPERTY_PREFIX}: <{BASE_URI}/property/> .\n"
-    )?;
-
-    let rows = sqlx::query("SELECT subject, predicate, object FROM triples")
-        .fetch_all(pool)
-        .await?;
+/// Will return `Err` if function cannot create db table
+pub async fn report(pool: &sqlx::SqlitePool) -> Result<String, sqlx::Error> {
+    let counts = sqlx::query_as(STATS_SQL).fetch_one(pool).await?;
+    Ok(stats(counts, &runs(pool).await?))
+}
 
-    let mut triples: HashMap<String, HashMap<String, Vec<String>>> = HashMap::new();
+/// Stream every triple in `storage` to `rdffile_name`, ordered by subject
+/// so Turtle can group them, then the scan runs that wrote them, through
+/// `anonymizer` when sharing it, and write its manifest next to it.
+///
+/// # Errors
+///
+/// Will return `Err` if function cannot read the db or write the export
+pub async fn export_rdf(
+    storage: &impl StorageBackend,
+    rdffile_name: &str,
+    format: RdfFormat,
+    anonymizer: Option<&Anonymizer>,
+    progress: impl FnMut(u64) + Send + 'static,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    let mut writer = RdfWriter::new(Output::create(rdffile_name)?, format, progress)?;
+    let mut tally = Tally::default();
+    let mut rows = storage.triples();
+    while let Some(triple) = rows.try_next().await? {
+        let (subject, predicate, object) = match anonymizer {
+            Some(anonymizer) => anonymizer.triple(triple),
+            None => triple,
+        };
+        tally.add(&predicate, &object);
+        writer.write(&subject, &predicate, &object)?;
+    }
+    for run in storage.runs().await? {
+        for triple in run.triples() {
+            let (subject, predicate, object) = match anonymizer {
+                Some(anonymizer) => anonymizer.triple(triple),
+                None => triple,
+            };
+            tally.add(&predicate, &object);
+            writer.write(&subject, &predicate, &object)?;
+        }
+    }
+    let (output, written) = writer.finish()?;
+    output.finish()?;
+    let clusters: Vec<_> = storage
+        .clusters()
+        .await?
+        .iter()
+        .map(|cluster| match anonymizer {
+            Some(anonymizer) => anonymizer.cluster(cluster),
+            None => cluster.clone(),
+        })
+        .collect();
+    manifest::write(rdffile_name, format, written, &tally, &clusters)?;
+    Ok(written)
+}
 
-    for row in rows {
-        let subject: String = row.get("subject");
-        let predicate: String = row.get("predicate");
-        let object: String = row.get("object");
+/// Write `triples` taken from `clusters` to `rdffile_name` and its manifest
+/// next to it.
+///
+/// # Errors
+///
+/// Will return `Err` if function cannot write the export
+pub fn export_triples(
+    mut triples: Vec<(String, String, String)>,
+    rdffile_name: &str,
+    format: RdfFormat,
+    clusters: &[ClusterIdentity],
+    progress: impl FnMut(u64) + Send + 'static,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    triples.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut writer = RdfWriter::new(Output::create(rdffile_name)?, format, progress)?;
+    let mut tally = Tally::default();
+    for (subject, predicate, object) in &triples {
+        tally.add(predicate, object);
+        writer.write(subject, predicate, object)?;
+    }
+    let (output, written) = writer.finish()?;
+    output.finish()?;
+    manifest::write(rdffile_name, format, written, &tally, clusters)?;
+    Ok(written)
+}
 
-        // Use the prefixes instead of the full URIs
-        let subject_uri = format!("{RESOURCE_PREFIX}:{subject}");
-        let predicate_uri = format!("{PROPERTY_PREFIX}:{predicate}");
+/// how old scan data may get, ie: "30d", "12h" or "90m"
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Age(pub TimeDelta);
+
+impl FromStr for Age {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s.char_indices().last().map_or(0, |(i, _)| i);
+        let count: i64 = s[..split]
+            .parse()
+            .map_err(|_| format!("{s:?} is not like 30d, 12h or 90m"))?;
+        match &s[split..] {
+            "d" => Ok(Self(TimeDelta::days(count))),
+            "h" => Ok(Self(TimeDelta::hours(count))),
+            "m" => Ok(Self(TimeDelta::minutes(count))),
+            _ => Err(format!("{s:?} is not like 30d, 12h or 90m")),
+        }
+    }
+}
 
-        let object = object.replace('\"', "\\\"");
+/// what a prune removed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    pub observations: u64,
+    pub rows: u64,
+    pub bytes_reclaimed: i64,
+}
 
-        triples
-            .entry(subject_uri)
-            .or_default()
-            .entry(predicate_uri)
-            .or_default()
-            .push(object);
+impl fmt::Display for PruneReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "pruned {} observations ({} rows), reclaimed {} KiB",
+            self.observations,
+            self.rows,
+            self.bytes_reclaimed / 1024
+        )
     }
+}
 
-    for (subject, predicates) in triples {
-        writeln!(file, "{subject} ")?;
-        let pred_vec: Vec<String> = predicates
-            .iter()
-            .map(|(predicate, objects)| {
-                let obj_str = objects
-                    .iter()
-                    .map(|obj| format!("\"{obj}\""))
-                    .collect::<Vec<_>>()
-                    .join(" , ");
-                format!("    {predicate} {obj_str} ;")
-            })
-            .collect();
-        let predicates_str = pred_vec.join("\n");
-        writeln!(file, "{predicates_str} .\n")?; // Add an empty line between records
-    }
+async fn db_size(pool: &Pool<Sqlite>) -> Result<i64, sqlx::Error> {
+    let (pages,): (i64,) = sqlx::query_as("PRAGMA page_count").fetch_one(pool).await?;
+    let (page_size,): (i64,) = sqlx::query_as("PRAGMA page_size").fetch_one(pool).await?;
+    Ok(pages * page_size)
+}
 
-    Ok(())
+/// Delete every observation scanned and every change journaled more than
+/// `older_than` ago and vacuum the file.
+///
+/// # Errors
+///
+/// Will return `Err` if function cannot write the db
+pub async fn prune(pool: &Pool<Sqlite>, older_than: Age) -> Result<PruneReport, sqlx::Error> {
+    let cutoff = (Utc::now() - older_than.0).to_rfc3339();
+    let before = db_size(pool).await?;
+    let (observations,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM triples WHERE predicate = 'navipod_datetime' AND object < ?",
+    )
+    .bind(&cutoff)
+    .fetch_one(pool)
+    .await?;
+    let deleted = sqlx::query(
+        r"
+        DELETE FROM triples WHERE subject IN (
+            SELECT subject FROM triples WHERE predicate = 'navipod_datetime' AND object < ?
+        )
+        ",
+    )
+    .bind(&cutoff)
+    .execute(pool)
+    .await?;
+    let journaled = journal::prune(pool, &cutoff).await?;
+    let runs = sqlx::query("DELETE FROM scan_runs WHERE started < ?")
+        .bind(&cutoff)
+        .execute(pool)
+        .await?;
+    sqlx::query("VACUUM").execute(pool).await?;
+    Ok(PruneReport {
+        observations: u64::try_from(observations).unwrap_or_default(),
+        rows: deleted.rows_affected() + journaled + runs.rows_affected(),
+        bytes_reclaimed: before - db_size(pool).await?,
+    })
 }
 
 #[cfg(test)]
@@ -234,4 +698,135 @@ mod tests {
         // Clean up after the test
         let _ = fs::remove_file(db_location);
     }
+
+    #[test]
+    fn test_prune() {
+        let db_location = "/tmp/test_prune_navipod.db";
+        let _ = fs::remove_file(db_location);
+
+        let rt = Runtime::new().unwrap();
+        rt.block_on(async {
+            let pool = init(db_location.to_string()).await.unwrap();
+            create_table(&pool).await.unwrap();
+            let old = (Utc::now() - TimeDelta::days(40)).to_rfc3339();
+            let new = Utc::now().to_rfc3339();
+            for (subject, datetime) in [("old", &old), ("new", &new)] {
+                for (predicate, object) in [
+                    ("navipod_datetime", datetime.as_str()),
+                    ("navipod_value", "1"),
+                ] {
+                    sqlx::query(
+                        "INSERT INTO triples (subject, predicate, object) VALUES (?, ?, ?)",
+                    )
+                    .bind(subject)
+                    .bind(predicate)
+                    .bind(object)
+                    .execute(&pool)
+                    .await
+                    .unwrap();
+                }
+            }
+
+            let report = prune(&pool, "30d".parse().unwrap()).await.unwrap();
+            assert_eq!((report.observations, report.rows), (1, 2));
+            let (left,): (i64,) =
+                sqlx::query_as("SELECT COUNT(*) FROM triples WHERE subject = 'new'")
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap();
+            assert_eq!(left, 2);
+        });
+
+        let _ = fs::remove_file(db_location);
+        assert!("2w".parse::<Age>().is_err());
+    }
+
+    #[test]
+    fn test_scan_runs() {
+        let db_location = "/tmp/test_scan_runs_navipod.db";
+        let _ = fs::remove_file(db_location);
+
+        let rt = Runtime::new().unwrap();
+        rt.block_on(async {
+            let pool = init(db_location.to_string()).await.unwrap();
+            create_table(&pool).await.unwrap();
+            let run = |id: &str, context: &str, started: String| ScanRun {
+                id: id.to_string(),
+                context: context.to_string(),
+                server_hash: ScanRun::server_hash("https://prod.example.com"),
+                k8s_version: "v1.29.3".to_string(),
+                navipod_version: "0.8.20".to_string(),
+                started,
+                duration_ms: 4200,
+                pods: 12,
+            };
+            let old = run("r1", "prod", (Utc::now() - TimeDelta::days(40)).to_rfc3339());
+            let new = run("r2", "prod", Utc::now().to_rfc3339());
+            record_run(&pool, &old).await.unwrap();
+            record_run(&pool, &new).await.unwrap();
+            assert_eq!(runs(&pool).await.unwrap(), [new.clone(), old]);
+
+            let report = report(&pool).await.unwrap();
+            assert!(report.starts_with(&format!(
+                "Scan runs: 2\n  last of prod (server {}) k8s v1.29.3, navipod 0.8.20, at {}, 12 pods in 4.2s\nRecords: 0",
+                new.server_hash, new.started
+            )));
+
+            assert_eq!(prune(&pool, "30d".parse().unwrap()).await.unwrap().rows, 1);
+            assert_eq!(runs(&pool).await.unwrap(), [new]);
+        });
+
+        let _ = fs::remove_file(db_location);
+        assert_eq!(ScanRun::server_hash("https://a").len(), SERVER_HASH_LEN);
+        assert_ne!(
+            ScanRun::server_hash("https://a"),
+            ScanRun::server_hash("https://b")
+        );
+    }
+
+    #[test]
+    fn test_path_safe() {
+        assert_eq!(
+            path_safe("arn:aws:eks:us-east-1:1:cluster/prod"),
+            "arn_aws_eks_us-east-1_1_cluster_prod"
+        );
+        assert_eq!(path_safe("kind-kind"), "kind-kind");
+    }
+
+    #[test]
+    fn test_one_writer_many_readers() {
+        let db_location = "/tmp/test_lock_navipod.db";
+        let _ = fs::remove_file(db_location);
+
+        let rt = Runtime::new().unwrap();
+        rt.block_on(async {
+            assert!(open_read_only(db_location).await.is_err());
+            let (pool, lock) = open_for_write(db_location).await.unwrap();
+            create_table(&pool).await.unwrap();
+
+            let busy = lock_for_write(db_location).unwrap_err();
+            let busy = busy.downcast_ref::<DbLocked>().unwrap();
+            assert_eq!(busy.holder, Some(std::process::id()));
+
+            let reader = open_read_only(db_location).await.unwrap();
+            assert!(report(&reader)
+                .await
+                .unwrap()
+                .starts_with("Scan runs: 0\nRecords: 0"));
+
+            let identity = ClusterIdentity {
+                context: "arn:aws:eks:us-east-1:1:cluster/prod".to_string(),
+                server: "https://prod.example.com".to_string(),
+            };
+            record_cluster(&pool, &identity).await.unwrap();
+            record_cluster(&pool, &identity).await.unwrap();
+            assert_eq!(clusters(&reader).await.unwrap(), [identity]);
+
+            drop(lock);
+            assert!(lock_for_write(db_location).is_ok());
+        });
+
+        let _ = fs::remove_file(db_location);
+        let _ = fs::remove_file(lock_path(db_location));
+    }
 }
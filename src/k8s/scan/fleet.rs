@@ -0,0 +1,277 @@
+//!`navipod scan-all`, the metrics scan of `scan-metrics` run against many
+//!kubeconfig contexts, one after the other or a few at a time.  Each
+//!context is scanned into its own db, or every one into the db
+//!`--db-location`, `--db-url` or the config's `storage:` names, with its
+//!observations tagged with the context so a central db can tell the
+//!clusters apart.  A context that fails is reported and the rest go on.
+//!
+use crate::k8s::client_manager;
+use crate::k8s::scan::background;
+use crate::k8s::scan::db::{self, ClusterIdentity, PruneReport};
+use crate::k8s::scan::storage::{self, Location, Storage, StorageBackend};
+use futures::{stream, StreamExt};
+use kube::config::{Config, KubeConfigOptions};
+use kube::Client;
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// how one context's scan went
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClusterScan {
+    /// the server is empty when the context could not be read
+    pub identity: ClusterIdentity,
+    pub namespace: String,
+    /// where it was scanned into, without any password
+    pub location: String,
+    /// how many pods there were
+    pub result: Result<usize, String>,
+    pub took: Duration,
+}
+
+/// what a db the contexts were scanned into says after the scans
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DbSummary {
+    /// without any password in it
+    pub location: String,
+    pub stats: Result<String, String>,
+    pub pruned: Option<Result<PruneReport, String>>,
+}
+
+/// every context's scan and the dbs they went into
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FleetReport {
+    pub scans: Vec<ClusterScan>,
+    pub dbs: Vec<DbSummary>,
+}
+
+impl FleetReport {
+    #[must_use]
+    pub fn failed(&self) -> usize {
+        self.scans
+            .iter()
+            .filter(|scan| scan.result.is_err())
+            .count()
+    }
+}
+
+impl fmt::Display for FleetReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pods: usize = self
+            .scans
+            .iter()
+            .filter_map(|scan| scan.result.as_ref().ok())
+            .sum();
+        write!(
+            f,
+            "Scanned {} contexts, {} failed, {pods} pods",
+            self.scans.len(),
+            self.failed()
+        )?;
+        let context_width = width("CONTEXT", self.scans.iter().map(|s| &s.identity.context));
+        let server_width = width("SERVER", self.scans.iter().map(|s| &s.identity.server));
+        let namespace_width = width("NAMESPACE", self.scans.iter().map(|s| &s.namespace));
+        if !self.scans.is_empty() {
+            write!(
+                f,
+                "\n  {:<context_width$}  {:<server_width$}  {:<namespace_width$}  {:>6}  {:>7}  RESULT",
+                "CONTEXT", "SERVER", "NAMESPACE", "PODS", "TOOK"
+            )?;
+        }
+        for scan in &self.scans {
+            let (pods, result) = match &scan.result {
+                Ok(pods) => (pods.to_string(), "ok".to_string()),
+                Err(error) => ("-".to_string(), error.clone()),
+            };
+            write!(
+                f,
+                "\n  {:<context_width$}  {:<server_width$}  {:<namespace_width$}  {pods:>6}  {:>6.1}s  {result}",
+                scan.identity.context,
+                scan.identity.server,
+                scan.namespace,
+                scan.took.as_secs_f64()
+            )?;
+        }
+        for db in &self.dbs {
+            write!(f, "\n\n{}", db.location)?;
+            match &db.stats {
+                Ok(stats) => write!(f, "\n{stats}")?,
+                Err(error) => write!(f, "\nunavailable ({error})")?,
+            }
+            match &db.pruned {
+                Some(Ok(report)) if report.rows > 0 => write!(f, "\n{report}")?,
+                Some(Err(error)) => write!(f, "\nprune failed ({error})")?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// the widest of `values` and the `header` they go under
+fn width<'a>(header: &str, values: impl Iterator<Item = &'a String>) -> usize {
+    values.map(String::len).max().unwrap_or(0).max(header.len())
+}
+
+/// Where `context` is scanned into: `db_location` when given, else its own
+/// db in the data dir, unless `--db-url` or `storage:` name another.
+#[must_use]
+pub fn location(context: &str, db_location: Option<&str>) -> String {
+    let own = db_location.map_or_else(
+        || {
+            db::default_location(context).map_or_else(
+                || format!("/tmp/navipod-{context}.db"),
+                |path| path.display().to_string(),
+            )
+        },
+        ToString::to_string,
+    );
+    storage::location(&own)
+}
+
+/// Scan `contexts`, or every context of the kubeconfig when empty, at
+/// most `parallel` at a time.  The namespace scanned is `namespace`, or
+/// each context's own when `None`.  Each db scanned into is opened once
+/// and pruned past `retention_days` after all the scans into it.
+pub async fn scan_all(
+    contexts: &[String],
+    db_location: Option<&str>,
+    namespace: Option<&str>,
+    parallel: usize,
+    retention_days: i64,
+) -> FleetReport {
+    let contexts = if contexts.is_empty() {
+        client_manager::contexts()
+    } else {
+        contexts.to_vec()
+    };
+    let mut storages: BTreeMap<String, Result<Storage, String>> = BTreeMap::new();
+    for context in &contexts {
+        if let Entry::Vacant(entry) = storages.entry(location(context, db_location)) {
+            let opened = open(entry.key()).await;
+            entry.insert(opened);
+        }
+    }
+    let scans = stream::iter(&contexts)
+        .map(|context| {
+            let location = location(context, db_location);
+            let storage = storages.get(&location);
+            async move {
+                let started = Instant::now();
+                let (identity, namespace, result) = match storage {
+                    Some(Ok(storage)) => scan_context(storage, context, namespace).await,
+                    Some(Err(error)) => (unread(context), String::new(), Err(error.clone())),
+                    None => (unread(context), String::new(), Err("no db".to_string())),
+                };
+                ClusterScan {
+                    identity,
+                    namespace,
+                    location: Location::parse(&location).to_string(),
+                    result,
+                    took: started.elapsed(),
+                }
+            }
+        })
+        .buffered(parallel.max(1))
+        .collect()
+        .await;
+    let mut dbs = Vec::new();
+    for (location, storage) in &storages {
+        let Ok(storage) = storage else {
+            continue;
+        };
+        let pruned = if retention_days > 0 {
+            Some(background::prune(storage, retention_days).await)
+        } else {
+            None
+        };
+        dbs.push(DbSummary {
+            location: Location::parse(location).to_string(),
+            stats: storage.report().await.map_err(|e| e.to_string()),
+            pruned,
+        });
+    }
+    FleetReport { scans, dbs }
+}
+
+async fn open(location: &str) -> Result<Storage, String> {
+    let storage = Storage::open(location).await.map_err(|e| e.to_string())?;
+    storage.create_schema().await.map_err(|e| e.to_string())?;
+    Ok(storage)
+}
+
+fn unread(context: &str) -> ClusterIdentity {
+    ClusterIdentity {
+        context: context.to_string(),
+        server: String::new(),
+    }
+}
+
+async fn scan_context(
+    storage: &Storage,
+    context: &str,
+    namespace: Option<&str>,
+) -> (ClusterIdentity, String, Result<usize, String>) {
+    let options = KubeConfigOptions {
+        context: Some(context.to_string()),
+        ..KubeConfigOptions::default()
+    };
+    let config = match Config::from_kubeconfig(&options).await {
+        Ok(config) => config,
+        Err(e) => return (unread(context), String::new(), Err(e.to_string())),
+    };
+    let identity = ClusterIdentity {
+        context: context.to_string(),
+        server: config.cluster_url.to_string(),
+    };
+    let namespace = namespace.map_or_else(|| config.default_namespace.clone(), str::to_string);
+    let result = match Client::try_from(config) {
+        Ok(client) => {
+            background::scan_cluster(storage, &identity, client, namespace.clone(), |_, _| {}).await
+        }
+        Err(e) => Err(e.to_string()),
+    };
+    (identity, namespace, result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let report = FleetReport {
+            scans: vec![
+                ClusterScan {
+                    identity: ClusterIdentity {
+                        context: "prod".to_string(),
+                        server: "https://prod:6443/".to_string(),
+                    },
+                    namespace: "web".to_string(),
+                    location: "/tmp/prod.db".to_string(),
+                    result: Ok(12),
+                    took: Duration::from_millis(2500),
+                },
+                ClusterScan {
+                    identity: unread("staging"),
+                    namespace: String::new(),
+                    location: "/tmp/staging.db".to_string(),
+                    result: Err("no such context".to_string()),
+                    took: Duration::ZERO,
+                },
+            ],
+            dbs: vec![DbSummary {
+                location: "/tmp/prod.db".to_string(),
+                stats: Ok("Records: 3".to_string()),
+                pruned: Some(Ok(PruneReport::default())),
+            }],
+        };
+        let text = report.to_string();
+        assert!(text.starts_with("Scanned 2 contexts, 1 failed, 12 pods"));
+        assert!(text.contains("prod     https://prod:6443/  web"));
+        assert!(text.contains("    12     2.5s  ok"));
+        assert!(text.contains("-     0.0s  no such context"));
+        assert!(text.ends_with("/tmp/prod.db\nRecords: 3"));
+    }
+}
@@ -0,0 +1,272 @@
+//!Triples built straight from the cluster, without the db, for one-shot
+//!RDF snapshots of a namespace.  Subjects are object uids so owner links
+//!resolve within the snapshot.
+//!
+use crate::error::Result;
+use crate::k8s::client_manager::{self, get_client};
+use crate::k8s::scan::db::ClusterIdentity;
+use crate::k8s::timeout::with_timeout;
+use crate::net::analyze_tls_certificate;
+use k8s_openapi::api::apps::v1::{Deployment, ReplicaSet};
+use k8s_openapi::api::core::v1::{Pod, Service};
+use k8s_openapi::api::networking::v1::Ingress;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use kube::api::{Api, ListParams};
+use kube::Config;
+use tracing::warn;
+
+pub type Triple = (String, String, String);
+
+fn triple(subject: &str, predicate: &str, object: impl Into<String>) -> Triple {
+    (subject.to_string(), predicate.to_string(), object.into())
+}
+
+/// kind, name, namespace and owners common to every object
+fn meta_triples(kind: &str, meta: &ObjectMeta) -> (String, Vec<Triple>) {
+    let subject = meta.uid.clone().unwrap_or_default();
+    let mut triples = vec![
+        triple(&subject, "navipod_kind", kind),
+        triple(
+            &subject,
+            "navipod_name",
+            meta.name.clone().unwrap_or_default(),
+        ),
+        triple(
+            &subject,
+            "navipod_namespace",
+            meta.namespace.clone().unwrap_or_default(),
+        ),
+    ];
+    if let Some(created) = &meta.creation_timestamp {
+        triples.push(triple(&subject, "navipod_created", created.0.to_rfc3339()));
+    }
+    for owner in meta.owner_references.iter().flatten() {
+        triples.push(triple(&subject, "navipod_owner", owner.uid.clone()));
+    }
+    if let Some(app) = meta.labels.as_ref().and_then(|labels| labels.get("app")) {
+        triples.push(triple(&subject, "navipod_appname", app.clone()));
+    }
+    (subject, triples)
+}
+
+/// the pod and one subject per container, linked back to the pod
+#[must_use]
+pub fn pod_triples(pod: &Pod) -> Vec<Triple> {
+    let (subject, mut triples) = meta_triples("Pod", &pod.metadata);
+    let status = pod.status.as_ref();
+    if let Some(phase) = status.and_then(|s| s.phase.clone()) {
+        triples.push(triple(&subject, "navipod_phase", phase));
+    }
+    if let Some(node) = pod.spec.as_ref().and_then(|s| s.node_name.clone()) {
+        triples.push(triple(&subject, "navipod_node", node));
+    }
+    for container in pod.spec.iter().flat_map(|s| &s.containers) {
+        let container_subject = format!("{subject}-{}", container.name);
+        triples.push(triple(&container_subject, "navipod_kind", "Container"));
+        triples.push(triple(
+            &container_subject,
+            "navipod_name",
+            container.name.clone(),
+        ));
+        triples.push(triple(&container_subject, "navipod_pod", subject.clone()));
+        if let Some(image) = &container.image {
+            triples.push(triple(&container_subject, "navipod_image", image.clone()));
+        }
+        let restarts = status
+            .and_then(|s| s.container_statuses.as_ref())
+            .and_then(|statuses| statuses.iter().find(|cs| cs.name == container.name))
+            .map(|cs| cs.restart_count);
+        if let Some(restarts) = restarts {
+            triples.push(triple(
+                &container_subject,
+                "navipod_restarts",
+                restarts.to_string(),
+            ));
+        }
+    }
+    triples
+}
+
+#[must_use]
+pub fn replicaset_triples(rs: &ReplicaSet) -> Vec<Triple> {
+    let (subject, mut triples) = meta_triples("ReplicaSet", &rs.metadata);
+    if let Some(replicas) = rs.spec.as_ref().and_then(|s| s.replicas) {
+        triples.push(triple(&subject, "navipod_replicas", replicas.to_string()));
+    }
+    if let Some(ready) = rs.status.as_ref().and_then(|s| s.ready_replicas) {
+        triples.push(triple(
+            &subject,
+            "navipod_ready_replicas",
+            ready.to_string(),
+        ));
+    }
+    triples
+}
+
+#[must_use]
+pub fn deployment_triples(deployment: &Deployment) -> Vec<Triple> {
+    let (subject, mut triples) = meta_triples("Deployment", &deployment.metadata);
+    if let Some(replicas) = deployment.spec.as_ref().and_then(|s| s.replicas) {
+        triples.push(triple(&subject, "navipod_replicas", replicas.to_string()));
+    }
+    triples
+}
+
+/// the service and a link to each of `pods` its selector matches
+#[must_use]
+pub fn service_triples(service: &Service, pods: &[Pod]) -> Vec<Triple> {
+    let (subject, mut triples) = meta_triples("Service", &service.metadata);
+    let selector = service.spec.as_ref().and_then(|s| s.selector.as_ref());
+    if let Some(selector) = selector.filter(|selector| !selector.is_empty()) {
+        for pod in pods {
+            let labels = pod.metadata.labels.as_ref();
+            let selected = selector
+                .iter()
+                .all(|(k, v)| labels.and_then(|labels| labels.get(k)) == Some(v));
+            if selected {
+                triples.push(triple(
+                    &subject,
+                    "navipod_selects",
+                    pod.metadata.uid.clone().unwrap_or_default(),
+                ));
+            }
+        }
+    }
+    triples
+}
+
+#[must_use]
+pub fn ingress_triples(ingress: &Ingress) -> Vec<Triple> {
+    let (subject, mut triples) = meta_triples("Ingress", &ingress.metadata);
+    let spec = ingress.spec.as_ref();
+    for rule in spec.iter().flat_map(|s| s.rules.iter().flatten()) {
+        if let Some(host) = &rule.host {
+            triples.push(triple(&subject, "navipod_host", host.clone()));
+        }
+        let backends = rule
+            .http
+            .iter()
+            .flat_map(|http| &http.paths)
+            .filter_map(|path| path.backend.service.as_ref());
+        for service in backends {
+            triples.push(triple(&subject, "navipod_service", service.name.clone()));
+        }
+    }
+    triples
+}
+
+/// hosts an ingress terminates TLS for
+fn tls_hosts(ingress: &Ingress) -> Vec<String> {
+    ingress
+        .spec
+        .iter()
+        .flat_map(|s| s.tls.iter().flatten())
+        .flat_map(|tls| tls.hosts.iter().flatten().cloned())
+        .collect()
+}
+
+/// the certificate served for `host`, skipped with a warning when the host
+/// can not be reached
+async fn cert_triples(host: &str, ingress_uid: &str) -> Vec<Triple> {
+    let subject = format!("cert-{host}");
+    match analyze_tls_certificate(host).await {
+        Ok(info) => vec![
+            triple(&subject, "navipod_kind", "Certificate"),
+            triple(&subject, "navipod_host", host),
+            triple(&subject, "navipod_ingress", ingress_uid),
+            triple(&subject, "navipod_valid", info.is_valid.to_string()),
+            triple(&subject, "navipod_expires", info.expires.to_string()),
+            triple(&subject, "navipod_issued_by", info.issued_by),
+        ],
+        Err(e) => {
+            warn!("no certificate for {host}: {e}");
+            vec![]
+        }
+    }
+}
+
+/// the context and api server snapshots are taken from
+///
+/// # Errors
+///
+/// Will return `Err` if no kubeconfig or in-cluster config is found
+pub async fn cluster() -> Result<ClusterIdentity> {
+    let config = Config::infer().await?;
+    Ok(ClusterIdentity {
+        context: client_manager::current_context(),
+        server: config.cluster_url.to_string(),
+    })
+}
+
+/// Scan deployments, replicasets, pods, containers, services, ingresses and
+/// their certificates.
+///
+/// # Errors
+///
+/// Will return `Err` if the namespace can not be listed
+pub async fn snapshot(namespace: &str) -> Result<Vec<Triple>> {
+    let client = get_client().await?;
+    let lp = ListParams::default();
+    let pods = Api::<Pod>::namespaced(client.clone(), namespace);
+    let deployments = Api::<Deployment>::namespaced(client.clone(), namespace);
+    let replicasets = Api::<ReplicaSet>::namespaced(client.clone(), namespace);
+    let services = Api::<Service>::namespaced(client.clone(), namespace);
+    let ingresses = Api::<Ingress>::namespaced(client, namespace);
+
+    let mut triples = Vec::new();
+    let pods = with_timeout("list pods", pods.list(&lp)).await?.items;
+    for pod in &pods {
+        triples.extend(pod_triples(pod));
+    }
+    for service in with_timeout("list services", services.list(&lp)).await? {
+        triples.extend(service_triples(&service, &pods));
+    }
+    for deployment in with_timeout("list deployments", deployments.list(&lp)).await? {
+        triples.extend(deployment_triples(&deployment));
+    }
+    for rs in with_timeout("list replicasets", replicasets.list(&lp)).await? {
+        triples.extend(replicaset_triples(&rs));
+    }
+    for ingress in with_timeout("list ingresses", ingresses.list(&lp)).await? {
+        triples.extend(ingress_triples(&ingress));
+        let uid = ingress.metadata.uid.clone().unwrap_or_default();
+        for host in tls_hosts(&ingress) {
+            triples.extend(cert_triples(&host, &uid).await);
+        }
+    }
+    Ok(triples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::serde_json::{from_value, json};
+
+    #[test]
+    fn test_pod_triples() {
+        let pod: Pod = from_value(json!({
+            "metadata": {
+                "name": "web-5d8f7-abcde",
+                "namespace": "default",
+                "uid": "p1",
+                "labels": {"app": "web"},
+                "ownerReferences": [{"apiVersion": "apps/v1", "kind": "ReplicaSet", "name": "web-5d8f7", "uid": "rs1"}]
+            },
+            "spec": {"nodeName": "node-a", "containers": [{"name": "web", "image": "web:1.2"}]},
+            "status": {
+                "phase": "Running",
+                "containerStatuses": [{"name": "web", "image": "web:1.2", "imageID": "", "ready": true, "restartCount": 3}]
+            }
+        }))
+        .unwrap();
+        let triples = pod_triples(&pod);
+        let has = |s: &str, p: &str, o: &str| triples.contains(&triple(s, p, o));
+        assert!(has("p1", "navipod_kind", "Pod"));
+        assert!(has("p1", "navipod_owner", "rs1"));
+        assert!(has("p1", "navipod_appname", "web"));
+        assert!(has("p1", "navipod_node", "node-a"));
+        assert!(has("p1-web", "navipod_pod", "p1"));
+        assert!(has("p1-web", "navipod_image", "web:1.2"));
+        assert!(has("p1-web", "navipod_restarts", "3"));
+    }
+}
@@ -0,0 +1,178 @@
+//!Every export gets a `<file>.manifest.json` next to it so a pipeline
+//!picking the file up can check it got all of it: the records of each kind
+//!and the time they span, the clusters and namespaces they came from, the
+//!navipod that wrote them and the size and SHA-256 of the file.
+//!
+use crate::k8s::scan::db::ClusterIdentity;
+use crate::k8s::scan::rdf::RdfFormat;
+use chrono::Utc;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// what the triples of an export add up to, fed as they are written
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Tally {
+    /// subjects by their `navipod_kind`, metric observations as `Metric`
+    pub records: BTreeMap<String, u64>,
+    pub namespaces: BTreeSet<String>,
+    /// the first and last scan or creation time
+    pub range: Option<(String, String)>,
+}
+
+impl Tally {
+    pub fn add(&mut self, predicate: &str, object: &str) {
+        match predicate {
+            "navipod_kind" => *self.records.entry(object.to_string()).or_default() += 1,
+            "navipod_metric_name" => *self.records.entry("Metric".to_string()).or_default() += 1,
+            "navipod_namespace" if !object.is_empty() => {
+                self.namespaces.insert(object.to_string());
+            }
+            // rfc3339 in utc, so they order as strings
+            "navipod_datetime" | "navipod_created" => {
+                self.range = Some(match self.range.take() {
+                    None => (object.to_string(), object.to_string()),
+                    Some((first, last)) => {
+                        (first.min(object.to_string()), last.max(object.to_string()))
+                    }
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct TimeRange<'a> {
+    first: &'a str,
+    last: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct Artifact {
+    /// relative to the manifest
+    file: String,
+    bytes: u64,
+    sha256: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Manifest<'a> {
+    navipod_version: &'static str,
+    written_at: String,
+    format: &'static str,
+    triples: u64,
+    records: &'a BTreeMap<String, u64>,
+    time_range: Option<TimeRange<'a>>,
+    clusters: &'a [ClusterIdentity],
+    namespaces: &'a BTreeSet<String>,
+    artifacts: Vec<Artifact>,
+}
+
+/// where the manifest of the export at `artifact` goes
+#[must_use]
+pub fn path_of(artifact: &str) -> String {
+    format!("{artifact}.manifest.json")
+}
+
+/// size and SHA-256 of the file as written, compressed or not
+fn checksum(path: &str) -> io::Result<Artifact> {
+    let mut hasher = Sha256::new();
+    let bytes = io::copy(&mut File::open(path)?, &mut hasher)?;
+    let sha256 = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    let file = Path::new(path)
+        .file_name()
+        .map_or_else(|| path.to_string(), |name| name.to_string_lossy().into());
+    Ok(Artifact {
+        file,
+        bytes,
+        sha256,
+    })
+}
+
+/// Write the manifest of the `triples` just exported to `artifact`.
+///
+/// # Errors
+///
+/// Will return `Err` if the export can not be read back or the manifest
+/// can not be written
+pub fn write(
+    artifact: &str,
+    format: RdfFormat,
+    triples: u64,
+    tally: &Tally,
+    clusters: &[ClusterIdentity],
+) -> io::Result<()> {
+    let manifest = Manifest {
+        navipod_version: env!("CARGO_PKG_VERSION"),
+        written_at: Utc::now().to_rfc3339(),
+        format: format.name(),
+        triples,
+        records: &tally.records,
+        time_range: tally
+            .range
+            .as_ref()
+            .map(|(first, last)| TimeRange { first, last }),
+        clusters,
+        namespaces: &tally.namespaces,
+        artifacts: vec![checksum(artifact)?],
+    };
+    let file = File::create(path_of(artifact))?;
+    serde_json::to_writer_pretty(file, &manifest).map_err(io::Error::other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest() {
+        let mut tally = Tally::default();
+        for (predicate, object) in [
+            ("navipod_kind", "Pod"),
+            ("navipod_namespace", "shop"),
+            ("navipod_created", "2024-05-02T10:00:00+00:00"),
+            ("navipod_kind", "Pod"),
+            ("navipod_metric_name", "http_requests_total"),
+            ("navipod_datetime", "2024-05-01T08:30:00+00:00"),
+            ("navipod_name", "web-1"),
+        ] {
+            tally.add(predicate, object);
+        }
+        assert_eq!(tally.records["Pod"], 2);
+        assert_eq!(tally.records["Metric"], 1);
+        assert_eq!(
+            tally.range,
+            Some((
+                "2024-05-01T08:30:00+00:00".to_string(),
+                "2024-05-02T10:00:00+00:00".to_string()
+            ))
+        );
+
+        let artifact = "/tmp/test_navipod_manifest.nt";
+        std::fs::write(artifact, "abc").unwrap();
+        let clusters = [ClusterIdentity {
+            context: "kind-kind".to_string(),
+            server: "https://127.0.0.1:6443".to_string(),
+        }];
+        write(artifact, RdfFormat::NTriples, 3, &tally, &clusters).unwrap();
+        let manifest: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(path_of(artifact)).unwrap()).unwrap();
+        assert_eq!(manifest["artifacts"][0]["file"], "test_navipod_manifest.nt");
+        assert_eq!(
+            manifest["artifacts"][0]["sha256"],
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(manifest["namespaces"][0], "shop");
+        assert_eq!(manifest["clusters"][0]["context"], "kind-kind");
+        let _ = std::fs::remove_file(artifact);
+        let _ = std::fs::remove_file(path_of(artifact));
+    }
+}
@@ -0,0 +1,202 @@
+//!Merge a scan db collected elsewhere, ie: by a teammate inside a network
+//!this machine can not reach, into the local one.
+//!
+//!Scans group their triples under a uuid subject, so an observation is
+//!either already here (the same file imported twice, or a db copied back
+//!and forth) or new.  Every imported row points at a row of `imports`
+//!saying which file and cluster it came from.
+//!
+use crate::k8s::scan::db::{clusters, open_read_only};
+use chrono::Utc;
+use clap::ValueEnum;
+use futures::TryStreamExt;
+use sqlx::{Pool, Row, Sqlite};
+use std::fmt;
+
+/// what to do with an observation the local db already has
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OnConflict {
+    /// keep the local rows
+    #[default]
+    Skip,
+    /// drop the local rows and take the imported ones
+    Replace,
+}
+
+/// what an import merged
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    pub import_id: i64,
+    pub observations: u64,
+    pub rows: u64,
+    /// observations already in the local db
+    pub conflicts: u64,
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "import {}: merged {} observations ({} rows), {} already here",
+            self.import_id, self.observations, self.rows, self.conflicts
+        )
+    }
+}
+
+/// Merge every triple of the db at `source` into `pool`, in one
+/// transaction.
+///
+/// # Errors
+///
+/// Will return `Err` if `source` is not a navipod db or `pool` can not be
+/// written
+pub async fn import(
+    pool: &Pool<Sqlite>,
+    source: &str,
+    on_conflict: OnConflict,
+) -> Result<ImportReport, Box<dyn std::error::Error>> {
+    let incoming = open_read_only(source).await?;
+    let cluster = clusters(&incoming)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .next();
+
+    let mut tx = pool.begin().await?;
+    let import_id = sqlx::query(
+        "INSERT INTO imports (source, context, server, imported_at) VALUES (?, ?, ?, ?)",
+    )
+    .bind(source)
+    .bind(cluster.as_ref().map(|cluster| cluster.context.as_str()))
+    .bind(cluster.as_ref().map(|cluster| cluster.server.as_str()))
+    .bind(Utc::now().to_rfc3339())
+    .execute(&mut *tx)
+    .await?
+    .last_insert_rowid();
+
+    let mut report = ImportReport {
+        import_id,
+        ..ImportReport::default()
+    };
+    let mut current: Option<(String, bool)> = None;
+    let mut rows =
+        sqlx::query("SELECT subject, predicate, object FROM triples ORDER BY subject, id")
+            .fetch(&incoming);
+    while let Some(row) = rows.try_next().await? {
+        let subject: String = row.get("subject");
+        if current.as_ref().map(|(seen, _)| seen) != Some(&subject) {
+            let exists = sqlx::query("SELECT 1 FROM triples WHERE subject = ? LIMIT 1")
+                .bind(&subject)
+                .fetch_optional(&mut *tx)
+                .await?
+                .is_some();
+            let take = match (exists, on_conflict) {
+                (false, _) => true,
+                (true, OnConflict::Skip) => false,
+                (true, OnConflict::Replace) => {
+                    sqlx::query("DELETE FROM triples WHERE subject = ?")
+                        .bind(&subject)
+                        .execute(&mut *tx)
+                        .await?;
+                    true
+                }
+            };
+            report.conflicts += u64::from(exists);
+            report.observations += u64::from(take);
+            current = Some((subject.clone(), take));
+        }
+        if current.as_ref().is_some_and(|(_, take)| *take) {
+            sqlx::query(
+                "INSERT INTO triples (subject, predicate, object, import_id) VALUES (?, ?, ?, ?)",
+            )
+            .bind(&subject)
+            .bind(row.get::<String, _>("predicate"))
+            .bind(row.get::<String, _>("object"))
+            .bind(import_id)
+            .execute(&mut *tx)
+            .await?;
+            report.rows += 1;
+        }
+    }
+    drop(rows);
+    tx.commit().await?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::k8s::scan::db::{create_table, init, record_cluster, ClusterIdentity};
+    use std::fs;
+    use tokio::runtime::Runtime;
+
+    async fn observation(pool: &Pool<Sqlite>, subject: &str, value: &str) {
+        for (predicate, object) in [("navipod_metric_name", "up"), ("navipod_value", value)] {
+            sqlx::query("INSERT INTO triples (subject, predicate, object) VALUES (?, ?, ?)")
+                .bind(subject)
+                .bind(predicate)
+                .bind(object)
+                .execute(pool)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_import_merges_new_observations() {
+        let local_location = "/tmp/test_merge_local_navipod.db";
+        let remote_location = "/tmp/test_merge_remote_navipod.db";
+        for location in [local_location, remote_location] {
+            let _ = fs::remove_file(location);
+        }
+
+        let rt = Runtime::new().unwrap();
+        rt.block_on(async {
+            let remote = init(remote_location.to_string()).await.unwrap();
+            create_table(&remote).await.unwrap();
+            let identity = ClusterIdentity {
+                context: "restricted".to_string(),
+                server: "https://10.0.0.1".to_string(),
+            };
+            record_cluster(&remote, &identity).await.unwrap();
+            observation(&remote, "a", "1").await;
+            observation(&remote, "b", "2").await;
+            remote.close().await;
+
+            let local = init(local_location.to_string()).await.unwrap();
+            create_table(&local).await.unwrap();
+            observation(&local, "a", "0").await;
+
+            let report = import(&local, remote_location, OnConflict::Skip)
+                .await
+                .unwrap();
+            assert_eq!(
+                (report.observations, report.rows, report.conflicts),
+                (1, 2, 1)
+            );
+            let again = import(&local, remote_location, OnConflict::Skip)
+                .await
+                .unwrap();
+            assert_eq!((again.observations, again.conflicts), (0, 2));
+
+            let replaced = import(&local, remote_location, OnConflict::Replace)
+                .await
+                .unwrap();
+            assert_eq!(replaced.observations, 2);
+            let (value, context): (String, String) = sqlx::query_as(
+                r"
+                SELECT t.object, i.context FROM triples t JOIN imports i ON t.import_id = i.id
+                WHERE t.subject = 'a' AND t.predicate = 'navipod_value'
+                ",
+            )
+            .fetch_one(&local)
+            .await
+            .unwrap();
+            assert_eq!((value.as_str(), context.as_str()), ("1", "restricted"));
+        });
+
+        for location in [local_location, remote_location] {
+            let _ = fs::remove_file(location);
+        }
+    }
+}
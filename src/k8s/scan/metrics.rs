@@ -1,12 +1,12 @@
 //!A module to encapsulate how the k8s data is marshaled into triples.
 //!
+use crate::k8s::scan::storage::StorageBackend;
 use crate::k8s::scan::triples;
 use crate::k8s::scan::tuples;
 use futures::StreamExt;
 use k8s_openapi::api::core::v1::Pod;
 use kube::api::Api;
 use regex::Regex;
-use sqlx::sqlite::SqlitePool;
 use std::error::Error;
 use tokio::io::AsyncWriteExt;
 use tracing::{error, info, warn};
@@ -139,10 +139,7 @@ pub async fn get_text(
 
     let mut port_forwarder = pods.portforward(metadata_name, &[local_port]).await?;
     let Some(mut port_stream) = port_forwarder.take_stream(local_port) else {
-        return Err(Box::new(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "Unable to take stream",
-        )));
+        return Err(Box::new(std::io::Error::other("Unable to take stream")));
     };
 
     // Write a HTTP GET request to the metrics path
@@ -170,22 +167,24 @@ pub async fn get_text(
 /// # Errors
 ///
 /// Will return `Err` if access to k8s is not enabled via `kubeconfig`.
+#[allow(clippy::too_many_arguments)]
 pub async fn process(
-    pool: &SqlitePool,
+    storage: &impl StorageBackend,
     pods: &Api<Pod>,
     metadata_name: &str,
     path: &str,
     port: &str,
     appname: &str,
     namespace: &str,
+    context: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
     info!("getting health from {}{}:{}", metadata_name, path, port);
     let metrics_text = get_text(pods, metadata_name, path, port).await?;
 
     let metrics = parse_all(&metrics_text);
-    let tuples = tuples::format(metrics, metadata_name, appname, namespace);
+    let tuples = tuples::format(metrics, metadata_name, appname, namespace, context);
     let triples = triples::format(tuples);
-    triples::persist(triples, pool).await
+    Ok(storage.persist(triples).await?)
 }
 
 #[cfg(test)]
@@ -1,5 +1,18 @@
+pub mod anonymize;
+pub mod background;
 pub mod db;
+pub mod fleet;
+pub mod live;
+pub mod manifest;
+pub mod merge;
 pub mod metrics;
 pub mod pods;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod query;
+pub mod rdf;
+pub mod schedule;
+pub mod storage;
+pub mod topology;
 pub mod triples;
 pub mod tuples;
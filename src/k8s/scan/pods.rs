@@ -1,41 +1,42 @@
 use crate::k8s::scan::metrics;
+use crate::k8s::scan::storage::StorageBackend;
+use crate::k8s::timeout::with_timeout;
 use k8s_openapi::api::core::v1::Pod;
 use kube::api::ObjectList;
 use kube::{
     api::{Api, ListParams},
     Client,
 };
-use sqlx::sqlite::SqlitePool;
 use tracing::error;
 
 /// # Errors
 ///
 /// Will return `Err` if function cannot connect to Kubernetes
 pub async fn fetch(
+    client: Client,
     namespace: String,
 ) -> Result<(ObjectList<Pod>, Api<Pod>), Box<dyn std::error::Error>> {
-    let client = Client::try_default()
-        .await
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-
     let lp = ListParams::default();
     let pods: Api<Pod> = Api::namespaced(client.clone(), namespace.as_str());
 
-    let pod_list: ObjectList<Pod> = pods
-        .list(&lp)
-        .await
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+    let pod_list: ObjectList<Pod> = with_timeout("list pods", pods.list(&lp)).await?;
 
     Ok((pod_list, pods))
 }
 
+/// Scrape the pods annotated for prometheus, each observation tagged with
+/// the `context` it came from, `progress` is told how many of all the pods
+/// were looked at after each.
 pub async fn gather_metrics(
-    pool: &SqlitePool,
+    storage: &impl StorageBackend,
     pod_list: ObjectList<Pod>,
     pods: &Api<Pod>,
+    context: &str,
     namespace: String,
+    progress: impl Fn(usize, usize),
 ) {
-    for p in pod_list.items {
+    let total = pod_list.items.len();
+    for (i, p) in pod_list.items.into_iter().enumerate() {
         let metadata = p.metadata.clone();
         let metadata_name = metadata.name.unwrap_or_default();
         let labels = metadata.labels.unwrap_or_default();
@@ -59,13 +60,14 @@ pub async fn gather_metrics(
 
         if scrape == "true" {
             let p = metrics::process(
-                pool,
+                storage,
                 pods,
                 metadata_name.as_str(),
                 path.as_str(),
                 port.as_str(),
                 appname.as_str(),
                 namespace.as_str(),
+                context,
             )
             .await;
 
@@ -74,5 +76,6 @@ pub async fn gather_metrics(
                 Err(e) => error!("Error processing metrics for {}: {:?}", metadata_name, e),
             }
         }
+        progress(i + 1, total);
     }
 }
@@ -0,0 +1,462 @@
+//!The postgres storage backend, for a db many navipods scan into, ie: one
+//!per cluster feeding a team's central metrics store.  The schema is
+//!created by numbered migrations, applied once each under an advisory lock
+//!so navipods starting together do not race, and observations are written
+//!in batches of multi-row inserts.
+//!
+use crate::k8s::journal::{self, Change};
+use crate::k8s::scan::db::{self, Age, ClusterIdentity, PruneReport, ScanRun};
+use crate::k8s::scan::live::Triple;
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use sqlx::postgres::{PgPool, PgPoolOptions, Postgres};
+use sqlx::{Executor, QueryBuilder, Row};
+use std::time::Duration;
+
+/// connections each navipod keeps open
+const POOL_SIZE: u32 = 4;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// rows per insert, well under postgres' 65535 bind parameters
+const BATCH: usize = 1000;
+
+/// any constant all navipods agree on, held while migrating
+const MIGRATION_LOCK: i64 = 0x006e_6176_6970_6f64;
+
+/// applied in order, the version of each is its place in the list
+const MIGRATIONS: [&str; 4] = [
+    r"
+    CREATE TABLE triples (
+        id BIGSERIAL PRIMARY KEY,
+        subject TEXT NOT NULL,
+        predicate TEXT NOT NULL,
+        object TEXT NOT NULL
+    );
+    CREATE INDEX idx_subject ON triples (subject);
+    CREATE INDEX idx_predicate ON triples (predicate);
+    CREATE INDEX idx_object ON triples (object);
+    ",
+    r"
+    CREATE TABLE cluster (
+        context TEXT NOT NULL,
+        server TEXT NOT NULL,
+        first_seen TEXT NOT NULL,
+        last_seen TEXT NOT NULL,
+        PRIMARY KEY (context, server)
+    );
+    ",
+    r"
+    CREATE TABLE scan_runs (
+        id TEXT PRIMARY KEY,
+        context TEXT NOT NULL,
+        server_hash TEXT NOT NULL,
+        k8s_version TEXT NOT NULL,
+        navipod_version TEXT NOT NULL,
+        started TEXT NOT NULL,
+        duration_ms BIGINT NOT NULL,
+        pods BIGINT NOT NULL
+    );
+    ",
+    r"
+    CREATE TABLE journal (
+        id BIGSERIAL PRIMARY KEY,
+        at TEXT NOT NULL,
+        namespace TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        name TEXT NOT NULL,
+        change TEXT NOT NULL,
+        resource_version TEXT NOT NULL
+    );
+    CREATE INDEX idx_journal_at ON journal (namespace, at);
+    ",
+];
+
+#[derive(Clone, Debug)]
+pub struct PostgresStorage {
+    pool: PgPool,
+}
+
+impl PostgresStorage {
+    /// # Errors
+    ///
+    /// Will return `Err` if the db at `url` can not be reached
+    pub async fn connect(url: &str) -> Result<Self, sqlx::Error> {
+        let pool = PgPoolOptions::new()
+            .max_connections(POOL_SIZE)
+            .acquire_timeout(CONNECT_TIMEOUT)
+            .connect(url)
+            .await?;
+        Ok(Self { pool })
+    }
+
+    /// Apply the migrations the db has not seen yet.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a migration fails, it is rolled back
+    pub async fn migrate(&self) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("SELECT pg_advisory_xact_lock($1)")
+            .bind(MIGRATION_LOCK)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            r"
+            CREATE TABLE IF NOT EXISTS navipod_migrations (
+                version BIGINT PRIMARY KEY,
+                applied_at TEXT NOT NULL
+            )
+            ",
+        )
+        .execute(&mut *tx)
+        .await?;
+        let (applied,): (Option<i64>,) =
+            sqlx::query_as("SELECT MAX(version) FROM navipod_migrations")
+                .fetch_one(&mut *tx)
+                .await?;
+        let applied = usize::try_from(applied.unwrap_or_default()).unwrap_or_default();
+        for (version, migration) in MIGRATIONS.iter().enumerate().skip(applied) {
+            tx.execute(sqlx::raw_sql(migration)).await?;
+            sqlx::query("INSERT INTO navipod_migrations (version, applied_at) VALUES ($1, $2)")
+                .bind(i64::try_from(version + 1).unwrap_or(i64::MAX))
+                .bind(Utc::now().to_rfc3339())
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if function cannot write the db
+    pub async fn record_cluster(&self, identity: &ClusterIdentity) -> Result<(), sqlx::Error> {
+        let now = Utc::now().to_rfc3339();
+        sqlx::query(
+            r"
+            INSERT INTO cluster (context, server, first_seen, last_seen) VALUES ($1, $2, $3, $4)
+            ON CONFLICT (context, server) DO UPDATE SET last_seen = excluded.last_seen
+            ",
+        )
+        .bind(&identity.context)
+        .bind(&identity.server)
+        .bind(&now)
+        .bind(&now)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if function cannot read the db
+    pub async fn clusters(&self) -> Result<Vec<ClusterIdentity>, sqlx::Error> {
+        let rows: Vec<(String, String)> =
+            sqlx::query_as("SELECT context, server FROM cluster ORDER BY last_seen DESC")
+                .fetch_all(&self.pool)
+                .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(context, server)| ClusterIdentity { context, server })
+            .collect())
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if function cannot write the db
+    pub async fn record_run(&self, run: &ScanRun) -> Result<(), sqlx::Error> {
+        sqlx::query(db::RECORD_RUN_SQL)
+            .bind(&run.id)
+            .bind(&run.context)
+            .bind(&run.server_hash)
+            .bind(&run.k8s_version)
+            .bind(&run.navipod_version)
+            .bind(&run.started)
+            .bind(run.duration_ms)
+            .bind(run.pods)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if function cannot read the db
+    pub async fn runs(&self) -> Result<Vec<ScanRun>, sqlx::Error> {
+        sqlx::query_as(db::RUNS_SQL).fetch_all(&self.pool).await
+    }
+
+    /// Insert the observations `BATCH` triples at a time, all of them or
+    /// none.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if function cannot write the db
+    pub async fn persist(&self, observations: Vec<Vec<Triple>>) -> Result<(), sqlx::Error> {
+        let triples: Vec<Triple> = observations.into_iter().flatten().collect();
+        let mut tx = self.pool.begin().await?;
+        for batch in triples.chunks(BATCH) {
+            let mut insert =
+                QueryBuilder::<Postgres>::new("INSERT INTO triples (subject, predicate, object) ");
+            insert.push_values(batch, |mut row, (subject, predicate, object)| {
+                row.push_bind(subject)
+                    .push_bind(predicate)
+                    .push_bind(object);
+            });
+            insert.build().execute(&mut *tx).await?;
+        }
+        tx.commit().await
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if function cannot read the db
+    pub async fn report(&self) -> Result<String, sqlx::Error> {
+        let counts = sqlx::query_as(db::STATS_SQL).fetch_one(&self.pool).await?;
+        Ok(db::stats(counts, &self.runs().await?))
+    }
+
+    /// every triple, ordered by subject
+    pub fn triples(&self) -> BoxStream<'_, Result<Triple, sqlx::Error>> {
+        sqlx::query("SELECT subject, predicate, object FROM triples ORDER BY subject, id")
+            .fetch(&self.pool)
+            .map_ok(|row| (row.get("subject"), row.get("predicate"), row.get("object")))
+            .boxed()
+    }
+
+    async fn size(&self) -> Result<i64, sqlx::Error> {
+        let (bytes,): (i64,) = sqlx::query_as("SELECT pg_total_relation_size('triples')")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(bytes)
+    }
+
+    /// Delete every observation scanned and every change journaled more
+    /// than `older_than` ago and vacuum the table.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if function cannot write the db
+    pub async fn prune(&self, older_than: Age) -> Result<PruneReport, sqlx::Error> {
+        let cutoff = (Utc::now() - older_than.0).to_rfc3339();
+        let before = self.size().await?;
+        let (observations,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM triples WHERE predicate = 'navipod_datetime' AND object < $1",
+        )
+        .bind(&cutoff)
+        .fetch_one(&self.pool)
+        .await?;
+        let deleted = sqlx::query(
+            r"
+            DELETE FROM triples WHERE subject IN (
+                SELECT subject FROM triples WHERE predicate = 'navipod_datetime' AND object < $1
+            )
+            ",
+        )
+        .bind(&cutoff)
+        .execute(&self.pool)
+        .await?;
+        let journaled = sqlx::query("DELETE FROM journal WHERE at < $1")
+            .bind(&cutoff)
+            .execute(&self.pool)
+            .await?;
+        let runs = sqlx::query("DELETE FROM scan_runs WHERE started < $1")
+            .bind(&cutoff)
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("VACUUM triples").execute(&self.pool).await?;
+        Ok(PruneReport {
+            observations: u64::try_from(observations).unwrap_or_default(),
+            rows: deleted.rows_affected() + journaled.rows_affected() + runs.rows_affected(),
+            bytes_reclaimed: before - self.size().await?,
+        })
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if function cannot write the db
+    pub async fn record_change(&self, change: &Change) -> Result<(), sqlx::Error> {
+        sqlx::query(journal::INSERT_SQL)
+            .bind(change.at.to_rfc3339())
+            .bind(&change.namespace)
+            .bind(&change.kind)
+            .bind(&change.name)
+            .bind(change.change.to_string())
+            .bind(&change.resource_version)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if function cannot read the db
+    pub async fn changes(
+        &self,
+        namespace: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Change>, sqlx::Error> {
+        let rows: Vec<journal::Row> = sqlx::query_as(journal::SINCE_SQL)
+            .bind(namespace)
+            .bind(since.to_rfc3339())
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.into_iter().filter_map(journal::from_row).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::k8s::journal::ChangeType;
+    use chrono::Duration as ChronoDuration;
+
+    /// a db of its own per test, dropped after, or `None` when no postgres
+    /// is set up for the tests
+    async fn scratch(name: &str) -> Option<PostgresStorage> {
+        let url = std::env::var("NAVIPOD_TEST_POSTGRES_URL").ok()?;
+        let admin = PostgresStorage::connect(&url).await.unwrap();
+        let db = format!("navipod_test_{name}");
+        admin
+            .pool
+            .execute(format!("DROP DATABASE IF EXISTS {db} WITH (FORCE)").as_str())
+            .await
+            .unwrap();
+        admin
+            .pool
+            .execute(format!("CREATE DATABASE {db}").as_str())
+            .await
+            .unwrap();
+        let (base, _) = url.rsplit_once('/').unwrap();
+        Some(
+            PostgresStorage::connect(&format!("{base}/{db}"))
+                .await
+                .unwrap(),
+        )
+    }
+
+    fn observation(subject: &str, at: &str, samples: usize) -> Vec<Triple> {
+        let mut triples = vec![(
+            subject.to_string(),
+            "navipod_datetime".to_string(),
+            at.to_string(),
+        )];
+        triples.extend(
+            (0..samples).map(|i| (subject.to_string(), format!("metric_{i}"), i.to_string())),
+        );
+        triples
+    }
+
+    async fn count(storage: &PostgresStorage, table: &str) -> i64 {
+        let (rows,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) FROM {table}"))
+            .fetch_one(&storage.pool)
+            .await
+            .unwrap();
+        rows
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a postgres, set NAVIPOD_TEST_POSTGRES_URL"]
+    async fn test_migrate_once() {
+        let Some(storage) = scratch("migrate").await else {
+            return;
+        };
+        storage.migrate().await.unwrap();
+        storage.migrate().await.unwrap();
+        let (applied,): (i64,) = sqlx::query_as("SELECT MAX(version) FROM navipod_migrations")
+            .fetch_one(&storage.pool)
+            .await
+            .unwrap();
+        assert_eq!(applied, i64::try_from(MIGRATIONS.len()).unwrap());
+        assert_eq!(
+            count(&storage, "navipod_migrations").await,
+            i64::try_from(MIGRATIONS.len()).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a postgres, set NAVIPOD_TEST_POSTGRES_URL"]
+    async fn test_persist_in_batches() {
+        let Some(storage) = scratch("persist").await else {
+            return;
+        };
+        storage.migrate().await.unwrap();
+        let now = Utc::now().to_rfc3339();
+        let observations = vec![
+            observation("pod_a", &now, BATCH * 2),
+            observation("pod_b", &now, 10),
+        ];
+        storage.persist(observations).await.unwrap();
+        assert_eq!(
+            count(&storage, "triples").await,
+            i64::try_from(BATCH * 2 + 1 + 11).unwrap()
+        );
+        let subjects: Vec<String> = storage
+            .triples()
+            .map_ok(|(subject, _, _)| subject)
+            .try_collect()
+            .await
+            .unwrap();
+        assert!(subjects.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a postgres, set NAVIPOD_TEST_POSTGRES_URL"]
+    async fn test_prune() {
+        let Some(storage) = scratch("prune").await else {
+            return;
+        };
+        storage.migrate().await.unwrap();
+        let old = Utc::now() - ChronoDuration::days(30);
+        let recent = Utc::now();
+        storage
+            .persist(vec![
+                observation("pod_old", &old.to_rfc3339(), 3),
+                observation("pod_new", &recent.to_rfc3339(), 3),
+            ])
+            .await
+            .unwrap();
+        for at in [old, recent] {
+            storage
+                .record_change(&Change {
+                    at,
+                    namespace: "default".to_string(),
+                    kind: "Pod".to_string(),
+                    name: "web".to_string(),
+                    change: ChangeType::Modified,
+                    resource_version: "1".to_string(),
+                })
+                .await
+                .unwrap();
+        }
+        for (id, started) in [("old", old), ("new", recent)] {
+            storage
+                .record_run(&ScanRun {
+                    id: id.to_string(),
+                    context: "test".to_string(),
+                    server_hash: "hash".to_string(),
+                    k8s_version: "1.30".to_string(),
+                    navipod_version: "0".to_string(),
+                    started: started.to_rfc3339(),
+                    duration_ms: 1,
+                    pods: 1,
+                })
+                .await
+                .unwrap();
+        }
+
+        let report = storage.prune(Age(ChronoDuration::days(7))).await.unwrap();
+
+        assert_eq!(report.observations, 1);
+        assert_eq!(report.rows, 4 + 1 + 1);
+        assert_eq!(count(&storage, "triples").await, 4);
+        assert_eq!(count(&storage, "scan_runs").await, 1);
+        let changes = storage
+            .changes("default", old - ChronoDuration::days(1))
+            .await
+            .unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].at.timestamp(), recent.timestamp());
+    }
+}
@@ -0,0 +1,250 @@
+//!Read-only SQL against the scan db, for questions the report does not
+//!answer.  The db is opened read-only so a query can never change it.
+//!
+use crate::k8s::scan::db::BUSY_TIMEOUT;
+use clap::ValueEnum;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteRow};
+use sqlx::{Column, Pool, Row, Sqlite};
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// name, description and sql of the queries selectable by name
+pub const CANNED_QUERIES: [(&str, &str, &str); 3] = [
+    (
+        "top-restarts",
+        "pods with the highest restart counters they export",
+        r"
+        SELECT p.object AS pod, n.object AS metric, MAX(CAST(v.object AS REAL)) AS restarts
+        FROM triples n
+        JOIN triples v ON v.subject = n.subject AND v.predicate = 'navipod_value'
+        JOIN triples p ON p.subject = n.subject AND p.predicate = 'navipod_podname'
+        WHERE n.predicate = 'navipod_metric_name' AND n.object LIKE '%restart%'
+        GROUP BY pod, metric
+        ORDER BY restarts DESC
+        LIMIT 20
+        ",
+    ),
+    (
+        "memory-growth",
+        "spread between the lowest and highest memory reading of each pod",
+        r"
+        SELECT p.object AS pod, n.object AS metric,
+            MIN(CAST(v.object AS REAL)) AS lowest,
+            MAX(CAST(v.object AS REAL)) AS highest,
+            MAX(CAST(v.object AS REAL)) - MIN(CAST(v.object AS REAL)) AS growth,
+            COUNT(*) AS samples
+        FROM triples n
+        JOIN triples v ON v.subject = n.subject AND v.predicate = 'navipod_value'
+        JOIN triples p ON p.subject = n.subject AND p.predicate = 'navipod_podname'
+        WHERE n.predicate = 'navipod_metric_name' AND n.object LIKE '%memory%bytes%'
+        GROUP BY pod, metric
+        ORDER BY growth DESC
+        LIMIT 20
+        ",
+    ),
+    (
+        "scan-history",
+        "observations and pods recorded per minute of scanning",
+        r"
+        SELECT substr(d.object, 1, 16) AS scanned, COUNT(DISTINCT d.subject) AS observations,
+            COUNT(DISTINCT p.object) AS pods
+        FROM triples d
+        JOIN triples p ON p.subject = d.subject AND p.predicate = 'navipod_podname'
+        WHERE d.predicate = 'navipod_datetime'
+        GROUP BY scanned
+        ORDER BY scanned DESC
+        ",
+    ),
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Csv,
+    Json,
+}
+
+/// column names and every value rendered as text, `None` for NULL
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Option<String>>>,
+}
+
+/// the sql of a canned query, or `query` itself
+#[must_use]
+pub fn resolve(query: &str) -> &str {
+    CANNED_QUERIES
+        .iter()
+        .find(|(name, _, _)| *name == query)
+        .map_or(query, |(_, _, sql)| sql)
+}
+
+/// # Errors
+///
+/// Will return `Err` if the db cannot be opened
+pub async fn connect_read_only(db_location: &str) -> Result<Pool<Sqlite>, sqlx::Error> {
+    let options = SqliteConnectOptions::from_str(&format!("sqlite:{db_location}"))?
+        .read_only(true)
+        .busy_timeout(BUSY_TIMEOUT);
+    Pool::connect_with(options).await
+}
+
+fn cell(row: &SqliteRow, index: usize) -> Option<String> {
+    row.try_get::<Option<i64>, _>(index)
+        .map(|v| v.map(|v| v.to_string()))
+        .or_else(|_| {
+            row.try_get::<Option<f64>, _>(index)
+                .map(|v| v.map(|v| v.to_string()))
+        })
+        .or_else(|_| row.try_get::<Option<String>, _>(index))
+        .unwrap_or_else(|_| Some("<blob>".to_string()))
+}
+
+/// # Errors
+///
+/// Will return `Err` if the sql is invalid or tries to write
+pub async fn run(pool: &Pool<Sqlite>, sql: &str) -> Result<QueryResult, sqlx::Error> {
+    let rows = sqlx::query(sql).fetch_all(pool).await?;
+    let columns = rows.first().map_or_else(Vec::new, |row| {
+        row.columns()
+            .iter()
+            .map(|column| column.name().to_string())
+            .collect()
+    });
+    let rows = rows
+        .iter()
+        .map(|row| (0..row.len()).map(|i| cell(row, i)).collect())
+        .collect();
+    Ok(QueryResult { columns, rows })
+}
+
+impl QueryResult {
+    /// # Errors
+    ///
+    /// Will return `Err` if the csv or json cannot be serialized
+    pub fn render(&self, format: OutputFormat) -> Result<String, Box<dyn std::error::Error>> {
+        match format {
+            OutputFormat::Table => Ok(self.table()),
+            OutputFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(vec![]);
+                writer.write_record(&self.columns)?;
+                for row in &self.rows {
+                    writer.write_record(row.iter().map(|v| v.as_deref().unwrap_or_default()))?;
+                }
+                Ok(String::from_utf8(writer.into_inner()?)?)
+            }
+            OutputFormat::Json => {
+                let objects: Vec<serde_json::Map<String, serde_json::Value>> = self
+                    .rows
+                    .iter()
+                    .map(|row| {
+                        self.columns
+                            .iter()
+                            .cloned()
+                            .zip(row.iter().map(|v| v.clone().into()))
+                            .collect()
+                    })
+                    .collect();
+                Ok(serde_json::to_string_pretty(&objects)?)
+            }
+        }
+    }
+
+    fn table(&self) -> String {
+        let widths: Vec<usize> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                self.rows
+                    .iter()
+                    .filter_map(|row| row[i].as_ref().map(String::len))
+                    .chain([column.len()])
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+        let line = |values: Vec<String>| {
+            values
+                .iter()
+                .zip(&widths)
+                .map(|(value, width)| format!("{value:width$}"))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        };
+        let mut out = String::new();
+        let _ = writeln!(out, "{}", line(self.columns.clone()));
+        let _ = writeln!(
+            out,
+            "{}",
+            line(widths.iter().map(|width| "-".repeat(*width)).collect())
+        );
+        for row in &self.rows {
+            let values = row
+                .iter()
+                .map(|v| v.clone().unwrap_or_else(|| "NULL".to_string()))
+                .collect();
+            let _ = writeln!(out, "{}", line(values));
+        }
+        let _ = write!(out, "({} rows)", self.rows.len());
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::k8s::scan::db::{create_table, init};
+    use crate::k8s::scan::triples::persist;
+    use tokio::runtime::Runtime;
+
+    #[test]
+    fn test_canned_query() {
+        let db_location = "/tmp/test_query_navipod.db";
+        let _ = std::fs::remove_file(db_location);
+
+        let rt = Runtime::new().unwrap();
+        rt.block_on(async {
+            let pool = init(db_location.to_string()).await.unwrap();
+            create_table(&pool).await.unwrap();
+            let observation = |subject: &str, value: &str| {
+                [
+                    ("navipod_metric_name", "process_memory_bytes"),
+                    ("navipod_value", value),
+                    ("navipod_podname", "web-1"),
+                ]
+                .iter()
+                .map(|(p, o)| (subject.to_string(), p.to_string(), o.to_string()))
+                .collect()
+            };
+            persist(vec![observation("a", "100"), observation("b", "250")], &pool)
+                .await
+                .unwrap();
+
+            let read_only = connect_read_only(db_location).await.unwrap();
+            let result = run(&read_only, resolve("memory-growth")).await.unwrap();
+            assert_eq!(
+                result.rows,
+                vec![vec![
+                    Some("web-1".to_string()),
+                    Some("process_memory_bytes".to_string()),
+                    Some("100".to_string()),
+                    Some("250".to_string()),
+                    Some("150".to_string()),
+                    Some("2".to_string()),
+                ]]
+            );
+            assert_eq!(
+                result.render(OutputFormat::Csv).unwrap(),
+                "pod,metric,lowest,highest,growth,samples\nweb-1,process_memory_bytes,100,250,150,2\n"
+            );
+            assert!(run(&read_only, "DELETE FROM triples").await.is_err());
+        });
+
+        let _ = std::fs::remove_file(db_location);
+    }
+}
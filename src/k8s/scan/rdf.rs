@@ -0,0 +1,335 @@
+//!Writes triples as N-Triples, Turtle or a JSON array as they arrive, so
+//!exports of millions of triples never hold more than the current subject
+//!in memory.  Files ending in `.gz` or `.zst` are compressed on the way out.
+//!
+use clap::ValueEnum;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+const BASE_URI: &str = "http://navipod.navicore.tech";
+const RESOURCE_PREFIX: &str = "res";
+const PROPERTY_PREFIX: &str = "prop";
+
+/// how often the progress callback runs
+pub const PROGRESS_EVERY: u64 = 100_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RdfFormat {
+    NTriples,
+    Turtle,
+    /// `[{"subject": .., "predicate": .., "object": ..}]`, not RDF but
+    /// easy to load anywhere
+    Json,
+}
+
+impl RdfFormat {
+    pub const ALL: [Self; 3] = [Self::Turtle, Self::NTriples, Self::Json];
+
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::NTriples => "n-triples",
+            Self::Turtle => "turtle",
+            Self::Json => "json",
+        }
+    }
+
+    /// what files of the format end in, before any `.gz` or `.zst`
+    #[must_use]
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::NTriples => "nt",
+            Self::Turtle => "ttl",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// how an export is compressed on the way out
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Compress {
+    Gzip,
+    Zstd,
+}
+
+impl Compress {
+    #[must_use]
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Gzip => "gz",
+            Self::Zstd => "zst",
+        }
+    }
+
+    /// the compression a file name asks for, by its extension
+    #[must_use]
+    pub fn of_path(path: &str) -> Option<Self> {
+        [Self::Gzip, Self::Zstd]
+            .into_iter()
+            .find(|compress| path.ends_with(&format!(".{}", compress.extension())))
+    }
+
+    /// `path` with the extension of the compression added when missing
+    #[must_use]
+    pub fn path(self, path: &str) -> String {
+        if Self::of_path(path) == Some(self) {
+            path.to_string()
+        } else {
+            format!("{path}.{}", self.extension())
+        }
+    }
+}
+
+/// an export's output, compressed as it is written
+pub enum Output<W: Write = BufWriter<File>> {
+    Plain(W),
+    Gzip(GzEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl Output {
+    /// a file, compressed when its name ends in `.gz` or `.zst`
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the file cannot be created
+    pub fn create(path: &str) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?), Compress::of_path(path))
+    }
+}
+
+impl<W: Write> Output<W> {
+    /// # Errors
+    ///
+    /// Will return `Err` if the zstd encoder cannot be set up
+    pub fn new(out: W, compress: Option<Compress>) -> io::Result<Self> {
+        Ok(match compress {
+            None => Self::Plain(out),
+            Some(Compress::Gzip) => Self::Gzip(GzEncoder::new(out, Compression::default())),
+            Some(Compress::Zstd) => Self::Zstd(zstd::Encoder::new(out, 0)?),
+        })
+    }
+
+    /// flush and, when compressed, write the trailer
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the output cannot be written
+    pub fn finish(self) -> io::Result<()> {
+        match self {
+            Self::Plain(mut out) => out.flush(),
+            Self::Gzip(gz) => gz.finish()?.flush(),
+            Self::Zstd(zst) => zst.finish()?.flush(),
+        }
+    }
+}
+
+impl<W: Write> Write for Output<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(out) => out.write(buf),
+            Self::Gzip(gz) => gz.write(buf),
+            Self::Zstd(zst) => zst.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Plain(out) => out.flush(),
+            Self::Gzip(gz) => gz.flush(),
+            Self::Zstd(zst) => zst.flush(),
+        }
+    }
+}
+
+/// escape a string literal as both N-Triples and Turtle require
+fn escape_literal(object: &str) -> String {
+    let mut escaped = String::with_capacity(object.len());
+    for c in object.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Turtle groups consecutive triples of a subject, so feed it triples
+/// ordered by subject to get one block per subject.
+pub struct RdfWriter<W: Write> {
+    out: W,
+    format: RdfFormat,
+    subject: Option<String>,
+    written: u64,
+    progress: Box<dyn FnMut(u64) + Send>,
+}
+
+impl<W: Write> RdfWriter<W> {
+    /// # Errors
+    ///
+    /// Will return `Err` if the Turtle prefixes cannot be written
+    pub fn new(
+        mut out: W,
+        format: RdfFormat,
+        progress: impl FnMut(u64) + Send + 'static,
+    ) -> io::Result<Self> {
+        match format {
+            RdfFormat::Turtle => {
+                writeln!(out, "@prefix {RESOURCE_PREFIX}: <{BASE_URI}/resource/> .")?;
+                writeln!(out, "@prefix {PROPERTY_PREFIX}: <{BASE_URI}/property/> .\n")?;
+            }
+            RdfFormat::Json => write!(out, "[")?,
+            RdfFormat::NTriples => {}
+        }
+        Ok(Self {
+            out,
+            format,
+            subject: None,
+            written: 0,
+            progress: Box::new(progress),
+        })
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if the output cannot be written
+    pub fn write(&mut self, subject: &str, predicate: &str, object: &str) -> io::Result<()> {
+        if self.format == RdfFormat::Json {
+            let separator = if self.written == 0 { "" } else { "," };
+            let triple = serde_json::json!({
+                "subject": subject,
+                "predicate": predicate,
+                "object": object,
+            });
+            write!(self.out, "{separator}\n  {triple}")?;
+            return self.count();
+        }
+        let object = escape_literal(object);
+        match self.format {
+            RdfFormat::NTriples => writeln!(
+                self.out,
+                "<{BASE_URI}/resource/{subject}> <{BASE_URI}/property/{predicate}> \"{object}\" ."
+            )?,
+            RdfFormat::Turtle => {
+                if self.subject.as_deref() == Some(subject) {
+                    write!(
+                        self.out,
+                        " ;\n    {PROPERTY_PREFIX}:{predicate} \"{object}\""
+                    )?;
+                } else {
+                    if self.subject.is_some() {
+                        writeln!(self.out, " .\n")?;
+                    }
+                    write!(
+                        self.out,
+                        "{RESOURCE_PREFIX}:{subject}\n    {PROPERTY_PREFIX}:{predicate} \"{object}\""
+                    )?;
+                    self.subject = Some(subject.to_string());
+                }
+            }
+            RdfFormat::Json => {}
+        }
+        self.count()
+    }
+
+    fn count(&mut self) -> io::Result<()> {
+        self.written += 1;
+        if self.written.is_multiple_of(PROGRESS_EVERY) {
+            (self.progress)(self.written);
+        }
+        Ok(())
+    }
+
+    /// close the last Turtle block, returning the output and the number of
+    /// triples written
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the output cannot be written
+    pub fn finish(mut self) -> io::Result<(W, u64)> {
+        if self.subject.is_some() {
+            writeln!(self.out, " .")?;
+        }
+        if self.format == RdfFormat::Json {
+            writeln!(self.out, "\n]")?;
+        }
+        Ok((self.out, self.written))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn write_all(format: RdfFormat, triples: &[(&str, &str, &str)]) -> String {
+        let mut writer = RdfWriter::new(Vec::new(), format, |_| {}).unwrap();
+        for (s, p, o) in triples {
+            writer.write(s, p, o).unwrap();
+        }
+        let (out, written) = writer.finish().unwrap();
+        assert_eq!(written, triples.len() as u64);
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_rdf_writer() {
+        let triples = [
+            ("a", "navipod_kind", "Pod"),
+            ("a", "navipod_name", "say \"hi\"\n"),
+            ("b", "navipod_kind", "Container"),
+        ];
+        assert_eq!(
+            write_all(RdfFormat::NTriples, &triples[1..2]),
+            "<http://navipod.navicore.tech/resource/a> <http://navipod.navicore.tech/property/navipod_name> \"say \\\"hi\\\"\\n\" .\n"
+        );
+        let ttl = write_all(RdfFormat::Turtle, &triples);
+        assert!(ttl.ends_with(
+            "res:a\n    prop:navipod_kind \"Pod\" ;\n    prop:navipod_name \"say \\\"hi\\\"\\n\" .\n\nres:b\n    prop:navipod_kind \"Container\" .\n"
+        ));
+        let json: serde_json::Value =
+            serde_json::from_str(&write_all(RdfFormat::Json, &triples)).unwrap();
+        assert_eq!(json[1]["object"], "say \"hi\"\n");
+        assert_eq!(json.as_array().map(Vec::len), Some(3));
+        assert_eq!(write_all(RdfFormat::Json, &[]), "[\n]\n");
+    }
+
+    #[test]
+    fn test_gzip_output() {
+        let path = "/tmp/test_navipod_rdf.nt.gz";
+        let mut writer =
+            RdfWriter::new(Output::create(path).unwrap(), RdfFormat::NTriples, |_| {}).unwrap();
+        writer.write("a", "navipod_kind", "Pod").unwrap();
+        let (out, _) = writer.finish().unwrap();
+        out.finish().unwrap();
+
+        let mut text = String::new();
+        GzDecoder::new(File::open(path).unwrap())
+            .read_to_string(&mut text)
+            .unwrap();
+        assert!(text.ends_with("\"Pod\" .\n"));
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_zstd_output() {
+        assert_eq!(Compress::Zstd.path("out.nt"), "out.nt.zst");
+        assert_eq!(Compress::Gzip.path("out.nt.gz"), "out.nt.gz");
+        let path = "/tmp/test_navipod_rdf.nt.zst";
+        let mut writer =
+            RdfWriter::new(Output::create(path).unwrap(), RdfFormat::NTriples, |_| {}).unwrap();
+        writer.write("a", "navipod_kind", "Pod").unwrap();
+        let (out, _) = writer.finish().unwrap();
+        out.finish().unwrap();
+
+        let text = zstd::decode_all(File::open(path).unwrap()).unwrap();
+        assert!(String::from_utf8(text).unwrap().ends_with("\"Pod\" .\n"));
+        let _ = std::fs::remove_file(path);
+    }
+}
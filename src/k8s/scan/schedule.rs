@@ -0,0 +1,270 @@
+//!Cron schedules for `scan-metrics --schedule`, so a baseline builds up
+//!without an external scheduler.  The five usual fields, minute hour
+//!day-of-month month day-of-week, in UTC, each `*`, a value, a range, a
+//!list or a step like `*/6` or `1-5/2`, and `@hourly`, `@daily`, `@weekly`.
+//!
+use chrono::{DateTime, Datelike, Duration as TimeDelta, TimeZone, Timelike, Utc};
+use std::collections::hash_map::RandomState;
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::hash::BuildHasher;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+/// `next_after` gives up on schedules like `0 0 31 2 *` after this many steps
+const MAX_STEPS: usize = 100_000;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Schedule {
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// cron matches either day field when both are restricted and both
+    /// otherwise, a field starting with `*`, ie: `*/2`, is not restricted
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+/// the values `text` allows as bits, ie: `*/15` of minutes is 0, 15, 30, 45
+fn field(text: &str, min: u32, max: u32, name: &str) -> Result<u64, String> {
+    let mut bits = 0u64;
+    for part in text.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("{name} step {step:?} is not a positive number"))?,
+            ),
+            None => (part, 1),
+        };
+        let value = |v: &str| {
+            v.parse::<u32>()
+                .ok()
+                .filter(|v| (min..=max).contains(v))
+                .ok_or_else(|| format!("{name} {v:?} is not between {min} and {max}"))
+        };
+        let (from, to) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((from, to)) => (value(from)?, value(to)?),
+                // `5/15` is every 15 from 5
+                None if step > 1 => (value(range)?, max),
+                None => {
+                    let v = value(range)?;
+                    (v, v)
+                }
+            },
+        };
+        if from > to {
+            return Err(format!("{name} range {range:?} runs backwards"));
+        }
+        for v in (from..=to).step_by(step as usize) {
+            bits |= 1 << v;
+        }
+    }
+    Ok(bits)
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let source = s.trim();
+        let expanded = match source {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields.as_slice() else {
+            return Err(format!(
+                "{source:?} is not five fields: minute hour day-of-month month day-of-week"
+            ));
+        };
+        let mut weekdays = field(weekday, 0, 7, "day of week")?;
+        // 7 is another Sunday
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        Ok(Self {
+            source: source.to_string(),
+            minutes: field(minute, 0, 59, "minute")?,
+            hours: field(hour, 0, 23, "hour")?,
+            days: field(day, 1, 31, "day of month")?,
+            months: field(month, 1, 12, "month")?,
+            weekdays,
+            days_restricted: !day.starts_with('*'),
+            weekdays_restricted: !weekday.starts_with('*'),
+        })
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+const fn has(bits: u64, value: u32) -> bool {
+    bits & (1 << value) != 0
+}
+
+impl Schedule {
+    fn day_matches(&self, at: DateTime<Utc>) -> bool {
+        let day = has(self.days, at.day());
+        let weekday = has(self.weekdays, at.weekday().num_days_from_sunday());
+        if self.days_restricted && self.weekdays_restricted {
+            day || weekday
+        } else {
+            day && weekday
+        }
+    }
+
+    /// the first minute after `after` the schedule fires, `None` for a
+    /// schedule that never does
+    #[must_use]
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut at = after.with_second(0)?.with_nanosecond(0)? + TimeDelta::minutes(1);
+        for _ in 0..MAX_STEPS {
+            if !has(self.months, at.month()) {
+                let (year, month) = if at.month() == 12 {
+                    (at.year() + 1, 1)
+                } else {
+                    (at.year(), at.month() + 1)
+                };
+                at = Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single()?;
+            } else if !self.day_matches(at) {
+                at = at.date_naive().and_hms_opt(0, 0, 0)?.and_utc() + TimeDelta::days(1);
+            } else if !has(self.hours, at.hour()) {
+                at = at.with_minute(0)? + TimeDelta::hours(1);
+            } else if !has(self.minutes, at.minute()) {
+                at += TimeDelta::minutes(1);
+            } else {
+                return Some(at);
+            }
+        }
+        None
+    }
+
+    /// how often the schedule fired after `from` up to and with `to`
+    #[must_use]
+    pub fn count_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> usize {
+        let mut count = 0;
+        let mut at = from;
+        while let Some(next) = self.next_after(at).filter(|next| *next <= to) {
+            count += 1;
+            at = next;
+        }
+        count
+    }
+}
+
+/// up to `max`, so many schedulers on one expression do not all hit the api
+/// server in the same second
+fn jitter(max: Duration) -> Duration {
+    if max.is_zero() {
+        return max;
+    }
+    let random = RandomState::new().hash_one(SystemTime::now());
+    #[allow(clippy::cast_possible_truncation)]
+    let max_ms = max.as_millis() as u64;
+    Duration::from_millis(random % max_ms)
+}
+
+/// Run `job` every time `schedule` fires, a random delay of up to `max_jitter`
+/// after.  Runs never overlap: a run still going when the schedule fires
+/// again is waited for and the missed runs are skipped, not queued.
+pub async fn run<F, Fut>(schedule: &Schedule, max_jitter: Duration, mut job: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), Box<dyn Error>>>,
+{
+    let mut after = Utc::now();
+    while let Some(next) = schedule.next_after(after) {
+        let delay = jitter(max_jitter);
+        println!(
+            "next scan {} (+{}s jitter)",
+            next.format("%Y-%m-%d %H:%M UTC"),
+            delay.as_secs()
+        );
+        let wait = (next - Utc::now()).to_std().unwrap_or_default() + delay;
+        tokio::time::sleep(wait).await;
+        let started = Utc::now();
+        match job().await {
+            Ok(()) => println!("scan done in {}s", (Utc::now() - started).num_seconds()),
+            Err(e) => eprintln!("scan failed: {e}"),
+        }
+        after = Utc::now();
+        let skipped = schedule.count_between(next, after);
+        if skipped > 0 {
+            println!("skipped {skipped} runs of {schedule}, the scan outlasted them");
+        }
+    }
+    eprintln!("{schedule} never fires");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_schedule() {
+        let every_6h: Schedule = "0 */6 * * *".parse().unwrap();
+        assert_eq!(
+            every_6h.next_after(at("2024-03-01T05:30:10Z")),
+            Some(at("2024-03-01T06:00:00Z"))
+        );
+        assert_eq!(
+            every_6h.next_after(at("2024-03-01T18:00:00Z")),
+            Some(at("2024-03-02T00:00:00Z"))
+        );
+        assert_eq!(
+            every_6h.count_between(at("2024-03-01T06:00:00Z"), at("2024-03-01T18:00:00Z")),
+            2
+        );
+
+        // 2024-03-02 is a Saturday
+        let weekdays: Schedule = "30 2 * * 1-5".parse().unwrap();
+        assert_eq!(
+            weekdays.next_after(at("2024-03-02T12:00:00Z")),
+            Some(at("2024-03-04T02:30:00Z"))
+        );
+        let first_or_monday: Schedule = "0 0 1 * 1".parse().unwrap();
+        assert_eq!(
+            first_or_monday.next_after(at("2024-03-01T12:00:00Z")),
+            Some(at("2024-03-04T00:00:00Z"))
+        );
+        // odd days that are Mondays, not every odd day and every Monday:
+        // not the 3rd nor Monday the 4th
+        let odd_mondays: Schedule = "0 0 */2 * 1".parse().unwrap();
+        assert_eq!(
+            odd_mondays.next_after(at("2024-03-01T12:00:00Z")),
+            Some(at("2024-03-11T00:00:00Z"))
+        );
+        let new_year: Schedule = "@monthly".parse().unwrap();
+        assert_eq!(
+            new_year.next_after(at("2024-12-15T00:00:00Z")),
+            Some(at("2025-01-01T00:00:00Z"))
+        );
+        let never: Schedule = "0 0 31 2 *".parse().unwrap();
+        assert_eq!(never.next_after(at("2024-03-01T00:00:00Z")), None);
+
+        assert!("61 * * * *".parse::<Schedule>().is_err());
+        assert!("* * *".parse::<Schedule>().is_err());
+        assert!("*/0 * * * *".parse::<Schedule>().is_err());
+        assert!("5-1 * * * *".parse::<Schedule>().is_err());
+        assert!(jitter(Duration::from_secs(60)) < Duration::from_secs(60));
+    }
+}
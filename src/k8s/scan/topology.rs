@@ -0,0 +1,206 @@
+//!Workload topology drawn from scan triples as Graphviz DOT or Mermaid:
+//!Deployment → ReplicaSet → Pod → Container, with Ingress → Service →
+//!Pod edges for the traffic path.
+//!
+use crate::k8s::scan::live::Triple;
+use clap::ValueEnum;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum GraphFormat {
+    #[default]
+    Dot,
+    Mermaid,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Node {
+    pub id: String,
+    pub kind: String,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Topology {
+    pub nodes: Vec<Node>,
+    /// (from, to) node ids
+    pub edges: Vec<(String, String)>,
+}
+
+/// a node id both formats accept
+fn node_id(subject: &str) -> String {
+    let id: String = subject
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("n{id}")
+}
+
+impl Topology {
+    /// Kinds other than the workload and traffic ones are left out, as are
+    /// links to objects outside the scan.
+    #[must_use]
+    pub fn from_triples(triples: &[Triple]) -> Self {
+        let mut subjects: BTreeMap<&str, BTreeMap<&str, Vec<&str>>> = BTreeMap::new();
+        for (subject, predicate, object) in triples {
+            subjects
+                .entry(subject)
+                .or_default()
+                .entry(predicate)
+                .or_default()
+                .push(object);
+        }
+        let first = |props: &BTreeMap<&str, Vec<&str>>, predicate: &str| {
+            props
+                .get(predicate)
+                .and_then(|values| values.first())
+                .map(|value| (*value).to_string())
+                .unwrap_or_default()
+        };
+        let kinds = [
+            "Deployment",
+            "ReplicaSet",
+            "Pod",
+            "Container",
+            "Service",
+            "Ingress",
+        ];
+        let mut topology = Self::default();
+        let mut services = BTreeMap::new();
+        for (subject, props) in &subjects {
+            let kind = first(props, "navipod_kind");
+            if !kinds.contains(&kind.as_str()) {
+                continue;
+            }
+            if kind == "Service" {
+                services.insert(first(props, "navipod_name"), *subject);
+            }
+            topology.nodes.push(Node {
+                id: node_id(subject),
+                name: first(props, "navipod_name"),
+                kind,
+            });
+        }
+        let mut link = |from: &str, to: &str| {
+            if subjects.contains_key(from) && subjects.contains_key(to) {
+                topology.edges.push((node_id(from), node_id(to)));
+            }
+        };
+        for (subject, props) in &subjects {
+            let values = |predicate: &str| props.get(predicate).cloned().unwrap_or_default();
+            for owner in values("navipod_owner") {
+                link(owner, subject);
+            }
+            for pod in values("navipod_pod") {
+                link(pod, subject);
+            }
+            for pod in values("navipod_selects") {
+                link(subject, pod);
+            }
+            for service in values("navipod_service") {
+                if let Some(service) = services.get(service) {
+                    link(subject, service);
+                }
+            }
+        }
+        topology.edges.sort();
+        topology.edges.dedup();
+        topology
+    }
+
+    #[must_use]
+    pub fn render(&self, format: GraphFormat) -> String {
+        let mut out = String::new();
+        match format {
+            GraphFormat::Dot => {
+                let _ = writeln!(out, "digraph navipod {{\n    rankdir=LR;");
+                for node in &self.nodes {
+                    let _ = writeln!(
+                        out,
+                        "    {} [label=\"{}\\n{}\" shape={}];",
+                        node.id,
+                        node.kind,
+                        node.name.replace('"', "\\\""),
+                        if node.kind == "Container" {
+                            "ellipse"
+                        } else {
+                            "box"
+                        }
+                    );
+                }
+                for (from, to) in &self.edges {
+                    let _ = writeln!(out, "    {from} -> {to};");
+                }
+                out.push('}');
+            }
+            GraphFormat::Mermaid => {
+                out.push_str("flowchart LR");
+                for node in &self.nodes {
+                    let _ = write!(
+                        out,
+                        "\n    {}[\"{}<br/>{}\"]",
+                        node.id,
+                        node.kind,
+                        node.name.replace('"', "#quot;")
+                    );
+                }
+                for (from, to) in &self.edges {
+                    let _ = write!(out, "\n    {from} --> {to}");
+                }
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topology() {
+        let triples: Vec<Triple> = [
+            ("d1", "navipod_kind", "Deployment"),
+            ("d1", "navipod_name", "web"),
+            ("rs1", "navipod_kind", "ReplicaSet"),
+            ("rs1", "navipod_name", "web-5d8f7"),
+            ("rs1", "navipod_owner", "d1"),
+            ("p1", "navipod_kind", "Pod"),
+            ("p1", "navipod_name", "web-5d8f7-abcde"),
+            ("p1", "navipod_owner", "rs1"),
+            ("p1-web", "navipod_kind", "Container"),
+            ("p1-web", "navipod_name", "web"),
+            ("p1-web", "navipod_pod", "p1"),
+            ("s1", "navipod_kind", "Service"),
+            ("s1", "navipod_name", "web-svc"),
+            ("s1", "navipod_selects", "p1"),
+            ("i1", "navipod_kind", "Ingress"),
+            ("i1", "navipod_name", "web"),
+            ("i1", "navipod_service", "web-svc"),
+            ("i1", "navipod_service", "elsewhere"),
+            ("cert-web.example.com", "navipod_kind", "Certificate"),
+        ]
+        .iter()
+        .map(|(s, p, o)| (s.to_string(), p.to_string(), o.to_string()))
+        .collect();
+        let topology = Topology::from_triples(&triples);
+        assert_eq!(topology.nodes.len(), 6);
+        assert_eq!(
+            topology.edges,
+            vec![
+                ("nd1".to_string(), "nrs1".to_string()),
+                ("ni1".to_string(), "ns1".to_string()),
+                ("np1".to_string(), "np1_web".to_string()),
+                ("nrs1".to_string(), "np1".to_string()),
+                ("ns1".to_string(), "np1".to_string()),
+            ]
+        );
+        let dot = topology.render(GraphFormat::Dot);
+        assert!(dot.contains("    nd1 [label=\"Deployment\\nweb\" shape=box];"));
+        assert!(dot.contains("    nd1 -> nrs1;"));
+        let mermaid = topology.render(GraphFormat::Mermaid);
+        assert!(mermaid.starts_with("flowchart LR\n    nd1[\"Deployment<br/>web\"]"));
+        assert!(mermaid.ends_with("    ns1 --> np1"));
+    }
+}
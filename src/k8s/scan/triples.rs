@@ -1,6 +1,5 @@
 use sqlx::Pool;
 use sqlx::Sqlite;
-use std::error::Error;
 use tracing::debug;
 use uuid::Uuid;
 
@@ -10,7 +9,7 @@ use uuid::Uuid;
 pub async fn persist(
     triples: Vec<Vec<(String, String, String)>>,
     pool: &Pool<Sqlite>,
-) -> Result<(), Box<dyn Error>> {
+) -> Result<(), sqlx::Error> {
     debug!("persisting {} metrics", triples.len());
 
     for vec in triples {
@@ -6,6 +6,7 @@ pub fn format(
     podname: &str,
     appname: &str,
     namespace: &str,
+    context: &str,
 ) -> Vec<Vec<(String, String)>> {
     let datetime: DateTime<Utc> = Utc::now();
     let date_string: String = datetime.to_rfc3339();
@@ -22,6 +23,7 @@ pub fn format(
         observation.push(("navipod_podname".to_string(), podname.to_string()));
         observation.push(("navipod_appname".to_string(), appname.to_string()));
         observation.push(("navipod_namespace".to_string(), namespace.to_string()));
+        observation.push(("navipod_context".to_string(), context.to_string()));
     }
     metrics
 }
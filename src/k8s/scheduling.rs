@@ -0,0 +1,509 @@
+//!Why a Pending pod does not fit anywhere.  The scheduler's own event only
+//!says how many nodes failed which predicate, this checks each node against
+//!the pod's node selector, required node affinity, tolerations and resource
+//!requests so the constraint to relax is named per node.
+//!
+use crate::error::Result;
+use crate::k8s::cache::cache_manager::get_cache;
+use crate::k8s::cache::fetcher::fetch;
+use crate::k8s::cache::{CachedData, DataRequest, FetchStatus};
+use crate::k8s::client_manager::get_client;
+use crate::k8s::paging;
+use k8s_openapi::api::core::v1::{
+    Container, Node, NodeSelectorRequirement, Pod, PodSpec, Taint, Toleration,
+};
+use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+use kube::api::ListParams;
+use kube::Api;
+use std::collections::{BTreeMap, HashMap};
+use tracing::debug;
+
+/// the parts of a node the scheduler looks at
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NodeInfo {
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+    pub taints: Vec<Taint>,
+    pub unschedulable: bool,
+    pub allocatable: Resources,
+    /// requests of the pods already running there
+    pub requested: Resources,
+    /// the same by namespace
+    pub namespaces: BTreeMap<String, Resources>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Resources {
+    pub cpu_millis: f64,
+    pub memory_bytes: f64,
+    pub pods: f64,
+}
+
+impl Resources {
+    pub fn add(&mut self, other: &Self) {
+        self.cpu_millis += other.cpu_millis;
+        self.memory_bytes += other.memory_bytes;
+        self.pods += other.pods;
+    }
+}
+
+/// One reason a node was ruled out.  `kind` groups reasons for the summary.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Exclusion {
+    pub kind: &'static str,
+    pub detail: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeVerdict {
+    pub node: String,
+    /// empty if the pod fits
+    pub exclusions: Vec<Exclusion>,
+}
+
+/// Kubernetes quantity in base units: cores or bytes.
+#[must_use]
+pub fn parse_quantity(quantity: &str) -> Option<f64> {
+    let quantity = quantity.trim();
+    let suffixes: [(&str, f64); 13] = [
+        ("Ki", 1024_f64),
+        ("Mi", 1024_f64.powi(2)),
+        ("Gi", 1024_f64.powi(3)),
+        ("Ti", 1024_f64.powi(4)),
+        ("Pi", 1024_f64.powi(5)),
+        ("Ei", 1024_f64.powi(6)),
+        ("n", 1e-9),
+        ("u", 1e-6),
+        ("m", 1e-3),
+        ("k", 1e3),
+        ("M", 1e6),
+        ("G", 1e9),
+        ("T", 1e12),
+    ];
+    for (suffix, factor) in suffixes {
+        if let Some(number) = quantity.strip_suffix(suffix) {
+            return number.parse::<f64>().ok().map(|n| n * factor);
+        }
+    }
+    quantity.parse().ok()
+}
+
+fn quantity(map: Option<&BTreeMap<String, Quantity>>, name: &str) -> Option<f64> {
+    map?.get(name).and_then(|q| parse_quantity(&q.0))
+}
+
+/// requests of one container, falling back to limits as the api server does
+fn container_requests(container: &Container) -> Resources {
+    let resources = container.resources.as_ref();
+    let get = |name| {
+        quantity(resources.and_then(|r| r.requests.as_ref()), name)
+            .or_else(|| quantity(resources.and_then(|r| r.limits.as_ref()), name))
+            .unwrap_or(0.0)
+    };
+    Resources {
+        cpu_millis: get("cpu") * 1000.0,
+        memory_bytes: get("memory"),
+        pods: 0.0,
+    }
+}
+
+/// what the scheduler reserves for a pod: all containers together, or the
+/// largest init container if that is more
+#[must_use]
+pub fn pod_requests(spec: &PodSpec) -> Resources {
+    let mut total = Resources {
+        pods: 1.0,
+        ..Resources::default()
+    };
+    for container in &spec.containers {
+        let r = container_requests(container);
+        total.cpu_millis += r.cpu_millis;
+        total.memory_bytes += r.memory_bytes;
+    }
+    for container in spec.init_containers.iter().flatten() {
+        let r = container_requests(container);
+        total.cpu_millis = total.cpu_millis.max(r.cpu_millis);
+        total.memory_bytes = total.memory_bytes.max(r.memory_bytes);
+    }
+    total
+}
+
+fn tolerates(toleration: &Toleration, taint: &Taint) -> bool {
+    let effect_matches = toleration
+        .effect
+        .as_deref()
+        .is_none_or(|effect| effect.is_empty() || effect == taint.effect);
+    let key = toleration.key.as_deref().unwrap_or_default();
+    let key_matches = match toleration.operator.as_deref() {
+        Some("Exists") => key.is_empty() || key == taint.key,
+        _ => key == taint.key && toleration.value == taint.value,
+    };
+    effect_matches && key_matches
+}
+
+fn requirement_matches(
+    requirement: &NodeSelectorRequirement,
+    labels: &BTreeMap<String, String>,
+) -> bool {
+    let label = labels.get(&requirement.key);
+    let values = requirement.values.as_deref().unwrap_or_default();
+    let number = |s: &str| s.parse::<i64>().ok();
+    match requirement.operator.as_str() {
+        "In" => label.is_some_and(|l| values.contains(l)),
+        "NotIn" => label.is_none_or(|l| !values.contains(l)),
+        "Exists" => label.is_some(),
+        "DoesNotExist" => label.is_none(),
+        "Gt" => label
+            .and_then(|l| number(l))
+            .zip(values.first().and_then(|v| number(v)))
+            .is_some_and(|(l, v)| l > v),
+        "Lt" => label
+            .and_then(|l| number(l))
+            .zip(values.first().and_then(|v| number(v)))
+            .is_some_and(|(l, v)| l < v),
+        _ => false,
+    }
+}
+
+fn requirement_string(requirement: &NodeSelectorRequirement) -> String {
+    match requirement.values.as_deref() {
+        Some(values) if !values.is_empty() => format!(
+            "{} {} ({})",
+            requirement.key,
+            requirement.operator,
+            values.join(", ")
+        ),
+        _ => format!("{} {}", requirement.key, requirement.operator),
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn check_node(spec: &PodSpec, requests: &Resources, node: &NodeInfo) -> Vec<Exclusion> {
+    let mut exclusions = Vec::new();
+    let mut exclude = |kind, detail| exclusions.push(Exclusion { kind, detail });
+
+    if node.unschedulable {
+        exclude("cordoned", "node is cordoned".to_string());
+    }
+    for (key, value) in spec.node_selector.iter().flatten() {
+        match node.labels.get(key) {
+            Some(label) if label == value => {}
+            Some(label) => exclude("node selector", format!("{key}={label}, pod wants {value}")),
+            None => exclude(
+                "node selector",
+                format!("no label {key}, pod wants {value}"),
+            ),
+        }
+    }
+    let terms = spec
+        .affinity
+        .as_ref()
+        .and_then(|a| a.node_affinity.as_ref())
+        .and_then(|a| {
+            a.required_during_scheduling_ignored_during_execution
+                .as_ref()
+        })
+        .map(|required| required.node_selector_terms.as_slice())
+        .unwrap_or_default();
+    // terms are ORed, the expressions of a term ANDed
+    let term_fits = |term: &k8s_openapi::api::core::v1::NodeSelectorTerm| {
+        term.match_expressions
+            .iter()
+            .flatten()
+            .all(|r| requirement_matches(r, &node.labels))
+    };
+    if !terms.is_empty() && !terms.iter().any(term_fits) {
+        let failed: Vec<String> = terms
+            .iter()
+            .flat_map(|term| term.match_expressions.iter().flatten())
+            .filter(|r| !requirement_matches(r, &node.labels))
+            .map(requirement_string)
+            .collect();
+        exclude("node affinity", format!("fails {}", failed.join(" and ")));
+    }
+    let tolerations = spec.tolerations.as_deref().unwrap_or_default();
+    for taint in node
+        .taints
+        .iter()
+        .filter(|t| t.effect == "NoSchedule" || t.effect == "NoExecute")
+        .filter(|t| {
+            !tolerations
+                .iter()
+                .any(|toleration| tolerates(toleration, t))
+        })
+    {
+        let value = taint
+            .value
+            .as_deref()
+            .map_or_else(String::new, |v| format!("={v}"));
+        exclude(
+            "untolerated taint",
+            format!("{}{value}:{}", taint.key, taint.effect),
+        );
+    }
+    let free = |allocatable: f64, requested: f64| allocatable - requested;
+    if requests.cpu_millis > free(node.allocatable.cpu_millis, node.requested.cpu_millis) {
+        exclude(
+            "insufficient cpu",
+            format!(
+                "wants {}m, {}m of {}m free",
+                requests.cpu_millis as i64,
+                free(node.allocatable.cpu_millis, node.requested.cpu_millis) as i64,
+                node.allocatable.cpu_millis as i64
+            ),
+        );
+    }
+    if requests.memory_bytes > free(node.allocatable.memory_bytes, node.requested.memory_bytes) {
+        let mib = |bytes: f64| (bytes / 1024_f64.powi(2)) as i64;
+        exclude(
+            "insufficient memory",
+            format!(
+                "wants {}Mi, {}Mi of {}Mi free",
+                mib(requests.memory_bytes),
+                mib(free(
+                    node.allocatable.memory_bytes,
+                    node.requested.memory_bytes
+                )),
+                mib(node.allocatable.memory_bytes)
+            ),
+        );
+    }
+    if node.allocatable.pods > 0.0 && node.requested.pods + 1.0 > node.allocatable.pods {
+        exclude(
+            "too many pods",
+            format!("{} of {} pods", node.requested.pods, node.allocatable.pods),
+        );
+    }
+    exclusions
+}
+
+/// Check every node for a pod that is not scheduled yet.
+#[must_use]
+pub fn diagnose(pod: &Pod, nodes: &[NodeInfo]) -> Vec<NodeVerdict> {
+    let Some(spec) = &pod.spec else {
+        return vec![];
+    };
+    let requests = pod_requests(spec);
+    nodes
+        .iter()
+        .map(|node| NodeVerdict {
+            node: node.name.clone(),
+            exclusions: check_node(spec, &requests, node),
+        })
+        .collect()
+}
+
+/// ie: "0/3 nodes fit: 2 untolerated taint, 1 insufficient memory"
+#[must_use]
+pub fn summary(verdicts: &[NodeVerdict]) -> String {
+    let fitting = verdicts.iter().filter(|v| v.exclusions.is_empty()).count();
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for exclusion in verdicts.iter().flat_map(|v| &v.exclusions) {
+        match counts.iter_mut().find(|(kind, _)| *kind == exclusion.kind) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((exclusion.kind, 1)),
+        }
+    }
+    let reasons: Vec<String> = counts
+        .iter()
+        .map(|(kind, count)| format!("{count} {kind}"))
+        .collect();
+    if reasons.is_empty() {
+        format!("{fitting}/{} nodes fit", verdicts.len())
+    } else {
+        format!(
+            "{fitting}/{} nodes fit: {}",
+            verdicts.len(),
+            reasons.join(", ")
+        )
+    }
+}
+
+/// waiting for a node rather than starting on one
+#[must_use]
+pub fn is_unscheduled(pod: &Pod) -> bool {
+    pod.status.as_ref().and_then(|s| s.phase.as_deref()) == Some("Pending")
+        && pod
+            .spec
+            .as_ref()
+            .is_none_or(|spec| spec.node_name.is_none())
+}
+
+fn to_node_info(
+    node: &Node,
+    requested: Option<&Resources>,
+    namespaces: Option<&BTreeMap<String, Resources>>,
+) -> NodeInfo {
+    let allocatable = node.status.as_ref().and_then(|s| s.allocatable.as_ref());
+    NodeInfo {
+        name: node.metadata.name.clone().unwrap_or_default(),
+        labels: node.metadata.labels.clone().unwrap_or_default(),
+        taints: node
+            .spec
+            .as_ref()
+            .and_then(|s| s.taints.clone())
+            .unwrap_or_default(),
+        unschedulable: node
+            .spec
+            .as_ref()
+            .and_then(|s| s.unschedulable)
+            .unwrap_or(false),
+        allocatable: Resources {
+            cpu_millis: quantity(allocatable, "cpu").unwrap_or(0.0) * 1000.0,
+            memory_bytes: quantity(allocatable, "memory").unwrap_or(0.0),
+            pods: quantity(allocatable, "pods").unwrap_or(0.0),
+        },
+        requested: requested.copied().unwrap_or_default(),
+        namespaces: namespaces.cloned().unwrap_or_default(),
+    }
+}
+
+/// # Errors
+///
+/// Will return `Err` if nodes can not be listed
+pub async fn list_nodes() -> Result<Vec<NodeInfo>> {
+    let client = get_client().await?;
+    let nodes: Api<Node> = Api::all(client.clone());
+    let nodes = paging::list(&nodes, ListParams::default(), "list nodes").await?;
+
+    // running pods count against allocatable, but seeing them needs
+    // cluster wide read access, without it only allocatable is compared
+    let pods: Api<Pod> = Api::all(client);
+    let lp = ListParams::default()
+        .fields("status.phase!=Succeeded,status.phase!=Failed,spec.nodeName!=");
+    let mut requested: HashMap<String, Resources> = HashMap::new();
+    let mut namespaces: HashMap<String, BTreeMap<String, Resources>> = HashMap::new();
+    match paging::list(&pods, lp, "list scheduled pods").await {
+        Ok(pods) => {
+            for pod in &pods {
+                let Some(spec) = &pod.spec else {
+                    continue;
+                };
+                let Some(node) = &spec.node_name else {
+                    continue;
+                };
+                let r = pod_requests(spec);
+                requested.entry(node.clone()).or_default().add(&r);
+                namespaces
+                    .entry(node.clone())
+                    .or_default()
+                    .entry(pod.metadata.namespace.clone().unwrap_or_default())
+                    .or_default()
+                    .add(&r);
+            }
+        }
+        Err(e) => debug!("can not list scheduled pods: {e}"),
+    }
+    Ok(nodes
+        .iter()
+        .map(|node| {
+            let name = node.metadata.name.as_deref().unwrap_or_default();
+            to_node_info(node, requested.get(name), namespaces.get(name))
+        })
+        .collect())
+}
+
+/// Nodes from the cache, fetched into it when missing or stale.
+///
+/// # Errors
+///
+/// Will return `Err` if nodes have to be fetched and can not be listed
+pub async fn cached_nodes() -> Result<Vec<NodeInfo>> {
+    let cache = get_cache();
+    if cache.status(&DataRequest::Nodes) == FetchStatus::Fresh {
+        if let Some(CachedData::Nodes(nodes)) = cache.get(&DataRequest::Nodes) {
+            return Ok(nodes);
+        }
+    }
+    match fetch(&DataRequest::Nodes)
+        .await
+        .inspect_err(|e| cache.mark_failed(&DataRequest::Nodes, e.to_string()))?
+    {
+        CachedData::Nodes(nodes) => {
+            cache.put(DataRequest::Nodes, CachedData::Nodes(nodes.clone()));
+            Ok(nodes)
+        }
+        _ => Ok(vec![]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::serde_json::{from_value, json};
+
+    fn node(name: &str, labels: &[(&str, &str)], taints: Vec<Taint>, memory_gib: f64) -> NodeInfo {
+        NodeInfo {
+            name: name.to_string(),
+            labels: labels
+                .iter()
+                .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+                .collect(),
+            taints,
+            unschedulable: false,
+            allocatable: Resources {
+                cpu_millis: 4000.0,
+                memory_bytes: memory_gib * 1024_f64.powi(3),
+                pods: 110.0,
+            },
+            requested: Resources::default(),
+            namespaces: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_diagnose_pending_pod() {
+        assert_eq!(parse_quantity("500m"), Some(0.5));
+        assert_eq!(parse_quantity("2Gi"), Some(2.0 * 1024_f64.powi(3)));
+        assert_eq!(parse_quantity("1e3"), Some(1000.0));
+
+        let pod: Pod = from_value(json!({
+            "metadata": {"name": "web"},
+            "spec": {
+                "nodeSelector": {"disk": "ssd"},
+                "tolerations": [{"key": "dedicated", "operator": "Equal", "value": "web", "effect": "NoSchedule"}],
+                "containers": [{"name": "web", "resources": {"requests": {"cpu": "500m", "memory": "3Gi"}}}]
+            },
+            "status": {"phase": "Pending"}
+        }))
+        .unwrap();
+        assert!(is_unscheduled(&pod));
+
+        let gpu_taint = Taint {
+            key: "gpu".to_string(),
+            effect: "NoSchedule".to_string(),
+            ..Taint::default()
+        };
+        let web_taint = Taint {
+            key: "dedicated".to_string(),
+            value: Some("web".to_string()),
+            effect: "NoSchedule".to_string(),
+            ..Taint::default()
+        };
+        let nodes = vec![
+            node("a", &[("disk", "hdd")], vec![], 8.0),
+            node("b", &[("disk", "ssd")], vec![gpu_taint], 8.0),
+            node("c", &[("disk", "ssd")], vec![web_taint.clone()], 2.0),
+            node("d", &[("disk", "ssd")], vec![web_taint], 8.0),
+        ];
+        let verdicts = diagnose(&pod, &nodes);
+        let kinds: Vec<Vec<&str>> = verdicts
+            .iter()
+            .map(|v| v.exclusions.iter().map(|e| e.kind).collect())
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                vec!["node selector"],
+                vec!["untolerated taint"],
+                vec!["insufficient memory"],
+                vec![],
+            ]
+        );
+        assert_eq!(verdicts[0].exclusions[0].detail, "disk=hdd, pod wants ssd");
+        assert_eq!(
+            summary(&verdicts),
+            "1/4 nodes fit: 1 node selector, 1 untolerated taint, 1 insufficient memory"
+        );
+    }
+}
@@ -0,0 +1,461 @@
+//!Evaluate a pod spec against the Pod Security Standards.
+//!
+//!The checks follow the upstream `baseline` and `restricted` profiles closely
+//!enough to tell which level a pod would be admitted under and why.
+//!
+use crate::tui::data::SecurityDetail;
+use crate::tui::theme::symbols;
+use k8s_openapi::api::core::v1::{
+    Capabilities, Container, Pod, PodSecurityContext, PodSpec, SecurityContext,
+};
+use std::fmt;
+
+const BASELINE_CAPABILITIES: [&str; 13] = [
+    "AUDIT_WRITE",
+    "CHOWN",
+    "DAC_OVERRIDE",
+    "FOWNER",
+    "FSETID",
+    "KILL",
+    "MKNOD",
+    "NET_BIND_SERVICE",
+    "SETFCAP",
+    "SETGID",
+    "SETPCAP",
+    "SETUID",
+    "SYS_CHROOT",
+];
+
+const SELINUX_TYPES: [&str; 3] = ["container_t", "container_init_t", "container_kvm_t"];
+
+const APPARMOR_ANNOTATION_PREFIX: &str = "container.apparmor.security.beta.kubernetes.io/";
+
+/// Pod Security Standard levels ordered from most to least strict.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Level {
+    Restricted,
+    Baseline,
+    Privileged,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Restricted => "restricted",
+            Self::Baseline => "baseline",
+            Self::Privileged => "privileged",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// A failed check.  `level` is the strictest level the pod can still satisfy.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Violation {
+    pub container: Option<String>,
+    pub level: Level,
+    pub reason: String,
+}
+
+impl Violation {
+    fn pod(level: Level, reason: String) -> Self {
+        Self {
+            container: None,
+            level,
+            reason,
+        }
+    }
+
+    fn container(name: &str, level: Level, reason: String) -> Self {
+        Self {
+            container: Some(name.to_string()),
+            level,
+            reason,
+        }
+    }
+}
+
+fn seccomp_type(sc: Option<&SecurityContext>, pod_sc: Option<&PodSecurityContext>) -> String {
+    sc.and_then(|sc| sc.seccomp_profile.as_ref())
+        .or_else(|| pod_sc.and_then(|sc| sc.seccomp_profile.as_ref()))
+        .map_or_else(String::new, |p| p.type_.clone())
+}
+
+/// `appArmorProfile` fields arrived after the API version we build against, so
+/// only the beta annotation is consulted.
+fn apparmor_type(pod: &Pod, container: &Container) -> String {
+    pod.metadata
+        .annotations
+        .as_ref()
+        .and_then(|a| a.get(&format!("{APPARMOR_ANNOTATION_PREFIX}{}", container.name)))
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn run_as_non_root(sc: Option<&SecurityContext>, pod_sc: Option<&PodSecurityContext>) -> bool {
+    sc.and_then(|sc| sc.run_as_non_root)
+        .or_else(|| pod_sc.and_then(|sc| sc.run_as_non_root))
+        .unwrap_or(false)
+}
+
+fn run_as_user(sc: Option<&SecurityContext>, pod_sc: Option<&PodSecurityContext>) -> Option<i64> {
+    sc.and_then(|sc| sc.run_as_user)
+        .or_else(|| pod_sc.and_then(|sc| sc.run_as_user))
+}
+
+fn capability_list(caps: Option<&Capabilities>, add: bool) -> Vec<String> {
+    caps.and_then(|c| if add { c.add.clone() } else { c.drop.clone() })
+        .unwrap_or_default()
+}
+
+fn check_pod_spec(spec: &PodSpec) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    for (flag, name) in [
+        (spec.host_network, "hostNetwork"),
+        (spec.host_pid, "hostPID"),
+        (spec.host_ipc, "hostIPC"),
+    ] {
+        if flag.unwrap_or(false) {
+            violations.push(Violation::pod(Level::Privileged, format!("{name}: true")));
+        }
+    }
+
+    for volume in spec.volumes.iter().flatten() {
+        if volume.host_path.is_some() {
+            violations.push(Violation::pod(
+                Level::Privileged,
+                format!("hostPath volume {}", volume.name),
+            ));
+        } else if !(volume.config_map.is_some()
+            || volume.csi.is_some()
+            || volume.downward_api.is_some()
+            || volume.empty_dir.is_some()
+            || volume.ephemeral.is_some()
+            || volume.persistent_volume_claim.is_some()
+            || volume.projected.is_some()
+            || volume.secret.is_some())
+        {
+            violations.push(Violation::pod(
+                Level::Baseline,
+                format!("volume {} has a restricted type", volume.name),
+            ));
+        }
+    }
+
+    if let Some(sc) = &spec.security_context {
+        if let Some(sysctls) = &sc.sysctls {
+            for sysctl in sysctls {
+                if !is_safe_sysctl(&sysctl.name) {
+                    violations.push(Violation::pod(
+                        Level::Privileged,
+                        format!("unsafe sysctl {}", sysctl.name),
+                    ));
+                }
+            }
+        }
+        if sc.se_linux_options.as_ref().is_some_and(|se| {
+            !se_linux_allowed(se.type_.as_deref(), se.user.as_deref(), se.role.as_deref())
+        }) {
+            violations.push(Violation::pod(
+                Level::Privileged,
+                "seLinuxOptions not allowed".to_string(),
+            ));
+        }
+    }
+
+    violations
+}
+
+fn is_safe_sysctl(name: &str) -> bool {
+    matches!(
+        name,
+        "kernel.shm_rmid_forced"
+            | "net.ipv4.ip_local_port_range"
+            | "net.ipv4.ip_unprivileged_port_start"
+            | "net.ipv4.tcp_syncookies"
+            | "net.ipv4.ping_group_range"
+            | "net.ipv4.ip_local_reserved_ports"
+            | "net.ipv4.tcp_keepalive_time"
+            | "net.ipv4.tcp_fin_timeout"
+            | "net.ipv4.tcp_keepalive_intvl"
+            | "net.ipv4.tcp_keepalive_probes"
+    )
+}
+
+fn se_linux_allowed(type_: Option<&str>, user: Option<&str>, role: Option<&str>) -> bool {
+    type_.is_none_or(|t| t.is_empty() || SELINUX_TYPES.contains(&t))
+        && user.is_none_or(str::is_empty)
+        && role.is_none_or(str::is_empty)
+}
+
+fn check_container(pod: &Pod, container: &Container) -> Vec<Violation> {
+    let name = container.name.as_str();
+    let pod_sc = pod.spec.as_ref().and_then(|s| s.security_context.as_ref());
+    let sc = container.security_context.as_ref();
+    let caps = sc.and_then(|sc| sc.capabilities.as_ref());
+    let mut violations = Vec::new();
+
+    // baseline
+    if sc.and_then(|sc| sc.privileged).unwrap_or(false) {
+        violations.push(Violation::container(
+            name,
+            Level::Privileged,
+            "privileged: true".to_string(),
+        ));
+    }
+    let added = capability_list(caps, true);
+    for cap in added
+        .iter()
+        .filter(|c| !BASELINE_CAPABILITIES.contains(&c.as_str()))
+    {
+        violations.push(Violation::container(
+            name,
+            Level::Privileged,
+            format!("adds capability {cap}"),
+        ));
+    }
+    for port in container.ports.iter().flatten() {
+        if port.host_port.unwrap_or(0) != 0 {
+            violations.push(Violation::container(
+                name,
+                Level::Privileged,
+                format!("hostPort {}", port.host_port.unwrap_or(0)),
+            ));
+        }
+    }
+    if sc
+        .and_then(|sc| sc.proc_mount.as_deref())
+        .is_some_and(|m| m != "Default")
+    {
+        violations.push(Violation::container(
+            name,
+            Level::Privileged,
+            "procMount is not Default".to_string(),
+        ));
+    }
+    if sc
+        .and_then(|sc| sc.se_linux_options.as_ref())
+        .is_some_and(|se| {
+            !se_linux_allowed(se.type_.as_deref(), se.user.as_deref(), se.role.as_deref())
+        })
+    {
+        violations.push(Violation::container(
+            name,
+            Level::Privileged,
+            "seLinuxOptions not allowed".to_string(),
+        ));
+    }
+    let seccomp = seccomp_type(sc, pod_sc);
+    if seccomp == "Unconfined" {
+        violations.push(Violation::container(
+            name,
+            Level::Privileged,
+            "seccomp Unconfined".to_string(),
+        ));
+    }
+    if apparmor_type(pod, container).eq_ignore_ascii_case("unconfined") {
+        violations.push(Violation::container(
+            name,
+            Level::Privileged,
+            "AppArmor unconfined".to_string(),
+        ));
+    }
+
+    // restricted
+    if sc.and_then(|sc| sc.allow_privilege_escalation) != Some(false) {
+        violations.push(Violation::container(
+            name,
+            Level::Baseline,
+            "allowPrivilegeEscalation is not false".to_string(),
+        ));
+    }
+    if !run_as_non_root(sc, pod_sc) {
+        violations.push(Violation::container(
+            name,
+            Level::Baseline,
+            "runAsNonRoot is not true".to_string(),
+        ));
+    }
+    if run_as_user(sc, pod_sc) == Some(0) {
+        violations.push(Violation::container(
+            name,
+            Level::Baseline,
+            "runAsUser is 0".to_string(),
+        ));
+    }
+    if seccomp != "RuntimeDefault" && seccomp != "Localhost" && seccomp != "Unconfined" {
+        violations.push(Violation::container(
+            name,
+            Level::Baseline,
+            "seccomp profile not set".to_string(),
+        ));
+    }
+    if !capability_list(caps, false).iter().any(|c| c == "ALL") {
+        violations.push(Violation::container(
+            name,
+            Level::Baseline,
+            "capabilities do not drop ALL".to_string(),
+        ));
+    }
+    for cap in added
+        .iter()
+        .filter(|c| c.as_str() != "NET_BIND_SERVICE" && BASELINE_CAPABILITIES.contains(&c.as_str()))
+    {
+        violations.push(Violation::container(
+            name,
+            Level::Baseline,
+            format!("adds capability {cap}"),
+        ));
+    }
+
+    violations
+}
+
+fn all_containers(spec: &PodSpec) -> impl Iterator<Item = &Container> {
+    spec.init_containers
+        .iter()
+        .flatten()
+        .chain(spec.containers.iter())
+}
+
+/// # Returns
+///
+/// all failed checks for the pod and its containers
+#[must_use]
+pub fn violations(pod: &Pod) -> Vec<Violation> {
+    pod.spec.as_ref().map_or_else(Vec::new, |spec| {
+        let mut violations = check_pod_spec(spec);
+        for container in all_containers(spec) {
+            violations.extend(check_container(pod, container));
+        }
+        violations
+    })
+}
+
+/// the strictest Pod Security Standard level the pod satisfies
+#[must_use]
+pub fn level(violations: &[Violation]) -> Level {
+    violations
+        .iter()
+        .map(|v| v.level)
+        .max()
+        .unwrap_or(Level::Restricted)
+}
+
+fn describe_container(pod: &Pod, container: &Container) -> String {
+    let pod_sc = pod.spec.as_ref().and_then(|s| s.security_context.as_ref());
+    let sc = container.security_context.as_ref();
+    let caps = sc.and_then(|sc| sc.capabilities.as_ref());
+    let user = run_as_user(sc, pod_sc).map_or_else(|| "image".to_string(), |u| u.to_string());
+    let seccomp = seccomp_type(sc, pod_sc);
+    let apparmor = apparmor_type(pod, container);
+    format!(
+        "user={user} nonRoot={} privileged={} caps+=[{}] caps-=[{}] seccomp={} apparmor={}",
+        run_as_non_root(sc, pod_sc),
+        sc.and_then(|sc| sc.privileged).unwrap_or(false),
+        capability_list(caps, true).join(","),
+        capability_list(caps, false).join(","),
+        if seccomp.is_empty() { "-" } else { &seccomp },
+        if apparmor.is_empty() { "-" } else { &apparmor },
+    )
+}
+
+/// Summarize the security posture of a pod as name/value rows for the
+/// details panel: the admitted level, each container's context, then every
+/// violation.
+#[must_use]
+pub fn security_details(pod: &Pod) -> Vec<SecurityDetail> {
+    let violations = violations(pod);
+    let mut details = vec![SecurityDetail {
+        name: "PSS level".to_string(),
+        value: level(&violations).to_string(),
+    }];
+
+    if let Some(spec) = &pod.spec {
+        for container in all_containers(spec) {
+            details.push(SecurityDetail {
+                name: container.name.clone(),
+                value: describe_container(pod, container),
+            });
+        }
+    }
+
+    for violation in violations {
+        let target = violation.container.unwrap_or_else(|| "pod".to_string());
+        let failed = match violation.level {
+            Level::Privileged => Level::Baseline,
+            _ => Level::Restricted,
+        };
+        details.push(SecurityDetail {
+            name: format!("{} {target}", symbols().failed),
+            value: format!("{failed}: {}", violation.reason),
+        });
+    }
+
+    details
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::serde_json::{from_value, json};
+
+    fn pod(value: k8s_openapi::serde_json::Value) -> Pod {
+        from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_restricted_pod() {
+        let p = pod(json!({
+            "metadata": {"name": "ok"},
+            "spec": {
+                "securityContext": {"runAsNonRoot": true, "seccompProfile": {"type": "RuntimeDefault"}},
+                "containers": [{
+                    "name": "app",
+                    "securityContext": {
+                        "allowPrivilegeEscalation": false,
+                        "capabilities": {"drop": ["ALL"], "add": ["NET_BIND_SERVICE"]}
+                    }
+                }]
+            }
+        }));
+        let v = violations(&p);
+        assert!(v.is_empty(), "{v:?}");
+        assert_eq!(level(&v), Level::Restricted);
+    }
+
+    #[test]
+    fn test_default_pod_is_baseline() {
+        let p = pod(json!({
+            "metadata": {"name": "default"},
+            "spec": {"containers": [{"name": "app"}]}
+        }));
+        let v = violations(&p);
+        assert_eq!(level(&v), Level::Baseline);
+        assert!(v.iter().all(|v| v.container.as_deref() == Some("app")));
+    }
+
+    #[test]
+    fn test_privileged_pod() {
+        let p = pod(json!({
+            "metadata": {"name": "bad"},
+            "spec": {
+                "hostNetwork": true,
+                "volumes": [{"name": "root", "hostPath": {"path": "/"}}],
+                "containers": [{
+                    "name": "app",
+                    "securityContext": {"privileged": true, "capabilities": {"add": ["SYS_ADMIN"]}}
+                }]
+            }
+        }));
+        let v = violations(&p);
+        assert_eq!(level(&v), Level::Privileged);
+        assert!(v.iter().any(|v| v.reason == "hostNetwork: true"));
+        assert!(v.iter().any(|v| v.reason == "adds capability SYS_ADMIN"));
+        assert!(v.iter().any(|v| v.reason == "hostPath volume root"));
+
+        let details = security_details(&p);
+        assert_eq!(details[0].value, "privileged");
+    }
+}
@@ -0,0 +1,96 @@
+//!Containers injected next to the app's own, ie: istio-proxy, linkerd-proxy
+//!or a log shipper, told apart by name or image.  Container lists, tiled
+//!logs and log searches leave them out until 's' in a container list shows
+//!them again.  `sidecars` in `config.yaml` names more.
+//!
+use crate::config::settings;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// container names meshes, secret stores and shippers inject
+const NAMES: [&str; 18] = [
+    "istio-proxy",
+    "istio-init",
+    "istio-validation",
+    "linkerd-proxy",
+    "linkerd-init",
+    "envoy",
+    "envoy-sidecar",
+    "cloud-sql-proxy",
+    "cloudsql-proxy",
+    "vault-agent",
+    "vault-agent-init",
+    "fluent-bit",
+    "fluentd",
+    "filebeat",
+    "promtail",
+    "vector",
+    "otel-collector",
+    "jaeger-agent",
+];
+
+/// parts of their images, for sidecars given another name
+const IMAGES: [&str; 12] = [
+    "istio/proxyv2",
+    "linkerd/proxy",
+    "envoyproxy/envoy",
+    "cloudsql-proxy",
+    "cloud-sql-proxy",
+    "hashicorp/vault",
+    "fluent-bit",
+    "fluent/fluentd",
+    "beats/filebeat",
+    "grafana/promtail",
+    "timberio/vector",
+    "opentelemetry-collector",
+];
+
+/// set once 's' asked for sidecars to be listed
+static SHOWN: AtomicBool = AtomicBool::new(false);
+
+#[must_use]
+pub fn is_sidecar(name: &str, image: &str) -> bool {
+    NAMES.contains(&name)
+        || settings().sidecars.iter().any(|sidecar| sidecar == name)
+        || IMAGES.iter().any(|part| image.contains(part))
+}
+
+/// whether lists show sidecars, hidden by default
+#[must_use]
+pub fn shown() -> bool {
+    SHOWN.load(Ordering::Relaxed)
+}
+
+pub fn toggle() {
+    SHOWN.fetch_xor(true, Ordering::Relaxed);
+}
+
+/// `items` without the sidecars unless they are shown, and how many were
+/// left out
+pub fn visible<T: Clone>(items: &[T], is_sidecar: impl Fn(&T) -> bool) -> (Vec<T>, usize) {
+    if shown() {
+        return (items.to_vec(), 0);
+    }
+    let kept: Vec<T> = items
+        .iter()
+        .filter(|item| !is_sidecar(item))
+        .cloned()
+        .collect();
+    let hidden = items.len() - kept.len();
+    (kept, hidden)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_sidecar() {
+        assert!(is_sidecar("istio-proxy", "docker.io/istio/proxyv2:1.22.0"));
+        assert!(is_sidecar("mesh", "cr.l5d.io/linkerd/proxy:stable-2.14"));
+        assert!(is_sidecar("logs", "cr.fluentbit.io/fluent/fluent-bit:3.0"));
+        assert!(!is_sidecar("web", "registry.acme.io/web:1.4"));
+
+        let (kept, hidden) = visible(&["web", "istio-proxy"], |name| is_sidecar(name, ""));
+        assert_eq!((kept, hidden), (vec!["web"], 1));
+    }
+}
@@ -0,0 +1,465 @@
+//!The life of one pod in order: creation, scheduling, image pulls,
+//!container starts, probe failures, restarts and deletion, pieced together
+//!from its status, its events and the transitions the pod watch saw.
+//!
+use crate::error::Result;
+use crate::k8s::compare::get_pod;
+use crate::k8s::events::pod_events;
+use crate::k8s::watch::WatchUpdate;
+use crate::tui::theme::symbols;
+use chrono::{DateTime, Utc};
+use k8s_openapi::api::core::v1::{Event, Pod};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+/// pods whose watched transitions are kept, the least recently seen go first
+const MAX_PODS: usize = 1000;
+const MAX_TRANSITIONS: usize = 50;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    Created,
+    Scheduled,
+    Pulling,
+    Pulled,
+    Started,
+    Ready,
+    Phase,
+    ProbeFailed,
+    Restarted,
+    Failed,
+    Deleting,
+    Deleted,
+    Other,
+}
+
+impl Stage {
+    /// stages worth a second look
+    #[must_use]
+    pub const fn is_problem(self) -> bool {
+        matches!(self, Self::ProbeFailed | Self::Restarted | Self::Failed)
+    }
+
+    fn from_reason(reason: &str) -> Self {
+        match reason {
+            "Scheduled" => Self::Scheduled,
+            "Pulling" => Self::Pulling,
+            "Pulled" => Self::Pulled,
+            "Started" => Self::Started,
+            "Unhealthy" => Self::ProbeFailed,
+            "BackOff" | "Failed" | "FailedScheduling" | "FailedMount" | "Evicted"
+            | "OOMKilling" => Self::Failed,
+            "Killing" => Self::Deleting,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Self::Created => "created",
+            Self::Scheduled => "scheduled",
+            Self::Pulling => "pulling",
+            Self::Pulled => "pulled",
+            Self::Started => "started",
+            Self::Ready => "ready",
+            Self::Phase => "phase",
+            Self::ProbeFailed => "probe failed",
+            Self::Restarted => "restarted",
+            Self::Failed => "failed",
+            Self::Deleting => "deleting",
+            Self::Deleted => "deleted",
+            Self::Other => "event",
+        };
+        f.write_str(label)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimelineEntry {
+    pub time: DateTime<Utc>,
+    pub stage: Stage,
+    pub detail: String,
+}
+
+impl TimelineEntry {
+    fn new(time: DateTime<Utc>, stage: Stage, detail: impl Into<String>) -> Self {
+        Self {
+            time,
+            stage,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// what the pod's own status still remembers
+#[must_use]
+pub fn from_pod(pod: &Pod) -> Vec<TimelineEntry> {
+    let mut entries = vec![];
+    if let Some(created) = &pod.metadata.creation_timestamp {
+        entries.push(TimelineEntry::new(created.0, Stage::Created, ""));
+    }
+    let node = pod
+        .spec
+        .as_ref()
+        .and_then(|spec| spec.node_name.clone())
+        .unwrap_or_default();
+    let status = pod.status.as_ref();
+    for condition in status
+        .and_then(|s| s.conditions.as_ref())
+        .into_iter()
+        .flatten()
+    {
+        let Some(time) = &condition.last_transition_time else {
+            continue;
+        };
+        match (condition.type_.as_str(), condition.status.as_str()) {
+            ("PodScheduled", "True") => {
+                entries.push(TimelineEntry::new(
+                    time.0,
+                    Stage::Scheduled,
+                    format!("on {node}"),
+                ));
+            }
+            ("Ready", "True") => entries.push(TimelineEntry::new(time.0, Stage::Ready, "")),
+            _ => {}
+        }
+    }
+    for container in status
+        .and_then(|s| s.container_statuses.as_ref())
+        .into_iter()
+        .flatten()
+    {
+        if let Some(started) = container
+            .state
+            .as_ref()
+            .and_then(|state| state.running.as_ref())
+            .and_then(|running| running.started_at.as_ref())
+        {
+            entries.push(TimelineEntry::new(
+                started.0,
+                Stage::Started,
+                format!("container {}", container.name),
+            ));
+        }
+        if let Some(terminated) = container
+            .last_state
+            .as_ref()
+            .and_then(|state| state.terminated.as_ref())
+        {
+            if let Some(finished) = &terminated.finished_at {
+                entries.push(TimelineEntry::new(
+                    finished.0,
+                    Stage::Restarted,
+                    format!(
+                        "container {} exited {} ({}), restart {}",
+                        container.name,
+                        terminated.exit_code,
+                        terminated.reason.as_deref().unwrap_or("unknown"),
+                        container.restart_count
+                    ),
+                ));
+            }
+        }
+    }
+    if let Some(deleting) = &pod.metadata.deletion_timestamp {
+        entries.push(TimelineEntry::new(deleting.0, Stage::Deleting, ""));
+    }
+    entries
+}
+
+#[must_use]
+pub fn from_event(event: &Event) -> Option<TimelineEntry> {
+    let time = event
+        .last_timestamp
+        .as_ref()
+        .map(|t| t.0)
+        .or_else(|| event.event_time.as_ref().map(|t| t.0))
+        .or_else(|| event.first_timestamp.as_ref().map(|t| t.0))?;
+    let reason = event.reason.as_deref().unwrap_or_default();
+    let count = event.count.filter(|count| *count > 1);
+    Some(TimelineEntry::new(
+        time,
+        Stage::from_reason(reason),
+        format!(
+            "{reason}: {}{}",
+            event.message.as_deref().unwrap_or_default().trim(),
+            count.map_or_else(String::new, |count| format!(" (x{count})"))
+        ),
+    ))
+}
+
+/// the parts of a pod whose changes are worth a timeline entry
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Observed {
+    phase: String,
+    ready: bool,
+    restarts: i32,
+}
+
+impl Observed {
+    fn of(pod: &Pod) -> Self {
+        let status = pod.status.as_ref();
+        Self {
+            phase: status.and_then(|s| s.phase.clone()).unwrap_or_default(),
+            ready: status
+                .and_then(|s| s.conditions.as_ref())
+                .into_iter()
+                .flatten()
+                .any(|c| c.type_ == "Ready" && c.status == "True"),
+            restarts: status
+                .and_then(|s| s.container_statuses.as_ref())
+                .into_iter()
+                .flatten()
+                .map(|c| c.restart_count)
+                .sum(),
+        }
+    }
+
+    /// entries for what changed since `self`
+    fn changes(&self, next: &Self, at: DateTime<Utc>) -> Vec<TimelineEntry> {
+        let mut entries = vec![];
+        if self.phase != next.phase {
+            entries.push(TimelineEntry::new(
+                at,
+                Stage::Phase,
+                format!("{} {} {}", self.phase, symbols().arrow, next.phase),
+            ));
+        }
+        if self.ready != next.ready {
+            let detail = if next.ready { "ready" } else { "not ready" };
+            entries.push(TimelineEntry::new(at, Stage::Ready, detail));
+        }
+        if next.restarts > self.restarts {
+            entries.push(TimelineEntry::new(
+                at,
+                Stage::Restarted,
+                format!(
+                    "restart count {} {} {}",
+                    self.restarts,
+                    symbols().arrow,
+                    next.restarts
+                ),
+            ));
+        }
+        entries
+    }
+}
+
+#[derive(Debug, Default)]
+struct Watched {
+    last: Observed,
+    last_seen: DateTime<Utc>,
+    transitions: Vec<TimelineEntry>,
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    /// keyed by namespace/name
+    pods: HashMap<String, Watched>,
+}
+
+impl Registry {
+    fn record(&mut self, key: String, observed: Option<Observed>, at: DateTime<Utc>) {
+        if !self.pods.contains_key(&key) && self.pods.len() >= MAX_PODS {
+            let oldest = self
+                .pods
+                .iter()
+                .min_by_key(|(_, watched)| watched.last_seen)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.pods.remove(&oldest);
+            }
+        }
+        let first_seen = !self.pods.contains_key(&key);
+        let watched = self.pods.entry(key).or_default();
+        let new = match observed {
+            // the first sighting is the baseline, the pod status covers before it
+            Some(observed) if first_seen => {
+                watched.last = observed;
+                vec![]
+            }
+            Some(observed) => {
+                let changes = watched.last.changes(&observed, at);
+                watched.last = observed;
+                changes
+            }
+            None => vec![TimelineEntry::new(at, Stage::Deleted, "gone from the api")],
+        };
+        watched.last_seen = at;
+        watched.transitions.extend(new);
+        let excess = watched.transitions.len().saturating_sub(MAX_TRANSITIONS);
+        watched.transitions.drain(..excess);
+    }
+}
+
+static REGISTRY: Mutex<Option<Registry>> = Mutex::new(None);
+
+fn with_registry<T>(f: impl FnOnce(&mut Registry) -> T) -> Option<T> {
+    let mut registry = REGISTRY.lock().ok()?;
+    Some(f(registry.get_or_insert_with(Registry::default)))
+}
+
+fn pod_key(pod: &Pod) -> String {
+    format!(
+        "{}/{}",
+        pod.metadata.namespace.as_deref().unwrap_or_default(),
+        pod.metadata.name.as_deref().unwrap_or_default()
+    )
+}
+
+/// Feed pod watch updates in, ie: the transitions between two polls.
+pub fn record(update: &WatchUpdate<Pod>) {
+    let now = Utc::now();
+    match update {
+        WatchUpdate::Applied(pod) => {
+            with_registry(|registry| registry.record(pod_key(pod), Some(Observed::of(pod)), now));
+        }
+        WatchUpdate::Deleted(pod) => {
+            with_registry(|registry| registry.record(pod_key(pod), None, now));
+        }
+        WatchUpdate::Resynced(_) => {}
+    }
+}
+
+/// Everything known about a pod, oldest first.  Entries of one stage at the
+/// same second, eg: the scheduled condition and the Scheduled event, are
+/// shown once, preferring the event's wording.
+#[must_use]
+pub fn merge(
+    pod: Option<&Pod>,
+    events: &[Event],
+    watched: Vec<TimelineEntry>,
+) -> Vec<TimelineEntry> {
+    let mut entries: Vec<TimelineEntry> = events.iter().filter_map(from_event).collect();
+    entries.extend(pod.map(from_pod).unwrap_or_default());
+    entries.extend(watched);
+    entries.sort_by_key(|entry| entry.time);
+    let mut merged: Vec<TimelineEntry> = vec![];
+    for entry in entries {
+        let duplicate = merged
+            .iter()
+            .rev()
+            .take_while(|e| e.time == entry.time)
+            .any(|e| e.stage == entry.stage);
+        if !duplicate {
+            merged.push(entry);
+        }
+    }
+    merged
+}
+
+/// # Errors
+///
+/// Will return `Err` if the pod's events cannot be listed
+pub async fn pod_timeline(namespace: &str, name: &str) -> Result<Vec<TimelineEntry>> {
+    // a deleted pod still has its events and watched transitions
+    let pod = get_pod(&format!("{namespace}/{name}")).await.ok();
+    let events = pod_events(Some(namespace), name).await?;
+    let key = format!("{namespace}/{name}");
+    let watched = with_registry(|registry| {
+        registry
+            .pods
+            .get(&key)
+            .map(|watched| watched.transitions.clone())
+            .unwrap_or_default()
+    })
+    .unwrap_or_default();
+    Ok(merge(pod.as_ref(), &events, watched))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::serde_json::{from_value, json};
+
+    fn pod(phase: &str, restarts: i32) -> Pod {
+        from_value(json!({
+            "metadata": {
+                "name": "web-1",
+                "namespace": "default",
+                "creationTimestamp": "2024-03-01T10:00:00Z"
+            },
+            "spec": {"nodeName": "node-1", "containers": []},
+            "status": {
+                "phase": phase,
+                "conditions": [
+                    {"type": "PodScheduled", "status": "True", "lastTransitionTime": "2024-03-01T10:00:01Z"},
+                    {"type": "Ready", "status": "True", "lastTransitionTime": "2024-03-01T10:00:09Z"}
+                ],
+                "containerStatuses": [{
+                    "name": "web", "image": "web", "imageID": "", "ready": true, "restartCount": restarts,
+                    "state": {"running": {"startedAt": "2024-03-01T10:00:08Z"}},
+                    "lastState": {"terminated": {"exitCode": 137, "reason": "OOMKilled", "finishedAt": "2024-03-01T10:00:07Z"}}
+                }]
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_merge_timeline() {
+        let events: Vec<Event> = vec![
+            from_value(json!({
+                "metadata": {"name": "e1"},
+                "involvedObject": {},
+                "reason": "Scheduled",
+                "message": "Successfully assigned default/web-1 to node-1",
+                "lastTimestamp": "2024-03-01T10:00:01Z"
+            }))
+            .unwrap(),
+            from_value(json!({
+                "metadata": {"name": "e2"},
+                "involvedObject": {},
+                "reason": "Unhealthy",
+                "message": "Readiness probe failed",
+                "count": 3,
+                "lastTimestamp": "2024-03-01T10:00:05Z"
+            }))
+            .unwrap(),
+        ];
+        let web = pod("Running", 1);
+        let timeline = merge(Some(&web), &events, vec![]);
+        assert!(timeline[1]
+            .detail
+            .starts_with("Scheduled: Successfully assigned"));
+        let stages: Vec<Stage> = timeline.iter().map(|entry| entry.stage).collect();
+        assert_eq!(
+            stages,
+            vec![
+                Stage::Created,
+                Stage::Scheduled,
+                Stage::ProbeFailed,
+                Stage::Restarted,
+                Stage::Started,
+                Stage::Ready,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_record_transitions() {
+        let mut registry = Registry::default();
+        let at = Utc::now();
+        let key = "default/web-1".to_string();
+        registry.record(key.clone(), Some(Observed::of(&pod("Pending", 0))), at);
+        registry.record(key.clone(), Some(Observed::of(&pod("Running", 0))), at);
+        registry.record(key.clone(), Some(Observed::of(&pod("Running", 2))), at);
+        registry.record(key.clone(), None, at);
+        let details: Vec<String> = registry.pods[&key]
+            .transitions
+            .iter()
+            .map(|entry| entry.detail.clone())
+            .collect();
+        assert_eq!(
+            details,
+            vec![
+                "Pending → Running",
+                "restart count 0 → 2",
+                "gone from the api"
+            ]
+        );
+    }
+}
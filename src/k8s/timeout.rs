@@ -0,0 +1,172 @@
+//!Bound every api call in time so a slow or wedged api server shows up as
+//!a timeout instead of a view that never updates.  List calls, every page
+//!of them, and fetches can additionally be hedged: if the first attempt is
+//!slow a second one is started on another connection and whichever
+//!finishes first wins.  Hedged fetches are
+//!retried as a whole, outside the timeout, so a retry gets a full request
+//!timeout of its own instead of nesting inside one.  Whether a hedged fetch
+//!counts as blocking the UI is up to its caller.
+//!
+use crate::error::{Error, Result};
+use crate::k8s::retry::{with_retry, RetryPolicy};
+use crate::perf;
+use futures::future::{select, Either};
+use std::future::Future;
+use std::pin::pin;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// how long a timeout stays visible in the UI
+const NOTICE_TTL: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, Debug)]
+struct Settings {
+    request_timeout: Duration,
+    hedge_after: Option<Duration>,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+static LAST_TIMEOUT: Mutex<Option<(String, Instant)>> = Mutex::new(None);
+
+/// Set the timeouts once at startup, later calls are ignored.  A `hedge_after`
+/// of `None` disables hedging.
+pub fn configure(request_timeout: Duration, hedge_after: Option<Duration>) {
+    let _ = SETTINGS.set(Settings {
+        request_timeout,
+        hedge_after,
+    });
+}
+
+fn settings() -> Settings {
+    SETTINGS.get().copied().unwrap_or(Settings {
+        request_timeout: DEFAULT_REQUEST_TIMEOUT,
+        hedge_after: None,
+    })
+}
+
+#[must_use]
+pub fn request_timeout() -> Duration {
+    settings().request_timeout
+}
+
+fn timed_out(operation: &str, after: Duration) -> Error {
+    debug!("{operation} timed out after {after:?}");
+    if let Ok(mut last) = LAST_TIMEOUT.lock() {
+        *last = Some((operation.to_string(), Instant::now()));
+    }
+    Error::Timeout {
+        operation: operation.to_string(),
+        after,
+    }
+}
+
+/// the most recent timeout if it happened recently enough to still matter
+#[must_use]
+pub fn recent_timeout() -> Option<String> {
+    let last = LAST_TIMEOUT.lock().ok()?;
+    last.as_ref()
+        .filter(|(_, at)| at.elapsed() < NOTICE_TTL)
+        .map(|(operation, _)| operation.clone())
+}
+
+/// Run one api call under the configured request timeout.
+///
+/// # Errors
+///
+/// Will return `Err` if the call fails or does not finish in time
+pub async fn with_timeout<T, E, F>(operation: &str, fut: F) -> Result<T>
+where
+    F: Future<Output = core::result::Result<T, E>>,
+    Error: From<E>,
+{
+    with_timeout_after(operation, request_timeout(), fut).await
+}
+
+async fn with_timeout_after<T, E, F>(operation: &str, after: Duration, fut: F) -> Result<T>
+where
+    F: Future<Output = core::result::Result<T, E>>,
+    Error: From<E>,
+{
+    let started = Instant::now();
+    let result = tokio::time::timeout(after, fut).await;
+    perf::record_fetch(operation, started.elapsed());
+    match result {
+        Ok(result) => result.map_err(Error::from),
+        Err(_) => Err(timed_out(operation, after)),
+    }
+}
+
+/// Run a fetch, starting a second attempt if the first has not finished
+/// after the hedge delay.  A caller the UI waits on holds
+/// `InFlight::blocking` around it, the background fetcher its own guard.  Fetchers take their client round-robin
+/// from the client manager, so the second attempt goes out on another
+/// connection instead of queueing behind the first.  `make` should not
+/// retry itself, the hedged pair is retried by `with_retry`.
+///
+/// # Errors
+///
+/// Will return `Err` if every attempt fails or the request timeout passes
+pub async fn hedged<T, F, Fut>(operation: &str, make: F) -> Result<T>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    with_retry(&RetryPolicy::default(), || hedge(operation, &make)).await
+}
+
+/// One try of `hedged`, bounded by the request timeout, for a single api
+/// call that is retried around it, ie: a page of a list.
+///
+/// # Errors
+///
+/// Will return `Err` if both attempts fail or the request timeout passes
+pub async fn hedge<T, F, Fut>(operation: &str, make: F) -> Result<T>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let settings = settings();
+    let Some(hedge_after) = settings.hedge_after else {
+        return with_timeout(operation, make()).await;
+    };
+
+    let attempts = async {
+        let mut first = pin!(make());
+        match select(first.as_mut(), pin!(tokio::time::sleep(hedge_after))).await {
+            Either::Left((result, _)) => result,
+            Either::Right(((), _)) => {
+                debug!("{operation} slower than {hedge_after:?}, hedging");
+                match select(first, pin!(make())).await {
+                    Either::Left((Ok(v), _)) | Either::Right((Ok(v), _)) => Ok(v),
+                    Either::Left((Err(_), second)) => second.await,
+                    Either::Right((Err(_), first)) => first.await,
+                }
+            }
+        }
+    };
+
+    match tokio::time::timeout(settings.request_timeout, attempts).await {
+        Ok(result) => result,
+        Err(_) => Err(timed_out(operation, settings.request_timeout)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_with_timeout_reports_operation() {
+        let slow = async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok::<(), Error>(())
+        };
+        let result = with_timeout_after("list pods", Duration::from_millis(10), slow).await;
+        assert!(
+            matches!(result, Err(Error::Timeout { ref operation, .. }) if operation == "list pods")
+        );
+        assert_eq!(recent_timeout().as_deref(), Some("list pods"));
+    }
+}
@@ -0,0 +1,381 @@
+//!Long running watches for pods, replicasets and events.  Each watch keeps
+//!the last resourceVersion it saw so a dropped connection resumes where it
+//!left off instead of replaying the whole namespace, and a 410 Gone (the
+//!version has been compacted away) triggers a relist that is announced to
+//!the consumer as a resync.  Reconnects back off on their own, doubling
+//!while the api server keeps refusing, and are counted in the
+//!`WatchCounters` of the watch so each namespace can be told apart.
+//!
+use crate::error::Result;
+use crate::k8s::paging::Pager;
+use crate::k8s::timeout::with_timeout;
+use crate::perf;
+use futures::StreamExt;
+use k8s_openapi::api::apps::v1::ReplicaSet;
+use k8s_openapi::api::core::v1::{Event, Pod};
+use k8s_openapi::serde::de::DeserializeOwned;
+use k8s_openapi::NamespaceResourceScope;
+use kube::api::{ListParams, WatchEvent, WatchParams};
+use kube::{Api, Client, Resource};
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::Sender;
+use tracing::debug;
+
+/// api server closes watches after this many seconds, we just reconnect
+const WATCH_TIMEOUT_SECS: u32 = 290;
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+/// reconnects back off up to this
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+const GONE: u16 = 410;
+
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
+static STREAMING: AtomicUsize = AtomicUsize::new(0);
+
+/// how many watches run and how many of those have a live stream, the
+/// others are relisting or waiting to reconnect
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WatchHealth {
+    pub running: usize,
+    pub streaming: usize,
+}
+
+#[must_use]
+pub fn health() -> WatchHealth {
+    WatchHealth {
+        running: RUNNING.load(Ordering::Relaxed),
+        streaming: STREAMING.load(Ordering::Relaxed),
+    }
+}
+
+/// What the watches sharing them went through, ie: the pod and replicaset
+/// watches of one namespace.
+#[derive(Debug, Default)]
+pub struct WatchCounters {
+    running: AtomicUsize,
+    streaming: AtomicUsize,
+    restarts: AtomicU64,
+    updates: AtomicU64,
+    /// the reconnect delay of the last watch that had to wait
+    backoff_ms: AtomicU64,
+}
+
+/// `WatchCounters` at one point in time
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WatchStats {
+    pub health: WatchHealth,
+    pub restarts: u64,
+    pub updates: u64,
+    pub backoff: Duration,
+}
+
+impl WatchCounters {
+    #[must_use]
+    pub fn stats(&self) -> WatchStats {
+        WatchStats {
+            health: WatchHealth {
+                running: self.running.load(Ordering::Relaxed),
+                streaming: self.streaming.load(Ordering::Relaxed),
+            },
+            restarts: self.restarts.load(Ordering::Relaxed),
+            updates: self.updates.load(Ordering::Relaxed),
+            backoff: Duration::from_millis(self.backoff_ms.load(Ordering::Relaxed)),
+        }
+    }
+
+    fn restarted(&self) {
+        self.restarts.fetch_add(1, Ordering::Relaxed);
+        perf::record_watch_restart();
+    }
+}
+
+/// Reconnect delay of one watch, doubling up to `MAX_RECONNECT_DELAY` and
+/// back to `RECONNECT_DELAY` once a stream delivers again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Backoff {
+    delay: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            delay: RECONNECT_DELAY,
+        }
+    }
+}
+
+impl Backoff {
+    /// the delay to wait now, the next one is longer
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.delay;
+        self.delay = (self.delay * 2).min(MAX_RECONNECT_DELAY);
+        delay
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    async fn wait(&mut self, counters: &WatchCounters) {
+        let delay = self.next_delay();
+        #[allow(clippy::cast_possible_truncation)]
+        counters
+            .backoff_ms
+            .store(delay.as_millis() as u64, Ordering::Relaxed);
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// counts itself in `counters` while alive
+struct Counted<'a>(&'a AtomicUsize);
+
+impl<'a> Counted<'a> {
+    fn new(counter: &'a AtomicUsize) -> Self {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Self(counter)
+    }
+}
+
+impl Drop for Counted<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum WatchUpdate<K> {
+    Applied(K),
+    Deleted(K),
+    /// the watch lost its place and relisted, the items are the complete
+    /// current state and anything cached before should be replaced
+    Resynced(Vec<K>),
+}
+
+/// What the watch loop should do after handling an event
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Resume {
+    Continue,
+    Relist,
+}
+
+/// Tracks the resourceVersion a watch can resume from.
+#[derive(Clone, Debug, Default)]
+pub struct ResumeState {
+    resource_version: Option<String>,
+}
+
+impl ResumeState {
+    #[must_use]
+    pub fn resource_version(&self) -> Option<&str> {
+        self.resource_version.as_deref()
+    }
+
+    pub fn listed(&mut self, resource_version: Option<String>) {
+        self.resource_version = resource_version;
+    }
+
+    pub fn observe<K: Resource>(&mut self, event: &WatchEvent<K>) -> Resume {
+        match event {
+            WatchEvent::Added(obj) | WatchEvent::Modified(obj) | WatchEvent::Deleted(obj) => {
+                if let Some(rv) = obj.meta().resource_version.clone() {
+                    self.resource_version = Some(rv);
+                }
+                Resume::Continue
+            }
+            WatchEvent::Bookmark(bookmark) => {
+                self.resource_version = Some(bookmark.metadata.resource_version.clone());
+                Resume::Continue
+            }
+            WatchEvent::Error(e) if e.code == GONE => {
+                self.resource_version = None;
+                Resume::Relist
+            }
+            WatchEvent::Error(_) => Resume::Continue,
+        }
+    }
+}
+
+async fn relist<K>(
+    api: &Api<K>,
+    state: &mut ResumeState,
+    tx: &Sender<WatchUpdate<K>>,
+) -> Result<bool>
+where
+    K: Resource + Clone + DeserializeOwned + Debug,
+{
+    let mut pager = Pager::new(api, ListParams::default(), "list for watch");
+    let mut items = vec![];
+    while let Some(page) = pager.next_page().await? {
+        if pager.take_restart() {
+            items.clear();
+        }
+        items.extend(page);
+    }
+    state.listed(pager.resource_version().map(ToString::to_string));
+    Ok(tx.send(WatchUpdate::Resynced(items)).await.is_ok())
+}
+
+/// Watch one resource type until the receiver goes away, counted in
+/// `counters`.
+///
+/// # Errors
+///
+/// Will return `Err` if the initial list can not be retrieved from k8s cluster api
+pub async fn watch_resource<K>(
+    api: Api<K>,
+    tx: Sender<WatchUpdate<K>>,
+    counters: Arc<WatchCounters>,
+) -> Result<()>
+where
+    K: Resource + Clone + DeserializeOwned + Debug + Send + 'static,
+{
+    let _running = (Counted::new(&RUNNING), Counted::new(&counters.running));
+    let mut state = ResumeState::default();
+    let mut backoff = Backoff::default();
+    if !relist(&api, &mut state, &tx).await? {
+        return Ok(());
+    }
+
+    loop {
+        let Some(version) = state.resource_version().map(ToString::to_string) else {
+            match relist(&api, &mut state, &tx).await {
+                Ok(true) => continue,
+                Ok(false) => return Ok(()),
+                Err(e) => {
+                    debug!("relist failed: {e}");
+                    backoff.wait(&counters).await;
+                    continue;
+                }
+            }
+        };
+
+        let wp = WatchParams::default().timeout(WATCH_TIMEOUT_SECS);
+        let stream = match with_timeout("start watch", api.watch(&wp, &version)).await {
+            Ok(stream) => stream,
+            Err(e) => {
+                debug!("watch from {version} failed: {e}");
+                counters.restarted();
+                backoff.wait(&counters).await;
+                continue;
+            }
+        };
+        let mut stream = stream.boxed();
+        let _streaming = (Counted::new(&STREAMING), Counted::new(&counters.streaming));
+
+        while let Some(event) = stream.next().await {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    debug!("watch stream error: {e}");
+                    break;
+                }
+            };
+            if state.observe(&event) == Resume::Relist {
+                debug!("resourceVersion {version} expired, relisting");
+                break;
+            }
+            let update = match event {
+                WatchEvent::Added(obj) | WatchEvent::Modified(obj) => WatchUpdate::Applied(obj),
+                WatchEvent::Deleted(obj) => WatchUpdate::Deleted(obj),
+                WatchEvent::Bookmark(_) | WatchEvent::Error(_) => continue,
+            };
+            backoff.reset();
+            counters.updates.fetch_add(1, Ordering::Relaxed);
+            if tx.send(update).await.is_err() {
+                return Ok(());
+            }
+        }
+        counters.restarted();
+    }
+}
+
+fn namespaced<K>(client: Client, namespace: Option<&str>) -> Api<K>
+where
+    K: Resource<Scope = NamespaceResourceScope>,
+    K::DynamicType: Default,
+{
+    namespace.map_or_else(
+        || Api::default_namespaced(client.clone()),
+        |ns| Api::namespaced(client.clone(), ns),
+    )
+}
+
+/// # Errors
+///
+/// Will return `Err` if data can not be retrieved from k8s cluster api
+pub async fn watch_pods(
+    client: Client,
+    namespace: Option<&str>,
+    tx: Sender<WatchUpdate<Pod>>,
+    counters: Arc<WatchCounters>,
+) -> Result<()> {
+    watch_resource(namespaced(client, namespace), tx, counters).await
+}
+
+/// # Errors
+///
+/// Will return `Err` if data can not be retrieved from k8s cluster api
+pub async fn watch_replicasets(
+    client: Client,
+    namespace: Option<&str>,
+    tx: Sender<WatchUpdate<ReplicaSet>>,
+    counters: Arc<WatchCounters>,
+) -> Result<()> {
+    watch_resource(namespaced(client, namespace), tx, counters).await
+}
+
+/// # Errors
+///
+/// Will return `Err` if data can not be retrieved from k8s cluster api
+pub async fn watch_events(
+    client: Client,
+    namespace: Option<&str>,
+    tx: Sender<WatchUpdate<Event>>,
+    counters: Arc<WatchCounters>,
+) -> Result<()> {
+    watch_resource(namespaced(client, namespace), tx, counters).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::serde_json::{from_value, json};
+
+    fn pod(rv: &str) -> Pod {
+        from_value(json!({"metadata": {"name": "p", "resourceVersion": rv}})).unwrap()
+    }
+
+    #[test]
+    fn test_resume_tracks_last_version_and_relists_on_gone() {
+        let mut state = ResumeState::default();
+        state.listed(Some("10".to_string()));
+        assert_eq!(
+            state.observe(&WatchEvent::Added(pod("11"))),
+            Resume::Continue
+        );
+        assert_eq!(
+            state.observe(&WatchEvent::Modified(pod("12"))),
+            Resume::Continue
+        );
+        assert_eq!(state.resource_version(), Some("12"));
+
+        let gone: WatchEvent<Pod> = from_value(json!({
+            "type": "ERROR",
+            "object": {"status": "Failure", "message": "too old", "reason": "Expired", "code": 410}
+        }))
+        .unwrap();
+        assert_eq!(state.observe(&gone), Resume::Relist);
+        assert_eq!(state.resource_version(), None);
+    }
+
+    #[test]
+    fn test_backoff_doubles_and_resets() {
+        let mut backoff = Backoff::default();
+        let delays: Vec<u64> = (0..7).map(|_| backoff.next_delay().as_secs()).collect();
+        assert_eq!(delays, [2, 4, 8, 16, 32, 60, 60]);
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), RECONNECT_DELAY);
+    }
+}
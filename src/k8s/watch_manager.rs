@@ -0,0 +1,211 @@
+//!The watches navipod keeps running, one set per namespace.  A set is the
+//!pod watch feeding problems, restarts, audit, timeline and hooks, the
+//!replicaset watch feeding the journal and the ingress, service and
+//!endpointslice watches refreshing the cached ingress rows.  Each set has
+//!its own backoff and counters, so a namespace the api server refuses does
+//!not hold up the others.  Namespaces are added and removed while running,
+//!ie: for all namespaces or a list of favorites.
+//!
+use crate::k8s::cache::invalidation;
+use crate::k8s::client_manager::get_client;
+use crate::k8s::watch::{watch_resource, WatchCounters, WatchStats, WatchUpdate};
+use crate::k8s::{journal, problems};
+use crate::shutdown;
+use k8s_openapi::api::apps::v1::ReplicaSet;
+use k8s_openapi::api::core::v1::{Pod, Service};
+use k8s_openapi::api::discovery::v1::EndpointSlice;
+use k8s_openapi::api::networking::v1::Ingress;
+use k8s_openapi::serde::de::DeserializeOwned;
+use k8s_openapi::NamespaceResourceScope;
+use kube::{Api, Resource};
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+/// starts the watches of one namespace, counted in the counters given
+pub type SpawnWatches = dyn Fn(&str, Arc<WatchCounters>) -> Vec<JoinHandle<()>> + Send + Sync;
+
+/// the watches of one namespace, stopped when dropped
+struct WatchSet {
+    counters: Arc<WatchCounters>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Drop for WatchSet {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+pub struct WatchManager {
+    spawn: Box<SpawnWatches>,
+    sets: Mutex<BTreeMap<String, WatchSet>>,
+}
+
+impl Debug for WatchManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WatchManager")
+            .field("namespaces", &self.namespaces())
+            .finish_non_exhaustive()
+    }
+}
+
+impl WatchManager {
+    #[must_use]
+    pub fn new(spawn: Box<SpawnWatches>) -> Self {
+        Self {
+            spawn,
+            sets: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Start watching `namespace`, `false` when it is watched already.
+    pub fn add(&self, namespace: &str) -> bool {
+        let Ok(mut sets) = self.sets.lock() else {
+            return false;
+        };
+        if sets.contains_key(namespace) {
+            return false;
+        }
+        let counters = Arc::new(WatchCounters::default());
+        let tasks = (self.spawn)(namespace, counters.clone());
+        sets.insert(namespace.to_string(), WatchSet { counters, tasks });
+        true
+    }
+
+    /// Stop the watches of `namespace`, `false` when it was not watched.
+    pub fn remove(&self, namespace: &str) -> bool {
+        self.sets
+            .lock()
+            .is_ok_and(|mut sets| sets.remove(namespace).is_some())
+    }
+
+    /// Watch exactly `namespaces`, stopping the others.
+    pub fn set(&self, namespaces: &[String]) {
+        for watched in self.namespaces() {
+            if !namespaces.contains(&watched) {
+                self.remove(&watched);
+            }
+        }
+        for namespace in namespaces {
+            self.add(namespace);
+        }
+    }
+
+    #[must_use]
+    pub fn namespaces(&self) -> Vec<String> {
+        self.sets
+            .lock()
+            .map(|sets| sets.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// the counters of every watched namespace, by name
+    #[must_use]
+    pub fn stats(&self) -> Vec<(String, WatchStats)> {
+        self.sets
+            .lock()
+            .map(|sets| {
+                sets.iter()
+                    .map(|(namespace, set)| (namespace.clone(), set.counters.stats()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// one watch of `K` in `namespace` and the task handing its updates to
+/// `on_update`
+fn spawn_watch<K>(
+    namespace: &str,
+    counters: Arc<WatchCounters>,
+    on_update: fn(&WatchUpdate<K>),
+) -> [JoinHandle<()>; 2]
+where
+    K: Resource<Scope = NamespaceResourceScope, DynamicType = ()>
+        + Clone
+        + DeserializeOwned
+        + Debug
+        + Send
+        + Sync
+        + 'static,
+{
+    let (tx, mut rx) = mpsc::channel(100);
+    let namespace = namespace.to_string();
+    let watch = tokio::spawn(async move {
+        let result = match get_client().await {
+            Ok(client) => tokio::select! {
+                result = watch_resource(Api::<K>::namespaced(client, &namespace), tx, counters) => result,
+                () = shutdown::token().cancelled() => Ok(()),
+            },
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            debug!("{} watch of {namespace} stopped: {e}", K::kind(&()));
+        }
+    });
+    let consume = tokio::spawn(async move {
+        while let Some(update) = rx.recv().await {
+            on_update(&update);
+        }
+    });
+    [watch, consume]
+}
+
+/// the watches of `namespace`
+fn spawn_namespace(namespace: &str, counters: Arc<WatchCounters>) -> Vec<JoinHandle<()>> {
+    let mut tasks = vec![];
+    tasks.extend(spawn_watch::<Pod>(
+        namespace,
+        counters.clone(),
+        problems::on_pod_update,
+    ));
+    tasks.extend(spawn_watch::<ReplicaSet>(
+        namespace,
+        counters.clone(),
+        |update| journal::record("ReplicaSet", update),
+    ));
+    tasks.extend(spawn_watch::<Ingress>(namespace, counters.clone(), |_| {
+        invalidation::changed("Ingress");
+    }));
+    tasks.extend(spawn_watch::<Service>(namespace, counters.clone(), |_| {
+        invalidation::changed("Service");
+    }));
+    tasks.extend(spawn_watch::<EndpointSlice>(namespace, counters, |_| {
+        invalidation::changed("EndpointSlice");
+    }));
+    tasks
+}
+
+/// the watches of the TUI, no namespace is watched until one is added
+pub fn manager() -> &'static WatchManager {
+    static MANAGER: OnceLock<WatchManager> = OnceLock::new();
+    MANAGER.get_or_init(|| WatchManager::new(Box::new(spawn_namespace)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::pending;
+
+    #[tokio::test]
+    async fn test_namespaces_come_and_go() {
+        let manager = WatchManager::new(Box::new(|_, _| vec![tokio::spawn(pending())]));
+        assert!(manager.add("shop"));
+        assert!(!manager.add("shop"));
+        assert!(manager.add("payments"));
+        assert_eq!(manager.namespaces(), ["payments", "shop"]);
+        assert_eq!(manager.stats()[0].1, WatchStats::default());
+
+        manager.set(&["shop".to_string(), "search".to_string()]);
+        assert_eq!(manager.namespaces(), ["search", "shop"]);
+        assert!(manager.remove("shop"));
+        assert!(!manager.remove("shop"));
+        assert_eq!(manager.namespaces(), ["search"]);
+    }
+}
@@ -0,0 +1,159 @@
+//!The namespace's Deployments and StatefulSets as rows of their top-level
+//!views.  A Deployment leads on to the ReplicaSets it owns, a StatefulSet
+//!has no ReplicaSets and leads straight to its pods.
+//!
+use crate::error::Result;
+use crate::k8s::client_manager::get_client;
+use crate::k8s::events::format_duration;
+use crate::k8s::paging::Pager;
+use crate::k8s::retry::{with_retry, RetryPolicy};
+use crate::tui::data::{DeploymentRow, StatefulSetRow};
+use chrono::Utc;
+use k8s_openapi::api::apps::v1::{Deployment, StatefulSet};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{LabelSelector, ObjectMeta};
+use kube::api::ListParams;
+use kube::Api;
+
+fn age(metadata: &ObjectMeta) -> String {
+    metadata.creation_timestamp.as_ref().map_or_else(
+        || "Unk".to_string(),
+        |created| format_duration(Utc::now().signed_duration_since(created.0)),
+    )
+}
+
+/// the pods a workload selects, `None` when it selects by expressions only
+fn selectors(selector: &LabelSelector) -> Option<std::collections::BTreeMap<String, String>> {
+    selector
+        .match_labels
+        .clone()
+        .filter(|labels| !labels.is_empty())
+}
+
+#[must_use]
+pub fn deployment_row(deployment: &Deployment) -> DeploymentRow {
+    let spec = deployment.spec.as_ref();
+    let status = deployment.status.as_ref();
+    let desired = spec.and_then(|spec| spec.replicas).unwrap_or(1);
+    let ready = status.and_then(|status| status.ready_replicas).unwrap_or(0);
+    DeploymentRow {
+        name: deployment.metadata.name.clone().unwrap_or_default(),
+        ready: format!("{ready}/{desired}"),
+        up_to_date: status
+            .and_then(|status| status.updated_replicas)
+            .unwrap_or(0)
+            .to_string(),
+        available: status
+            .and_then(|status| status.available_replicas)
+            .unwrap_or(0)
+            .to_string(),
+        age: age(&deployment.metadata),
+        strategy: spec
+            .and_then(|spec| spec.strategy.as_ref())
+            .and_then(|strategy| strategy.type_.clone())
+            .unwrap_or_else(|| "RollingUpdate".to_string()),
+        selectors: spec.and_then(|spec| selectors(&spec.selector)),
+    }
+}
+
+#[must_use]
+pub fn statefulset_row(statefulset: &StatefulSet) -> StatefulSetRow {
+    let spec = statefulset.spec.as_ref();
+    let status = statefulset.status.as_ref();
+    let desired = spec.and_then(|spec| spec.replicas).unwrap_or(1);
+    let ready = status.and_then(|status| status.ready_replicas).unwrap_or(0);
+    StatefulSetRow {
+        name: statefulset.metadata.name.clone().unwrap_or_default(),
+        ready: format!("{ready}/{desired}"),
+        age: age(&statefulset.metadata),
+        service: spec
+            .map(|spec| spec.service_name.clone())
+            .unwrap_or_default(),
+        update_strategy: spec
+            .and_then(|spec| spec.update_strategy.as_ref())
+            .and_then(|strategy| strategy.type_.clone())
+            .unwrap_or_else(|| "RollingUpdate".to_string()),
+        selectors: spec.and_then(|spec| selectors(&spec.selector)),
+    }
+}
+
+/// # Errors
+///
+/// Will return `Err` if data can not be retrieved from k8s cluster api
+pub async fn list_deployments() -> Result<Vec<DeploymentRow>> {
+    with_retry(&RetryPolicy::default(), || async {
+        let deployments: Api<Deployment> = Api::default_namespaced(get_client().await?);
+        let items = Pager::new(&deployments, ListParams::default(), "list deployments")
+            .collect()
+            .await?;
+        Ok(items.iter().map(deployment_row).collect())
+    })
+    .await
+}
+
+/// # Errors
+///
+/// Will return `Err` if data can not be retrieved from k8s cluster api
+pub async fn list_statefulsets() -> Result<Vec<StatefulSetRow>> {
+    with_retry(&RetryPolicy::default(), || async {
+        let statefulsets: Api<StatefulSet> = Api::default_namespaced(get_client().await?);
+        let items = Pager::new(&statefulsets, ListParams::default(), "list statefulsets")
+            .collect()
+            .await?;
+        Ok(items.iter().map(statefulset_row).collect())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::serde_json::{from_value, json};
+
+    #[test]
+    fn test_deployment_row() {
+        let deployment: Deployment = from_value(json!({
+            "metadata": {"name": "web"},
+            "spec": {
+                "replicas": 3,
+                "selector": {"matchLabels": {"app": "web"}},
+                "strategy": {"type": "Recreate"},
+                "template": {}
+            },
+            "status": {"readyReplicas": 2, "updatedReplicas": 3, "availableReplicas": 2}
+        }))
+        .unwrap();
+        let row = deployment_row(&deployment);
+        assert_eq!(row.name, "web");
+        assert_eq!(row.ready, "2/3");
+        assert_eq!(
+            (row.up_to_date.as_str(), row.available.as_str()),
+            ("3", "2")
+        );
+        assert_eq!(row.strategy, "Recreate");
+        assert_eq!(row.age, "Unk");
+        assert_eq!(
+            row.selectors.unwrap().get("app").map(String::as_str),
+            Some("web")
+        );
+    }
+
+    #[test]
+    fn test_statefulset_row() {
+        let statefulset: StatefulSet = from_value(json!({
+            "metadata": {"name": "db"},
+            "spec": {
+                "serviceName": "db-headless",
+                "selector": {"matchExpressions": [
+                    {"key": "app", "operator": "In", "values": ["db"]}
+                ]},
+                "template": {}
+            }
+        }))
+        .unwrap();
+        let row = statefulset_row(&statefulset);
+        assert_eq!(row.ready, "0/1");
+        assert_eq!(row.service, "db-headless");
+        assert_eq!(row.update_strategy, "RollingUpdate");
+        assert!(row.selectors.is_none());
+    }
+}
@@ -1,4 +1,10 @@
+pub mod bug_report;
+pub mod config;
 pub mod error;
 pub mod k8s;
 pub mod net;
+pub mod perf;
+pub mod plugins;
+pub mod shutdown;
 pub mod tui;
+pub mod web;
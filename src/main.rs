@@ -1,57 +1,256 @@
+use chrono::TimeDelta;
 use clap::{CommandFactory, Parser};
 use clap_complete::{generate, Shell};
 use kube::{config::KubeConfigOptions, Config};
 
-use navipod::k8s::pod_ingress;
-use navipod::k8s::scan::db;
-use navipod::k8s::scan::pods;
+use navipod::bug_report::{self, Recorder};
+use navipod::config::data_dir;
+use navipod::k8s::audit;
+use navipod::k8s::cache::cache_manager;
+use navipod::k8s::cache::memory::{self, DEFAULT_CACHE_SIZE_MB};
+use navipod::k8s::client_manager;
+use navipod::k8s::compare;
+use navipod::k8s::deprecations;
+use navipod::k8s::journal;
+use navipod::k8s::noisy;
+use navipod::k8s::pod_ingress::{self, ExplainOutput};
+use navipod::k8s::probes;
+use navipod::k8s::scan::anonymize::Anonymizer;
+use navipod::k8s::scan::background;
+use navipod::k8s::scan::db::{self, Age};
+use navipod::k8s::scan::fleet;
+use navipod::k8s::scan::live;
+use navipod::k8s::scan::merge::{self, OnConflict};
+use navipod::k8s::scan::query::{self, OutputFormat, CANNED_QUERIES};
+use navipod::k8s::scan::rdf::{Compress, Output, RdfFormat};
+use navipod::k8s::scan::schedule::{self, Schedule};
+use navipod::k8s::scan::storage::{self, Storage, StorageBackend};
+use navipod::k8s::scan::topology::{GraphFormat, Topology};
+use navipod::k8s::timeout;
+use navipod::perf;
+use navipod::shutdown;
 use navipod::tui;
+use navipod::tui::utils::time::{configure_timezone, TimeZoneSetting};
+use navipod::web::server;
+use std::io::{self, Write};
+use std::time::Duration;
 
 #[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 enum Command {
     /// start text-based UI
     Tui,
+    /// serve the replicaset, pod and event views as a read-only web dashboard
+    Serve {
+        /// address to listen on, :8080 for every interface
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        listen: String,
+        /// a name teammates reach this machine by, repeatable; without it
+        /// only localhost and ip addresses are answered
+        #[arg(long = "allow-host", value_name = "NAME")]
+        allow_hosts: Vec<String>,
+    },
     /// report on pod external ingress
-    ExplainPod { podname: String },
+    ExplainPod {
+        /// the pod, or leave out and pick pods with --selector or --replicaset
+        podname: Option<String>,
+        /// explain every pod matching a label selector, ie: app=web
+        #[arg(short = 'l', long, conflicts_with = "podname")]
+        selector: Option<String>,
+        /// explain every pod of a replicaset
+        #[arg(long, conflicts_with_all = ["podname", "selector"])]
+        replicaset: Option<String>,
+        /// text, or a json or yaml document for scripts and CI policies
+        #[arg(short, long, value_enum, default_value_t)]
+        output: ExplainOutput,
+    },
+    /// show where two pods' images, env, resources, labels and mounts differ, as [namespace/]name
+    ComparePods { left: String, right: String },
     /// collect pod metrics and write to db
-    ScanMetrics,
+    ScanMetrics {
+        /// keep running and scan on a cron schedule in UTC, ie: "0 */6 * * *"
+        #[arg(long)]
+        schedule: Option<Schedule>,
+        /// start each scheduled scan up to this many seconds late
+        #[arg(long, default_value_t = 60, requires = "schedule")]
+        jitter_secs: u64,
+    },
+    /// scan-metrics each of many kubeconfig contexts and report on them all
+    ScanAll {
+        /// contexts to scan, ie: prod-us,prod-eu, every context of the kubeconfig when left out
+        #[arg(long, value_delimiter = ',')]
+        contexts: Vec<String>,
+        /// how many contexts to scan at once
+        #[arg(long, default_value_t = 1)]
+        parallel: usize,
+    },
     /// export db data to RDF nt files
     ExportTriples,
     /// export db data to RDF turtle files
-    ExportTurtle,
-    /// show db stats
+    ExportTurtle {
+        /// scan the namespace now instead of reading the db
+        #[arg(long)]
+        live: bool,
+    },
+    /// print the namespace's workload topology as Graphviz DOT or Mermaid
+    ExportGraph {
+        #[arg(long, value_enum, default_value_t)]
+        format: GraphFormat,
+    },
+    /// show db stats and deprecated API usage in the namespace
     Report,
+    /// rank the objects and reasons behind the namespace's events, the noisiest first
+    NoisyEvents {
+        /// count events last seen this many minutes back
+        #[arg(long, default_value_t = noisy::DEFAULT_WINDOW_MINUTES)]
+        minutes: i64,
+        /// how many to list
+        #[arg(long, default_value_t = noisy::DEFAULT_TOP)]
+        top: usize,
+    },
+    /// list what the TUI saw added, modified and deleted in the namespace, the newest first
+    Changes {
+        /// changes this many minutes back
+        #[arg(long, default_value_t = 60)]
+        minutes: i64,
+    },
+    /// maintain the scan db
+    Db {
+        #[clap(subcommand)]
+        command: DbCommand,
+    },
+    /// run read-only sql or a named query against the db, lists the named queries without one
+    Query {
+        /// sql or the name of a canned query, ie: top-restarts
+        query: Option<String>,
+        #[arg(long, value_enum, default_value_t)]
+        format: OutputFormat,
+    },
+    /// write the version, os, recent log lines, cache stats and redacted config to a file to attach to an issue
+    BugReport,
+    /// introspect navipod itself
+    Debug {
+        #[clap(subcommand)]
+        command: DebugCommand,
+    },
     /// generate completion script for bash and zsh
     GenerateCompletion { shell: Shell },
 }
 
+#[derive(Parser, Debug, Clone)]
+enum DebugCommand {
+    /// warm the cache like the TUI does and print the estimated bytes of every entry
+    Memory,
+}
+
+#[derive(Parser, Debug, Clone)]
+enum DbCommand {
+    /// list the per-context dbs in the data dir
+    List,
+    /// merge a scan db from elsewhere, ie: a teammate's, into this one
+    Import {
+        file: String,
+        /// what to do with observations this db already has
+        #[arg(long, value_enum, default_value_t)]
+        on_conflict: OnConflict,
+    },
+    /// delete scans older than an age like 30d, 12h or 90m and vacuum the db
+    Prune {
+        #[arg(long)]
+        older_than: Age,
+    },
+}
+
 #[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// export Turtle RDF file
+    /// export Turtle RDF file, gzipped if it ends in .gz
     #[arg(short, long, default_value = "navipod.ttl")]
     ttl_rdf_filename: Option<String>,
-    /// export N-Triples RDF file
+    /// export N-Triples RDF file, gzipped if it ends in .gz
     #[arg(short, long, default_value = "navipod.nt")]
     rdf_filename: Option<String>,
+    /// hash names, hosts, images and label values in exports so they can be shared
+    #[arg(long)]
+    anonymize: bool,
+    /// key for the --anonymize hashes, keeps common names from being guessed
+    #[arg(long, requires = "anonymize")]
+    anonymize_key: Option<String>,
+    /// compress exports and query output as they are written, export files get .gz or .zst added
+    #[arg(long, value_enum)]
+    compress: Option<Compress>,
     /// Name of the namespace to walk
     #[arg(short, long)]
     namespace: Option<String>,
-    #[arg(short, long, default_value = "/tmp/navipod.db")]
-    db_location: String,
+    /// defaults to navipod/<context>/navipod.db in the XDG data dir
+    #[arg(short, long)]
+    db_location: Option<String>,
+    /// a postgres://user@host/db to scan and journal into, report on, prune and export from instead of the sqlite db, needs --features postgres
+    #[arg(long)]
+    db_url: Option<String>,
+    /// scan-metrics prunes scans older than this many days, 0 keeps them all
+    #[arg(long, default_value_t = 30)]
+    retention_days: i64,
+    /// seconds to wait for any single k8s api call
+    #[arg(long, default_value_t = 15)]
+    request_timeout_secs: u64,
+    /// start a second attempt of a slow list call, each page of the replicaset, pod, event and container lists, or ingress fetch after this many ms, 0 disables
+    #[arg(long, default_value_t = 0)]
+    hedge_ms: u64,
+    /// timezone for log times, event times and cert expiries: utc, local or an offset like +02:00
+    #[arg(long, default_value = "utc")]
+    timezone: TimeZoneSetting,
+    /// run the probes of listed pods every this many seconds, 0 only runs them on 'P'
+    #[arg(long, default_value_t = 0)]
+    probe_interval_secs: u64,
+    /// cached fetches beyond this are evicted, oldest first
+    #[arg(long, default_value_t = DEFAULT_CACHE_SIZE_MB)]
+    cache_size_mb: usize,
+    /// seconds hook actions still running get to finish on 'q', SIGTERM or SIGHUP
+    #[arg(long, default_value_t = shutdown::DEFAULT_TIMEOUT_SECS)]
+    shutdown_timeout_secs: u64,
+    /// Kubernetes audit log to tail for the 'A' view of who changed what
+    #[arg(long)]
+    audit_log: Option<String>,
+    /// log a line of navipod's own frame times, api latencies and watch restarts this often, 0 never
+    #[arg(long, default_value_t = 0)]
+    perf_log_secs: u64,
+    /// serve what the TUI has cached as JSON on this localhost port
+    #[cfg(feature = "api")]
+    #[arg(long)]
+    api_port: Option<u16>,
 
     #[clap(subcommand)]
     command: Option<Command>,
 }
 
 async fn process_command() -> Result<(), Box<dyn std::error::Error>> {
-    tracing_subscriber::fmt::init();
+    tracing_subscriber::fmt().with_writer(Recorder).init();
     let _ =
         rustls::crypto::CryptoProvider::install_default(rustls::crypto::ring::default_provider());
     let args = Args::parse();
-    let db_location = args.db_location;
-    let pool = db::init(db_location).await?;
+    timeout::configure(
+        Duration::from_secs(args.request_timeout_secs),
+        (args.hedge_ms > 0).then(|| Duration::from_millis(args.hedge_ms)),
+    );
+    configure_timezone(args.timezone);
+    shutdown::configure(Duration::from_secs(args.shutdown_timeout_secs));
+    if let Some(url) = args.db_url.clone() {
+        storage::configure(url);
+    }
+    cache_manager::get_cache().set_max_bytes(args.cache_size_mb * 1024 * 1024);
+    probes::configure_schedule(
+        (args.probe_interval_secs > 0).then(|| Duration::from_secs(args.probe_interval_secs)),
+    );
+    let scan_all_db = args.db_location.clone();
+    let scan_all_namespace = args.namespace.clone();
+    let db_location = args
+        .db_location
+        .or_else(|| {
+            db::default_location(&client_manager::current_context())
+                .map(|path| path.display().to_string())
+        })
+        .unwrap_or_else(|| "/tmp/navipod.db".to_string());
     let namespace = if let Some(n) = args.namespace {
         n
     } else {
@@ -59,12 +258,67 @@ async fn process_command() -> Result<(), Box<dyn std::error::Error>> {
         config.default_namespace
     };
 
+    let anonymizer = args
+        .anonymize
+        .then(|| Anonymizer::new(args.anonymize_key.clone().unwrap_or_default()));
+    let share = |triples| match &anonymizer {
+        Some(anonymizer) => anonymizer.triples(triples),
+        None => triples,
+    };
+
+    let compressed = |path: String| match args.compress {
+        Some(compress) => compress.path(&path),
+        None => path,
+    };
+
     let command = args.command.unwrap_or(Command::Tui);
 
     match command {
         Command::Tui => {
+            if let Some(path) = args.audit_log {
+                audit::spawn_tail(path, namespace.clone());
+            }
+            journal::spawn(storage::location(&db_location), namespace.clone());
+            background::configure(db_location.clone(), namespace.clone(), args.retention_days);
+            if args.perf_log_secs > 0 {
+                perf::spawn_summary(Duration::from_secs(args.perf_log_secs));
+            }
+            #[cfg(feature = "api")]
+            if let Some(port) = args.api_port {
+                navipod::web::api::spawn(port).await?;
+            }
             tui::ui_loop::run().await?;
         }
+        Command::Serve {
+            listen,
+            allow_hosts,
+        } => server::serve(&listen, allow_hosts).await?,
+        Command::BugReport => {
+            let path = bug_report::write("bug-report", &bug_report::bundle())?;
+            println!("wrote {}, look it over before attaching it", path.display());
+        }
+        Command::Debug {
+            command: DebugCommand::Memory,
+        } => {
+            cache_manager::initialize_cache().await;
+            // the prefetches queued by the warmup land in the background
+            let mut idle = 0;
+            for _ in 0..120 {
+                tokio::time::sleep(Duration::from_millis(250)).await;
+                idle = if cache_manager::has_network_activity() {
+                    0
+                } else {
+                    idle + 1
+                };
+                if idle >= 2 {
+                    break;
+                }
+            }
+            println!(
+                "{}",
+                memory::render(&memory::report(), cache_manager::get_cache().max_bytes())
+            );
+        }
         Command::GenerateCompletion { shell } => {
             let app = Args::command();
             generate(
@@ -74,29 +328,169 @@ async fn process_command() -> Result<(), Box<dyn std::error::Error>> {
                 &mut std::io::stdout(),
             );
         }
-        Command::ExplainPod { podname } => {
-            pod_ingress::explain(&namespace, &podname).await?;
+        Command::ExplainPod {
+            podname,
+            selector,
+            replicaset,
+            output,
+        } => match podname {
+            Some(podname) => pod_ingress::explain(&namespace, &podname, output).await?,
+            None if selector.is_some() || replicaset.is_some() => {
+                pod_ingress::explain_matching(
+                    &namespace,
+                    selector.as_deref(),
+                    replicaset.as_deref(),
+                    output,
+                )
+                .await?;
+            }
+            None => println!("give a pod name, --selector or --replicaset"),
+        },
+        Command::ComparePods { left, right } => {
+            let qualify = |pod: String| {
+                if pod.contains('/') {
+                    pod
+                } else {
+                    format!("{namespace}/{pod}")
+                }
+            };
+            compare::explain_diff(&qualify(left), &qualify(right)).await?;
         }
-        Command::ScanMetrics => {
-            db::create_table(&pool).await?;
-            let (pod_list, pods) = pods::fetch(namespace.clone()).await?;
-            pods::gather_metrics(&pool, pod_list, &pods, namespace).await;
+        Command::ScanMetrics {
+            schedule,
+            jitter_secs,
+        } => match schedule {
+            Some(schedule) => {
+                schedule::run(&schedule, Duration::from_secs(jitter_secs), || {
+                    scan_metrics(&db_location, namespace.clone(), args.retention_days)
+                })
+                .await;
+            }
+            None => scan_metrics(&db_location, namespace, args.retention_days).await?,
+        },
+        Command::ScanAll { contexts, parallel } => {
+            let report = fleet::scan_all(
+                &contexts,
+                scan_all_db.as_deref(),
+                scan_all_namespace.as_deref(),
+                parallel,
+                args.retention_days,
+            )
+            .await;
+            println!("{report}");
         }
         Command::Report => {
-            // Here you will implement the reporting logic
-            let report = db::report(&pool).await?;
-            println!("{report}");
+            match Storage::open_read_only(&storage::location(&db_location)).await {
+                Ok(storage) => println!("{}", storage.report().await?),
+                Err(e) => println!("Scan db: unavailable ({e})"),
+            }
+            match deprecations::report(&namespace).await {
+                Ok(deprecation_report) => println!("{deprecation_report}"),
+                Err(e) => println!("Deprecated APIs: unavailable ({e})"),
+            }
+        }
+        Command::Changes { minutes } => {
+            let window = TimeDelta::minutes(minutes);
+            let location = storage::location(&db_location);
+            let changes = journal::list(&location, &namespace, window).await?;
+            println!("{}", journal::render(&changes, &namespace, window));
+        }
+        Command::NoisyEvents { minutes, top } => {
+            let window = TimeDelta::minutes(minutes);
+            let found = noisy::list(window).await?;
+            println!("{}", noisy::render(&found, window, top, chrono::Utc::now()));
+        }
+        Command::Db {
+            command: DbCommand::List,
+        } => {
+            let root = data_dir().ok_or("no data dir, set XDG_DATA_HOME or HOME")?;
+            let dbs = db::list(&root).await;
+            if dbs.is_empty() {
+                println!("no dbs in {}", root.display());
+            }
+            for db in dbs {
+                println!("{db}");
+            }
+        }
+        Command::Db {
+            command: DbCommand::Import { file, on_conflict },
+        } => {
+            let path = storage::sqlite_path(&storage::location(&db_location), "db import")?;
+            let (pool, _lock) = db::open_for_write(&path).await?;
+            db::create_table(&pool).await?;
+            println!("{}", merge::import(&pool, &file, on_conflict).await?);
         }
-        Command::ExportTurtle => {
-            if let Some(ttl_rdf_filename) = args.ttl_rdf_filename {
-                db::export_to_ttl_rdf(&pool, &ttl_rdf_filename).await?;
+        Command::Db {
+            command: DbCommand::Prune { older_than },
+        } => {
+            let storage = Storage::open(&storage::location(&db_location)).await?;
+            storage.create_schema().await?;
+            println!("{}", storage.prune(older_than).await?);
+        }
+        Command::Query { query, format } => match query {
+            Some(query) => {
+                let path = storage::sqlite_path(&storage::location(&db_location), "query")?;
+                let pool = query::connect_read_only(&path).await?;
+                let result = query::run(&pool, query::resolve(&query)).await?;
+                print_compressed(&result.render(format)?, args.compress)?;
+            }
+            None => {
+                for (name, description, _) in CANNED_QUERIES {
+                    println!("{name:15} {description}");
+                }
+            }
+        },
+        Command::ExportGraph { format } => {
+            let triples = share(live::snapshot(&namespace).await?);
+            print_compressed(
+                &Topology::from_triples(&triples).render(format),
+                args.compress,
+            )?;
+        }
+        Command::ExportTurtle { live } => {
+            if let Some(ttl_rdf_filename) = args.ttl_rdf_filename.map(compressed) {
+                let written = if live {
+                    let triples = share(live::snapshot(&namespace).await?);
+                    let cluster = live::cluster().await?;
+                    let clusters = [match &anonymizer {
+                        Some(anonymizer) => anonymizer.cluster(&cluster),
+                        None => cluster,
+                    }];
+                    db::export_triples(
+                        triples,
+                        &ttl_rdf_filename,
+                        RdfFormat::Turtle,
+                        &clusters,
+                        progress,
+                    )?
+                } else {
+                    let storage = Storage::open_read_only(&storage::location(&db_location)).await?;
+                    db::export_rdf(
+                        &storage,
+                        &ttl_rdf_filename,
+                        RdfFormat::Turtle,
+                        anonymizer.as_ref(),
+                        progress,
+                    )
+                    .await?
+                };
+                println!("wrote {written} triples to {ttl_rdf_filename}");
             } else {
                 println!("'rdf_filename' is required for export");
             }
         }
         Command::ExportTriples => {
-            if let Some(rdf_filename) = args.rdf_filename {
-                db::export_to_nt_rdf(&pool, &rdf_filename).await?;
+            if let Some(rdf_filename) = args.rdf_filename.map(compressed) {
+                let storage = Storage::open_read_only(&storage::location(&db_location)).await?;
+                let written = db::export_rdf(
+                    &storage,
+                    &rdf_filename,
+                    RdfFormat::NTriples,
+                    anonymizer.as_ref(),
+                    progress,
+                )
+                .await?;
+                println!("wrote {written} triples to {rdf_filename}");
             } else {
                 println!("'rdf_filename' is required for export");
             }
@@ -106,12 +500,49 @@ async fn process_command() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// One scan into the db.  The db's write lock is held for the scan, a
+/// second navipod scanning the same db fails instead of interleaving.
+async fn scan_metrics(
+    db_location: &str,
+    namespace: String,
+    retention_days: i64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (_, report) = background::scan(db_location, namespace, retention_days, |_, _| {}).await?;
+    if let Some(report) = report.filter(|report| report.rows > 0) {
+        println!("{report}");
+    }
+    Ok(())
+}
+
+/// `text` on stdout, compressed when asked to
+fn print_compressed(text: &str, compress: Option<Compress>) -> io::Result<()> {
+    let mut out = Output::new(io::stdout().lock(), compress)?;
+    writeln!(out, "{text}")?;
+    out.finish()
+}
+
+/// keeps long exports from looking stuck
+fn progress(written: u64) {
+    eprintln!("{written} triples written...");
+}
+
 #[tokio::main]
 async fn main() {
-    match process_command().await {
-        Ok(()) => (),
-        Err(e) => {
-            eprintln!("Failed to run command: {e}");
+    shutdown::listen_for_signals();
+    // a signal drops the command, the TUI's terminal guard restores the screen
+    let result = tokio::select! {
+        result = process_command() => result,
+        () = shutdown::token().cancelled() => Ok(()),
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to run command: {e}");
+        let text = format!("error: {e}\n\n{}", bug_report::bundle());
+        if let Ok(path) = bug_report::write("error", &text) {
+            eprintln!("diagnostics: {}", path.display());
         }
     }
+    shutdown::run().await;
+    if let Some(code) = shutdown::exit_code() {
+        std::process::exit(code);
+    }
 }
@@ -6,11 +6,14 @@ use tokio_rustls::TlsConnector;
 use webpki_roots::TLS_SERVER_ROOTS;
 use x509_parser::prelude::*;
 
+#[derive(Clone, Debug)]
 pub struct CertificateInfo {
     pub host: String,
     pub is_valid: bool,
     pub expires: ASN1Time,
     pub issued_by: String,
+    /// `0a:1b:..`, to tell two certificates for the same host apart
+    pub serial: String,
 }
 
 use std::convert::TryFrom;
@@ -65,6 +68,7 @@ pub async fn analyze_tls_certificate(
     let oid_o = Oid::from(&[2, 5, 4, 10])
         .map_err(|e| format!("Failed to create OID for Organization: {e:?}"))?;
 
+    let serial = cert.tbs_certificate.raw_serial_as_string();
     let issuer = cert.tbs_certificate.issuer;
     let mut issued_by_parts = Vec::new();
 
@@ -112,6 +116,7 @@ pub async fn analyze_tls_certificate(
         is_valid,  // Assuming the certificate is valid if the handshake was successful.
         expires,   // Placeholder for actual expiry date
         issued_by, // Placeholder for actual issuer
+        serial,
     };
 
     Ok(certificate_info)
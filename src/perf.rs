@@ -0,0 +1,252 @@
+//!navipod's own overhead: how long frames take to draw, how long api calls
+//!take, how often the cache answers drill-downs and how often watches
+//!reconnect.  The diagnostics view shows it, `/metrics` on the api port
+//!serves it to Prometheus and `--perf-log-secs` logs it as one line.
+//!
+use crate::k8s::cache::cache_manager::hit_rate;
+use crate::k8s::retry::retries;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::info;
+
+/// quantiles are over this many of the latest samples
+const KEEP: usize = 512;
+
+#[derive(Clone, Debug, Default)]
+struct Samples {
+    recent: VecDeque<Duration>,
+    count: u64,
+    total: Duration,
+}
+
+impl Samples {
+    fn record(&mut self, took: Duration) {
+        if self.recent.len() == KEEP {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(took);
+        self.count += 1;
+        self.total += took;
+    }
+
+    fn quantile(&self, q: f64) -> Option<Duration> {
+        let mut sorted: Vec<Duration> = self.recent.iter().copied().collect();
+        sorted.sort_unstable();
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            clippy::cast_precision_loss
+        )]
+        let index = ((sorted.len() as f64 * q).ceil() as usize).saturating_sub(1);
+        sorted.get(index).copied()
+    }
+
+    fn stats(&self) -> Timing {
+        Timing {
+            count: self.count,
+            total: self.total,
+            p50: self.quantile(0.5).unwrap_or_default(),
+            p95: self.quantile(0.95).unwrap_or_default(),
+        }
+    }
+}
+
+static FRAMES: Mutex<Samples> = Mutex::new(Samples {
+    recent: VecDeque::new(),
+    count: 0,
+    total: Duration::ZERO,
+});
+static FETCHES: Mutex<BTreeMap<String, Samples>> = Mutex::new(BTreeMap::new());
+static WATCH_RESTARTS: AtomicU64 = AtomicU64::new(0);
+
+pub fn record_frame(took: Duration) {
+    if let Ok(mut frames) = FRAMES.lock() {
+        frames.record(took);
+    }
+}
+
+/// one api call named like its timeout, ie: `list pods`
+pub fn record_fetch(operation: &str, took: Duration) {
+    if let Ok(mut fetches) = FETCHES.lock() {
+        match fetches.get_mut(operation) {
+            Some(samples) => samples.record(took),
+            None => {
+                let mut samples = Samples::default();
+                samples.record(took);
+                fetches.insert(operation.to_string(), samples);
+            }
+        }
+    }
+}
+
+pub fn record_watch_restart() {
+    WATCH_RESTARTS.fetch_add(1, Ordering::Relaxed);
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Timing {
+    pub count: u64,
+    pub total: Duration,
+    pub p50: Duration,
+    pub p95: Duration,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Snapshot {
+    pub frames: Timing,
+    /// per operation, by name
+    pub fetches: Vec<(String, Timing)>,
+    pub cache_hit_rate: Option<f64>,
+    pub watch_restarts: u64,
+    pub retries: u64,
+}
+
+impl Snapshot {
+    /// the operation with the slowest p95
+    #[must_use]
+    pub fn slowest_fetch(&self) -> Option<&(String, Timing)> {
+        self.fetches.iter().max_by_key(|(_, timing)| timing.p95)
+    }
+
+    /// `frames 1200 p95 4ms, api calls 210, slowest list pods p95 900ms, ...`
+    #[must_use]
+    pub fn summary(&self) -> String {
+        let calls: u64 = self.fetches.iter().map(|(_, timing)| timing.count).sum();
+        let mut summary = format!(
+            "frames {} p95 {}ms, api calls {calls}",
+            self.frames.count,
+            self.frames.p95.as_millis()
+        );
+        if let Some((operation, timing)) = self.slowest_fetch() {
+            let _ = write!(
+                summary,
+                ", slowest {operation} p95 {}ms",
+                timing.p95.as_millis()
+            );
+        }
+        if let Some(rate) = self.cache_hit_rate {
+            let _ = write!(summary, ", cache hits {:.0}%", rate * 100.0);
+        }
+        let _ = write!(
+            summary,
+            ", watch restarts {}, retries {}",
+            self.watch_restarts, self.retries
+        );
+        summary
+    }
+
+    /// the Prometheus text format
+    #[must_use]
+    pub fn prometheus(&self) -> String {
+        fn timing(out: &mut String, name: &str, labels: &str, timing: &Timing) {
+            let sep = if labels.is_empty() { "" } else { "," };
+            for (quantile, value) in [("0.5", timing.p50), ("0.95", timing.p95)] {
+                let _ = writeln!(
+                    out,
+                    "{name}{{{labels}{sep}quantile=\"{quantile}\"}} {}",
+                    value.as_secs_f64()
+                );
+            }
+            let labels = if labels.is_empty() {
+                String::new()
+            } else {
+                format!("{{{labels}}}")
+            };
+            let _ = writeln!(out, "{name}_sum{labels} {}", timing.total.as_secs_f64());
+            let _ = writeln!(out, "{name}_count{labels} {}", timing.count);
+        }
+
+        let mut out = String::new();
+        out.push_str("# HELP navipod_frame_seconds time to draw one frame\n");
+        out.push_str("# TYPE navipod_frame_seconds summary\n");
+        timing(&mut out, "navipod_frame_seconds", "", &self.frames);
+        out.push_str("# HELP navipod_api_call_seconds time of one k8s api call\n");
+        out.push_str("# TYPE navipod_api_call_seconds summary\n");
+        for (operation, stats) in &self.fetches {
+            let labels = format!("operation=\"{}\"", operation.replace('"', "'"));
+            timing(&mut out, "navipod_api_call_seconds", &labels, stats);
+        }
+        if let Some(rate) = self.cache_hit_rate {
+            out.push_str("# HELP navipod_cache_hit_ratio drill-downs answered by the cache\n");
+            out.push_str("# TYPE navipod_cache_hit_ratio gauge\n");
+            let _ = writeln!(out, "navipod_cache_hit_ratio {rate}");
+        }
+        out.push_str("# HELP navipod_watch_restarts_total watches reconnected\n");
+        out.push_str("# TYPE navipod_watch_restarts_total counter\n");
+        let _ = writeln!(out, "navipod_watch_restarts_total {}", self.watch_restarts);
+        out.push_str("# HELP navipod_api_retries_total api calls retried\n");
+        out.push_str("# TYPE navipod_api_retries_total counter\n");
+        let _ = writeln!(out, "navipod_api_retries_total {}", self.retries);
+        out
+    }
+}
+
+#[must_use]
+pub fn snapshot() -> Snapshot {
+    Snapshot {
+        frames: FRAMES.lock().map(|f| f.stats()).unwrap_or_default(),
+        fetches: FETCHES
+            .lock()
+            .map(|fetches| {
+                fetches
+                    .iter()
+                    .map(|(operation, samples)| (operation.clone(), samples.stats()))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        cache_hit_rate: hit_rate(),
+        watch_restarts: WATCH_RESTARTS.load(Ordering::Relaxed),
+        retries: retries(),
+    }
+}
+
+/// Log the summary every `interval` in the background.
+pub fn spawn_summary(interval: Duration) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            info!("navipod perf: {}", snapshot().summary());
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantiles_and_export() {
+        let mut samples = Samples::default();
+        for ms in 1..=100 {
+            samples.record(Duration::from_millis(ms));
+        }
+        let frames = samples.stats();
+        assert_eq!(frames.count, 100);
+        assert_eq!(frames.p50, Duration::from_millis(50));
+        assert_eq!(frames.p95, Duration::from_millis(95));
+
+        let mut list = Samples::default();
+        list.record(Duration::from_millis(900));
+        let snapshot = Snapshot {
+            frames,
+            fetches: vec![
+                ("get pod".to_string(), Samples::default().stats()),
+                ("list pods".to_string(), list.stats()),
+            ],
+            cache_hit_rate: Some(0.82),
+            watch_restarts: 3,
+            retries: 1,
+        };
+        assert_eq!(
+            snapshot.summary(),
+            "frames 100 p95 95ms, api calls 1, slowest list pods p95 900ms, cache hits 82%, watch restarts 3, retries 1"
+        );
+        let text = snapshot.prometheus();
+        assert!(text.contains("navipod_frame_seconds{quantile=\"0.95\"} 0.095\n"));
+        assert!(text.contains("navipod_api_call_seconds_count{operation=\"list pods\"} 1\n"));
+        assert!(text.contains("navipod_watch_restarts_total 3\n"));
+    }
+}
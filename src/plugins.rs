@@ -0,0 +1,535 @@
+//!Plugins: external commands that add table views, detail panels and
+//!actions.  A plugin is a manifest in the `plugins` dir of the config dir,
+//!ie: `~/.config/navipod/plugins/cost.yaml`:
+//!
+//!```yaml
+//!name: cost
+//!command: [./cost-report, --json]
+//!view:
+//!  title: Cost
+//!  columns: [POD, CPU, $/DAY]
+//!detail: [pod]
+//!actions:
+//!  - name: scale to zero
+//!    kinds: [replicaset]
+//!permissions:
+//!  kube: true
+//!  timeout_secs: 20
+//!```
+//!
+//!The command is run once per call with one JSON request on stdin and
+//!answers with one JSON object on stdout:
+//!
+//!- `{"request":"view","namespace":"..","context":".."}` gets `{"rows":[["web-1","250m","0.40"]]}`
+//!- `{"request":"detail","target":{"kind":"pod","namespace":"..","name":".."}}` gets `{"details":[["cost","0.40"]]}`
+//!- `{"request":"action","action":"scale to zero","target":{..}}` gets `{"message":"scaled"}`
+//!
+//!Any of them may answer `{"error":".."}` instead.  Commands run in the
+//!plugins dir with a cleared environment, are killed at their timeout and
+//!may print at most `MAX_OUTPUT` bytes.  Only plugins with the `kube`
+//!permission get `KUBECONFIG` and the user's `HOME`, the others get a
+//!`HOME` of their own under the data dir so `~/.kube` is out of reach.
+//!Manifests or commands that other users can write to, or that sit in a
+//!dir they can, are not loaded, nor are names other than letters, digits,
+//!`_` and `-`.
+//!
+use crate::config::{config_dir, data_dir};
+use crate::error::{Error, Result};
+use crate::k8s::client_manager::{current_context, get_client};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+use tracing::debug;
+
+const PLUGINS_DIR: &str = "plugins";
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+const MAX_TIMEOUT_SECS: u64 = 120;
+/// bytes a plugin may print on stdout
+const MAX_OUTPUT: u64 = 1024 * 1024;
+/// bytes of stderr kept for the error message
+const MAX_STDERR: u64 = 4096;
+/// passed on to every plugin
+const BASE_ENV: [&str; 3] = ["PATH", "LANG", "TERM"];
+/// passed on to plugins with the kube permission, where kubectl and cloud
+/// CLIs find their credentials
+const KUBE_ENV: [&str; 2] = ["KUBECONFIG", "HOME"];
+/// under the data dir, the `HOME` of each plugin without the kube permission
+const PLUGIN_HOMES: &str = "plugin-home";
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Manifest {
+    pub name: String,
+    /// program and arguments, a relative path is resolved against the
+    /// plugins dir and a bare name is looked up in `PATH`
+    pub command: Vec<String>,
+    #[serde(default)]
+    pub view: Option<ViewSpec>,
+    /// kinds of resources it has a detail panel for
+    #[serde(default)]
+    pub detail: Vec<String>,
+    #[serde(default)]
+    pub actions: Vec<ActionSpec>,
+    #[serde(default)]
+    pub permissions: Permissions,
+}
+
+/// a table of the plugin's own rows
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct ViewSpec {
+    pub title: String,
+    pub columns: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct ActionSpec {
+    pub name: String,
+    /// kinds of resources it applies to: replicaset, pod or container
+    pub kinds: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct Permissions {
+    /// hand the kubeconfig to the plugin
+    pub kube: bool,
+    pub timeout_secs: Option<u64>,
+}
+
+/// the resource a detail panel or action is for, a container is named
+/// `pod/container`
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct Target {
+    pub kind: String,
+    pub namespace: String,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+#[serde(tag = "request", rename_all = "lowercase")]
+pub enum Request {
+    View { namespace: String, context: String },
+    Detail { target: Target },
+    Action { action: String, target: Target },
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct Response {
+    pub rows: Vec<Vec<String>>,
+    pub details: Vec<(String, String)>,
+    pub message: Option<String>,
+    pub error: Option<String>,
+}
+
+/// what a plugin gets to run with
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Policy {
+    pub timeout: Duration,
+    pub max_output: u64,
+    pub env: Vec<(String, String)>,
+    /// made and passed as `HOME` when the real one is not
+    pub home: Option<PathBuf>,
+}
+
+impl Policy {
+    fn new(
+        permissions: &Permissions,
+        home: Option<PathBuf>,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Self {
+        let kube: &[&str] = if permissions.kube { &KUBE_ENV } else { &[] };
+        let env = BASE_ENV
+            .iter()
+            .chain(kube)
+            .filter_map(|key| Some(((*key).to_string(), lookup(key)?)))
+            .collect();
+        let timeout_secs = permissions
+            .timeout_secs
+            .unwrap_or(DEFAULT_TIMEOUT_SECS)
+            .clamp(1, MAX_TIMEOUT_SECS);
+        Self {
+            timeout: Duration::from_secs(timeout_secs),
+            max_output: MAX_OUTPUT,
+            env,
+            home: home.filter(|_| !permissions.kube),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Plugin {
+    pub manifest: Manifest,
+    dir: PathBuf,
+    policy: Policy,
+}
+
+impl Plugin {
+    fn program(&self) -> Option<PathBuf> {
+        let program = Path::new(self.manifest.command.first()?);
+        if program.is_relative() && program.components().count() > 1 {
+            Some(self.dir.join(program))
+        } else {
+            Some(program.to_path_buf())
+        }
+    }
+
+    #[must_use]
+    pub fn has_detail(&self, kind: &str) -> bool {
+        self.manifest.detail.iter().any(|k| k == kind)
+    }
+
+    pub fn actions_for<'a>(&'a self, kind: &'a str) -> impl Iterator<Item = &'a ActionSpec> {
+        self.manifest
+            .actions
+            .iter()
+            .filter(move |action| action.kinds.iter().any(|k| k == kind))
+    }
+
+    fn failed(&self, why: impl std::fmt::Display) -> Error {
+        Error::Plugin(format!("{}: {why}", self.manifest.name))
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if the command can not be started, fails, runs past
+    /// its timeout or does not answer with a response
+    pub async fn call(&self, request: &Request) -> Result<Response> {
+        let program = self.program().ok_or_else(|| self.failed("empty command"))?;
+        let mut command = Command::new(program);
+        command
+            .args(&self.manifest.command[1..])
+            .env_clear()
+            .envs(self.policy.env.iter().map(|(key, value)| (key, value)));
+        if let Some(home) = &self.policy.home {
+            tokio::fs::create_dir_all(home).await?;
+            command.env("HOME", home);
+        }
+        let mut child = command
+            .current_dir(&self.dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let input = serde_json::to_vec(request)?;
+        let (stdin, stdout, stderr) =
+            (child.stdin.take(), child.stdout.take(), child.stderr.take());
+        let max_output = self.policy.max_output;
+        let run = async {
+            let write = async {
+                if let Some(mut stdin) = stdin {
+                    stdin.write_all(&input).await?;
+                }
+                Ok::<_, std::io::Error>(())
+            };
+            let mut out = Vec::new();
+            let read_out = async {
+                if let Some(stdout) = stdout {
+                    stdout.take(max_output + 1).read_to_end(&mut out).await?;
+                }
+                Ok::<_, std::io::Error>(())
+            };
+            let mut err = Vec::new();
+            let read_err = async {
+                if let Some(stderr) = stderr {
+                    stderr.take(MAX_STDERR).read_to_end(&mut err).await?;
+                }
+                Ok::<_, std::io::Error>(())
+            };
+            // a plugin that does not read its request is fine
+            let (_, read_out, read_err) = tokio::join!(write, read_out, read_err);
+            read_out?;
+            read_err?;
+            if out.len() as u64 > max_output {
+                return Ok(Err(
+                    self.failed(format!("printed more than {max_output} bytes"))
+                ));
+            }
+            let status = child.wait().await?;
+            Ok::<_, std::io::Error>(Ok((status, out, err)))
+        };
+        let (status, out, err) = tokio::time::timeout(self.policy.timeout, run)
+            .await
+            .map_err(|_| Error::Timeout {
+                operation: format!("plugin {}", self.manifest.name),
+                after: self.policy.timeout,
+            })???;
+        if !status.success() {
+            let err = String::from_utf8_lossy(&err);
+            let reason = err.lines().next().unwrap_or_default();
+            return Err(self.failed(format!("{status} {reason}")));
+        }
+        let response: Response = serde_json::from_slice(&out)?;
+        match response.error {
+            Some(error) => Err(self.failed(error)),
+            None => Ok(response),
+        }
+    }
+}
+
+/// `plugins` in the config dir
+#[must_use]
+pub fn plugins_dir() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(PLUGINS_DIR))
+}
+
+/// manifests and commands other users can change, or swap for another in
+/// their dir, are not run
+#[cfg(unix)]
+fn check_not_shared(path: &Path) -> std::result::Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+    for path in std::iter::once(path).chain(dir) {
+        let mode = std::fs::metadata(path)
+            .map_err(|e| e.to_string())?
+            .permissions()
+            .mode();
+        if mode & 0o022 != 0 {
+            return Err(format!("{} is writable by group or others", path.display()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_not_shared(_path: &Path) -> std::result::Result<(), String> {
+    Ok(())
+}
+
+fn load(dir: &Path, path: &Path) -> std::result::Result<Plugin, String> {
+    check_not_shared(path)?;
+    let yaml = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let manifest: Manifest = serde_yaml::from_str(&yaml).map_err(|e| e.to_string())?;
+    // the name is a dir under the data dir, ie: no `..`
+    if manifest.name.is_empty()
+        || !manifest
+            .name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(format!(
+            "name {:?} is not letters, digits, _ and -",
+            manifest.name
+        ));
+    }
+    let home = data_dir().map(|dir| dir.join(PLUGIN_HOMES).join(&manifest.name));
+    let plugin = Plugin {
+        policy: Policy::new(&manifest.permissions, home, |key| std::env::var(key).ok()),
+        manifest,
+        dir: dir.to_path_buf(),
+    };
+    match plugin.program() {
+        None => return Err("empty command".to_string()),
+        // a path, absolute or resolved against the plugins dir, not a bare
+        // name looked up in PATH
+        Some(program) if program.components().count() > 1 => check_not_shared(&program)?,
+        Some(_) => {}
+    }
+    Ok(plugin)
+}
+
+fn discover(dir: &Path) -> Vec<Plugin> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut plugins: Vec<Plugin> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == "yaml" || ext == "yml")
+        })
+        .filter_map(|path| {
+            load(dir, &path)
+                .map_err(|e| debug!("ignoring plugin {}: {e}", path.display()))
+                .ok()
+        })
+        .collect();
+    plugins.sort_by(|a, b| a.manifest.name.cmp(&b.manifest.name));
+    plugins
+}
+
+static PLUGINS: OnceLock<Vec<Plugin>> = OnceLock::new();
+
+/// the plugins found on first use, by name
+pub fn plugins() -> &'static [Plugin] {
+    PLUGINS.get_or_init(|| plugins_dir().map(|dir| discover(&dir)).unwrap_or_default())
+}
+
+#[must_use]
+pub fn find(name: &str) -> Option<&'static Plugin> {
+    plugins().iter().find(|plugin| plugin.manifest.name == name)
+}
+
+/// plugins with a table view
+pub fn views() -> impl Iterator<Item = &'static Plugin> {
+    plugins()
+        .iter()
+        .filter(|plugin| plugin.manifest.view.is_some())
+}
+
+/// # Errors
+///
+/// Will return `Err` if the plugin is gone or its call fails
+pub async fn view_rows(name: &str) -> Result<Vec<Vec<String>>> {
+    let plugin = find(name).ok_or_else(|| Error::Plugin(format!("{name}: not installed")))?;
+    let namespace = get_client().await?.default_namespace().to_string();
+    let request = Request::View {
+        namespace,
+        context: current_context(),
+    };
+    Ok(plugin.call(&request).await?.rows)
+}
+
+/// what the plugins with a detail panel for `target.kind` say about it,
+/// a failing plugin gives one row with its error
+pub async fn details(target: &Target) -> Vec<(String, String)> {
+    let request = Request::Detail {
+        target: target.clone(),
+    };
+    let mut details = vec![];
+    for plugin in plugins().iter().filter(|p| p.has_detail(&target.kind)) {
+        match plugin.call(&request).await {
+            Ok(response) => details.extend(response.details),
+            Err(e) => details.push((plugin.manifest.name.clone(), e.to_string())),
+        }
+    }
+    details
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shell_plugin(script: &str, permissions: Permissions) -> Plugin {
+        let home = std::env::temp_dir().join("navipod-plugin-test-home");
+        Plugin {
+            policy: Policy::new(&permissions, Some(home), |key| match key {
+                "KUBECONFIG" => Some("/tmp/kubeconfig".to_string()),
+                "HOME" => Some("/home/user".to_string()),
+                _ => std::env::var(key).ok(),
+            }),
+            manifest: Manifest {
+                name: "test".to_string(),
+                command: vec!["sh".to_string(), "-c".to_string(), script.to_string()],
+                view: None,
+                detail: vec![],
+                actions: vec![],
+                permissions,
+            },
+            dir: std::env::temp_dir(),
+        }
+    }
+
+    #[test]
+    fn test_manifest() {
+        let manifest: Manifest = serde_yaml::from_str(
+            "name: cost\ncommand: [./cost, --json]\ndetail: [pod]\nactions:\n  - name: drain\n    kinds: [pod]\n",
+        )
+        .unwrap();
+        let plugin = Plugin {
+            policy: Policy::new(&manifest.permissions, None, |_| None),
+            manifest,
+            dir: PathBuf::from("/plugins"),
+        };
+        assert_eq!(plugin.program().unwrap(), PathBuf::from("/plugins/./cost"));
+        assert!(plugin.has_detail("pod"));
+        assert_eq!(plugin.actions_for("pod").count(), 1);
+        assert_eq!(plugin.actions_for("replicaset").count(), 0);
+        assert_eq!(
+            plugin.policy.timeout,
+            Duration::from_secs(DEFAULT_TIMEOUT_SECS)
+        );
+
+        let request = Request::Detail {
+            target: Target {
+                kind: "pod".to_string(),
+                namespace: "default".to_string(),
+                name: "web-1".to_string(),
+            },
+        };
+        assert_eq!(
+            serde_json::to_string(&request).unwrap(),
+            r#"{"request":"detail","target":{"kind":"pod","namespace":"default","name":"web-1"}}"#
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_sandbox() {
+        let echo_kubeconfig = r#"cat > /dev/null; printf '{"message":"%s %s","rows":[["a","b"]]}' "$KUBECONFIG" "$HOME""#;
+        let request = Request::View {
+            namespace: "default".to_string(),
+            context: "kind".to_string(),
+        };
+        let plugin = shell_plugin(echo_kubeconfig, Permissions::default());
+        let response = plugin.call(&request).await.unwrap();
+        let scratch = std::env::temp_dir().join("navipod-plugin-test-home");
+        assert_eq!(response.message.unwrap(), format!(" {}", scratch.display()));
+        assert!(scratch.is_dir());
+        assert_eq!(response.rows, vec![vec!["a".to_string(), "b".to_string()]]);
+
+        let kube = Permissions {
+            kube: true,
+            ..Permissions::default()
+        };
+        let plugin = shell_plugin(echo_kubeconfig, kube);
+        let response = plugin.call(&request).await.unwrap();
+        assert_eq!(
+            response.message.as_deref(),
+            Some("/tmp/kubeconfig /home/user")
+        );
+
+        let plugin = shell_plugin(r#"echo '{"error":"no metrics"}'"#, Permissions::default());
+        assert!(
+            matches!(plugin.call(&request).await, Err(Error::Plugin(m)) if m.contains("no metrics"))
+        );
+
+        let mut plugin = shell_plugin("sleep 5", Permissions::default());
+        plugin.policy.timeout = Duration::from_millis(100);
+        assert!(matches!(
+            plugin.call(&request).await,
+            Err(Error::Timeout { .. })
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_shared_commands_are_not_loaded() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = std::env::temp_dir().join(format!("navipod-plugins-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let manifest = dir.join("cost.yaml");
+        std::fs::write(&manifest, "name: cost\ncommand: [./cost]\n").unwrap();
+        let command = dir.join("cost");
+        std::fs::write(&command, "#!/bin/sh\n").unwrap();
+        let mode = |path: &Path, mode| {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).unwrap();
+        };
+        mode(&dir, 0o755);
+        mode(&manifest, 0o644);
+        mode(&command, 0o755);
+        assert!(load(&dir, &manifest).is_ok());
+        for shared in [0o777, 0o775] {
+            mode(&command, shared);
+            assert!(load(&dir, &manifest)
+                .unwrap_err()
+                .contains("writable by group or others"));
+        }
+        mode(&command, 0o755);
+        mode(&dir, 0o775);
+        assert!(load(&dir, &manifest)
+            .unwrap_err()
+            .contains("writable by group or others"));
+        mode(&dir, 0o755);
+        std::fs::write(&manifest, "name: ../..\ncommand: [./cost]\n").unwrap();
+        assert!(load(&dir, &manifest)
+            .unwrap_err()
+            .contains("is not letters, digits, _ and -"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,131 @@
+//!SIGTERM and SIGHUP, ie: `tmux kill-session` or a CI job timing out, end
+//!navipod the way 'q' does.  The running command is dropped, which puts the
+//!terminal back, watches stop, and hook actions still patching the cluster
+//!get `--shutdown-timeout-secs` to finish before the history is saved.
+//!
+//!The tasks are registered by what they do so 'q' can list them and ask
+//!whether to wait for them or abort them first.
+//!
+use crate::k8s::cache::cache_manager;
+use crate::k8s::metrics_history;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+use tokio_util::sync::CancellationToken;
+
+/// what the background tasks get to finish, by default
+pub const DEFAULT_TIMEOUT_SECS: u64 = 5;
+
+/// the signal that asked navipod to stop, 0 for none
+static SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+static TIMEOUT: OnceLock<Duration> = OnceLock::new();
+
+/// what the tasks the way out waits for are doing, by when they started
+static PENDING: Mutex<BTreeMap<u64, String>> = Mutex::new(BTreeMap::new());
+
+static NEXT_PENDING: AtomicU64 = AtomicU64::new(0);
+
+/// set when the user chose to quit without waiting for `PENDING`
+static ABORTED: AtomicBool = AtomicBool::new(false);
+
+/// Held by a task changing the cluster, ie: a hook annotating a pod, so the
+/// way out waits for it instead of cutting it off half done.
+pub struct Pending(u64);
+
+impl Pending {
+    /// `what` is how the quit dialog lists the task, ie: `hook oom on web-1`
+    #[must_use]
+    pub fn start(what: impl Into<String>) -> Self {
+        let id = NEXT_PENDING.fetch_add(1, Ordering::Relaxed);
+        if let Ok(mut pending) = PENDING.lock() {
+            pending.insert(id, what.into());
+        }
+        Self(id)
+    }
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        if let Ok(mut pending) = PENDING.lock() {
+            pending.remove(&self.0);
+        }
+    }
+}
+
+/// what the tasks still running are doing, oldest first
+#[must_use]
+pub fn outstanding() -> Vec<String> {
+    PENDING
+        .lock()
+        .map(|pending| pending.values().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Quit without waiting for the tasks still running.
+pub fn abort() {
+    ABORTED.store(true, Ordering::Relaxed);
+}
+
+/// Set the drain timeout once at startup, later calls are ignored.
+pub fn configure(timeout: Duration) {
+    let _ = TIMEOUT.set(timeout);
+}
+
+/// Cancelled once navipod is asked to stop, tasks running for as long as
+/// navipod does select on it.
+pub fn token() -> &'static CancellationToken {
+    static TOKEN: OnceLock<CancellationToken> = OnceLock::new();
+    TOKEN.get_or_init(CancellationToken::new)
+}
+
+/// The exit status of a process a signal ended, `None` when none did.
+#[must_use]
+pub fn exit_code() -> Option<i32> {
+    match SIGNAL.load(Ordering::Relaxed) {
+        0 => None,
+        signal => Some(128 + signal),
+    }
+}
+
+/// Cancel `token` on SIGTERM or SIGHUP.
+#[cfg(unix)]
+pub fn listen_for_signals() {
+    use tokio::signal::unix::{signal, SignalKind};
+    tokio::spawn(async {
+        let (Ok(mut term), Ok(mut hangup)) = (
+            signal(SignalKind::terminate()),
+            signal(SignalKind::hangup()),
+        ) else {
+            return;
+        };
+        let kind = tokio::select! {
+            _ = term.recv() => SignalKind::terminate(),
+            _ = hangup.recv() => SignalKind::hangup(),
+        };
+        SIGNAL.store(kind.as_raw_value(), Ordering::Relaxed);
+        token().cancel();
+    });
+}
+
+/// Cancel `token` on SIGTERM or SIGHUP.
+#[cfg(not(unix))]
+pub const fn listen_for_signals() {}
+
+/// The cleanup on the way out, after 'q' and after a signal alike.
+pub async fn run() {
+    token().cancel();
+    let timeout = TIMEOUT
+        .get()
+        .copied()
+        .unwrap_or(Duration::from_secs(DEFAULT_TIMEOUT_SECS));
+    let deadline = Instant::now() + timeout;
+    while !ABORTED.load(Ordering::Relaxed) && !outstanding().is_empty() && Instant::now() < deadline
+    {
+        sleep(Duration::from_millis(50)).await;
+    }
+    cache_manager::shutdown_cache();
+    metrics_history::flush();
+}
@@ -0,0 +1,108 @@
+use crate::error::Error;
+use crate::error::Result as NvResult;
+use crate::k8s::cache::cache_manager::get_cache;
+use crate::k8s::cache::{CachedData, DataRequest};
+use crate::k8s::scheduling::cached_nodes;
+use crate::tui::data::NodeCapacity;
+use crate::tui::stream::Message;
+use crate::tui::table_app::{self, TableApp};
+use crate::tui::ui_loop::{AppBehavior, Transition};
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use futures::Stream;
+use ratatui::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::debug;
+
+const POLL_MS: u64 = 10000;
+
+/// Requests of the scheduled pods summed per node against allocatable, to
+/// see why pods stay Pending and where the room to scale is.
+#[derive(Clone, Debug)]
+pub struct App {
+    pub(crate) table: TableApp<NodeCapacity>,
+}
+
+impl AppBehavior for App {
+    const NAME: &'static str = "capacity";
+
+    async fn handle_event(&mut self, event: &Message) -> NvResult<Transition> {
+        match event {
+            Message::Key(Event::Key(key))
+                if key.kind == KeyEventKind::Press
+                    && key.code == KeyCode::Char('R')
+                    && !self.table.show_filter_edit =>
+            {
+                return Ok(Transition::Refresh);
+            }
+            Message::Key(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                return Ok(self.table.handle_key(key));
+            }
+            Message::Capacity(data_vec) => {
+                debug!("updating capacity app data...");
+                self.table.set_items(data_vec);
+            }
+            _ => {}
+        }
+        Ok(Transition::Stay)
+    }
+
+    fn draw_ui<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<(), std::io::Error> {
+        terminal.draw(|f| table_app::ui(f, &mut self.table))?;
+        Ok(())
+    }
+
+    fn source(&self) -> Option<DataRequest> {
+        Some(DataRequest::Nodes)
+    }
+
+    fn stream(&self, should_stop: Arc<AtomicBool>) -> impl Stream<Item = Message> {
+        let (tx, rx) = mpsc::channel(100);
+
+        let initial_items = self.table.items.clone();
+
+        tokio::spawn(async move {
+            while !should_stop.load(Ordering::Relaxed) {
+                match cached_nodes().await {
+                    Ok(nodes) => {
+                        let d = NodeCapacity::from_nodes(&nodes);
+                        if d != initial_items && tx.send(Message::Capacity(d)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(Error::Timeout { .. }) => {
+                        // already surfaced in the header, keep polling
+                    }
+                    Err(_e) => {
+                        break;
+                    }
+                }
+                sleep(Duration::from_millis(POLL_MS)).await;
+            }
+        });
+
+        ReceiverStream::new(rx)
+    }
+}
+
+impl App {
+    pub fn new() -> Self {
+        let nodes = match get_cache().get(&DataRequest::Nodes) {
+            Some(CachedData::Nodes(nodes)) => nodes,
+            _ => vec![],
+        };
+        Self {
+            table: TableApp::new(NodeCapacity::from_nodes(&nodes)),
+        }
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
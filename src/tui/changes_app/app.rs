@@ -0,0 +1,88 @@
+use crate::error::Result as NvResult;
+use crate::k8s::events::format_duration;
+use crate::k8s::journal::{self, Change};
+use crate::tui::stream::Message;
+use crate::tui::table_app::{self, TableApp};
+use crate::tui::ui_loop::{AppBehavior, Transition};
+use chrono::{Duration, Utc};
+use crossterm::event::{Event, KeyEventKind};
+use futures::Stream;
+use ratatui::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::debug;
+
+const POLL_MS: u64 = 2000;
+/// how far back the view looks
+const WINDOW_MINUTES: i64 = 60;
+
+/// What the pod and replicaset watches saw added, modified and deleted in
+/// the last hour, the newest first.
+#[derive(Clone, Debug)]
+pub struct App {
+    pub(crate) table: TableApp<Change>,
+}
+
+impl AppBehavior for App {
+    const NAME: &'static str = "changes";
+
+    async fn handle_event(&mut self, event: &Message) -> NvResult<Transition> {
+        match event {
+            Message::Key(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                return Ok(self.table.handle_key(key));
+            }
+            Message::Changes(data_vec) => {
+                debug!("updating changes app data...");
+                self.table.set_items(data_vec);
+            }
+            _ => {}
+        }
+        Ok(Transition::Stay)
+    }
+
+    fn draw_ui<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<(), std::io::Error> {
+        terminal.draw(|f| table_app::ui(f, &mut self.table))?;
+        Ok(())
+    }
+
+    fn stream(&self, should_stop: Arc<AtomicBool>) -> impl Stream<Item = Message> {
+        let (tx, rx) = mpsc::channel(100);
+
+        tokio::spawn(async move {
+            let mut sent: Option<Vec<Change>> = None;
+            while !should_stop.load(Ordering::Relaxed) {
+                let changes = journal::recent(Utc::now() - window());
+                if sent.as_ref() != Some(&changes) {
+                    sent = Some(changes.clone());
+                    if tx.send(Message::Changes(changes)).await.is_err() {
+                        break;
+                    }
+                }
+                sleep(std::time::Duration::from_millis(POLL_MS)).await;
+            }
+        });
+
+        ReceiverStream::new(rx)
+    }
+}
+
+fn window() -> Duration {
+    Duration::minutes(WINDOW_MINUTES)
+}
+
+impl App {
+    pub fn new() -> Self {
+        let mut table = TableApp::new(journal::recent(Utc::now() - window()));
+        table.note = format!("last {}", format_duration(window()));
+        Self { table }
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
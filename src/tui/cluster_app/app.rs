@@ -0,0 +1,149 @@
+use crate::error::Error;
+use crate::error::Result as NvResult;
+use crate::k8s::actions::Target;
+use crate::k8s::cluster::{self, ClusterListing};
+use crate::k8s::namespaces::delete_dry_run;
+use crate::tui::data::ClusterRow;
+use crate::tui::metadata_editor::{self, MetadataEditor};
+use crate::tui::namespace_dialog::{self, NamespaceDialog};
+use crate::tui::stream::Message;
+use crate::tui::table_app::{self, TableApp};
+use crate::tui::table_ui::TuiTableState;
+use crate::tui::ui_loop::{AppBehavior, Transition};
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use futures::Stream;
+use ratatui::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::debug;
+
+const POLL_MS: u64 = 15000;
+
+/// Namespaces, persistent volumes, storage classes and CRDs, with counts per
+/// kind above the table.  Namespace rows can be labeled, `n` creates a
+/// namespace and `D` deletes an empty one.
+#[derive(Clone, Debug)]
+pub struct App {
+    pub(crate) table: TableApp<ClusterRow>,
+    pub(crate) metadata_editor: Option<Box<MetadataEditor>>,
+    pub(crate) namespace_dialog: Option<Box<NamespaceDialog>>,
+}
+
+impl AppBehavior for App {
+    const NAME: &'static str = "cluster";
+
+    async fn handle_event(&mut self, event: &Message) -> NvResult<Transition> {
+        match event {
+            Message::Key(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                use KeyCode::Char;
+                if self.namespace_dialog.is_some() {
+                    namespace_dialog::handle_key(&mut self.namespace_dialog, key).await;
+                } else if self.metadata_editor.is_some() {
+                    metadata_editor::handle_key(&mut self.metadata_editor, key).await;
+                } else if self.table.show_filter_edit {
+                    return Ok(self.table.handle_key(key));
+                } else {
+                    match key.code {
+                        Char('n') => {
+                            self.namespace_dialog = Some(Box::new(NamespaceDialog::create()));
+                        }
+                        Char('D') => {
+                            if let Some(namespace) = self.selected_namespace() {
+                                let contents = delete_dry_run(&namespace)
+                                    .await
+                                    .map_err(|e| format!("{}: {e}", e.code()));
+                                self.namespace_dialog =
+                                    Some(Box::new(NamespaceDialog::delete(namespace, contents)));
+                            }
+                        }
+                        Char('l' | 'L') => {
+                            self.metadata_editor = self
+                                .selected_namespace()
+                                .map(|namespace| vec![Target::Namespace(namespace)])
+                                .and_then(MetadataEditor::new)
+                                .map(Box::new);
+                        }
+                        _ => return Ok(self.table.handle_key(key)),
+                    }
+                }
+            }
+            Message::Cluster(listing) => {
+                debug!("updating cluster app data...");
+                self.table.set_items(&listing.rows);
+                self.table.note = listing.summary();
+            }
+            _ => {}
+        }
+        Ok(Transition::Stay)
+    }
+
+    fn draw_ui<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<(), std::io::Error> {
+        terminal.draw(|f| {
+            table_app::ui(f, &mut self.table);
+            if let Some(editor) = &self.metadata_editor {
+                metadata_editor::render(f, editor);
+            }
+            if let Some(dialog) = &self.namespace_dialog {
+                namespace_dialog::render(f, dialog);
+            }
+        })?;
+        Ok(())
+    }
+
+    fn stream(&self, should_stop: Arc<AtomicBool>) -> impl Stream<Item = Message> {
+        let (tx, rx) = mpsc::channel(100);
+
+        tokio::spawn(async move {
+            let mut sent: Option<ClusterListing> = None;
+            while !should_stop.load(Ordering::Relaxed) {
+                match cluster::list_cluster_resources().await {
+                    Ok(d) => {
+                        if sent.as_ref() != Some(&d) {
+                            sent = Some(d.clone());
+                            if tx.send(Message::Cluster(d)).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Err(Error::Timeout { .. }) => {
+                        // already surfaced in the header, keep polling
+                    }
+                    Err(_e) => {
+                        break;
+                    }
+                }
+                sleep(Duration::from_millis(POLL_MS)).await;
+            }
+        });
+
+        ReceiverStream::new(rx)
+    }
+}
+
+impl App {
+    pub fn new() -> Self {
+        Self {
+            table: TableApp::new(vec![]),
+            metadata_editor: None,
+            namespace_dialog: None,
+        }
+    }
+
+    /// the name of the selected row when it is a namespace
+    fn selected_namespace(&mut self) -> Option<String> {
+        self.table
+            .get_selected_item()
+            .filter(|row| row.kind == "Namespace")
+            .map(|row| row.name.clone())
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,15 +1,22 @@
+use crate::error::Result as NvResult;
+use crate::k8s::sidecars::{self, is_sidecar};
+use crate::plugins::Target;
 use crate::tui::container_app;
 use crate::tui::data::{container_constraint_len_calculator, Container};
 use crate::tui::log_app;
+use crate::tui::marks::Scope;
+use crate::tui::open_menu::{self, context_vars, OpenMenu};
+use crate::tui::panes::{self, Session};
+use crate::tui::plugin_menu::{self, PluginMenu};
 use crate::tui::stream::Message;
 use crate::tui::style::{TableColors, ITEM_HEIGHT, PALETTES};
+use crate::tui::table_search::TableSearch;
 use crate::tui::table_ui::TuiTableState;
-use crate::tui::ui_loop::{AppBehavior, Apps};
+use crate::tui::ui_loop::{AppBehavior, Apps, Transition};
 use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
 use futures::{stream, Stream};
 use ratatui::prelude::*;
 use ratatui::widgets::{ScrollbarState, TableState};
-use std::io;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 
@@ -17,16 +24,36 @@ use std::sync::Arc;
 pub struct App {
     pub(crate) state: TableState,
     pub(crate) items: Vec<Container>,
+    /// every container, sidecars too
+    all: Vec<Container>,
+    /// sidecars left out of `items`
+    pub(crate) hidden: usize,
     pub(crate) longest_item_lens: (u16, u16, u16, u16, u16),
     pub(crate) scroll_state: ScrollbarState,
     pub(crate) colors: TableColors,
     color_index: usize,
     pub(crate) filter: String,
+    pub(crate) open_menu: Option<Box<OpenMenu>>,
+    pub(crate) plugin_menu: Option<Box<PluginMenu>>,
+    pub(crate) search: TableSearch,
 }
 
 impl TuiTableState for App {
     type Item = Container;
 
+    /// the pod of the containers, a pod without any is never marked
+    fn mark_scope(&self) -> Option<Scope> {
+        let container = self.all.first()?;
+        Some(Scope::Containers {
+            selector: container.selectors.clone()?,
+            pod: container.pod_name.clone(),
+        })
+    }
+
+    fn table_search(&mut self) -> Option<&mut TableSearch> {
+        Some(&mut self.search)
+    }
+
     fn get_items(&self) -> &[Self::Item] {
         &self.items
     }
@@ -86,72 +113,107 @@ impl TuiTableState for App {
 }
 
 impl AppBehavior for container_app::app::App {
-    async fn handle_event(&mut self, event: &Message) -> Result<Option<Apps>, io::Error> {
-        let mut app_holder = Some(Apps::Container { app: self.clone() });
+    const NAME: &'static str = "containers";
+
+    async fn handle_event(&mut self, event: &Message) -> NvResult<Transition> {
         match event {
-            Message::Key(Event::Key(key)) => {
-                if key.kind == KeyEventKind::Press {
-                    use KeyCode::{Char, Down, Enter, Esc, Up};
-                    match key.code {
-                        Char('q') | Esc => {
-                            app_holder = None;
-                        }
-                        Char('j') | Down => {
-                            self.next();
-                            //todo: stop all this cloning
-                            app_holder = Some(Apps::Container { app: self.clone() });
-                        }
-                        Char('k') | Up => {
-                            self.previous();
-                            app_holder = Some(Apps::Container { app: self.clone() });
-                        }
-                        Char('c' | 'C') => {
-                            self.next_color();
-                            app_holder = Some(Apps::Container { app: self.clone() });
+            Message::Key(Event::Key(key))
+                if key.kind == KeyEventKind::Press && self.open_menu.is_some() =>
+            {
+                open_menu::handle_key(&mut self.open_menu, key);
+            }
+            Message::Key(Event::Key(key))
+                if key.kind == KeyEventKind::Press && self.plugin_menu.is_some() =>
+            {
+                plugin_menu::handle_key(&mut self.plugin_menu, key).await;
+            }
+            Message::Key(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                use KeyCode::{Char, Down, Enter, Esc, Up};
+                if let Some(transition) = self.handle_mark(key) {
+                    return Ok(transition);
+                }
+                if self.handle_search(key) || self.handle_motion(key) {
+                    return Ok(Transition::Stay);
+                }
+                match key.code {
+                    Char('q') | Esc => return Ok(Transition::Pop),
+                    Char('j') | Down => self.next(),
+                    Char('k') | Up => self.previous(),
+                    Char('c' | 'C') => self.next_color(),
+                    Char('s') => {
+                        sidecars::toggle();
+                        self.show_rows();
+                    }
+                    Char('w' | 'W') => {
+                        if let Some(selection) = self.get_selected_item() {
+                            let pod = selection.pod_name.clone();
+                            let container = selection.name.clone();
+                            let mut vars = context_vars(None).await;
+                            vars.insert("pod", pod);
+                            vars.insert("container", container);
+                            self.open_menu = Some(Box::new(OpenMenu::new(&vars)));
                         }
-                        Char('f' | 'F') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                            self.page_forward();
+                    }
+                    Char('x' | 'l') => {
+                        if let Some(selection) = self.get_selected_item() {
+                            let mut vars = context_vars(None).await;
+                            vars.insert("pod", selection.pod_name.clone());
+                            vars.insert("container", selection.name.clone());
+                            let session = if key.code == Char('x') {
+                                Session::Exec
+                            } else {
+                                Session::Logs
+                            };
+                            panes::open(session, &vars).await?;
                         }
-                        Char('b' | 'B') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                            self.page_backward();
+                    }
+                    Char('V') => {
+                        if let Some(selection) = self.get_selected_item() {
+                            let name = format!("{}/{}", selection.pod_name, selection.name);
+                            let namespace = context_vars(None)
+                                .await
+                                .remove("namespace")
+                                .unwrap_or_default();
+                            self.plugin_menu = Some(Box::new(PluginMenu::new(Target {
+                                kind: "container".to_string(),
+                                namespace,
+                                name,
+                            })));
                         }
-                        Enter => {
-                            if let Some(selection) = self.get_selected_item() {
-                                if let Some(selectors) = selection.selectors.clone() {
-                                    let new_app_holder = Apps::Log {
-                                        app: log_app::app::App::new(
-                                            selectors,
-                                            selection.pod_name.clone(),
-                                            selection.name.clone(),
-                                        ),
-                                    };
-                                    app_holder = Some(new_app_holder);
-                                }
+                    }
+                    Char('f' | 'F') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.page_forward();
+                    }
+                    Char('b' | 'B') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.page_backward();
+                    }
+                    Enter => {
+                        if let Some(selection) = self.get_selected_item() {
+                            if let Some(selectors) = selection.selectors.clone() {
+                                return Ok(Transition::Push(Box::new(Apps::new(
+                                    log_app::app::App::new(
+                                        selectors,
+                                        selection.pod_name.clone(),
+                                        selection.name.clone(),
+                                    ),
+                                ))));
                             }
                         }
-
-                        _k => {}
                     }
+
+                    _k => {}
                 }
             }
             Message::Container(data_vec) => {
-                let new_app = Self {
-                    longest_item_lens: container_constraint_len_calculator(data_vec),
-                    scroll_state: ScrollbarState::new(
-                        data_vec.len().saturating_sub(1) * ITEM_HEIGHT,
-                    ),
-                    items: data_vec.clone(),
-                    ..self.clone()
-                };
-                let new_app_holder = Apps::Container { app: new_app };
-                app_holder = Some(new_app_holder);
+                self.all.clone_from(data_vec);
+                self.show_rows();
             }
             _ => {}
         }
-        Ok(app_holder)
+        Ok(Transition::Stay)
     }
     fn draw_ui<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<(), std::io::Error> {
-        terminal.draw(|f| container_app::ui::ui(f, &mut self.clone()))?;
+        terminal.draw(|f| container_app::ui::ui(f, self))?;
         Ok(())
     }
 
@@ -162,15 +224,33 @@ impl AppBehavior for container_app::app::App {
 
 impl App {
     pub fn new(data_vec: Vec<Container>) -> Self {
-        Self {
+        let mut app = Self {
             state: TableState::default().with_selected(0),
-            longest_item_lens: container_constraint_len_calculator(&data_vec),
-            scroll_state: ScrollbarState::new(data_vec.len().saturating_sub(1) * ITEM_HEIGHT),
+            longest_item_lens: (0, 0, 0, 0, 0),
+            scroll_state: ScrollbarState::new(0),
             colors: TableColors::new(&PALETTES[0]),
             color_index: 2,
-            items: data_vec,
+            items: vec![],
+            all: data_vec,
+            hidden: 0,
             filter: String::new(),
-        }
+            open_menu: None,
+            plugin_menu: None,
+            search: TableSearch::default(),
+        };
+        app.show_rows();
+        app
+    }
+
+    /// the containers to list, without sidecars unless they are shown
+    fn show_rows(&mut self) {
+        let (items, hidden) = sidecars::visible(&self.all, |container| {
+            is_sidecar(&container.name, &container.image)
+        });
+        self.longest_item_lens = container_constraint_len_calculator(&items);
+        self.scroll_state = ScrollbarState::new(items.len().saturating_sub(1) * ITEM_HEIGHT);
+        self.items = items;
+        self.hidden = hidden;
     }
 
     // pub fn get_event_details(&mut self) -> Vec<(String, String, Option<String>)> {
@@ -197,3 +277,12 @@ impl App {
         })
     }
 }
+
+/// what the first column's title says about hidden sidecars
+pub(crate) fn sidecar_note(hidden: usize) -> String {
+    match hidden {
+        0 => String::new(),
+        1 => "1 sidecar hidden, s shows".to_string(),
+        hidden => format!("{hidden} sidecars hidden, s shows"),
+    }
+}
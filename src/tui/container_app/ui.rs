@@ -1,12 +1,20 @@
-use crate::tui::container_app::app::App;
+use crate::tui::container_app::app::{sidecar_note, App};
+use crate::tui::error_card;
+use crate::tui::open_menu;
+use crate::tui::plugin_menu;
+use crate::tui::status_line;
 use crate::tui::table_ui::{render_detail_section, TuiTableState};
+use crate::tui::theme::symbols;
 use ratatui::{
     prelude::*,
     widgets::{Cell, HighlightSpacing, Row, Scrollbar, ScrollbarOrientation, Table},
 };
 
+const RESTARTS_COLUMN: usize = 2;
+
 pub fn ui(f: &mut Frame, app: &mut App) {
-    let rects = Layout::vertical([Constraint::Min(8), Constraint::Percentage(40)]).split(f.area());
+    let rects = Layout::vertical([Constraint::Min(8), Constraint::Percentage(40)])
+        .split(status_line::render(f));
 
     app.set_colors();
 
@@ -14,6 +22,15 @@ pub fn ui(f: &mut Frame, app: &mut App) {
     let details_area = rects[1];
 
     render_ui_sections(f, app, table_area, details_area);
+
+    if let Some(menu) = &app.open_menu {
+        open_menu::render(f, menu);
+    }
+    if let Some(menu) = &app.plugin_menu {
+        plugin_menu::render(f, menu);
+    }
+    status_line::render_activity(f);
+    error_card::render(f);
 }
 
 fn render_ui_sections(f: &mut Frame, app: &mut App, table_area: Rect, details_area: Rect) {
@@ -30,7 +47,14 @@ fn render_table(f: &mut Frame, app: &mut App, area: Rect) {
         .add_modifier(Modifier::REVERSED)
         .fg(app.colors.selected_style_fg);
 
-    let header = ["Container", "Description", "Restarts", "Image", "Ports"]
+    let note = sidecar_note(app.hidden);
+    let first = if note.is_empty() {
+        "Container".to_string()
+    } else {
+        format!("Container ({note})")
+    };
+    let first = app.search.header(first);
+    let header = [first.as_str(), "Description", "Restarts", "Image", "Ports"]
         .iter()
         .copied()
         .map(Cell::from)
@@ -49,12 +73,21 @@ fn render_table(f: &mut Frame, app: &mut App, area: Rect) {
             let item = data.ref_array();
             item.iter()
                 .copied()
-                .map(|content| Cell::from(Text::from(format!("\n{content}\n"))))
+                .enumerate()
+                .map(|(column, content)| match column {
+                    0 => Cell::from(app.search.cell(content.clone())),
+                    // recent restarts go under the absolute count
+                    RESTARTS_COLUMN if !data.restart_trend.is_empty() => {
+                        Cell::from(Text::from(format!("\n{content}\n{}", data.restart_trend)))
+                            .style(Style::new().fg(Color::LightRed))
+                    }
+                    _ => Cell::from(Text::from(format!("\n{content}\n"))),
+                })
                 .collect::<Row>()
                 .style(Style::new().fg(app.colors.row_fg).bg(color))
                 .height(3) //height
         });
-    let bar = " █ ";
+    let bar = format!(" {} ", symbols().bar);
     let t = Table::new(
         rows,
         [
@@ -0,0 +1,86 @@
+use crate::error::Result as NvResult;
+use crate::k8s::sidecars::{self, is_sidecar};
+use crate::tui::container_app::{self, app::sidecar_note};
+use crate::tui::data::{Container, ContainerGroup};
+use crate::tui::stream::Message;
+use crate::tui::table_app::{self, TableApp};
+use crate::tui::table_ui::TuiTableState;
+use crate::tui::ui_loop::{create_container_data_vec, AppBehavior, Apps, Transition};
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use futures::{stream, Stream};
+use ratatui::prelude::*;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+/// the containers of every pod of a replicaset, a row per container name
+#[derive(Clone, Debug)]
+pub struct App {
+    pub(crate) table: TableApp<ContainerGroup>,
+    /// every group, sidecars too
+    all: Vec<ContainerGroup>,
+}
+
+impl AppBehavior for App {
+    const NAME: &'static str = "replicaset containers";
+
+    async fn handle_event(&mut self, event: &Message) -> NvResult<Transition> {
+        match event {
+            Message::Key(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                // into the containers of the pod restarting most
+                if key.code == KeyCode::Enter && !self.table.show_filter_edit {
+                    let selection = self.table.get_selected_item();
+                    if let Some(((pod, _), selectors)) = selection
+                        .and_then(|group| Some((group.worst()?.clone(), group.selectors.clone()?)))
+                    {
+                        let data_vec = create_container_data_vec(selectors, pod).await?;
+                        return Ok(Transition::Push(Box::new(Apps::new(
+                            container_app::app::App::new(data_vec),
+                        ))));
+                    }
+                    return Ok(Transition::Stay);
+                }
+                if key.code == KeyCode::Char('s') && !self.table.show_filter_edit {
+                    sidecars::toggle();
+                    self.show_rows();
+                    return Ok(Transition::Stay);
+                }
+                return Ok(self.table.handle_key(key));
+            }
+            Message::Container(data_vec) => {
+                self.all = ContainerGroup::group(data_vec);
+                self.show_rows();
+            }
+            _ => {}
+        }
+        Ok(Transition::Stay)
+    }
+
+    fn draw_ui<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<(), std::io::Error> {
+        terminal.draw(|f| table_app::ui(f, &mut self.table))?;
+        Ok(())
+    }
+
+    fn stream(&self, _should_stop: Arc<AtomicBool>) -> impl Stream<Item = Message> {
+        stream::empty()
+    }
+}
+
+impl App {
+    pub fn new(data_vec: &[Container]) -> Self {
+        let mut app = Self {
+            table: TableApp::new(vec![]),
+            all: ContainerGroup::group(data_vec),
+        };
+        app.show_rows();
+        app
+    }
+
+    /// the groups to list, without sidecars unless they are shown
+    fn show_rows(&mut self) {
+        let (groups, hidden) = sidecars::visible(&self.all, |group| {
+            is_sidecar(&group.name, group.images.first().map_or("", String::as_str))
+        });
+        self.table.set_items(&groups);
+        self.table.note = sidecar_note(hidden);
+    }
+}
@@ -0,0 +1,117 @@
+//!A panic anywhere while the ui is up would leave the shell in raw mode on
+//!the alternate screen with the message drawn over the last frame.  The
+//!hook here puts the terminal back first, then says what happened and
+//!where the full report, the backtrace and a bug report bundle, went.
+//!
+use crate::bug_report;
+use crossterm::{
+    cursor::Show,
+    event::{DisableFocusChange, DisableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, LeaveAlternateScreen},
+};
+use std::backtrace::Backtrace;
+use std::fmt::Write as _;
+use std::io;
+use std::panic::{self, PanicHookInfo};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+use std::time::Duration;
+
+/// set while the terminal is in raw mode on the alternate screen
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Leave raw mode and the alternate screen, once, however often called.
+pub fn restore() {
+    if ACTIVE.swap(false, Ordering::SeqCst) {
+        _ = disable_raw_mode();
+        _ = execute!(
+            io::stdout(),
+            LeaveAlternateScreen,
+            DisableMouseCapture,
+            DisableFocusChange,
+            Show
+        );
+    }
+}
+
+/// Restores the terminal when dropped, on return and on unwinding alike.
+pub struct TerminalGuard;
+
+impl TerminalGuard {
+    /// to create right after the terminal was set up
+    pub fn new() -> Self {
+        install_hook();
+        ACTIVE.store(true, Ordering::SeqCst);
+        Self
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+fn message(info: &PanicHookInfo<'_>) -> String {
+    let payload = info.payload();
+    payload
+        .downcast_ref::<&str>()
+        .map(ToString::to_string)
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+fn report(message: &str, location: &str, backtrace: &Backtrace, bundle: &str) -> String {
+    let mut text = String::new();
+    _ = writeln!(text, "panicked at {location}: {message}\n");
+    _ = writeln!(text, "{bundle}");
+    _ = write!(text, "# backtrace\n{backtrace}");
+    text
+}
+
+/// A panic with the ui up restores the terminal, reports and exits, a
+/// panicked task would otherwise leave the ui drawing over a normal shell.
+fn install_hook() {
+    static INSTALLED: Once = Once::new();
+    INSTALLED.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !ACTIVE.load(Ordering::SeqCst) {
+                previous(info);
+                return;
+            }
+            restore();
+            let message = message(info);
+            let location = info
+                .location()
+                .map_or_else(String::new, ToString::to_string);
+            let bundle = bug_report::bundle_within(Duration::from_secs(2))
+                .unwrap_or_else(|| "bundle not collected in time".to_string());
+            let text = report(&message, &location, &Backtrace::force_capture(), &bundle);
+            eprintln!("navipod crashed: {message}");
+            match bug_report::write("crash", &text) {
+                Ok(path) => eprintln!("crash report: {}", path.display()),
+                Err(_) => eprintln!("{text}"),
+            }
+            std::process::exit(101);
+        }));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_has_location_and_bundle() {
+        let text = report(
+            "boom",
+            "src/tui/ui_loop.rs:1:1",
+            &Backtrace::disabled(),
+            "navipod 1.0",
+        );
+        assert!(text.starts_with("panicked at src/tui/ui_loop.rs:1:1: boom\n\nnavipod 1.0\n"));
+        assert!(text.contains("# backtrace"));
+    }
+}
@@ -1,3 +1,15 @@
+use crate::k8s::cache::memory::human;
+use crate::k8s::journal::Change;
+use crate::k8s::noisy::NoisyObject;
+use crate::k8s::probes::ProbeSpec;
+use crate::k8s::scheduling::{NodeInfo, NodeVerdict, Resources};
+use crate::tui::sort::Columns;
+use crate::tui::table_app::{TableConfig, TableRow};
+use crate::tui::theme::symbols;
+use crate::tui::utils::ansi::ColorRun;
+use crate::tui::utils::time::{timezone, EVENT_TIME_FORMAT};
+use chrono::{DateTime, Utc};
+use std::borrow::Cow;
 use std::collections::BTreeMap;
 use unicode_width::UnicodeWidthStr;
 
@@ -13,37 +25,90 @@ pub struct ResourceEvent {
     pub reason: String,
     pub type_: String,
     pub age: String,
+    /// when the event was last seen, in the configured timezone
+    pub time: String,
 }
 
 impl ResourceEvent {
-    pub(crate) const fn ref_array(&self) -> [&String; 5] {
-        [
-            &self.object,
-            &self.message,
-            &self.reason,
-            &self.type_,
-            &self.age,
-        ]
+    pub(crate) const fn ref_array(&self) -> [&String; 4] {
+        [&self.object, &self.message, &self.reason, &self.type_]
     }
+}
 
-    pub(crate) fn object(&self) -> &str {
-        &self.object
+impl TableRow for ResourceEvent {
+    const CONFIG: TableConfig = TableConfig {
+        headers: &["Resource", "Message", "Reason", "Type", "Age"],
+        filter_column: Some(1),
+        color_index: 3,
+    };
+
+    /// the time the event was last seen goes under its age
+    fn cells(&self) -> Vec<Cow<'_, str>> {
+        self.ref_array()
+            .into_iter()
+            .map(|cell| Cow::Borrowed(cell.as_str()))
+            .chain([Cow::Owned(format!("{}\n{}", self.age, self.time))])
+            .collect()
     }
+}
 
-    pub(crate) fn message(&self) -> &str {
-        &self.message
+impl Filterable for NoisyObject {
+    fn filter_by(&self) -> &str {
+        self.object.as_str()
     }
+}
+
+impl TableRow for NoisyObject {
+    const CONFIG: TableConfig = TableConfig {
+        headers: &[
+            "Object",
+            "Reason",
+            "Count",
+            "First Seen",
+            "Last Seen",
+            "Message",
+        ],
+        filter_column: Some(0),
+        color_index: 3,
+    };
 
-    pub(crate) fn reason(&self) -> &str {
-        &self.reason
+    fn cells(&self) -> Vec<Cow<'_, str>> {
+        let now = Utc::now();
+        vec![
+            Cow::Borrowed(self.object.as_str()),
+            Cow::Owned(format!("{}\n{}", self.reason, self.type_)),
+            Cow::Owned(self.count.to_string()),
+            Cow::Owned(Self::ago(self.first_seen, now)),
+            Cow::Owned(Self::ago(self.last_seen, now)),
+            Cow::Borrowed(self.message.as_str()),
+        ]
     }
+}
 
-    pub(crate) fn type_(&self) -> &str {
-        &self.type_
+impl Filterable for Change {
+    fn filter_by(&self) -> &str {
+        self.name.as_str()
     }
+}
 
-    pub(crate) fn age(&self) -> &str {
-        &self.age
+impl TableRow for Change {
+    const CONFIG: TableConfig = TableConfig {
+        headers: &["Object", "Change", "Resource Version", "When"],
+        filter_column: Some(0),
+        color_index: 1,
+    };
+
+    fn cells(&self) -> Vec<Cow<'_, str>> {
+        vec![
+            Cow::Owned(format!("{}/{}", self.kind, self.name)),
+            Cow::Owned(self.change.to_string()),
+            Cow::Borrowed(self.resource_version.as_str()),
+            Cow::Owned(format!(
+                "{}\n{}",
+                self.ago(Utc::now()),
+                timezone().format(&self.at, EVENT_TIME_FORMAT)
+            )),
+        ]
     }
 }
 
@@ -71,6 +136,24 @@ pub struct ContainerEnvVar {
     pub value: String,
 }
 
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct SecurityDetail {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct IdentityDetail {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct IngressDetail {
+    pub name: String,
+    pub value: String,
+}
+
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub struct Cert {
     pub host: String,
@@ -119,6 +202,48 @@ impl Detail for ContainerEnvVar {
     }
 }
 
+impl Detail for SecurityDetail {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn value(&self) -> String {
+        self.value.clone()
+    }
+
+    fn age(&self) -> Option<String> {
+        None
+    }
+}
+
+impl Detail for IdentityDetail {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn value(&self) -> String {
+        self.value.clone()
+    }
+
+    fn age(&self) -> Option<String> {
+        None
+    }
+}
+
+impl Detail for IngressDetail {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn value(&self) -> String {
+        self.value.clone()
+    }
+
+    fn age(&self) -> Option<String> {
+        None
+    }
+}
+
 impl Detail for ResourcceLabel {
     fn name(&self) -> String {
         self.name.clone()
@@ -154,21 +279,20 @@ impl Cert {
     pub(crate) const fn ref_array(&self) -> [&String; 4] {
         [&self.host, &self.is_valid, &self.expires, &self.issued_by]
     }
+}
 
-    pub(crate) fn host(&self) -> &str {
-        &self.host
-    }
-
-    pub(crate) fn is_valid(&self) -> &str {
-        &self.is_valid
-    }
-
-    pub(crate) fn expires(&self) -> &str {
-        &self.expires
-    }
+impl TableRow for Cert {
+    const CONFIG: TableConfig = TableConfig {
+        headers: &["Host", "Valid", "Expires", "Issued By"],
+        filter_column: None,
+        color_index: 1,
+    };
 
-    pub(crate) fn issued_by(&self) -> &str {
-        &self.issued_by
+    fn cells(&self) -> Vec<Cow<'_, str>> {
+        self.ref_array()
+            .into_iter()
+            .map(|cell| Cow::Borrowed(cell.as_str()))
+            .collect()
     }
 }
 
@@ -177,6 +301,8 @@ pub struct Container {
     pub name: String,
     pub description: String,
     pub restarts: String,
+    /// recent restarts, ie: "+3 in last 10m", empty if there were none
+    pub restart_trend: String,
     pub image: String,
     pub ports: String,
     pub envvars: Vec<ContainerEnvVar>,
@@ -223,15 +349,316 @@ impl Container {
     }
 }
 
+/// One container of a replicaset's pod template across all its pods, to
+/// see whether one replica misbehaves or all of them do.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContainerGroup {
+    pub name: String,
+    pub description: String,
+    /// restarts by pod, in pod name order
+    pub restarts: Vec<(String, u32)>,
+    /// pods with recent restarts
+    pub restarting: usize,
+    /// the distinct images, more than one while a rollout is half way
+    pub images: Vec<String>,
+    pub selectors: Option<BTreeMap<String, String>>,
+}
+
+/// the per pod restarts listed in full up to this many pods
+const LISTED_RESTARTS: usize = 12;
+
+impl ContainerGroup {
+    /// `containers` of many pods grouped by name and kind, in the order the
+    /// first pod lists them
+    #[must_use]
+    pub fn group(containers: &[Container]) -> Vec<Self> {
+        let mut groups: Vec<Self> = vec![];
+        for container in containers {
+            let index = groups
+                .iter()
+                .position(|g| g.name == container.name && g.description == container.description)
+                .unwrap_or_else(|| {
+                    groups.push(Self {
+                        name: container.name.clone(),
+                        description: container.description.clone(),
+                        restarts: vec![],
+                        restarting: 0,
+                        images: vec![],
+                        selectors: container.selectors.clone(),
+                    });
+                    groups.len() - 1
+                });
+            let group = &mut groups[index];
+            group.restarts.push((
+                container.pod_name.clone(),
+                container.restarts.parse().unwrap_or(0),
+            ));
+            if !container.restart_trend.is_empty() {
+                group.restarting += 1;
+            }
+            if !group.images.contains(&container.image) {
+                group.images.push(container.image.clone());
+            }
+        }
+        for group in &mut groups {
+            group.restarts.sort();
+        }
+        groups
+    }
+
+    #[must_use]
+    pub fn total_restarts(&self) -> u32 {
+        self.restarts.iter().map(|(_, restarts)| restarts).sum()
+    }
+
+    /// the pod restarting most, the first of a tie
+    #[must_use]
+    pub fn worst(&self) -> Option<&(String, u32)> {
+        self.restarts
+            .iter()
+            .reduce(|worst, pod| if pod.1 > worst.1 { pod } else { worst })
+    }
+
+    /// `0 0 7 0` for a few pods, `min 0 max 7` for many, and which pod
+    /// restarted most when they differ
+    fn distribution(&self) -> String {
+        let counts: Vec<u32> = self
+            .restarts
+            .iter()
+            .map(|(_, restarts)| *restarts)
+            .collect();
+        let (Some(min), Some(max)) = (counts.iter().min(), counts.iter().max()) else {
+            return String::new();
+        };
+        let spread = if counts.len() <= LISTED_RESTARTS {
+            counts
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        } else {
+            format!("min {min} max {max}")
+        };
+        match self.worst() {
+            Some((pod, _)) if min != max => format!("{spread}\nmost: {pod}"),
+            _ => spread,
+        }
+    }
+}
+
+impl Filterable for ContainerGroup {
+    fn filter_by(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl TableRow for ContainerGroup {
+    const CONFIG: TableConfig = TableConfig {
+        headers: &["Container", "Pods", "Restarts", "Per Pod", "Images"],
+        filter_column: Some(0),
+        color_index: 2,
+    };
+
+    fn cells(&self) -> Vec<Cow<'_, str>> {
+        let restarts = match self.restarting {
+            0 => self.total_restarts().to_string(),
+            pods => format!("{}\n{pods} restarting now", self.total_restarts()),
+        };
+        vec![
+            Cow::Owned(format!("{}\n{}", self.name, self.description)),
+            Cow::Owned(self.restarts.len().to_string()),
+            Cow::Owned(restarts),
+            Cow::Owned(self.distribution()),
+            Cow::Owned(self.images.join("\n")),
+        ]
+    }
+}
+
+/// What the pods on a node request against what it can give, the first row
+/// of the capacity view sums all nodes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeCapacity {
+    pub name: String,
+    pub description: String,
+    pub requested: Resources,
+    pub allocatable: Resources,
+    /// requests by namespace, the most cpu first
+    pub namespaces: Vec<(String, Resources)>,
+}
+
+/// cells of a saturation bar
+const BAR_WIDTH: usize = 10;
+/// namespaces named per row
+const LISTED_NAMESPACES: usize = 3;
+
+/// `███░░░░░░░  30%`, a full bar for anything overcommitted
+#[must_use]
+pub fn saturation_bar(used: f64, total: f64) -> String {
+    let ratio = if total > 0.0 { used / total } else { 0.0 };
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let filled = ((ratio * BAR_WIDTH as f64).round() as usize).min(BAR_WIDTH);
+    #[allow(clippy::cast_possible_truncation)]
+    let percent = (ratio * 100.0).round() as i64;
+    format!(
+        "{}{} {percent:>3}%",
+        symbols().gauge_full.repeat(filled),
+        symbols().gauge_empty.repeat(BAR_WIDTH - filled)
+    )
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn bytes(memory: f64) -> String {
+    human(memory.max(0.0) as usize)
+}
+
+impl NodeCapacity {
+    /// a row per node, the most saturated first, below one for all of them
+    #[must_use]
+    pub fn from_nodes(nodes: &[NodeInfo]) -> Vec<Self> {
+        let mut rows: Vec<Self> = nodes
+            .iter()
+            .map(|node| {
+                let mut description = vec![];
+                if node.unschedulable {
+                    description.push("cordoned".to_string());
+                }
+                if !node.taints.is_empty() {
+                    description.push(format!("{} taints", node.taints.len()));
+                }
+                Self {
+                    name: node.name.clone(),
+                    description: description.join(", "),
+                    requested: node.requested,
+                    allocatable: node.allocatable,
+                    namespaces: by_cpu(node.namespaces.clone()),
+                }
+            })
+            .collect();
+        rows.sort_by(|a, b| b.saturation().total_cmp(&a.saturation()));
+
+        let mut requested = Resources::default();
+        let mut allocatable = Resources::default();
+        let mut namespaces: BTreeMap<String, Resources> = BTreeMap::new();
+        for node in nodes {
+            requested.add(&node.requested);
+            allocatable.add(&node.allocatable);
+            for (namespace, r) in &node.namespaces {
+                namespaces.entry(namespace.clone()).or_default().add(r);
+            }
+        }
+        rows.insert(
+            0,
+            Self {
+                name: "all nodes".to_string(),
+                description: format!("{} nodes", nodes.len()),
+                requested,
+                allocatable,
+                namespaces: by_cpu(namespaces),
+            },
+        );
+        rows
+    }
+
+    /// the fullest of cpu, memory and pod slots, as a fraction
+    #[must_use]
+    pub fn saturation(&self) -> f64 {
+        let ratio = |used: f64, total: f64| if total > 0.0 { used / total } else { 0.0 };
+        ratio(self.requested.cpu_millis, self.allocatable.cpu_millis)
+            .max(ratio(
+                self.requested.memory_bytes,
+                self.allocatable.memory_bytes,
+            ))
+            .max(ratio(self.requested.pods, self.allocatable.pods))
+    }
+
+    fn top_namespaces(&self) -> String {
+        self.namespaces
+            .iter()
+            .take(LISTED_NAMESPACES)
+            .map(|(namespace, r)| {
+                format!(
+                    "{namespace} {:.1} cpu {}",
+                    r.cpu_millis / 1000.0,
+                    bytes(r.memory_bytes)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+fn by_cpu(namespaces: BTreeMap<String, Resources>) -> Vec<(String, Resources)> {
+    let mut namespaces: Vec<(String, Resources)> = namespaces.into_iter().collect();
+    namespaces.sort_by(|a, b| b.1.cpu_millis.total_cmp(&a.1.cpu_millis));
+    namespaces
+}
+
+impl Filterable for NodeCapacity {
+    fn filter_by(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl TableRow for NodeCapacity {
+    const CONFIG: TableConfig = TableConfig {
+        headers: &["Node", "Cpu", "Memory", "Pods", "Top Namespaces"],
+        filter_column: Some(0),
+        color_index: 1,
+    };
+
+    fn cells(&self) -> Vec<Cow<'_, str>> {
+        let (requested, allocatable) = (&self.requested, &self.allocatable);
+        let name = if self.description.is_empty() {
+            Cow::Borrowed(self.name.as_str())
+        } else {
+            Cow::Owned(format!("{} ({})", self.name, self.description))
+        };
+        vec![
+            name,
+            Cow::Owned(format!(
+                "{} {:.1}/{:.1}",
+                saturation_bar(requested.cpu_millis, allocatable.cpu_millis),
+                requested.cpu_millis / 1000.0,
+                allocatable.cpu_millis / 1000.0
+            )),
+            Cow::Owned(format!(
+                "{} {}/{}",
+                saturation_bar(requested.memory_bytes, allocatable.memory_bytes),
+                bytes(requested.memory_bytes),
+                bytes(allocatable.memory_bytes)
+            )),
+            Cow::Owned(format!(
+                "{} {}/{}",
+                saturation_bar(requested.pods, allocatable.pods),
+                requested.pods,
+                allocatable.pods
+            )),
+            Cow::Owned(self.top_namespaces()),
+        ]
+    }
+}
+
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub struct RsPod {
     pub name: String,
+    pub namespace: String,
     pub status: String,
     pub description: String,
     pub age: String,
     pub containers: String,
     pub selectors: Option<BTreeMap<String, String>>,
     pub events: Vec<ResourceEvent>,
+    pub security: Vec<SecurityDetail>,
+    pub identity: Vec<IdentityDetail>,
+    pub probes: Vec<ProbeSpec>,
+    /// why no node fits, only for pods waiting to be scheduled
+    pub scheduling: Vec<NodeVerdict>,
+    /// values of the custom columns configured for the pod view
+    pub extra: Vec<String>,
+    pub node: String,
+    /// name of the owning replicaset or other controller
+    pub owner: String,
+    pub images: Vec<String>,
 }
 
 impl Filterable for RsPod {
@@ -240,6 +667,16 @@ impl Filterable for RsPod {
     }
 }
 
+impl Columns for RsPod {
+    fn columns(&self) -> Vec<&str> {
+        self.ref_array()
+            .into_iter()
+            .chain(&self.extra)
+            .map(String::as_str)
+            .collect()
+    }
+}
+
 impl RsPod {
     pub(crate) const fn ref_array(&self) -> [&String; 5] {
         [
@@ -279,8 +716,12 @@ pub struct Rs {
     pub description: String,
     pub age: String,
     pub pods: String,
+    /// badges of the deprecated api versions the replicaset was written with
+    pub deprecations: String,
     pub selectors: Option<BTreeMap<String, String>>,
     pub events: Vec<ResourceEvent>,
+    /// values of the custom columns configured for the rs view
+    pub extra: Vec<String>,
 }
 
 impl Filterable for Rs {
@@ -289,14 +730,25 @@ impl Filterable for Rs {
     }
 }
 
+impl Columns for Rs {
+    fn columns(&self) -> Vec<&str> {
+        self.ref_array()
+            .into_iter()
+            .chain(&self.extra)
+            .map(String::as_str)
+            .collect()
+    }
+}
+
 impl Rs {
-    pub(crate) const fn ref_array(&self) -> [&String; 5] {
+    pub(crate) const fn ref_array(&self) -> [&String; 6] {
         [
             &self.name,
             &self.pods,
             &self.age,
             &self.description,
             &self.owner,
+            &self.deprecations,
         ]
     }
 
@@ -319,13 +771,23 @@ impl Rs {
     pub(crate) fn pods(&self) -> &str {
         &self.pods
     }
+
+    pub(crate) fn deprecations(&self) -> &str {
+        &self.deprecations
+    }
 }
 
-#[derive(Eq, PartialEq, Clone, Debug)]
+#[derive(Eq, PartialEq, Clone, Debug, Default)]
 pub struct LogRec {
     pub datetime: String,
+    /// when the kubelet received the line, shown in the view's timezone
+    pub timestamp: Option<DateTime<Utc>>,
     pub level: String,
     pub message: String,
+    /// foreground colors the container wrote as ANSI escapes
+    pub colors: Vec<ColorRun>,
+    /// `Normal` or `Warning` when this is a pod event interleaved with the logs
+    pub event_type: Option<String>,
 }
 
 impl Filterable for LogRec {
@@ -335,10 +797,6 @@ impl Filterable for LogRec {
 }
 
 impl LogRec {
-    pub(crate) const fn ref_array(&self) -> [&String; 3] {
-        [&self.datetime, &self.level, &self.message]
-    }
-
     pub(crate) fn datetime(&self) -> &str {
         &self.datetime
     }
@@ -352,6 +810,232 @@ impl LogRec {
     }
 }
 
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Diagnostic {
+    pub area: String,
+    pub name: String,
+    pub value: String,
+}
+
+impl Filterable for Diagnostic {
+    fn filter_by(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl Diagnostic {
+    pub(crate) const fn ref_array(&self) -> [&String; 3] {
+        [&self.area, &self.name, &self.value]
+    }
+}
+
+impl TableRow for Diagnostic {
+    const CONFIG: TableConfig = TableConfig {
+        headers: &["Area", "Metric", "Value"],
+        filter_column: Some(1),
+        color_index: 2,
+    };
+
+    fn cells(&self) -> Vec<Cow<'_, str>> {
+        self.ref_array()
+            .into_iter()
+            .map(|cell| Cow::Borrowed(cell.as_str()))
+            .collect()
+    }
+}
+
+/// A Gateway, one of its listeners or an HTTPRoute.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GatewayRow {
+    pub kind: String,
+    pub name: String,
+    /// listener hostname, route hostnames or the gateway's addresses
+    pub hosts: String,
+    /// the class of a gateway, protocol, port and attached routes of a
+    /// listener, the parents of a route
+    pub attached: String,
+    pub backends: String,
+    pub status: String,
+}
+
+impl Filterable for GatewayRow {
+    fn filter_by(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl GatewayRow {
+    pub(crate) const fn ref_array(&self) -> [&String; 6] {
+        [
+            &self.kind,
+            &self.name,
+            &self.hosts,
+            &self.attached,
+            &self.backends,
+            &self.status,
+        ]
+    }
+}
+
+impl TableRow for GatewayRow {
+    const CONFIG: TableConfig = TableConfig {
+        headers: &["Kind", "Name", "Hosts", "Attached", "Backends", "Status"],
+        filter_column: Some(1),
+        color_index: 3,
+    };
+
+    fn cells(&self) -> Vec<Cow<'_, str>> {
+        self.ref_array()
+            .into_iter()
+            .map(|cell| Cow::Borrowed(cell.as_str()))
+            .collect()
+    }
+}
+
+/// a Deployment of the namespace, Enter lists the replicasets it owns
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeploymentRow {
+    pub name: String,
+    /// ready of the desired replicas, ie: 2/3
+    pub ready: String,
+    pub up_to_date: String,
+    pub available: String,
+    pub age: String,
+    pub strategy: String,
+    pub selectors: Option<BTreeMap<String, String>>,
+}
+
+impl Filterable for DeploymentRow {
+    fn filter_by(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl DeploymentRow {
+    pub(crate) const fn ref_array(&self) -> [&String; 6] {
+        [
+            &self.name,
+            &self.ready,
+            &self.up_to_date,
+            &self.available,
+            &self.age,
+            &self.strategy,
+        ]
+    }
+}
+
+impl TableRow for DeploymentRow {
+    const CONFIG: TableConfig = TableConfig {
+        headers: &[
+            "Deployment",
+            "Ready",
+            "Up To Date",
+            "Available",
+            "Age",
+            "Strategy",
+        ],
+        filter_column: Some(0),
+        color_index: 0,
+    };
+
+    fn cells(&self) -> Vec<Cow<'_, str>> {
+        self.ref_array()
+            .into_iter()
+            .map(|cell| Cow::Borrowed(cell.as_str()))
+            .collect()
+    }
+}
+
+/// a StatefulSet of the namespace, Enter lists its pods
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatefulSetRow {
+    pub name: String,
+    /// ready of the desired replicas, ie: 2/3
+    pub ready: String,
+    pub age: String,
+    /// the headless service giving the pods their names
+    pub service: String,
+    pub update_strategy: String,
+    pub selectors: Option<BTreeMap<String, String>>,
+}
+
+impl Filterable for StatefulSetRow {
+    fn filter_by(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl StatefulSetRow {
+    pub(crate) const fn ref_array(&self) -> [&String; 5] {
+        [
+            &self.name,
+            &self.ready,
+            &self.age,
+            &self.service,
+            &self.update_strategy,
+        ]
+    }
+}
+
+impl TableRow for StatefulSetRow {
+    const CONFIG: TableConfig = TableConfig {
+        headers: &["StatefulSet", "Ready", "Age", "Service", "Update Strategy"],
+        filter_column: Some(0),
+        color_index: 0,
+    };
+
+    fn cells(&self) -> Vec<Cow<'_, str>> {
+        self.ref_array()
+            .into_iter()
+            .map(|cell| Cow::Borrowed(cell.as_str()))
+            .collect()
+    }
+}
+
+/// a cluster scoped object: namespace, persistent volume, storage class or CRD
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClusterRow {
+    pub kind: String,
+    pub name: String,
+    /// namespace or volume phase, established or not, the default class
+    pub status: String,
+    pub detail: String,
+    pub age: String,
+}
+
+impl Filterable for ClusterRow {
+    fn filter_by(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl ClusterRow {
+    pub(crate) const fn ref_array(&self) -> [&String; 5] {
+        [
+            &self.kind,
+            &self.name,
+            &self.status,
+            &self.detail,
+            &self.age,
+        ]
+    }
+}
+
+impl TableRow for ClusterRow {
+    const CONFIG: TableConfig = TableConfig {
+        headers: &["Kind", "Name", "Status", "Details", "Age"],
+        filter_column: Some(1),
+        color_index: 2,
+    };
+
+    fn cells(&self) -> Vec<Cow<'_, str>> {
+        self.ref_array()
+            .into_iter()
+            .map(|cell| Cow::Borrowed(cell.as_str()))
+            .collect()
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Ingress {
     pub name: String,
@@ -359,6 +1043,10 @@ pub struct Ingress {
     pub path: String,
     pub backend_svc: String,
     pub port: String,
+    /// the ingress class, which controller serves it
+    pub class: String,
+    /// class, tls check and controller annotations, for the detail pane
+    pub details: Vec<IngressDetail>,
 }
 
 impl Filterable for Ingress {
@@ -368,34 +1056,71 @@ impl Filterable for Ingress {
 }
 
 impl Ingress {
-    pub(crate) const fn ref_array(&self) -> [&String; 5] {
+    pub(crate) const fn ref_array(&self) -> [&String; 6] {
         [
             &self.name,
+            &self.class,
             &self.host,
             &self.path,
             &self.backend_svc,
             &self.port,
         ]
     }
+}
 
-    pub(crate) fn name(&self) -> &str {
-        &self.name
+impl TableRow for Ingress {
+    const CONFIG: TableConfig = TableConfig {
+        headers: &["Ingress", "Class", "Host", "Path", "Service", "Port"],
+        filter_column: None,
+        color_index: 3,
+    };
+
+    fn cells(&self) -> Vec<Cow<'_, str>> {
+        self.ref_array()
+            .into_iter()
+            .map(|cell| Cow::Borrowed(cell.as_str()))
+            .collect()
     }
+}
 
-    pub(crate) fn host(&self) -> &str {
-        &self.host
+/// a row of a plugin's table view, filtered by its first cell
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct PluginRow {
+    pub cells: Vec<String>,
+}
+
+impl PluginRow {
+    #[must_use]
+    pub const fn new(cells: Vec<String>) -> Self {
+        Self { cells }
     }
+}
 
-    pub(crate) fn path(&self) -> &str {
-        &self.path
+impl Filterable for PluginRow {
+    fn filter_by(&self) -> &str {
+        self.cells.first().map_or("", String::as_str)
     }
+}
 
-    pub(crate) fn backend_svc(&self) -> &str {
-        &self.backend_svc
+impl Columns for PluginRow {
+    fn columns(&self) -> Vec<&str> {
+        self.cells.iter().map(String::as_str).collect()
     }
+}
+
+impl TableRow for PluginRow {
+    /// the columns come from the plugin's manifest
+    const CONFIG: TableConfig = TableConfig {
+        headers: &[],
+        filter_column: Some(0),
+        color_index: 0,
+    };
 
-    pub(crate) fn port(&self) -> &str {
-        &self.port
+    fn cells(&self) -> Vec<Cow<'_, str>> {
+        self.cells
+            .iter()
+            .map(|cell| Cow::Borrowed(cell.as_str()))
+            .collect()
     }
 }
 
@@ -421,96 +1146,9 @@ pub fn log_constraint_len_calculator(items: &[LogRec]) -> (u16, u16, u16) {
         .unwrap_or(0);
     (datetime_len as u16, level_len as u16, message_len as u16)
 }
-// pub resource_name: String,
-// pub message: String,
-// pub reason: String,
-// pub type_: String,
-// pub age: String,
-
-#[allow(clippy::cast_possible_truncation)]
-pub fn event_constraint_len_calculator(items: &[ResourceEvent]) -> (u16, u16, u16, u16, u16) {
-    let object_len = items
-        .iter()
-        .map(ResourceEvent::object)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let message_len = items
-        .iter()
-        .map(ResourceEvent::message)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let reason_len = items
-        .iter()
-        .map(ResourceEvent::reason)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let type_len = items
-        .iter()
-        .map(ResourceEvent::type_)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let age_len = items
-        .iter()
-        .map(ResourceEvent::age)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    (
-        object_len as u16,
-        message_len as u16,
-        reason_len as u16,
-        type_len as u16,
-        age_len as u16,
-    )
-}
-
-#[allow(clippy::cast_possible_truncation)]
-pub fn ingress_constraint_len_calculator(items: &[Ingress]) -> (u16, u16, u16, u16, u16) {
-    let name_len = items
-        .iter()
-        .map(Ingress::name)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let host_len = items
-        .iter()
-        .map(Ingress::host)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let path_len = items
-        .iter()
-        .map(Ingress::path)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let backend_svc_len = items
-        .iter()
-        .map(Ingress::backend_svc)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let port_len = items
-        .iter()
-        .map(Ingress::port)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    (
-        name_len as u16,
-        host_len as u16,
-        path_len as u16,
-        backend_svc_len as u16,
-        port_len as u16,
-    )
-}
 
 #[allow(clippy::cast_possible_truncation)]
-pub fn rs_constraint_len_calculator(items: &[Rs]) -> (u16, u16, u16, u16, u16) {
+pub fn rs_constraint_len_calculator(items: &[Rs]) -> (u16, u16, u16, u16, u16, u16) {
     let name_len = items
         .iter()
         .map(Rs::name)
@@ -542,6 +1180,12 @@ pub fn rs_constraint_len_calculator(items: &[Rs]) -> (u16, u16, u16, u16, u16) {
         .map(UnicodeWidthStr::width)
         .max()
         .unwrap_or(0);
+    let deprecations_len = items
+        .iter()
+        .map(Rs::deprecations)
+        .map(UnicodeWidthStr::width)
+        .max()
+        .unwrap_or(0);
 
     (
         name_len as u16,
@@ -549,6 +1193,7 @@ pub fn rs_constraint_len_calculator(items: &[Rs]) -> (u16, u16, u16, u16, u16) {
         age_len as u16,
         description_len as u16,
         owner_len as u16,
+        deprecations_len as u16,
     )
 }
 
@@ -595,41 +1240,6 @@ pub fn pod_constraint_len_calculator(items: &[RsPod]) -> (u16, u16, u16, u16, u1
     )
 }
 
-#[allow(clippy::cast_possible_truncation)]
-pub fn cert_constraint_len_calculator(items: &[Cert]) -> (u16, u16, u16, u16) {
-    let host_len = items
-        .iter()
-        .map(Cert::host)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let valid_len = items
-        .iter()
-        .map(Cert::is_valid)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let expires_len = items
-        .iter()
-        .map(Cert::expires)
-        .flat_map(str::lines)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let issued_by_len = items
-        .iter()
-        .map(Cert::issued_by)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    (
-        host_len as u16,
-        valid_len as u16,
-        expires_len as u16,
-        issued_by_len as u16,
-    )
-}
-
 #[allow(clippy::cast_possible_truncation)]
 pub fn container_constraint_len_calculator(items: &[Container]) -> (u16, u16, u16, u16, u16) {
     let name_len = items
@@ -647,7 +1257,7 @@ pub fn container_constraint_len_calculator(items: &[Container]) -> (u16, u16, u1
         .unwrap_or(0);
     let restarts_len = items
         .iter()
-        .map(Container::restarts)
+        .flat_map(|c| [c.restarts(), c.restart_trend.as_str()])
         .flat_map(str::lines)
         .map(UnicodeWidthStr::width)
         .max()
@@ -678,11 +1288,76 @@ pub fn container_constraint_len_calculator(items: &[Container]) -> (u16, u16, u1
 
 #[cfg(test)]
 mod tests {
+    use crate::k8s::scheduling::{NodeInfo, Resources};
     use crate::tui::data::{
         container_constraint_len_calculator, pod_constraint_len_calculator,
-        rs_constraint_len_calculator, Container, Rs, RsPod,
+        rs_constraint_len_calculator, saturation_bar, Container, ContainerGroup, NodeCapacity, Rs,
+        RsPod,
     };
 
+    fn container(pod: &str, name: &str, restarts: u32, image: &str) -> Container {
+        Container {
+            name: name.to_string(),
+            description: "a pod container".to_string(),
+            restarts: restarts.to_string(),
+            restart_trend: String::new(),
+            image: image.to_string(),
+            ports: String::new(),
+            envvars: vec![],
+            mounts: vec![],
+            selectors: None,
+            pod_name: pod.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_container_groups() {
+        let groups = ContainerGroup::group(&[
+            container("web-b", "web", 7, "web:2"),
+            container("web-b", "proxy", 0, "envoy:1"),
+            container("web-a", "web", 0, "web:1"),
+            container("web-a", "proxy", 0, "envoy:1"),
+        ]);
+        assert_eq!(groups.len(), 2);
+        let web = &groups[0];
+        assert_eq!(web.name, "web");
+        assert_eq!(web.total_restarts(), 7);
+        assert_eq!(web.worst(), Some(&("web-b".to_string(), 7)));
+        assert_eq!(web.images, vec!["web:2", "web:1"]);
+        assert_eq!(web.distribution(), "0 7\nmost: web-b");
+        assert_eq!(groups[1].distribution(), "0 0");
+    }
+
+    #[test]
+    fn test_node_capacity() {
+        assert_eq!(saturation_bar(1.0, 4.0), "███░░░░░░░  25%");
+        assert_eq!(saturation_bar(6.0, 4.0), "██████████ 150%");
+        assert_eq!(saturation_bar(1.0, 0.0), "░░░░░░░░░░   0%");
+
+        let resources = |cpu_millis, pods| Resources {
+            cpu_millis,
+            memory_bytes: 0.0,
+            pods,
+        };
+        let node = |name: &str, cpu_millis, namespace: &str| NodeInfo {
+            name: name.to_string(),
+            allocatable: resources(4000.0, 110.0),
+            requested: resources(cpu_millis, 2.0),
+            namespaces: [(namespace.to_string(), resources(cpu_millis, 2.0))].into(),
+            ..NodeInfo::default()
+        };
+        let rows =
+            NodeCapacity::from_nodes(&[node("a", 1000.0, "web"), node("b", 3000.0, "batch")]);
+        let names: Vec<&str> = rows.iter().map(|row| row.name.as_str()).collect();
+        assert_eq!(names, ["all nodes", "b", "a"]);
+        assert_eq!(rows[0].requested, resources(4000.0, 4.0));
+        assert!((rows[0].saturation() - 0.5).abs() < f64::EPSILON);
+        assert_eq!(
+            rows[0].top_namespaces(),
+            "batch 3.0 cpu 0 B, web 1.0 cpu 0 B"
+        );
+    }
+
     #[test]
     fn test_container_constraint_len_calculator() {
         let test_data = vec![
@@ -690,6 +1365,7 @@ mod tests {
                 name: "replica-123456-123456".to_string(),
                 description: "Deployment".to_string(),
                 restarts: "0".to_string(),
+                restart_trend: String::new(),
                 image: "navicore/echo-secret-py:v0.1.1".to_string(),
                 ports: "http:1234".to_string(),
                 envvars: vec![],
@@ -701,6 +1377,7 @@ mod tests {
                 name: "replica-923450-987654".to_string(),
                 description: "Deployment".to_string(),
                 restarts: "0".to_string(),
+                restart_trend: String::new(),
                 image: "navicore/echo-secret-py:v0.1.1".to_string(),
                 ports: "http:1234".to_string(),
                 envvars: vec![],
@@ -728,21 +1405,39 @@ mod tests {
         let test_data = vec![
             RsPod {
                 name: "replica-123456-123456".to_string(),
+                namespace: "default".to_string(),
                 status: "Running".to_string(),
                 description: "Deployment".to_string(),
                 age: "150d".to_string(),
                 containers: "2/2".to_string(),
                 selectors: None,
                 events: vec![],
+                security: vec![],
+                identity: vec![],
+                probes: vec![],
+                scheduling: vec![],
+                extra: vec![],
+                node: String::new(),
+                owner: String::new(),
+                images: vec![],
             },
             RsPod {
                 name: "replica-923450-987654".to_string(),
+                namespace: "default".to_string(),
                 status: "Terminating".to_string(),
                 description: "Deployment".to_string(),
                 age: "10d".to_string(),
                 containers: "2/2".to_string(),
                 selectors: None,
                 events: vec![],
+                security: vec![],
+                identity: vec![],
+                probes: vec![],
+                scheduling: vec![],
+                extra: vec![],
+                node: String::new(),
+                owner: String::new(),
+                images: vec![],
             },
         ];
         let (
@@ -768,8 +1463,10 @@ mod tests {
                 description: "Deployment".to_string(),
                 age: "300d".to_string(),
                 pods: "10/10".to_string(),
+                deprecations: String::new(),
                 selectors: None,
                 events: vec![],
+                extra: vec![],
             },
             Rs {
                 name: "my-replica-923450".to_string(),
@@ -777,8 +1474,10 @@ mod tests {
                 description: "Deployment".to_string(),
                 age: "10d".to_string(),
                 pods: "1/1".to_string(),
+                deprecations: "[deprecated apps/v1beta2]".to_string(),
                 selectors: None,
                 events: vec![],
+                extra: vec![],
             },
         ];
         let (
@@ -787,6 +1486,7 @@ mod tests {
             longest_age_len,
             longest_description_len,
             longest_owner_len,
+            longest_deprecations_len,
         ) = rs_constraint_len_calculator(&test_data);
 
         assert_eq!(17, longest_name_len);
@@ -794,5 +1494,6 @@ mod tests {
         assert_eq!(10, longest_description_len);
         assert_eq!(4, longest_age_len);
         assert_eq!(5, longest_pods_len);
+        assert_eq!(25, longest_deprecations_len);
     }
 }
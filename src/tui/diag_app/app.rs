@@ -0,0 +1,186 @@
+use crate::error::Result as NvResult;
+use crate::k8s::cache::cache_manager;
+use crate::k8s::cache::memory::human;
+use crate::k8s::cache::throttle;
+use crate::k8s::client_manager;
+use crate::k8s::retry::retries;
+use crate::k8s::timeout::{recent_timeout, request_timeout};
+use crate::k8s::watch_manager;
+use crate::perf;
+use crate::tui::data::Diagnostic;
+use crate::tui::stream::Message;
+use crate::tui::table_app::{self, TableApp};
+use crate::tui::ui_loop::{AppBehavior, Transition};
+use crossterm::event::{Event, KeyEventKind};
+use futures::Stream;
+use ratatui::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+use tokio_stream::wrappers::ReceiverStream;
+
+const POLL_MS: u64 = 1000;
+
+fn diag(area: &str, name: &str, value: String) -> Diagnostic {
+    Diagnostic {
+        area: area.to_string(),
+        name: name.to_string(),
+        value,
+    }
+}
+
+/// Snapshot of navipod's own health: clients, cache, api timeouts and what
+/// drawing and fetching cost.
+#[must_use]
+pub fn collect() -> Vec<Diagnostic> {
+    let clients = client_manager::stats();
+    let cache = cache_manager::get_cache();
+    let perf = perf::snapshot();
+    let throttle = throttle::stats();
+    let mut rows = vec![
+        diag("client", "context", client_manager::current_context()),
+        diag("client", "built", clients.built.to_string()),
+        diag("client", "rebuilds", clients.rebuilds.to_string()),
+        diag("client", "warmups", clients.warmups.to_string()),
+        diag("client", "reused", clients.reused.to_string()),
+        diag(
+            "client",
+            "token refreshes",
+            clients.token_refreshes.to_string(),
+        ),
+        diag("client", "auth retries", clients.auth_retries.to_string()),
+        diag("api", "retries", retries().to_string()),
+        diag("cache", "entries", cache.len().to_string()),
+        diag(
+            "cache",
+            "estimated size",
+            format!(
+                "{} of {}",
+                human(cache.usage().iter().map(|(_, bytes)| bytes).sum()),
+                human(cache.max_bytes())
+            ),
+        ),
+        diag(
+            "cache",
+            "drill-down hit rate",
+            cache_manager::hit_rate()
+                .map_or_else(|| "n/a".to_string(), |rate| format!("{:.0}%", rate * 100.0)),
+        ),
+        diag(
+            "api",
+            "request timeout",
+            format!("{}s", request_timeout().as_secs()),
+        ),
+        diag(
+            "api",
+            "last timeout",
+            recent_timeout().unwrap_or_else(|| "none".to_string()),
+        ),
+        diag(
+            "perf",
+            "frame time",
+            format!(
+                "p50 {}ms p95 {}ms of {} frames",
+                perf.frames.p50.as_millis(),
+                perf.frames.p95.as_millis(),
+                perf.frames.count
+            ),
+        ),
+        diag(
+            "perf",
+            "slowest api call",
+            perf.slowest_fetch().map_or_else(
+                || "none yet".to_string(),
+                |(operation, timing)| {
+                    format!(
+                        "{operation} p95 {}ms of {}",
+                        timing.p95.as_millis(),
+                        timing.count
+                    )
+                },
+            ),
+        ),
+        diag("perf", "watch restarts", perf.watch_restarts.to_string()),
+        diag("throttle", "state", throttle.state.to_string()),
+        diag(
+            "throttle",
+            "prefetch batch",
+            match throttle.divisor {
+                1 => "full".to_string(),
+                divisor => format!("1/{divisor}"),
+            },
+        ),
+        diag("throttle", "trips", throttle.trips.to_string()),
+    ];
+    for (namespace, stats) in watch_manager::manager().stats() {
+        rows.push(diag(
+            "watch",
+            &namespace,
+            format!(
+                "{}/{} streaming, {} updates, {} restarts, backoff {}s",
+                stats.health.streaming,
+                stats.health.running,
+                stats.updates,
+                stats.restarts,
+                stats.backoff.as_secs()
+            ),
+        ));
+    }
+    rows
+}
+
+#[derive(Clone, Debug)]
+pub struct App {
+    pub(crate) table: TableApp<Diagnostic>,
+}
+
+impl AppBehavior for App {
+    const NAME: &'static str = "diagnostics";
+
+    async fn handle_event(&mut self, event: &Message) -> NvResult<Transition> {
+        match event {
+            Message::Key(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                return Ok(self.table.handle_key(key));
+            }
+            Message::Diag(data_vec) => self.table.set_items(data_vec),
+            _ => {}
+        }
+        Ok(Transition::Stay)
+    }
+
+    fn draw_ui<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<(), std::io::Error> {
+        terminal.draw(|f| table_app::ui(f, &mut self.table))?;
+        Ok(())
+    }
+
+    fn stream(&self, should_stop: Arc<AtomicBool>) -> impl Stream<Item = Message> {
+        let (tx, rx) = mpsc::channel(100);
+
+        tokio::spawn(async move {
+            while !should_stop.load(Ordering::Relaxed) {
+                if tx.send(Message::Diag(collect())).await.is_err() {
+                    break;
+                }
+                sleep(Duration::from_millis(POLL_MS)).await;
+            }
+        });
+
+        ReceiverStream::new(rx)
+    }
+}
+
+impl App {
+    pub fn new() -> Self {
+        Self {
+            table: TableApp::new(collect()),
+        }
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
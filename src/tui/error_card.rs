@@ -0,0 +1,67 @@
+//!A card drawn over the current view when an action fails, instead of
+//!dropping the user out of the UI.  Any key dismisses it.
+//!
+use crate::error::Error;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+use std::sync::Mutex;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Card {
+    code: &'static str,
+    message: String,
+    remediation: &'static str,
+}
+
+static CARD: Mutex<Option<Card>> = Mutex::new(None);
+
+pub fn show(e: &Error) {
+    if let Ok(mut card) = CARD.lock() {
+        *card = Some(Card {
+            code: e.code(),
+            message: e.to_string(),
+            remediation: e.remediation(),
+        });
+    }
+}
+
+/// Hide the card, returns `false` if none was showing.
+pub fn dismiss() -> bool {
+    CARD.lock().is_ok_and(|mut card| card.take().is_some())
+}
+
+pub fn render(f: &mut Frame) {
+    let Some(card) = CARD.lock().ok().and_then(|card| card.clone()) else {
+        return;
+    };
+    let area = card_rect(f.area());
+    let text = vec![
+        Line::from(card.message).style(Style::new().add_modifier(Modifier::BOLD)),
+        Line::from(""),
+        Line::from(card.remediation),
+        Line::from(""),
+        Line::from("press any key to dismiss").style(Style::new().add_modifier(Modifier::DIM)),
+    ];
+    let paragraph = Paragraph::new(text)
+        .wrap(Wrap { trim: true })
+        .style(Style::new().fg(Color::White).bg(Color::Black))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::new().fg(Color::Red))
+                .title(format!(" {} ", card.code)),
+        );
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+fn card_rect(r: Rect) -> Rect {
+    let vertical = Layout::vertical([Constraint::Length(9)])
+        .flex(layout::Flex::Center)
+        .split(r);
+    Layout::horizontal([Constraint::Percentage(60)])
+        .flex(layout::Flex::Center)
+        .split(vertical[0])[0]
+}
@@ -0,0 +1,141 @@
+//!Overlay to export the namespace without leaving the TUI, opened with 'x'
+//!from the replicaset view.  It asks for the file and the format and runs
+//!the export in the background, the status line shows how far it got.
+//!
+use crate::k8s::scan::background::{self, export_path};
+use crate::k8s::scan::rdf::{Compress, RdfFormat};
+use crate::tui::theme::symbols;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+
+#[derive(Clone, Debug)]
+pub struct ExportDialog {
+    /// indexes `RdfFormat::ALL`
+    format: usize,
+    /// export what the api server has now instead of the db
+    live: bool,
+    path: String,
+    message: Option<String>,
+}
+
+impl ExportDialog {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            format: 0,
+            live: false,
+            path: export_path(RdfFormat::ALL[0]),
+            message: None,
+        }
+    }
+
+    fn format(&self) -> RdfFormat {
+        RdfFormat::ALL[self.format]
+    }
+
+    /// the next format, the path's extension following it
+    fn next_format(&mut self) {
+        let old = self.format().extension();
+        self.format = (self.format + 1) % RdfFormat::ALL.len();
+        let new = self.format().extension();
+        let compressed = Compress::of_path(&self.path)
+            .map(|compress| format!(".{}", compress.extension()))
+            .unwrap_or_default();
+        let stem = self.path.strip_suffix(&compressed).unwrap_or(&self.path);
+        if let Some(stem) = stem.strip_suffix(&format!(".{old}")) {
+            self.path = format!("{stem}.{new}{compressed}");
+        }
+    }
+}
+
+impl Default for ExportDialog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Handle a key for an open dialog, starting the export on Enter.
+pub fn handle_key(dialog: &mut Option<Box<ExportDialog>>, key: &KeyEvent) {
+    let Some(open) = dialog.as_mut() else {
+        return;
+    };
+    match key.code {
+        KeyCode::Esc => *dialog = None,
+        KeyCode::Tab => open.next_format(),
+        KeyCode::Up | KeyCode::Down => open.live = !open.live,
+        KeyCode::Backspace => _ = open.path.pop(),
+        KeyCode::Char(c) => open.path.push(c),
+        KeyCode::Enter if open.path.trim().is_empty() => {
+            open.message = Some("a file to export to is needed".to_string());
+        }
+        KeyCode::Enter => {
+            if background::export(open.path.trim().to_string(), open.format(), open.live) {
+                *dialog = None;
+            } else {
+                open.message = Some("an export is already running".to_string());
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn render(f: &mut Frame, dialog: &ExportDialog) {
+    let area = Layout::vertical([Constraint::Length(9)])
+        .flex(layout::Flex::Center)
+        .split(f.area())[0];
+    let area = Layout::horizontal([Constraint::Percentage(60)])
+        .flex(layout::Flex::Center)
+        .split(area)[0];
+    let source = if dialog.live {
+        "a live snapshot of the namespace"
+    } else {
+        "the scan db"
+    };
+    let text = vec![
+        Line::from(format!("{}{}", dialog.path, symbols().cursor))
+            .style(Style::new().add_modifier(Modifier::BOLD)),
+        Line::from(format!("as {}, from {source}", dialog.format().name())),
+        Line::from(""),
+        Line::from(dialog.message.clone().unwrap_or_default())
+            .style(Style::new().fg(Color::Yellow)),
+        Line::from(format!(
+            "enter exports  tab next format  {} db or live  esc closes",
+            symbols().up_down
+        ))
+        .style(Style::new().add_modifier(Modifier::DIM)),
+    ];
+    let paragraph = Paragraph::new(text)
+        .wrap(Wrap { trim: true })
+        .style(Style::new().fg(Color::White).bg(Color::Black))
+        .block(Block::default().borders(Borders::ALL).title(" export "));
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extension_follows_format() {
+        let mut dialog = ExportDialog::new();
+        assert_eq!(dialog.path, "navipod.ttl");
+        dialog.next_format();
+        assert_eq!(dialog.path, "navipod.nt");
+        dialog.path = "out.nt.gz".to_string();
+        dialog.next_format();
+        assert_eq!(dialog.path, "out.json.gz");
+        dialog.path = "out.json.zst".to_string();
+        dialog.next_format();
+        assert_eq!(dialog.path, "out.ttl.zst");
+        dialog.path = "mine.txt".to_string();
+        dialog.next_format();
+        assert_eq!(
+            (dialog.path.as_str(), dialog.format()),
+            ("mine.txt", RdfFormat::NTriples)
+        );
+    }
+}
@@ -0,0 +1,305 @@
+//!The filters of each view kept between sessions: the last ones used, and
+//!filters saved under a name, ie: `prod web errors`.  In the filter editor
+//!the arrow keys step through the saved filters and then the recent ones,
+//!and ctrl-s saves the filter typed under a name.  `filters.json` in the
+//!config dir keeps them.
+//!
+use crate::config::config_file;
+use crate::tui::theme::symbols;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{prelude::*, widgets::Paragraph};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+use tracing::debug;
+
+const FILTERS_FILE: &str = "filters.json";
+
+/// recent filters kept per view
+const KEEP: usize = 20;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedFilter {
+    pub name: String,
+    pub pattern: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ViewFilters {
+    /// the latest first
+    pub recent: Vec<String>,
+    pub saved: Vec<SavedFilter>,
+}
+
+impl ViewFilters {
+    pub fn remember(&mut self, pattern: &str) {
+        if pattern.is_empty() {
+            return;
+        }
+        self.recent.retain(|recent| recent != pattern);
+        self.recent.insert(0, pattern.to_string());
+        self.recent.truncate(KEEP);
+    }
+
+    /// Save `pattern` as `name`, replacing a filter saved as `name` before.
+    pub fn save(&mut self, name: &str, pattern: &str) {
+        self.saved.retain(|saved| saved.name != name);
+        self.saved.push(SavedFilter {
+            name: name.to_string(),
+            pattern: pattern.to_string(),
+        });
+        self.saved.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    /// what the arrow keys step through: the saved filters by name, then
+    /// the recent ones not saved
+    #[must_use]
+    pub fn choices(&self) -> Vec<SavedFilter> {
+        let mut choices = self.saved.clone();
+        for pattern in &self.recent {
+            if !self.saved.iter().any(|saved| saved.pattern == *pattern) {
+                choices.push(SavedFilter {
+                    name: String::new(),
+                    pattern: pattern.clone(),
+                });
+            }
+        }
+        choices
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilterStore {
+    pub views: BTreeMap<String, ViewFilters>,
+}
+
+impl FilterStore {
+    #[must_use]
+    pub fn load() -> Self {
+        config_file(FILTERS_FILE)
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        let Some(path) = config_file(FILTERS_FILE) else {
+            return;
+        };
+        match serde_json::to_string_pretty(self) {
+            Ok(json) => {
+                if let Err(e) = std::fs::write(path, json) {
+                    debug!("can not save filters: {e}");
+                }
+            }
+            Err(e) => debug!("can not serialize filters: {e}"),
+        }
+    }
+
+    pub fn view_mut(&mut self, view: &str) -> &mut ViewFilters {
+        self.views.entry(view.to_string()).or_default()
+    }
+}
+
+fn store() -> &'static Mutex<FilterStore> {
+    static STORE: OnceLock<Mutex<FilterStore>> = OnceLock::new();
+    STORE.get_or_init(|| Mutex::new(FilterStore::load()))
+}
+
+/// The filter editor's side of the history, one per view.
+#[derive(Clone, Debug)]
+pub struct FilterRecall {
+    view: String,
+    /// the choice the arrow keys are on
+    at: Option<usize>,
+    /// the name typed after ctrl-s
+    naming: Option<String>,
+    /// the name of the saved filter shown, or what saving did
+    message: Option<String>,
+}
+
+impl FilterRecall {
+    #[must_use]
+    pub fn new(view: &str) -> Self {
+        Self {
+            view: view.to_string(),
+            at: None,
+            naming: None,
+            message: None,
+        }
+    }
+
+    /// Handle a key of the filter editor before the editor does, `true`
+    /// when it was used up here.  `filter` and `cursor` are replaced by a
+    /// recalled filter, Enter remembers the filter and is left to the
+    /// editor.
+    pub fn consume(&mut self, key: &KeyEvent, filter: &mut String, cursor: &mut usize) -> bool {
+        let Ok(mut store) = store().lock() else {
+            return false;
+        };
+        let (consumed, changed) = self.handle(&mut store, key, filter, cursor);
+        if changed {
+            store.save();
+        }
+        consumed
+    }
+
+    /// `consume` against `store`, and whether `store` changed
+    fn handle(
+        &mut self,
+        store: &mut FilterStore,
+        key: &KeyEvent,
+        filter: &mut String,
+        cursor: &mut usize,
+    ) -> (bool, bool) {
+        let filters = store.view_mut(&self.view);
+        if let Some(name) = self.naming.as_mut() {
+            match key.code {
+                KeyCode::Char(c) => name.push(c),
+                KeyCode::Backspace => {
+                    name.pop();
+                }
+                KeyCode::Enter if !name.trim().is_empty() => {
+                    let name = name.trim().to_string();
+                    filters.save(&name, filter);
+                    self.message = Some(format!("saved as {name}"));
+                    self.naming = None;
+                    return (true, true);
+                }
+                KeyCode::Esc => self.naming = None,
+                _ => {}
+            }
+            return (true, false);
+        }
+        let choices = filters.choices();
+        let step = |at: Option<usize>, older: bool| match (at, older) {
+            (None, true) => Some(0),
+            (None, false) => None,
+            (Some(at), true) => Some((at + 1).min(choices.len().saturating_sub(1))),
+            (Some(0), false) => None,
+            (Some(at), false) => Some(at - 1),
+        };
+        match key.code {
+            KeyCode::Up | KeyCode::Down if !choices.is_empty() => {
+                self.at = step(self.at, key.code == KeyCode::Up);
+                let choice = self.at.and_then(|at| choices.get(at));
+                filter.clone_from(&choice.map(|c| c.pattern.clone()).unwrap_or_default());
+                *cursor = filter.len();
+                self.message = choice
+                    .filter(|c| !c.name.is_empty())
+                    .map(|c| format!("saved: {}", c.name));
+                (true, false)
+            }
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                if !filter.is_empty() {
+                    self.naming = Some(String::new());
+                }
+                (true, false)
+            }
+            KeyCode::Enter => {
+                self.at = None;
+                self.message = None;
+                filters.remember(filter);
+                (false, true)
+            }
+            _ => {
+                self.at = None;
+                self.message = None;
+                (false, false)
+            }
+        }
+    }
+
+    /// Draw the name being typed, or what the history is showing, on the
+    /// last line inside the filter editor at `input_area`.
+    pub fn render(&self, f: &mut Frame, input_area: Rect) {
+        if input_area.height < 4 || input_area.width < 3 {
+            return;
+        }
+        let (text, style) = match (&self.naming, &self.message) {
+            (Some(name), _) => (
+                format!("save as: {name}{}", symbols().cursor),
+                Style::new().add_modifier(Modifier::BOLD),
+            ),
+            (None, Some(message)) => (message.clone(), Style::new().fg(Color::Green)),
+            (None, None) => (
+                format!(
+                    "{} saved and recent filters  ctrl-s save  alt-c/w/r case, words, regex",
+                    symbols().up_down
+                ),
+                Style::new().add_modifier(Modifier::DIM),
+            ),
+        };
+        // the name goes on the line under the input, over a regex error,
+        // the rest only when there is a line below that
+        let y = if self.naming.is_some() {
+            input_area.y + 2
+        } else {
+            input_area.bottom() - 2
+        };
+        if y < input_area.y + 2 || (self.naming.is_none() && y < input_area.y + 3) {
+            return;
+        }
+        f.render_widget(
+            Paragraph::new(text).style(style),
+            Rect::new(input_area.x + 1, y, input_area.width - 2, 1),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyEvent;
+
+    fn press(
+        store: &mut FilterStore,
+        recall: &mut FilterRecall,
+        filter: &mut String,
+        code: KeyCode,
+    ) {
+        let mut cursor = 0;
+        recall.handle(store, &KeyEvent::from(code), filter, &mut cursor);
+    }
+
+    #[test]
+    fn test_recall_and_save() {
+        let mut store = FilterStore::default();
+        let mut recall = FilterRecall::new("pods");
+        let mut filter = "web".to_string();
+        press(&mut store, &mut recall, &mut filter, KeyCode::Enter);
+        filter = "api".to_string();
+        press(&mut store, &mut recall, &mut filter, KeyCode::Enter);
+        assert_eq!(store.views["pods"].recent, ["api", "web"]);
+
+        filter = "error|fatal".to_string();
+        let mut cursor = 0;
+        let ctrl_s = KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        assert_eq!(
+            recall.handle(&mut store, &ctrl_s, &mut filter, &mut cursor),
+            (true, false)
+        );
+        for c in "errors".chars() {
+            press(&mut store, &mut recall, &mut filter, KeyCode::Char(c));
+        }
+        press(&mut store, &mut recall, &mut filter, KeyCode::Enter);
+        assert_eq!(recall.message.as_deref(), Some("saved as errors"));
+
+        filter.clear();
+        press(&mut store, &mut recall, &mut filter, KeyCode::Up);
+        assert_eq!(filter, "error|fatal");
+        assert_eq!(recall.message.as_deref(), Some("saved: errors"));
+        press(&mut store, &mut recall, &mut filter, KeyCode::Up);
+        assert_eq!(filter, "api");
+        press(&mut store, &mut recall, &mut filter, KeyCode::Down);
+        press(&mut store, &mut recall, &mut filter, KeyCode::Down);
+        assert_eq!(filter, "");
+
+        let mut filters = ViewFilters::default();
+        for i in 0..30 {
+            filters.remember(&i.to_string());
+        }
+        assert_eq!(filters.recent.len(), KEEP);
+        assert_eq!(filters.recent[0], "29");
+    }
+}
@@ -0,0 +1,235 @@
+//!The pattern typed into a filter.  It is a regex when it parses as one and
+//!plain text otherwise, so a half typed `(java|` still narrows the rows
+//!instead of matching nothing, while the filter editor says what is wrong
+//!with it and the headers mark the filter as literal.
+//!
+//!In the filter editor alt-c toggles matching case, alt-w whole words and
+//!alt-r regex or plain text.  The toggles hold for every view, the headers
+//!show which are on.
+//!
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{prelude::*, widgets::Paragraph};
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// compiled patterns larger than this are refused like invalid ones
+const SIZE_LIMIT: usize = 1 << 20;
+
+static IGNORE_CASE: AtomicBool = AtomicBool::new(false);
+static WHOLE_WORD: AtomicBool = AtomicBool::new(false);
+static LITERAL: AtomicBool = AtomicBool::new(false);
+
+/// how filters match, case sensitive regexes anywhere in the text unless
+/// toggled
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FilterOptions {
+    pub ignore_case: bool,
+    pub whole_word: bool,
+    /// the filter is plain text, not a regex
+    pub literal: bool,
+}
+
+impl FilterOptions {
+    /// what the headers show next to the filter
+    fn markers(self) -> Vec<&'static str> {
+        [
+            (self.ignore_case, "any case"),
+            (self.whole_word, "words"),
+            (self.literal, "literal"),
+        ]
+        .into_iter()
+        .filter_map(|(on, marker)| on.then_some(marker))
+        .collect()
+    }
+}
+
+#[must_use]
+pub fn options() -> FilterOptions {
+    FilterOptions {
+        ignore_case: IGNORE_CASE.load(Ordering::Relaxed),
+        whole_word: WHOLE_WORD.load(Ordering::Relaxed),
+        literal: LITERAL.load(Ordering::Relaxed),
+    }
+}
+
+/// Flip the option an alt key of the filter editor stands for, `true` when
+/// `key` was one.
+pub fn toggle(key: &KeyEvent) -> bool {
+    if !key.modifiers.contains(KeyModifiers::ALT) {
+        return false;
+    }
+    let option = match key.code {
+        KeyCode::Char('c' | 'C') => &IGNORE_CASE,
+        KeyCode::Char('w' | 'W') => &WHOLE_WORD,
+        KeyCode::Char('r' | 'R') => &LITERAL,
+        _ => return false,
+    };
+    option.fetch_xor(true, Ordering::Relaxed);
+    true
+}
+
+#[derive(Clone, Debug)]
+pub struct FilterPattern {
+    /// `None` only when even the escaped text is refused
+    regex: Option<Regex>,
+    text: String,
+    /// why the filter is matched as text though regexes are on
+    error: Option<String>,
+}
+
+impl FilterPattern {
+    /// `filter` under the options toggled in the filter editor
+    #[must_use]
+    pub fn parse(filter: &str) -> Self {
+        Self::parse_with(filter, options())
+    }
+
+    #[must_use]
+    pub fn parse_with(filter: &str, options: FilterOptions) -> Self {
+        let (source, error) = if options.literal {
+            (regex::escape(filter), None)
+        } else {
+            match RegexBuilder::new(filter).size_limit(SIZE_LIMIT).build() {
+                Ok(_) => (filter.to_string(), None),
+                Err(e) => (regex::escape(filter), Some(short(&e))),
+            }
+        };
+        let source = if options.whole_word {
+            format!(r"\b(?:{source})\b")
+        } else {
+            source
+        };
+        let regex = RegexBuilder::new(&source)
+            .case_insensitive(options.ignore_case)
+            .size_limit(SIZE_LIMIT)
+            .build()
+            .ok();
+        Self {
+            regex,
+            text: filter.to_string(),
+            error,
+        }
+    }
+
+    #[must_use]
+    pub fn is_match(&self, haystack: &str) -> bool {
+        self.regex.as_ref().map_or_else(
+            || haystack.contains(self.text.as_str()),
+            |regex| regex.is_match(haystack),
+        )
+    }
+
+    /// the bytes of the first match in `haystack`
+    #[must_use]
+    pub fn find(&self, haystack: &str) -> Option<Range<usize>> {
+        self.regex.as_ref().map_or_else(
+            || {
+                haystack
+                    .find(self.text.as_str())
+                    .map(|start| start..start + self.text.len())
+            },
+            |regex| regex.find(haystack).map(|found| found.range()),
+        )
+    }
+
+    /// why the filter is matched as text, `None` for a valid regex
+    #[must_use]
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
+
+/// the last line of the regex error, ie: `unclosed group`
+fn short(e: &regex::Error) -> String {
+    let message = e.to_string();
+    message
+        .lines()
+        .last()
+        .unwrap_or_default()
+        .trim_start_matches("error: ")
+        .to_string()
+}
+
+/// `filter` as a header shows it, with the options that are on
+#[must_use]
+pub fn label(filter: &str) -> String {
+    label_with(filter, options())
+}
+
+fn label_with(filter: &str, options: FilterOptions) -> String {
+    let mut markers = options.markers();
+    if !options.literal && FilterPattern::parse_with(filter, options).error().is_some() {
+        markers.push("literal");
+    }
+    if markers.is_empty() {
+        filter.to_string()
+    } else {
+        format!("{filter}, {}", markers.join(", "))
+    }
+}
+
+/// Draw why `filter` is matched as text on the line under the input of the
+/// filter editor at `input_area`.
+pub fn render_error(f: &mut Frame, input_area: Rect, filter: &str) {
+    let pattern = FilterPattern::parse(filter);
+    let Some(error) = pattern.error() else {
+        return;
+    };
+    if input_area.height < 4 || input_area.width < 3 {
+        return;
+    }
+    let line = Rect::new(input_area.x + 1, input_area.y + 2, input_area.width - 2, 1);
+    f.render_widget(
+        Paragraph::new(format!("{error}, matching as text")).style(Style::new().fg(Color::Red)),
+        line,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_patterns_match_as_text() {
+        let regex = FilterOptions::default();
+        let pattern = FilterPattern::parse_with("java|api", regex);
+        assert!(pattern.error().is_none());
+        assert!(pattern.is_match("api-gateway"));
+
+        let pattern = FilterPattern::parse_with("(java|", regex);
+        assert_eq!(pattern.error(), Some("unclosed group"));
+        assert!(pattern.is_match("log (java|scala)"));
+        assert!(!pattern.is_match("java"));
+
+        assert_eq!(label_with("web", regex), "web");
+        assert_eq!(label_with("web[", regex), "web[, literal");
+    }
+
+    #[test]
+    fn test_options() {
+        let options = |ignore_case, whole_word, literal| FilterOptions {
+            ignore_case,
+            whole_word,
+            literal,
+        };
+        assert!(
+            !FilterPattern::parse_with("error", options(false, false, false)).is_match("ERROR")
+        );
+        assert!(FilterPattern::parse_with("error", options(true, false, false)).is_match("ERROR"));
+
+        let words = FilterPattern::parse_with("api", options(false, true, false));
+        assert!(words.is_match("the api is down"));
+        assert!(!words.is_match("rapid"));
+
+        let literal = FilterPattern::parse_with("a.b", options(false, false, true));
+        assert!(literal.is_match("x a.b y"));
+        assert!(!literal.is_match("axb"));
+        assert!(literal.error().is_none());
+
+        assert_eq!(
+            label_with("api", options(true, true, true)),
+            "api, any case, words, literal"
+        );
+    }
+}
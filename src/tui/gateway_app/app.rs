@@ -0,0 +1,103 @@
+use crate::error::Error;
+use crate::error::Result as NvResult;
+use crate::k8s::client_manager::get_client;
+use crate::k8s::gateway;
+use crate::tui::data::GatewayRow;
+use crate::tui::stream::Message;
+use crate::tui::table_app::{self, TableApp};
+use crate::tui::ui_loop::{AppBehavior, Transition};
+use crossterm::event::{Event, KeyEventKind};
+use futures::Stream;
+use ratatui::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::debug;
+
+const POLL_MS: u64 = 10000;
+
+/// The namespace's Gateways, their listeners and the HTTPRoutes attached.
+#[derive(Clone, Debug)]
+pub struct App {
+    pub(crate) table: TableApp<GatewayRow>,
+}
+
+impl AppBehavior for App {
+    const NAME: &'static str = "gateways";
+
+    async fn handle_event(&mut self, event: &Message) -> NvResult<Transition> {
+        match event {
+            Message::Key(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                return Ok(self.table.handle_key(key));
+            }
+            Message::Gateway(data_vec) => {
+                debug!("updating gateway app data...");
+                self.table.set_items(data_vec);
+                self.table.note = if data_vec.is_empty() {
+                    "none here, or no Gateway API CRDs".to_string()
+                } else {
+                    String::new()
+                };
+            }
+            _ => {}
+        }
+        Ok(Transition::Stay)
+    }
+
+    fn draw_ui<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<(), std::io::Error> {
+        terminal.draw(|f| table_app::ui(f, &mut self.table))?;
+        Ok(())
+    }
+
+    fn stream(&self, should_stop: Arc<AtomicBool>) -> impl Stream<Item = Message> {
+        let (tx, rx) = mpsc::channel(100);
+
+        tokio::spawn(async move {
+            let mut sent: Option<Vec<GatewayRow>> = None;
+            while !should_stop.load(Ordering::Relaxed) {
+                match fetch_rows().await {
+                    Ok(d) => {
+                        if sent.as_ref() != Some(&d) {
+                            sent = Some(d.clone());
+                            if tx.send(Message::Gateway(d)).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Err(Error::Timeout { .. }) => {
+                        // already surfaced in the header, keep polling
+                    }
+                    Err(_e) => {
+                        break;
+                    }
+                }
+                sleep(Duration::from_millis(POLL_MS)).await;
+            }
+        });
+
+        ReceiverStream::new(rx)
+    }
+}
+
+async fn fetch_rows() -> NvResult<Vec<GatewayRow>> {
+    let client = get_client().await?;
+    let (gateways, routes) = gateway::fetch(&client, None).await?;
+    Ok(gateway::rows(&gateways, &routes))
+}
+
+impl App {
+    pub fn new() -> Self {
+        Self {
+            table: TableApp::new(vec![]),
+        }
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,179 +1,140 @@
+use crate::error::Result as NvResult;
+use crate::k8s::cache::cache_manager::get_cache;
+use crate::k8s::cache::{CachedData, DataRequest};
+use crate::k8s::ingress_controller::{list_controller_pods, log_filter};
 use crate::tui::cert_app;
-use crate::tui::data::{ingress_constraint_len_calculator, Ingress};
-use crate::tui::ingress_app;
+use crate::tui::data::Ingress;
+use crate::tui::log_app;
 use crate::tui::stream::Message;
-use crate::tui::style::{TableColors, ITEM_HEIGHT, PALETTES};
+use crate::tui::table_app::{self, Details, TableApp};
 use crate::tui::table_ui::TuiTableState;
-use crate::tui::ui_loop::{create_cert_data_vec, AppBehavior, Apps};
-use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
-use futures::{stream, Stream};
+use crate::tui::ui_loop::{create_cert_data_vec, AppBehavior, Apps, Transition};
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use futures::Stream;
 use ratatui::prelude::*;
-use ratatui::widgets::{ScrollbarState, TableState};
-use std::io;
-use std::sync::atomic::AtomicBool;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 use tracing::debug;
 
 #[derive(Clone, Debug)]
 pub struct App {
-    pub(crate) state: TableState,
-    pub(crate) items: Vec<Ingress>,
-    pub(crate) longest_item_lens: (u16, u16, u16, u16, u16),
-    pub(crate) scroll_state: ScrollbarState,
-    pub(crate) colors: TableColors,
-    color_index: usize,
-    pub(crate) filter: String,
+    pub(crate) table: TableApp<Ingress>,
+    /// picks the replicaset the ingresses route to
+    pub(crate) selector: BTreeMap<String, String>,
+    /// the detail pane of the selected row is open
+    pub(crate) expanded: bool,
 }
 
-impl TuiTableState for App {
-    type Item = Ingress;
+impl AppBehavior for App {
+    const NAME: &'static str = "ingresses";
 
-    fn get_items(&self) -> &[Self::Item] {
-        &self.items
-    }
-
-    fn get_state(&mut self) -> &mut TableState {
-        &mut self.state
-    }
-
-    fn get_scroll_state(&self) -> &ScrollbarState {
-        &self.scroll_state
-    }
-
-    fn set_scroll_state(&mut self, scroll_state: ScrollbarState) {
-        self.scroll_state = scroll_state;
-    }
-
-    fn set_table_colors(&mut self, colors: TableColors) {
-        self.colors = colors;
-    }
-
-    fn get_color_index(&self) -> usize {
-        self.color_index
-    }
-
-    fn set_color_index(&mut self, color_index: usize) {
-        self.color_index = color_index;
-    }
-
-    fn reset_selection_state(&mut self) {
-        self.state = TableState::default().with_selected(0);
-        self.scroll_state = ScrollbarState::new(self.items.len().saturating_sub(1) * ITEM_HEIGHT);
-    }
-
-    fn get_filter(&self) -> String {
-        self.filter.clone()
-    }
-
-    fn set_filter(&mut self, filter: String) {
-        self.filter = filter;
-    }
-
-    fn set_cursor_pos(&mut self, _cursor_pos: usize) {
-        todo!()
-    }
-
-    fn get_cursor_pos(&self) -> usize {
-        todo!()
-    }
-
-    fn set_show_filter_edit(&mut self, _show_filter_edit: bool) {
-        todo!()
-    }
-
-    fn get_show_filter_edit(&self) -> bool {
-        todo!()
-    }
-}
-
-impl AppBehavior for ingress_app::app::App {
-    async fn handle_event(&mut self, event: &Message) -> Result<Option<Apps>, io::Error> {
-        let mut app_holder = Some(Apps::Ingress { app: self.clone() });
+    async fn handle_event(&mut self, event: &Message) -> NvResult<Transition> {
         match event {
-            Message::Key(Event::Key(key)) => {
-                if key.kind == KeyEventKind::Press {
-                    use KeyCode::{Char, Down, Enter, Esc, Up};
-                    match key.code {
-                        Char('q') | Esc => {
-                            app_holder = None;
-                        }
-                        Char('j') | Down => {
-                            self.next();
-                            //todo: stop all this cloning
-                            app_holder = Some(Apps::Ingress { app: self.clone() });
-                        }
-                        Char('k') | Up => {
-                            self.previous();
-                            app_holder = Some(Apps::Ingress { app: self.clone() });
-                        }
-                        Char('c' | 'C') => {
-                            self.next_color();
-                            app_holder = Some(Apps::Ingress { app: self.clone() });
-                        }
-                        Char('f' | 'F') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                            self.page_forward();
-                        }
-                        Char('b' | 'B') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                            self.page_backward();
-                        }
-                        Enter => {
-                            if let Some(selection) = self.get_selected_item() {
-                                let host = &selection.host;
-                                match create_cert_data_vec(&host.clone()).await {
-                                    Ok(data_vec) => {
-                                        let new_app_holder = Apps::Cert {
-                                            app: cert_app::app::App::new(data_vec),
-                                        };
-                                        app_holder = Some(new_app_holder);
-                                        debug!("changing app from pod to cert...");
-                                    }
-                                    Err(e) => {
-                                        debug!("can not read certificate: {e}");
-                                    }
-                                }
-                            };
-                        }
-
-                        _k => {}
+            Message::Key(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                use KeyCode::{Char, Enter};
+                match key.code {
+                    Char('x' | 'X') if !self.table.show_filter_edit => {
+                        self.expanded = !self.expanded;
+                    }
+                    Char('R') if !self.table.show_filter_edit => return Ok(Transition::Refresh),
+                    Char('l' | 'L') => {
+                        if let Some(selection) = self.table.get_selected_item() {
+                            let filter = log_filter(&selection.host, &selection.path);
+                            let controllers = list_controller_pods().await?;
+                            if let Some(controller) = controllers.first() {
+                                debug!("changing app from ingress to controller log...");
+                                return Ok(Transition::Push(Box::new(Apps::new(
+                                    log_app::app::App::new_for_controller(controller, filter),
+                                ))));
+                            }
+                            debug!("no ingress controller pods found");
+                        };
+                    }
+                    Enter => {
+                        if let Some(selection) = self.table.get_selected_item() {
+                            let host = &selection.host;
+                            let data_vec = create_cert_data_vec(host).await?;
+                            debug!("changing app from pod to cert...");
+                            return Ok(Transition::Push(Box::new(Apps::new(
+                                cert_app::app::App::new(data_vec),
+                            ))));
+                        };
                     }
+                    _ => return Ok(self.table.handle_key(key)),
                 }
             }
-            Message::Ingress(data_vec) => {
-                let new_app = Self {
-                    longest_item_lens: ingress_constraint_len_calculator(data_vec),
-                    scroll_state: ScrollbarState::new(
-                        data_vec.len().saturating_sub(1) * ITEM_HEIGHT,
-                    ),
-                    items: data_vec.clone(),
-                    ..self.clone()
-                };
-                let new_app_holder = Apps::Ingress { app: new_app };
-                app_holder = Some(new_app_holder);
-            }
+            Message::Ingress(data_vec) => self.table.set_items(data_vec),
             _ => {}
         }
-        Ok(app_holder)
+        Ok(Transition::Stay)
     }
+
     fn draw_ui<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<(), std::io::Error> {
-        terminal.draw(|f| ingress_app::ui::ui(f, &mut self.clone()))?;
+        let details = self.expanded.then(|| self.details());
+        terminal.draw(|f| {
+            table_app::ui_with_details(f, &mut self.table, details.as_ref());
+        })?;
         Ok(())
     }
 
-    fn stream(&self, _should_stop: Arc<AtomicBool>) -> impl Stream<Item = Message> {
-        stream::empty()
+    fn source(&self) -> Option<DataRequest> {
+        Some(DataRequest::Ingresses {
+            selector: self.selector.clone(),
+        })
+    }
+
+    /// the rows the ingress, service and endpointslice watches had
+    /// refetched into the cache
+    fn stream(&self, should_stop: Arc<AtomicBool>) -> impl Stream<Item = Message> {
+        let (tx, rx) = mpsc::channel(10);
+        let request = self.source();
+        let mut landed = get_cache().subscribe();
+        tokio::spawn(async move {
+            while !should_stop.load(Ordering::Relaxed) {
+                match landed.recv().await {
+                    Ok(put) if Some(&put) == request.as_ref() => {
+                        if let Some(CachedData::Ingresses(rows)) = get_cache().get(&put) {
+                            if tx.send(Message::Ingress(rows)).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+        ReceiverStream::new(rx)
     }
 }
 
 impl App {
-    pub fn new(data_vec: Vec<Ingress>) -> Self {
+    pub fn new(selector: BTreeMap<String, String>, data_vec: Vec<Ingress>) -> Self {
         Self {
-            state: TableState::default().with_selected(0),
-            longest_item_lens: ingress_constraint_len_calculator(&data_vec),
-            scroll_state: ScrollbarState::new(data_vec.len().saturating_sub(1) * ITEM_HEIGHT),
-            colors: TableColors::new(&PALETTES[0]),
-            color_index: 3,
-            items: data_vec,
-            filter: String::new(),
+            table: TableApp::new(data_vec),
+            selector,
+            expanded: false,
         }
     }
+
+    /// class, tls check and controller annotations of the selected row
+    fn details(&mut self) -> (String, Details) {
+        self.table.get_selected_item().map_or_else(
+            || ("Details".to_string(), vec![]),
+            |ingress| {
+                (
+                    format!("{} {}", ingress.name, ingress.host),
+                    ingress
+                        .details
+                        .iter()
+                        .map(|detail| (detail.name.clone(), detail.value.clone(), None))
+                        .collect(),
+                )
+            },
+        )
+    }
 }
@@ -1,2 +1 @@
 pub mod app;
-pub mod ui;
@@ -1,82 +0,0 @@
-use crate::tui::ingress_app::app::App;
-use crate::tui::table_ui::TuiTableState;
-use ratatui::{
-    prelude::*,
-    widgets::{Cell, HighlightSpacing, Row, Scrollbar, ScrollbarOrientation, Table},
-};
-
-pub fn ui(f: &mut Frame, app: &mut App) {
-    let rects = Layout::vertical([Constraint::Min(5)]).split(f.area());
-
-    app.set_colors();
-
-    render_table(f, app, rects[0]);
-
-    render_scrollbar(f, app, rects[0]);
-}
-
-fn render_table(f: &mut Frame, app: &mut App, area: Rect) {
-    let header_style = Style::default()
-        .fg(app.colors.header_fg)
-        .bg(app.colors.header_bg);
-    let selected_style = Style::default()
-        .add_modifier(Modifier::REVERSED)
-        .fg(app.colors.selected_style_fg);
-
-    let header = ["Ingress", "Host", "Path", "Service", "Port"]
-        .iter()
-        .copied()
-        .map(Cell::from)
-        .collect::<Row>()
-        .style(header_style)
-        .height(1);
-    let rows = app
-        .get_filtered_items()
-        .into_iter()
-        .enumerate()
-        .map(|(i, data)| {
-            let color = match i % 2 {
-                0 => app.colors.normal_row_color,
-                _ => app.colors.alt_row_color,
-            };
-            let item = data.ref_array();
-            item.iter()
-                .copied()
-                .map(|content| Cell::from(Text::from(format!("\n{content}\n"))))
-                .collect::<Row>()
-                .style(Style::new().fg(app.colors.row_fg).bg(color))
-                .height(3)
-        });
-    let bar = " █ ";
-    let t = Table::new(
-        rows,
-        [
-            // + 1 is for padding.
-            Constraint::Min(app.longest_item_lens.0 + 1),
-            Constraint::Min(app.longest_item_lens.1 + 1),
-            Constraint::Min(app.longest_item_lens.2 + 1),
-            Constraint::Min(app.longest_item_lens.3 + 1),
-            Constraint::Min(app.longest_item_lens.4),
-        ],
-    )
-    .header(header)
-    .row_highlight_style(selected_style)
-    .highlight_symbol(Text::from(vec!["".into(), bar.into(), "".into()]))
-    .bg(app.colors.buffer_bg)
-    .highlight_spacing(HighlightSpacing::Always);
-    f.render_stateful_widget(t, area, &mut app.state);
-}
-
-fn render_scrollbar(f: &mut Frame, app: &mut App, area: Rect) {
-    f.render_stateful_widget(
-        Scrollbar::default()
-            .orientation(ScrollbarOrientation::VerticalRight)
-            .begin_symbol(None)
-            .end_symbol(None),
-        area.inner(Margin {
-            vertical: 1,
-            horizontal: 1,
-        }),
-        &mut app.scroll_state,
-    );
-}
@@ -1,16 +1,25 @@
-use crate::k8s::containers::logs;
+use crate::error::Error;
+use crate::error::Result as NvResult;
+use crate::k8s::cache::memory::{estimate, track};
+use crate::k8s::containers::{logs, namespaced_logs, previous_logs, EarlierInstances};
+use crate::k8s::events::{interleave_events, pod_event_log_recs};
+use crate::k8s::ingress_controller::ControllerPod;
+use crate::k8s::restarts::restart_count;
 use crate::tui::data::{log_constraint_len_calculator, LogRec};
+use crate::tui::filter_history::FilterRecall;
+use crate::tui::filter_pattern::{self, FilterPattern};
 use crate::tui::log_app;
+use crate::tui::log_app::fields::FieldFilter;
 use crate::tui::stream::Message;
 use crate::tui::style::{TableColors, ITEM_HEIGHT, PALETTES};
 use crate::tui::table_ui::TuiTableState;
-use crate::tui::ui_loop::{AppBehavior, Apps};
+use crate::tui::ui_loop::{AppBehavior, Transition};
+use crate::tui::utils::time::{timezone, TimeZoneSetting};
 use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
 use futures::Stream;
 use ratatui::prelude::*;
 use ratatui::widgets::{ScrollbarState, TableState};
 use std::collections::BTreeMap;
-use std::io;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
@@ -20,6 +29,29 @@ use tokio_stream::wrappers::ReceiverStream;
 use tracing::debug;
 
 const POLL_MS: u64 = 5000;
+const CONTROLLER_TAIL_LINES: i64 = 1000;
+/// lines kept of the instance a restart ended
+const PREVIOUS_TAIL_LINES: i64 = 200;
+
+/// how long messages are laid out, 'w' cycles through them
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WrapMode {
+    #[default]
+    Off,
+    Selected,
+    All,
+}
+
+impl WrapMode {
+    #[must_use]
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Selected,
+            Self::Selected => Self::All,
+            Self::All => Self::Off,
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct App {
@@ -32,7 +64,15 @@ pub struct App {
     pub(crate) selector: BTreeMap<String, String>,
     pub(crate) pod_name: String,
     pub(crate) container_name: String,
+    pub(crate) namespace: Option<String>,
+    /// draw the colors the container wrote instead of the table colors
+    pub(crate) show_colors: bool,
+    pub(crate) wrap: WrapMode,
+    pub(crate) timezone: TimeZoneSetting,
+    /// interleave the pod's events with its log lines
+    pub(crate) show_events: bool,
     pub(crate) filter: String,
+    pub(crate) recall: FilterRecall,
     pub(crate) show_filter_edit: bool,
     pub(crate) edit_filter_cursor_position: usize,
 }
@@ -77,6 +117,24 @@ impl TuiTableState for App {
         self.filter.clone()
     }
 
+    /// events are hidden unless the overlay is on, and then they skip the
+    /// filter so the cause stays next to the lines it explains.  A filter of
+    /// `key op value` terms is checked against the fields of JSON lines.
+    fn get_filtered_items(&self) -> Vec<&Self::Item> {
+        let pattern = FilterPattern::parse(&self.filter);
+        let fields = FieldFilter::parse(&self.filter);
+        self.items
+            .iter()
+            .filter(|item| match item.event_type {
+                Some(_) => self.show_events,
+                None => fields
+                    .as_ref()
+                    .and_then(|fields| fields.matches(&item.message))
+                    .unwrap_or_else(|| pattern.is_match(&item.message)),
+            })
+            .collect()
+    }
+
     fn set_filter(&mut self, filter: String) {
         self.filter = filter;
     }
@@ -101,7 +159,9 @@ impl TuiTableState for App {
 }
 
 impl AppBehavior for log_app::app::App {
-    async fn handle_event(&mut self, event: &Message) -> Result<Option<Apps>, io::Error> {
+    const NAME: &'static str = "logs";
+
+    async fn handle_event(&mut self, event: &Message) -> NvResult<Transition> {
         if self.get_show_filter_edit() {
             Ok(self.handle_filter_edit_event(event))
         } else {
@@ -110,7 +170,7 @@ impl AppBehavior for log_app::app::App {
     }
 
     fn draw_ui<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<(), std::io::Error> {
-        terminal.draw(|f| log_app::ui::ui(f, &mut self.clone()))?;
+        terminal.draw(|f| log_app::ui::ui(f, self))?;
         Ok(())
     }
 
@@ -122,13 +182,54 @@ impl AppBehavior for log_app::app::App {
         let pod_name = self.pod_name.clone();
         let container_name = self.container_name.clone();
         let selector = self.selector.clone();
+        let namespace = self.namespace.clone();
 
         tokio::spawn(async move {
+            // a restarted container is followed into its new instance, the
+            // lines of the old one stay below a divider
+            let mut earlier = EarlierInstances::default();
             while !should_stop.load(Ordering::Relaxed) {
+                let restarts = restart_count(namespace.as_deref(), &pod_name, &container_name);
+                if earlier.restarted(restarts) {
+                    match previous_logs(
+                        namespace.as_deref(),
+                        &pod_name,
+                        &container_name,
+                        PREVIOUS_TAIL_LINES,
+                    )
+                    .await
+                    {
+                        Ok(previous) => earlier.push(previous),
+                        Err(e) => {
+                            debug!("can not read logs before the restart of {pod_name}: {e}");
+                            earlier.push(vec![]);
+                        }
+                    }
+                }
                 //get Vec and send
-                match logs(selector.clone(), pod_name.clone(), container_name.clone()).await {
+                let result = match &namespace {
+                    Some(ns) => {
+                        namespaced_logs(ns, &pod_name, &container_name, CONTROLLER_TAIL_LINES).await
+                    }
+                    None => logs(selector.clone(), pod_name.clone(), container_name.clone()).await,
+                };
+                let result = match result {
+                    Ok(d) => {
+                        let d = earlier.below(d);
+                        match pod_event_log_recs(namespace.as_deref(), &pod_name).await {
+                            Ok(events) => Ok(interleave_events(d, events)),
+                            Err(e) => {
+                                debug!("can not list events of {pod_name}: {e}");
+                                Ok(d)
+                            }
+                        }
+                    }
+                    Err(e) => Err(e),
+                };
+                match result {
                     Ok(d) => {
                         if !d.is_empty() && d != initial_items {
+                            track("log view", estimate(&d));
                             let sevent = Message::Log(d);
                             if tx.send(sevent).await.is_err() {
                                 break;
@@ -136,6 +237,16 @@ impl AppBehavior for log_app::app::App {
                         }
                         sleep(Duration::from_millis(POLL_MS)).await;
                     }
+                    Err(Error::Timeout { .. }) => {
+                        // already surfaced in the header, keep polling
+                        sleep(Duration::from_millis(POLL_MS)).await;
+                    }
+                    Err(e) if restarts.is_some() => {
+                        // the pod is still watched, ie: its container is
+                        // coming back from a restart
+                        debug!("waiting for {pod_name}/{container_name}: {e}");
+                        sleep(Duration::from_millis(POLL_MS)).await;
+                    }
                     Err(_e) => {
                         break;
                     }
@@ -165,116 +276,128 @@ impl App {
             selector,
             pod_name,
             container_name,
+            namespace: None,
+            show_colors: true,
+            wrap: WrapMode::Off,
+            timezone: timezone(),
+            show_events: false,
             filter: String::new(),
+            recall: FilterRecall::new(Self::NAME),
             show_filter_edit: false,
             edit_filter_cursor_position: 0,
         }
     }
 
-    fn handle_table_event(&mut self, event: &Message) -> Option<Apps> {
-        let mut app_holder = Some(Apps::Log { app: self.clone() });
+    /// open at `selected` of logs fetched elsewhere, ie: by a search across
+    /// replicas, the stream takes over from there
+    pub fn with_context(
+        selector: BTreeMap<String, String>,
+        pod_name: String,
+        container_name: String,
+        logs: Vec<LogRec>,
+        selected: usize,
+    ) -> Self {
+        Self {
+            state: TableState::default().with_selected(selected),
+            longest_item_lens: log_constraint_len_calculator(&logs),
+            scroll_state: ScrollbarState::new(logs.len().saturating_sub(1) * ITEM_HEIGHT)
+                .position(selected * ITEM_HEIGHT),
+            items: logs,
+            ..Self::new(selector, pod_name, container_name)
+        }
+    }
+
+    /// tail an ingress controller in its own namespace with the filter preset
+    pub fn new_for_controller(controller: &ControllerPod, filter: String) -> Self {
+        let selector = controller.selectors.clone().unwrap_or_default();
+        Self {
+            namespace: Some(controller.namespace.clone()),
+            edit_filter_cursor_position: filter.len(),
+            filter,
+            ..Self::new(
+                selector,
+                controller.pod_name.clone(),
+                controller.container_name.clone(),
+            )
+        }
+    }
+
+    fn update_items(&mut self, data_vec: &[LogRec]) {
+        self.longest_item_lens = log_constraint_len_calculator(data_vec);
+        self.scroll_state = ScrollbarState::new(data_vec.len().saturating_sub(1) * ITEM_HEIGHT);
+        self.items = data_vec.to_vec();
+    }
+
+    fn handle_table_event(&mut self, event: &Message) -> Transition {
         match event {
-            Message::Key(Event::Key(key)) => {
-                if key.kind == KeyEventKind::Press {
-                    use KeyCode::{Char, Down, Enter, Esc, Up};
-                    match key.code {
-                        Char('q') | Esc => {
-                            app_holder = None;
-                        }
-                        Char('j') | Down => {
-                            self.next();
-                            //todo: stop all this cloning
-                            app_holder = Some(Apps::Log { app: self.clone() });
-                        }
-                        Char('k') | Up => {
-                            self.previous();
-                            app_holder = Some(Apps::Log { app: self.clone() });
-                        }
-                        Char('c' | 'C') => {
-                            self.next_color();
-                            app_holder = Some(Apps::Log { app: self.clone() });
-                        }
-                        Char('f' | 'F') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                            self.page_forward();
-                        }
-                        Char('b' | 'B') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                            self.page_backward();
-                        }
-                        Enter => {
-                            // noop for now but will be pretty printed detail analysis popup
-                        }
-                        Char('/') => {
-                            self.set_show_filter_edit(true);
-                            app_holder = Some(Apps::Log { app: self.clone() });
-                        }
-                        _k => {}
+            Message::Key(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                use KeyCode::{Char, Down, Enter, Esc, Up};
+                if let Some(transition) = self.handle_mark(key) {
+                    return transition;
+                }
+                if self.handle_motion(key) {
+                    return Transition::Stay;
+                }
+                match key.code {
+                    Char('q') | Esc => return Transition::Pop,
+                    Char('j') | Down => self.next(),
+                    Char('k') | Up => self.previous(),
+                    Char('c' | 'C') => self.next_color(),
+                    Char('w' | 'W') => self.wrap = self.wrap.next(),
+                    Char('z' | 'Z') => self.timezone = self.timezone.toggle(),
+                    Char('a' | 'A') => self.show_colors = !self.show_colors,
+                    Char('e' | 'E') => {
+                        self.show_events = !self.show_events;
+                        self.reset_selection_state();
                     }
+                    Char('f' | 'F') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.page_forward();
+                    }
+                    Char('b' | 'B') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.page_backward();
+                    }
+                    Enter => {
+                        // noop for now but will be pretty printed detail analysis popup
+                    }
+                    Char('/') => self.set_show_filter_edit(true),
+                    _k => {}
                 }
             }
-            Message::Log(data_vec) => {
-                let new_app = Self {
-                    longest_item_lens: log_constraint_len_calculator(data_vec),
-                    scroll_state: ScrollbarState::new(
-                        data_vec.len().saturating_sub(1) * ITEM_HEIGHT,
-                    ),
-                    items: data_vec.clone(),
-                    ..self.clone()
-                };
-                let new_app_holder = Apps::Log { app: new_app };
-                app_holder = Some(new_app_holder);
-            }
+            Message::Log(data_vec) => self.update_items(data_vec),
             _ => {}
         }
-        app_holder
+        Transition::Stay
     }
 
-    fn handle_filter_edit_event(&mut self, event: &Message) -> Option<Apps> {
-        let mut app_holder = Some(Apps::Log { app: self.clone() });
+    fn handle_filter_edit_event(&mut self, event: &Message) -> Transition {
         match event {
-            Message::Key(Event::Key(key)) => {
-                if key.kind == KeyEventKind::Press {
-                    use KeyCode::{Backspace, Char, Enter, Esc, Left, Right};
-
-                    match key.code {
-                        Char(to_insert) => {
-                            self.enter_char(to_insert);
-                            app_holder = Some(Apps::Log { app: self.clone() });
-                        }
-                        Backspace => {
-                            self.delete_char();
-                            app_holder = Some(Apps::Log { app: self.clone() });
-                        }
-                        Left => {
-                            self.move_cursor_left();
-                            app_holder = Some(Apps::Log { app: self.clone() });
-                        }
-                        Right => {
-                            self.move_cursor_right();
-                            app_holder = Some(Apps::Log { app: self.clone() });
-                        }
-                        Esc | Enter => {
-                            self.set_show_filter_edit(false);
-                            app_holder = Some(Apps::Log { app: self.clone() });
-                        }
-                        _ => {}
-                    }
+            Message::Key(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                use KeyCode::{Backspace, Char, Enter, Esc, Left, Right};
+
+                if filter_pattern::toggle(key)
+                    || self.recall.consume(
+                        key,
+                        &mut self.filter,
+                        &mut self.edit_filter_cursor_position,
+                    )
+                {
+                    return Transition::Stay;
+                }
+                match key.code {
+                    Char(to_insert) => self.enter_char(to_insert),
+                    Backspace => self.delete_char(),
+                    Left => self.move_cursor_left(),
+                    Right => self.move_cursor_right(),
+                    Esc | Enter => self.set_show_filter_edit(false),
+                    _ => {}
                 }
             }
             Message::Log(data_vec) => {
                 debug!("updating log app data...");
-                let new_app = Self {
-                    longest_item_lens: log_constraint_len_calculator(data_vec),
-                    scroll_state: ScrollbarState::new(
-                        data_vec.len().saturating_sub(1) * ITEM_HEIGHT,
-                    ),
-                    items: data_vec.clone(),
-                    ..self.clone()
-                };
-                let new_app_holder = Apps::Log { app: new_app };
-                app_holder = Some(new_app_holder);
+                self.update_items(data_vec);
             }
             _ => {}
         }
-        app_holder
+        Transition::Stay
     }
 }
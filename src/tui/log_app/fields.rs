@@ -0,0 +1,139 @@
+//!Filters on the fields of JSON log lines, ie: `level=error status>=500
+//!service=checkout`.  A filter made only of `key op value` terms is one,
+//!the ops are `=`, `!=`, `>=`, `<=`, `>` and `<`, and a line matches when
+//!every term holds.  Keys reach into nested objects with dots, ie:
+//!`http.status>=500`, `=` and `!=` compare text ignoring case and the others
+//!compare numbers.  Lines that are not JSON objects are matched by the filter
+//!as a pattern like any other filter.
+//!
+use serde_json::Value;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Ge,
+    Le,
+    Gt,
+    Lt,
+}
+
+impl Op {
+    /// longest first so `>=` is not read as `>`
+    const ALL: [(&'static str, Self); 6] = [
+        ("!=", Self::Ne),
+        (">=", Self::Ge),
+        ("<=", Self::Le),
+        ("=", Self::Eq),
+        (">", Self::Gt),
+        ("<", Self::Lt),
+    ];
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Term {
+    key: String,
+    op: Op,
+    value: String,
+}
+
+impl Term {
+    fn parse(term: &str) -> Option<Self> {
+        let (at, text, op) = Op::ALL
+            .iter()
+            .filter_map(|(text, op)| term.find(text).map(|at| (at, *text, *op)))
+            .min_by_key(|(at, text, _)| (*at, std::cmp::Reverse(text.len())))?;
+        let key = &term[..at];
+        let value = &term[at + text.len()..];
+        let is_key = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '@');
+        (!key.is_empty() && key.chars().all(is_key) && !value.is_empty()).then(|| Self {
+            key: key.to_string(),
+            op,
+            value: value.trim_matches('"').to_string(),
+        })
+    }
+
+    fn holds(&self, fields: &Value) -> bool {
+        let found = self
+            .key
+            .split('.')
+            .try_fold(fields, |value, key| value.get(key));
+        let Some(found) = found.filter(|value| !value.is_null()) else {
+            return self.op == Op::Ne;
+        };
+        let text = match found {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        match self.op {
+            Op::Eq => text.eq_ignore_ascii_case(&self.value),
+            Op::Ne => !text.eq_ignore_ascii_case(&self.value),
+            op => match (text.parse::<f64>(), self.value.parse::<f64>()) {
+                (Ok(field), Ok(value)) => match op {
+                    Op::Ge => field >= value,
+                    Op::Le => field <= value,
+                    Op::Gt => field > value,
+                    _ => field < value,
+                },
+                _ => false,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldFilter {
+    terms: Vec<Term>,
+}
+
+impl FieldFilter {
+    /// `None` unless every word of `filter` is a `key op value` term
+    #[must_use]
+    pub fn parse(filter: &str) -> Option<Self> {
+        let terms = filter
+            .split_whitespace()
+            .map(Term::parse)
+            .collect::<Option<Vec<_>>>()?;
+        (!terms.is_empty()).then_some(Self { terms })
+    }
+
+    /// whether every term holds for the fields of `message`, `None` when it
+    /// is not a JSON object
+    #[must_use]
+    pub fn matches(&self, message: &str) -> Option<bool> {
+        let message = message.trim();
+        if !message.starts_with('{') {
+            return None;
+        }
+        let fields: Value = serde_json::from_str(message).ok()?;
+        fields
+            .is_object()
+            .then(|| self.terms.iter().all(|term| term.holds(&fields)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_filter() {
+        assert!(FieldFilter::parse("timeout").is_none());
+        assert!(FieldFilter::parse("level=error timeout").is_none());
+        assert!(FieldFilter::parse("=error").is_none());
+
+        let filter = FieldFilter::parse("level=error http.status>=500 service!=cart").unwrap();
+        let line = |level: &str, status: u16| {
+            format!(r#"{{"level":"{level}","service":"checkout","http":{{"status":{status}}}}}"#)
+        };
+        assert_eq!(filter.matches(&line("ERROR", 503)), Some(true));
+        assert_eq!(filter.matches(&line("error", 404)), Some(false));
+        assert_eq!(filter.matches(&line("info", 500)), Some(false));
+        assert_eq!(filter.matches("error 500 from checkout"), None);
+
+        let missing = FieldFilter::parse("user!=admin").unwrap();
+        assert_eq!(missing.matches(r#"{"level":"info"}"#), Some(true));
+        let missing = FieldFilter::parse("latency>100").unwrap();
+        assert_eq!(missing.matches(r#"{"level":"info"}"#), Some(false));
+    }
+}
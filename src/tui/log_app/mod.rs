@@ -1,2 +1,3 @@
 pub mod app;
+pub mod fields;
 pub mod ui;
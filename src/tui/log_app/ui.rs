@@ -1,15 +1,26 @@
-use crate::tui::log_app::app::App;
+use crate::k8s::containers::RESTART_DIVIDER;
+use crate::tui::data::LogRec;
+use crate::tui::error_card;
+use crate::tui::filter_pattern;
+use crate::tui::log_app::app::{App, WrapMode};
+use crate::tui::log_app::fields::FieldFilter;
+use crate::tui::status_line;
+use crate::tui::style::ITEM_HEIGHT;
+use crate::tui::table_ui::with_timeout_notice;
 use crate::tui::table_ui::TuiTableState;
+use crate::tui::theme::symbols;
+use crate::tui::utils::time::LOG_TIME_FORMAT;
+use crate::tui::utils::wrap::wrap_ranges;
 use ratatui::{
     prelude::*,
     widgets::{
         Block, Borders, Cell, Clear, HighlightSpacing, Paragraph, Row, Scrollbar,
-        ScrollbarOrientation, Table,
+        ScrollbarOrientation, ScrollbarState, Table,
     },
 };
 
 pub fn ui(f: &mut Frame, app: &mut App) {
-    let rects = Layout::vertical([Constraint::Min(5)]).split(f.area());
+    let rects = Layout::vertical([Constraint::Min(5)]).split(status_line::render(f));
 
     app.set_colors();
 
@@ -20,6 +31,9 @@ pub fn ui(f: &mut Frame, app: &mut App) {
     if app.get_show_filter_edit() {
         render_filter_edit(f, app);
     }
+
+    status_line::render_activity(f);
+    error_card::render(f);
 }
 
 fn render_filter_edit(f: &mut Frame, app: &App) {
@@ -40,6 +54,8 @@ fn render_filter_edit(f: &mut Frame, app: &App) {
 
     f.render_widget(Clear, input_area); //this clears out the background
     f.render_widget(block, input_area);
+    filter_pattern::render_error(f, input_area, &app.filter);
+    app.recall.render(f, input_area);
 
     #[allow(clippy::cast_possible_truncation)]
     let p = Position {
@@ -65,6 +81,33 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     .split(popup_layout[1])[1]
 }
 
+/// the message cell, one line per wrapped segment, drawn with the
+/// container's own colors if it wrote any
+fn message_text(data: &LogRec, show_colors: bool, segments: &[(usize, usize)]) -> Text<'static> {
+    let mut lines = vec![Line::from("")];
+    for &(start, end) in segments {
+        let runs = data
+            .colors
+            .iter()
+            .filter(|run| show_colors && run.start < end && run.end > start);
+        let mut spans = Vec::new();
+        let mut pos = start;
+        for run in runs {
+            let (run_start, run_end) = (run.start.max(start), run.end.min(end));
+            spans.push(Span::raw(data.message[pos..run_start].to_string()));
+            spans.push(Span::styled(
+                data.message[run_start..run_end].to_string(),
+                Style::new().fg(run.fg),
+            ));
+            pos = run_end;
+        }
+        spans.push(Span::raw(data.message[pos..end].to_string()));
+        lines.push(Line::from(spans));
+    }
+    lines.push(Line::from(""));
+    Text::from(lines)
+}
+
 fn render_table(f: &mut Frame, app: &mut App, area: Rect) {
     let header_style = Style::default()
         .fg(app.colors.header_fg)
@@ -73,14 +116,37 @@ fn render_table(f: &mut Frame, app: &mut App, area: Rect) {
         .add_modifier(Modifier::REVERSED)
         .fg(app.colors.selected_style_fg);
 
-    let header = ["Timestamp", "Level", "Message"]
+    let wrap = match app.wrap {
+        WrapMode::Off => "",
+        WrapMode::Selected => " [wrap selected]",
+        WrapMode::All => " [wrap all]",
+    };
+    let events = if app.show_events { " [events]" } else { "" };
+    let filter_header = with_timeout_notice(match app.get_filter() {
+        filter if FieldFilter::parse(&filter).is_some() => {
+            format!("Message ({filter}, fields){wrap}{events}")
+        }
+        filter if filter != String::new() => {
+            format!("Message ({}){wrap}{events}", filter_pattern::label(&filter))
+        }
+        _ => format!("Message{wrap}{events}"),
+    });
+
+    let timestamp_header = format!("Timestamp ({})", app.timezone.label());
+    let header = [timestamp_header.as_str(), "Level", &filter_header]
         .iter()
         .copied()
         .map(Cell::from)
         .collect::<Row>()
         .style(header_style)
         .height(1);
-    let rows = app
+    // timestamp, level, highlight bar, column spacing and the scrollbar
+    let message_width = usize::from(area.width).saturating_sub(
+        usize::from(app.longest_item_lens.0 + app.longest_item_lens.1) + 2 + 3 + 2 + 2,
+    );
+    let selected = app.state.selected().unwrap_or(0);
+    let mut heights = Vec::new();
+    let rows: Vec<Row> = app
         .get_filtered_items()
         .into_iter()
         .enumerate()
@@ -89,15 +155,62 @@ fn render_table(f: &mut Frame, app: &mut App, area: Rect) {
                 0 => app.colors.normal_row_color,
                 _ => app.colors.alt_row_color,
             };
-            let item = data.ref_array();
-            item.iter()
-                .copied()
-                .map(|content| Cell::from(Text::from(format!("\n{content}\n"))))
-                .collect::<Row>()
-                .style(Style::new().fg(app.colors.row_fg).bg(color))
-                .height(3)
-        });
-    let bar = " █ ";
+            let wrapped = match app.wrap {
+                WrapMode::Off => false,
+                WrapMode::Selected => i == selected,
+                WrapMode::All => true,
+            };
+            let segments = if wrapped {
+                wrap_ranges(&data.message, message_width)
+            } else {
+                vec![(0, data.message.len())]
+            };
+            let height = segments.len() + 2;
+            heights.push(height);
+            let style = match data.event_type.as_deref() {
+                None if data.timestamp.is_none() && data.message == RESTART_DIVIDER => {
+                    Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                }
+                Some("Warning") => Style::new()
+                    .fg(Color::LightRed)
+                    .add_modifier(Modifier::ITALIC),
+                Some(_) => Style::new().fg(Color::Cyan).add_modifier(Modifier::ITALIC),
+                None => Style::new().fg(app.colors.row_fg),
+            };
+            Row::new([
+                Cell::from(Text::from(format!(
+                    "\n{}\n",
+                    data.timestamp.map_or_else(
+                        || data.datetime.clone(),
+                        |ts| app.timezone.format(&ts, LOG_TIME_FORMAT)
+                    )
+                ))),
+                Cell::from(Text::from(format!("\n{}\n", data.level))),
+                if data.timestamp.is_none() && data.message == RESTART_DIVIDER {
+                    Cell::from(Text::from(format!(
+                        "\n{}\n",
+                        RESTART_DIVIDER.replace('─', symbols().horizontal)
+                    )))
+                } else {
+                    Cell::from(message_text(data, app.show_colors, &segments))
+                },
+            ])
+            .style(style.bg(color))
+            .height(u16::try_from(height).unwrap_or(u16::MAX))
+        })
+        .collect();
+
+    // wrapped rows are taller, so the scrollbar has to count lines
+    if app.wrap != WrapMode::Off {
+        let position = heights.iter().take(selected).sum();
+        app.scroll_state =
+            ScrollbarState::new(heights.iter().sum::<usize>().saturating_sub(1)).position(position);
+    }
+    let selected_height = heights.get(selected).copied().unwrap_or(ITEM_HEIGHT);
+    let mut symbol = vec![Line::from("")];
+    symbol.extend((2..selected_height).map(|_| Line::from(format!(" {} ", symbols().bar))));
+    symbol.push(Line::from(""));
+
     let t = Table::new(
         rows,
         [
@@ -109,7 +222,7 @@ fn render_table(f: &mut Frame, app: &mut App, area: Rect) {
     )
     .header(header)
     .row_highlight_style(selected_style)
-    .highlight_symbol(Text::from(vec!["".into(), bar.into(), "".into()]))
+    .highlight_symbol(Text::from(symbol))
     .bg(app.colors.buffer_bg)
     .highlight_spacing(HighlightSpacing::Always);
     f.render_stateful_widget(t, area, &mut app.state);
@@ -0,0 +1,176 @@
+//!Vim style marks: `m` and a letter marks the selected replicaset, pod or
+//!container and `'` and the letter jumps back to it from any table,
+//!opening the view that lists it when another view has the keys.  Marks
+//!last for the session, a resource holds one mark at a time.
+//!
+use crate::error::{Error, Result as NvResult};
+use crate::k8s::cache::cache_manager;
+use crate::tui::table_ui::TuiTableState;
+use crate::tui::ui_loop::{create_container_data_vec, Apps};
+use crate::tui::{container_app, pod_app, rs_app};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// the `m` or `'` typed so far, waiting for its letter
+static PENDING: Mutex<Option<char>> = Mutex::new(None);
+
+static MARKS: Mutex<BTreeMap<char, Resource>> = Mutex::new(BTreeMap::new());
+
+/// the mark a view opened by a jump selects once its rows arrive
+static LANDING: Mutex<Option<Resource>> = Mutex::new(None);
+
+/// the table a marked row is listed in
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Scope {
+    ReplicaSets,
+    Pods(BTreeMap<String, String>),
+    Containers {
+        selector: BTreeMap<String, String>,
+        pod: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Resource {
+    pub scope: Scope,
+    pub name: String,
+}
+
+/// what a key did to the mark being typed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// `m` or `'`, or the key after them that was not a letter
+    Pending,
+    Set(char),
+    Jump(char),
+    /// not a mark, the view handles it
+    Other,
+}
+
+/// Take `key` into a mark, `pending` being the `m` or `'` before it.
+fn step_from(pending: &mut Option<char>, key: &KeyEvent) -> Step {
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        *pending = None;
+        return Step::Other;
+    }
+    let KeyCode::Char(c) = key.code else {
+        return if pending.take().is_some() {
+            Step::Pending
+        } else {
+            Step::Other
+        };
+    };
+    match pending.take() {
+        Some('m') if c.is_ascii_alphabetic() => Step::Set(c),
+        Some(_) if c.is_ascii_alphabetic() => Step::Jump(c),
+        Some(_) => Step::Pending,
+        None if c == 'm' || c == '\'' => {
+            *pending = Some(c);
+            Step::Pending
+        }
+        None => Step::Other,
+    }
+}
+
+/// `key` taken into the mark the view with the keys is typing
+#[must_use]
+pub fn step(key: &KeyEvent) -> Step {
+    PENDING
+        .lock()
+        .map_or(Step::Other, |mut pending| step_from(&mut pending, key))
+}
+
+/// Mark `resource` as `letter`, moving its mark when it had another.
+pub fn set(letter: char, resource: Resource) {
+    if let Ok(mut marks) = MARKS.lock() {
+        marks.retain(|_, marked| *marked != resource);
+        marks.insert(letter, resource);
+    }
+}
+
+/// # Errors
+///
+/// Will return `Err` if nothing is marked as `letter`
+pub fn get(letter: char) -> NvResult<Resource> {
+    MARKS
+        .lock()
+        .ok()
+        .and_then(|marks| marks.get(&letter).cloned())
+        .ok_or_else(|| Error::NotFound(format!("no mark '{letter}'")))
+}
+
+/// Select the mark a jump opened `table` for, once its rows list it.
+pub fn land<T: TuiTableState>(table: &mut T) {
+    let Ok(mut landing) = LANDING.lock() else {
+        return;
+    };
+    let Some(resource) = landing.as_ref() else {
+        return;
+    };
+    if table.mark_scope().as_ref() == Some(&resource.scope) && table.select_named(&resource.name) {
+        *landing = None;
+    }
+}
+
+/// # Errors
+///
+/// Will return `Err` if nothing is marked as `letter` or the containers of
+/// a marked container's pod can not be listed
+pub async fn open(letter: char) -> NvResult<Apps> {
+    let resource = get(letter)?;
+    if let Ok(mut landing) = LANDING.lock() {
+        *landing = Some(resource.clone());
+    }
+    Ok(match resource.scope {
+        Scope::ReplicaSets => {
+            let mut app =
+                rs_app::app::App::new(cache_manager::cached_replicasets().unwrap_or_default());
+            land(&mut app);
+            Apps::new(app)
+        }
+        Scope::Pods(selector) => {
+            let data_vec = cache_manager::cached_pods(&selector).unwrap_or_default();
+            let mut app = pod_app::app::App::new(selector, data_vec);
+            land(&mut app);
+            Apps::new(app)
+        }
+        Scope::Containers { selector, pod } => {
+            let data_vec = create_container_data_vec(selector, pod).await?;
+            let mut app = container_app::app::App::new(data_vec);
+            land(&mut app);
+            Apps::new(app)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(pending: &mut Option<char>, typed: &str) -> Vec<Step> {
+        typed
+            .chars()
+            .map(|c| step_from(pending, &KeyEvent::from(KeyCode::Char(c))))
+            .collect()
+    }
+
+    #[test]
+    fn test_mark_keys() {
+        let mut pending = None;
+        assert_eq!(keys(&mut pending, "ma"), [Step::Pending, Step::Set('a')]);
+        assert_eq!(keys(&mut pending, "'a"), [Step::Pending, Step::Jump('a')]);
+        assert_eq!(
+            keys(&mut pending, "m1j"),
+            [Step::Pending, Step::Pending, Step::Other]
+        );
+        assert_eq!(
+            step_from(&mut Some('m'), &KeyEvent::from(KeyCode::Esc)),
+            Step::Pending
+        );
+        assert_eq!(
+            step_from(&mut None, &KeyEvent::from(KeyCode::Esc)),
+            Step::Other
+        );
+    }
+}
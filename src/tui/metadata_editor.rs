@@ -0,0 +1,142 @@
+//!Overlay to add or remove a label or annotation on the selected object,
+//!shared by the views that list objects one can label.
+//!
+use crate::k8s::actions::{apply, last_edit, undo_last, MetadataEdit, MetadataKind, Target};
+use crate::tui::theme::symbols;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+
+/// what the view should do after the editor handled a key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MetadataAction {
+    None,
+    Apply(MetadataEdit),
+    Undo,
+    Close,
+}
+
+#[derive(Clone, Debug)]
+pub struct MetadataEditor {
+    targets: Vec<Target>,
+    target_index: usize,
+    kind: MetadataKind,
+    input: String,
+    /// parsed edit waiting for 'y'
+    pending: Option<MetadataEdit>,
+    pub(crate) message: Option<String>,
+}
+
+impl MetadataEditor {
+    /// `None` without targets
+    #[must_use]
+    pub fn new(targets: Vec<Target>) -> Option<Self> {
+        (!targets.is_empty()).then(|| Self {
+            targets,
+            target_index: 0,
+            kind: MetadataKind::Label,
+            input: String::new(),
+            pending: None,
+            message: None,
+        })
+    }
+
+    pub fn handle_key(&mut self, key: &KeyEvent) -> MetadataAction {
+        if let Some(edit) = self.pending.take() {
+            if key.code == KeyCode::Char('y') {
+                self.input.clear();
+                return MetadataAction::Apply(edit);
+            }
+            self.message = Some("not applied".to_string());
+            return MetadataAction::None;
+        }
+        match key.code {
+            KeyCode::Esc => return MetadataAction::Close,
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return MetadataAction::Undo;
+            }
+            KeyCode::Tab => self.target_index = (self.target_index + 1) % self.targets.len(),
+            KeyCode::BackTab => {
+                self.kind = match self.kind {
+                    MetadataKind::Label => MetadataKind::Annotation,
+                    MetadataKind::Annotation => MetadataKind::Label,
+                };
+            }
+            KeyCode::Enter => {
+                match MetadataEdit::parse(
+                    self.targets[self.target_index].clone(),
+                    self.kind,
+                    &self.input,
+                ) {
+                    Ok(edit) => {
+                        self.message = Some(format!("{edit}? y to confirm"));
+                        self.pending = Some(edit);
+                    }
+                    Err(e) => self.message = Some(e),
+                }
+            }
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Char(c) => self.input.push(c),
+            _ => {}
+        }
+        MetadataAction::None
+    }
+}
+
+/// Handle a key for an open editor, applying or undoing edits.  Failures
+/// stay in the editor so the input can be fixed.
+pub async fn handle_key(editor: &mut Option<Box<MetadataEditor>>, key: &KeyEvent) {
+    let Some(open) = editor.as_mut() else {
+        return;
+    };
+    match open.handle_key(key) {
+        MetadataAction::Close => *editor = None,
+        MetadataAction::Apply(edit) => {
+            let summary = edit.to_string();
+            open.message = Some(match apply(edit).await {
+                Ok(()) => format!("done: {summary}"),
+                Err(e) => format!("{}: {e}", e.code()),
+            });
+        }
+        MetadataAction::Undo => {
+            open.message = Some(match undo_last().await {
+                Ok(restore) => format!("undone: {restore}"),
+                Err(e) => format!("{}: {e}", e.code()),
+            });
+        }
+        MetadataAction::None => {}
+    }
+}
+
+pub fn render(f: &mut Frame, editor: &MetadataEditor) {
+    let area = Layout::vertical([Constraint::Length(9)])
+        .flex(layout::Flex::Center)
+        .split(f.area())[0];
+    let area = Layout::horizontal([Constraint::Percentage(60)])
+        .flex(layout::Flex::Center)
+        .split(area)[0];
+    let undo = last_edit().map_or_else(String::new, |edit| format!("ctrl-u undoes: {edit}"));
+    let text = vec![
+        Line::from(format!("{}{}", editor.input, symbols().cursor))
+            .style(Style::new().add_modifier(Modifier::BOLD)),
+        Line::from(""),
+        Line::from(editor.message.clone().unwrap_or_default())
+            .style(Style::new().fg(Color::Yellow)),
+        Line::from(undo),
+        Line::from("key=value sets, key- removes  tab target  shift-tab label/annotation")
+            .style(Style::new().add_modifier(Modifier::DIM)),
+    ];
+    let paragraph = Paragraph::new(text)
+        .wrap(Wrap { trim: true })
+        .style(Style::new().fg(Color::White).bg(Color::Black))
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            " {} on {} ",
+            editor.kind, editor.targets[editor.target_index]
+        )));
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
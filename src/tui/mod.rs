@@ -1,13 +1,44 @@
+mod capacity_app;
 mod cert_app;
+mod changes_app;
+mod cluster_app;
 mod container_app;
+mod container_group_app;
+mod crash;
 pub mod data;
+mod deployment_app;
+mod diag_app;
+mod error_card;
 mod event_app;
+mod export_dialog;
+mod filter_history;
+mod filter_pattern;
+mod gateway_app;
 mod ingress_app;
 mod log_app;
+mod marks;
+mod metadata_editor;
+mod motions;
+mod namespace_dialog;
+mod noisy_app;
+mod open_menu;
+mod panes;
+mod plugin_app;
+mod plugin_menu;
 mod pod_app;
+mod quit_dialog;
+mod registry;
 mod rs_app;
+pub(crate) mod sort;
+mod statefulset_app;
+mod status_line;
 mod stream;
 mod style;
+mod table_app;
+mod table_search;
 mod table_ui;
+pub(crate) mod theme;
+mod tile_app;
+mod tiles;
 pub mod ui_loop;
-mod utils;
+pub mod utils;
@@ -0,0 +1,154 @@
+//!Vim style motions shared by every table: a count typed before `j` or `k`
+//!moves that many rows, `gg` and `G` jump to the first and last row,
+//!`{n}G` to row n, ctrl-d and ctrl-u move half a page and ctrl-f and
+//!ctrl-b a page.  Without a count `j` and `k` stay with the view, which
+//!may do more on them than move.
+//!
+use crate::tui::style::ITEM_HEIGHT;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::sync::Mutex;
+
+/// counts past this are taken as this
+const MAX_COUNT: usize = 100_000;
+
+/// the count and `g` typed so far, for whichever view has the keys
+static PENDING: Mutex<Motions> = Mutex::new(Motions {
+    count: None,
+    g: false,
+});
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Motion {
+    Down(usize),
+    Up(usize),
+    First,
+    Last,
+    /// 1 based, as typed
+    Row(usize),
+}
+
+impl Motion {
+    /// the row selected after moving from `row` in a table of `len` rows
+    #[must_use]
+    pub fn target(self, row: usize, len: usize) -> usize {
+        let last = len.saturating_sub(1);
+        match self {
+            Self::Down(n) => row.saturating_add(n).min(last),
+            Self::Up(n) => row.saturating_sub(n),
+            Self::First => 0,
+            Self::Last => last,
+            Self::Row(n) => n.saturating_sub(1).min(last),
+        }
+    }
+}
+
+/// what a key did to the motion being typed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// part of a count or the first `g`
+    Pending,
+    Motion(Motion),
+    /// not a motion, the view handles it
+    Other,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Motions {
+    count: Option<usize>,
+    g: bool,
+}
+
+impl Motions {
+    /// Take `key` into the motion being typed, `page` rows being a page.
+    pub fn step(&mut self, key: &KeyEvent, page: usize) -> Step {
+        let count = self.count.take();
+        let g = std::mem::take(&mut self.g);
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        let motion = match key.code {
+            KeyCode::Char(digit @ '0'..='9') if !control && (digit != '0' || count.is_some()) => {
+                let digit = digit.to_digit(10).map_or(0, |digit| digit as usize);
+                self.count = Some(
+                    count
+                        .unwrap_or(0)
+                        .saturating_mul(10)
+                        .saturating_add(digit)
+                        .min(MAX_COUNT),
+                );
+                return Step::Pending;
+            }
+            KeyCode::Char('g') if !control && g => count.map_or(Motion::First, Motion::Row),
+            KeyCode::Char('g') if !control => {
+                self.count = count;
+                self.g = true;
+                return Step::Pending;
+            }
+            KeyCode::Char('G') => count.map_or(Motion::Last, Motion::Row),
+            KeyCode::Char('j') | KeyCode::Down if !control && count.is_some() => {
+                Motion::Down(count.unwrap_or(1))
+            }
+            KeyCode::Char('k') | KeyCode::Up if !control && count.is_some() => {
+                Motion::Up(count.unwrap_or(1))
+            }
+            KeyCode::Char('d') if control => Motion::Down((page / 2).max(1)),
+            KeyCode::Char('u') if control => Motion::Up((page / 2).max(1)),
+            KeyCode::Char('f') if control => Motion::Down(page.max(1)),
+            KeyCode::Char('b') if control => Motion::Up(page.max(1)),
+            _ => return Step::Other,
+        };
+        Step::Motion(motion)
+    }
+}
+
+/// about how many rows a table shows, the details below take the rest
+#[must_use]
+pub fn page_rows() -> usize {
+    let height = crossterm::terminal::size().map_or(24, |(_, height)| usize::from(height));
+    (height * 3 / 5 / ITEM_HEIGHT).max(1)
+}
+
+/// `key` taken into the motion the view with the keys is typing
+#[must_use]
+pub fn step(key: &KeyEvent) -> Step {
+    PENDING
+        .lock()
+        .map_or(Step::Other, |mut motions| motions.step(key, page_rows()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(motions: &mut Motions, typed: &str) -> Vec<Step> {
+        typed
+            .chars()
+            .map(|c| motions.step(&KeyEvent::from(KeyCode::Char(c)), 20))
+            .collect()
+    }
+
+    #[test]
+    fn test_counts_and_jumps() {
+        let mut motions = Motions::default();
+        assert_eq!(
+            keys(&mut motions, "10j"),
+            [Step::Pending, Step::Pending, Step::Motion(Motion::Down(10))]
+        );
+        assert_eq!(keys(&mut motions, "j"), [Step::Other]);
+        assert_eq!(
+            keys(&mut motions, "gg"),
+            [Step::Pending, Step::Motion(Motion::First)]
+        );
+        assert_eq!(keys(&mut motions, "G"), [Step::Motion(Motion::Last)]);
+        assert_eq!(keys(&mut motions, "42G")[2], Step::Motion(Motion::Row(42)));
+        assert_eq!(keys(&mut motions, "0"), [Step::Other]);
+        assert_eq!(keys(&mut motions, "5x"), [Step::Pending, Step::Other]);
+        assert_eq!(keys(&mut motions, "k"), [Step::Other]);
+        let ctrl_d = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(motions.step(&ctrl_d, 20), Step::Motion(Motion::Down(10)));
+
+        assert_eq!(Motion::Down(10).target(95, 100), 99);
+        assert_eq!(Motion::Up(10).target(5, 100), 0);
+        assert_eq!(Motion::Row(42).target(0, 100), 41);
+        assert_eq!(Motion::Row(420).target(0, 100), 99);
+        assert_eq!(Motion::Last.target(3, 0), 0);
+    }
+}
@@ -0,0 +1,196 @@
+//!Overlay to create a namespace from a label preset or delete an empty one,
+//!opened from the namespace rows of the cluster view.
+//!
+use crate::k8s::namespaces::{create, delete_empty, NewNamespace, PRESETS};
+use crate::tui::theme::symbols;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+
+#[derive(Clone, Debug)]
+pub enum Mode {
+    /// `preset` indexes `PRESETS`, `pending` waits for 'y'
+    Create {
+        preset: usize,
+        pending: Option<NewNamespace>,
+    },
+    /// `contents` is what the dry-run found, only an empty namespace is
+    /// deleted, `blocked` says why when it can not be
+    Delete {
+        namespace: String,
+        contents: Vec<String>,
+        blocked: Option<String>,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct NamespaceDialog {
+    mode: Mode,
+    input: String,
+    pub(crate) message: Option<String>,
+}
+
+impl NamespaceDialog {
+    #[must_use]
+    pub const fn create() -> Self {
+        Self {
+            mode: Mode::Create {
+                preset: 0,
+                pending: None,
+            },
+            input: String::new(),
+            message: None,
+        }
+    }
+
+    /// `contents` as the dry-run delete listed them, or why it failed
+    #[must_use]
+    pub fn delete(namespace: String, contents: Result<Vec<String>, String>) -> Self {
+        let (contents, blocked) = match contents {
+            Ok(contents) if contents.is_empty() => (contents, None),
+            Ok(contents) => (
+                contents,
+                Some("not empty, delete what it holds first".to_string()),
+            ),
+            Err(e) => (vec![], Some(e)),
+        };
+        Self {
+            mode: Mode::Delete {
+                namespace,
+                contents,
+                blocked,
+            },
+            input: String::new(),
+            message: None,
+        }
+    }
+}
+
+/// Handle a key for an open dialog, creating or deleting on confirmation.
+/// Failures stay in the dialog so the input can be fixed.
+pub async fn handle_key(dialog: &mut Option<Box<NamespaceDialog>>, key: &KeyEvent) {
+    let Some(open) = dialog.as_mut() else {
+        return;
+    };
+    if key.code == KeyCode::Esc {
+        *dialog = None;
+        return;
+    }
+    match &mut open.mode {
+        Mode::Create { preset, pending } => {
+            if let Some(namespace) = pending.take() {
+                open.message = Some(if key.code == KeyCode::Char('y') {
+                    open.input.clear();
+                    match create(&namespace).await {
+                        Ok(()) => format!("done: {namespace}"),
+                        Err(e) => format!("{}: {e}", e.code()),
+                    }
+                } else {
+                    "not created".to_string()
+                });
+                return;
+            }
+            match key.code {
+                KeyCode::Tab => *preset = (*preset + 1) % PRESETS.len(),
+                KeyCode::Enter => match NewNamespace::parse(&open.input, *preset) {
+                    Ok(namespace) => {
+                        open.message = Some(format!("{namespace}? y to confirm"));
+                        *pending = Some(namespace);
+                    }
+                    Err(e) => open.message = Some(e),
+                },
+                KeyCode::Backspace => {
+                    open.input.pop();
+                }
+                KeyCode::Char(c) => open.input.push(c),
+                _ => {}
+            }
+        }
+        Mode::Delete {
+            namespace, blocked, ..
+        } => {
+            if blocked.is_some() {
+                return;
+            }
+            match key.code {
+                KeyCode::Enter => {
+                    open.message = Some(match delete_empty(namespace, &open.input).await {
+                        Ok(()) => {
+                            *blocked = Some("deleted".to_string());
+                            format!("done: namespace {namespace} is terminating")
+                        }
+                        Err(e) => format!("{}: {e}", e.code()),
+                    });
+                }
+                KeyCode::Backspace => {
+                    open.input.pop();
+                }
+                KeyCode::Char(c) => open.input.push(c),
+                _ => {}
+            }
+        }
+    }
+}
+
+pub fn render(f: &mut Frame, dialog: &NamespaceDialog) {
+    let area = Layout::vertical([Constraint::Length(14)])
+        .flex(layout::Flex::Center)
+        .split(f.area())[0];
+    let area = Layout::horizontal([Constraint::Percentage(60)])
+        .flex(layout::Flex::Center)
+        .split(area)[0];
+    let message = Line::from(dialog.message.clone().unwrap_or_default())
+        .style(Style::new().fg(Color::Yellow));
+    let input = Line::from(format!("{}{}", dialog.input, symbols().cursor))
+        .style(Style::new().add_modifier(Modifier::BOLD));
+    let dim = Style::new().add_modifier(Modifier::DIM);
+    let (title, text) = match &dialog.mode {
+        Mode::Create { preset, .. } => (
+            " new namespace ".to_string(),
+            vec![
+                input,
+                Line::from(format!("labels: {}", PRESETS[*preset].0)),
+                Line::from(""),
+                message,
+                Line::from("enter creates  tab next label preset  esc closes").style(dim),
+            ],
+        ),
+        Mode::Delete {
+            namespace,
+            contents,
+            blocked,
+        } => {
+            let mut text = vec![];
+            if let Some(blocked) = blocked {
+                text.push(Line::from(blocked.clone()).style(Style::new().fg(Color::Red)));
+                text.extend(
+                    contents
+                        .iter()
+                        .take(6)
+                        .map(|c| Line::from(format!("  {c}"))),
+                );
+                if contents.len() > 6 {
+                    text.push(Line::from(format!("  and {} more", contents.len() - 6)));
+                }
+            } else {
+                text.push(Line::from("empty, the dry-run delete was accepted"));
+                text.push(Line::from(format!(
+                    "type {namespace} and enter to delete it"
+                )));
+                text.push(input);
+            }
+            text.push(Line::from(""));
+            text.push(message);
+            text.push(Line::from("esc closes").style(dim));
+            (format!(" delete namespace {namespace} "), text)
+        }
+    };
+    let paragraph = Paragraph::new(text)
+        .wrap(Wrap { trim: true })
+        .style(Style::new().fg(Color::White).bg(Color::Black))
+        .block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
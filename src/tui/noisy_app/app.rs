@@ -0,0 +1,91 @@
+use crate::error::Error;
+use crate::error::Result as NvResult;
+use crate::k8s::events::format_duration;
+use crate::k8s::noisy::{self, NoisyObject, DEFAULT_WINDOW_MINUTES};
+use crate::tui::stream::Message;
+use crate::tui::table_app::{self, TableApp};
+use crate::tui::ui_loop::{AppBehavior, Transition};
+use chrono::Duration;
+use crossterm::event::{Event, KeyEventKind};
+use futures::Stream;
+use ratatui::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::debug;
+
+const POLL_MS: u64 = 10000;
+
+/// The namespace's events by object and reason, the noisiest first.
+#[derive(Clone, Debug)]
+pub struct App {
+    pub(crate) table: TableApp<NoisyObject>,
+}
+
+impl AppBehavior for App {
+    const NAME: &'static str = "noisy events";
+
+    async fn handle_event(&mut self, event: &Message) -> NvResult<Transition> {
+        match event {
+            Message::Key(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                return Ok(self.table.handle_key(key));
+            }
+            Message::Noisy(data_vec) => {
+                debug!("updating noisy events app data...");
+                self.table.set_items(data_vec);
+            }
+            _ => {}
+        }
+        Ok(Transition::Stay)
+    }
+
+    fn draw_ui<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<(), std::io::Error> {
+        terminal.draw(|f| table_app::ui(f, &mut self.table))?;
+        Ok(())
+    }
+
+    fn stream(&self, should_stop: Arc<AtomicBool>) -> impl Stream<Item = Message> {
+        let (tx, rx) = mpsc::channel(100);
+
+        tokio::spawn(async move {
+            while !should_stop.load(Ordering::Relaxed) {
+                match noisy::list(window()).await {
+                    Ok(d) => {
+                        if tx.send(Message::Noisy(d)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(Error::Timeout { .. }) => {
+                        // already surfaced in the header, keep polling
+                    }
+                    Err(_e) => {
+                        break;
+                    }
+                }
+                sleep(std::time::Duration::from_millis(POLL_MS)).await;
+            }
+        });
+
+        ReceiverStream::new(rx)
+    }
+}
+
+fn window() -> Duration {
+    Duration::minutes(DEFAULT_WINDOW_MINUTES)
+}
+
+impl App {
+    pub fn new() -> Self {
+        let mut table = TableApp::new(vec![]);
+        table.note = format!("last {}", format_duration(window()));
+        Self { table }
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,140 @@
+//!"Open in…" menu: the dashboards of the config file that apply to the
+//!selected resource, opened in the browser.
+//!
+use crate::config::settings;
+use crate::k8s::client_manager::{current_context, get_client};
+use crate::tui::theme::symbols;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+use std::collections::BTreeMap;
+use std::io;
+use std::process::{Command, Stdio};
+
+#[derive(Clone, Debug)]
+pub struct OpenMenu {
+    /// (dashboard name, url)
+    links: Vec<(String, String)>,
+    selected: usize,
+    pub(crate) message: Option<String>,
+}
+
+impl OpenMenu {
+    /// the dashboards whose variables are all in `vars`
+    #[must_use]
+    pub fn new(vars: &BTreeMap<&str, String>) -> Self {
+        let links: Vec<(String, String)> = settings()
+            .dashboards
+            .iter()
+            .filter_map(|dashboard| Some((dashboard.name.clone(), dashboard.url(vars)?)))
+            .collect();
+        let message = links
+            .is_empty()
+            .then(|| "no dashboards for this view in config.yaml".to_string());
+        Self {
+            links,
+            selected: 0,
+            message,
+        }
+    }
+}
+
+/// the variables every view has, `namespace` defaulting to the client's
+pub async fn context_vars(namespace: Option<String>) -> BTreeMap<&'static str, String> {
+    let namespace = match namespace {
+        Some(namespace) => namespace,
+        None => get_client()
+            .await
+            .map(|client| client.default_namespace().to_string())
+            .unwrap_or_default(),
+    };
+    BTreeMap::from([("namespace", namespace), ("context", current_context())])
+}
+
+fn open_url(url: &str) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    command
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+}
+
+/// Move through the links, enter opens one and closes the menu.  A failed
+/// open stays in the menu with the url to copy instead.
+pub fn handle_key(menu: &mut Option<Box<OpenMenu>>, key: &KeyEvent) {
+    let Some(open) = menu.as_mut() else {
+        return;
+    };
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q' | 'w') => *menu = None,
+        KeyCode::Char('j') | KeyCode::Down => {
+            open.selected = (open.selected + 1).min(open.links.len().saturating_sub(1));
+        }
+        KeyCode::Char('k') | KeyCode::Up => open.selected = open.selected.saturating_sub(1),
+        KeyCode::Enter => {
+            if let Some((_, url)) = open.links.get(open.selected) {
+                match open_url(url) {
+                    Ok(()) => *menu = None,
+                    Err(e) => open.message = Some(format!("can not open {url}: {e}")),
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn render(f: &mut Frame, menu: &OpenMenu) {
+    let height = u16::try_from(menu.links.len()).unwrap_or(u16::MAX) + 4;
+    let area = Layout::vertical([Constraint::Length(height)])
+        .flex(layout::Flex::Center)
+        .split(f.area())[0];
+    let area = Layout::horizontal([Constraint::Percentage(60)])
+        .flex(layout::Flex::Center)
+        .split(area)[0];
+    let mut lines: Vec<Line> = menu
+        .links
+        .iter()
+        .enumerate()
+        .map(|(i, (name, url))| {
+            let line = Line::from(vec![
+                Span::styled(
+                    format!("{name}  "),
+                    Style::new().add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(url.clone(), Style::new().add_modifier(Modifier::DIM)),
+            ]);
+            if i == menu.selected {
+                line.style(Style::new().add_modifier(Modifier::REVERSED))
+            } else {
+                line
+            }
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(
+        Line::from(menu.message.clone().unwrap_or_default()).style(Style::new().fg(Color::Yellow)),
+    );
+    let paragraph = Paragraph::new(lines)
+        .style(Style::new().fg(Color::White).bg(Color::Black))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" open in{} ", symbols().ellipsis))
+                .title_bottom(" j/k to move, enter to open, esc to close "),
+        );
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
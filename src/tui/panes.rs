@@ -0,0 +1,141 @@
+//!Exec shells and log tails in a tmux or wezterm pane next to navipod, so
+//!long sessions do not take over the TUI.
+//!
+use crate::config::{fill, settings};
+use crate::error::{Error, Result as NvResult};
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use tokio::process::Command;
+
+/// the context is passed so the pane talks to the cluster navipod shows
+const EXEC: &str = "kubectl --context {context} -n {namespace} exec -it {pod} -c {container} -- sh";
+const LOGS: &str =
+    "kubectl --context {context} -n {namespace} logs -f --tail 200 {pod} -c {container}";
+
+const TMUX: &str = "tmux split-window -h {command}";
+const WEZTERM: &str = "wezterm cli split-pane --right -- sh -c {command}";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Session {
+    Exec,
+    Logs,
+}
+
+/// single quoted for sh unless it is plainly a name
+fn quote(value: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./:=@".contains(c);
+    if !value.is_empty() && value.chars().all(plain) {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+/// the program and arguments opening a pane that runs `command`
+fn pane_command(
+    open: Option<&str>,
+    command: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> NvResult<Vec<String>> {
+    let open = open
+        .or_else(|| lookup("TMUX").map(|_| TMUX))
+        .or_else(|| lookup("WEZTERM_PANE").map(|_| WEZTERM))
+        .ok_or_else(|| Error::Pane("not inside tmux or wezterm".to_string()))?;
+    let vars = BTreeMap::from([("command", command.to_string())]);
+    let argv = open
+        .split_whitespace()
+        .map(|word| fill(word, &vars))
+        .collect::<Option<Vec<String>>>()
+        .ok_or_else(|| Error::Pane(format!("only {{command}} can be used in {open}")))?;
+    if argv.is_empty() {
+        return Err(Error::Pane("panes.open is empty".to_string()));
+    }
+    Ok(argv)
+}
+
+/// Open a pane running the `session` command of the config file for the
+/// container in `vars`.
+///
+/// # Errors
+///
+/// Will return `Err` if there is no multiplexer to ask or it refuses
+pub async fn open(session: Session, vars: &BTreeMap<&str, String>) -> NvResult<()> {
+    let panes = &settings().panes;
+    let template = match session {
+        Session::Exec => panes.exec.as_deref().unwrap_or(EXEC),
+        Session::Logs => panes.logs.as_deref().unwrap_or(LOGS),
+    };
+    let quoted = vars
+        .iter()
+        .map(|(name, value)| (*name, quote(value)))
+        .collect();
+    let command = fill(template, &quoted).ok_or_else(|| {
+        Error::Pane(format!(
+            "{template} needs a variable this view does not have"
+        ))
+    })?;
+    let argv = pane_command(panes.open.as_deref(), &command, |name| {
+        std::env::var(name).ok()
+    })?;
+    let output = Command::new(&argv[0])
+        .args(&argv[1..])
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| Error::Pane(format!("{}: {e}", argv[0])))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(Error::Pane(format!(
+            "{}: {}",
+            argv[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pane_command() {
+        assert_eq!(quote("web-5d8f7-abc"), "web-5d8f7-abc");
+        assert_eq!(quote("it's"), r"'it'\''s'");
+        assert_eq!(quote(""), "''");
+
+        let tmux = |name: &str| (name == "TMUX").then(|| "/tmp/tmux-1000/default".to_string());
+        assert_eq!(
+            pane_command(None, "kubectl logs -f web", tmux).unwrap(),
+            ["tmux", "split-window", "-h", "kubectl logs -f web"]
+        );
+        let wezterm = |name: &str| (name == "WEZTERM_PANE").then(|| "0".to_string());
+        assert_eq!(
+            pane_command(None, "kubectl logs -f web", wezterm).unwrap()[..4],
+            ["wezterm", "cli", "split-pane", "--right"]
+        );
+        assert_eq!(
+            pane_command(Some("tmux new-window {command}"), "top", |_| None).unwrap(),
+            ["tmux", "new-window", "top"]
+        );
+        assert!(matches!(
+            pane_command(None, "top", |_| None),
+            Err(Error::Pane(_))
+        ));
+        assert!(pane_command(Some("tmux {pod}"), "top", tmux).is_err());
+
+        let vars = BTreeMap::from([
+            ("context", "prod".to_string()),
+            ("namespace", "web".to_string()),
+            ("pod", "web-1".to_string()),
+            ("container", "app".to_string()),
+        ]);
+        assert_eq!(
+            fill(LOGS, &vars).unwrap(),
+            "kubectl --context prod -n web logs -f --tail 200 web-1 -c app"
+        );
+        assert!(fill(EXEC, &vars)
+            .unwrap()
+            .starts_with("kubectl --context prod -n web exec"));
+    }
+}
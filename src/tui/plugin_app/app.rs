@@ -0,0 +1,150 @@
+use crate::error::{Error, Result as NvResult};
+use crate::k8s::cache::{cache_manager, CachedData, DataRequest};
+use crate::plugins::{plugins_dir, view_rows, views, Plugin};
+use crate::tui::data::PluginRow;
+use crate::tui::error_card;
+use crate::tui::filter_history::FilterRecall;
+use crate::tui::stream::Message;
+use crate::tui::table_app::{self, TableApp};
+use crate::tui::ui_loop::{AppBehavior, Apps, Transition};
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use futures::Stream;
+use ratatui::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+use tokio_stream::wrappers::ReceiverStream;
+
+const POLL_MS: u64 = 10000;
+
+/// the table view of a plugin
+#[derive(Clone, Debug)]
+pub struct App {
+    pub(crate) plugin: String,
+    pub(crate) table: TableApp<PluginRow>,
+}
+
+impl AppBehavior for App {
+    const NAME: &'static str = "plugin";
+
+    async fn handle_event(&mut self, event: &Message) -> NvResult<Transition> {
+        match event {
+            Message::Key(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                if self.table.handle_sort_key(key) {
+                    return Ok(Transition::Stay);
+                }
+                if !self.table.show_filter_edit {
+                    match key.code {
+                        // the view of another plugin polls its own rows
+                        KeyCode::Tab => {
+                            if let Some(app) = self.next_view() {
+                                return Ok(Transition::Push(Box::new(Apps::new(app))));
+                            }
+                            return Ok(Transition::Stay);
+                        }
+                        KeyCode::Char('R') => return Ok(Transition::Refresh),
+                        _ => {}
+                    }
+                }
+                return Ok(self.table.handle_key(key));
+            }
+            Message::Plugin(data_vec) => self.table.set_sorted_items(data_vec),
+            _ => {}
+        }
+        Ok(Transition::Stay)
+    }
+
+    fn draw_ui<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<(), std::io::Error> {
+        terminal.draw(|f| table_app::ui(f, &mut self.table))?;
+        Ok(())
+    }
+
+    fn source(&self) -> Option<DataRequest> {
+        Some(DataRequest::Custom {
+            plugin: self.plugin.clone(),
+        })
+    }
+
+    fn stream(&self, should_stop: Arc<AtomicBool>) -> impl Stream<Item = Message> {
+        let (tx, rx) = mpsc::channel(100);
+
+        let initial_items = self.table.items.clone();
+        let plugin = self.plugin.clone();
+
+        tokio::spawn(async move {
+            let mut last = initial_items;
+            while !should_stop.load(Ordering::Relaxed) {
+                match view_rows(&plugin).await {
+                    Ok(rows) => {
+                        let rows: Vec<PluginRow> = rows.into_iter().map(PluginRow::new).collect();
+                        cache_manager::get_cache().put(
+                            DataRequest::Custom {
+                                plugin: plugin.clone(),
+                            },
+                            CachedData::Custom(rows.clone()),
+                        );
+                        if rows != last {
+                            last.clone_from(&rows);
+                            if tx.send(Message::Plugin(rows)).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        cache_manager::get_cache().mark_failed(
+                            &DataRequest::Custom {
+                                plugin: plugin.clone(),
+                            },
+                            e.to_string(),
+                        );
+                        // a slow plugin gets another chance at the next poll
+                        if !matches!(e, Error::Timeout { .. }) {
+                            error_card::show(&e);
+                            break;
+                        }
+                    }
+                }
+                sleep(Duration::from_millis(POLL_MS)).await;
+            }
+        });
+
+        ReceiverStream::new(rx)
+    }
+}
+
+impl App {
+    /// starts with what the cache has, the plugin is asked right after
+    pub fn new(plugin: &Plugin) -> Self {
+        let name = plugin.manifest.name.clone();
+        // the view's title stands in for the first column's
+        let (title, mut headers) = plugin.manifest.view.as_ref().map_or_else(
+            || (name.clone(), vec![]),
+            |view| (view.title.clone(), view.columns.clone()),
+        );
+        match headers.first_mut() {
+            Some(first) => *first = title,
+            None => headers.push(title),
+        }
+        let data_vec = cache_manager::cached_plugin_rows(&name).unwrap_or_default();
+        let mut table = TableApp::new(data_vec).with_headers(headers);
+        table.recall = FilterRecall::new(&format!("plugin {name}"));
+        let dir = plugins_dir().map_or_else(String::new, |dir| dir.display().to_string());
+        table.footer = format!("plugin {name} from {dir}, tab for the next view");
+        Self {
+            plugin: name,
+            table,
+        }
+    }
+
+    /// the view of the plugin after this one, wrapping around
+    fn next_view(&self) -> Option<Self> {
+        let all: Vec<&Plugin> = views().collect();
+        let at = all
+            .iter()
+            .position(|plugin| plugin.manifest.name == self.plugin)?;
+        let next = all.get((at + 1) % all.len())?;
+        (next.manifest.name != self.plugin).then(|| Self::new(next))
+    }
+}
@@ -0,0 +1,117 @@
+//!The actions plugins offer for the selected resource.
+//!
+use crate::plugins::{find, plugins, Request, Target};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+#[derive(Clone, Debug)]
+pub struct PluginMenu {
+    target: Target,
+    /// (plugin name, action name)
+    actions: Vec<(String, String)>,
+    selected: usize,
+    pub(crate) message: Option<String>,
+}
+
+impl PluginMenu {
+    #[must_use]
+    pub fn new(target: Target) -> Self {
+        let actions: Vec<(String, String)> = plugins()
+            .iter()
+            .flat_map(|plugin| {
+                plugin
+                    .actions_for(&target.kind)
+                    .map(|action| (plugin.manifest.name.clone(), action.name.clone()))
+            })
+            .collect();
+        let message = actions
+            .is_empty()
+            .then(|| format!("no plugin has actions for a {}", target.kind));
+        Self {
+            target,
+            actions,
+            selected: 0,
+            message,
+        }
+    }
+}
+
+/// Move through the actions, enter runs one and shows what the plugin
+/// answered.
+pub async fn handle_key(menu: &mut Option<Box<PluginMenu>>, key: &KeyEvent) {
+    let Some(open) = menu.as_mut() else {
+        return;
+    };
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q' | 'V') => *menu = None,
+        KeyCode::Char('j') | KeyCode::Down => {
+            open.selected = (open.selected + 1).min(open.actions.len().saturating_sub(1));
+        }
+        KeyCode::Char('k') | KeyCode::Up => open.selected = open.selected.saturating_sub(1),
+        KeyCode::Enter => {
+            let Some((plugin, action)) = open.actions.get(open.selected).cloned() else {
+                return;
+            };
+            let Some(plugin) = find(&plugin) else {
+                return;
+            };
+            let request = Request::Action {
+                action: action.clone(),
+                target: open.target.clone(),
+            };
+            open.message = Some(match plugin.call(&request).await {
+                Ok(response) => response
+                    .message
+                    .unwrap_or_else(|| format!("{action}: done")),
+                Err(e) => format!("{}: {e}", e.code()),
+            });
+        }
+        _ => {}
+    }
+}
+
+pub fn render(f: &mut Frame, menu: &PluginMenu) {
+    let height = u16::try_from(menu.actions.len()).unwrap_or(u16::MAX) + 4;
+    let area = Layout::vertical([Constraint::Length(height)])
+        .flex(layout::Flex::Center)
+        .split(f.area())[0];
+    let area = Layout::horizontal([Constraint::Percentage(60)])
+        .flex(layout::Flex::Center)
+        .split(area)[0];
+    let mut lines: Vec<Line> = menu
+        .actions
+        .iter()
+        .enumerate()
+        .map(|(i, (plugin, action))| {
+            let line = Line::from(vec![
+                Span::styled(
+                    format!("{action}  "),
+                    Style::new().add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(plugin.clone(), Style::new().add_modifier(Modifier::DIM)),
+            ]);
+            if i == menu.selected {
+                line.style(Style::new().add_modifier(Modifier::REVERSED))
+            } else {
+                line
+            }
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(
+        Line::from(menu.message.clone().unwrap_or_default()).style(Style::new().fg(Color::Yellow)),
+    );
+    let paragraph = Paragraph::new(lines)
+        .style(Style::new().fg(Color::White).bg(Color::Black))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" {} {} ", menu.target.kind, menu.target.name))
+                .title_bottom(" j/k to move, enter to run, esc to close "),
+        );
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
@@ -1,28 +1,67 @@
-use crate::k8s::pods::list_rspods;
+use crate::error::Error;
+use crate::error::Result as NvResult;
+use crate::k8s::actions::Target;
+use crate::k8s::cache::cache_manager::get_cache;
+use crate::k8s::cache::{CachedData, DataRequest};
+use crate::k8s::compare::{compare_pods, get_pod};
+use crate::k8s::metrics_history;
+use crate::k8s::pods::list_rspods_paged;
+use crate::k8s::probes::{deployment_name, forget_pod, run_pod_probes, run_tuned_probe, schedule};
+use crate::k8s::scheduling::{summary, NodeVerdict};
+use crate::k8s::sidecars::{self, is_sidecar};
+use crate::k8s::timeline::pod_timeline;
+use crate::plugins;
 use crate::tui::container_app;
-use crate::tui::data::{pod_constraint_len_calculator, RsPod};
+use crate::tui::data::{pod_constraint_len_calculator, Filterable, RsPod};
+use crate::tui::error_card;
+use crate::tui::filter_pattern::FilterPattern;
 use crate::tui::ingress_app;
+use crate::tui::marks::{self, Scope};
+use crate::tui::metadata_editor::{self, MetadataEditor};
+use crate::tui::open_menu::{self, context_vars, OpenMenu};
+use crate::tui::plugin_menu::{self, PluginMenu};
 use crate::tui::pod_app;
+use crate::tui::pod_app::compare_view::CompareView;
+use crate::tui::pod_app::groups::{arrange, GroupBy};
+use crate::tui::pod_app::probe_editor::{EditorAction, ProbeEditor};
+use crate::tui::pod_app::timeline_view::TimelineView;
+use crate::tui::sort::{sort_rows, Columns, SortBy};
 use crate::tui::stream::Message;
 use crate::tui::style::{TableColors, ITEM_HEIGHT, PALETTES};
+use crate::tui::table_search::TableSearch;
 use crate::tui::table_ui::TuiTableState;
-use crate::tui::ui_loop::{create_container_data_vec, create_ingress_data_vec, AppBehavior, Apps};
+use crate::tui::tile_app::{self, app::Pane};
+use crate::tui::tiles::MAX_TILES;
+use crate::tui::ui_loop::{
+    create_container_data_vec, create_ingress_data_vec, AppBehavior, Apps, Transition,
+};
 use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
 use futures::Stream;
 use ratatui::prelude::*;
 use ratatui::widgets::{ScrollbarState, TableState};
-use std::collections::BTreeMap;
-use std::io;
+use std::collections::{BTreeMap, BTreeSet};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 use tokio::time::sleep;
 use tokio_stream::wrappers::ReceiverStream;
 use tracing::debug;
 
 const POLL_MS: u64 = 5000;
 
+/// what the left half of the details area shows for the selected pod
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DetailPanel {
+    Label,
+    Security,
+    Identity,
+    Probes,
+    Scheduling,
+    /// what plugins with a pod detail panel say
+    Plugin,
+}
+
 #[derive(Clone, Debug)]
 pub struct App {
     pub(crate) state: TableState,
@@ -33,11 +72,39 @@ pub struct App {
     pub(crate) color_index: usize,
     pub(crate) selector: BTreeMap<String, String>,
     pub(crate) filter: String,
+    pub(crate) detail_panel: DetailPanel,
+    pub(crate) probe_editor: Option<Box<ProbeEditor>>,
+    /// `namespace/name` of the pod pinned for comparing
+    pub(crate) pinned: Option<String>,
+    pub(crate) compare: Option<Box<CompareView>>,
+    pub(crate) timeline: Option<Box<TimelineView>>,
+    pub(crate) sort: Option<SortBy>,
+    pub(crate) group_by: Option<GroupBy>,
+    /// keys of the groups folded down to their first pod
+    pub(crate) collapsed: BTreeSet<String>,
+    pub(crate) metadata_editor: Option<Box<MetadataEditor>>,
+    pub(crate) open_menu: Option<Box<OpenMenu>>,
+    pub(crate) plugin_menu: Option<Box<PluginMenu>>,
+    /// (pod, details) fetched when the plugin panel was opened
+    pub(crate) plugin_details: Option<(String, Vec<(String, String)>)>,
+    /// pods to tail side by side, in the order they were marked
+    pub(crate) marked: Vec<String>,
+    pub(crate) search: TableSearch,
+    /// the running stream, for work spawned from a key to report back on
+    pub(crate) events: Arc<Mutex<Option<mpsc::Sender<Message>>>>,
 }
 
 impl TuiTableState for App {
     type Item = RsPod;
 
+    fn mark_scope(&self) -> Option<Scope> {
+        Some(Scope::Pods(self.selector.clone()))
+    }
+
+    fn table_search(&mut self) -> Option<&mut TableSearch> {
+        Some(&mut self.search)
+    }
+
     fn get_items(&self) -> &[Self::Item] {
         &self.items
     }
@@ -79,6 +146,20 @@ impl TuiTableState for App {
         self.filter = filter;
     }
 
+    /// grouped pods come in group order
+    fn get_filtered_items(&self) -> Vec<&Self::Item> {
+        let pattern = FilterPattern::parse(&self.filter);
+        let pods = self
+            .items
+            .iter()
+            .filter(|item| pattern.is_match(item.filter_by()))
+            .collect();
+        match &self.group_by {
+            Some(by) => arrange(pods, by, &self.collapsed),
+            None => pods,
+        }
+    }
+
     fn set_cursor_pos(&mut self, _cursor_pos: usize) {
         todo!()
     }
@@ -97,99 +178,244 @@ impl TuiTableState for App {
 }
 
 impl AppBehavior for pod_app::app::App {
-    async fn handle_event(&mut self, event: &Message) -> Result<Option<Apps>, io::Error> {
-        let mut app_holder = Some(Apps::Pod { app: self.clone() });
+    const NAME: &'static str = "pods";
+
+    async fn handle_event(&mut self, event: &Message) -> NvResult<Transition> {
         match event {
-            Message::Key(Event::Key(key)) => {
-                if key.kind == KeyEventKind::Press {
-                    use KeyCode::{Char, Down, Enter, Esc, Up};
-                    match key.code {
-                        Char('q') | Esc => {
-                            app_holder = None;
-                        }
-                        Char('j') | Down => {
-                            self.next();
-                            app_holder = Some(Apps::Pod { app: self.clone() });
+            Message::Key(Event::Key(key))
+                if key.kind == KeyEventKind::Press && self.probe_editor.is_some() =>
+            {
+                self.handle_probe_editor_key(key.code).await;
+            }
+            Message::Key(Event::Key(key))
+                if key.kind == KeyEventKind::Press && self.metadata_editor.is_some() =>
+            {
+                metadata_editor::handle_key(&mut self.metadata_editor, key).await;
+            }
+            Message::Key(Event::Key(key))
+                if key.kind == KeyEventKind::Press && self.open_menu.is_some() =>
+            {
+                open_menu::handle_key(&mut self.open_menu, key);
+            }
+            Message::Key(Event::Key(key))
+                if key.kind == KeyEventKind::Press && self.plugin_menu.is_some() =>
+            {
+                plugin_menu::handle_key(&mut self.plugin_menu, key).await;
+            }
+            Message::Key(Event::Key(key))
+                if key.kind == KeyEventKind::Press && self.timeline.is_some() =>
+            {
+                self.timeline
+                    .take_if(|timeline| !timeline.handle_key(key.code));
+            }
+            Message::Key(Event::Key(key))
+                if key.kind == KeyEventKind::Press && self.compare.is_some() =>
+            {
+                self.compare
+                    .take_if(|compare| !compare.handle_key(key.code));
+            }
+            Message::Key(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                use KeyCode::{Char, Down, Enter, Esc, Up};
+                if let Some(transition) = self.handle_mark(key) {
+                    return Ok(transition);
+                }
+                if self.handle_search(key) || self.handle_motion(key) {
+                    return Ok(Transition::Stay);
+                }
+                match key.code {
+                    Char('q') | Esc => return Ok(Transition::Pop),
+                    Char('j') | Down => {
+                        self.next();
+                    }
+                    Char('k') | Up => {
+                        self.previous();
+                    }
+                    Char('c' | 'C') => self.next_color(),
+                    Char('s' | 'S') => self.toggle_detail_panel(DetailPanel::Security),
+                    Char('a' | 'A') => self.toggle_detail_panel(DetailPanel::Identity),
+                    Char('p') => self.toggle_detail_panel(DetailPanel::Probes),
+                    Char('P') => {
+                        if let Some(pod) = self.get_selected_item().map(|pod| pod.name.clone()) {
+                            self.run_probes(pod);
+                            self.detail_panel = DetailPanel::Probes;
                         }
-                        Char('k') | Up => {
-                            self.previous();
-                            app_holder = Some(Apps::Pod { app: self.clone() });
+                    }
+                    Char('e' | 'E') if self.detail_panel == DetailPanel::Probes => {
+                        self.probe_editor = self
+                            .get_selected_item()
+                            .and_then(ProbeEditor::new)
+                            .map(Box::new);
+                    }
+                    Char('l' | 'L') => {
+                        self.metadata_editor = self
+                            .get_selected_item()
+                            .map(label_targets)
+                            .and_then(MetadataEditor::new)
+                            .map(Box::new);
+                    }
+                    Char('n' | 'N') => self.toggle_detail_panel(DetailPanel::Scheduling),
+                    Char('x' | 'X') => {
+                        self.pin_or_compare().await?;
+                    }
+                    Char(' ') => self.toggle_marked(),
+                    Char('w' | 'W') => {
+                        if let Some(selection) = self.get_selected_item() {
+                            let namespace = selection.namespace.clone();
+                            let pod = selection.name.clone();
+                            let replicaset = selection.owner.clone();
+                            let mut vars = context_vars(Some(namespace)).await;
+                            vars.insert("pod", pod);
+                            vars.insert("replicaset", replicaset);
+                            self.open_menu = Some(Box::new(OpenMenu::new(&vars)));
                         }
-                        Char('c' | 'C') => {
-                            self.next_color();
-                            app_holder = Some(Apps::Pod { app: self.clone() });
+                    }
+                    Char('v') => {
+                        if self.detail_panel == DetailPanel::Plugin {
+                            self.detail_panel = DetailPanel::Label;
+                        } else if let Some(selection) = self.get_selected_item() {
+                            let pod = selection.name.clone();
+                            let details = plugins::details(&pod_target(selection)).await;
+                            self.plugin_details = Some((pod, details));
+                            self.detail_panel = DetailPanel::Plugin;
                         }
-                        Char('i' | 'I') => {
-                            if let Some(selection) = self.get_selected_item() {
-                                if let Some(selector) = selection.selectors.clone() {
-                                    let data_vec =
-                                        create_ingress_data_vec(selector.clone()).await?;
-                                    let new_app_holder = Apps::Ingress {
-                                        app: ingress_app::app::App::new(data_vec),
-                                    };
-                                    app_holder = Some(new_app_holder);
-                                    debug!("changing app from rs to ingress...");
-                                };
-                            };
+                    }
+                    Char('V') => {
+                        if let Some(selection) = self.get_selected_item() {
+                            let target = pod_target(selection);
+                            self.plugin_menu = Some(Box::new(PluginMenu::new(target)));
                         }
-                        Char('f' | 'F') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                            self.page_forward();
+                    }
+                    Char('b') if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.group_by = GroupBy::next(self.group_by.as_ref());
+                        self.collapsed.clear();
+                        self.state.select(Some(0));
+                    }
+                    Char('z') => self.toggle_collapsed(),
+                    Char('o') => {
+                        self.sort = SortBy::next(
+                            self.sort,
+                            self.items.first().map_or(0, |item| item.columns().len()),
+                        );
+                        sort_rows(&mut self.items, self.sort);
+                    }
+                    Char('O') => {
+                        self.sort = SortBy::flip(self.sort);
+                        sort_rows(&mut self.items, self.sort);
+                    }
+                    Char('t') => {
+                        if let Some(selection) = self.get_selected_item() {
+                            let entries =
+                                pod_timeline(&selection.namespace, &selection.name).await?;
+                            self.timeline =
+                                Some(Box::new(TimelineView::new(selection.name.clone(), entries)));
                         }
-                        Char('b' | 'B') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                            self.page_backward();
+                    }
+                    Char('T') => {
+                        if let Some(app) = self.tile_app().await? {
+                            return Ok(Transition::Push(Box::new(Apps::new(app))));
                         }
-                        Enter => {
-                            if let Some(selection) = self.get_selected_item() {
-                                if let Some(selectors) = selection.selectors.clone() {
-                                    let data_vec = create_container_data_vec(
-                                        selectors,
-                                        selection.name.clone(),
-                                    )
-                                    .await?;
-                                    let new_app_holder = Apps::Container {
-                                        app: container_app::app::App::new(data_vec),
-                                    };
-                                    app_holder = Some(new_app_holder);
-                                };
-                            }
+                    }
+                    Char('i' | 'I') => {
+                        if let Some(selection) = self.get_selected_item() {
+                            if let Some(selector) = selection.selectors.clone() {
+                                let data_vec = create_ingress_data_vec(selector.clone()).await?;
+                                debug!("changing app from rs to ingress...");
+                                return Ok(Transition::Push(Box::new(Apps::new(
+                                    ingress_app::app::App::new(selector, data_vec),
+                                ))));
+                            };
+                        };
+                    }
+                    Char('R') => return Ok(Transition::Refresh),
+                    Char('f' | 'F') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.page_forward();
+                    }
+                    Char('b' | 'B') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.page_backward();
+                    }
+                    Enter => {
+                        if let Some(selection) = self.get_selected_item() {
+                            if let Some(selectors) = selection.selectors.clone() {
+                                let data_vec =
+                                    create_container_data_vec(selectors, selection.name.clone())
+                                        .await?;
+                                return Ok(Transition::Push(Box::new(Apps::new(
+                                    container_app::app::App::new(data_vec),
+                                ))));
+                            };
                         }
-                        _k => {}
                     }
+                    _k => {}
                 }
             }
             Message::Pod(data_vec) => {
                 debug!("updating pod app data...");
-                let new_app = Self {
-                    longest_item_lens: pod_constraint_len_calculator(data_vec),
-                    items: data_vec.clone(),
-                    scroll_state: ScrollbarState::new(
-                        data_vec.len().saturating_sub(1) * ITEM_HEIGHT,
-                    ),
-                    ..self.clone()
-                };
-                let new_app_holder = Apps::Pod { app: new_app };
-                app_holder = Some(new_app_holder);
+                self.longest_item_lens = pod_constraint_len_calculator(data_vec);
+                self.scroll_state =
+                    ScrollbarState::new(data_vec.len().saturating_sub(1) * ITEM_HEIGHT);
+                self.items.clone_from(data_vec);
+                sort_rows(&mut self.items, self.sort);
+                marks::land(self);
+            }
+            Message::Probed {
+                pod,
+                error: Some(e),
+            } => {
+                debug!("probes of {pod} failed: {e}");
+                error_card::show(e);
             }
             _ => {}
         }
-        Ok(app_holder)
+        Ok(Transition::Stay)
     }
     fn draw_ui<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<(), std::io::Error> {
-        terminal.draw(|f| pod_app::ui::ui(f, &mut self.clone()))?;
+        terminal.draw(|f| pod_app::ui::ui(f, self))?;
         Ok(())
     }
 
+    fn source(&self) -> Option<DataRequest> {
+        Some(DataRequest::Pods {
+            selector: self.selector.clone(),
+        })
+    }
+
     fn stream(&self, should_stop: Arc<AtomicBool>) -> impl Stream<Item = Message> {
         let (tx, rx) = mpsc::channel(100);
+        if let Ok(mut events) = self.events.lock() {
+            *events = Some(tx.clone());
+        }
+        // latencies show once the history is read, the render never waits on it
+        tokio::task::spawn_blocking(metrics_history::preload);
 
         let initial_items = self.get_items().to_vec();
         let selector = self.selector.clone();
+        let (listed, pods) = watch::channel(Vec::new());
+        spawn_scheduled_probes(pods, should_stop.clone());
 
         tokio::spawn(async move {
+            let partial = |rows: &[RsPod]| {
+                if rows.len() > initial_items.len() {
+                    _ = tx.try_send(Message::Pod(rows.to_vec()));
+                }
+            };
             while !should_stop.load(Ordering::Relaxed) {
                 //get Vec and send
-                match list_rspods(selector.clone()).await {
+                match list_rspods_paged(selector.clone(), &partial).await {
                     Ok(d) => {
+                        for gone in listed
+                            .borrow()
+                            .iter()
+                            .filter(|pod| !d.iter().any(|listed| listed.name == pod.name))
+                        {
+                            forget_pod(&gone.namespace, &gone.name);
+                        }
+                        listed.send_replace(d.clone());
+                        get_cache().put(
+                            DataRequest::Pods {
+                                selector: selector.clone(),
+                            },
+                            CachedData::Pods(d.clone()),
+                        );
                         if !d.is_empty() && d != initial_items {
                             let sevent = Message::Pod(d);
                             if tx.send(sevent).await.is_err() {
@@ -198,7 +424,23 @@ impl AppBehavior for pod_app::app::App {
                         }
                         sleep(Duration::from_millis(POLL_MS)).await;
                     }
-                    Err(_e) => {
+                    Err(e @ Error::Timeout { .. }) => {
+                        // already surfaced in the header, keep polling
+                        get_cache().mark_failed(
+                            &DataRequest::Pods {
+                                selector: selector.clone(),
+                            },
+                            e.to_string(),
+                        );
+                        sleep(Duration::from_millis(POLL_MS)).await;
+                    }
+                    Err(e) => {
+                        get_cache().mark_failed(
+                            &DataRequest::Pods {
+                                selector: selector.clone(),
+                            },
+                            e.to_string(),
+                        );
                         break;
                     }
                 }
@@ -209,6 +451,57 @@ impl AppBehavior for pod_app::app::App {
     }
 }
 
+/// Run the probes of the listed pods every `schedule()`, apart from the
+/// listing so a slow probe does not hold up the next poll.
+fn spawn_scheduled_probes(mut pods: watch::Receiver<Vec<RsPod>>, should_stop: Arc<AtomicBool>) {
+    let Some(interval) = schedule() else {
+        return;
+    };
+    tokio::spawn(async move {
+        // ends with the listing, whose sender goes with it
+        while !should_stop.load(Ordering::Relaxed) && pods.changed().await.is_ok() {
+            let probed: Vec<String> = pods
+                .borrow_and_update()
+                .iter()
+                .filter(|pod| !pod.probes.is_empty())
+                .map(|pod| pod.name.clone())
+                .collect();
+            for pod in probed {
+                if let Err(e) = run_pod_probes(&pod).await {
+                    debug!("scheduled probes of {pod} failed: {e}");
+                }
+            }
+            sleep(interval).await;
+        }
+    });
+}
+
+fn pod_target(pod: &RsPod) -> plugins::Target {
+    plugins::Target {
+        kind: "pod".to_string(),
+        namespace: pod.namespace.clone(),
+        name: pod.name.clone(),
+    }
+}
+
+/// the pod and, when it belongs to a deployment, its replicaset and deployment
+fn label_targets(pod: &RsPod) -> Vec<Target> {
+    let mut targets = vec![Target::Pod {
+        name: pod.name.clone(),
+        namespace: None,
+    }];
+    if let Some(hash) = pod
+        .selectors
+        .as_ref()
+        .and_then(|labels| labels.get("pod-template-hash"))
+    {
+        let deployment = deployment_name(&pod.name, pod.selectors.as_ref());
+        targets.push(Target::ReplicaSet(format!("{deployment}-{hash}")));
+        targets.push(Target::Deployment(deployment));
+    }
+    targets
+}
+
 impl App {
     pub fn new(selector: BTreeMap<String, String>, data_vec: Vec<RsPod>) -> Self {
         Self {
@@ -220,9 +513,210 @@ impl App {
             items: data_vec,
             selector,
             filter: String::new(),
+            detail_panel: DetailPanel::Label,
+            probe_editor: None,
+            pinned: None,
+            compare: None,
+            timeline: None,
+            sort: None,
+            group_by: None,
+            collapsed: BTreeSet::new(),
+            metadata_editor: None,
+            open_menu: None,
+            plugin_menu: None,
+            plugin_details: None,
+            marked: vec![],
+            search: TableSearch::default(),
+            events: Arc::default(),
+        }
+    }
+
+    /// mark or unmark the selected pod for tiling, up to `MAX_TILES`
+    fn toggle_marked(&mut self) {
+        let Some(name) = self.get_selected_item().map(|pod| pod.name.clone()) else {
+            return;
+        };
+        if let Some(i) = self.marked.iter().position(|marked| *marked == name) {
+            self.marked.remove(i);
+        } else if self.marked.len() < MAX_TILES {
+            self.marked.push(name);
+        }
+    }
+
+    /// tail the first container that is not a sidecar of each marked pod,
+    /// or of the selected pod when none are marked
+    async fn tile_app(&mut self) -> NvResult<Option<tile_app::app::App>> {
+        let pods = if self.marked.is_empty() {
+            self.get_selected_item()
+                .map(|pod| vec![pod.name.clone()])
+                .unwrap_or_default()
+        } else {
+            self.marked.clone()
+        };
+        let mut panes = Vec::new();
+        for pod in pods {
+            let containers = create_container_data_vec(self.selector.clone(), pod.clone()).await?;
+            // the app's own container rather than a mesh proxy
+            let first = containers
+                .iter()
+                .find(|c| sidecars::shown() || !is_sidecar(&c.name, &c.image))
+                .or_else(|| containers.first());
+            if let Some(container) = first {
+                panes.push(Pane::new(pod, container.name.clone()));
+            }
+        }
+        Ok((!panes.is_empty()).then(|| tile_app::app::App::new(self.selector.clone(), panes)))
+    }
+
+    /// Pin the selected pod, or compare it with the pinned one.
+    /// fold or unfold the selected pod's group, keeping it selected
+    fn toggle_collapsed(&mut self) {
+        let Some(by) = self.group_by.clone() else {
+            return;
+        };
+        let Some(key) = self.get_selected_item().map(|pod| by.key(pod)) else {
+            return;
+        };
+        if !self.collapsed.remove(&key) {
+            self.collapsed.insert(key.clone());
+        }
+        let first = self
+            .get_filtered_items()
+            .iter()
+            .position(|pod| by.key(pod) == key);
+        self.state.select(first);
+    }
+
+    async fn pin_or_compare(&mut self) -> NvResult<()> {
+        let Some(selection) = self.get_selected_item() else {
+            return Ok(());
+        };
+        let selected = format!("{}/{}", selection.namespace, selection.name);
+        match self.pinned.take() {
+            Some(pinned) if pinned != selected => {
+                let rows = compare_pods(&get_pod(&pinned).await?, &get_pod(&selected).await?);
+                self.compare = Some(Box::new(CompareView::new(pinned, selected, rows)));
+            }
+            Some(_) => {}
+            None => self.pinned = Some(selected),
+        }
+        Ok(())
+    }
+
+    /// Run the probes of `pod` in the background, the outcome comes back as
+    /// a `Message::Probed`.
+    fn run_probes(&self, pod: String) {
+        let events = self.events.lock().ok().and_then(|events| events.clone());
+        tokio::spawn(async move {
+            let error = run_pod_probes(&pod).await.err();
+            if let Some(events) = events {
+                _ = events.send(Message::Probed { pod, error }).await;
+            }
+        });
+    }
+
+    /// A failed run is shown in the editor rather than on an error card so
+    /// the user can keep tuning.
+    async fn handle_probe_editor_key(&mut self, code: KeyCode) {
+        let Some(editor) = self.probe_editor.as_mut() else {
+            return;
+        };
+        match editor.handle_key(code) {
+            EditorAction::Close => self.probe_editor = None,
+            EditorAction::Run => {
+                let outcome = run_tuned_probe(&editor.pod, &editor.tuned).await;
+                match outcome {
+                    Ok(Some(result)) => {
+                        editor.message = None;
+                        editor.last_result = Some(result);
+                    }
+                    Ok(None) => {
+                        editor.message = Some(format!(
+                            "{} probes run on the node only",
+                            editor.tuned.handler
+                        ));
+                    }
+                    Err(e) => editor.message = Some(format!("{}: {e}", e.code())),
+                }
+            }
+            EditorAction::None => {}
         }
     }
 
+    /// show `panel` in place of the labels, or go back to labels if it is
+    /// already showing
+    pub fn toggle_detail_panel(&mut self, panel: DetailPanel) {
+        self.detail_panel = if self.detail_panel == panel {
+            DetailPanel::Label
+        } else {
+            panel
+        };
+    }
+
+    /// title and rows of the plugin panel, the pod it was fetched for in
+    /// the title since the selection may have moved on
+    pub fn get_plugin_details(&self) -> (String, Vec<(String, String, Option<String>)>) {
+        self.plugin_details.as_ref().map_or_else(
+            || ("Plugin".to_string(), Vec::new()),
+            |(pod, details)| {
+                let rows = details
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.clone(), None))
+                    .collect();
+                (format!("Plugin: {pod}"), rows)
+            },
+        )
+    }
+
+    pub fn get_identity_details(&mut self) -> Vec<(String, String, Option<String>)> {
+        self.get_selected_item().map_or_else(Vec::new, |pod| {
+            pod.identity
+                .iter()
+                .map(|detail| (detail.name.clone(), detail.value.clone(), None))
+                .collect()
+        })
+    }
+
+    pub fn get_security_details(&mut self) -> Vec<(String, String, Option<String>)> {
+        self.get_selected_item().map_or_else(Vec::new, |pod| {
+            pod.security
+                .iter()
+                .map(|detail| (detail.name.clone(), detail.value.clone(), None))
+                .collect()
+        })
+    }
+
+    /// why each node rejects the pod, nodes that fit first
+    pub fn get_scheduling_details(&mut self) -> (String, Vec<(String, String, Option<String>)>) {
+        self.get_selected_item().map_or_else(
+            || ("Scheduling".to_string(), Vec::new()),
+            |pod| {
+                if pod.scheduling.is_empty() {
+                    return ("Scheduling: not pending".to_string(), Vec::new());
+                }
+                let mut verdicts: Vec<&NodeVerdict> = pod.scheduling.iter().collect();
+                verdicts.sort_by_key(|v| !v.exclusions.is_empty());
+                let details = verdicts
+                    .into_iter()
+                    .map(|verdict| {
+                        let reasons = if verdict.exclusions.is_empty() {
+                            "fits".to_string()
+                        } else {
+                            verdict
+                                .exclusions
+                                .iter()
+                                .map(|e| format!("{}: {}", e.kind, e.detail))
+                                .collect::<Vec<_>>()
+                                .join("; ")
+                        };
+                        (verdict.node.clone(), reasons, None)
+                    })
+                    .collect();
+                (summary(&pod.scheduling), details)
+            },
+        )
+    }
+
     pub fn get_event_details(&mut self) -> Vec<(String, String, Option<String>)> {
         self.get_selected_item().map_or_else(Vec::new, |pod| {
             pod.events
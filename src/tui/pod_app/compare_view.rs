@@ -0,0 +1,53 @@
+//!Two pods side by side, opened by pinning one pod with 'x' and pressing
+//!'x' again on another.
+//!
+use crate::k8s::compare::CompareRow;
+use crossterm::event::KeyCode;
+
+#[derive(Clone, Debug)]
+pub struct CompareView {
+    pub(crate) left: String,
+    pub(crate) right: String,
+    pub(crate) rows: Vec<CompareRow>,
+    /// show the rows both pods agree on too
+    pub(crate) show_all: bool,
+    pub(crate) offset: usize,
+}
+
+impl CompareView {
+    #[must_use]
+    pub const fn new(left: String, right: String, rows: Vec<CompareRow>) -> Self {
+        Self {
+            left,
+            right,
+            rows,
+            show_all: false,
+            offset: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn visible_rows(&self) -> Vec<&CompareRow> {
+        self.rows
+            .iter()
+            .filter(|row| self.show_all || row.differs())
+            .collect()
+    }
+
+    /// `false` once the view should close
+    pub fn handle_key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Esc | KeyCode::Char('q' | 'x') => return false,
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.offset = (self.offset + 1).min(self.visible_rows().len().saturating_sub(1));
+            }
+            KeyCode::Char('k') | KeyCode::Up => self.offset = self.offset.saturating_sub(1),
+            KeyCode::Char('a') => {
+                self.show_all = !self.show_all;
+                self.offset = 0;
+            }
+            _ => {}
+        }
+        true
+    }
+}
@@ -0,0 +1,188 @@
+//!Pods collapsed under group headers, 'b' picks what to group by and 'z'
+//!folds the selected pod's group down to its first pod.
+//!
+use crate::config::settings;
+use crate::tui::data::RsPod;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GroupBy {
+    Node,
+    ReplicaSet,
+    ImageTag,
+    Label(String),
+}
+
+impl GroupBy {
+    /// node, replicaset, image tag, then each label of `group_labels` in
+    /// the config file, ungrouped after the last
+    #[must_use]
+    pub fn next(current: Option<&Self>) -> Option<Self> {
+        let labels = &settings().group_labels;
+        match current {
+            None => Some(Self::Node),
+            Some(Self::Node) => Some(Self::ReplicaSet),
+            Some(Self::ReplicaSet) => Some(Self::ImageTag),
+            Some(Self::ImageTag) => labels.first().cloned().map(Self::Label),
+            Some(Self::Label(label)) => labels
+                .iter()
+                .skip_while(|l| *l != label)
+                .nth(1)
+                .cloned()
+                .map(Self::Label),
+        }
+    }
+
+    #[must_use]
+    pub fn key(&self, pod: &RsPod) -> String {
+        let key = match self {
+            Self::Node => pod.node.clone(),
+            Self::ReplicaSet => pod.owner.clone(),
+            Self::ImageTag => pod
+                .images
+                .iter()
+                .map(|image| image_tag(image))
+                .collect::<Vec<_>>()
+                .join(","),
+            Self::Label(label) => pod
+                .selectors
+                .as_ref()
+                .and_then(|labels| labels.get(label))
+                .cloned()
+                .unwrap_or_default(),
+        };
+        if key.is_empty() {
+            "<none>".to_string()
+        } else {
+            key
+        }
+    }
+}
+
+impl fmt::Display for GroupBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Node => f.write_str("node"),
+            Self::ReplicaSet => f.write_str("replicaset"),
+            Self::ImageTag => f.write_str("image tag"),
+            Self::Label(label) => write!(f, "label {label}"),
+        }
+    }
+}
+
+/// the tag of an image reference, "latest" when it has none
+fn image_tag(image: &str) -> &str {
+    let image = image.split('@').next().unwrap_or(image);
+    let name = image.rsplit('/').next().unwrap_or(image);
+    name.split_once(':').map_or("latest", |(_, tag)| tag)
+}
+
+/// health of a group's pods
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GroupHealth {
+    pub pods: usize,
+    pub ready: usize,
+}
+
+impl GroupHealth {
+    #[must_use]
+    pub const fn healthy(&self) -> bool {
+        self.ready == self.pods
+    }
+}
+
+impl fmt::Display for GroupHealth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{} ready", self.ready, self.pods)
+    }
+}
+
+fn is_ready(pod: &RsPod) -> bool {
+    pod.status == "Running"
+        && pod
+            .containers
+            .split_once('/')
+            .is_some_and(|(ready, desired)| ready == desired)
+}
+
+#[must_use]
+pub fn health(pods: &[&RsPod], by: &GroupBy) -> BTreeMap<String, GroupHealth> {
+    let mut groups: BTreeMap<String, GroupHealth> = BTreeMap::new();
+    for pod in pods {
+        let group = groups.entry(by.key(pod)).or_default();
+        group.pods += 1;
+        if is_ready(pod) {
+            group.ready += 1;
+        }
+    }
+    groups
+}
+
+/// pods in group order, a collapsed group showing only its first pod
+#[must_use]
+pub fn arrange<'a>(
+    mut pods: Vec<&'a RsPod>,
+    by: &GroupBy,
+    collapsed: &BTreeSet<String>,
+) -> Vec<&'a RsPod> {
+    pods.sort_by_cached_key(|pod| by.key(pod));
+    let mut shown = BTreeSet::new();
+    pods.retain(|pod| {
+        let key = by.key(pod);
+        !collapsed.contains(&key) || shown.insert(key)
+    });
+    pods
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pod(name: &str, node: &str, image: &str, containers: &str) -> RsPod {
+        RsPod {
+            name: name.to_string(),
+            namespace: "default".to_string(),
+            status: "Running".to_string(),
+            description: "ReplicaSet".to_string(),
+            age: "1d".to_string(),
+            containers: containers.to_string(),
+            selectors: None,
+            events: vec![],
+            security: vec![],
+            identity: vec![],
+            probes: vec![],
+            scheduling: vec![],
+            extra: vec![],
+            node: node.to_string(),
+            owner: "web-5d8f7".to_string(),
+            images: vec![image.to_string()],
+        }
+    }
+
+    #[test]
+    fn test_group_pods() {
+        let pods = [
+            pod("a", "node-2", "registry:5000/web:1.2", "1/1"),
+            pod("b", "node-1", "web:1.1@sha256:abc", "0/1"),
+            pod("c", "node-2", "web", "1/1"),
+        ];
+        let refs: Vec<&RsPod> = pods.iter().collect();
+        assert_eq!(GroupBy::ImageTag.key(&pods[0]), "1.2");
+        assert_eq!(GroupBy::ImageTag.key(&pods[1]), "1.1");
+        assert_eq!(GroupBy::ImageTag.key(&pods[2]), "latest");
+
+        let health = health(&refs, &GroupBy::Node);
+        assert_eq!(health["node-1"].to_string(), "0/1 ready");
+        assert!(health["node-2"].healthy());
+
+        let names = |pods: Vec<&RsPod>| pods.iter().map(|p| p.name.clone()).collect::<Vec<_>>();
+        let open = BTreeSet::new();
+        assert_eq!(
+            names(arrange(refs.clone(), &GroupBy::Node, &open)),
+            ["b", "a", "c"]
+        );
+        let collapsed = BTreeSet::from(["node-2".to_string()]);
+        assert_eq!(names(arrange(refs, &GroupBy::Node, &collapsed)), ["b", "a"]);
+    }
+}
@@ -1,2 +1,6 @@
 pub mod app;
+pub mod compare_view;
+pub mod groups;
+pub mod probe_editor;
+pub mod timeline_view;
 pub mod ui;
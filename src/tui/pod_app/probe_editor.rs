@@ -0,0 +1,225 @@
+//!Tweak a pod's probe parameters and re-run the probe to see how the tuned
+//!values would behave, with the Deployment patch that applies them kept up
+//!to date as the fields change.
+//!
+use crate::k8s::probes::{deployment_name, ProbeResult, ProbeSpec, PROBE_FIELDS};
+use crate::tui::data::RsPod;
+use crossterm::event::KeyCode;
+
+/// what the pod app should do after the editor handled a key
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EditorAction {
+    None,
+    Run,
+    Close,
+}
+
+#[derive(Clone, Debug)]
+pub struct ProbeEditor {
+    pub(crate) pod: String,
+    pub(crate) deployment: String,
+    pub(crate) probes: Vec<ProbeSpec>,
+    pub(crate) probe_index: usize,
+    /// the selected probe with the edits applied so far
+    pub(crate) tuned: ProbeSpec,
+    pub(crate) selected_field: usize,
+    /// text of the selected field while it is being edited
+    pub(crate) input: String,
+    pub(crate) message: Option<String>,
+    pub(crate) last_result: Option<ProbeResult>,
+}
+
+impl ProbeEditor {
+    /// `None` if the pod declares no probes
+    #[must_use]
+    pub fn new(pod: &RsPod) -> Option<Self> {
+        let tuned = pod.probes.first()?.clone();
+        Some(Self {
+            pod: pod.name.clone(),
+            deployment: deployment_name(&pod.name, pod.selectors.as_ref()),
+            probes: pod.probes.clone(),
+            probe_index: 0,
+            input: tuned.field(0),
+            tuned,
+            selected_field: 0,
+            message: None,
+            last_result: None,
+        })
+    }
+
+    pub fn handle_key(&mut self, code: KeyCode) -> EditorAction {
+        match code {
+            KeyCode::Esc => return EditorAction::Close,
+            KeyCode::Enter if self.commit() => {
+                self.message = Some(format!("running {} probe...", self.tuned.kind));
+                return EditorAction::Run;
+            }
+            KeyCode::Up => self.select_field(self.selected_field.saturating_sub(1)),
+            KeyCode::Down => {
+                self.select_field((self.selected_field + 1).min(PROBE_FIELDS.len() - 1));
+            }
+            KeyCode::Tab => {
+                self.probe_index = (self.probe_index + 1) % self.probes.len();
+                self.tuned = self.probes[self.probe_index].clone();
+                self.input = self.tuned.field(self.selected_field);
+                self.message = None;
+                self.last_result = None;
+            }
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Char(c) => self.input.push(c),
+            _ => {}
+        }
+        EditorAction::None
+    }
+
+    /// the patch for the committed values, plus the field being edited if it
+    /// parses
+    #[must_use]
+    pub fn patch_yaml(&self) -> String {
+        let mut preview = self.tuned.clone();
+        let _ = preview.set_field(self.selected_field, &self.input);
+        preview.patch_yaml(&self.deployment)
+    }
+
+    fn select_field(&mut self, field: usize) {
+        if field != self.selected_field && self.commit() {
+            self.selected_field = field;
+            self.input = self.tuned.field(field);
+        }
+    }
+
+    /// apply the input to the tuned probe, `false` if it was rejected
+    fn commit(&mut self) -> bool {
+        match self.tuned.set_field(self.selected_field, &self.input) {
+            Ok(()) => {
+                self.input = self.tuned.field(self.selected_field);
+                self.message = None;
+                true
+            }
+            Err(e) => {
+                self.message = Some(e);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::k8s::probes::{ProbeHandler, ProbeKind};
+
+    fn probe(kind: ProbeKind, handler: ProbeHandler) -> ProbeSpec {
+        ProbeSpec {
+            container: "web".to_string(),
+            kind,
+            handler,
+            timeout_secs: 1,
+            period_secs: 10,
+            initial_delay_secs: 0,
+            failure_threshold: 3,
+            success_threshold: 1,
+        }
+    }
+
+    fn editor() -> ProbeEditor {
+        let probes = vec![
+            probe(
+                ProbeKind::Liveness,
+                ProbeHandler::Http {
+                    scheme: "HTTP".to_string(),
+                    path: "/healthz".to_string(),
+                    port: "8080".to_string(),
+                },
+            ),
+            probe(
+                ProbeKind::Readiness,
+                ProbeHandler::Tcp {
+                    port: "8080".to_string(),
+                },
+            ),
+        ];
+        ProbeEditor {
+            pod: "web-5d8f7-x2x9q".to_string(),
+            deployment: "web".to_string(),
+            input: probes[0].field(0),
+            tuned: probes[0].clone(),
+            probes,
+            probe_index: 0,
+            selected_field: 0,
+            message: None,
+            last_result: None,
+        }
+    }
+
+    fn type_over(editor: &mut ProbeEditor, text: &str) {
+        while !editor.input.is_empty() {
+            editor.handle_key(KeyCode::Backspace);
+        }
+        for c in text.chars() {
+            editor.handle_key(KeyCode::Char(c));
+        }
+    }
+
+    #[test]
+    fn test_moving_between_fields_commits_the_input() {
+        let mut editor = editor();
+        type_over(&mut editor, "ready");
+        assert_eq!(editor.handle_key(KeyCode::Down), EditorAction::None);
+        assert_eq!(editor.selected_field, 1);
+        assert_eq!(editor.input, "8080");
+        assert_eq!(editor.tuned.field(0), "/ready");
+
+        editor.handle_key(KeyCode::Down);
+        type_over(&mut editor, "5");
+        editor.handle_key(KeyCode::Up);
+        assert_eq!(editor.tuned.timeout_secs, 5);
+        editor.handle_key(KeyCode::Up);
+        editor.handle_key(KeyCode::Up);
+        assert_eq!(editor.selected_field, 0);
+        for _ in 0..PROBE_FIELDS.len() + 2 {
+            editor.handle_key(KeyCode::Down);
+        }
+        assert_eq!(editor.selected_field, PROBE_FIELDS.len() - 1);
+    }
+
+    #[test]
+    fn test_invalid_input_stays_on_the_field() {
+        let mut editor = editor();
+        editor.handle_key(KeyCode::Down);
+        editor.handle_key(KeyCode::Down);
+        type_over(&mut editor, "slow");
+        assert_eq!(editor.handle_key(KeyCode::Down), EditorAction::None);
+        assert_eq!(editor.selected_field, 2);
+        assert_eq!(editor.tuned.timeout_secs, 1);
+        assert!(editor
+            .message
+            .as_deref()
+            .unwrap()
+            .contains("timeoutSeconds"));
+        // the preview leaves out what does not parse
+        assert!(editor.patch_yaml().contains("timeoutSeconds: 1\n"));
+        assert_eq!(editor.handle_key(KeyCode::Enter), EditorAction::None);
+
+        type_over(&mut editor, "4");
+        assert!(editor.patch_yaml().contains("timeoutSeconds: 4\n"));
+        assert_eq!(editor.handle_key(KeyCode::Enter), EditorAction::Run);
+        assert_eq!(editor.tuned.timeout_secs, 4);
+        assert_eq!(editor.message.as_deref(), Some("running liveness probe..."));
+    }
+
+    #[test]
+    fn test_tab_cycles_probes_and_drops_edits() {
+        let mut editor = editor();
+        type_over(&mut editor, "ready");
+        editor.handle_key(KeyCode::Down);
+        assert_eq!(editor.handle_key(KeyCode::Tab), EditorAction::None);
+        assert_eq!(editor.tuned.kind, ProbeKind::Readiness);
+        assert_eq!((editor.selected_field, editor.input.as_str()), (1, "8080"));
+        editor.handle_key(KeyCode::Tab);
+        assert_eq!(editor.tuned.field(0), "/healthz");
+        assert_eq!(editor.handle_key(KeyCode::Esc), EditorAction::Close);
+    }
+}
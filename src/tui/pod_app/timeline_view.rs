@@ -0,0 +1,38 @@
+//!The selected pod's life as a vertical timeline, opened with 't'.
+//!
+use crate::k8s::timeline::TimelineEntry;
+use crossterm::event::KeyCode;
+
+#[derive(Clone, Debug)]
+pub struct TimelineView {
+    pub(crate) pod: String,
+    pub(crate) entries: Vec<TimelineEntry>,
+    pub(crate) offset: usize,
+}
+
+impl TimelineView {
+    #[must_use]
+    pub const fn new(pod: String, entries: Vec<TimelineEntry>) -> Self {
+        Self {
+            pod,
+            entries,
+            offset: 0,
+        }
+    }
+
+    /// `false` once the view should close
+    pub fn handle_key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Esc | KeyCode::Char('q' | 't') => return false,
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.offset = (self.offset + 1).min(self.entries.len().saturating_sub(1));
+            }
+            KeyCode::Char('k') | KeyCode::Up => self.offset = self.offset.saturating_sub(1),
+            KeyCode::Char('G') | KeyCode::End => {
+                self.offset = self.entries.len().saturating_sub(1);
+            }
+            _ => {}
+        }
+        true
+    }
+}
@@ -1,18 +1,67 @@
-use crate::tui::pod_app::app::App;
+use crate::config::settings;
+use crate::k8s::probes::{latency, ProbeHandler, Slo, PROBE_FIELDS};
+use crate::tui::data::RsPod;
+use crate::tui::error_card;
+use crate::tui::filter_pattern;
+use crate::tui::metadata_editor;
+use crate::tui::open_menu;
+use crate::tui::plugin_menu;
+use crate::tui::pod_app::app::{App, DetailPanel};
+use crate::tui::pod_app::compare_view::CompareView;
+use crate::tui::pod_app::groups;
+use crate::tui::pod_app::probe_editor::ProbeEditor;
+use crate::tui::pod_app::timeline_view::TimelineView;
+use crate::tui::sort::{custom_constraints, headers, Columns};
+use crate::tui::status_line;
+use crate::tui::table_ui::with_timeout_notice;
 use crate::tui::table_ui::{render_detail_section, TuiTableState};
+use crate::tui::theme::symbols;
+use crate::tui::utils::names::{middle_ellipsis, truncated, MAX_NAME_WIDTH};
+use crate::tui::utils::time::{timezone, LOG_TIME_FORMAT};
 use ratatui::{
     prelude::*,
-    widgets::{Cell, HighlightSpacing, Row, Scrollbar, ScrollbarOrientation, Table},
+    widgets::{
+        Block, Borders, Cell, Clear, HighlightSpacing, Paragraph, Row, Scrollbar,
+        ScrollbarOrientation, Table,
+    },
 };
 
 pub fn ui(f: &mut Frame, app: &mut App) {
-    let rects = Layout::vertical([Constraint::Min(8), Constraint::Percentage(40)]).split(f.area());
+    status_line::set_selected(
+        app.get_selected_item()
+            .filter(|pod| truncated(&pod.name))
+            .map(|pod| pod.name.clone()),
+    );
+    let rects = Layout::vertical([Constraint::Min(8), Constraint::Percentage(40)])
+        .split(status_line::render(f));
     app.set_colors();
 
     let table_area = rects[0];
     let details_area = rects[1];
 
     render_ui_sections(f, app, table_area, details_area);
+
+    if let Some(editor) = &app.probe_editor {
+        render_probe_editor(f, editor);
+    }
+    if let Some(compare) = &app.compare {
+        render_compare(f, compare);
+    }
+    if let Some(timeline) = &app.timeline {
+        render_timeline(f, timeline);
+    }
+    if let Some(menu) = &app.open_menu {
+        open_menu::render(f, menu);
+    }
+    if let Some(menu) = &app.plugin_menu {
+        plugin_menu::render(f, menu);
+    }
+    if let Some(editor) = &app.metadata_editor {
+        metadata_editor::render(f, editor);
+    }
+
+    status_line::render_activity(f);
+    error_card::render(f);
 }
 
 fn render_ui_sections(f: &mut Frame, app: &mut App, table_area: Rect, details_area: Rect) {
@@ -25,18 +74,32 @@ fn render_details(f: &mut Frame, app: &mut App, area: Rect) {
     let detail_rects =
         Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).split(area);
 
-    let left_details = app.get_label_details();
-    let event_details = app.get_event_details();
-
     let (foreground_color, background_color) = get_colors(app);
-    render_detail_section(
-        f,
-        foreground_color,
-        background_color,
-        detail_rects[0],
-        "Label",
-        &left_details,
-    );
+    let left = match app.detail_panel {
+        DetailPanel::Label => Some(("Label".to_string(), app.get_label_details())),
+        DetailPanel::Security => Some(("Security".to_string(), app.get_security_details())),
+        DetailPanel::Identity => Some(("Identity".to_string(), app.get_identity_details())),
+        DetailPanel::Scheduling => Some(app.get_scheduling_details()),
+        DetailPanel::Plugin => Some(app.get_plugin_details()),
+        DetailPanel::Probes => None,
+    };
+    if let Some((mut left_title, left_details)) = left {
+        // the name the table cut short, in full
+        if let Some(pod) = app.get_selected_item().filter(|pod| truncated(&pod.name)) {
+            left_title = format!("{left_title}: {}", pod.name);
+        }
+        render_detail_section(
+            f,
+            foreground_color,
+            background_color,
+            detail_rects[0],
+            &left_title,
+            &left_details,
+        );
+    } else {
+        render_probes(f, app, detail_rects[0]);
+    }
+    let event_details = app.get_event_details();
     render_detail_section(
         f,
         foreground_color,
@@ -47,6 +110,222 @@ fn render_details(f: &mut Frame, app: &mut App, area: Rect) {
     );
 }
 
+/// probe latencies of the selected pod, p95 colored against the probe timeout
+fn render_probes(f: &mut Frame, app: &mut App, area: Rect) {
+    let (foreground_color, background_color) = get_colors(app);
+    let lines: Vec<Line> = app.get_selected_item().map_or_else(Vec::new, |pod| {
+        pod.probes
+            .iter()
+            .map(|spec| {
+                let mut spans = vec![Span::raw(format!(
+                    "{} {}: {} ",
+                    spec.kind, spec.container, spec.handler
+                ))];
+                match (&spec.handler, latency(spec, &pod.namespace, &pod.name)) {
+                    (ProbeHandler::Http { .. }, Some(latency)) => {
+                        let color = match latency.slo {
+                            Slo::Met => Color::Green,
+                            Slo::AtRisk => Color::Yellow,
+                            Slo::Breached => Color::Red,
+                        };
+                        spans.push(Span::raw(format!("last {:.0}ms ", latency.last_ms)));
+                        spans.push(Span::styled(
+                            format!("p95 {:.0}ms", latency.p95_ms),
+                            Style::new().fg(color).add_modifier(Modifier::BOLD),
+                        ));
+                        spans.push(Span::raw(format!(
+                            " of {}s (n={})",
+                            spec.timeout_secs, latency.samples
+                        )));
+                    }
+                    (ProbeHandler::Http { .. }, None) => {
+                        spans.push(Span::raw("not run yet, P to run"));
+                    }
+                    _ => spans.push(Span::raw("runs on the node only")),
+                }
+                Line::from(spans)
+            })
+            .collect()
+    });
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(Span::styled(
+            format!("Probes ({}) e to tune", lines.len()),
+            Style::default().add_modifier(Modifier::BOLD),
+        ))
+        .style(Style::default().fg(foreground_color).bg(background_color));
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// the tuned fields on the left, the deployment patch on the right
+fn render_probe_editor(f: &mut Frame, editor: &ProbeEditor) {
+    let area = Layout::vertical([Constraint::Percentage(70)])
+        .flex(layout::Flex::Center)
+        .split(f.area())[0];
+    let area = Layout::horizontal([Constraint::Percentage(80)])
+        .flex(layout::Flex::Center)
+        .split(area)[0];
+    let halves =
+        Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)]).split(area);
+
+    let mut lines: Vec<Line> = PROBE_FIELDS
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            if i == editor.selected_field {
+                Line::from(format!("{name}: {}{}", editor.input, symbols().cursor))
+                    .style(Style::new().add_modifier(Modifier::REVERSED))
+            } else {
+                Line::from(format!("{name}: {}", editor.tuned.field(i)))
+            }
+        })
+        .collect();
+    lines.push(Line::from(""));
+    if let Some(result) = &editor.last_result {
+        let color = if result.ok { Color::Green } else { Color::Red };
+        lines.push(Line::from(Span::styled(
+            format!("{} in {}ms", result.detail, result.response_time_ms),
+            Style::new().fg(color).add_modifier(Modifier::BOLD),
+        )));
+    }
+    if let Some(message) = &editor.message {
+        lines.push(Line::from(message.as_str()).style(Style::new().fg(Color::Yellow)));
+    }
+    lines.push(
+        Line::from(format!(
+            "{} field  enter run  tab next probe  esc close",
+            symbols().up_down
+        ))
+        .style(Style::new().add_modifier(Modifier::DIM)),
+    );
+    let style = Style::new().fg(Color::White).bg(Color::Black);
+    let fields =
+        Paragraph::new(lines)
+            .style(style)
+            .block(Block::default().borders(Borders::ALL).title(format!(
+                " {} {} probe {}/{} ",
+                editor.tuned.container,
+                editor.tuned.kind,
+                editor.probe_index + 1,
+                editor.probes.len()
+            )));
+    let patch = Paragraph::new(editor.patch_yaml())
+        .style(style)
+        .block(Block::default().borders(Borders::ALL).title(" Patch "));
+
+    f.render_widget(Clear, area);
+    f.render_widget(fields, halves[0]);
+    f.render_widget(patch, halves[1]);
+}
+
+/// the compared facts in three columns, differences highlighted
+fn render_compare(f: &mut Frame, compare: &CompareView) {
+    let area = Layout::vertical([Constraint::Percentage(80)])
+        .flex(layout::Flex::Center)
+        .split(f.area())[0];
+    let area = Layout::horizontal([Constraint::Percentage(90)])
+        .flex(layout::Flex::Center)
+        .split(area)[0];
+    let visible = compare.visible_rows();
+    let key_width = visible.iter().map(|row| row.key.len()).max().unwrap_or(0);
+    let rows = visible.iter().skip(compare.offset).map(|row| {
+        let style = if row.differs() {
+            Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::new().add_modifier(Modifier::DIM)
+        };
+        Row::new([
+            Cell::from(row.section),
+            Cell::from(row.key.clone()),
+            Cell::from(row.left.clone().unwrap_or_else(|| "-".to_string())),
+            Cell::from(row.right.clone().unwrap_or_else(|| "-".to_string())),
+        ])
+        .style(style)
+    });
+    let title = if visible.is_empty() {
+        " configured the same, a to show all, esc to close ".to_string()
+    } else {
+        format!(
+            " {} of {} differ, a to show all, esc to close ",
+            compare.rows.iter().filter(|row| row.differs()).count(),
+            compare.rows.len()
+        )
+    };
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(10),
+            Constraint::Length(u16::try_from(key_width).unwrap_or(u16::MAX)),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(
+        Row::new([
+            String::new(),
+            String::new(),
+            compare.left.clone(),
+            compare.right.clone(),
+        ])
+        .style(Style::new().add_modifier(Modifier::BOLD | Modifier::UNDERLINED)),
+    )
+    .style(Style::new().fg(Color::White).bg(Color::Black))
+    .block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(Clear, area);
+    f.render_widget(table, area);
+}
+
+/// one dot per entry on a line down the left, problems in red
+fn render_timeline(f: &mut Frame, timeline: &TimelineView) {
+    let area = Layout::vertical([Constraint::Percentage(80)])
+        .flex(layout::Flex::Center)
+        .split(f.area())[0];
+    let area = Layout::horizontal([Constraint::Percentage(80)])
+        .flex(layout::Flex::Center)
+        .split(area)[0];
+    let time_width = LOG_TIME_FORMAT.len() + 2;
+    let mut lines = vec![];
+    for (i, entry) in timeline.entries.iter().enumerate().skip(timeline.offset) {
+        let color = if entry.stage.is_problem() {
+            Color::Red
+        } else {
+            Color::Green
+        };
+        lines.push(Line::from(vec![
+            Span::raw(format!(
+                "{:time_width$}",
+                timezone().format(&entry.time, LOG_TIME_FORMAT)
+            )),
+            Span::styled(format!("{} ", symbols().dot), Style::new().fg(color)),
+            Span::styled(
+                format!("{:<13}", entry.stage.to_string()),
+                Style::new().fg(color).add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(entry.detail.clone()),
+        ]));
+        if i + 1 < timeline.entries.len() {
+            lines.push(Line::from(format!(
+                "{:time_width$}{}",
+                "",
+                symbols().vertical
+            )));
+        }
+    }
+    if timeline.entries.is_empty() {
+        lines.push(Line::from("nothing recorded for this pod"));
+    }
+    let paragraph = Paragraph::new(lines)
+        .style(Style::new().fg(Color::White).bg(Color::Black))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" timeline of {} ", timeline.pod))
+                .title_bottom(" j/k to scroll, G to the end, esc to close "),
+        );
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
 const fn get_colors(app: &App) -> (Color, Color) {
     (app.colors.header_fg, app.colors.buffer_bg)
 }
@@ -59,55 +338,123 @@ fn render_table(f: &mut Frame, app: &mut App, area: Rect) {
         .add_modifier(Modifier::REVERSED)
         .fg(app.colors.selected_style_fg);
 
-    let filter_header = match app.get_filter() {
-        filter if filter != String::new() => format!("Pod ({filter})"),
-        _ => "Pod".to_string(),
-    };
+    let mut pinned = app
+        .pinned
+        .as_ref()
+        .map_or_else(String::new, |pinned| format!(" [x: {pinned}]"));
+    if !app.marked.is_empty() {
+        pinned.push_str(&format!(" [{} marked, T to tile]", app.marked.len()));
+    }
+    if let Some(by) = &app.group_by {
+        pinned.push_str(&format!(" [by {by}, z to fold]"));
+    }
+    let filter_header = with_timeout_notice(app.search.header(match app.get_filter() {
+        filter if filter != String::new() => {
+            format!("Pod ({}){pinned}", filter_pattern::label(&filter))
+        }
+        _ => format!("Pod{pinned}"),
+    }));
 
-    let header = [&filter_header, "Status", "C", "Age", "Description"]
-        .iter()
-        .copied()
-        .map(Cell::from)
-        .collect::<Row>()
-        .style(header_style)
-        .height(1);
-    let rows = app
-        .get_filtered_items()
-        .into_iter()
-        .enumerate()
-        .map(|(i, data)| {
-            let color = match i % 2 {
-                0 => app.colors.normal_row_color,
-                _ => app.colors.alt_row_color,
-            };
-            let item = data.ref_array();
-            item.iter()
-                .copied()
-                .map(|content| Cell::from(Text::from(format!("\n{content}\n"))))
-                .collect::<Row>()
-                .style(Style::new().fg(app.colors.row_fg).bg(color))
-                .height(3) //height
-        });
-    let bar = " █ ";
-    let t = Table::new(
-        rows,
-        [
-            // + 1 is for padding.
-            Constraint::Min(app.longest_item_lens.0 + 1),
-            Constraint::Min(app.longest_item_lens.1 + 1),
-            Constraint::Min(app.longest_item_lens.2 + 1),
-            Constraint::Min(app.longest_item_lens.3 + 1),
-            Constraint::Min(app.longest_item_lens.4),
-        ],
+    let custom = &settings().columns.pod;
+    let header = headers(
+        &[&filter_header, "Status", "C", "Age", "Description"],
+        custom,
+        app.sort,
     )
-    .header(header)
-    .row_highlight_style(selected_style)
-    .highlight_symbol(Text::from(vec!["".into(), bar.into(), "".into()]))
-    .bg(app.colors.buffer_bg)
-    .highlight_spacing(HighlightSpacing::Always);
+    .into_iter()
+    .map(Cell::from)
+    .collect::<Row>()
+    .style(header_style)
+    .height(1);
+    let items = app.get_filtered_items();
+    let mut widths = vec![
+        // + 1 is for padding.
+        Constraint::Min(app.longest_item_lens.0.min(MAX_NAME_WIDTH as u16) + 1),
+        Constraint::Min(app.longest_item_lens.1 + 1),
+        Constraint::Min(app.longest_item_lens.2 + 1),
+        Constraint::Min(app.longest_item_lens.3 + 1),
+        Constraint::Min(app.longest_item_lens.4),
+    ];
+    widths.extend(custom_constraints(&items, widths.len(), custom));
+    let heads = group_heads(app, &items);
+    let rows = items.into_iter().enumerate().map(|(i, data)| {
+        let color = match i % 2 {
+            0 => app.colors.normal_row_color,
+            _ => app.colors.alt_row_color,
+        };
+        let mark = if app.marked.contains(&data.name) {
+            "+ "
+        } else {
+            ""
+        };
+        let name = |content: &str| {
+            let mut name = app
+                .search
+                .highlight(middle_ellipsis(content, MAX_NAME_WIDTH));
+            name.spans.insert(0, Span::raw(mark));
+            name
+        };
+        data.columns()
+            .into_iter()
+            .enumerate()
+            .map(|(j, content)| match &heads[i] {
+                // the first pod of a group carries its header
+                Some(head) if j == 0 => Cell::from(Text::from(vec![head.clone(), name(content)])),
+                _ if j == 0 => Cell::from(Text::from(vec![
+                    Line::from(""),
+                    name(content),
+                    Line::from(""),
+                ])),
+                _ => Cell::from(Text::from(format!("\n{content}\n"))),
+            })
+            .collect::<Row>()
+            .style(Style::new().fg(app.colors.row_fg).bg(color))
+            .height(3) //height
+    });
+    let bar = format!(" {} ", symbols().bar);
+    let t = Table::new(rows, widths)
+        .header(header)
+        .row_highlight_style(selected_style)
+        .highlight_symbol(Text::from(vec!["".into(), bar.into(), "".into()]))
+        .bg(app.colors.buffer_bg)
+        .highlight_spacing(HighlightSpacing::Always);
     f.render_stateful_widget(t, area, &mut app.state);
 }
 
+/// a header line for each row that starts a group, ie: "▾ node-1 2/3 ready"
+fn group_heads(app: &App, items: &[&RsPod]) -> Vec<Option<Line<'static>>> {
+    let Some(by) = &app.group_by else {
+        return vec![None; items.len()];
+    };
+    let all: Vec<&RsPod> = app.items.iter().collect();
+    let health = groups::health(&all, by);
+    let mut previous = None;
+    items
+        .iter()
+        .map(|pod| {
+            let key = by.key(pod);
+            if previous.as_ref() == Some(&key) {
+                return None;
+            }
+            let group = health.get(&key).copied().unwrap_or_default();
+            let fold = if app.collapsed.contains(&key) {
+                symbols().collapsed
+            } else {
+                symbols().expanded
+            };
+            let color = if group.healthy() {
+                Color::Green
+            } else {
+                Color::LightRed
+            };
+            let head = Line::from(format!("{fold} {key} {group}"))
+                .style(Style::new().fg(color).add_modifier(Modifier::BOLD));
+            previous = Some(key);
+            Some(head)
+        })
+        .collect()
+}
+
 fn render_scrollbar(f: &mut Frame, app: &mut App, area: Rect) {
     f.render_stateful_widget(
         Scrollbar::default()
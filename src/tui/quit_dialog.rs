@@ -0,0 +1,181 @@
+//!Asked on 'q' while tasks registered with `shutdown::Pending` are still
+//!changing the cluster, ie: hook actions patching a pod, instead of cutting
+//!them off.  It lists them as they finish and quits once the last one is
+//!done when asked to wait, or at once when asked to abort them.
+//!
+use crate::error::Result as NvResult;
+use crate::shutdown;
+use crate::tui::stream::Message;
+use crate::tui::ui_loop::{AppBehavior, Transition};
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use futures::Stream;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+use tokio_stream::wrappers::ReceiverStream;
+
+const POLL_MS: u64 = 250;
+
+/// operations listed, the rest are counted
+const SHOWN: usize = 8;
+
+#[derive(Clone, Debug, Default)]
+pub struct QuitDialog {
+    operations: Vec<String>,
+    /// quit as soon as `operations` is empty
+    waiting: bool,
+    /// the user went back instead of quitting
+    pub(crate) stay: bool,
+}
+
+impl QuitDialog {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            operations: shutdown::outstanding(),
+            ..Self::default()
+        }
+    }
+}
+
+impl AppBehavior for QuitDialog {
+    const NAME: &'static str = "quit";
+
+    async fn handle_event(&mut self, event: &Message) -> NvResult<Transition> {
+        match event {
+            Message::Operations(operations) => {
+                self.operations.clone_from(operations);
+                if self.waiting && self.operations.is_empty() {
+                    return Ok(Transition::Pop);
+                }
+            }
+            Message::Key(Event::Key(key)) if key.kind == KeyEventKind::Press => match key.code {
+                KeyCode::Char('w') => {
+                    self.waiting = true;
+                    if self.operations.is_empty() {
+                        return Ok(Transition::Pop);
+                    }
+                }
+                KeyCode::Char('a') => {
+                    shutdown::abort();
+                    return Ok(Transition::Pop);
+                }
+                KeyCode::Esc | KeyCode::Char('q') => {
+                    self.stay = true;
+                    return Ok(Transition::Pop);
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+        Ok(Transition::Stay)
+    }
+
+    fn draw_ui<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<(), std::io::Error> {
+        terminal.draw(|f| render(f, self))?;
+        Ok(())
+    }
+
+    fn stream(&self, should_stop: Arc<AtomicBool>) -> impl Stream<Item = Message> {
+        let (tx, rx) = mpsc::channel(100);
+
+        tokio::spawn(async move {
+            while !should_stop.load(Ordering::Relaxed) {
+                if tx
+                    .send(Message::Operations(shutdown::outstanding()))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+                sleep(Duration::from_millis(POLL_MS)).await;
+            }
+        });
+
+        ReceiverStream::new(rx)
+    }
+}
+
+fn render(f: &mut Frame, dialog: &QuitDialog) {
+    let area = Layout::vertical([Constraint::Length(SHOWN as u16 + 7)])
+        .flex(layout::Flex::Center)
+        .split(f.area())[0];
+    let area = Layout::horizontal([Constraint::Percentage(60)])
+        .flex(layout::Flex::Center)
+        .split(area)[0];
+    let mut text = vec![Line::from(match dialog.operations.len() {
+        0 => "nothing is running anymore".to_string(),
+        1 => "1 operation is still running:".to_string(),
+        n => format!("{n} operations are still running:"),
+    })];
+    text.extend(
+        dialog
+            .operations
+            .iter()
+            .take(SHOWN)
+            .map(|operation| Line::from(format!("  {operation}"))),
+    );
+    if dialog.operations.len() > SHOWN {
+        text.push(Line::from(format!(
+            "  and {} more",
+            dialog.operations.len() - SHOWN
+        )));
+    }
+    text.push(Line::from(""));
+    let dim = Style::new().add_modifier(Modifier::DIM);
+    text.push(
+        Line::from(if dialog.waiting {
+            "waiting to quit  a aborts them and quits  esc goes back"
+        } else {
+            "w waits for them and quits  a aborts them and quits  esc goes back"
+        })
+        .style(dim),
+    );
+    let paragraph = Paragraph::new(text)
+        .wrap(Wrap { trim: true })
+        .style(Style::new().fg(Color::White).bg(Color::Black))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::new().fg(Color::Yellow))
+                .title(" quit? "),
+        );
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyEvent;
+
+    fn key(c: KeyCode) -> Message {
+        Message::Key(Event::Key(KeyEvent::from(c)))
+    }
+
+    #[tokio::test]
+    async fn test_waits_for_the_last_operation() {
+        let mut dialog = QuitDialog::default();
+        let running = Message::Operations(vec!["hook oom on web-1".to_string()]);
+        assert!(matches!(
+            dialog.handle_event(&running).await,
+            Ok(Transition::Stay)
+        ));
+        let wait = dialog.handle_event(&key(KeyCode::Char('w'))).await;
+        assert!(matches!(wait, Ok(Transition::Stay)));
+        let done = dialog.handle_event(&Message::Operations(vec![])).await;
+        assert!(matches!(done, Ok(Transition::Pop)));
+        assert!(!dialog.stay);
+
+        let mut dialog = QuitDialog::default();
+        let back = dialog.handle_event(&key(KeyCode::Esc)).await;
+        assert!(matches!(back, Ok(Transition::Pop)));
+        assert!(dialog.stay);
+    }
+}
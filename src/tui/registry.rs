@@ -0,0 +1,153 @@
+//!Views the root view opens by a key alone, ie: events, diagnostics, node
+//!capacity, changes, cluster scoped resources, Gateway API, Deployments,
+//!StatefulSets and plugin views.  A new view of this kind is one `register`
+//!call here, the loop, the history and the replicaset view's keys pick it
+//!up from the registry without knowing its type.
+//!
+use crate::error::{Error, Result as NvResult};
+use crate::k8s::cache::cache_manager;
+use crate::k8s::cache::history::EVENTS_KEY;
+use crate::plugins::{self, plugins_dir};
+use crate::tui::ui_loop::{AppBehavior, Apps};
+use crate::tui::{
+    capacity_app, changes_app, cluster_app, deployment_app, diag_app, event_app, gateway_app,
+    plugin_app, statefulset_app,
+};
+use std::sync::OnceLock;
+
+/// builds a view from nothing but the key pressed
+pub type Open = fn() -> NvResult<Apps>;
+
+struct Registration {
+    name: &'static str,
+    keys: &'static [char],
+    open: Open,
+}
+
+#[derive(Default)]
+pub struct AppRegistry {
+    registrations: Vec<Registration>,
+}
+
+impl AppRegistry {
+    /// Make the view `name` reachable by `keys` from the root view.  Keys
+    /// the root view handles itself are never looked up here.
+    pub fn register(&mut self, name: &'static str, keys: &'static [char], open: Open) {
+        self.registrations.push(Registration { name, keys, open });
+    }
+
+    /// the view `key` opens, `None` when no view registered it
+    #[must_use]
+    pub fn by_key(&self, key: char) -> Option<Open> {
+        self.registrations
+            .iter()
+            .find(|registration| registration.keys.contains(&key))
+            .map(|registration| registration.open)
+    }
+
+    /// the view registered as `name`
+    #[must_use]
+    pub fn by_name(&self, name: &str) -> Option<Open> {
+        self.registrations
+            .iter()
+            .find(|registration| registration.name == name)
+            .map(|registration| registration.open)
+    }
+}
+
+fn open_events() -> NvResult<Apps> {
+    cache_manager::record_navigation(EVENTS_KEY);
+    Ok(Apps::new(event_app::app::App::new()))
+}
+
+fn open_diagnostics() -> NvResult<Apps> {
+    Ok(Apps::new(diag_app::app::App::new()))
+}
+
+fn open_capacity() -> NvResult<Apps> {
+    Ok(Apps::new(capacity_app::app::App::new()))
+}
+
+fn open_changes() -> NvResult<Apps> {
+    Ok(Apps::new(changes_app::app::App::new()))
+}
+
+fn open_cluster() -> NvResult<Apps> {
+    Ok(Apps::new(cluster_app::app::App::new()))
+}
+
+fn open_gateways() -> NvResult<Apps> {
+    Ok(Apps::new(gateway_app::app::App::new()))
+}
+
+fn open_deployments() -> NvResult<Apps> {
+    Ok(Apps::new(deployment_app::app::App::new()))
+}
+
+fn open_statefulsets() -> NvResult<Apps> {
+    Ok(Apps::new(statefulset_app::app::App::new()))
+}
+
+/// the first plugin with a view, the others are a Tab away
+fn open_plugin_view() -> NvResult<Apps> {
+    let Some(plugin) = plugins::views().next() else {
+        let dir = plugins_dir().unwrap_or_default();
+        return Err(Error::Plugin(format!(
+            "no plugin has a view, add one to {}",
+            dir.display()
+        )));
+    };
+    Ok(Apps::new(plugin_app::app::App::new(plugin)))
+}
+
+fn build() -> AppRegistry {
+    let mut registry = AppRegistry::default();
+    registry.register(event_app::app::App::NAME, &['e', 'E'], open_events);
+    registry.register(diag_app::app::App::NAME, &['d', 'D'], open_diagnostics);
+    registry.register(capacity_app::app::App::NAME, &['n', 'N'], open_capacity);
+    registry.register(gateway_app::app::App::NAME, &['r'], open_gateways);
+    registry.register(changes_app::app::App::NAME, &['h', 'H'], open_changes);
+    registry.register(cluster_app::app::App::NAME, &['K'], open_cluster);
+    registry.register(
+        deployment_app::app::App::NAME,
+        &['y', 'Y'],
+        open_deployments,
+    );
+    registry.register(
+        statefulset_app::app::App::NAME,
+        &['t', 'T'],
+        open_statefulsets,
+    );
+    registry.register(plugin_app::app::App::NAME, &['v'], open_plugin_view);
+    registry
+}
+
+#[must_use]
+pub fn registry() -> &'static AppRegistry {
+    static REGISTRY: OnceLock<AppRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(build)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registered_keys_are_distinct() {
+        let registry = build();
+        let mut keys: Vec<char> = registry
+            .registrations
+            .iter()
+            .flat_map(|registration| registration.keys.iter().copied())
+            .collect();
+        let count = keys.len();
+        keys.sort_unstable();
+        keys.dedup();
+        assert_eq!(keys.len(), count);
+
+        assert!(registry.by_key('e').is_some());
+        assert!(registry.by_key('x').is_none());
+        assert!(registry.by_name(diag_app::app::App::NAME).is_some());
+        assert!(registry.by_name("pods").is_none());
+    }
+}
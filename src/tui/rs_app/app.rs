@@ -1,20 +1,44 @@
-use crate::k8s::rs::list_replicas;
+use crate::error::Error;
+use crate::error::Result as NvResult;
+use crate::k8s::actions::Target;
+use crate::k8s::cache::cache_manager::{self, InFlight};
+use crate::k8s::cache::CachedData;
+use crate::k8s::cache::DataRequest;
+use crate::k8s::containers::{fetch_containers, search_replica_logs};
+use crate::k8s::list_filter::ListFilter;
+use crate::k8s::retry::{with_retry, RetryPolicy};
+use crate::k8s::rs::list_replicas_paged;
+use crate::k8s::scan::background;
+use crate::k8s::sidecars;
+use crate::plugins;
 use crate::tui::data::{rs_constraint_len_calculator, Rs};
+use crate::tui::export_dialog::{self, ExportDialog};
+use crate::tui::filter_history::FilterRecall;
+use crate::tui::filter_pattern;
+use crate::tui::marks::{self, Scope};
+use crate::tui::metadata_editor::{self, MetadataEditor};
+use crate::tui::open_menu::{self, context_vars, OpenMenu};
+use crate::tui::plugin_menu::{self, PluginMenu};
 use crate::tui::pod_app;
+use crate::tui::registry::registry;
+use crate::tui::rs_app::log_search::{LogSearch, SearchAction, SEARCH_TAIL_LINES};
 use crate::tui::rs_app::ui;
+use crate::tui::sort::{sort_rows, Columns, SortBy};
 use crate::tui::stream::Message;
 use crate::tui::style::{TableColors, ITEM_HEIGHT, PALETTES};
+use crate::tui::table_search::TableSearch;
 use crate::tui::table_ui::TuiTableState;
-use crate::tui::ui_loop::{create_ingress_data_vec, AppBehavior, Apps};
-use crate::tui::{event_app, ingress_app};
+use crate::tui::ui_loop::{create_ingress_data_vec, AppBehavior, Apps, Transition};
+use crate::tui::{container_group_app, ingress_app, log_app};
 use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
 use futures::Stream;
 use ratatui::prelude::*;
 use ratatui::widgets::{ScrollbarState, TableState};
-use std::io;
+use regex::Regex;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::mpsc;
 use tokio::time::sleep;
 use tokio_stream::wrappers::ReceiverStream;
@@ -26,18 +50,45 @@ const POLL_MS: u64 = 5000;
 pub struct App {
     pub(crate) state: TableState,
     pub(crate) items: Vec<Rs>,
-    pub(crate) longest_item_lens: (u16, u16, u16, u16, u16),
+    pub(crate) longest_item_lens: (u16, u16, u16, u16, u16, u16),
     pub(crate) scroll_state: ScrollbarState,
     pub(crate) colors: TableColors,
     pub(crate) color_index: usize,
     pub(crate) filter: String,
+    pub(crate) recall: FilterRecall,
     pub(crate) show_filter_edit: bool,
     pub(crate) edit_filter_cursor_position: usize,
+    pub(crate) metadata_editor: Option<Box<MetadataEditor>>,
+    pub(crate) log_search: Option<Box<LogSearch>>,
+    pub(crate) export_dialog: Option<Box<ExportDialog>>,
+    pub(crate) search: TableSearch,
+    pub(crate) open_menu: Option<Box<OpenMenu>>,
+    pub(crate) plugin_menu: Option<Box<PluginMenu>>,
+    /// scroll offset of the audit overlay, `None` while it is closed
+    pub(crate) audit_offset: Option<usize>,
+    pub(crate) sort: Option<SortBy>,
+    /// what the api server filters the replicasets by, shared with the
+    /// stream polling them
+    pub(crate) pushdown: Arc<RwLock<ListFilter>>,
+    /// the deployment whose replicasets are shown, every one when `None`
+    pub(crate) owner: Option<String>,
 }
 
 impl TuiTableState for App {
     type Item = Rs;
 
+    fn mark_scope(&self) -> Option<Scope> {
+        Some(Scope::ReplicaSets)
+    }
+
+    fn table_search(&mut self) -> Option<&mut TableSearch> {
+        Some(&mut self.search)
+    }
+
+    fn search_key(&self) -> char {
+        '?'
+    }
+
     fn get_items(&self) -> &[Self::Item] {
         &self.items
     }
@@ -79,6 +130,10 @@ impl TuiTableState for App {
         self.filter = filter;
     }
 
+    fn is_filter_pushed_down(&self) -> bool {
+        self.pushdown.read().is_ok_and(|filter| !filter.is_empty())
+    }
+
     fn set_cursor_pos(&mut self, cursor_pos: usize) {
         self.edit_filter_cursor_position = cursor_pos;
     }
@@ -99,9 +154,62 @@ impl TuiTableState for App {
 }
 
 impl AppBehavior for App {
-    async fn handle_event(&mut self, event: &Message) -> Result<Option<Apps>, io::Error> {
+    const NAME: &'static str = "replicasets";
+
+    async fn handle_event(&mut self, event: &Message) -> NvResult<Transition> {
         if self.get_show_filter_edit() {
-            Ok(self.handle_filter_edit_event(event))
+            self.handle_filter_edit_event(event);
+            if !self.get_show_filter_edit() {
+                self.push_filter_down().await?;
+            }
+            Ok(Transition::Stay)
+        } else if self.metadata_editor.is_some() {
+            if let Message::Key(Event::Key(key)) = event {
+                if key.kind == KeyEventKind::Press {
+                    metadata_editor::handle_key(&mut self.metadata_editor, key).await;
+                }
+            }
+            Ok(Transition::Stay)
+        } else if self.log_search.is_some() {
+            match event {
+                Message::Key(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                    Ok(self.handle_log_search_key(key.code).await)
+                }
+                _ => Ok(Transition::Stay),
+            }
+        } else if self.export_dialog.is_some() {
+            if let Message::Key(Event::Key(key)) = event {
+                if key.kind == KeyEventKind::Press {
+                    export_dialog::handle_key(&mut self.export_dialog, key);
+                }
+            }
+            Ok(Transition::Stay)
+        } else if self.open_menu.is_some() {
+            if let Message::Key(Event::Key(key)) = event {
+                if key.kind == KeyEventKind::Press {
+                    open_menu::handle_key(&mut self.open_menu, key);
+                }
+            }
+            Ok(Transition::Stay)
+        } else if self.plugin_menu.is_some() {
+            if let Message::Key(Event::Key(key)) = event {
+                if key.kind == KeyEventKind::Press {
+                    plugin_menu::handle_key(&mut self.plugin_menu, key).await;
+                }
+            }
+            Ok(Transition::Stay)
+        } else if let Some(offset) = self.audit_offset {
+            if let Message::Key(Event::Key(key)) = event {
+                if key.kind == KeyEventKind::Press {
+                    self.audit_offset = match key.code {
+                        KeyCode::Char('j') | KeyCode::Down => Some(offset + 1),
+                        KeyCode::Char('k') | KeyCode::Up => Some(offset.saturating_sub(1)),
+                        KeyCode::Esc | KeyCode::Char('q' | 'a' | 'A') => None,
+                        _ => Some(offset),
+                    };
+                }
+            }
+            Ok(Transition::Stay)
         } else {
             self.handle_table_event(event).await
         }
@@ -112,15 +220,53 @@ impl AppBehavior for App {
         Ok(())
     }
 
+    fn source(&self) -> Option<DataRequest> {
+        let filter = self.pushdown.read().ok()?.clone();
+        Some(if filter.is_empty() {
+            DataRequest::ReplicaSets
+        } else {
+            DataRequest::ReplicaSetsMatching(filter)
+        })
+    }
+
     fn stream(&self, should_stop: Arc<AtomicBool>) -> impl Stream<Item = Message> {
         let (tx, rx) = mpsc::channel(1);
         let initial_items = self.get_items().to_vec(); // Clone or get owned data from self
+        let pushdown = self.pushdown.clone();
 
+        // redraw when the pods of a replicaset land so its row shows ready
+        let mut landed = cache_manager::get_cache().subscribe();
+        let prefetched = tx.clone();
+        let stop_prefetched = should_stop.clone();
         tokio::spawn(async move {
+            while !stop_prefetched.load(Ordering::Relaxed) {
+                match landed.recv().await {
+                    Ok(DataRequest::Pods { .. }) => {
+                        if prefetched.send(Message::Prefetched).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+
+        tokio::spawn(async move {
+            // rows of a large namespace show as pages land, never fewer
+            // than the view already has
+            let partial = |rows: &[Rs]| {
+                if rows.len() > initial_items.len() {
+                    _ = tx.try_send(Message::Rs(rows.to_vec()));
+                }
+            };
             while !should_stop.load(Ordering::Relaxed) {
-                match list_replicas().await {
+                let filter = pushdown.read().map(|f| f.clone()).unwrap_or_default();
+                match list_replicas_paged(&filter, &partial).await {
                     Ok(new_items) => {
-                        if !new_items.is_empty() && new_items != initial_items {
+                        // the filter changed while listing, the view has newer rows
+                        let current = pushdown.read().is_ok_and(|f| *f == filter);
+                        if current && !new_items.is_empty() && new_items != initial_items {
                             let sevent = Message::Rs(new_items);
                             if tx.send(sevent).await.is_err() {
                                 break;
@@ -128,6 +274,10 @@ impl AppBehavior for App {
                         }
                         sleep(Duration::from_millis(POLL_MS)).await;
                     }
+                    Err(Error::Timeout { .. }) => {
+                        // already surfaced in the header, keep polling
+                        sleep(Duration::from_millis(POLL_MS)).await;
+                    }
                     Err(_e) => {
                         break;
                     }
@@ -149,155 +299,298 @@ impl App {
             color_index: 0,
             items: data_vec,
             filter: String::new(),
+            recall: FilterRecall::new(Self::NAME),
             show_filter_edit: false,
             edit_filter_cursor_position: 0,
+            metadata_editor: None,
+            log_search: None,
+            export_dialog: None,
+            search: TableSearch::default(),
+            open_menu: None,
+            plugin_menu: None,
+            audit_offset: None,
+            sort: None,
+            pushdown: Arc::default(),
+            owner: None,
         }
     }
 
-    fn handle_filter_edit_event(&mut self, event: &Message) -> Option<Apps> {
-        let mut app_holder = Some(Apps::Rs { app: self.clone() });
-        match event {
-            Message::Key(Event::Key(key)) => {
-                if key.kind == KeyEventKind::Press {
-                    use KeyCode::{Backspace, Char, Enter, Esc, Left, Right};
+    /// the replicasets of the deployment `owner`
+    pub fn owned_by(owner: &str, data_vec: Vec<Rs>) -> Self {
+        let owner = Some(owner.to_string());
+        let mut app = Self::new(owned(owner.as_deref(), data_vec));
+        app.owner = owner;
+        app
+    }
 
-                    match key.code {
-                        Char(to_insert) => {
-                            self.enter_char(to_insert);
-                            app_holder = Some(Apps::Rs { app: self.clone() });
-                        }
-                        Backspace => {
-                            self.delete_char();
-                            app_holder = Some(Apps::Rs { app: self.clone() });
-                        }
-                        Left => {
-                            self.move_cursor_left();
-                            app_holder = Some(Apps::Rs { app: self.clone() });
-                        }
-                        Right => {
-                            self.move_cursor_right();
-                            app_holder = Some(Apps::Rs { app: self.clone() });
-                        }
-                        Esc | Enter => {
-                            self.set_show_filter_edit(false);
-                            app_holder = Some(Apps::Rs { app: self.clone() });
+    /// Have the api server apply a filter it understands, a name or label
+    /// selector, instead of listing every replicaset to match it here.
+    async fn push_filter_down(&mut self) -> NvResult<()> {
+        let filter = ListFilter::parse(&self.filter).unwrap_or_default();
+        let changed = self.pushdown.write().is_ok_and(|mut current| {
+            let changed = *current != filter;
+            *current = filter.clone();
+            changed
+        });
+        if changed {
+            let request = if filter.is_empty() {
+                DataRequest::ReplicaSets
+            } else {
+                DataRequest::ReplicaSetsMatching(filter)
+            };
+            if let CachedData::ReplicaSets(items) = cache_manager::get_or_fetch(request).await? {
+                let items = owned(self.owner.as_deref(), items);
+                self.longest_item_lens = rs_constraint_len_calculator(&items);
+                self.items = items;
+                sort_rows(&mut self.items, self.sort);
+                self.reset_selection_state();
+            }
+        }
+        Ok(())
+    }
+
+    /// warm the pods of the selected replicaset so Enter does not wait
+    fn prefetch_selected(&mut self) {
+        if let Some(selector) = self.get_selected_item().and_then(|rs| rs.selectors.clone()) {
+            cache_manager::prefetch(DataRequest::Pods { selector });
+        }
+    }
+
+    async fn handle_log_search_key(&mut self, code: KeyCode) -> Transition {
+        let Some(search) = self.log_search.as_mut() else {
+            return Transition::Stay;
+        };
+        match search.handle_key(code) {
+            SearchAction::Close => self.log_search = None,
+            SearchAction::Search => match Regex::new(&search.pattern) {
+                Ok(pattern) => {
+                    search.selected = 0;
+                    match search_replica_logs(
+                        &search.selector,
+                        &pattern,
+                        SEARCH_TAIL_LINES,
+                        sidecars::shown(),
+                    )
+                    .await
+                    {
+                        Ok(results) => {
+                            search.message = Some(format!(
+                                "{} matches in {} containers",
+                                results.iter().map(|r| r.matches.len()).sum::<usize>(),
+                                results.len()
+                            ));
+                            search.results = results;
                         }
-                        _ => {}
+                        Err(e) => search.message = Some(format!("{}: {e}", e.code())),
                     }
                 }
+                Err(e) => {
+                    search.message = Some(format!("invalid pattern: {e}"));
+                    search.editing = true;
+                }
+            },
+            SearchAction::Open { result, line } => {
+                if let Some(result) = search.results.get(result) {
+                    return Transition::Push(Box::new(Apps::new(log_app::app::App::with_context(
+                        search.selector.clone(),
+                        result.pod.clone(),
+                        result.container.clone(),
+                        result.logs.clone(),
+                        line,
+                    ))));
+                }
             }
-            Message::Rs(data_vec) => {
-                debug!("updating rs app data...");
-                let new_app = Self {
-                    longest_item_lens: rs_constraint_len_calculator(data_vec),
-                    scroll_state: ScrollbarState::new(
-                        data_vec.len().saturating_sub(1) * ITEM_HEIGHT,
-                    ),
-                    items: data_vec.clone(),
-                    ..self.clone()
-                };
-                let new_app_holder = Apps::Rs { app: new_app };
-                app_holder = Some(new_app_holder);
+            SearchAction::None => {}
+        }
+        Transition::Stay
+    }
+
+    fn update_items(&mut self, data_vec: &[Rs]) {
+        debug!("updating rs app data...");
+        let data_vec = owned(self.owner.as_deref(), data_vec.to_vec());
+        self.longest_item_lens = rs_constraint_len_calculator(&data_vec);
+        self.scroll_state = ScrollbarState::new(data_vec.len().saturating_sub(1) * ITEM_HEIGHT);
+        self.items = data_vec;
+        sort_rows(&mut self.items, self.sort);
+        marks::land(self);
+    }
+
+    fn handle_filter_edit_event(&mut self, event: &Message) {
+        match event {
+            Message::Key(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                use KeyCode::{Backspace, Char, Enter, Esc, Left, Right};
+
+                if filter_pattern::toggle(key)
+                    || self.recall.consume(
+                        key,
+                        &mut self.filter,
+                        &mut self.edit_filter_cursor_position,
+                    )
+                {
+                    return;
+                }
+                match key.code {
+                    Char(to_insert) => self.enter_char(to_insert),
+                    Backspace => self.delete_char(),
+                    Left => self.move_cursor_left(),
+                    Right => self.move_cursor_right(),
+                    Esc | Enter => self.set_show_filter_edit(false),
+                    _ => {}
+                }
             }
+            Message::Rs(data_vec) => self.update_items(data_vec),
             _ => {}
         }
-        app_holder
     }
 
-    async fn handle_table_event(&mut self, event: &Message) -> Result<Option<Apps>, io::Error> {
-        let mut app_holder = Some(Apps::Rs { app: self.clone() });
+    async fn handle_table_event(&mut self, event: &Message) -> NvResult<Transition> {
         match event {
-            Message::Key(Event::Key(key)) => {
-                if key.kind == KeyEventKind::Press {
-                    use KeyCode::{Char, Down, Enter, Up};
+            Message::Key(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                use KeyCode::{Char, Down, Enter, Up};
 
-                    match key.code {
-                        Char('q') => {
-                            app_holder = None;
-                            debug!("quitting...");
-                        }
-                        Char('j') | Down => {
-                            self.next();
-                            app_holder = Some(Apps::Rs { app: self.clone() });
-                        }
-                        Char('k') | Up => {
-                            self.previous();
-                            app_holder = Some(Apps::Rs { app: self.clone() });
+                if let Some(transition) = self.handle_mark(key) {
+                    return Ok(transition);
+                }
+                if self.handle_search(key) || self.handle_motion(key) {
+                    self.prefetch_selected();
+                    return Ok(Transition::Stay);
+                }
+                match key.code {
+                    Char('q') => {
+                        debug!("quitting...");
+                        return Ok(Transition::Pop);
+                    }
+                    Char('j') | Down => {
+                        self.next();
+                        self.prefetch_selected();
+                    }
+                    Char('k') | Up => {
+                        self.previous();
+                        self.prefetch_selected();
+                    }
+                    Char('c' | 'C') => self.next_color(),
+                    Char('l' | 'L') => {
+                        self.metadata_editor = self
+                            .get_selected_item()
+                            .map(|rs| {
+                                let mut targets = vec![Target::ReplicaSet(rs.name.clone())];
+                                if rs.description.starts_with("Deployment") {
+                                    targets.push(Target::Deployment(rs.owner.clone()));
+                                }
+                                targets
+                            })
+                            .and_then(MetadataEditor::new)
+                            .map(Box::new);
+                    }
+                    Char('s') => {
+                        self.log_search = self.get_selected_item().and_then(|rs| {
+                            let selector = rs.selectors.clone()?;
+                            Some(Box::new(LogSearch::new(rs.name.clone(), selector)))
+                        });
+                    }
+                    Char('o') => {
+                        self.sort = SortBy::next(
+                            self.sort,
+                            self.items.first().map_or(0, |item| item.columns().len()),
+                        );
+                        sort_rows(&mut self.items, self.sort);
+                    }
+                    Char('O') => {
+                        self.sort = SortBy::flip(self.sort);
+                        sort_rows(&mut self.items, self.sort);
+                    }
+                    Char('w' | 'W') => {
+                        if let Some(selection) = self.get_selected_item() {
+                            let replicaset = selection.name.clone();
+                            let mut vars = context_vars(None).await;
+                            vars.insert("replicaset", replicaset);
+                            self.open_menu = Some(Box::new(OpenMenu::new(&vars)));
                         }
-                        Char('c' | 'C') => {
-                            self.next_color();
-                            app_holder = Some(Apps::Rs { app: self.clone() });
+                    }
+                    Char('V') => {
+                        if let Some(selection) = self.get_selected_item() {
+                            let name = selection.name.clone();
+                            let namespace = context_vars(None)
+                                .await
+                                .remove("namespace")
+                                .unwrap_or_default();
+                            self.plugin_menu = Some(Box::new(PluginMenu::new(plugins::Target {
+                                kind: "replicaset".to_string(),
+                                namespace,
+                                name,
+                            })));
                         }
-                        Char('e' | 'E') => {
-                            let new_app_holder = Apps::Event {
-                                app: event_app::app::App::new(),
+                    }
+                    Char('a' | 'A') => self.audit_offset = Some(0),
+                    Char('i' | 'I') => {
+                        if let Some(selection) = self.get_selected_item() {
+                            if let Some(selector) = selection.selectors.clone() {
+                                let data_vec = create_ingress_data_vec(selector.clone()).await?;
+                                debug!("changing app from rs to ingress...");
+                                return Ok(Transition::Push(Box::new(Apps::new(
+                                    ingress_app::app::App::new(selector, data_vec),
+                                ))));
                             };
-                            app_holder = Some(new_app_holder);
-                            debug!("changing app from rs to event...");
-                        }
-                        Char('i' | 'I') => {
-                            if let Some(selection) = self.get_selected_item() {
-                                if let Some(selector) = selection.selectors.clone() {
-                                    let data_vec =
-                                        create_ingress_data_vec(selector.clone()).await?;
-                                    let new_app_holder = Apps::Ingress {
-                                        app: ingress_app::app::App::new(data_vec),
-                                    };
-                                    app_holder = Some(new_app_holder);
-                                    debug!("changing app from rs to ingress...");
-                                };
+                        };
+                    }
+                    Char('f' | 'F') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.page_forward();
+                    }
+                    Char('b' | 'B') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.page_backward();
+                    }
+                    // with a modifier, the containers of all the pods at once
+                    Enter if !key.modifiers.is_empty() => {
+                        if let Some(selectors) = self
+                            .get_selected_item()
+                            .and_then(|selection| selection.selectors.clone())
+                        {
+                            let data_vec = {
+                                let _blocking = InFlight::blocking();
+                                with_retry(&RetryPolicy::default(), || {
+                                    fetch_containers(&selectors, None)
+                                })
+                                .await?
                             };
+                            debug!("changing app from rs to replicaset containers...");
+                            return Ok(Transition::Push(Box::new(Apps::new(
+                                container_group_app::app::App::new(&data_vec),
+                            ))));
                         }
-                        Char('f' | 'F') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                            self.page_forward();
-                        }
-                        Char('b' | 'B') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                            self.page_backward();
-                        }
-                        Enter => {
-                            if let Some(selection) = self.get_selected_item() {
-                                if let Some(selectors) = selection.selectors.clone() {
-                                    let data_vec = vec![];
-                                    let new_app_holder = Apps::Pod {
-                                        app: pod_app::app::App::new(selectors, data_vec),
-                                    };
-                                    app_holder = Some(new_app_holder);
-                                    debug!("changing app from rs to pod...");
-                                };
+                    }
+                    Enter => {
+                        if let Some(selection) = self.get_selected_item() {
+                            if let Some(selectors) = selection.selectors.clone() {
+                                cache_manager::record_navigation(&selection.name);
+                                let data_vec =
+                                    cache_manager::cached_pods(&selectors).unwrap_or_default();
+                                debug!("changing app from rs to pod...");
+                                return Ok(Transition::Push(Box::new(Apps::new(
+                                    pod_app::app::App::new(selectors, data_vec),
+                                ))));
                             };
+                        };
+                    }
+                    Char('/') => self.set_show_filter_edit(true),
+                    Char('R') => return Ok(Transition::Refresh),
+                    Char('S') => _ = background::start(),
+                    Char('x') => self.export_dialog = Some(Box::default()),
+                    // the views registered to open from here by a key
+                    Char(c) => {
+                        if let Some(open) = registry().by_key(c) {
+                            return Ok(Transition::Push(Box::new(open()?)));
                         }
-                        Char('/') => {
-                            self.set_show_filter_edit(true);
-                            app_holder = Some(Apps::Rs { app: self.clone() });
-                        }
-                        _k => {}
                     }
+                    _k => {}
                 }
             }
             Message::Rs(data_vec) => {
-                debug!("updating rs app data...");
-                let new_app = Self {
-                    longest_item_lens: rs_constraint_len_calculator(data_vec),
-                    scroll_state: ScrollbarState::new(
-                        data_vec.len().saturating_sub(1) * ITEM_HEIGHT,
-                    ),
-                    items: data_vec.clone(),
-                    ..self.clone()
-                };
-                let new_app_holder = Apps::Rs { app: new_app };
-                app_holder = Some(new_app_holder);
+                self.update_items(data_vec);
+                self.prefetch_selected();
             }
             _ => {}
         }
-        Ok(app_holder)
-    }
-
-    pub fn set_cursor_pos(&mut self, cursor_pos: usize) {
-        self.edit_filter_cursor_position = cursor_pos;
-    }
-    #[allow(clippy::missing_const_for_fn)]
-    pub fn get_cursor_pos(&self) -> usize {
-        self.edit_filter_cursor_position
+        Ok(Transition::Stay)
     }
 
     pub fn set_show_filter_edit(&mut self, show_filter_edit: bool) {
@@ -335,3 +628,11 @@ impl App {
         })
     }
 }
+
+/// the replicasets of `owner`, all of them when `None`
+fn owned(owner: Option<&str>, mut data_vec: Vec<Rs>) -> Vec<Rs> {
+    if let Some(owner) = owner {
+        data_vec.retain(|rs| rs.owner == owner);
+    }
+    data_vec
+}
@@ -0,0 +1,93 @@
+//!Grep the recent logs of every pod of a replicaset at once, with the
+//!matches grouped by pod and Enter opening the log view at the match.
+//!
+use crate::k8s::containers::ReplicaLogs;
+use crossterm::event::KeyCode;
+use std::collections::BTreeMap;
+
+/// lines of each container searched
+pub const SEARCH_TAIL_LINES: i64 = 500;
+
+/// what the rs app should do after the overlay handled a key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SearchAction {
+    None,
+    Search,
+    /// open the log view of `results[result]` at `logs[line]`
+    Open {
+        result: usize,
+        line: usize,
+    },
+    Close,
+}
+
+#[derive(Clone, Debug)]
+pub struct LogSearch {
+    pub(crate) selector: BTreeMap<String, String>,
+    pub(crate) replicaset: String,
+    pub(crate) pattern: String,
+    /// typing the pattern rather than moving through matches
+    pub(crate) editing: bool,
+    pub(crate) results: Vec<ReplicaLogs>,
+    /// index into `matches()`
+    pub(crate) selected: usize,
+    pub(crate) message: Option<String>,
+}
+
+impl LogSearch {
+    #[must_use]
+    pub const fn new(replicaset: String, selector: BTreeMap<String, String>) -> Self {
+        Self {
+            selector,
+            replicaset,
+            pattern: String::new(),
+            editing: true,
+            results: vec![],
+            selected: 0,
+            message: None,
+        }
+    }
+
+    /// (result, line) of every match, grouped by pod
+    #[must_use]
+    pub fn matches(&self) -> Vec<(usize, usize)> {
+        self.results
+            .iter()
+            .enumerate()
+            .flat_map(|(i, result)| result.matches.iter().map(move |line| (i, *line)))
+            .collect()
+    }
+
+    pub fn handle_key(&mut self, code: KeyCode) -> SearchAction {
+        if self.editing {
+            match code {
+                KeyCode::Esc => return SearchAction::Close,
+                KeyCode::Enter if !self.pattern.is_empty() => {
+                    self.editing = false;
+                    return SearchAction::Search;
+                }
+                KeyCode::Backspace => {
+                    self.pattern.pop();
+                }
+                KeyCode::Char(c) => self.pattern.push(c),
+                _ => {}
+            }
+            return SearchAction::None;
+        }
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') => return SearchAction::Close,
+            KeyCode::Char('/' | 'g') => self.editing = true,
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.selected = (self.selected + 1).min(self.matches().len().saturating_sub(1));
+            }
+            KeyCode::Char('k') | KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Enter => {
+                if let Some((result, line)) = self.matches().get(self.selected).copied() {
+                    return SearchAction::Open { result, line };
+                }
+            }
+            _ => {}
+        }
+        SearchAction::None
+    }
+}
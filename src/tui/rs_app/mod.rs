@@ -1,2 +1,3 @@
 pub mod app;
+pub mod log_search;
 pub mod ui;
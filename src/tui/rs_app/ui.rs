@@ -1,19 +1,68 @@
+use crate::config::settings;
+use crate::k8s::audit;
+use crate::k8s::cache::cache_manager::get_cache;
+use crate::k8s::cache::{DataRequest, FetchStatus};
+use crate::k8s::problems::problem_counts;
+use crate::tui::data::Rs;
+use crate::tui::error_card;
+use crate::tui::export_dialog;
+use crate::tui::filter_pattern;
+use crate::tui::metadata_editor;
+use crate::tui::open_menu;
+use crate::tui::plugin_menu;
 use crate::tui::rs_app::app::App;
+use crate::tui::rs_app::log_search::LogSearch;
+use crate::tui::sort::{custom_constraints, headers, Columns};
+use crate::tui::status_line;
 use crate::tui::table_ui::render_detail_section;
+use crate::tui::table_ui::with_timeout_notice;
 use crate::tui::table_ui::TuiTableState;
+use crate::tui::theme::symbols;
+use crate::tui::utils::names::{middle_ellipsis, truncated, MAX_NAME_WIDTH};
+use crate::tui::utils::time::{timezone, EVENT_TIME_FORMAT};
 use ratatui::widgets::{Cell, HighlightSpacing, Row, Scrollbar, ScrollbarOrientation, Table};
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Clear, Paragraph},
 };
+const DESCRIPTION_COLUMN: usize = 3;
+
 pub fn ui(f: &mut Frame, app: &mut App) {
-    let rects = Layout::vertical([Constraint::Min(8), Constraint::Percentage(40)]).split(f.area());
+    status_line::set_selected(
+        app.get_selected_item()
+            .filter(|rs| truncated(&rs.name))
+            .map(|rs| rs.name.clone()),
+    );
+    let rects = Layout::vertical([Constraint::Min(8), Constraint::Percentage(40)])
+        .split(status_line::render(f));
     let table_area = rects[0];
     let details_area = rects[1];
 
     app.set_colors();
 
     render_ui_sections(f, app, table_area, details_area);
+
+    if let Some(editor) = &app.metadata_editor {
+        metadata_editor::render(f, editor);
+    }
+    if let Some(search) = &app.log_search {
+        render_log_search(f, search);
+    }
+    if let Some(offset) = app.audit_offset {
+        render_audit(f, offset);
+    }
+    if let Some(menu) = &app.open_menu {
+        open_menu::render(f, menu);
+    }
+    if let Some(menu) = &app.plugin_menu {
+        plugin_menu::render(f, menu);
+    }
+    if let Some(dialog) = &app.export_dialog {
+        export_dialog::render(f, dialog);
+    }
+
+    status_line::render_activity(f);
+    error_card::render(f);
 }
 
 fn render_ui_sections(f: &mut Frame, app: &mut App, table_area: Rect, details_area: Rect) {
@@ -43,6 +92,8 @@ fn render_filter_edit(f: &mut Frame, app: &App) {
 
     f.render_widget(Clear, input_area); //this clears out the background
     f.render_widget(block, input_area);
+    filter_pattern::render_error(f, input_area, &app.filter);
+    app.recall.render(f, input_area);
 
     #[allow(clippy::cast_possible_truncation)]
     let p = Position {
@@ -75,12 +126,17 @@ fn render_details(f: &mut Frame, app: &mut App, area: Rect) {
     let event_details = app.get_event_details();
 
     let (foreground_color, background_color) = get_colors(app);
+    // the name the table cut short, in full
+    let title = app
+        .get_selected_item()
+        .filter(|rs| truncated(&rs.name))
+        .map_or_else(|| "Label".to_string(), |rs| format!("Label: {}", rs.name));
     render_detail_section(
         f,
         foreground_color,
         background_color,
         detail_rects[0],
-        "Label",
+        &title,
         &left_details,
     );
     render_detail_section(
@@ -97,6 +153,20 @@ const fn get_colors(app: &App) -> (Color, Color) {
     (app.colors.header_fg, app.colors.buffer_bg)
 }
 
+/// whether Enter on the replicaset will show its pods right away
+fn prefetch_hint(rs: &Rs) -> Line<'static> {
+    let Some(selector) = rs.selectors.clone() else {
+        return Line::from("");
+    };
+    match get_cache().status(&DataRequest::Pods { selector }) {
+        FetchStatus::Missing => Line::from(format!("prefetching{}", symbols().ellipsis))
+            .style(Style::new().add_modifier(Modifier::DIM)),
+        FetchStatus::Stale | FetchStatus::Fresh => {
+            Line::from(format!("{} ready", symbols().ok)).style(Style::new().fg(Color::Green))
+        }
+    }
+}
+
 fn render_table(f: &mut Frame, app: &App, area: Rect) {
     let header_style = Style::default()
         .fg(app.colors.header_fg)
@@ -105,52 +175,83 @@ fn render_table(f: &mut Frame, app: &App, area: Rect) {
         .add_modifier(Modifier::REVERSED)
         .fg(app.colors.selected_style_fg);
 
-    let filter_header = match app.get_filter() {
-        filter if filter != String::new() => format!("ReplicaSet ({filter})"),
+    let filter_header = with_timeout_notice(app.search.header(match app.get_filter() {
+        filter if filter != String::new() => {
+            format!("ReplicaSet ({})", filter_pattern::label(&filter))
+        }
         _ => "ReplicaSet".to_string(),
-    };
+    }));
 
-    let header = [&filter_header, "P", "Age", "Description", "Owner"]
-        .iter()
-        .copied()
-        .map(Cell::from)
-        .collect::<Row>()
-        .style(header_style)
-        .height(1);
-    let rows = app
-        .get_filtered_items()
-        .into_iter()
-        .enumerate()
-        .map(|(i, data)| {
-            let color = match i % 2 {
-                0 => app.colors.normal_row_color,
-                _ => app.colors.alt_row_color,
-            };
-            let item = data.ref_array();
-            item.iter()
-                .copied()
-                .map(|content| Cell::from(Text::from(format!("\n{content}\n"))))
-                .collect::<Row>()
-                .style(Style::new().fg(app.colors.row_fg).bg(color))
-                .height(3) // height
-        });
-    let bar = " █ ";
-    let t = Table::new(
-        rows,
-        [
-            // + 1 is for padding.
-            Constraint::Min(app.longest_item_lens.0 + 1),
-            Constraint::Min(app.longest_item_lens.1 + 1),
-            Constraint::Min(app.longest_item_lens.2 + 1),
-            Constraint::Min(app.longest_item_lens.3 + 1),
-            Constraint::Min(app.longest_item_lens.4),
+    let custom = &settings().columns.rs;
+    let header = headers(
+        &[
+            &filter_header,
+            "P",
+            "Age",
+            "Description",
+            "Owner",
+            "Deprecated",
         ],
+        custom,
+        app.sort,
     )
-    .header(header)
-    .row_highlight_style(selected_style)
-    .highlight_symbol(Text::from(vec!["".into(), bar.into(), "".into()]))
-    .bg(app.colors.buffer_bg)
-    .highlight_spacing(HighlightSpacing::Always);
+    .into_iter()
+    .map(Cell::from)
+    .collect::<Row>()
+    .style(header_style)
+    .height(1);
+    let items = app.get_filtered_items();
+    let selected = app.state.selected();
+    let mut widths = vec![
+        // + 1 is for padding.
+        Constraint::Min(app.longest_item_lens.0.min(MAX_NAME_WIDTH as u16) + 1),
+        Constraint::Min(app.longest_item_lens.1 + 1),
+        Constraint::Min(app.longest_item_lens.2 + 1),
+        Constraint::Min(app.longest_item_lens.3 + 1),
+        Constraint::Min(app.longest_item_lens.4 + 1),
+        Constraint::Min(app.longest_item_lens.5),
+    ];
+    widths.extend(custom_constraints(&items, widths.len(), custom));
+    let rows = items.into_iter().enumerate().map(|(i, data)| {
+        let color = match i % 2 {
+            0 => app.colors.normal_row_color,
+            _ => app.colors.alt_row_color,
+        };
+        let problems = problem_counts(&data.name);
+        data.columns()
+            .into_iter()
+            .enumerate()
+            .map(|(column, content)| match column {
+                0 if selected == Some(i) => Cell::from(Text::from(vec![
+                    Line::from(""),
+                    app.search
+                        .highlight(middle_ellipsis(content, MAX_NAME_WIDTH)),
+                    prefetch_hint(data),
+                ])),
+                0 => Cell::from(app.search.cell(middle_ellipsis(content, MAX_NAME_WIDTH))),
+                // problems of the pods go under the kind
+                DESCRIPTION_COLUMN if !problems.is_empty() => Cell::from(Text::from(vec![
+                    Line::from(""),
+                    Line::from(content),
+                    Line::from(problems.badges()).style(
+                        Style::new()
+                            .fg(Color::LightRed)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                ])),
+                _ => Cell::from(Text::from(format!("\n{content}\n"))),
+            })
+            .collect::<Row>()
+            .style(Style::new().fg(app.colors.row_fg).bg(color))
+            .height(3) // height
+    });
+    let bar = format!(" {} ", symbols().bar);
+    let t = Table::new(rows, widths)
+        .header(header)
+        .row_highlight_style(selected_style)
+        .highlight_symbol(Text::from(vec!["".into(), bar.into(), "".into()]))
+        .bg(app.colors.buffer_bg)
+        .highlight_spacing(HighlightSpacing::Always);
     f.render_stateful_widget(t, area, &mut app.state.clone());
 }
 
@@ -167,3 +268,136 @@ fn render_scrollbar(f: &mut Frame, app: &App, area: Rect) {
         &mut app.scroll_state.clone(),
     );
 }
+
+/// matches grouped under a line per pod and container
+fn render_log_search(f: &mut Frame, search: &LogSearch) {
+    let area = Layout::vertical([Constraint::Percentage(80)])
+        .flex(layout::Flex::Center)
+        .split(f.area())[0];
+    let area = Layout::horizontal([Constraint::Percentage(90)])
+        .flex(layout::Flex::Center)
+        .split(area)[0];
+
+    let cursor = if search.editing { symbols().cursor } else { "" };
+    let mut lines = vec![
+        Line::from(format!("grep: {}{cursor}", search.pattern))
+            .style(Style::new().add_modifier(Modifier::BOLD)),
+        Line::from(search.message.clone().unwrap_or_default())
+            .style(Style::new().fg(Color::Yellow)),
+    ];
+    let mut selected_line = 0;
+    let mut index = 0;
+    for result in &search.results {
+        lines.push(
+            Line::from(format!(
+                "{}/{} ({})",
+                result.pod,
+                result.container,
+                result.matches.len()
+            ))
+            .style(Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+        );
+        for line in &result.matches {
+            let rec = &result.logs[*line];
+            let text = Line::from(format!("  {} {}", rec.datetime, rec.message));
+            if !search.editing && index == search.selected {
+                selected_line = lines.len();
+                lines.push(text.style(Style::new().add_modifier(Modifier::REVERSED)));
+            } else {
+                lines.push(text);
+            }
+            index += 1;
+        }
+    }
+    let height = usize::from(area.height.saturating_sub(2));
+    let scroll = u16::try_from(selected_line.saturating_sub(height / 2)).unwrap_or(u16::MAX);
+    let help = if search.editing {
+        " enter to search, esc to close "
+    } else {
+        " j/k to move, enter to open, / for a new pattern, esc to close "
+    };
+    let paragraph = Paragraph::new(lines)
+        .scroll((scroll, 0))
+        .style(Style::new().fg(Color::White).bg(Color::Black))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" logs of {} ", search.replicaset))
+                .title_bottom(help),
+        );
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+/// recent mutating calls from the audit log, newest first
+fn render_audit(f: &mut Frame, offset: usize) {
+    let area = Layout::vertical([Constraint::Percentage(70)])
+        .flex(layout::Flex::Center)
+        .split(f.area())[0];
+    let area = Layout::horizontal([Constraint::Percentage(90)])
+        .flex(layout::Flex::Center)
+        .split(area)[0];
+
+    let title = audit::source().map_or_else(
+        || " audit ".to_string(),
+        |source| format!(" audit: {source} "),
+    );
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .title_bottom(" j/k to scroll, esc to close ");
+    f.render_widget(Clear, area);
+    if audit::source().is_none() {
+        let paragraph =
+            Paragraph::new("start navipod with --audit-log <file> to see who changed what")
+                .style(Style::new().fg(Color::Yellow).bg(Color::Black))
+                .block(block);
+        f.render_widget(paragraph, area);
+        return;
+    }
+
+    let header = ["TIME", "USER", "VERB", "OBJECT", "CODE", "POD CHANGES"]
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+        .style(Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD));
+    let rows = audit::recent()
+        .into_iter()
+        .skip(offset)
+        .map(|(entry, changes)| {
+            let object = format!(
+                "{}/{}",
+                entry.resource,
+                entry.name.as_deref().unwrap_or("*")
+            );
+            let code = entry.code.map(|code| code.to_string()).unwrap_or_default();
+            let changes = if changes > 0 {
+                format!("{} {changes}", symbols().arrow)
+            } else {
+                String::new()
+            };
+            Row::new([
+                timezone().format(&entry.time, EVENT_TIME_FORMAT),
+                entry.user,
+                entry.verb,
+                object,
+                code,
+                changes,
+            ])
+        });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(14),
+            Constraint::Percentage(25),
+            Constraint::Length(16),
+            Constraint::Fill(1),
+            Constraint::Length(5),
+            Constraint::Length(12),
+        ],
+    )
+    .header(header)
+    .style(Style::new().fg(Color::White).bg(Color::Black))
+    .block(block);
+    f.render_widget(table, area);
+}
@@ -0,0 +1,164 @@
+//!Sorting a table by any of its columns, built-in or configured: 'o' moves
+//!to the next column, 'O' flips the order.
+//!
+use crate::config::CustomColumn;
+use crate::tui::theme::symbols;
+use ratatui::layout::Constraint;
+use std::cmp::Ordering;
+
+/// the cells of a row, in header order
+pub trait Columns {
+    fn columns(&self) -> Vec<&str>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SortBy {
+    pub column: usize,
+    pub descending: bool,
+}
+
+impl SortBy {
+    /// the next of `columns` columns, unsorted after the last
+    #[must_use]
+    pub const fn next(sort: Option<Self>, columns: usize) -> Option<Self> {
+        match sort {
+            None if columns > 0 => Some(Self {
+                column: 0,
+                descending: false,
+            }),
+            Some(sort) if sort.column + 1 < columns => Some(Self {
+                column: sort.column + 1,
+                descending: sort.descending,
+            }),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn flip(sort: Option<Self>) -> Option<Self> {
+        match sort {
+            Some(sort) => Some(Self {
+                column: sort.column,
+                descending: !sort.descending,
+            }),
+            None => None,
+        }
+    }
+
+    /// appended to the header of the sorted column
+    #[must_use]
+    pub fn marker(sort: Option<Self>, column: usize) -> String {
+        match sort {
+            Some(sort) if sort.column == column && sort.descending => {
+                format!(" {}", symbols().descending)
+            }
+            Some(sort) if sort.column == column => format!(" {}", symbols().ascending),
+            _ => String::new(),
+        }
+    }
+}
+
+/// the number a cell starts with, ie: 12 for "12/15", ages like "3d" in
+/// seconds so they order across units
+fn leading_number(cell: &str) -> Option<f64> {
+    let end = cell
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(cell.len());
+    let number: f64 = cell[..end].parse().ok()?;
+    let scale = match &cell[end..] {
+        "s" => 1.0,
+        "m" => 60.0,
+        "h" => 3_600.0,
+        "d" => 86_400.0,
+        _ => 1.0,
+    };
+    Some(number * scale)
+}
+
+/// numbers by value, everything else as text
+#[must_use]
+pub fn compare_cells(a: &str, b: &str) -> Ordering {
+    match (leading_number(a), leading_number(b)) {
+        (Some(x), Some(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal).then(a.cmp(b)),
+        _ => a.cmp(b),
+    }
+}
+
+pub fn sort_rows<T: Columns>(items: &mut [T], sort: Option<SortBy>) {
+    let Some(sort) = sort else {
+        return;
+    };
+    items.sort_by(|a, b| {
+        let a = a.columns();
+        let b = b.columns();
+        let ordering = compare_cells(
+            a.get(sort.column).copied().unwrap_or_default(),
+            b.get(sort.column).copied().unwrap_or_default(),
+        );
+        if sort.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+}
+
+/// the built-in headers then the configured ones, the sorted one marked
+#[must_use]
+pub fn headers(built_in: &[&str], custom: &[CustomColumn], sort: Option<SortBy>) -> Vec<String> {
+    built_in
+        .iter()
+        .copied()
+        .chain(custom.iter().map(|column| column.header.as_str()))
+        .enumerate()
+        .map(|(i, header)| format!("{header}{}", SortBy::marker(sort, i)))
+        .collect()
+}
+
+/// widths of the configured columns, which follow the `built_in` ones
+#[must_use]
+pub fn custom_constraints<T: Columns>(
+    items: &[&T],
+    built_in: usize,
+    custom: &[CustomColumn],
+) -> Vec<Constraint> {
+    custom
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let widest = items
+                .iter()
+                .filter_map(|item| item.columns().get(built_in + i).map(|cell| cell.len()))
+                .chain([column.header.len() + 2])
+                .max()
+                .unwrap_or_default();
+            Constraint::Min(u16::try_from(widest + 1).unwrap_or(u16::MAX))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    impl Columns for (&str, &str) {
+        fn columns(&self) -> Vec<&str> {
+            vec![self.0, self.1]
+        }
+    }
+
+    #[test]
+    fn test_sort_rows() {
+        let mut rows = vec![("web", "10d"), ("api", "9d"), ("db", "2h"), ("mq", "30m")];
+        let by_age = SortBy::next(SortBy::next(None, 2), 2);
+        sort_rows(&mut rows, by_age);
+        assert_eq!(
+            rows,
+            vec![("mq", "30m"), ("db", "2h"), ("api", "9d"), ("web", "10d")]
+        );
+        sort_rows(&mut rows, SortBy::flip(by_age));
+        assert_eq!(rows[0], ("web", "10d"));
+        assert_eq!(SortBy::next(by_age, 2), None);
+        assert_eq!(SortBy::marker(by_age, 1), " ▲");
+    }
+}
@@ -0,0 +1,131 @@
+use crate::error::Error;
+use crate::error::Result as NvResult;
+use crate::k8s::cache::fetcher::fetch;
+use crate::k8s::cache::{cache_manager, CachedData, DataRequest};
+use crate::tui::data::StatefulSetRow;
+use crate::tui::pod_app;
+use crate::tui::stream::Message;
+use crate::tui::table_app::{self, TableApp};
+use crate::tui::table_ui::TuiTableState;
+use crate::tui::ui_loop::{AppBehavior, Apps, Transition};
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use futures::Stream;
+use ratatui::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::debug;
+
+const POLL_MS: u64 = 5000;
+
+/// The namespace's StatefulSets, Enter lists the replicasets of one.
+#[derive(Clone, Debug)]
+pub struct App {
+    pub(crate) table: TableApp<StatefulSetRow>,
+}
+
+impl AppBehavior for App {
+    const NAME: &'static str = "statefulsets";
+
+    async fn handle_event(&mut self, event: &Message) -> NvResult<Transition> {
+        match event {
+            Message::Key(Event::Key(key))
+                if key.kind == KeyEventKind::Press
+                    && key.code == KeyCode::Enter
+                    && !self.table.show_filter_edit =>
+            {
+                if let Some(selection) = self.table.get_selected_item() {
+                    if let Some(selectors) = selection.selectors.clone() {
+                        cache_manager::record_navigation(&selection.name);
+                        let data_vec = cache_manager::cached_pods(&selectors).unwrap_or_default();
+                        debug!("changing app from statefulset to pod...");
+                        return Ok(Transition::Push(Box::new(Apps::new(
+                            pod_app::app::App::new(selectors, data_vec),
+                        ))));
+                    }
+                }
+            }
+            Message::Key(Event::Key(key))
+                if key.kind == KeyEventKind::Press
+                    && key.code == KeyCode::Char('R')
+                    && !self.table.show_filter_edit =>
+            {
+                return Ok(Transition::Refresh);
+            }
+            Message::Key(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                return Ok(self.table.handle_key(key));
+            }
+            Message::StatefulSet(data_vec) => {
+                debug!("updating statefulset app data...");
+                self.table.set_items(data_vec);
+            }
+            _ => {}
+        }
+        Ok(Transition::Stay)
+    }
+
+    fn draw_ui<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<(), std::io::Error> {
+        terminal.draw(|f| table_app::ui(f, &mut self.table))?;
+        Ok(())
+    }
+
+    fn source(&self) -> Option<DataRequest> {
+        Some(DataRequest::StatefulSets)
+    }
+
+    fn stream(&self, should_stop: Arc<AtomicBool>) -> impl Stream<Item = Message> {
+        let (tx, rx) = mpsc::channel(100);
+
+        let mut sent = Some(self.table.items.clone());
+
+        tokio::spawn(async move {
+            while !should_stop.load(Ordering::Relaxed) {
+                match fetch(&DataRequest::StatefulSets).await {
+                    Ok(CachedData::StatefulSets(d)) => {
+                        cache_manager::get_cache().put(
+                            DataRequest::StatefulSets,
+                            CachedData::StatefulSets(d.clone()),
+                        );
+                        if sent.as_ref() != Some(&d) {
+                            sent = Some(d.clone());
+                            if tx.send(Message::StatefulSet(d)).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Ok(_) => break,
+                    Err(e @ Error::Timeout { .. }) => {
+                        // already surfaced in the header, keep polling
+                        cache_manager::get_cache()
+                            .mark_failed(&DataRequest::StatefulSets, e.to_string());
+                    }
+                    Err(e) => {
+                        cache_manager::get_cache()
+                            .mark_failed(&DataRequest::StatefulSets, e.to_string());
+                        break;
+                    }
+                }
+                sleep(Duration::from_millis(POLL_MS)).await;
+            }
+        });
+
+        ReceiverStream::new(rx)
+    }
+}
+
+impl App {
+    pub fn new() -> Self {
+        Self {
+            table: TableApp::new(cache_manager::cached_statefulsets().unwrap_or_default()),
+        }
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,418 @@
+//!The bottom line of every view: context and namespace as plain text, how
+//!old the cached replicasets are, how far a large list has been paged,
+//!whether api calls are being retried or timing out and whether the pod
+//!watch is connected.  Clicking the
+//!context or namespace copies it, clicking the rest opens the diagnostics
+//!view.
+//!
+//!The top right corner of the header row spins yellow while the cache is
+//!being filled in the background and red while a view waits on a fetch.
+//!Left of it a badge says how old the cached rows of the view are, orange
+//!past the cache ttl and red when the last refetch failed.  After a forced
+//!refresh the status line says how long it took for a while.
+//!
+//!Names too long for the pod and replicaset tables are cut in the middle
+//!there, the status line ends with the full name of the selected one and
+//!clicking it copies it.
+//!
+//!A metrics scan started with `S` and an export started with `x` show how
+//!far they got, and how they ended for a while after.
+//!
+use crate::k8s::cache::cache_manager::{get_cache, has_blocking_activity, has_network_activity};
+use crate::k8s::cache::{DataRequest, Freshness};
+use crate::k8s::paging::{self, Loading};
+use crate::k8s::retry::retries;
+use crate::k8s::scan::background::{self, ExportStatus, ScanStatus};
+use crate::k8s::timeout::recent_timeout;
+use crate::k8s::watch::{self, WatchHealth};
+use crate::tui::theme::symbols;
+use base64::Engine;
+use crossterm::event::{Event, MouseButton, MouseEventKind};
+use ratatui::{prelude::*, widgets::Paragraph};
+use std::io::{self, Write};
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// frames drawn while busy, turns the spinner
+static FRAMES: AtomicUsize = AtomicUsize::new(0);
+
+static SCOPE: OnceLock<(String, String)> = OnceLock::new();
+
+/// where the last frame put each segment
+struct Placed {
+    row: u16,
+    columns: Vec<(Range<u16>, Segment)>,
+}
+
+static PLACED: Mutex<Option<Placed>> = Mutex::new(None);
+
+/// how long a forced refresh stays on the status line
+const REFRESHED_FOR: Duration = Duration::from_secs(10);
+
+/// when the last forced refresh finished and how long it took
+static REFRESHED: Mutex<Option<(Instant, Duration)>> = Mutex::new(None);
+
+/// the cache entry of the view being drawn
+static SOURCE: Mutex<Option<DataRequest>> = Mutex::new(None);
+
+/// the full name of the selected row when the view cut it short
+static SELECTED: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Segment {
+    Context(String),
+    Namespace(String),
+    Cache {
+        age: Option<Duration>,
+        fresh: bool,
+    },
+    Loading(Loading),
+    Api {
+        retries: u64,
+        timeout: Option<String>,
+    },
+    Watch(WatchHealth),
+    Refreshed(Duration),
+    Scan(ScanStatus),
+    Export(ExportStatus),
+    Selected(String),
+}
+
+impl Segment {
+    fn text(&self) -> String {
+        match self {
+            Self::Context(context) => context.clone(),
+            Self::Namespace(namespace) => namespace.clone(),
+            Self::Cache { age: None, .. } => "cache empty".to_string(),
+            Self::Cache { age: Some(age), .. } => format!("cache {}s", age.as_secs()),
+            Self::Loading(Loading {
+                loaded,
+                total: Some(total),
+            }) => format!("loading {}/{}", thousands(*loaded), thousands(*total)),
+            Self::Loading(Loading { loaded, .. }) => format!("loading {}", thousands(*loaded)),
+            Self::Api {
+                timeout: Some(operation),
+                ..
+            } => format!("api timed out: {operation}"),
+            Self::Api { retries: 0, .. } => "api ok".to_string(),
+            Self::Api { retries, .. } => format!("api ok, {retries} retried"),
+            Self::Watch(health) if health.running == 0 => "watch off".to_string(),
+            Self::Watch(health) if health.streaming < health.running => {
+                "watch reconnecting".to_string()
+            }
+            Self::Watch(_) => "watch live".to_string(),
+            Self::Refreshed(took) => format!("refreshed in {}ms", took.as_millis()),
+            Self::Scan(ScanStatus::Running { total: 0, .. }) => "scan starting".to_string(),
+            Self::Scan(ScanStatus::Running { done, total }) => format!("scan {done}/{total}"),
+            Self::Scan(ScanStatus::Done { pods, .. }) => format!("scan done, {pods} pods"),
+            Self::Scan(ScanStatus::Failed { error, .. }) => format!("scan failed: {error}"),
+            Self::Export(ExportStatus::Running { written }) => {
+                format!("export {} triples", thousands(*written))
+            }
+            Self::Export(ExportStatus::Done { written, path, .. }) => {
+                format!("exported {} triples to {path}", thousands(*written))
+            }
+            Self::Export(ExportStatus::Failed { error, .. }) => format!("export failed: {error}"),
+            Self::Selected(name) => name.clone(),
+        }
+    }
+
+    fn style(&self) -> Style {
+        let style = Style::new().fg(Color::Gray).bg(Color::Black);
+        match self {
+            Self::Context(_) | Self::Namespace(_) | Self::Selected(_) => style.fg(Color::White),
+            Self::Cache {
+                age: Some(_),
+                fresh: false,
+            }
+            | Self::Api {
+                timeout: Some(_), ..
+            } => style.fg(Color::Yellow),
+            Self::Watch(health) if health.streaming < health.running => style.fg(Color::Yellow),
+            Self::Refreshed(_)
+            | Self::Scan(ScanStatus::Done { .. })
+            | Self::Export(ExportStatus::Done { .. }) => style.fg(Color::Green),
+            Self::Scan(ScanStatus::Running { .. }) | Self::Export(ExportStatus::Running { .. }) => {
+                style.fg(Color::Yellow)
+            }
+            Self::Scan(ScanStatus::Failed { .. }) | Self::Export(ExportStatus::Failed { .. }) => {
+                style.fg(Color::Red)
+            }
+            _ => style,
+        }
+    }
+}
+
+/// 9000 as 9,000
+fn thousands(n: u64) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// what the views show for context and namespace, set once the client is
+/// built
+pub fn set_scope(context: String, namespace: String) {
+    _ = SCOPE.set((context, namespace));
+}
+
+fn segments() -> Vec<Segment> {
+    let (context, namespace) = SCOPE.get().cloned().unwrap_or_else(|| {
+        (
+            symbols().ellipsis.to_string(),
+            symbols().ellipsis.to_string(),
+        )
+    });
+    let cache = get_cache();
+    let age = cache.age(&DataRequest::ReplicaSets);
+    let mut segments = vec![
+        Segment::Context(context),
+        Segment::Namespace(namespace),
+        Segment::Cache {
+            age,
+            fresh: age.is_some_and(|age| age <= cache.ttl()),
+        },
+    ];
+    segments.extend(paging::loading().map(Segment::Loading));
+    segments.push(Segment::Api {
+        retries: retries(),
+        timeout: recent_timeout(),
+    });
+    segments.push(Segment::Watch(watch::health()));
+    let refreshed = REFRESHED.lock().ok().and_then(|refreshed| *refreshed);
+    if let Some((_, took)) = refreshed.filter(|(at, _)| at.elapsed() < REFRESHED_FOR) {
+        segments.push(Segment::Refreshed(took));
+    }
+    segments.extend(background::status().map(Segment::Scan));
+    segments.extend(background::export_status().map(Segment::Export));
+    let selected = SELECTED.lock().ok().and_then(|selected| selected.clone());
+    segments.extend(selected.map(Segment::Selected));
+    segments
+}
+
+/// show how long a forced refresh took
+pub fn refreshed(took: Duration) {
+    if let Ok(mut refreshed) = REFRESHED.lock() {
+        *refreshed = Some((Instant::now(), took));
+    }
+}
+
+/// the full name of the selected row for views that cut names short,
+/// `None` when it fits
+pub fn set_selected(name: Option<String>) {
+    if let Ok(mut selected) = SELECTED.lock() {
+        *selected = name;
+    }
+}
+
+/// where the view being drawn gets its rows, `None` for views without
+pub fn set_source(source: Option<DataRequest>) {
+    if let Ok(mut current) = SOURCE.lock() {
+        *current = source;
+    }
+}
+
+/// `cached 45s ago`, orange when stale and red when the refetch failed
+fn badge(freshness: &Freshness) -> (String, Color) {
+    let secs = freshness.age.as_secs();
+    let age = if secs < 120 {
+        format!("{secs}s")
+    } else {
+        format!("{}m", secs / 60)
+    };
+    let text = format!("cached {age} ago");
+    if freshness.error.is_some() {
+        (format!("{text}, refresh failed"), Color::Red)
+    } else if freshness.stale {
+        (text, Color::Rgb(255, 140, 0))
+    } else {
+        (text, Color::DarkGray)
+    }
+}
+
+fn render_badge(f: &mut Frame) {
+    let source = SOURCE.lock().ok().and_then(|source| source.clone());
+    let Some(freshness) = source.and_then(|source| get_cache().freshness(&source)) else {
+        return;
+    };
+    let (text, color) = badge(&freshness);
+    let area = f.area();
+    let width = u16::try_from(text.chars().count()).unwrap_or(u16::MAX);
+    // the spinner and a space to its right
+    if area.height == 0 || area.width < width + 4 {
+        return;
+    }
+    let at = Rect::new(area.right() - 3 - width, area.y, width, 1);
+    f.render_widget(Paragraph::new(text).style(Style::new().fg(color)), at);
+}
+
+fn activity_color() -> Option<Color> {
+    if has_blocking_activity() {
+        Some(Color::Red)
+    } else if has_network_activity() {
+        Some(Color::Yellow)
+    } else {
+        None
+    }
+}
+
+/// Draw the staleness badge and the spinner over the view, before any
+/// error card.
+pub fn render_activity(f: &mut Frame) {
+    render_badge(f);
+    let Some(color) = activity_color() else {
+        return;
+    };
+    let area = f.area();
+    if area.width < 2 || area.height == 0 {
+        return;
+    }
+    let frame = FRAMES.fetch_add(1, Ordering::Relaxed) % symbols().spinner.len();
+    let corner = Rect::new(area.right() - 2, area.y, 1, 1);
+    f.render_widget(
+        Paragraph::new(symbols().spinner[frame])
+            .style(Style::new().fg(color).add_modifier(Modifier::BOLD)),
+        corner,
+    );
+}
+
+/// Draw the status line on the last row, the rest is for the view.
+pub fn render(f: &mut Frame) -> Rect {
+    let [body, line] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(f.area());
+    let mut spans = vec![Span::raw(" ")];
+    let mut columns = vec![];
+    let mut x = line.x + 1;
+    for (i, segment) in segments().into_iter().enumerate() {
+        if i > 0 {
+            let separator = format!(" {} ", symbols().vertical);
+            x = x.saturating_add(u16::try_from(separator.chars().count()).unwrap_or(0));
+            spans.push(Span::styled(separator, Style::new().fg(Color::DarkGray)));
+        }
+        let text = segment.text();
+        let width = u16::try_from(text.chars().count()).unwrap_or(u16::MAX);
+        spans.push(Span::styled(text, segment.style()));
+        columns.push((x..x.saturating_add(width), segment));
+        x = x.saturating_add(width);
+    }
+    f.render_widget(
+        Paragraph::new(Line::from(spans)).style(Style::new().bg(Color::Black)),
+        line,
+    );
+    if let Ok(mut placed) = PLACED.lock() {
+        *placed = Some(Placed {
+            row: line.y,
+            columns,
+        });
+    }
+    body
+}
+
+/// OSC 52, understood by most terminals and passed on by tmux and ssh, so
+/// copying works where the mouse is captured
+fn copy(text: &str) -> io::Result<()> {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{encoded}\x07")?;
+    stdout.flush()
+}
+
+/// A click on the status line, `true` when it asks for the diagnostics
+/// view.  Context and namespace are copied instead.
+#[must_use]
+pub fn clicked(event: &Event) -> bool {
+    let Event::Mouse(mouse) = event else {
+        return false;
+    };
+    if mouse.kind != MouseEventKind::Down(MouseButton::Left) {
+        return false;
+    }
+    let Some(segment) = PLACED.lock().ok().and_then(|placed| {
+        let placed = placed.as_ref().filter(|placed| placed.row == mouse.row)?;
+        placed
+            .columns
+            .iter()
+            .find(|(columns, _)| columns.contains(&mouse.column))
+            .map(|(_, segment)| segment.clone())
+    }) else {
+        return false;
+    };
+    match segment {
+        Segment::Context(text) | Segment::Namespace(text) | Segment::Selected(text) => {
+            _ = copy(&text);
+            false
+        }
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment_text() {
+        let cache = |age| Segment::Cache { age, fresh: true };
+        assert_eq!(cache(None).text(), "cache empty");
+        assert_eq!(cache(Some(Duration::from_secs(42))).text(), "cache 42s");
+        assert_eq!(
+            Segment::Api {
+                retries: 2,
+                timeout: None
+            }
+            .text(),
+            "api ok, 2 retried"
+        );
+        assert_eq!(
+            Segment::Api {
+                retries: 2,
+                timeout: Some("list pods".to_string())
+            }
+            .text(),
+            "api timed out: list pods"
+        );
+        let loading = |loaded, total| Segment::Loading(Loading { loaded, total });
+        assert_eq!(loading(2400, Some(9000)).text(), "loading 2,400/9,000");
+        assert_eq!(loading(500, None).text(), "loading 500");
+        assert_eq!(thousands(1_234_567), "1,234,567");
+        let reconnecting = WatchHealth {
+            running: 1,
+            streaming: 0,
+        };
+        assert_eq!(Segment::Watch(reconnecting).text(), "watch reconnecting");
+        assert_eq!(Segment::Watch(WatchHealth::default()).text(), "watch off");
+        assert_eq!(
+            Segment::Refreshed(Duration::from_millis(840)).text(),
+            "refreshed in 840ms"
+        );
+    }
+
+    #[test]
+    fn test_badge() {
+        let freshness = |secs, stale, error: Option<&str>| Freshness {
+            age: Duration::from_secs(secs),
+            stale,
+            error: error.map(str::to_string),
+        };
+        assert_eq!(
+            badge(&freshness(45, false, None)),
+            ("cached 45s ago".to_string(), Color::DarkGray)
+        );
+        assert_eq!(
+            badge(&freshness(300, true, None)),
+            ("cached 5m ago".to_string(), Color::Rgb(255, 140, 0))
+        );
+        assert_eq!(
+            badge(&freshness(300, true, Some("timed out"))),
+            ("cached 5m ago, refresh failed".to_string(), Color::Red)
+        );
+    }
+}
@@ -1,3 +1,6 @@
+use crate::k8s::cluster::ClusterListing;
+use crate::k8s::journal::Change;
+use crate::k8s::noisy::NoisyObject;
 use crate::tui::data;
 use crossterm::event::Event;
 use crossterm::event::{poll, read};
@@ -13,7 +16,8 @@ pub enum Message {
     Key(Event),
     Pod(Vec<data::RsPod>),
     Rs(Vec<data::Rs>),
-    #[allow(dead_code)]
+    Deployment(Vec<data::DeploymentRow>),
+    StatefulSet(Vec<data::StatefulSetRow>),
     Ingress(Vec<data::Ingress>),
     #[allow(dead_code)]
     Container(Vec<data::Container>),
@@ -23,6 +27,41 @@ pub enum Message {
     Log(Vec<data::LogRec>),
     #[allow(dead_code)]
     Event(Vec<data::ResourceEvent>),
+    Diag(Vec<data::Diagnostic>),
+    Capacity(Vec<data::NodeCapacity>),
+    Noisy(Vec<NoisyObject>),
+    Gateway(Vec<data::GatewayRow>),
+    Cluster(ClusterListing),
+    Changes(Vec<Change>),
+    Plugin(Vec<data::PluginRow>),
+    /// the pods of a replicaset landed in the cache
+    Prefetched,
+    /// what is still changing the cluster, for the quit dialog
+    Operations(Vec<String>),
+    /// the probes of a pod ran in the background, `error` if they could not
+    Probed {
+        pod: String,
+        error: Option<crate::error::Error>,
+    },
+    /// new lines for one pane of the tiled log view
+    TileLog {
+        pane: usize,
+        logs: Vec<data::LogRec>,
+    },
+}
+
+/// how long a key poll waits before checking whether to stop
+const KEY_POLL: Duration = Duration::from_millis(100);
+/// the same while unfocused, a focus gained event still ends it at once
+const IDLE_KEY_POLL: Duration = Duration::from_secs(1);
+
+/// cleared while another window has the terminal's focus
+static FOCUSED: AtomicBool = AtomicBool::new(true);
+
+/// `false` between a focus lost and a focus gained event, terminals that do
+/// not report focus always count as focused
+pub fn focused() -> bool {
+    FOCUSED.load(Ordering::Relaxed)
 }
 
 pub fn async_key_events(should_stop: Arc<AtomicBool>) -> impl Stream<Item = Message> {
@@ -30,9 +69,15 @@ pub fn async_key_events(should_stop: Arc<AtomicBool>) -> impl Stream<Item = Mess
 
     tokio::spawn(async move {
         while !should_stop.load(Ordering::Relaxed) {
-            match poll(Duration::from_millis(100)) {
+            let timeout = if focused() { KEY_POLL } else { IDLE_KEY_POLL };
+            match poll(timeout) {
                 Ok(true) => {
                     if let Ok(event) = read() {
+                        match event {
+                            Event::FocusLost => FOCUSED.store(false, Ordering::Relaxed),
+                            Event::FocusGained => FOCUSED.store(true, Ordering::Relaxed),
+                            _ => {}
+                        }
                         let sevent = Message::Key(event);
                         if tx.send(sevent).await.is_err() {
                             break;
@@ -0,0 +1,465 @@
+//!The views that are a table and nothing else, ie: events, certs, ingresses
+//!and diagnostics, share their selection, filtering, scrolling and drawing
+//!here.  A row type says what its columns are and how it draws, the view
+//!keeps only fetching rows and the keys that lead elsewhere.
+//!
+use crate::tui::data::Filterable;
+use crate::tui::error_card;
+use crate::tui::filter_history::FilterRecall;
+use crate::tui::filter_pattern;
+use crate::tui::sort::{sort_rows, Columns, SortBy};
+use crate::tui::status_line;
+use crate::tui::style::{TableColors, ITEM_HEIGHT, PALETTES};
+use crate::tui::table_ui::{render_detail_section, TuiTableState};
+use crate::tui::theme::symbols;
+use crate::tui::ui_loop::Transition;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    prelude::*,
+    widgets::{
+        Block, Borders, Cell, Clear, HighlightSpacing, Paragraph, Row, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Table, TableState,
+    },
+};
+use std::borrow::Cow;
+use unicode_width::UnicodeWidthStr;
+
+/// the rows of a detail pane: name, value and an optional age
+pub type Details = Vec<(String, String, Option<String>)>;
+
+/// what a resource's table looks like
+pub struct TableConfig {
+    /// column titles, views whose columns are only known at runtime set
+    /// theirs with `TableApp::with_headers`
+    pub headers: &'static [&'static str],
+    /// the column whose title shows the filter, `None` when '/' does not
+    /// open the filter editor
+    pub filter_column: Option<usize>,
+    /// palette the view opens with
+    pub color_index: usize,
+}
+
+/// A row of a `TableApp`.
+pub trait TableRow: Filterable + Clone + 'static {
+    const CONFIG: TableConfig;
+
+    /// the text of each column, a cell may have more than one line
+    fn cells(&self) -> Vec<Cow<'_, str>>;
+
+    /// every cell with a blank line above and below
+    fn row(&self) -> Row<'static> {
+        self.cells()
+            .into_iter()
+            .map(|content| Cell::from(Text::from(format!("\n{content}\n"))))
+            .collect::<Row>()
+            .height(3)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TableApp<T> {
+    pub(crate) state: TableState,
+    pub(crate) items: Vec<T>,
+    pub(crate) headers: Vec<String>,
+    /// the widest line of each column
+    widths: Vec<u16>,
+    pub(crate) scroll_state: ScrollbarState,
+    pub(crate) colors: TableColors,
+    color_index: usize,
+    pub(crate) filter: String,
+    pub(crate) show_filter_edit: bool,
+    pub(crate) edit_filter_cursor_position: usize,
+    /// shown after the first column's title, ie: rows left out
+    pub(crate) note: String,
+    /// shown under the table, ie: where the rows come from
+    pub(crate) footer: String,
+    /// only views that call `handle_sort_key` are ever sorted
+    pub(crate) sort: Option<SortBy>,
+    pub(crate) recall: FilterRecall,
+}
+
+fn widths<T: TableRow>(items: &[T], columns: usize) -> Vec<u16> {
+    let mut widths = vec![0; columns];
+    for item in items {
+        for (width, cell) in widths.iter_mut().zip(item.cells()) {
+            let widest = cell.lines().map(UnicodeWidthStr::width).max().unwrap_or(0);
+            *width = (*width).max(u16::try_from(widest).unwrap_or(u16::MAX));
+        }
+    }
+    widths
+}
+
+impl<T: TableRow> TableApp<T> {
+    pub fn new(items: Vec<T>) -> Self {
+        Self {
+            state: TableState::default().with_selected(0),
+            headers: T::CONFIG.headers.iter().map(ToString::to_string).collect(),
+            widths: widths(&items, T::CONFIG.headers.len()),
+            scroll_state: ScrollbarState::new(items.len().saturating_sub(1) * ITEM_HEIGHT),
+            colors: TableColors::new(&PALETTES[0]),
+            color_index: T::CONFIG.color_index,
+            items,
+            filter: String::new(),
+            show_filter_edit: false,
+            edit_filter_cursor_position: 0,
+            note: String::new(),
+            footer: String::new(),
+            sort: None,
+            // the history is kept under the row type, ie: `ResourceEvent`
+            recall: FilterRecall::new(
+                std::any::type_name::<T>()
+                    .rsplit("::")
+                    .next()
+                    .unwrap_or_default(),
+            ),
+        }
+    }
+
+    /// columns in place of `T::CONFIG.headers`
+    #[must_use]
+    pub fn with_headers(mut self, headers: Vec<String>) -> Self {
+        self.widths = widths(&self.items, headers.len());
+        self.headers = headers;
+        self
+    }
+
+    /// take the rows of a new fetch, keeping the selection
+    pub fn set_items(&mut self, items: &[T]) {
+        self.widths = widths(items, self.headers.len());
+        self.scroll_state = ScrollbarState::new(items.len().saturating_sub(1) * ITEM_HEIGHT);
+        self.items = items.to_vec();
+    }
+
+    /// The keys every table answers alike: moving, paging, colors, the
+    /// filter editor, and 'q' or Esc to leave.
+    pub fn handle_key(&mut self, key: &KeyEvent) -> Transition {
+        use KeyCode::{Backspace, Char, Down, Enter, Esc, Left, Right, Up};
+        if self.show_filter_edit {
+            if filter_pattern::toggle(key)
+                || self
+                    .recall
+                    .consume(key, &mut self.filter, &mut self.edit_filter_cursor_position)
+            {
+                return Transition::Stay;
+            }
+            match key.code {
+                Char(to_insert) => self.enter_char(to_insert),
+                Backspace => self.delete_char(),
+                Left => self.move_cursor_left(),
+                Right => self.move_cursor_right(),
+                Esc | Enter => self.set_show_filter_edit(false),
+                _ => {}
+            }
+            return Transition::Stay;
+        }
+        if let Some(transition) = self.handle_mark(key) {
+            return transition;
+        }
+        if self.handle_motion(key) {
+            return Transition::Stay;
+        }
+        match key.code {
+            Char('q') | Esc => return Transition::Pop,
+            Char('j') | Down => self.next(),
+            Char('k') | Up => self.previous(),
+            Char('c' | 'C') => self.next_color(),
+            Char('f' | 'F') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.page_forward();
+            }
+            Char('b' | 'B') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.page_backward();
+            }
+            Char('/') if T::CONFIG.filter_column.is_some() => self.set_show_filter_edit(true),
+            _ => {}
+        }
+        Transition::Stay
+    }
+}
+
+impl<T: TableRow + Columns> TableApp<T> {
+    /// 'o' sorts by the next column and 'O' flips the order, for the views
+    /// whose rows can be sorted.  Returns whether the key was one of them.
+    pub fn handle_sort_key(&mut self, key: &KeyEvent) -> bool {
+        if self.show_filter_edit {
+            return false;
+        }
+        self.sort = match key.code {
+            KeyCode::Char('o') => SortBy::next(self.sort, self.headers.len()),
+            KeyCode::Char('O') => SortBy::flip(self.sort),
+            _ => return false,
+        };
+        sort_rows(&mut self.items, self.sort);
+        true
+    }
+
+    /// `set_items`, in the order the view is sorted by
+    pub fn set_sorted_items(&mut self, items: &[T]) {
+        self.set_items(items);
+        sort_rows(&mut self.items, self.sort);
+    }
+}
+
+impl<T: TableRow> TuiTableState for TableApp<T> {
+    type Item = T;
+
+    fn get_items(&self) -> &[Self::Item] {
+        &self.items
+    }
+
+    fn get_state(&mut self) -> &mut TableState {
+        &mut self.state
+    }
+
+    fn get_scroll_state(&self) -> &ScrollbarState {
+        &self.scroll_state
+    }
+
+    fn set_scroll_state(&mut self, scroll_state: ScrollbarState) {
+        self.scroll_state = scroll_state;
+    }
+
+    fn set_table_colors(&mut self, colors: TableColors) {
+        self.colors = colors;
+    }
+
+    fn get_color_index(&self) -> usize {
+        self.color_index
+    }
+
+    fn set_color_index(&mut self, color_index: usize) {
+        self.color_index = color_index;
+    }
+
+    fn reset_selection_state(&mut self) {
+        self.state = TableState::default().with_selected(0);
+        self.scroll_state = ScrollbarState::new(self.items.len().saturating_sub(1) * ITEM_HEIGHT);
+    }
+
+    fn get_filter(&self) -> String {
+        self.filter.clone()
+    }
+
+    fn set_filter(&mut self, filter: String) {
+        self.filter = filter;
+    }
+
+    fn set_cursor_pos(&mut self, cursor_pos: usize) {
+        self.edit_filter_cursor_position = cursor_pos;
+    }
+
+    fn get_cursor_pos(&self) -> usize {
+        self.edit_filter_cursor_position
+    }
+
+    fn set_show_filter_edit(&mut self, show_filter_edit: bool) {
+        self.show_filter_edit = show_filter_edit;
+    }
+
+    fn get_show_filter_edit(&self) -> bool {
+        self.show_filter_edit
+    }
+}
+
+/// the whole screen of a table view
+pub fn ui<T: TableRow>(f: &mut Frame, app: &mut TableApp<T>) {
+    ui_with_details(f, app, None);
+}
+
+/// the same with a pane of `(title, details)` of the selected row below
+pub fn ui_with_details<T: TableRow>(
+    f: &mut Frame,
+    app: &mut TableApp<T>,
+    details: Option<&(String, Details)>,
+) {
+    let rects = match details {
+        Some(_) => Layout::vertical([Constraint::Min(5), Constraint::Percentage(40)]),
+        None => Layout::vertical([Constraint::Min(5), Constraint::Length(0)]),
+    }
+    .split(status_line::render(f));
+
+    app.set_colors();
+
+    render_table(f, app, rects[0]);
+
+    render_scrollbar(f, app, rects[0]);
+
+    if let Some((title, details)) = details {
+        render_detail_section(
+            f,
+            app.colors.header_fg,
+            app.colors.buffer_bg,
+            rects[1],
+            title,
+            details,
+        );
+    }
+
+    if app.get_show_filter_edit() {
+        render_filter_edit(f, app);
+    }
+
+    status_line::render_activity(f);
+    error_card::render(f);
+}
+
+fn render_filter_edit<T>(f: &mut Frame, app: &TableApp<T>) {
+    let edit_style = Style::default()
+        .fg(app.colors.header_fg)
+        .bg(app.colors.header_bg);
+
+    let input_area = centered_rect(60, 20, f.area());
+
+    let block = Paragraph::new(app.filter.as_str()).style(edit_style).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Edit Filter - try: (java|api)"),
+    );
+
+    f.render_widget(Clear, input_area); //this clears out the background
+    f.render_widget(block, input_area);
+    filter_pattern::render_error(f, input_area, &app.filter);
+    app.recall.render(f, input_area);
+
+    #[allow(clippy::cast_possible_truncation)]
+    let p = Position {
+        x: input_area.x + app.edit_filter_cursor_position as u16 + 1,
+        y: input_area.y + 1,
+    };
+    f.set_cursor_position(p);
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::vertical([
+        Constraint::Percentage((100 - percent_y) / 2),
+        Constraint::Percentage(percent_y),
+        Constraint::Percentage((100 - percent_y) / 2),
+    ])
+    .split(r);
+
+    Layout::horizontal([
+        Constraint::Percentage((100 - percent_x) / 2),
+        Constraint::Percentage(percent_x),
+        Constraint::Percentage((100 - percent_x) / 2),
+    ])
+    .split(popup_layout[1])[1]
+}
+
+fn render_table<T: TableRow>(f: &mut Frame, app: &mut TableApp<T>, area: Rect) {
+    let header_style = Style::default()
+        .fg(app.colors.header_fg)
+        .bg(app.colors.header_bg);
+    let selected_style = Style::default()
+        .add_modifier(Modifier::REVERSED)
+        .fg(app.colors.selected_style_fg);
+
+    let filter = app.get_filter();
+    let header = app
+        .headers
+        .iter()
+        .enumerate()
+        .map(|(i, title)| {
+            let mut title = title.clone();
+            if i == 0 && !app.note.is_empty() {
+                title = format!("{title} ({})", app.note);
+            }
+            if T::CONFIG.filter_column == Some(i) && !filter.is_empty() {
+                title = format!("{title} ({})", filter_pattern::label(&filter));
+            }
+            Cell::from(format!("{title}{}", SortBy::marker(app.sort, i)))
+        })
+        .collect::<Row>()
+        .style(header_style)
+        .height(1);
+    let rows = app
+        .get_filtered_items()
+        .into_iter()
+        .enumerate()
+        .map(|(i, data)| {
+            let color = match i % 2 {
+                0 => app.colors.normal_row_color,
+                _ => app.colors.alt_row_color,
+            };
+            data.row()
+                .style(Style::new().fg(app.colors.row_fg).bg(color))
+        });
+    let last = app.widths.len().saturating_sub(1);
+    // + 1 is for padding.
+    let constraints = app
+        .widths
+        .iter()
+        .enumerate()
+        .map(|(i, width)| Constraint::Min(if i == last { *width } else { width + 1 }));
+    let mut t = Table::new(rows, constraints)
+        .header(header)
+        .row_highlight_style(selected_style)
+        .highlight_symbol(Text::from(vec![
+            "".into(),
+            format!(" {} ", symbols().bar).into(),
+            "".into(),
+        ]))
+        .bg(app.colors.buffer_bg)
+        .highlight_spacing(HighlightSpacing::Always);
+    if !app.footer.is_empty() {
+        t = t.block(Block::default().title_bottom(format!(" {} ", app.footer)));
+    }
+    f.render_stateful_widget(t, area, &mut app.state);
+}
+
+fn render_scrollbar<T>(f: &mut Frame, app: &mut TableApp<T>, area: Rect) {
+    f.render_stateful_widget(
+        Scrollbar::default()
+            .orientation(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(None)
+            .end_symbol(None),
+        area.inner(Margin {
+            vertical: 1,
+            horizontal: 1,
+        }),
+        &mut app.scroll_state,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tui::data::{Cert, PluginRow};
+
+    fn cert(host: &str, expires: &str) -> Cert {
+        Cert {
+            host: host.to_string(),
+            is_valid: "true".to_string(),
+            expires: expires.to_string(),
+            issued_by: "ca".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_widths_take_the_widest_line() {
+        let certs = vec![cert("a.io", "30 days"), cert("api.acme.io", "9 days\n2030")];
+        assert_eq!(widths(&certs, 4), vec![11, 4, 7, 2]);
+
+        let mut app = TableApp::new(certs);
+        app.set_items(&[cert("b.io", "1 day")]);
+        assert_eq!(app.widths, vec![4, 4, 5, 2]);
+    }
+
+    #[test]
+    fn test_runtime_headers_sort_their_rows() {
+        let row = |cells: &[&str]| PluginRow::new(cells.iter().map(ToString::to_string).collect());
+        let mut app =
+            TableApp::new(vec![]).with_headers(vec!["Queue".to_string(), "Depth".to_string()]);
+        app.set_sorted_items(&[row(&["b", "12"]), row(&["a", "3"])]);
+        assert_eq!(app.widths, vec![1, 2]);
+
+        let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+        assert!(app.handle_sort_key(&key('o')));
+        assert!(app.handle_sort_key(&key('o')));
+        assert_eq!(app.items[0].cells, ["a", "3"]);
+        assert!(app.handle_sort_key(&key('O')));
+        assert_eq!(app.items[0].cells, ["b", "12"]);
+        assert!(!app.handle_sort_key(&key('q')));
+
+        app.set_sorted_items(&[row(&["c", "1"]), row(&["d", "40"])]);
+        assert_eq!(app.items[0].cells, ["d", "40"]);
+    }
+}
@@ -0,0 +1,172 @@
+//!Incremental search in the replicaset, pod and container tables.  `/`, or
+//!`?` in the replicaset table where `/` filters, moves the selection to the
+//!first row from the cursor whose name matches while the pattern is typed,
+//!without hiding the others the way a filter does, `n` and `N` go to the
+//!next and previous match and the match is highlighted in the names.
+//!Patterns match like filters, the alt keys of the filter editor toggle
+//!case, words and regex here too.
+//!
+use crate::tui::filter_pattern::FilterPattern;
+use crate::tui::theme::symbols;
+use ratatui::prelude::*;
+
+#[derive(Clone, Debug, Default)]
+pub struct TableSearch {
+    pub pattern: String,
+    /// the pattern is being typed
+    pub editing: bool,
+    /// the row selected when the search started, Esc goes back to it
+    pub origin: usize,
+    /// the key that started it, shown before the pattern
+    pub key: char,
+}
+
+impl TableSearch {
+    /// a pattern was typed, `n` and `N` are the search's
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        !self.pattern.is_empty()
+    }
+
+    /// `None` without a pattern, every name would match an empty one
+    #[must_use]
+    pub fn matcher(&self) -> Option<FilterPattern> {
+        self.is_active()
+            .then(|| FilterPattern::parse(&self.pattern))
+    }
+
+    /// `header` with ` /pattern` after it while there is a search
+    #[must_use]
+    pub fn header(&self, header: String) -> String {
+        if self.editing {
+            format!("{header} {}{}{}", self.key, self.pattern, symbols().cursor)
+        } else if self.is_active() {
+            format!("{header} {}{}", self.key, self.pattern)
+        } else {
+            header
+        }
+    }
+
+    /// a name cell of three lines with the name in the middle
+    #[must_use]
+    pub fn cell(&self, name: String) -> Text<'static> {
+        Text::from(vec![Line::from(""), self.highlight(name), Line::from("")])
+    }
+
+    /// `name` with the match styled as a search hit
+    #[must_use]
+    pub fn highlight(&self, name: String) -> Line<'static> {
+        let found = self.matcher().and_then(|matcher| matcher.find(&name));
+        let Some(found) = found.filter(|found| !found.is_empty()) else {
+            return Line::from(name);
+        };
+        Line::from(vec![
+            Span::raw(name[..found.start].to_string()),
+            Span::styled(
+                name[found.clone()].to_string(),
+                Style::new()
+                    .fg(Color::Black)
+                    .bg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(name[found.end..].to_string()),
+        ])
+    }
+}
+
+/// The row of the next name `pattern` matches going from `from` either way
+/// and wrapping around, `from` itself only when `inclusive`.
+#[must_use]
+pub fn next_match(
+    names: &[&str],
+    pattern: &FilterPattern,
+    from: usize,
+    forward: bool,
+    inclusive: bool,
+) -> Option<usize> {
+    let len = names.len();
+    if len == 0 {
+        return None;
+    }
+    let from = from.min(len - 1);
+    let skip = usize::from(!inclusive);
+    (skip..skip + len)
+        .map(|step| {
+            if forward {
+                (from + step) % len
+            } else {
+                (from + len * 2 - step) % len
+            }
+        })
+        .find(|&row| pattern.is_match(names[row]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_match_wraps() {
+        let names = ["web-1", "db-1", "web-2", "cache"];
+        let web = FilterPattern::parse("web");
+        assert_eq!(next_match(&names, &web, 0, true, true), Some(0));
+        assert_eq!(next_match(&names, &web, 0, true, false), Some(2));
+        assert_eq!(next_match(&names, &web, 2, true, false), Some(0));
+        assert_eq!(next_match(&names, &web, 0, false, false), Some(2));
+        assert_eq!(next_match(&names, &web, 1, false, true), Some(0));
+        let none = FilterPattern::parse("api");
+        assert_eq!(next_match(&names, &none, 1, true, true), None);
+        assert_eq!(next_match(&[], &web, 0, true, true), None);
+        assert_eq!(FilterPattern::parse("b-").find("web-2"), Some(2..4));
+    }
+
+    #[test]
+    fn test_header_shows_the_search_key() {
+        let search = TableSearch {
+            pattern: "web".to_string(),
+            key: '?',
+            ..TableSearch::default()
+        };
+        assert_eq!(search.header("ReplicaSet".to_string()), "ReplicaSet ?web");
+        assert_eq!(TableSearch::default().header("Pod".to_string()), "Pod");
+    }
+
+    #[test]
+    fn test_question_mark_searches_the_replicaset_table() {
+        use crate::tui::data::Rs;
+        use crate::tui::rs_app::app::App;
+        use crate::tui::table_ui::TuiTableState;
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let rs = |name: &str| Rs {
+            name: name.to_string(),
+            owner: String::new(),
+            description: String::new(),
+            age: String::new(),
+            pods: String::new(),
+            deprecations: String::new(),
+            selectors: None,
+            events: vec![],
+            extra: vec![],
+        };
+        let mut app = App::new(vec![rs("web-1"), rs("db-1"), rs("web-2")]);
+        let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+
+        // `/` is the replicaset table's filter, not its search
+        assert!(!app.handle_search(&key('/')));
+        assert!(!app.search.editing);
+
+        assert!(app.handle_search(&key('?')));
+        assert!(app.search.editing);
+        assert_eq!(app.search.key, '?');
+        for c in "db".chars() {
+            assert!(app.handle_search(&key(c)));
+        }
+        assert_eq!(app.state.selected(), Some(1));
+        assert!(app.handle_search(&KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)));
+        assert_eq!(
+            app.search.header("ReplicaSet".to_string()),
+            "ReplicaSet ?db"
+        );
+    }
+}
@@ -1,10 +1,24 @@
+use crate::k8s::timeout::recent_timeout;
 use crate::tui::data::Filterable;
+use crate::tui::filter_pattern::{self, FilterPattern};
+use crate::tui::marks::{self, Scope};
+use crate::tui::motions::{self, page_rows, Motion, Step};
 use crate::tui::style::{TableColors, ITEM_HEIGHT, PALETTES};
+use crate::tui::table_search::{next_match, TableSearch};
+use crate::tui::theme::symbols;
+use crate::tui::ui_loop::Transition;
+use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::widgets::{Block, Borders, ScrollbarState, TableState};
 use ratatui::{prelude::*, widgets::Paragraph};
-use regex::Regex;
 use std::rc::Rc;
-use tracing::debug;
+
+/// mark a table header while api calls are timing out
+#[must_use]
+pub fn with_timeout_notice(header: String) -> String {
+    recent_timeout().map_or(header.clone(), |operation| {
+        format!("{header} {} {operation} timed out", symbols().timer)
+    })
+}
 
 pub fn draw_timeseries_name_value_paragraphs(
     f: &mut Frame,
@@ -112,9 +126,150 @@ where
         }
     }
 
-    fn page_forward(&mut self) {}
+    fn page_forward(&mut self) {
+        self.apply_motion(Motion::Down(page_rows()));
+    }
 
-    fn page_backward(&mut self) {}
+    fn page_backward(&mut self) {
+        self.apply_motion(Motion::Up(page_rows()));
+    }
+
+    fn apply_motion(&mut self, motion: Motion) {
+        let len = self.get_filtered_items().len();
+        let pos = self.get_state().selected().unwrap_or(0);
+        let new_pos = motion.target(pos, len);
+        self.get_state().select(Some(new_pos));
+        let new_scroll_state = self.get_scroll_state().position(new_pos * ITEM_HEIGHT);
+        self.set_scroll_state(new_scroll_state);
+    }
+
+    /// Take `key` into a vim style motion, see `motions`, `true` when the
+    /// key was one or part of one.
+    fn handle_motion(&mut self, key: &KeyEvent) -> bool {
+        match motions::step(key) {
+            Step::Pending => true,
+            Step::Motion(motion) => {
+                self.apply_motion(motion);
+                true
+            }
+            Step::Other => false,
+        }
+    }
+
+    /// the incremental search of the table, `None` for tables without one
+    fn table_search(&mut self) -> Option<&mut TableSearch> {
+        None
+    }
+
+    /// the key starting the search, tables where `/` already filters use `?`
+    fn search_key(&self) -> char {
+        '/'
+    }
+
+    /// Take `key` into the table's search, see `table_search`, `true` when
+    /// it was the search's.
+    fn handle_search(&mut self, key: &KeyEvent) -> bool {
+        let row = self.get_state().selected().unwrap_or(0);
+        let search_key = self.search_key();
+        let Some(search) = self.table_search() else {
+            return false;
+        };
+        let (from, forward, inclusive) = if search.editing {
+            if !filter_pattern::toggle(key) {
+                match key.code {
+                    KeyCode::Char(c) => search.pattern.push(c),
+                    KeyCode::Backspace => _ = search.pattern.pop(),
+                    KeyCode::Enter => search.editing = false,
+                    KeyCode::Esc => {
+                        search.pattern.clear();
+                        search.editing = false;
+                    }
+                    _ => {}
+                }
+            }
+            (search.origin, true, true)
+        } else {
+            match key.code {
+                KeyCode::Char(c) if c == search_key => {
+                    *search = TableSearch {
+                        editing: true,
+                        origin: row,
+                        key: search_key,
+                        ..TableSearch::default()
+                    };
+                    return true;
+                }
+                KeyCode::Char('n') if search.is_active() => (row, true, false),
+                KeyCode::Char('N') if search.is_active() => (row, false, false),
+                KeyCode::Esc if search.is_active() => {
+                    search.pattern.clear();
+                    return true;
+                }
+                _ => return false,
+            }
+        };
+        let target = match search.matcher() {
+            Some(matcher) => {
+                let items = self.get_filtered_items();
+                let names: Vec<&str> = items.iter().map(|item| item.filter_by()).collect();
+                next_match(&names, &matcher, from, forward, inclusive)
+            }
+            // nothing typed, or the search was let go of
+            None => Some(from),
+        };
+        if let Some(target) = target {
+            self.apply_motion(Motion::Row(target + 1));
+        }
+        true
+    }
+
+    /// the table the rows are listed in, `None` for rows marks can not
+    /// find again
+    fn mark_scope(&self) -> Option<Scope> {
+        None
+    }
+
+    /// select the row named `name`, `false` when no row is
+    fn select_named(&mut self, name: &str) -> bool {
+        let Some(row) = self
+            .get_filtered_items()
+            .iter()
+            .position(|item| item.filter_by() == name)
+        else {
+            return false;
+        };
+        self.apply_motion(Motion::Row(row + 1));
+        true
+    }
+
+    /// Take `key` into a mark, see `marks`, `None` when the key is not
+    /// one.  Jumping to a mark another view lists switches to that view.
+    fn handle_mark(&mut self, key: &KeyEvent) -> Option<Transition> {
+        // the search being typed takes every key
+        if self.table_search().is_some_and(|search| search.editing) {
+            return None;
+        }
+        match marks::step(key) {
+            marks::Step::Other => return None,
+            marks::Step::Pending => {}
+            marks::Step::Set(letter) => {
+                let scope = self.mark_scope();
+                let name = self
+                    .get_selected_item()
+                    .map(|item| item.filter_by().to_string());
+                if let (Some(scope), Some(name)) = (scope, name) {
+                    marks::set(letter, marks::Resource { scope, name });
+                }
+            }
+            marks::Step::Jump(letter) => match marks::get(letter) {
+                Ok(resource) if self.mark_scope().as_ref() == Some(&resource.scope) => {
+                    self.select_named(&resource.name);
+                }
+                _ => return Some(Transition::Jump(letter)),
+            },
+        }
+        Some(Transition::Stay)
+    }
 
     fn next_color(&mut self) {
         let new_color_index = (self.get_color_index() + 1) % PALETTES.len();
@@ -157,19 +312,19 @@ where
 
     fn get_filter(&self) -> String;
     fn set_filter(&mut self, filter: String);
+    /// `true` when the api server already applied the filter to the items
+    fn is_filter_pushed_down(&self) -> bool {
+        false
+    }
     fn get_filtered_items(&self) -> Vec<&Self::Item> {
-        let filter_pattern = self.get_filter();
-        match Regex::new(&filter_pattern) {
-            Ok(regex) => self
-                .get_items()
-                .iter()
-                .filter(|item| regex.is_match(item.filter_by()))
-                .collect(),
-            Err(e) => {
-                debug!("Invalid regex pattern: {}", e); // Log the error
-                self.get_items().iter().collect() // Return the unfiltered list
-            }
+        if self.is_filter_pushed_down() {
+            return self.get_items().iter().collect();
         }
+        let pattern = FilterPattern::parse(&self.get_filter());
+        self.get_items()
+            .iter()
+            .filter(|item| pattern.is_match(item.filter_by()))
+            .collect()
     }
 
     fn move_cursor_left(&mut self) {
@@ -237,9 +392,7 @@ pub fn render_detail_section(
 }
 
 fn get_chunks_from_area(area: Rect, sz: usize) -> Rc<[Rect]> {
-    let constraints = std::iter::repeat(Constraint::Length(1))
-        .take(sz)
-        .collect::<Vec<Constraint>>();
+    let constraints = std::iter::repeat_n(Constraint::Length(1), sz).collect::<Vec<Constraint>>();
 
     Layout::default()
         .direction(Direction::Vertical)
@@ -0,0 +1,147 @@
+//!The symbols every view draws with.  Some fonts and terminals, ie: the
+//!linux console, draw block, arrow and braille characters badly, so there
+//!is a plain ASCII set besides the unicode one.  `symbols: ascii` or
+//!`symbols: unicode` in `config.yaml` picks one, otherwise ASCII is used
+//!for terminals known to lack the glyphs and for locales that are not
+//!UTF-8.
+//!
+use crate::config::{settings, SymbolSet};
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Symbols {
+    /// marks the selected row
+    pub bar: &'static str,
+    /// where typing goes in an input
+    pub cursor: &'static str,
+    pub ellipsis: &'static str,
+    pub ok: &'static str,
+    pub failed: &'static str,
+    pub warning: &'static str,
+    /// a value changing to the next, ie: `Pending → Running`
+    pub arrow: &'static str,
+    /// the keys that step through a list
+    pub up_down: &'static str,
+    pub ascending: &'static str,
+    pub descending: &'static str,
+    pub expanded: &'static str,
+    pub collapsed: &'static str,
+    pub dot: &'static str,
+    /// separates columns and status line segments
+    pub vertical: &'static str,
+    /// draws dividers
+    pub horizontal: &'static str,
+    pub timer: &'static str,
+    pub spinner: [&'static str; 8],
+    /// sparkline levels, lowest first
+    pub sparks: [char; 8],
+    pub gauge_full: &'static str,
+    pub gauge_empty: &'static str,
+}
+
+pub const UNICODE: Symbols = Symbols {
+    bar: "█",
+    cursor: "▏",
+    ellipsis: "…",
+    ok: "✓",
+    failed: "✗",
+    warning: "⚠",
+    arrow: "→",
+    up_down: "↑↓",
+    ascending: "▲",
+    descending: "▼",
+    expanded: "▾",
+    collapsed: "▸",
+    dot: "●",
+    vertical: "│",
+    horizontal: "─",
+    timer: "⏱",
+    spinner: ["⣾", "⣽", "⣻", "⢿", "⡿", "⣟", "⣯", "⣷"],
+    sparks: ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'],
+    gauge_full: "█",
+    gauge_empty: "░",
+};
+
+pub const ASCII: Symbols = Symbols {
+    bar: "#",
+    cursor: "_",
+    ellipsis: "...",
+    ok: "ok",
+    failed: "x",
+    warning: "!",
+    arrow: "->",
+    up_down: "up/down",
+    ascending: "^",
+    descending: "v",
+    expanded: "-",
+    collapsed: "+",
+    dot: "*",
+    vertical: "|",
+    horizontal: "-",
+    timer: "!",
+    spinner: ["|", "/", "-", "\\", "|", "/", "-", "\\"],
+    sparks: ['_', '.', ',', '-', '~', '=', '*', '#'],
+    gauge_full: "#",
+    gauge_empty: ".",
+};
+
+/// terminals whose fonts lack block and braille characters
+const ASCII_TERMS: [&str; 5] = ["linux", "dumb", "vt100", "vt102", "vt220"];
+
+/// Which set suits a terminal with `TERM` set to `term` and the locale
+/// `locale`, from `LC_ALL`, `LC_CTYPE` or `LANG`.
+#[must_use]
+pub fn detect(term: Option<&str>, locale: Option<&str>) -> SymbolSet {
+    let plain_term = term.is_some_and(|term| ASCII_TERMS.contains(&term));
+    let plain_locale = locale.is_some_and(|locale| {
+        let locale = locale.to_ascii_lowercase();
+        !locale.contains("utf-8") && !locale.contains("utf8")
+    });
+    if plain_term || plain_locale {
+        SymbolSet::Ascii
+    } else {
+        SymbolSet::Unicode
+    }
+}
+
+/// the symbol set picked in `config.yaml` or detected, always unicode in
+/// tests so they do not depend on the terminal running them
+#[must_use]
+pub fn symbols() -> &'static Symbols {
+    static SYMBOLS: OnceLock<&'static Symbols> = OnceLock::new();
+    if cfg!(test) {
+        return &UNICODE;
+    }
+    SYMBOLS.get_or_init(|| {
+        let set = settings().symbols.unwrap_or_else(|| {
+            let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+                .into_iter()
+                .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()));
+            detect(std::env::var("TERM").ok().as_deref(), locale.as_deref())
+        });
+        match set {
+            SymbolSet::Unicode => &UNICODE,
+            SymbolSet::Ascii => &ASCII,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            detect(Some("xterm-256color"), Some("en_US.UTF-8")),
+            SymbolSet::Unicode
+        );
+        assert_eq!(detect(None, None), SymbolSet::Unicode);
+        assert_eq!(detect(Some("linux"), Some("en_US.UTF-8")), SymbolSet::Ascii);
+        assert_eq!(detect(Some("xterm"), Some("C")), SymbolSet::Ascii);
+        assert_eq!(
+            detect(Some("xterm"), Some("de_DE.utf8")),
+            SymbolSet::Unicode
+        );
+    }
+}
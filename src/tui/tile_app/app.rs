@@ -0,0 +1,228 @@
+use crate::error::Error;
+use crate::error::Result as NvResult;
+use crate::k8s::containers::logs;
+use crate::tui::data::LogRec;
+use crate::tui::filter_pattern::FilterPattern;
+use crate::tui::stream::Message;
+use crate::tui::tile_app;
+use crate::tui::tiles::MAX_TILES;
+use crate::tui::ui_loop::{AppBehavior, Transition};
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use futures::Stream;
+use ratatui::prelude::*;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::debug;
+
+const POLL_MS: u64 = 5000;
+
+/// one pod's log in a tile, newest line first
+#[derive(Clone, Debug)]
+pub struct Pane {
+    pub(crate) pod_name: String,
+    pub(crate) container_name: String,
+    pub(crate) items: Vec<LogRec>,
+    /// stay on the newest line as lines arrive
+    pub(crate) follow: bool,
+    /// lines scrolled back from the newest when not following
+    pub(crate) offset: usize,
+    /// lines that arrived while paused
+    pending: Option<Vec<LogRec>>,
+}
+
+impl Pane {
+    #[must_use]
+    pub const fn new(pod_name: String, container_name: String) -> Self {
+        Self {
+            pod_name,
+            container_name,
+            items: vec![],
+            follow: true,
+            offset: 0,
+            pending: None,
+        }
+    }
+
+    /// take new lines, keeping a scrolled back pane on the same line
+    fn update(&mut self, items: Vec<LogRec>) {
+        if !self.follow {
+            let top = self.items.get(self.offset);
+            let shift = top
+                .and_then(|top| items.iter().position(|item| item == top))
+                .unwrap_or(self.offset);
+            self.offset = shift;
+        }
+        self.items = items;
+    }
+
+    fn scroll(&mut self, lines: isize) {
+        self.offset = self
+            .offset
+            .saturating_add_signed(lines)
+            .min(self.items.len().saturating_sub(1));
+        self.follow = self.offset == 0;
+    }
+}
+
+/// Logs of several pods tailed side by side.  Each pane follows or scrolls
+/// on its own, the filter and pause apply to all of them.
+#[derive(Clone, Debug)]
+pub struct App {
+    pub(crate) selector: BTreeMap<String, String>,
+    pub(crate) panes: Vec<Pane>,
+    pub(crate) focused: usize,
+    pub(crate) filter: String,
+    pub(crate) show_filter_edit: bool,
+    pub(crate) paused: bool,
+}
+
+impl AppBehavior for tile_app::app::App {
+    const NAME: &'static str = "tiles";
+
+    async fn handle_event(&mut self, event: &Message) -> NvResult<Transition> {
+        match event {
+            Message::Key(Event::Key(key))
+                if key.kind == KeyEventKind::Press && self.show_filter_edit =>
+            {
+                match key.code {
+                    KeyCode::Char(c) => self.filter.push(c),
+                    KeyCode::Backspace => {
+                        self.filter.pop();
+                    }
+                    KeyCode::Esc | KeyCode::Enter => self.show_filter_edit = false,
+                    _ => {}
+                }
+            }
+            Message::Key(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                use KeyCode::{BackTab, Char, Down, Esc, Tab, Up};
+                let count = self.panes.len().max(1);
+                match key.code {
+                    Char('q') | Esc => return Ok(Transition::Pop),
+                    Tab => self.focused = (self.focused + 1) % count,
+                    BackTab => self.focused = (self.focused + count - 1) % count,
+                    Char('j') | Down => self.scroll_focused(1),
+                    Char('k') | Up => self.scroll_focused(-1),
+                    Char('f' | 'F') => {
+                        if let Some(pane) = self.panes.get_mut(self.focused) {
+                            pane.follow = !pane.follow;
+                            if pane.follow {
+                                pane.offset = 0;
+                            }
+                        }
+                    }
+                    Char(' ' | 'p' | 'P') => self.toggle_pause(),
+                    Char('/') => self.show_filter_edit = true,
+                    _ => {}
+                }
+            }
+            Message::TileLog { pane, logs } => {
+                if let Some(pane) = self.panes.get_mut(*pane) {
+                    if self.paused {
+                        pane.pending = Some(logs.clone());
+                    } else {
+                        pane.update(logs.clone());
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(Transition::Stay)
+    }
+
+    fn draw_ui<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<(), std::io::Error> {
+        terminal.draw(|f| tile_app::ui::ui(f, self))?;
+        Ok(())
+    }
+
+    fn stream(&self, should_stop: Arc<AtomicBool>) -> impl Stream<Item = Message> {
+        let (tx, rx) = mpsc::channel(100);
+
+        for (index, pane) in self.panes.iter().enumerate() {
+            let tx = tx.clone();
+            let should_stop = should_stop.clone();
+            let selector = self.selector.clone();
+            let pod_name = pane.pod_name.clone();
+            let container_name = pane.container_name.clone();
+            tokio::spawn(async move {
+                let mut last = vec![];
+                while !should_stop.load(Ordering::Relaxed) {
+                    match logs(selector.clone(), pod_name.clone(), container_name.clone()).await {
+                        Ok(d) => {
+                            if !d.is_empty() && d != last {
+                                last.clone_from(&d);
+                                let sevent = Message::TileLog {
+                                    pane: index,
+                                    logs: d,
+                                };
+                                if tx.send(sevent).await.is_err() {
+                                    break;
+                                }
+                            }
+                        }
+                        Err(Error::Timeout { .. }) => {
+                            // already surfaced in the header, keep polling
+                        }
+                        Err(e) => {
+                            debug!("stopped tailing {pod_name}/{container_name}: {e}");
+                            break;
+                        }
+                    }
+                    sleep(Duration::from_millis(POLL_MS)).await;
+                }
+            });
+        }
+
+        ReceiverStream::new(rx)
+    }
+}
+
+impl App {
+    /// at most `MAX_TILES` of `panes` are tailed
+    pub fn new(selector: BTreeMap<String, String>, mut panes: Vec<Pane>) -> Self {
+        panes.truncate(MAX_TILES);
+        Self {
+            selector,
+            panes,
+            focused: 0,
+            filter: String::new(),
+            show_filter_edit: false,
+            paused: false,
+        }
+    }
+
+    fn scroll_focused(&mut self, lines: isize) {
+        if let Some(pane) = self.panes.get_mut(self.focused) {
+            pane.scroll(lines);
+        }
+    }
+
+    /// pause or resume every pane at once, resuming catches up on the lines
+    /// that arrived meanwhile
+    fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        if !self.paused {
+            for pane in &mut self.panes {
+                if let Some(pending) = pane.pending.take() {
+                    pane.update(pending);
+                }
+            }
+        }
+    }
+
+    /// the lines of `pane` from its scroll position on that match the shared
+    /// filter, an invalid pattern as text
+    #[must_use]
+    pub fn visible<'a>(&self, pane: &'a Pane) -> Vec<&'a LogRec> {
+        let pattern = FilterPattern::parse(&self.filter);
+        pane.items
+            .iter()
+            .skip(pane.offset)
+            .filter(|item| pattern.is_match(&item.message))
+            .collect()
+    }
+}
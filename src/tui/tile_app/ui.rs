@@ -0,0 +1,85 @@
+use crate::tui::error_card;
+use crate::tui::filter_pattern;
+use crate::tui::status_line;
+use crate::tui::table_ui::with_timeout_notice;
+use crate::tui::theme::symbols;
+use crate::tui::tile_app::app::App;
+use crate::tui::tiles::tile_areas;
+use crate::tui::utils::names::{middle_ellipsis, MAX_NAME_WIDTH};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph},
+};
+
+pub fn ui(f: &mut Frame, app: &App) {
+    let rects =
+        Layout::vertical([Constraint::Min(5), Constraint::Length(1)]).split(status_line::render(f));
+
+    for (index, (pane, area)) in app
+        .panes
+        .iter()
+        .zip(tile_areas(rects[0], app.panes.len()))
+        .enumerate()
+    {
+        let lines: Vec<Line> = app
+            .visible(pane)
+            .into_iter()
+            .take(usize::from(area.height))
+            .map(|rec| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{} ", rec.datetime),
+                        Style::new().add_modifier(Modifier::DIM),
+                    ),
+                    Span::raw(rec.message.clone()),
+                ])
+            })
+            .collect();
+        let mode = if pane.follow {
+            "follow".to_string()
+        } else {
+            format!("-{}", pane.offset)
+        };
+        let border = if index == app.focused {
+            Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+        } else {
+            Style::new().add_modifier(Modifier::DIM)
+        };
+        // the borders, spaces, slash and mode take the rest of the title
+        let room = usize::from(area.width)
+            .saturating_sub(pane.container_name.chars().count() + mode.chars().count() + 8)
+            .min(MAX_NAME_WIDTH);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(border)
+            .title(format!(
+                " {}/{} [{mode}] ",
+                middle_ellipsis(&pane.pod_name, room),
+                pane.container_name
+            ));
+        f.render_widget(Paragraph::new(lines).block(block), area);
+    }
+
+    let filter = if app.show_filter_edit {
+        format!(
+            "filter: {}{}",
+            filter_pattern::label(&app.filter),
+            symbols().cursor
+        )
+    } else if app.filter.is_empty() {
+        "/ filter".to_string()
+    } else {
+        format!("filter: {}", filter_pattern::label(&app.filter))
+    };
+    let paused = if app.paused { " [paused]" } else { "" };
+    let status = with_timeout_notice(format!(
+        "{filter}{paused}  tab next pane  j/k scroll  f follow  space pause all  esc back"
+    ));
+    f.render_widget(
+        Paragraph::new(status).style(Style::new().add_modifier(Modifier::REVERSED)),
+        rects[1],
+    );
+
+    status_line::render_activity(f);
+    error_card::render(f);
+}
@@ -0,0 +1,57 @@
+//!Splits an area into up to `MAX_TILES` panes: side by side for two, a
+//!wide pane under two for three, a grid for four.
+//!
+use ratatui::prelude::*;
+
+pub const MAX_TILES: usize = 4;
+
+/// the area of each of `count` panes, left to right then top to bottom
+#[must_use]
+pub fn tile_areas(area: Rect, count: usize) -> Vec<Rect> {
+    let halves = |area: Rect, direction: Direction| {
+        Layout::new(direction, [Constraint::Ratio(1, 2); 2])
+            .split(area)
+            .to_vec()
+    };
+    match count.min(MAX_TILES) {
+        0 => vec![],
+        1 => vec![area],
+        2 => halves(area, Direction::Horizontal),
+        n => {
+            let rows = halves(area, Direction::Vertical);
+            let mut areas = halves(rows[0], Direction::Horizontal);
+            if n == 3 {
+                areas.push(rows[1]);
+            } else {
+                areas.extend(halves(rows[1], Direction::Horizontal));
+            }
+            areas
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_areas() {
+        let area = Rect::new(0, 0, 100, 40);
+        assert_eq!(tile_areas(area, 1), vec![area]);
+        assert_eq!(
+            tile_areas(area, 2),
+            vec![Rect::new(0, 0, 50, 40), Rect::new(50, 0, 50, 40)]
+        );
+        assert_eq!(
+            tile_areas(area, 3),
+            vec![
+                Rect::new(0, 0, 50, 20),
+                Rect::new(50, 0, 50, 20),
+                Rect::new(0, 20, 100, 20)
+            ]
+        );
+        let grid = tile_areas(area, 6);
+        assert_eq!(grid.len(), MAX_TILES);
+        assert_eq!(grid[3], Rect::new(50, 20, 50, 20));
+    }
+}
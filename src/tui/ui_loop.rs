@@ -1,77 +1,154 @@
-use crate::k8s::containers::list as list_containers;
-use crate::k8s::rs::get_replicaset;
-use crate::k8s::rs_ingress::list_ingresses;
+use crate::error::{Error as NvError, Result as NvResult};
+use crate::k8s::cache::cache_manager::{self, InFlight};
+use crate::k8s::cache::{CachedData, DataRequest};
+use crate::k8s::client_manager;
+use crate::k8s::containers;
 use crate::net::analyze_tls_certificate;
-use crate::tui::cert_app;
-use crate::tui::container_app;
+use crate::perf;
+use crate::shutdown;
+use crate::tui::crash::TerminalGuard;
 use crate::tui::data;
-use crate::tui::event_app;
-use crate::tui::ingress_app;
-use crate::tui::log_app;
-use crate::tui::pod_app;
+use crate::tui::diag_app;
+use crate::tui::error_card;
+use crate::tui::marks;
+use crate::tui::quit_dialog::QuitDialog;
+use crate::tui::registry::registry;
 use crate::tui::rs_app;
-use crate::tui::stream::{async_key_events, Message};
+use crate::tui::status_line;
+use crate::tui::stream::{self, async_key_events, Message};
 use crate::tui::utils::time::asn1time_to_future_days_string;
 use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
+    event::{EnableFocusChange, EnableMouseCapture, Event, KeyEventKind},
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{enable_raw_mode, EnterAlternateScreen},
 };
+use futures::future::LocalBoxFuture;
 use futures::stream::Stream;
 use futures::stream::StreamExt;
 use ratatui::prelude::*;
 use std::collections::BTreeMap;
+use std::fmt;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::{error::Error, io};
+use std::time::Duration;
+use std::{error::Error, io, io::Stdout};
+use tokio::time::{sleep_until, Instant};
 use tracing::error;
 
+/// Messages arriving within a frame are drawn once, a watch streaming many
+/// updates a second redraws at most this often.
+const FRAME: Duration = Duration::from_millis(33);
+/// the same while the terminal is in a background window or pane
+const IDLE_FRAME: Duration = Duration::from_secs(1);
+
+fn frame() -> Duration {
+    if stream::focused() {
+        FRAME
+    } else {
+        IDLE_FRAME
+    }
+}
+
+/// What a view asks of the loop after handling an event.  Views change
+/// themselves in place, only a switch carries another view.
+#[derive(Debug)]
+pub enum Transition {
+    /// keep showing this view
+    Stay,
+    /// show another view, leaving it comes back to this one
+    Push(Box<Apps>),
+    /// back to the previous view, quitting from the first
+    Pop,
+    /// fetch the view's `source` again past the cache
+    Refresh,
+    /// show the view listing the mark, see `marks`
+    Jump(char),
+}
+
 pub(crate) trait AppBehavior {
-    async fn handle_event(&mut self, event: &Message) -> Result<Option<Apps>, io::Error>;
+    /// what the registry and the history know the view as
+    const NAME: &'static str;
+
+    async fn handle_event(&mut self, event: &Message) -> NvResult<Transition>;
 
     fn draw_ui<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<(), std::io::Error>;
 
     fn stream(&self, should_stop: Arc<AtomicBool>) -> impl Stream<Item = Message>;
+
+    /// the cache entry the rows come from, for the staleness badge
+    fn source(&self) -> Option<DataRequest> {
+        None
+    }
 }
 
 /// # Errors
 ///
 /// Will return `Err` if function cannot access a terminal or render a ui
 pub async fn run() -> Result<(), Box<dyn Error>> {
-    // setup terminal
+    tokio::spawn(async {
+        client_manager::warm_up().await;
+        if let Ok(client) = client_manager::get_client().await {
+            status_line::set_scope(
+                client_manager::current_context(),
+                client.default_namespace().to_string(),
+            );
+        }
+        client_manager::start_token_refresh();
+        cache_manager::initialize_cache().await;
+    });
+
+    // setup terminal, restored when the guard drops, even on a panic
     enable_raw_mode()?;
+    let _guard = TerminalGuard::new();
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableFocusChange
+    )?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let res = run_root_ui_loop(&mut terminal).await;
+    run_root_ui_loop(&mut terminal).await;
 
-    // restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    Ok(())
+}
+
+type Term = Terminal<CrosstermBackend<Stdout>>;
+
+/// A view the loop runs without knowing its type, `AppBehavior` has async
+/// and generic methods and can not be boxed itself.
+trait View {
+    fn name(&self) -> &'static str;
+
+    /// show the view until it switches to another, `None` going back
+    fn run<'a>(&'a mut self, terminal: &'a mut Term) -> LocalBoxFuture<'a, Option<Apps>>;
+}
 
-    if let Err(err) = res {
-        error!("{err:?}");
+impl<A: AppBehavior + Clone + 'static> View for A {
+    fn name(&self) -> &'static str {
+        A::NAME
     }
 
-    Ok(())
+    fn run<'a>(&'a mut self, terminal: &'a mut Term) -> LocalBoxFuture<'a, Option<Apps>> {
+        Box::pin(run_view(terminal, self))
+    }
+}
+
+/// a view on the history, any `AppBehavior`
+pub struct Apps(Box<dyn View>);
+
+impl Apps {
+    pub(crate) fn new<A: AppBehavior + Clone + 'static>(app: A) -> Self {
+        Self(Box::new(app))
+    }
 }
 
-#[derive(Clone, Debug)]
-pub enum Apps {
-    Rs { app: rs_app::app::App },
-    Pod { app: pod_app::app::App },
-    Container { app: container_app::app::App },
-    Ingress { app: ingress_app::app::App },
-    Cert { app: cert_app::app::App },
-    Log { app: log_app::app::App },
-    Event { app: event_app::app::App },
+impl fmt::Debug for Apps {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Apps").field(&self.0.name()).finish()
+    }
 }
 
 /// # Errors
@@ -80,11 +157,19 @@ pub enum Apps {
 pub async fn create_container_data_vec(
     selectors: BTreeMap<String, String>,
     pod_name: String,
-) -> Result<Vec<data::Container>, io::Error> {
-    match list_containers(selectors, pod_name).await {
-        Ok(cntrs) => Ok(cntrs),
-        Err(e) => Err(io::Error::new(io::ErrorKind::Other, e.to_string())),
-    }
+) -> NvResult<Vec<data::Container>> {
+    let containers = {
+        let _blocking = InFlight::blocking();
+        containers::list(selectors.clone(), pod_name.clone()).await?
+    };
+    cache_manager::get_cache().put(
+        DataRequest::Containers {
+            selector: selectors,
+            pod: pod_name,
+        },
+        CachedData::Containers(containers.clone()),
+    );
+    Ok(containers)
 }
 
 /// # Errors
@@ -92,23 +177,17 @@ pub async fn create_container_data_vec(
 /// Will return `Err` if function cannot access the k8s api
 pub async fn create_ingress_data_vec(
     selector: BTreeMap<String, String>,
-) -> Result<Vec<data::Ingress>, io::Error> {
-    match get_replicaset(selector).await {
-        Ok(rso) => match rso {
-            Some(rs) => match list_ingresses(&rs, "").await {
-                Ok(ingress) => Ok(ingress),
-                Err(e) => Err(io::Error::new(io::ErrorKind::Other, e.to_string())),
-            },
-            _ => Ok(vec![]),
-        },
-        Err(e) => Err(io::Error::new(io::ErrorKind::Other, e.to_string())),
+) -> NvResult<Vec<data::Ingress>> {
+    match cache_manager::get_or_fetch(DataRequest::Ingresses { selector }).await? {
+        CachedData::Ingresses(ingresses) => Ok(ingresses),
+        _ => Ok(vec![]),
     }
 }
 
 /// # Errors
 ///
 /// Will return `Err` if function cannot access the remote host and cert
-pub async fn create_cert_data_vec(host: &str) -> Result<Vec<data::Cert>, io::Error> {
+pub async fn create_cert_data_vec(host: &str) -> NvResult<Vec<data::Cert>> {
     match analyze_tls_certificate(host).await {
         Ok(cinfo) => {
             let d = data::Cert {
@@ -119,192 +198,140 @@ pub async fn create_cert_data_vec(host: &str) -> Result<Vec<data::Cert>, io::Err
             };
             Ok(vec![d])
         }
-        Err(e) => {
-            let emsg = format!("host: {host} error: {e}");
-            Err(io::Error::new(io::ErrorKind::Other, emsg))
-        }
+        Err(e) => Err(NvError::Network(format!("host: {host} error: {e}"))),
     }
 }
 
-#[allow(clippy::too_many_lines)]
-async fn run_app<B>(
-    terminal: &mut Terminal<B>,
-    apps_app: &mut Apps,
-) -> Result<(Option<Apps>, Option<Apps>), io::Error>
-where
-    B: Backend + Send,
-{
+/// a key press while an error card is showing only dismisses the card
+fn card_dismissed(event: &Message) -> bool {
+    matches!(event, Message::Key(Event::Key(key)) if key.kind == KeyEventKind::Press)
+        && error_card::dismiss()
+}
+
+/// a click on the status line asking for the diagnostics view
+fn status_clicked(event: &Message) -> bool {
+    matches!(event, Message::Key(event) if status_line::clicked(event))
+}
+
+/// Show `app` until it switches to another view, `None` going back.
+async fn run_view<A: AppBehavior + Clone>(terminal: &mut Term, app: &mut A) -> Option<Apps> {
+    // the diagnostics view is already where a status line click leads
+    let diag_on_click = A::NAME != diag_app::app::App::NAME;
     let should_stop = Arc::new(AtomicBool::new(false));
     let key_events = async_key_events(should_stop.clone());
+    // the stream starts from the rows the view opened with
+    let data_init = app.clone();
+    let data_events = data_init.stream(should_stop.clone());
+    futures::pin_mut!(data_events, key_events);
 
-    #[allow(unused_assignments)] // we might quit or ESC
-    let mut old_app_holder = Some(apps_app.clone());
-    #[allow(unused_assignments)] // we might quit or ESC
-    let mut new_app_holder = None;
-    match apps_app {
-        Apps::Rs { ref mut app } => {
-            let data_init_clone = app.clone();
-            let data_events = data_init_clone.stream(should_stop.clone());
-            let mut events = futures::stream::select(data_events, key_events);
-            let mut current_app = app.clone();
-            loop {
-                _ = current_app.draw_ui(terminal);
-                if let Some(event) = events.next().await {
-                    let app_holder = current_app.handle_event(&event).await?;
-                    if let Some(Apps::Rs { app }) = &app_holder {
-                        current_app = app.clone();
-                        old_app_holder = app_holder;
-                    } else {
-                        new_app_holder = app_holder;
-                        break;
-                    };
-                };
-            }
-        }
-        Apps::Pod { app } => {
-            let data_init_clone = app.clone();
-            let data_events = data_init_clone.stream(should_stop.clone());
-            let mut events = futures::stream::select(data_events, key_events);
-            let mut current_app = app.clone();
-            loop {
-                _ = current_app.draw_ui(terminal);
-                if let Some(event) = events.next().await {
-                    let app_holder = current_app.handle_event(&event).await?;
-                    if let Some(Apps::Pod { app }) = &app_holder {
-                        current_app = app.clone();
-                        old_app_holder = app_holder;
-                    } else {
-                        new_app_holder = app_holder;
-                        break;
-                    };
-                };
-            }
-        }
-        Apps::Container { app } => {
-            let data_init_clone = app.clone();
-            let data_events = data_init_clone.stream(should_stop.clone());
-            let mut events = futures::stream::select(data_events, key_events);
-            let mut current_app = app.clone();
-            loop {
-                _ = current_app.draw_ui(terminal);
-                if let Some(event) = events.next().await {
-                    let app_holder = current_app.handle_event(&event).await?;
-                    if let Some(Apps::Container { app }) = &app_holder {
-                        current_app = app.clone();
-                        old_app_holder = app_holder;
-                    } else {
-                        new_app_holder = app_holder;
-                        break;
-                    };
-                };
-            }
+    let mut dirty = true;
+    let mut drawn: Option<Instant> = None;
+    let next = loop {
+        if dirty && drawn.is_none_or(|at| at.elapsed() >= frame()) {
+            let started = Instant::now();
+            status_line::set_source(app.source());
+            status_line::set_selected(None);
+            _ = app.draw_ui(terminal);
+            perf::record_frame(started.elapsed());
+            dirty = false;
+            drawn = Some(Instant::now());
         }
-        Apps::Cert { app } => {
-            let data_init_clone = app.clone();
-            let data_events = data_init_clone.stream(should_stop.clone());
-            let mut events = futures::stream::select(data_events, key_events);
-            let mut current_app = app.clone();
-            loop {
-                _ = current_app.draw_ui(terminal);
-                if let Some(event) = events.next().await {
-                    let app_holder = current_app.handle_event(&event).await?;
-                    if let Some(Apps::Cert { app }) = &app_holder {
-                        current_app = app.clone();
-                        old_app_holder = app_holder;
-                    } else {
-                        new_app_holder = app_holder;
-                        break;
-                    };
-                };
-            }
+        let due = drawn.unwrap_or_else(Instant::now) + frame();
+        let event = tokio::select! {
+            // keys first, typing stays ahead of a chatty watch
+            biased;
+            Some(event) = key_events.next() => event,
+            Some(event) = data_events.next() => event,
+            () = sleep_until(due), if dirty => continue,
+            // the terminal is gone
+            else => break None,
+        };
+        dirty = true;
+        if card_dismissed(&event) {
+            continue;
         }
-        Apps::Ingress { app } => {
-            let data_init_clone = app.clone();
-            let data_events = data_init_clone.stream(should_stop.clone());
-            let mut events = futures::stream::select(data_events, key_events);
-            let mut current_app = app.clone();
-            loop {
-                _ = current_app.draw_ui(terminal);
-                if let Some(event) = events.next().await {
-                    let app_holder = current_app.handle_event(&event).await?;
-                    if let Some(Apps::Ingress { app }) = &app_holder {
-                        current_app = app.clone();
-                        old_app_holder = app_holder;
-                    } else {
-                        new_app_holder = app_holder;
-                        break;
-                    };
-                };
+        if diag_on_click && status_clicked(&event) {
+            match registry()
+                .by_name(diag_app::app::App::NAME)
+                .map(|open| open())
+            {
+                Some(Ok(next)) => break Some(next),
+                Some(Err(e)) => error_card::show(&e),
+                None => {}
             }
+            continue;
         }
-
-        Apps::Log { app } => {
-            let data_init_clone = app.clone();
-            let data_events = data_init_clone.stream(should_stop.clone());
-            let mut events = futures::stream::select(data_events, key_events);
-            let mut current_app = app.clone();
-            loop {
-                _ = current_app.draw_ui(terminal);
-                if let Some(event) = events.next().await {
-                    let app_holder = current_app.handle_event(&event).await?;
-                    if let Some(Apps::Log { app }) = &app_holder {
-                        current_app = app.clone();
-                        old_app_holder = app_holder;
-                    } else {
-                        new_app_holder = app_holder;
-                        break;
-                    };
-                };
+        match app.handle_event(&event).await {
+            Ok(Transition::Stay) => {}
+            Ok(Transition::Push(next)) => break Some(*next),
+            Ok(Transition::Pop) => break None,
+            Ok(Transition::Refresh) => {
+                if let Err(e) = force_refresh(app).await {
+                    error_card::show(&e);
+                }
             }
-        }
-
-        Apps::Event { app } => {
-            let data_init_clone = app.clone();
-            let data_events = data_init_clone.stream(should_stop.clone());
-            let mut events = futures::stream::select(data_events, key_events);
-            let mut current_app = app.clone();
-            loop {
-                _ = current_app.draw_ui(terminal);
-                if let Some(event) = events.next().await {
-                    let app_holder = current_app.handle_event(&event).await?;
-                    if let Some(Apps::Event { app }) = &app_holder {
-                        current_app = app.clone();
-                        old_app_holder = app_holder;
-                    } else {
-                        new_app_holder = app_holder;
-                        break;
-                    };
-                };
+            Ok(Transition::Jump(letter)) => match marks::open(letter).await {
+                Ok(next) => break Some(next),
+                Err(e) => error_card::show(&e),
+            },
+            Err(e) => {
+                error!("{}: {e}", e.code());
+                error_card::show(&e);
             }
         }
-    }
+    };
 
     should_stop.store(true, Ordering::Relaxed);
-    Ok((old_app_holder, new_app_holder))
+    next
+}
+
+/// Fetch the rows of `app` bypassing the cache and hand them to it like
+/// any other update, the status line says how long the fetch took.
+async fn force_refresh<A: AppBehavior>(app: &mut A) -> NvResult<()> {
+    let Some(request) = app.source() else {
+        return Ok(());
+    };
+    let (data, took) = cache_manager::force_refresh(request).await?;
+    status_line::refreshed(took);
+    let message = match data {
+        CachedData::ReplicaSets(rows) => Message::Rs(rows),
+        CachedData::Pods(rows) => Message::Pod(rows),
+        CachedData::Events(rows) => Message::Event(rows),
+        CachedData::Nodes(nodes) => Message::Capacity(data::NodeCapacity::from_nodes(&nodes)),
+        CachedData::Containers(rows) => Message::Container(rows),
+        CachedData::Custom(rows) => Message::Plugin(rows),
+        CachedData::Ingresses(rows) => Message::Ingress(rows),
+        CachedData::Deployments(rows) => Message::Deployment(rows),
+        CachedData::StatefulSets(rows) => Message::StatefulSet(rows),
+    };
+    app.handle_event(&message).await?;
+    Ok(())
 }
 
 /// runs a stack of apps where navigation is "<Enter>" into and "<Esc>" out of
-async fn run_root_ui_loop<B: Backend + Send>(terminal: &mut Terminal<B>) -> io::Result<()> {
+async fn run_root_ui_loop(terminal: &mut Term) {
     let data_vec = vec![];
-    let mut app_holder = Apps::Rs {
-        app: rs_app::app::App::new(data_vec),
-    };
+    let mut app_holder = Apps::new(rs_app::app::App::new(data_vec));
 
-    let mut history: Vec<Arc<Apps>> = Vec::new();
+    let mut history: Vec<Apps> = Vec::new();
     loop {
-        match run_app(terminal, &mut app_holder).await? {
-            (Some(old_app_holder), Some(new_app_holder)) => {
-                history.push(Arc::new(old_app_holder)); // this is an app switch
-                app_holder = new_app_holder;
-            }
-            (_, _) => {
-                if let Some(previous_app) = history.pop() {
-                    app_holder = (*previous_app).clone();
-                } else {
-                    break; //quit
-                }
-            }
+        if let Some(next) = app_holder.0.run(terminal).await {
+            // this is an app switch
+            history.push(std::mem::replace(&mut app_holder, next));
+        } else if let Some(previous_app) = history.pop() {
+            app_holder = previous_app;
+        } else if confirm_quit(terminal).await {
+            break; //quit
         }
     }
-    Ok(())
+}
+
+/// `true` to quit, asking first while tasks are still changing the cluster
+async fn confirm_quit(terminal: &mut Term) -> bool {
+    if shutdown::outstanding().is_empty() {
+        return true;
+    }
+    let mut dialog = QuitDialog::new();
+    _ = run_view(terminal, &mut dialog).await;
+    !dialog.stay
 }
@@ -0,0 +1,160 @@
+//!Container logs are written for terminals, not for a ratatui table: color
+//!escapes, cursor movement and stray control characters would garble the
+//!rendering.  `sanitize` strips them and remembers where the foreground
+//!colors were so the log view can draw them as spans.
+//!
+use ratatui::style::Color;
+
+const ESC: char = '\u{1b}';
+const BEL: char = '\u{7}';
+const TAB_WIDTH: usize = 4;
+
+/// a colored range of the sanitized text, in byte offsets
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColorRun {
+    pub start: usize,
+    pub end: usize,
+    pub fg: Color,
+}
+
+const fn basic_color(n: u16) -> Color {
+    match n {
+        0 => Color::Black,
+        1 => Color::Red,
+        2 => Color::Green,
+        3 => Color::Yellow,
+        4 => Color::Blue,
+        5 => Color::Magenta,
+        6 => Color::Cyan,
+        7 => Color::Gray,
+        8 => Color::DarkGray,
+        9 => Color::LightRed,
+        10 => Color::LightGreen,
+        11 => Color::LightYellow,
+        12 => Color::LightBlue,
+        13 => Color::LightMagenta,
+        14 => Color::LightCyan,
+        _ => Color::White,
+    }
+}
+
+/// apply an SGR parameter list, ie: `1;31` or `38;5;208`, to the current color
+fn apply_sgr(params: &str, fg: &mut Option<Color>) {
+    let codes: Vec<u16> = params.split(';').map(|p| p.parse().unwrap_or(0)).collect();
+    let mut i = 0;
+    while i < codes.len() {
+        match codes[i] {
+            0 | 39 => *fg = None,
+            n @ 30..=37 => *fg = Some(basic_color(n - 30)),
+            n @ 90..=97 => *fg = Some(basic_color(n - 90 + 8)),
+            38 => match codes.get(i + 1) {
+                Some(5) => {
+                    *fg = codes
+                        .get(i + 2)
+                        .and_then(|n| u8::try_from(*n).ok())
+                        .map(Color::Indexed);
+                    i += 2;
+                }
+                Some(2) => {
+                    let rgb: Vec<u8> = codes
+                        .iter()
+                        .skip(i + 2)
+                        .take(3)
+                        .filter_map(|n| u8::try_from(*n).ok())
+                        .collect();
+                    if let [r, g, b] = rgb[..] {
+                        *fg = Some(Color::Rgb(r, g, b));
+                    }
+                    i += 4;
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+        i += 1;
+    }
+}
+
+/// Strip escape sequences and control characters from one log line.  Tabs
+/// are expanded and a carriage return keeps only what was written after it,
+/// the way a terminal would show a progress bar.
+#[must_use]
+pub fn sanitize(line: &str) -> (String, Vec<ColorRun>) {
+    let line = line.rsplit('\r').find(|s| !s.is_empty()).unwrap_or("");
+    let mut text = String::with_capacity(line.len());
+    let mut runs: Vec<ColorRun> = Vec::new();
+    let mut fg: Option<Color> = None;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ESC => match chars.next() {
+                // CSI: parameters up to a final byte in @..~
+                Some('[') => {
+                    let mut params = String::new();
+                    for c in chars.by_ref() {
+                        if ('@'..='~').contains(&c) {
+                            if c == 'm' {
+                                apply_sgr(&params, &mut fg);
+                            }
+                            break;
+                        }
+                        params.push(c);
+                    }
+                }
+                // OSC: up to BEL or ESC \
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == BEL || (c == ESC && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            '\t' => {
+                let pad = TAB_WIDTH - text.chars().count() % TAB_WIDTH;
+                text.extend(std::iter::repeat_n(' ', pad));
+            }
+            c if c.is_control() => {}
+            c => {
+                let start = text.len();
+                text.push(c);
+                if let Some(fg) = fg {
+                    match runs.last_mut() {
+                        Some(run) if run.end == start && run.fg == fg => run.end = text.len(),
+                        _ => runs.push(ColorRun {
+                            start,
+                            end: text.len(),
+                            fg,
+                        }),
+                    }
+                }
+            }
+        }
+    }
+    (text, runs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_strips_escapes_and_keeps_colors() {
+        let (text, runs) = sanitize("\u{1b}[1;31mERROR\u{1b}[0m\tdone\u{7}");
+        assert_eq!(text, "ERROR   done");
+        assert_eq!(
+            runs,
+            vec![ColorRun {
+                start: 0,
+                end: 5,
+                fg: Color::Red
+            }]
+        );
+
+        let (text, runs) = sanitize("10%\r50%\r\u{1b}]0;title\u{7}\u{1b}[38;5;208mok\u{1b}[2K");
+        assert_eq!(text, "ok");
+        assert_eq!(runs[0].fg, Color::Indexed(208));
+    }
+}
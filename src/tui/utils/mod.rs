@@ -1 +1,4 @@
+pub mod ansi;
+pub mod names;
 pub mod time;
+pub mod wrap;
@@ -0,0 +1,61 @@
+use crate::tui::theme::symbols;
+
+/// name columns and card titles are no wider than this
+pub const MAX_NAME_WIDTH: usize = 40;
+
+/// whether `name` is cut short where names are shown
+#[must_use]
+pub fn truncated(name: &str) -> bool {
+    name.chars().count() > MAX_NAME_WIDTH
+}
+
+/// Cut `name` to `width` characters in the middle, keeping the suffix a
+/// controller adds, ie: `payments-reconc…7d9f8b6c4-x2k9p`, since that is
+/// what tells the pods of one replicaset apart.
+#[must_use]
+pub fn middle_ellipsis(name: &str, width: usize) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    if chars.len() <= width {
+        return name.to_string();
+    }
+    let ellipsis = symbols().ellipsis;
+    let room = width.saturating_sub(ellipsis.chars().count());
+    // the last two dash separated parts, ie: the template hash and the
+    // random part of a pod name
+    let suffix = name
+        .rmatch_indices('-')
+        .nth(1)
+        .map_or(0, |(at, _)| name[at + 1..].chars().count());
+    let tail = if suffix > 0 {
+        suffix.min(room * 2 / 3)
+    } else {
+        room / 2
+    };
+    let head = room - tail;
+    let mut cut: String = chars[..head].iter().collect();
+    cut.push_str(ellipsis);
+    cut.extend(&chars[chars.len() - tail..]);
+    cut
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_middle_ellipsis_keeps_the_suffix() {
+        let pod = "payments-reconciler-operator-generated-very-long-name-7d9f8b6c4-x2k9p";
+        let cut = middle_ellipsis(pod, MAX_NAME_WIDTH);
+        assert_eq!(cut, "payments-reconciler-oper…7d9f8b6c4-x2k9p");
+        assert_eq!(cut.chars().count(), MAX_NAME_WIDTH);
+        assert!(truncated(pod));
+
+        assert_eq!(
+            middle_ellipsis("web-7d9f8b6c4-x2k9p", 40),
+            "web-7d9f8b6c4-x2k9p"
+        );
+        assert_eq!(middle_ellipsis("abcdefghij", 7), "abc…hij");
+        assert_eq!(middle_ellipsis("web-7d9f8b6c4-x2k9p", 10), "web…-x2k9p");
+        assert_eq!(middle_ellipsis("web", 0), "…");
+    }
+}
@@ -1,6 +1,80 @@
+use chrono::{DateTime, FixedOffset, Local, Utc};
+use std::str::FromStr;
+use std::sync::OnceLock;
 use time::OffsetDateTime;
 use x509_parser::time::ASN1Time;
 
+/// how log times, event times and cert expiries are shown
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimeZoneSetting {
+    #[default]
+    Utc,
+    Local,
+    Fixed(FixedOffset),
+}
+
+impl FromStr for TimeZoneSetting {
+    type Err = String;
+
+    /// `utc`, `local` or an offset like `+02:00`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "utc" | "z" => Ok(Self::Utc),
+            "local" => Ok(Self::Local),
+            offset => offset
+                .parse::<FixedOffset>()
+                .map(Self::Fixed)
+                .map_err(|_| format!("expected utc, local or an offset like +02:00, got {s}")),
+        }
+    }
+}
+
+impl TimeZoneSetting {
+    #[must_use]
+    pub fn format(&self, time: &DateTime<Utc>, fmt: &str) -> String {
+        match self {
+            Self::Utc => time.format(fmt).to_string(),
+            Self::Local => time.with_timezone(&Local).format(fmt).to_string(),
+            Self::Fixed(offset) => time.with_timezone(offset).format(fmt).to_string(),
+        }
+    }
+
+    /// UTC and local time, a custom offset toggles to UTC first
+    #[must_use]
+    pub const fn toggle(self) -> Self {
+        match self {
+            Self::Utc => Self::Local,
+            Self::Local | Self::Fixed(_) => Self::Utc,
+        }
+    }
+
+    #[must_use]
+    pub fn label(&self) -> String {
+        match self {
+            Self::Utc => "UTC".to_string(),
+            Self::Local => "local".to_string(),
+            Self::Fixed(offset) => offset.to_string(),
+        }
+    }
+}
+
+/// same width in every timezone so the log columns do not jump on 'z'
+pub const LOG_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+pub const EVENT_TIME_FORMAT: &str = "%m-%d %H:%M:%S";
+
+static TIMEZONE: OnceLock<TimeZoneSetting> = OnceLock::new();
+
+/// Set the timezone once at startup, later calls are ignored.
+pub fn configure_timezone(timezone: TimeZoneSetting) {
+    let _ = TIMEZONE.set(timezone);
+}
+
+#[must_use]
+pub fn timezone() -> TimeZoneSetting {
+    TIMEZONE.get().copied().unwrap_or_default()
+}
+
 pub fn asn1time_to_future_days_string(asn1_time: &ASN1Time) -> String {
     let now = OffsetDateTime::now_utc();
 
@@ -9,5 +83,32 @@ pub fn asn1time_to_future_days_string(asn1_time: &ASN1Time) -> String {
     let duration = target_time - now;
     let days_difference = duration.whole_days();
 
-    format!("{days_difference}d")
+    match DateTime::from_timestamp(asn1_time.timestamp(), 0) {
+        Some(expires) => format!(
+            "{days_difference}d ({})",
+            timezone().format(&expires, "%Y-%m-%d %H:%M")
+        ),
+        None => format!("{days_difference}d"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timezone_setting() {
+        let time = DateTime::parse_from_rfc3339("2024-03-01T23:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let plus_two: TimeZoneSetting = "+02:00".parse().unwrap();
+        assert_eq!(plus_two.format(&time, "%Y-%m-%d %H:%M"), "2024-03-02 01:30");
+        assert_eq!(
+            TimeZoneSetting::Utc.format(&time, "%H:%M"),
+            "23:30".to_string()
+        );
+        assert_eq!("UTC".parse(), Ok(TimeZoneSetting::Utc));
+        assert!("mars".parse::<TimeZoneSetting>().is_err());
+        assert_eq!(plus_two.toggle(), TimeZoneSetting::Utc);
+    }
 }
@@ -0,0 +1,52 @@
+use unicode_width::UnicodeWidthChar;
+
+/// Split `text` into byte ranges no wider than `width` columns, breaking
+/// after the last space of a segment when there is one.
+#[must_use]
+pub fn wrap_ranges(text: &str, width: usize) -> Vec<(usize, usize)> {
+    let width = width.max(1);
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    let mut columns = 0;
+    let mut last_space = None;
+
+    for (i, c) in text.char_indices() {
+        let w = c.width().unwrap_or(0);
+        if columns + w > width && i > start {
+            let end = last_space.filter(|&s| s > start).unwrap_or(i);
+            ranges.push((start, end));
+            start = end;
+            columns = text[start..i]
+                .chars()
+                .filter_map(UnicodeWidthChar::width)
+                .sum();
+            last_space = None;
+        }
+        columns += w;
+        if c == ' ' {
+            last_space = Some(i + 1);
+        }
+    }
+    if start < text.len() || ranges.is_empty() {
+        ranges.push((start, text.len()));
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_ranges() {
+        let text = "one two three four";
+        let lines: Vec<&str> = wrap_ranges(text, 9)
+            .into_iter()
+            .map(|(s, e)| &text[s..e])
+            .collect();
+        assert_eq!(lines, vec!["one two ", "three ", "four"]);
+
+        assert_eq!(wrap_ranges("abcdef", 4), vec![(0, 4), (4, 6)]);
+        assert_eq!(wrap_ranges("", 4), vec![(0, 0)]);
+    }
+}
@@ -0,0 +1,215 @@
+//!What the TUI has cached, as JSON on localhost, so editor integrations
+//!and scripts can read it instead of asking the api server again.  Nothing
+//!here fetches: data the TUI has not loaded yet is a 404.
+//!
+//!- `/cache/stats` entries with their age and freshness, and the hit rate
+//!- `/cache/replicasets` and `/cache/events`
+//!- `/cache/pods` and `/cache/containers`, one table per cached selector
+//!  or pod
+//!- `/metrics` navipod's own frame times, api call latencies and watch
+//!  restarts in the Prometheus text format
+//!
+use crate::config::settings;
+use crate::k8s::cache::cache_manager::{get_cache, hit_rate};
+use crate::k8s::cache::{CachedData, DataRequest};
+use crate::perf;
+use crate::tui::data::Container;
+use crate::web::server::{accept, respond};
+use crate::web::views::{event_row, Table};
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::body::Incoming;
+use hyper::{Method, Request, Response, StatusCode};
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+fn container_table(pod: &str, containers: &[Container]) -> Table {
+    Table {
+        title: format!("Containers of {pod}"),
+        columns: ["Container", "Description", "Restarts", "Image", "Ports"]
+            .map(String::from)
+            .to_vec(),
+        rows: containers
+            .iter()
+            .map(|container| container.ref_array().into_iter().cloned().collect())
+            .collect(),
+    }
+}
+
+/// the body for `path` from `entries`, `None` when nothing is cached for it
+fn answer(
+    path: &str,
+    entries: &[(DataRequest, CachedData, Duration)],
+    ttl: Duration,
+) -> Option<Value> {
+    let tables = |want: fn(&DataRequest, &CachedData) -> Option<(Value, Table)>| {
+        let found: Vec<Value> = entries
+            .iter()
+            .filter_map(|(request, data, age)| {
+                let (key, table) = want(request, data)?;
+                Some(json!({"key": key, "age_secs": age.as_secs(), "table": table}))
+            })
+            .collect();
+        (!found.is_empty()).then_some(Value::Array(found))
+    };
+    match path.trim_end_matches('/') {
+        "/cache/stats" => {
+            let items: Vec<Value> = entries
+                .iter()
+                .map(|(request, _, age)| {
+                    json!({
+                        "request": request.to_string(),
+                        "age_secs": age.as_secs(),
+                        "fresh": *age <= ttl,
+                    })
+                })
+                .collect();
+            Some(json!({
+                "entries": entries.len(),
+                "ttl_secs": ttl.as_secs(),
+                "drill_down_hit_rate": hit_rate(),
+                "items": items,
+            }))
+        }
+        "/cache/replicasets" => entries.iter().find_map(|(_, data, _)| match data {
+            CachedData::ReplicaSets(replicasets) => serde_json::to_value(Table::new(
+                "ReplicaSets",
+                &[
+                    "ReplicaSet",
+                    "P",
+                    "Age",
+                    "Description",
+                    "Owner",
+                    "Deprecated",
+                ],
+                &settings().columns.rs,
+                replicasets,
+            ))
+            .ok(),
+            _ => None,
+        }),
+        "/cache/events" => entries.iter().find_map(|(_, data, _)| match data {
+            CachedData::Events(events) => serde_json::to_value(Table {
+                title: "Events".to_string(),
+                columns: ["Resource", "Message", "Reason", "Type", "Age"]
+                    .map(String::from)
+                    .to_vec(),
+                rows: events.iter().map(event_row).collect(),
+            })
+            .ok(),
+            _ => None,
+        }),
+        "/cache/pods" => tables(|request, data| match (request, data) {
+            (DataRequest::Pods { selector }, CachedData::Pods(pods)) => Some((
+                json!(selector),
+                Table::new(
+                    "Pods",
+                    &["Pod", "Status", "C", "Age", "Description"],
+                    &settings().columns.pod,
+                    pods,
+                ),
+            )),
+            _ => None,
+        }),
+        "/cache/containers" => tables(|request, data| match (request, data) {
+            (DataRequest::Containers { pod, .. }, CachedData::Containers(containers)) => {
+                Some((json!(pod), container_table(pod, containers)))
+            }
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+async fn handle(request: Request<Incoming>) -> Result<Response<Full<Bytes>>, Infallible> {
+    if request.method() != Method::GET {
+        return Ok(respond(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "text/plain",
+            "the cache api is read-only\n".to_string(),
+        ));
+    }
+    if request.uri().path() == "/metrics" {
+        return Ok(respond(
+            StatusCode::OK,
+            "text/plain; version=0.0.4",
+            perf::snapshot().prometheus(),
+        ));
+    }
+    let cache = get_cache();
+    Ok(
+        match answer(request.uri().path(), &cache.snapshot(), cache.ttl()) {
+            Some(body) => respond(StatusCode::OK, "application/json", body.to_string()),
+            None => respond(
+                StatusCode::NOT_FOUND,
+                "application/json",
+                json!({"error": "not cached"}).to_string(),
+            ),
+        },
+    )
+}
+
+/// Serve the cache on `127.0.0.1:port` in the background.
+///
+/// # Errors
+///
+/// Will return `Err` if the port can not be bound
+pub async fn spawn(port: u16) -> io::Result<()> {
+    let listener = TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, port))).await?;
+    tokio::spawn(accept(listener, handle, Arc::from([])));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tui::data::Rs;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_answers_from_cache_only() {
+        let selector = BTreeMap::from([("app".to_string(), "web".to_string())]);
+        let rs = Rs {
+            name: "web-5d8f7".to_string(),
+            owner: "web".to_string(),
+            description: String::new(),
+            age: "3d".to_string(),
+            pods: "2/2".to_string(),
+            deprecations: String::new(),
+            selectors: Some(selector.clone()),
+            events: vec![],
+            extra: vec![],
+        };
+        let entries = vec![
+            (
+                DataRequest::ReplicaSets,
+                CachedData::ReplicaSets(vec![rs]),
+                Duration::from_secs(5),
+            ),
+            (
+                DataRequest::Pods { selector },
+                CachedData::Pods(vec![]),
+                Duration::from_secs(90),
+            ),
+        ];
+        let ttl = Duration::from_secs(30);
+        let stats = answer("/cache/stats", &entries, ttl).unwrap();
+        assert_eq!(stats["entries"], 2);
+        assert_eq!(stats["items"][1]["request"], "pods app=web");
+        assert_eq!(stats["items"][1]["fresh"], false);
+        let replicasets = answer("/cache/replicasets/", &entries, ttl).unwrap();
+        assert_eq!(replicasets["rows"][0][0], "web-5d8f7");
+        assert_eq!(
+            answer("/cache/pods", &entries, ttl).unwrap()[0]["key"]["app"],
+            "web"
+        );
+        assert!(answer("/cache/events", &entries, ttl).is_none());
+        assert!(answer("/cache/containers", &entries, ttl).is_none());
+        assert!(answer("/", &entries, ttl).is_none());
+    }
+}
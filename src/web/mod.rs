@@ -0,0 +1,9 @@
+//!`navipod serve`: the replicaset, pod and event views as a read-only web
+//!dashboard, a JSON api and one html page on top of it, served from the
+//!same cache the TUI uses.  With the `api` feature the TUI can also hand
+//!out what it has cached to local scripts.
+//!
+#[cfg(feature = "api")]
+pub mod api;
+pub mod server;
+pub mod views;
@@ -0,0 +1,272 @@
+use crate::error::{Error, Result as NvResult};
+use crate::k8s::cache::cache_manager;
+use crate::k8s::client_manager;
+use crate::web::views::{self, Table};
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::body::Incoming;
+use hyper::header::{HeaderValue, CACHE_CONTROL, CONTENT_TYPE, HOST};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde_json::json;
+use std::convert::Infallible;
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::time::sleep;
+use tracing::{debug, warn};
+
+const INDEX: &str = include_str!("index.html");
+/// the longest pause after failed accepts, ie: while out of file descriptors
+const MAX_ACCEPT_BACKOFF: Duration = Duration::from_secs(1);
+
+#[derive(Debug, PartialEq, Eq)]
+enum Route {
+    Index,
+    ReplicaSets,
+    Pods(String),
+    Events,
+    NotFound,
+}
+
+fn route(path: &str) -> Route {
+    let parts: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match parts.as_slice() {
+        [""] => Route::Index,
+        ["api", "replicasets"] => Route::ReplicaSets,
+        ["api", "replicasets", name, "pods"] if !name.is_empty() => {
+            Route::Pods((*name).to_string())
+        }
+        ["api", "events"] => Route::Events,
+        _ => Route::NotFound,
+    }
+}
+
+/// `:8080` listens on every interface, like the go tools do
+///
+/// # Errors
+///
+/// Will return `Err` if `listen` is not a host:port
+pub fn listen_addr(listen: &str) -> io::Result<SocketAddr> {
+    let listen = if listen.starts_with(':') {
+        format!("0.0.0.0{listen}")
+    } else {
+        listen.to_string()
+    };
+    listen
+        .parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("{listen}: {e}")))
+}
+
+pub(crate) fn respond(
+    status: StatusCode,
+    content_type: &'static str,
+    body: String,
+) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from(body)));
+    *response.status_mut() = status;
+    let headers = response.headers_mut();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    response
+}
+
+fn table_response(table: NvResult<Table>) -> Response<Full<Bytes>> {
+    match table {
+        Ok(table) => respond(
+            StatusCode::OK,
+            "application/json",
+            serde_json::to_string(&table).unwrap_or_default(),
+        ),
+        Err(e) => {
+            let status = match e {
+                Error::NotFound(_) => StatusCode::NOT_FOUND,
+                Error::Timeout { .. } => StatusCode::GATEWAY_TIMEOUT,
+                _ => StatusCode::BAD_GATEWAY,
+            };
+            let body =
+                json!({"error": e.to_string(), "code": e.code(), "remediation": e.remediation()});
+            respond(status, "application/json", body.to_string())
+        }
+    }
+}
+
+async fn handle(request: Request<Incoming>) -> Result<Response<Full<Bytes>>, Infallible> {
+    if request.method() != Method::GET && request.method() != Method::HEAD {
+        return Ok(respond(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "text/plain",
+            "navipod serve is read-only\n".to_string(),
+        ));
+    }
+    Ok(match route(request.uri().path()) {
+        Route::Index => respond(
+            StatusCode::OK,
+            "text/html; charset=utf-8",
+            INDEX.to_string(),
+        ),
+        Route::ReplicaSets => table_response(views::replicasets().await),
+        Route::Pods(replicaset) => table_response(views::pods(&replicaset).await),
+        Route::Events => table_response(views::events().await),
+        Route::NotFound => respond(
+            StatusCode::NOT_FOUND,
+            "text/plain",
+            "not found\n".to_string(),
+        ),
+    })
+}
+
+/// Whether `request` was addressed to this machine: no Host, localhost, an
+/// ip address or one of the `allow` names.  A page whose own name was
+/// rebound to 127.0.0.1 sends its name and is turned away, so it can not
+/// read the cluster through us.
+fn allowed_host<B>(request: &Request<B>, allow: &[String]) -> bool {
+    let Some(host) = request.headers().get(HOST) else {
+        return true;
+    };
+    let Ok(host) = host.to_str() else {
+        return false;
+    };
+    // drop the port, ie: localhost:8080 or [::1]:8080
+    let name = match host.rsplit_once(':') {
+        Some((name, port)) if !name.ends_with(':') && port.parse::<u16>().is_ok() => name,
+        _ => host,
+    };
+    let name = name.trim_start_matches('[').trim_end_matches(']');
+    name.eq_ignore_ascii_case("localhost")
+        || name.to_ascii_lowercase().ends_with(".localhost")
+        || name.parse::<IpAddr>().is_ok()
+        || allow
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(name))
+}
+
+/// Answer every connection on `listener` with `handler` until the process
+/// stops, requests for other names than `allow` only when addressed to this
+/// machine.  A failed accept is logged and retried after a pause that grows
+/// while accepts keep failing.
+pub(crate) async fn accept<F, Fut>(listener: TcpListener, handler: F, allow: Arc<[String]>)
+where
+    F: Fn(Request<Incoming>) -> Fut + Copy + Send + 'static,
+    Fut: Future<Output = Result<Response<Full<Bytes>>, Infallible>> + Send + 'static,
+{
+    let mut backoff = Duration::from_millis(10);
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!("can not accept a connection: {e}, retrying in {backoff:?}");
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_ACCEPT_BACKOFF);
+                continue;
+            }
+        };
+        backoff = Duration::from_millis(10);
+        let allow = Arc::clone(&allow);
+        let checked = move |request: Request<Incoming>| {
+            let allow = Arc::clone(&allow);
+            async move {
+                if allowed_host(&request, &allow) {
+                    handler(request).await
+                } else {
+                    Ok(respond(
+                        StatusCode::FORBIDDEN,
+                        "text/plain",
+                        "navipod only answers requests for localhost, an ip address or an --allow-host name\n"
+                            .to_string(),
+                    ))
+                }
+            }
+        };
+        tokio::spawn(async move {
+            let connection =
+                http1::Builder::new().serve_connection(TokioIo::new(stream), service_fn(checked));
+            if let Err(e) = connection.await {
+                debug!("connection from {peer} failed: {e}");
+            }
+        });
+    }
+}
+
+/// Serve until the process is stopped, warming the cache like the TUI does,
+/// to requests for localhost, an ip address or one of the `allow_hosts`.
+///
+/// # Errors
+///
+/// Will return `Err` if `listen` is not an address or can not be bound
+pub async fn serve(listen: &str, allow_hosts: Vec<String>) -> io::Result<()> {
+    let addr = listen_addr(listen)?;
+    let listener = TcpListener::bind(addr).await?;
+    tokio::spawn(async {
+        client_manager::warm_up().await;
+        client_manager::start_token_refresh();
+        cache_manager::initialize_cache().await;
+    });
+    println!("serving on http://{}", listener.local_addr()?);
+    accept(listener, handle, allow_hosts.into()).await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_routes_and_listen() {
+        assert_eq!(route("/"), Route::Index);
+        assert_eq!(route("/api/replicasets"), Route::ReplicaSets);
+        assert_eq!(
+            route("/api/replicasets/web-5d8f7/pods"),
+            Route::Pods("web-5d8f7".to_string())
+        );
+        assert_eq!(route("/api/replicasets//pods"), Route::NotFound);
+        assert_eq!(route("/api/events/"), Route::Events);
+        assert_eq!(route("/etc/passwd"), Route::NotFound);
+
+        assert_eq!(listen_addr(":8080").unwrap().to_string(), "0.0.0.0:8080");
+        assert_eq!(
+            listen_addr("127.0.0.1:9000").unwrap().to_string(),
+            "127.0.0.1:9000"
+        );
+        assert!(listen_addr("localhost").is_err());
+    }
+
+    #[test]
+    fn test_allowed_host() {
+        let request = |host: Option<&str>| {
+            let mut builder = Request::builder().uri("/api/replicasets");
+            if let Some(host) = host {
+                builder = builder.header(HOST, host);
+            }
+            builder.body(()).unwrap()
+        };
+        for host in [
+            None,
+            Some("localhost:8080"),
+            Some("LOCALHOST"),
+            Some("app.localhost:80"),
+            Some("127.0.0.1:8080"),
+            Some("10.0.0.5"),
+            Some("[::1]:8080"),
+        ] {
+            assert!(allowed_host(&request(host), &[]), "{host:?}");
+        }
+        for host in [
+            "evil.example.com",
+            "evil.example.com:8080",
+            "localhost.evil.com",
+        ] {
+            assert!(!allowed_host(&request(Some(host)), &[]), "{host}");
+        }
+
+        let allow = ["myhost".to_string()];
+        assert!(allowed_host(&request(Some("myhost:8080")), &allow));
+        assert!(allowed_host(&request(Some("MyHost")), &allow));
+        assert!(!allowed_host(&request(Some("myhost.evil.com")), &allow));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::config::{settings, CustomColumn};
+use crate::error::{Error, Result};
+use crate::k8s::cache::cache_manager::get_or_fetch;
+use crate::k8s::cache::{CachedData, DataRequest};
+use crate::tui::data::{ResourceEvent, Rs};
+use crate::tui::sort::Columns;
+use serde::Serialize;
+
+/// one view as the TUI shows it: headers and cells as text
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct Table {
+    pub title: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub(crate) fn new<T: Columns>(
+        title: &str,
+        built_in: &[&str],
+        custom: &[CustomColumn],
+        items: &[T],
+    ) -> Self {
+        Self {
+            title: title.to_string(),
+            columns: built_in
+                .iter()
+                .map(|header| (*header).to_string())
+                .chain(custom.iter().map(|column| column.header.clone()))
+                .collect(),
+            rows: items
+                .iter()
+                .map(|item| item.columns().into_iter().map(String::from).collect())
+                .collect(),
+        }
+    }
+}
+
+async fn list_replicasets() -> Result<Vec<Rs>> {
+    match get_or_fetch(DataRequest::ReplicaSets).await? {
+        CachedData::ReplicaSets(replicasets) => Ok(replicasets),
+        _ => Ok(vec![]),
+    }
+}
+
+/// # Errors
+///
+/// Will return `Err` if the replicasets can not be fetched
+pub async fn replicasets() -> Result<Table> {
+    Ok(Table::new(
+        "ReplicaSets",
+        &[
+            "ReplicaSet",
+            "P",
+            "Age",
+            "Description",
+            "Owner",
+            "Deprecated",
+        ],
+        &settings().columns.rs,
+        &list_replicasets().await?,
+    ))
+}
+
+/// # Errors
+///
+/// Will return `Err` if there is no such replicaset or its pods can not be
+/// fetched
+pub async fn pods(replicaset: &str) -> Result<Table> {
+    let selector = list_replicasets()
+        .await?
+        .into_iter()
+        .find(|rs| rs.name == replicaset)
+        .and_then(|rs| rs.selectors)
+        .ok_or_else(|| Error::NotFound(format!("replicaset {replicaset}")))?;
+    let pods = match get_or_fetch(DataRequest::Pods { selector }).await? {
+        CachedData::Pods(pods) => pods,
+        _ => vec![],
+    };
+    Ok(Table::new(
+        &format!("Pods of {replicaset}"),
+        &["Pod", "Status", "C", "Age", "Description"],
+        &settings().columns.pod,
+        &pods,
+    ))
+}
+
+pub(crate) fn event_row(event: &ResourceEvent) -> Vec<String> {
+    event
+        .ref_array()
+        .into_iter()
+        .chain([&event.age])
+        .cloned()
+        .collect()
+}
+
+/// # Errors
+///
+/// Will return `Err` if the events can not be fetched
+pub async fn events() -> Result<Table> {
+    let events = match get_or_fetch(DataRequest::Events).await? {
+        CachedData::Events(events) => events,
+        _ => vec![],
+    };
+    Ok(Table {
+        title: "Events".to_string(),
+        columns: ["Resource", "Message", "Reason", "Type", "Age"]
+            .map(String::from)
+            .to_vec(),
+        rows: events.iter().map(event_row).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_follows_tui_columns() {
+        let rs = Rs {
+            name: "web-5d8f7".to_string(),
+            owner: "web".to_string(),
+            description: "ReplicaSet".to_string(),
+            age: "3d".to_string(),
+            pods: "2/2".to_string(),
+            deprecations: String::new(),
+            selectors: None,
+            events: vec![],
+            extra: vec!["payments".to_string()],
+        };
+        let custom = [CustomColumn {
+            header: "TEAM".to_string(),
+            label: Some("team".to_string()),
+            annotation: None,
+        }];
+        let table = Table::new("ReplicaSets", &["ReplicaSet", "P"], &custom, &[rs]);
+        assert_eq!(table.columns, ["ReplicaSet", "P", "TEAM"]);
+        assert_eq!(
+            table.rows[0],
+            [
+                "web-5d8f7",
+                "2/2",
+                "3d",
+                "ReplicaSet",
+                "web",
+                "",
+                "payments"
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&table).unwrap()["title"],
+            "ReplicaSets"
+        );
+    }
+}